@@ -19,6 +19,21 @@ impl Node for Comment {
     }
 }
 
+/// An attribute applied to the definition that follows it, such as
+/// `@deprecated('use Foo instead')`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: Identifier,
+    pub arguments: Option<Arguments>,
+    pub location: Location,
+}
+
+impl Node for Attribute {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct IntLiteral {
     pub value: String,
@@ -425,6 +440,7 @@ pub struct DefineMethod {
     pub type_parameters: Option<TypeParameters>,
     pub arguments: Option<MethodArguments>,
     pub return_type: Option<Type>,
+    pub bounds: Option<TypeBounds>,
     pub body: Option<Expressions>,
     pub location: Location,
 }
@@ -454,6 +470,7 @@ pub enum ClassExpression {
     DefineMethod(Box<DefineMethod>),
     DefineField(Box<DefineField>),
     DefineConstructor(Box<DefineConstructor>),
+    Attribute(Box<Attribute>),
     Comment(Box<Comment>),
 }
 
@@ -463,6 +480,7 @@ impl Node for ClassExpression {
             ClassExpression::DefineMethod(n) => &n.location,
             ClassExpression::DefineField(n) => &n.location,
             ClassExpression::DefineConstructor(n) => &n.location,
+            ClassExpression::Attribute(n) => &n.location,
             ClassExpression::Comment(n) => &n.location,
         }
     }
@@ -523,6 +541,7 @@ pub struct AssignInstanceLiteralField {
 #[derive(Debug, PartialEq, Eq)]
 pub enum TraitExpression {
     DefineMethod(Box<DefineMethod>),
+    Attribute(Box<Attribute>),
     Comment(Box<Comment>),
 }
 
@@ -530,6 +549,7 @@ impl Node for TraitExpression {
     fn location(&self) -> &Location {
         match self {
             TraitExpression::DefineMethod(n) => &n.location,
+            TraitExpression::Attribute(n) => &n.location,
             TraitExpression::Comment(n) => &n.location,
         }
     }
@@ -573,6 +593,7 @@ pub enum TopLevelExpression {
     ImplementTrait(Box<ImplementTrait>),
     Import(Box<Import>),
     ExternImport(Box<ExternImport>),
+    Attribute(Box<Attribute>),
     Comment(Box<Comment>),
 }
 
@@ -587,6 +608,7 @@ impl Node for TopLevelExpression {
             TopLevelExpression::ImplementTrait(ref n) => n.location(),
             TopLevelExpression::Import(ref n) => n.location(),
             TopLevelExpression::ExternImport(ref n) => n.location(),
+            TopLevelExpression::Attribute(ref n) => n.location(),
             TopLevelExpression::Comment(ref n) => n.location(),
         }
     }
@@ -595,6 +617,7 @@ impl Node for TopLevelExpression {
 #[derive(Debug, PartialEq, Eq)]
 pub enum ImplementationExpression {
     DefineMethod(Box<DefineMethod>),
+    Attribute(Box<Attribute>),
     Comment(Box<Comment>),
 }
 
@@ -602,6 +625,7 @@ impl Node for ImplementationExpression {
     fn location(&self) -> &Location {
         match self {
             ImplementationExpression::DefineMethod(n) => &n.location,
+            ImplementationExpression::Attribute(n) => &n.location,
             ImplementationExpression::Comment(n) => &n.location,
         }
     }
@@ -1018,6 +1042,7 @@ impl Node for ReferrableType {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ClosureType {
+    pub type_parameters: Option<TypeParameters>,
     pub arguments: Option<Types>,
     pub return_type: Option<Type>,
     pub location: Location,
@@ -105,6 +105,9 @@ impl Parser {
             TokenKind::Trait => self.define_trait(start)?,
             TokenKind::Fn => self.define_module_method(start)?,
             TokenKind::Let => self.define_constant(start)?,
+            TokenKind::Field => {
+                TopLevelExpression::Attribute(self.attribute(start)?)
+            }
             TokenKind::Comment => {
                 TopLevelExpression::Comment(self.comment(start))
             }
@@ -682,14 +685,16 @@ impl Parser {
         &mut self,
         start: Token,
     ) -> Result<ClosureType, ParseError> {
+        let type_parameters = self.optional_type_parameter_definitions()?;
         let arguments = self.optional_block_argument_types()?;
         let return_type = self.optional_return_type()?;
         let end_loc = location!(return_type)
             .or_else(|| location!(arguments))
+            .or_else(|| location!(type_parameters))
             .unwrap_or(&start.location);
         let location = Location::start_end(&start.location, end_loc);
 
-        Ok(ClosureType { arguments, return_type, location })
+        Ok(ClosureType { type_parameters, arguments, return_type, location })
     }
 
     fn optional_block_argument_types(
@@ -888,6 +893,7 @@ impl Parser {
             type_parameters,
             arguments,
             return_type,
+            bounds: None,
             location,
             body,
             kind,
@@ -930,6 +936,7 @@ impl Parser {
         let type_parameters = self.optional_type_parameter_definitions()?;
         let arguments = self.optional_method_arguments(false)?;
         let return_type = self.optional_return_type()?;
+        let bounds = self.optional_type_bounds()?;
         let body_token = self.expect(TokenKind::CurlyOpen)?;
         let body = self.expressions(body_token)?;
         let location = Location::start_end(&start.location, &body.location);
@@ -942,6 +949,7 @@ impl Parser {
             type_parameters,
             arguments,
             return_type,
+            bounds,
             location,
             body: Some(body),
             kind,
@@ -982,6 +990,7 @@ impl Parser {
             type_parameters,
             arguments,
             return_type,
+            bounds: None,
             location,
             body: Some(body),
             kind,
@@ -1170,6 +1179,9 @@ impl Parser {
             TokenKind::Case => ClassExpression::DefineConstructor(Box::new(
                 self.define_constructor(start)?,
             )),
+            TokenKind::Field => {
+                ClassExpression::Attribute(self.attribute(start)?)
+            }
             TokenKind::Comment => ClassExpression::Comment(self.comment(start)),
             _ => {
                 error!(
@@ -1358,6 +1370,9 @@ impl Parser {
                 TokenKind::Fn => ImplementationExpression::DefineMethod(
                     Box::new(self.define_method(token)?),
                 ),
+                TokenKind::Field => {
+                    ImplementationExpression::Attribute(self.attribute(token)?)
+                }
                 TokenKind::Comment => {
                     ImplementationExpression::Comment(self.comment(token))
                 }
@@ -1445,6 +1460,9 @@ impl Parser {
                         self.define_trait_method(token)?,
                     ))
                 }
+                TokenKind::Field => {
+                    TraitExpression::Attribute(self.attribute(token)?)
+                }
                 TokenKind::Comment => {
                     TraitExpression::Comment(self.comment(token))
                 }
@@ -1502,6 +1520,7 @@ impl Parser {
             type_parameters,
             arguments,
             return_type,
+            bounds: None,
             location,
             body,
             kind,
@@ -2910,6 +2929,20 @@ impl Parser {
         Ok(IfCondition { condition, body, location })
     }
 
+    fn attribute(
+        &mut self,
+        start: Token,
+    ) -> Result<Box<Attribute>, ParseError> {
+        let arguments = self.arguments(&start.location)?;
+        let location = Location::start_end(
+            &start.location,
+            arguments.as_ref().map_or(&start.location, |n| &n.location),
+        );
+        let name = Identifier::from(start);
+
+        Ok(Box::new(Attribute { name, arguments, location }))
+    }
+
     fn comment(&mut self, start: Token) -> Box<Comment> {
         Box::new(Comment { value: start.value, location: start.location })
     }
@@ -3920,6 +3953,7 @@ mod tests {
         assert_eq!(
             parser.type_reference(start).unwrap(),
             Type::Closure(Box::new(ClosureType {
+                type_parameters: None,
                 arguments: None,
                 return_type: None,
                 location: cols(1, 2)
@@ -3935,6 +3969,7 @@ mod tests {
         assert_eq!(
             parser.type_reference(start).unwrap(),
             Type::Closure(Box::new(ClosureType {
+                type_parameters: None,
                 arguments: Some(Types {
                     values: vec![Type::Named(Box::new(TypeName {
                         name: Constant {
@@ -3953,6 +3988,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_type_reference_with_closure_type_with_type_parameters() {
+        let mut parser = parser("fn [T] (ref T)");
+        let start = parser.require().unwrap();
+
+        assert_eq!(
+            parser.type_reference(start).unwrap(),
+            Type::Closure(Box::new(ClosureType {
+                type_parameters: Some(TypeParameters {
+                    values: vec![TypeParameter {
+                        name: Constant {
+                            source: None,
+                            name: "T".to_string(),
+                            location: cols(5, 5)
+                        },
+                        requirements: None,
+                        location: cols(5, 5)
+                    }],
+                    location: cols(4, 6)
+                }),
+                arguments: Some(Types {
+                    values: vec![Type::Ref(Box::new(ReferenceType {
+                        type_reference: ReferrableType::Named(Box::new(
+                            TypeName {
+                                name: Constant {
+                                    source: None,
+                                    name: "T".to_string(),
+                                    location: cols(13, 13),
+                                },
+                                arguments: None,
+                                location: cols(13, 13)
+                            }
+                        )),
+                        location: cols(9, 13)
+                    }))],
+                    location: cols(8, 14)
+                }),
+                return_type: None,
+                location: cols(1, 14)
+            }))
+        );
+    }
+
     #[test]
     fn test_type_reference_with_closure_type_with_return_type() {
         let mut parser = parser("fn -> T");
@@ -3961,6 +4039,7 @@ mod tests {
         assert_eq!(
             parser.type_reference(start).unwrap(),
             Type::Closure(Box::new(ClosureType {
+                type_parameters: None,
                 arguments: None,
                 return_type: Some(Type::Named(Box::new(TypeName {
                     name: Constant {
@@ -3984,6 +4063,7 @@ mod tests {
         assert_eq!(
             parser.type_reference(start).unwrap(),
             Type::Closure(Box::new(ClosureType {
+                type_parameters: None,
                 arguments: Some(Types {
                     values: vec![Type::Named(Box::new(TypeName {
                         name: Constant {
@@ -4108,6 +4188,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4130,6 +4211,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(15, 16)
                 }),
+                bounds: None,
                 location: cols(1, 16)
             }))
         );
@@ -4152,6 +4234,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4174,6 +4257,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(12, 13)
                 }),
+                bounds: None,
                 location: cols(1, 13)
             }))
         );
@@ -4196,6 +4280,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(19, 20)
                 }),
+                bounds: None,
                 location: cols(1, 20)
             }))
         );
@@ -4218,6 +4303,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4240,6 +4326,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4262,6 +4349,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4284,6 +4372,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(8, 9)
                 }),
+                bounds: None,
                 location: cols(1, 9)
             }))
         );
@@ -4320,6 +4409,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(12, 13)
                 }),
+                bounds: None,
                 location: cols(1, 13)
             }))
         );
@@ -4375,6 +4465,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(19, 20)
                 }),
+                bounds: None,
                 location: cols(1, 20)
             }))
         );
@@ -4437,6 +4528,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(21, 22)
                 }),
+                bounds: None,
                 location: cols(1, 22)
             }))
         );
@@ -4470,6 +4562,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(13, 14)
                 }),
+                bounds: None,
                 location: cols(1, 14)
             }))
         );
@@ -4498,6 +4591,7 @@ mod tests {
                     }))],
                     location: cols(8, 13)
                 }),
+                bounds: None,
                 location: cols(1, 13),
             }))
         );
@@ -4520,6 +4614,7 @@ mod tests {
                 arguments: None,
                 return_type: None,
                 body: None,
+                bounds: None,
                 location: cols(1, 13),
             }))
         );
@@ -4542,6 +4637,7 @@ mod tests {
                     values: Vec::new(),
                     location: cols(15, 16)
                 }),
+                bounds: None,
                 location: cols(1, 16),
             }))
         );
@@ -4565,6 +4661,7 @@ mod tests {
                 }),
                 return_type: None,
                 body: None,
+                bounds: None,
                 location: cols(1, 18),
             }))
         );
@@ -4588,6 +4685,7 @@ mod tests {
                 }),
                 return_type: None,
                 body: None,
+                bounds: None,
                 location: cols(1, 19),
             }))
         );
@@ -4750,6 +4848,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(24, 25)
                             }),
+                            bounds: None,
                             location: cols(11, 25)
                         }
                     ))],
@@ -4789,6 +4888,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(28, 29)
                             }),
+                            bounds: None,
                             location: cols(11, 29)
                         }
                     ))],
@@ -4932,6 +5032,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(18, 19)
                             }),
+                            bounds: None,
                             location: cols(11, 19)
                         }
                     ))],
@@ -4971,6 +5072,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(22, 23)
                             }),
+                            bounds: None,
                             location: cols(11, 23)
                         }
                     ))],
@@ -5013,6 +5115,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(23, 24)
                             }),
+                            bounds: None,
                             location: cols(11, 24)
                         }
                     ))],
@@ -5055,6 +5158,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(25, 26)
                             }),
+                            bounds: None,
                             location: cols(11, 26)
                         }
                     ))],
@@ -5097,6 +5201,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(22, 23)
                             }),
+                            bounds: None,
                             location: cols(11, 23)
                         }
                     ))],
@@ -5139,6 +5244,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(25, 26)
                             }),
+                            bounds: None,
                             location: cols(11, 26)
                         }
                     ))],
@@ -5421,6 +5527,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(23, 24)
                             }),
+                            bounds: None,
                             location: cols(16, 24)
                         })
                     )],
@@ -5477,6 +5584,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(17, 18)
                             }),
+                            bounds: None,
                             location: cols(10, 18)
                         })
                     )],
@@ -5513,6 +5621,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(23, 24)
                             }),
+                            bounds: None,
                             location: cols(10, 24)
                         })
                     )],
@@ -5645,6 +5754,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(24, 25)
                             }),
+                            bounds: None,
                             location: cols(10, 25)
                         })
                     )],
@@ -5684,6 +5794,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(24, 25)
                             }),
+                            bounds: None,
                             location: cols(10, 25)
                         })
                     )],
@@ -5695,11 +5806,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reopen_with_method_bounds() {
+        assert_eq!(
+            top(parse("impl A { fn foo if T: mut {} }")),
+            TopLevelExpression::ReopenClass(Box::new(ReopenClass {
+                class_name: Constant {
+                    source: None,
+                    name: "A".to_string(),
+                    location: cols(6, 6)
+                },
+                body: ImplementationExpressions {
+                    values: vec![ImplementationExpression::DefineMethod(
+                        Box::new(DefineMethod {
+                            inline: false,
+                            public: false,
+                            operator: false,
+                            kind: MethodKind::Instance,
+                            name: Identifier {
+                                name: "foo".to_string(),
+                                location: cols(13, 15)
+                            },
+                            type_parameters: None,
+                            arguments: None,
+                            return_type: None,
+                            body: Some(Expressions {
+                                values: Vec::new(),
+                                location: cols(27, 28)
+                            }),
+                            bounds: Some(TypeBounds {
+                                values: vec![TypeBound {
+                                    name: Constant {
+                                        source: None,
+                                        name: "T".to_string(),
+                                        location: cols(20, 20)
+                                    },
+                                    requirements: Requirements {
+                                        values: vec![Requirement::Mutable(
+                                            cols(23, 25)
+                                        )],
+                                        location: cols(23, 25)
+                                    },
+                                    location: cols(20, 25)
+                                }],
+                                location: cols(20, 25)
+                            }),
+                            location: cols(10, 28)
+                        })
+                    )],
+                    location: cols(8, 30)
+                },
+                bounds: None,
+                location: cols(1, 30)
+            }))
+        );
+    }
+
     #[test]
     fn test_invalid_implementations() {
         assert_error!("impl {}", cols(6, 6));
         assert_error!("impl A {", cols(8, 8));
-        assert_error!("impl A { @foo: A }", cols(10, 13));
+        assert_error!("impl A { @foo: A }", cols(14, 14));
     }
 
     #[test]
@@ -5903,6 +6070,7 @@ mod tests {
                             arguments: None,
                             return_type: None,
                             body: None,
+                            bounds: None,
                             location: cols(11, 16)
                         }
                     ))],
@@ -5941,6 +6109,7 @@ mod tests {
                             arguments: None,
                             return_type: None,
                             body: None,
+                            bounds: None,
                             location: cols(11, 16)
                         }
                     ))],
@@ -5989,6 +6158,7 @@ mod tests {
                                 }
                             ))),
                             body: None,
+                            bounds: None,
                             location: cols(11, 21)
                         }
                     ))],
@@ -6048,6 +6218,7 @@ mod tests {
                             }),
                             return_type: None,
                             body: None,
+                            bounds: None,
                             location: cols(11, 23)
                         }
                     ))],
@@ -6097,6 +6268,7 @@ mod tests {
                             arguments: None,
                             return_type: None,
                             body: None,
+                            bounds: None,
                             location: cols(11, 20)
                         }
                     ))],
@@ -6138,6 +6310,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(18, 19)
                             }),
+                            bounds: None,
                             location: cols(11, 19)
                         }
                     ))],
@@ -6179,6 +6352,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(25, 26)
                             }),
+                            bounds: None,
                             location: cols(11, 26)
                         }
                     ))],
@@ -6220,6 +6394,7 @@ mod tests {
                                 values: Vec::new(),
                                 location: cols(23, 24)
                             }),
+                            bounds: None,
                             location: cols(11, 24)
                         }
                     ))],
@@ -6235,7 +6410,7 @@ mod tests {
         assert_error!("trait {}", cols(7, 7));
         assert_error!("trait A {", cols(9, 9));
         assert_error!("trait A { fn static a {} }", cols(21, 21));
-        assert_error!("trait A { @foo: A }", cols(11, 14));
+        assert_error!("trait A { @foo: A }", cols(15, 15));
     }
 
     #[test]
@@ -9881,4 +10056,156 @@ mod tests {
             }))
         );
     }
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            top(parse("@packed")),
+            TopLevelExpression::Attribute(Box::new(Attribute {
+                name: Identifier {
+                    name: "packed".to_string(),
+                    location: cols(1, 7)
+                },
+                arguments: None,
+                location: cols(1, 7)
+            }))
+        );
+
+        assert_eq!(
+            top(parse("@deprecated('foo', since: '1.0')")),
+            TopLevelExpression::Attribute(Box::new(Attribute {
+                name: Identifier {
+                    name: "deprecated".to_string(),
+                    location: cols(1, 11)
+                },
+                arguments: Some(Arguments {
+                    values: vec![
+                        Argument::Positional(Expression::String(Box::new(
+                            StringLiteral {
+                                values: vec![StringValue::Text(Box::new(
+                                    StringText {
+                                        value: "foo".to_string(),
+                                        location: cols(14, 16)
+                                    }
+                                ))],
+                                location: cols(13, 17)
+                            }
+                        ))),
+                        Argument::Named(Box::new(NamedArgument {
+                            name: Identifier {
+                                name: "since".to_string(),
+                                location: cols(20, 24)
+                            },
+                            value: Expression::String(Box::new(
+                                StringLiteral {
+                                    values: vec![StringValue::Text(Box::new(
+                                        StringText {
+                                            value: "1.0".to_string(),
+                                            location: cols(28, 30)
+                                        }
+                                    ))],
+                                    location: cols(27, 31)
+                                }
+                            )),
+                            location: cols(20, 31)
+                        }))
+                    ],
+                    location: cols(12, 32)
+                }),
+                location: cols(1, 32)
+            }))
+        );
+
+        assert_eq!(
+            top(parse("class A { @packed }")),
+            TopLevelExpression::DefineClass(Box::new(DefineClass {
+                public: false,
+                inline: false,
+                kind: ClassKind::Regular,
+                name: Constant {
+                    source: None,
+                    name: "A".to_string(),
+                    location: cols(7, 7)
+                },
+                type_parameters: None,
+                body: ClassExpressions {
+                    values: vec![ClassExpression::Attribute(Box::new(
+                        Attribute {
+                            name: Identifier {
+                                name: "packed".to_string(),
+                                location: cols(11, 17)
+                            },
+                            arguments: None,
+                            location: cols(11, 17)
+                        }
+                    ))],
+                    location: cols(9, 19)
+                },
+                location: cols(1, 19)
+            }))
+        );
+
+        assert_eq!(
+            top(parse("trait A { @packed }")),
+            TopLevelExpression::DefineTrait(Box::new(DefineTrait {
+                public: false,
+                name: Constant {
+                    source: None,
+                    name: "A".to_string(),
+                    location: cols(7, 7)
+                },
+                type_parameters: None,
+                requirements: None,
+                body: TraitExpressions {
+                    values: vec![TraitExpression::Attribute(Box::new(
+                        Attribute {
+                            name: Identifier {
+                                name: "packed".to_string(),
+                                location: cols(11, 17)
+                            },
+                            arguments: None,
+                            location: cols(11, 17)
+                        }
+                    ))],
+                    location: cols(9, 19)
+                },
+                location: cols(1, 19)
+            }))
+        );
+
+        assert_eq!(
+            top(parse("impl A { @packed }")),
+            TopLevelExpression::ReopenClass(Box::new(ReopenClass {
+                bounds: None,
+                class_name: Constant {
+                    source: None,
+                    name: "A".to_string(),
+                    location: cols(6, 6)
+                },
+                body: ImplementationExpressions {
+                    values: vec![ImplementationExpression::Attribute(
+                        Box::new(Attribute {
+                            name: Identifier {
+                                name: "packed".to_string(),
+                                location: cols(10, 16)
+                            },
+                            arguments: None,
+                            location: cols(10, 16)
+                        })
+                    )],
+                    location: cols(8, 18)
+                },
+                location: cols(1, 18)
+            }))
+        );
+    }
+
+    #[test]
+    fn test_attribute_arguments_on_a_new_line() {
+        assert_error!("@align\n(8)", location(2..=2, 1..=1));
+    }
+
+    #[test]
+    fn test_attribute_in_trait_implementation() {
+        assert_error!("impl A for B { @packed }", cols(16, 22));
+    }
 }
@@ -18,9 +18,9 @@ use crate::pkg::version::Version;
 use crate::state::State;
 use crate::symbol_names::SymbolNames;
 use crate::type_check::define_types::{
-    check_recursive_types, CheckTraitImplementations, CheckTraitRequirements,
-    CheckTypeParameters, DefineConstructors, DefineFields,
-    DefineTraitRequirements, DefineTypeParameterRequirements,
+    check_coherence, check_recursive_types, CheckTraitImplementations,
+    CheckTraitRequirements, CheckTypeParameters, DefineConstructors,
+    DefineFields, DefineTraitRequirements, DefineTypeParameterRequirements,
     DefineTypeParameters, DefineTypes, ImplementTraits, InsertPrelude,
 };
 use crate::type_check::expressions::{define_constants, Expressions};
@@ -28,7 +28,7 @@ use crate::type_check::imports::{
     check_unused_imports, CollectExternImports, DefineImportedTypes,
 };
 use crate::type_check::methods::{
-    CheckMainMethod, DefineMethods, DefineModuleMethodNames,
+    CheckInlineCycles, CheckMainMethod, DefineMethods, DefineModuleMethodNames,
     ImplementTraitMethods,
 };
 use std::env::current_dir;
@@ -37,6 +37,7 @@ use std::fs::write;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use types::module_name::ModuleName;
+use types::statistics::Statistics;
 
 fn measure<R, F: FnOnce() -> R>(time: &mut Duration, func: F) -> R {
     let start = Instant::now();
@@ -111,6 +112,16 @@ pub(crate) fn all_source_modules(
     Ok(modules)
 }
 
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.2} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
 fn format_timing(duration: Duration, total: Option<Duration>) -> String {
     let base = if duration.as_secs() >= 1 {
         format!("{:.2} sec", duration.as_secs_f64())
@@ -205,11 +216,18 @@ impl From<String> for CompileError {
 pub struct Compiler {
     state: State,
     timings: Timings,
+
+    /// Statistics about the type database, gathered before compacting it.
+    statistics: Option<Statistics>,
 }
 
 impl Compiler {
     pub fn new(config: Config) -> Self {
-        Self { state: State::new(config), timings: Timings::new() }
+        Self {
+            state: State::new(config),
+            timings: Timings::new(),
+            statistics: None,
+        }
     }
 
     pub fn check(&mut self, file: Option<PathBuf>) -> Result<(), CompileError> {
@@ -260,10 +278,11 @@ impl Compiler {
 
         // Type specialization _must_ be done before optimizations and lowering
         // MIR to LLVM, otherwise we may generate incorrect code.
-        self.specialize_mir(&mut mir);
+        self.specialize_mir(&mut mir)?;
 
         // At this point we can get rid of various data structures stored in the
         // type database. This must be done _after_ specialization.
+        self.statistics = Some(self.state.db.statistics());
         self.state.db.compact();
 
         // Splitting is done _after_ specialization, since specialization
@@ -414,6 +433,35 @@ LLVM module timings:
         }
     }
 
+    pub fn print_statistics(&self) {
+        let stats = self
+            .statistics
+            .clone()
+            .unwrap_or_else(|| self.state.db.statistics());
+        let width =
+            stats.tables.iter().map(|t| t.name.len()).max().unwrap_or(0);
+
+        println!(
+            "\x1b[1m{:width$}\x1b[0m  \x1b[1m{:>10}\x1b[0m  \x1b[1m{:>10}\x1b[0m",
+            "Table",
+            "Count",
+            "Memory",
+            width = width
+        );
+
+        for table in &stats.tables {
+            println!(
+                "{:width$}  {:>10}  {:>10}",
+                table.name,
+                table.count,
+                format_bytes(table.bytes),
+                width = width
+            );
+        }
+
+        println!("\nTotal: {}", format_bytes(stats.total_bytes()));
+    }
+
     pub fn create_build_directory(&self) -> Result<(), String> {
         BuildDirectories::new(&self.state.config).create_build()
     }
@@ -470,6 +518,7 @@ LLVM module timings:
         self.timings.mir = start.elapsed();
 
         if ok {
+            self.validate_database("lowering to MIR");
             Ok(mir)
         } else {
             Err(CompileError::Invalid)
@@ -523,6 +572,7 @@ LLVM module timings:
             && CheckTraitRequirements::run_all(state, modules)
             && ImplementTraits::run_all(state, modules)
             && CheckTraitImplementations::run_all(state, modules)
+            && check_coherence(state)
             && CheckTypeParameters::run_all(state, modules)
             && DefineConstructors::run_all(state, modules)
             && DefineFields::run_all(state, modules)
@@ -532,22 +582,55 @@ LLVM module timings:
             && ImplementTraitMethods::run_all(state, modules)
             && define_constants(state, modules)
             && Expressions::run_all(state, modules)
+            && CheckInlineCycles::run(state)
             && check_unused_imports(state, modules);
 
         self.timings.type_check = start.elapsed();
 
         if res {
+            self.validate_database("type checking");
             Ok(())
         } else {
             Err(CompileError::Invalid)
         }
     }
 
-    fn specialize_mir(&mut self, mir: &mut Mir) {
+    fn specialize_mir(&mut self, mir: &mut Mir) -> Result<(), CompileError> {
         let start = Instant::now();
+        let limits = self.state.config.specialization_limits;
 
+        self.state.db.set_specialization_limits(limits);
         Specialize::run_all(&mut self.state, mir);
         self.timings.specialize_mir = start.elapsed();
+
+        for diag in self.state.db.take_specialization_errors() {
+            self.state.diagnostics.add_type_diagnostic(&self.state.db, diag);
+        }
+
+        if self.state.diagnostics.has_errors() {
+            Err(CompileError::Invalid)
+        } else {
+            self.validate_database("specialization");
+            Ok(())
+        }
+    }
+
+    /// Checks the internal invariants of the type database after the given
+    /// phase, panicking if any of them don't hold.
+    ///
+    /// This is only done for debug builds, as it requires iterating over all
+    /// the data stored in the database. Since `Database::compact()` removes the
+    /// type arguments, this can't be used after compacting the database.
+    fn validate_database(&self, phase: &str) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let report = self.state.db.validate();
+
+        if !report.is_valid() {
+            panic!("the type database is invalid after {}:\n{}", phase, report);
+        }
     }
 
     fn optimise_mir(&mut self, mir: &mut Mir) {
@@ -567,6 +650,9 @@ LLVM module timings:
         // These passes are optional and thus only enabled if optimizations are
         // enabled.
         if !matches!(self.state.config.opt, Opt::None) {
+            let policy = self.state.config.inline_policy;
+
+            self.state.db.set_inline_policy(policy);
             measure(&mut self.timings.optimize.inline, || {
                 InlineMethod::run_all(&mut self.state, mir);
             });
@@ -7,7 +7,9 @@ use std::fs::{create_dir_all, remove_dir_all};
 use std::path::{Path, PathBuf};
 use std::thread::available_parallelism;
 use std::time::SystemTime;
+use types::inline::InlinePolicy;
 use types::module_name::ModuleName;
+use types::specialize::SpecializationLimits;
 
 /// The extension to use for source files.
 pub const SOURCE_EXT: &str = "inko";
@@ -290,6 +292,12 @@ pub struct Config {
 
     /// Custom constant values to set at compile time.
     pub compile_time_variables: HashMap<(ModuleName, String), String>,
+
+    /// The limits to apply when specializing generic types and methods.
+    pub specialization_limits: SpecializationLimits,
+
+    /// The settings used for deciding which methods to inline.
+    pub inline_policy: InlinePolicy,
 }
 
 impl Config {
@@ -324,6 +332,8 @@ impl Config {
             incremental: true,
             compiled_at,
             compile_time_variables: HashMap::new(),
+            specialization_limits: SpecializationLimits::default(),
+            inline_policy: InlinePolicy::default(),
         }
     }
 
@@ -1,13 +1,19 @@
 //! Types and methods for producing compiler diagnostics.
 use location::Location;
+use std::env::current_dir;
 use std::fmt;
 use std::path::PathBuf;
+use types::diagnostics::{
+    Code, Diagnostic as TypeDiagnostic, Severity as TypeSeverity,
+};
+use types::Database;
 
 /// The unique ID of a diagnostic.
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub(crate) enum DiagnosticId {
     DuplicateSymbol,
     InvalidAssign,
+    InvalidAttribute,
     InvalidCall,
     InvalidCast,
     InvalidConstExpr,
@@ -28,6 +34,9 @@ pub(crate) enum DiagnosticId {
     Moved,
     Unreachable,
     UnusedSymbol,
+
+    /// A diagnostic produced by the `types` crate, identified by its code.
+    Types(Code),
 }
 
 impl fmt::Display for DiagnosticId {
@@ -55,6 +64,8 @@ impl fmt::Display for DiagnosticId {
             DiagnosticId::MissingMain => "missing-main",
             DiagnosticId::InvalidCast => "invalid-cast",
             DiagnosticId::UnusedSymbol => "unused-symbol",
+            DiagnosticId::InvalidAttribute => "invalid-attribute",
+            DiagnosticId::Types(code) => code.as_str(),
         };
 
         write!(f, "{}", id)
@@ -128,6 +139,13 @@ impl Diagnostic {
     }
 }
 
+/// Returns the text explaining which field makes a type unsendable, if any.
+fn unsendable_field_reason(field: Option<String>) -> String {
+    field
+        .map(|name| format!(", as its field '{}' isn't sendable", name))
+        .unwrap_or_default()
+}
+
 /// A collection of warnings and errors.
 pub(crate) struct Diagnostics {
     values: Vec<Diagnostic>,
@@ -183,6 +201,34 @@ impl Diagnostics {
         ));
     }
 
+    /// Adds a diagnostic produced by the `types` crate.
+    ///
+    /// The diagnostic retains its code, and any notes are appended to the
+    /// message as the compiler's diagnostics only support a single message.
+    pub(crate) fn add_type_diagnostic(
+        &mut self,
+        db: &Database,
+        diagnostic: TypeDiagnostic,
+    ) {
+        let file = diagnostic.module.file(db);
+        let id = DiagnosticId::Types(diagnostic.code);
+        let mut message = diagnostic.message;
+
+        for (idx, note) in diagnostic.notes.into_iter().enumerate() {
+            message.push_str(if idx == 0 { ": " } else { "; " });
+            message.push_str(&note.message);
+        }
+
+        match diagnostic.severity {
+            TypeSeverity::Warning => {
+                self.warn(id, message, file, diagnostic.location)
+            }
+            TypeSeverity::Error => {
+                self.error(id, message, file, diagnostic.location)
+            }
+        }
+    }
+
     pub(crate) fn undefined_symbol(
         &mut self,
         name: &str,
@@ -379,6 +425,58 @@ impl Diagnostics {
         );
     }
 
+    pub(crate) fn duplicate_trait_implementation(
+        &mut self,
+        trait_name: &str,
+        class_name: &str,
+        existing: (PathBuf, Location),
+        file: PathBuf,
+        location: Location,
+    ) {
+        let (existing_file, existing_loc) = existing;
+
+        // The path is shown relative to the working directory, similar to how
+        // the text presenter displays the paths of diagnostics.
+        let cwd = current_dir().unwrap_or_else(|_| PathBuf::new());
+        let existing_file =
+            existing_file.strip_prefix(&cwd).unwrap_or(&existing_file);
+
+        self.error(
+            DiagnosticId::InvalidImplementation,
+            format!(
+                "the trait '{}' is already implemented for class '{}', \
+                by the implementation defined at {}:{}:{}",
+                trait_name,
+                class_name,
+                existing_file.display(),
+                existing_loc.line_start,
+                existing_loc.column_start,
+            ),
+            file,
+            location,
+        );
+    }
+
+    pub(crate) fn recursive_inline_method(
+        &mut self,
+        name: &str,
+        cycle: &[String],
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.warn(
+            DiagnosticId::InvalidMethod,
+            format!(
+                "the method '{}' can't always be inlined as it's recursive \
+                (through '{}'), so it's inlined like a regular method instead",
+                name,
+                cycle.join("', '"),
+            ),
+            file,
+            location,
+        );
+    }
+
     pub(crate) fn private_method_call(
         &mut self,
         name: &str,
@@ -621,6 +719,26 @@ impl Diagnostics {
         );
     }
 
+    pub(crate) fn required_static_call(
+        &mut self,
+        name: &str,
+        receiver: String,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidCall,
+            format!(
+                "the static method '{}' of trait '{}' is a required method, \
+                and can only be called through a type that implements the \
+                trait",
+                name, receiver,
+            ),
+            file,
+            location,
+        );
+    }
+
     pub(crate) fn unreachable(&mut self, file: PathBuf, location: Location) {
         self.warn(
             DiagnosticId::Unreachable,
@@ -646,6 +764,7 @@ impl Diagnostics {
     pub(crate) fn unsendable_argument(
         &mut self,
         argument: String,
+        field: Option<String>,
         file: PathBuf,
         location: Location,
     ) {
@@ -653,8 +772,9 @@ impl Diagnostics {
             DiagnosticId::InvalidType,
             format!(
                 "the receiver of this call requires sendable arguments, \
-                but '{}' isn't sendable",
+                but '{}' isn't sendable{}",
                 argument,
+                unsendable_field_reason(field),
             ),
             file,
             location,
@@ -700,14 +820,16 @@ impl Diagnostics {
     pub(crate) fn unsendable_async_type(
         &mut self,
         name: String,
+        field: Option<String>,
         file: PathBuf,
         location: Location,
     ) {
         self.error(
             DiagnosticId::InvalidType,
             format!(
-                "values of type '{}' can't be sent between processes",
-                name
+                "values of type '{}' can't be sent between processes{}",
+                name,
+                unsendable_field_reason(field),
             ),
             file,
             location,
@@ -779,6 +901,25 @@ impl Diagnostics {
         );
     }
 
+    pub(crate) fn moved_into_closure(
+        &mut self,
+        name: &str,
+        captured: Location,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::Moved,
+            format!(
+                "'{}' can't be used as it has been moved into the closure \
+                that captures it on line {}, column {}",
+                name, captured.line_start, captured.column_start
+            ),
+            file,
+            location,
+        );
+    }
+
     pub(crate) fn implicit_receiver_moved(
         &mut self,
         name: &str,
@@ -937,6 +1078,50 @@ impl Diagnostics {
         );
     }
 
+    pub(crate) fn stack_pointer_escapes(
+        &mut self,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidType,
+            "pointers created using 'stack_alloc' can't escape the surrounding \
+            method",
+            file,
+            location,
+        );
+    }
+
+    pub(crate) fn stack_pointer_captured(
+        &mut self,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidSymbol,
+            "pointers created using 'stack_alloc' can't be captured by closures",
+            file,
+            location,
+        );
+    }
+
+    pub(crate) fn invalid_stack_alloc_size(
+        &mut self,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidCall,
+            format!(
+                "the size of a 'stack_alloc' allocation must be an Int literal \
+                between 1 and {}",
+                u32::MAX
+            ),
+            file,
+            location,
+        );
+    }
+
     pub(crate) fn invalid_throw(
         &mut self,
         error: String,
@@ -1015,6 +1200,51 @@ impl Diagnostics {
         );
     }
 
+    pub(crate) fn invalid_method_bounds(
+        &mut self,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidMethod,
+            "type bounds can only be applied to instance methods defined for \
+            classes",
+            file,
+            location,
+        );
+    }
+
+    pub(crate) fn unsupported_attribute(
+        &mut self,
+        name: &str,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidAttribute,
+            format!("the attribute '@{}' isn't supported here", name),
+            file,
+            location,
+        );
+    }
+
+    pub(crate) fn invalid_attribute_argument(
+        &mut self,
+        name: &str,
+        file: PathBuf,
+        location: Location,
+    ) {
+        self.error(
+            DiagnosticId::InvalidAttribute,
+            format!(
+                "this argument isn't supported by the '@{}' attribute",
+                name
+            ),
+            file,
+            location,
+        );
+    }
+
     pub(crate) fn invalid_mut_type(
         &mut self,
         name: &str,
@@ -31,6 +31,7 @@ fn class_kind(kind: ClassKind, stack: bool) -> i64 {
         ClassKind::Async => 2,
         ClassKind::Extern => 3,
         ClassKind::Atomic => 5,
+        ClassKind::Union => 6,
         _ if stack => 4,
         _ => 0,
     }
@@ -341,7 +342,7 @@ impl<'a> GenerateDocumentation<'a> {
             }
 
             let name = id.name(self.db()).clone();
-            let docs = id.documentation(self.db()).clone();
+            let docs = id.documentation(self.db()).source.clone();
             let mut obj = Object::new();
 
             // Constants such as arrays are exposed as references, but we want
@@ -390,7 +391,7 @@ impl<'a> GenerateDocumentation<'a> {
             }
 
             let name = id.name(self.db()).clone();
-            let docs = id.documentation(self.db()).clone();
+            let docs = id.documentation(self.db()).source.clone();
             let is_stack = id.is_stack_allocated(self.db());
             let mut obj = Object::new();
             let typ = format!(
@@ -401,6 +402,7 @@ impl<'a> GenerateDocumentation<'a> {
                     ClassKind::Enum => " enum",
                     ClassKind::Async => " async",
                     ClassKind::Extern => " extern",
+                    ClassKind::Union => " union",
                     _ if id.is_builtin() => " builtin",
                     _ if is_stack => " inline",
                     _ => "",
@@ -511,7 +513,7 @@ impl<'a> GenerateDocumentation<'a> {
             }
 
             let name = id.name(self.db()).clone();
-            let docs = id.documentation(self.db()).clone();
+            let docs = id.documentation(self.db()).source.clone();
             let mut obj = Object::new();
 
             obj.add("name", Json::String(name));
@@ -553,7 +555,7 @@ impl<'a> GenerateDocumentation<'a> {
                 continue;
             }
 
-            let docs = id.documentation(self.db()).clone();
+            let docs = id.documentation(self.db()).source.clone();
             let file = id.source_file(self.db()).to_string_lossy().into_owned();
             let mut obj = Object::new();
             let typ = format_method(self.db(), id);
@@ -583,7 +585,7 @@ impl<'a> GenerateDocumentation<'a> {
                 .collect();
 
             let typ = format!("{}({})", name, args.join(", "));
-            let docs = con.documentation(self.db()).clone();
+            let docs = con.documentation(self.db()).source.clone();
             let loc = location_to_json(con.location(self.db()));
 
             obj.add("name", Json::String(name));
@@ -608,7 +610,7 @@ impl<'a> GenerateDocumentation<'a> {
 
             let mut obj = Object::new();
             let name = field.name(self.db()).clone();
-            let docs = field.documentation(self.db()).clone();
+            let docs = field.documentation(self.db()).source.clone();
             let loc = location_to_json(field.location(self.db()));
             let typ = format!(
                 "let{} @{}: {}",
@@ -477,6 +477,12 @@ impl Document {
                         self.gen.new_line();
                     }
                 }
+                TopLevelExpression::Attribute(n) => {
+                    let node = self.attribute(n);
+
+                    self.gen.generate(node);
+                    self.gen.new_line();
+                }
                 TopLevelExpression::Comment(c) => {
                     self.top_level_comment(c);
 
@@ -711,6 +717,25 @@ impl Document {
         Node::Nodes(nodes)
     }
 
+    fn attribute(&mut self, node: &nodes::Attribute) -> Node {
+        let name = Node::text(&format!("@{}", node.name.name));
+        let args = match &node.arguments {
+            Some(args) if !args.values.is_empty() => args,
+            _ => return name,
+        };
+        let gid = self.new_group_id();
+        let vals = self.list(&args.values, gid, |this, node| match node {
+            nodes::Argument::Positional(n) => this.expression(n),
+            nodes::Argument::Named(n) => Node::Nodes(vec![
+                Node::text(&n.name.name),
+                Node::text(": "),
+                this.expression(&n.value),
+            ]),
+        });
+
+        Node::Nodes(vec![name, Node::Group(gid, self.argument_list(vals))])
+    }
+
     fn define_constant(&mut self, node: &nodes::DefineConstant) {
         let kw = if node.public { "let pub " } else { "let " };
         let val = self.expression(&node.value);
@@ -781,6 +806,7 @@ impl Document {
                     self.define_constructor(n),
                     matches!(next, Some(ClassExpression::DefineConstructor(_))),
                 ),
+                ClassExpression::Attribute(n) => (self.attribute(n), true),
                 ClassExpression::Comment(n) => (self.comment(n), true),
             };
 
@@ -881,6 +907,7 @@ impl Document {
                 TraitExpression::DefineMethod(n) => {
                     (self.define_method(n), false)
                 }
+                TraitExpression::Attribute(n) => (self.attribute(n), true),
                 TraitExpression::Comment(n) => (self.comment(n), true),
             };
 
@@ -1013,6 +1040,9 @@ impl Document {
                 ImplementationExpression::DefineMethod(n) => {
                     (self.define_method(n), false)
                 }
+                ImplementationExpression::Attribute(n) => {
+                    (self.attribute(n), true)
+                }
                 ImplementationExpression::Comment(n) => (self.comment(n), true),
             };
 
@@ -1133,6 +1163,10 @@ impl Document {
             header.push(self.return_type(rnode));
         }
 
+        if let Some(node) = &node.bounds {
+            header.push(self.type_bounds(node));
+        }
+
         if node.body.is_some() {
             header.push(Node::text(" {"));
         }
@@ -2302,6 +2336,13 @@ impl Document {
         };
         let mut closure = vec![open];
 
+        if let Some(nodes) =
+            node.type_parameters.as_ref().filter(|v| !v.values.is_empty())
+        {
+            closure.push(Node::text(" "));
+            closure.push(self.type_parameters(&nodes.values));
+        }
+
         if let Some(nodes) =
             node.arguments.as_ref().filter(|v| !v.values.is_empty())
         {
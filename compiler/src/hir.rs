@@ -8,14 +8,21 @@ use crate::modules_parser::ParsedModule;
 use crate::state::State;
 use ::ast::nodes::{self as ast, Node as _};
 use location::Location;
+use std::mem::take;
 use std::path::PathBuf;
 use std::str::FromStr;
+use types::deprecation::Deprecation;
+use types::layout::LayoutOptions;
 use types::{
     ARRAY_INTERNAL_NAME, ARRAY_LIMIT, ARRAY_PUSH, ARRAY_WITH_CAPACITY,
 };
 
 const BUILTIN_RECEIVER: &str = "_INKO";
 const ARRAY_LIT_VAR: &str = "$array";
+const DEPRECATED_ATTRIBUTE: &str = "deprecated";
+const SINCE_ARGUMENT: &str = "since";
+const PACKED_ATTRIBUTE: &str = "packed";
+const ALIGN_ATTRIBUTE: &str = "align";
 
 struct Comments {
     nodes: Vec<ast::Comment>,
@@ -70,6 +77,44 @@ impl Comments {
     }
 }
 
+/// The attributes that precede a definition.
+struct Attributes {
+    nodes: Vec<ast::Attribute>,
+
+    /// The documentation of the definition, which precedes the first attribute
+    /// instead of the definition itself.
+    documentation: String,
+}
+
+impl Attributes {
+    fn new() -> Attributes {
+        Attributes { nodes: Vec::new(), documentation: String::new() }
+    }
+
+    fn push(&mut self, comments: &mut Comments, node: ast::Attribute) {
+        if self.nodes.is_empty() {
+            self.documentation = comments.documentation_for(&node.location);
+        }
+
+        self.nodes.push(node);
+    }
+
+    /// Returns the documentation and attributes of the definition defined at
+    /// the given location.
+    fn take(
+        &mut self,
+        comments: &mut Comments,
+        location: &Location,
+    ) -> (String, Vec<ast::Attribute>) {
+        if self.nodes.is_empty() {
+            (comments.documentation_for(location), Vec::new())
+        } else {
+            comments.nodes.clear();
+            (take(&mut self.documentation), take(&mut self.nodes))
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct IntLiteral {
     pub(crate) value: i64,
@@ -263,6 +308,7 @@ pub(crate) struct ExternImport {
 pub(crate) struct DefineConstant {
     pub(crate) public: bool,
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) constant_id: Option<types::ConstantId>,
     pub(crate) name: Constant,
     pub(crate) value: ConstExpression,
@@ -279,6 +325,7 @@ pub(crate) enum MethodKind {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineInstanceMethod {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) inline: bool,
     pub(crate) kind: MethodKind,
@@ -286,6 +333,7 @@ pub(crate) struct DefineInstanceMethod {
     pub(crate) type_parameters: Vec<TypeParameter>,
     pub(crate) arguments: Vec<MethodArgument>,
     pub(crate) return_type: Option<Type>,
+    pub(crate) bounds: Vec<TypeBound>,
     pub(crate) body: Vec<Expression>,
     pub(crate) location: Location,
     pub(crate) method_id: Option<types::MethodId>,
@@ -294,6 +342,7 @@ pub(crate) struct DefineInstanceMethod {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineModuleMethod {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) inline: bool,
     pub(crate) c_calling_convention: bool,
@@ -309,6 +358,7 @@ pub(crate) struct DefineModuleMethod {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineExternFunction {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) name: Identifier,
     pub(crate) arguments: Vec<MethodArgument>,
@@ -321,6 +371,7 @@ pub(crate) struct DefineExternFunction {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineRequiredMethod {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) kind: MethodKind,
     pub(crate) name: Identifier,
@@ -334,6 +385,7 @@ pub(crate) struct DefineRequiredMethod {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineStaticMethod {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) inline: bool,
     pub(crate) name: Identifier,
@@ -348,6 +400,7 @@ pub(crate) struct DefineStaticMethod {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineAsyncMethod {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) mutable: bool,
     pub(crate) public: bool,
     pub(crate) name: Identifier,
@@ -389,6 +442,7 @@ pub(crate) enum ClassKind {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineClass {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) inline: bool,
     pub(crate) class_id: Option<types::ClassId>,
@@ -402,10 +456,12 @@ pub(crate) struct DefineClass {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineExternClass {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) class_id: Option<types::ClassId>,
     pub(crate) name: Constant,
     pub(crate) fields: Vec<DefineField>,
+    pub(crate) layout: LayoutOptions,
     pub(crate) location: Location,
 }
 
@@ -428,6 +484,7 @@ pub(crate) enum TraitExpression {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct DefineTrait {
     pub(crate) documentation: String,
+    pub(crate) deprecated: Option<Deprecation>,
     pub(crate) public: bool,
     pub(crate) trait_id: Option<types::TraitId>,
     pub(crate) name: Constant,
@@ -502,8 +559,34 @@ pub(crate) struct Try {
     pub(crate) return_type: types::TypeRef,
 }
 
+/// The kind of information to obtain about a type's memory layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SizeOfKind {
+    /// The size of the LLVM type of a type, as used by `size_of_type_name`.
+    TypeName,
+
+    /// The size of a type according to its layout, as computed by the type
+    /// database.
+    Size,
+
+    /// The alignment of a type according to its layout.
+    Alignment,
+}
+
+impl SizeOfKind {
+    fn from_name(name: &str) -> Option<SizeOfKind> {
+        match name {
+            "size_of_type_name" => Some(SizeOfKind::TypeName),
+            "size_of_type" => Some(SizeOfKind::Size),
+            "align_of_type" => Some(SizeOfKind::Alignment),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct SizeOf {
+    pub(crate) kind: SizeOfKind,
     pub(crate) argument: Type,
     pub(crate) resolved_type: types::TypeRef,
     pub(crate) location: Location,
@@ -685,6 +768,13 @@ impl Argument {
         }
     }
 
+    pub fn value(&self) -> &Expression {
+        match self {
+            Argument::Positional(n) => &n.value,
+            Argument::Named(n) => &n.value,
+        }
+    }
+
     pub fn into_value(self) -> Expression {
         match self {
             Argument::Positional(n) => n.value,
@@ -717,6 +807,7 @@ pub(crate) enum ReferrableType {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct ClosureType {
+    pub(crate) type_parameters: Vec<TypeParameter>,
     pub(crate) arguments: Vec<Type>,
     pub(crate) return_type: Option<Type>,
     pub(crate) location: Location,
@@ -1131,6 +1222,7 @@ impl<'a> LowerToHir<'a> {
                     module.ast.file.clone(),
                 );
 
+                state.assign_package(module_id);
                 LowerToHir { state, module: module_id }.run(module)
             })
             .collect()
@@ -1179,36 +1271,45 @@ impl<'a> LowerToHir<'a> {
 
         let mut exprs = Vec::new();
         let mut comments = Comments::new();
+        let mut attrs = Attributes::new();
 
         for node in nodes {
             match node {
                 ast::TopLevelExpression::DefineConstant(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
-                    exprs.push(self.define_constant(*node, doc));
+                    exprs.push(self.define_constant(*node, doc, attrs));
                 }
                 ast::TopLevelExpression::DefineMethod(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
-                    exprs.push(self.define_module_method(*node, doc));
+                    exprs.push(self.define_module_method(*node, doc, attrs));
                 }
                 ast::TopLevelExpression::DefineClass(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
-                    exprs.push(self.define_class(*node, doc));
+                    exprs.push(self.define_class(*node, doc, attrs));
                 }
                 ast::TopLevelExpression::DefineTrait(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
-                    exprs.push(self.define_trait(*node, doc));
+                    exprs.push(self.define_trait(*node, doc, attrs));
                 }
                 ast::TopLevelExpression::ReopenClass(node) => {
+                    self.unsupported_attributes(take(&mut attrs.nodes));
                     exprs.push(self.reopen_class(*node));
                 }
                 ast::TopLevelExpression::ImplementTrait(node) => {
+                    self.unsupported_attributes(take(&mut attrs.nodes));
                     exprs.push(self.implement_trait(*node));
                 }
                 ast::TopLevelExpression::Import(node) => {
+                    self.unsupported_attributes(take(&mut attrs.nodes));
+
                     // Build tags are evaluated as modules are parsed and
                     // imports are crawled. We ignore any imports filtered out
                     // through those tags here, such that the rest of the
@@ -1218,14 +1319,19 @@ impl<'a> LowerToHir<'a> {
                     }
                 }
                 ast::TopLevelExpression::ExternImport(node) => {
+                    self.unsupported_attributes(take(&mut attrs.nodes));
                     exprs.push(self.extern_import(*node));
                 }
+                ast::TopLevelExpression::Attribute(node) => {
+                    attrs.push(&mut comments, *node);
+                }
                 ast::TopLevelExpression::Comment(c) => {
                     comments.push(*c);
                 }
             }
         }
 
+        self.unsupported_attributes(attrs.nodes);
         (doc, exprs)
     }
 
@@ -1233,9 +1339,11 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineConstant,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TopLevelExpression {
         let node = DefineConstant {
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             constant_id: None,
             name: Constant {
@@ -1253,14 +1361,17 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TopLevelExpression {
         self.operator_method_not_allowed(node.operator, node.location);
 
         let external = matches!(node.kind, ast::MethodKind::Extern);
+        let deprecated = self.deprecation(attributes);
 
         if external && node.body.is_none() {
             TopLevelExpression::ExternFunction(Box::new(DefineExternFunction {
                 documentation,
+                deprecated,
                 public: node.public,
                 name: self.identifier(node.name),
                 variadic: node.arguments.as_ref().map_or(false, |a| a.variadic),
@@ -1273,6 +1384,7 @@ impl<'a> LowerToHir<'a> {
             TopLevelExpression::ModuleMethod(Box::new(DefineModuleMethod {
                 inline: node.inline,
                 documentation,
+                deprecated,
                 public: node.public,
                 c_calling_convention: external,
                 name: self.identifier(node.name),
@@ -1310,6 +1422,7 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineClass,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TopLevelExpression {
         if node.inline {
             match node.kind {
@@ -1327,11 +1440,12 @@ impl<'a> LowerToHir<'a> {
         }
 
         if let ast::ClassKind::Extern = node.kind {
-            return self.define_extern_class(node, documentation);
+            return self.define_extern_class(node, documentation, attributes);
         }
 
         TopLevelExpression::Class(Box::new(DefineClass {
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             inline: node.inline,
             class_id: None,
@@ -1353,7 +1467,10 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineClass,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TopLevelExpression {
+        let (layout, attributes) = self.layout_options(attributes);
+        let deprecated = self.deprecation(attributes);
         let mut fields = Vec::new();
         let mut comments = Comments::new();
 
@@ -1373,10 +1490,12 @@ impl<'a> LowerToHir<'a> {
 
         TopLevelExpression::ExternClass(Box::new(DefineExternClass {
             documentation,
+            deprecated,
             public: node.public,
             class_id: None,
             name: self.constant(node.name),
             fields,
+            layout,
             location: node.location,
         }))
     }
@@ -1387,32 +1506,42 @@ impl<'a> LowerToHir<'a> {
     ) -> Vec<ClassExpression> {
         let mut exprs = Vec::new();
         let mut comments = Comments::new();
+        let mut attrs = Attributes::new();
 
         for n in node.values {
             match n {
                 ast::ClassExpression::DefineMethod(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
-                    exprs.push(self.define_method_in_class(*node, doc));
+                    exprs.push(self.define_method_in_class(*node, doc, attrs));
                 }
                 ast::ClassExpression::DefineField(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
+                    self.unsupported_attributes(attrs);
                     exprs.push(ClassExpression::Field(Box::new(
                         self.define_field(*node, doc),
                     )));
                 }
                 ast::ClassExpression::DefineConstructor(node) => {
-                    let doc = comments.documentation_for(&node.location);
+                    let (doc, attrs) =
+                        attrs.take(&mut comments, &node.location);
 
+                    self.unsupported_attributes(attrs);
                     exprs.push(self.define_case(*node, doc));
                 }
+                ast::ClassExpression::Attribute(node) => {
+                    attrs.push(&mut comments, *node);
+                }
                 ast::ClassExpression::Comment(c) => {
                     comments.push(*c);
                 }
             }
         }
 
+        self.unsupported_attributes(attrs.nodes);
         exprs
     }
 
@@ -1450,18 +1579,21 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> ClassExpression {
         match node.kind {
             ast::MethodKind::Async | ast::MethodKind::AsyncMutable => {
-                ClassExpression::AsyncMethod(
-                    self.define_async_method(node, documentation),
-                )
+                ClassExpression::AsyncMethod(self.define_async_method(
+                    node,
+                    documentation,
+                    attributes,
+                ))
             }
             ast::MethodKind::Static => ClassExpression::StaticMethod(
-                self.define_static_method(node, documentation),
+                self.define_static_method(node, documentation, attributes),
             ),
             _ => ClassExpression::InstanceMethod(Box::new(
-                self.define_instance_method(node, documentation),
+                self.define_instance_method(node, documentation, attributes),
             )),
         }
     }
@@ -1470,12 +1602,15 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> Box<DefineStaticMethod> {
         self.operator_method_not_allowed(node.operator, node.location);
+        self.disallow_method_bounds(&node);
 
         Box::new(DefineStaticMethod {
             inline: node.inline,
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             name: self.identifier(node.name),
             type_parameters: self
@@ -1492,12 +1627,15 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> Box<DefineAsyncMethod> {
         self.operator_method_not_allowed(node.operator, node.location);
         self.disallow_inline_method(&node);
+        self.disallow_method_bounds(&node);
 
         Box::new(DefineAsyncMethod {
             documentation,
+            deprecated: self.deprecation(attributes),
             mutable: node.kind == ast::MethodKind::AsyncMutable,
             public: node.public,
             name: self.identifier(node.name),
@@ -1515,10 +1653,12 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> DefineInstanceMethod {
         DefineInstanceMethod {
             inline: node.inline,
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             kind: match node.kind {
                 ast::MethodKind::Moving => MethodKind::Moving,
@@ -1530,6 +1670,7 @@ impl<'a> LowerToHir<'a> {
                 .optional_type_parameters(node.type_parameters),
             arguments: self.optional_method_arguments(node.arguments),
             return_type: node.return_type.map(|n| self.type_reference(n)),
+            bounds: self.optional_type_bounds(node.bounds),
             body: self.optional_expressions(node.body),
             method_id: None,
             location: node.location,
@@ -1540,11 +1681,13 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> Box<DefineRequiredMethod> {
         self.disallow_inline_method(&node);
 
         Box::new(DefineRequiredMethod {
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             kind: match node.kind {
                 ast::MethodKind::Moving => MethodKind::Moving,
@@ -1642,9 +1785,11 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineTrait,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TopLevelExpression {
         TopLevelExpression::Trait(Box::new(DefineTrait {
             documentation,
+            deprecated: self.deprecation(attributes),
             public: node.public,
             trait_id: None,
             name: self.constant(node.name),
@@ -1662,13 +1807,17 @@ impl<'a> LowerToHir<'a> {
     ) -> Vec<TraitExpression> {
         let mut exprs = Vec::new();
         let mut comments = Comments::new();
+        let mut attrs = Attributes::new();
 
         for node in node.values {
             match node {
                 ast::TraitExpression::DefineMethod(n) => {
-                    let doc = comments.documentation_for(&n.location);
+                    let (doc, attrs) = attrs.take(&mut comments, &n.location);
 
-                    exprs.push(self.define_method_in_trait(*n, doc));
+                    exprs.push(self.define_method_in_trait(*n, doc, attrs));
+                }
+                ast::TraitExpression::Attribute(n) => {
+                    attrs.push(&mut comments, *n);
                 }
                 ast::TraitExpression::Comment(c) => {
                     comments.push(*c);
@@ -1676,6 +1825,7 @@ impl<'a> LowerToHir<'a> {
             }
         }
 
+        self.unsupported_attributes(attrs.nodes);
         exprs
     }
 
@@ -1683,15 +1833,20 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> TraitExpression {
+        self.disallow_method_bounds(&node);
+
         if node.body.is_some() {
             TraitExpression::InstanceMethod(Box::new(
-                self.define_instance_method(node, documentation),
+                self.define_instance_method(node, documentation, attributes),
             ))
         } else {
-            TraitExpression::RequiredMethod(
-                self.define_required_method(node, documentation),
-            )
+            TraitExpression::RequiredMethod(self.define_required_method(
+                node,
+                documentation,
+                attributes,
+            ))
         }
     }
 
@@ -1711,13 +1866,19 @@ impl<'a> LowerToHir<'a> {
     ) -> Vec<ReopenClassExpression> {
         let mut exprs = Vec::new();
         let mut comments = Comments::new();
+        let mut attrs = Attributes::new();
 
         for node in nodes.values {
             match node {
                 ast::ImplementationExpression::DefineMethod(n) => {
-                    let doc = comments.documentation_for(&n.location);
+                    let (doc, attrs) = attrs.take(&mut comments, &n.location);
 
-                    exprs.push(self.define_method_in_reopen_class(*n, doc));
+                    exprs.push(
+                        self.define_method_in_reopen_class(*n, doc, attrs),
+                    );
+                }
+                ast::ImplementationExpression::Attribute(n) => {
+                    attrs.push(&mut comments, *n);
                 }
                 ast::ImplementationExpression::Comment(c) => {
                     comments.push(*c);
@@ -1725,6 +1886,7 @@ impl<'a> LowerToHir<'a> {
             }
         }
 
+        self.unsupported_attributes(attrs.nodes);
         exprs
     }
 
@@ -1732,18 +1894,21 @@ impl<'a> LowerToHir<'a> {
         &mut self,
         node: ast::DefineMethod,
         documentation: String,
+        attributes: Vec<ast::Attribute>,
     ) -> ReopenClassExpression {
         match node.kind {
             ast::MethodKind::Static => ReopenClassExpression::StaticMethod(
-                self.define_static_method(node, documentation),
+                self.define_static_method(node, documentation, attributes),
             ),
             ast::MethodKind::Async | ast::MethodKind::AsyncMutable => {
-                ReopenClassExpression::AsyncMethod(
-                    self.define_async_method(node, documentation),
-                )
+                ReopenClassExpression::AsyncMethod(self.define_async_method(
+                    node,
+                    documentation,
+                    attributes,
+                ))
             }
             _ => ReopenClassExpression::InstanceMethod(Box::new(
-                self.define_instance_method(node, documentation),
+                self.define_instance_method(node, documentation, attributes),
             )),
         }
     }
@@ -1775,7 +1940,15 @@ impl<'a> LowerToHir<'a> {
                 ast::ImplementationExpression::DefineMethod(n) => {
                     let doc = comments.documentation_for(&n.location);
 
-                    exprs.push(self.define_instance_method(*n, doc));
+                    self.disallow_method_bounds(&n);
+                    exprs.push(self.define_instance_method(
+                        *n,
+                        doc,
+                        Vec::new(),
+                    ));
+                }
+                ast::ImplementationExpression::Attribute(n) => {
+                    self.unsupported_attributes(vec![*n]);
                 }
                 ast::ImplementationExpression::Comment(c) => {
                     comments.push(*c);
@@ -1903,7 +2076,15 @@ impl<'a> LowerToHir<'a> {
     }
 
     fn closure_type(&self, node: ast::ClosureType) -> Box<ClosureType> {
+        let type_parameters = node.type_parameters.map_or(Vec::new(), |n| {
+            n.values
+                .into_iter()
+                .map(|n| self.closure_type_parameter(n))
+                .collect()
+        });
+
         Box::new(ClosureType {
+            type_parameters,
             arguments: self.optional_types(node.arguments),
             return_type: node.return_type.map(|n| self.type_reference(n)),
             location: node.location,
@@ -1911,6 +2092,34 @@ impl<'a> LowerToHir<'a> {
         })
     }
 
+    fn closure_type_parameter(
+        &self,
+        node: ast::TypeParameter,
+    ) -> TypeParameter {
+        let mut requirements = Vec::new();
+        let mut mutable = false;
+        let mut inline = false;
+
+        for req in node.requirements.map_or(Vec::new(), |n| n.values) {
+            match req {
+                ast::Requirement::Trait(n) => {
+                    requirements.push(self.type_name(n))
+                }
+                ast::Requirement::Mutable(_) => mutable = true,
+                ast::Requirement::Inline(_) => inline = true,
+            }
+        }
+
+        TypeParameter {
+            type_parameter_id: None,
+            name: self.constant(node.name),
+            requirements,
+            mutable,
+            inline,
+            location: node.location,
+        }
+    }
+
     fn tuple_type(&self, node: ast::TupleType) -> Box<TupleType> {
         Box::new(TupleType {
             resolved_type: types::TypeRef::Unknown,
@@ -2513,11 +2722,11 @@ impl<'a> LowerToHir<'a> {
                     .intrinsic_not_available(self.file(), node.location);
             }
 
-            // We special-case this instruction because we need to attach extra
-            // type information, but don't want to introduce a dedicated
+            // We special-case these instructions because we need to attach
+            // extra type information, but don't want to introduce a dedicated
             // `size_of` keyword just for this.
-            if node.name.name == "size_of_type_name" {
-                return self.size_of(node);
+            if let Some(kind) = SizeOfKind::from_name(&node.name.name) {
+                return self.size_of(kind, node);
             }
 
             return Expression::BuiltinCall(Box::new(BuiltinCall {
@@ -2539,7 +2748,7 @@ impl<'a> LowerToHir<'a> {
         }))
     }
 
-    fn size_of(&mut self, node: ast::Call) -> Expression {
+    fn size_of(&mut self, kind: SizeOfKind, node: ast::Call) -> Expression {
         if let Some(ast::Argument::Positional(ast::Expression::Constant(n))) =
             node.arguments.and_then(|mut v| v.values.pop())
         {
@@ -2552,6 +2761,7 @@ impl<'a> LowerToHir<'a> {
             }));
 
             Expression::SizeOf(Box::new(SizeOf {
+                kind,
                 argument,
                 resolved_type: types::TypeRef::Unknown,
                 location: node.location,
@@ -3263,6 +3473,181 @@ impl<'a> LowerToHir<'a> {
         );
     }
 
+    /// Returns the deprecation of a definition, based on the attributes
+    /// applied to it.
+    fn deprecation(
+        &mut self,
+        nodes: Vec<ast::Attribute>,
+    ) -> Option<Deprecation> {
+        let mut value = None;
+
+        for node in nodes {
+            match node.name.name.as_str() {
+                DEPRECATED_ATTRIBUTE if value.is_none() => {
+                    value = self.deprecated_attribute(node);
+                }
+                _ => self.unsupported_attributes(vec![node]),
+            }
+        }
+
+        value
+    }
+
+    fn deprecated_attribute(
+        &mut self,
+        node: ast::Attribute,
+    ) -> Option<Deprecation> {
+        let mut value = Deprecation { message: String::new(), since: None };
+        let args = node.arguments.map_or(Vec::new(), |n| n.values);
+
+        for (idx, arg) in args.into_iter().enumerate() {
+            match arg {
+                ast::Argument::Positional(n) if idx == 0 => {
+                    value.message = self.attribute_string(n)?;
+                }
+                ast::Argument::Named(n)
+                    if n.name.name == SINCE_ARGUMENT
+                        && value.since.is_none() =>
+                {
+                    value.since = Some(self.attribute_string(n.value)?);
+                }
+                n => {
+                    self.state.diagnostics.invalid_attribute_argument(
+                        DEPRECATED_ATTRIBUTE,
+                        self.file(),
+                        *n.location(),
+                    );
+
+                    return None;
+                }
+            }
+        }
+
+        Some(value)
+    }
+
+    /// Returns the layout options of an extern class, along with the
+    /// attributes that don't specify any layout options.
+    fn layout_options(
+        &mut self,
+        nodes: Vec<ast::Attribute>,
+    ) -> (LayoutOptions, Vec<ast::Attribute>) {
+        let mut opts = LayoutOptions::default();
+        let mut rest = Vec::new();
+
+        for node in nodes {
+            match node.name.name.as_str() {
+                PACKED_ATTRIBUTE if !opts.packed => {
+                    match node
+                        .arguments
+                        .and_then(|n| n.values.into_iter().next())
+                    {
+                        Some(arg) => {
+                            self.state.diagnostics.invalid_attribute_argument(
+                                PACKED_ATTRIBUTE,
+                                self.file(),
+                                *arg.location(),
+                            );
+                        }
+                        None => opts.packed = true,
+                    }
+                }
+                ALIGN_ATTRIBUTE if opts.align.is_none() => {
+                    opts.align = self.align_attribute(node);
+                }
+                _ => rest.push(node),
+            }
+        }
+
+        (opts, rest)
+    }
+
+    fn align_attribute(&mut self, node: ast::Attribute) -> Option<u32> {
+        let mut args = node.arguments.map_or(Vec::new(), |n| n.values);
+
+        if args.len() != 1 {
+            self.state.diagnostics.error(
+                DiagnosticId::InvalidAttribute,
+                "the '@align' attribute requires a single Int literal argument",
+                self.file(),
+                node.location,
+            );
+
+            return None;
+        }
+
+        let loc = match args.pop().unwrap() {
+            ast::Argument::Positional(ast::Expression::Int(n)) => {
+                let loc = n.location;
+
+                if let Ok(val) = u32::try_from(self.int_literal(*n).value) {
+                    return Some(val);
+                }
+
+                loc
+            }
+            n => *n.location(),
+        };
+
+        self.state.diagnostics.invalid_attribute_argument(
+            ALIGN_ATTRIBUTE,
+            self.file(),
+            loc,
+        );
+
+        None
+    }
+
+    /// Returns the value of a String literal passed to an attribute.
+    ///
+    /// Attributes are processed before type checking, so the literal can't
+    /// use string interpolation.
+    fn attribute_string(&mut self, node: ast::Expression) -> Option<String> {
+        let lit = match node {
+            ast::Expression::String(n) => n,
+            n => {
+                self.state.diagnostics.error(
+                    DiagnosticId::InvalidAttribute,
+                    "expected a String literal",
+                    self.file(),
+                    *n.location(),
+                );
+
+                return None;
+            }
+        };
+        let mut value = String::new();
+
+        for val in lit.values {
+            match val {
+                ast::StringValue::Text(n) => value += &n.value,
+                ast::StringValue::Escape(n) => value += &n.value,
+                ast::StringValue::Expression(n) => {
+                    self.state.diagnostics.error(
+                        DiagnosticId::InvalidAttribute,
+                        "string interpolation isn't supported in attributes",
+                        self.file(),
+                        n.location,
+                    );
+
+                    return None;
+                }
+            }
+        }
+
+        Some(value)
+    }
+
+    fn unsupported_attributes(&mut self, nodes: Vec<ast::Attribute>) {
+        for node in nodes {
+            self.state.diagnostics.unsupported_attribute(
+                &node.name.name,
+                self.file(),
+                node.location,
+            );
+        }
+    }
+
     fn disallow_inline_method(&mut self, node: &ast::DefineMethod) {
         if node.inline {
             self.state
@@ -3270,6 +3655,14 @@ impl<'a> LowerToHir<'a> {
                 .invalid_inline_method(self.file(), node.location);
         }
     }
+
+    fn disallow_method_bounds(&mut self, node: &ast::DefineMethod) {
+        if let Some(bounds) = &node.bounds {
+            self.state
+                .diagnostics
+                .invalid_method_bounds(self.file(), bounds.location);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -3396,6 +3789,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3417,6 +3811,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: true,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(9, 9) },
@@ -3439,6 +3834,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3461,6 +3857,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3483,6 +3880,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3505,6 +3903,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3537,6 +3936,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3563,6 +3963,7 @@ mod tests {
             hir,
             TopLevelExpression::Constant(Box::new(DefineConstant {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 constant_id: None,
                 name: Constant { name: "A".to_string(), location: cols(5, 5) },
@@ -3727,6 +4128,7 @@ mod tests {
         assert_eq!(
             hir,
             Type::Closure(Box::new(ClosureType {
+                type_parameters: Vec::new(),
                 arguments: vec![Type::Named(Box::new(TypeName {
                     source: None,
                     resolved_type: types::TypeRef::Unknown,
@@ -3763,6 +4165,7 @@ mod tests {
             TopLevelExpression::ModuleMethod(Box::new(DefineModuleMethod {
                 inline: false,
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 c_calling_convention: false,
                 name: Identifier {
@@ -3837,6 +4240,7 @@ mod tests {
             TopLevelExpression::ModuleMethod(Box::new(DefineModuleMethod {
                 inline: true,
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 c_calling_convention: false,
                 name: Identifier {
@@ -3863,6 +4267,7 @@ mod tests {
             TopLevelExpression::ExternFunction(Box::new(
                 DefineExternFunction {
                     documentation: String::new(),
+                    deprecated: None,
                     public: false,
                     name: Identifier {
                         name: "foo".to_string(),
@@ -3888,6 +4293,7 @@ mod tests {
             TopLevelExpression::ModuleMethod(Box::new(DefineModuleMethod {
                 inline: false,
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 c_calling_convention: true,
                 name: Identifier {
@@ -3943,6 +4349,7 @@ mod tests {
             TopLevelExpression::ExternFunction(Box::new(
                 DefineExternFunction {
                     documentation: String::new(),
+                    deprecated: None,
                     public: false,
                     name: Identifier {
                         name: "foo".to_string(),
@@ -3958,6 +4365,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lower_deprecated_attribute() {
+        let (hir, diags) =
+            lower_top_expr("@deprecated('use B', since: '1.2')\nclass A {}");
+
+        assert_eq!(diags, 0);
+
+        match hir {
+            TopLevelExpression::Class(n) => assert_eq!(
+                n.deprecated,
+                Some(Deprecation {
+                    message: "use B".to_string(),
+                    since: Some("1.2".to_string())
+                })
+            ),
+            _ => panic!("expected a class"),
+        }
+    }
+
+    #[test]
+    fn test_lower_deprecated_attribute_without_arguments() {
+        let (hir, diags) = lower_top_expr("class A {}\n@deprecated\nfn foo {}");
+
+        assert_eq!(diags, 0);
+
+        match hir {
+            TopLevelExpression::ModuleMethod(n) => assert_eq!(
+                n.deprecated,
+                Some(Deprecation { message: String::new(), since: None })
+            ),
+            _ => panic!("expected a module method"),
+        }
+    }
+
+    #[test]
+    fn test_lower_invalid_attributes() {
+        assert_eq!(lower("@foo\nclass A {}").1, 1);
+        assert_eq!(lower("@deprecated\nimport std.foo").1, 1);
+        assert_eq!(lower("class A {\n@deprecated\nlet @a: Int\n}").1, 1);
+        assert_eq!(lower("class A {}\n@deprecated").1, 1);
+        assert_eq!(lower("@deprecated(10)\nclass A {}").1, 1);
+        assert_eq!(lower("@deprecated('${10}')\nclass A {}").1, 1);
+        assert_eq!(lower("@deprecated(foo: 'a')\nclass A {}").1, 1);
+        assert_eq!(lower("@deprecated\n@deprecated\nclass A {}").1, 1);
+    }
+
     #[test]
     fn test_lower_class() {
         let hir = lower_top_expr("class A[B: C] { let @a: B }").0;
@@ -3966,6 +4419,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 kind: ClassKind::Regular,
@@ -4024,6 +4478,7 @@ mod tests {
             hir,
             TopLevelExpression::ExternClass(Box::new(DefineExternClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 class_id: None,
                 name: Constant {
@@ -4050,11 +4505,47 @@ mod tests {
                     })),
                     location: cols(18, 26),
                 }],
+                layout: LayoutOptions::default(),
                 location: cols(1, 28)
             })),
         );
     }
 
+    #[test]
+    fn test_lower_extern_class_with_layout_attributes() {
+        let (hir, diags) = lower_top_expr(
+            "@packed\nclass extern A {}\n@align(16)\nclass extern B {}",
+        );
+
+        assert_eq!(diags, 0);
+
+        match hir {
+            TopLevelExpression::ExternClass(n) => assert_eq!(
+                n.layout,
+                LayoutOptions { packed: false, align: Some(16) }
+            ),
+            _ => panic!("expected an extern class"),
+        }
+
+        match lower("@packed\nclass extern A {}").0.expressions.pop() {
+            Some(TopLevelExpression::ExternClass(n)) => assert_eq!(
+                n.layout,
+                LayoutOptions { packed: true, align: None }
+            ),
+            _ => panic!("expected an extern class"),
+        }
+    }
+
+    #[test]
+    fn test_lower_invalid_layout_attributes() {
+        assert_eq!(lower("@packed\nclass A {}").1, 1);
+        assert_eq!(lower("@packed(1)\nclass extern A {}").1, 1);
+        assert_eq!(lower("@align\nclass extern A {}").1, 1);
+        assert_eq!(lower("@align('a')\nclass extern A {}").1, 1);
+        assert_eq!(lower("@align(-8)\nclass extern A {}").1, 1);
+        assert_eq!(lower("@align(8, 8)\nclass extern A {}").1, 1);
+    }
+
     #[test]
     fn test_lower_inline_class() {
         let hir = lower_top_expr("class inline A { let @a: B }").0;
@@ -4063,6 +4554,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: true,
                 class_id: None,
@@ -4105,6 +4597,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: true,
                 inline: false,
                 kind: ClassKind::Regular,
@@ -4128,6 +4621,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 kind: ClassKind::Regular,
@@ -4167,6 +4661,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4228,6 +4723,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4252,6 +4748,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4262,6 +4759,7 @@ mod tests {
                     DefineStaticMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         name: Identifier {
                             name: "a".to_string(),
@@ -4328,6 +4826,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4337,6 +4836,7 @@ mod tests {
                 body: vec![ClassExpression::AsyncMethod(Box::new(
                     DefineAsyncMethod {
                         documentation: String::new(),
+                        deprecated: None,
                         mutable: false,
                         public: false,
                         name: Identifier {
@@ -4403,6 +4903,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4413,6 +4914,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4457,6 +4959,7 @@ mod tests {
                             arguments: Vec::new(),
                             location: cols(28, 28)
                         }))),
+                        bounds: Vec::new(),
                         body: vec![Expression::Int(Box::new(IntLiteral {
                             value: 10,
                             resolved_type: types::TypeRef::Unknown,
@@ -4479,6 +4982,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 class_id: None,
@@ -4489,6 +4993,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: true,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4498,6 +5003,7 @@ mod tests {
                         type_parameters: Vec::new(),
                         arguments: Vec::new(),
                         return_type: None,
+                        bounds: Vec::new(),
                         body: Vec::new(),
                         method_id: None,
                         location: cols(11, 26)
@@ -4522,6 +5028,45 @@ mod tests {
         assert_eq!(diags, 1);
     }
 
+    #[test]
+    fn test_lower_method_bounds() {
+        let (hir, diags) = lower_top_expr("impl A { fn a if T: mut {} }");
+        let method = match hir {
+            TopLevelExpression::Reopen(mut node) => match node.body.remove(0) {
+                ReopenClassExpression::InstanceMethod(node) => node,
+                _ => panic!("the expression must be an instance method"),
+            },
+            _ => panic!("the top-level expression must be a reopened class"),
+        };
+
+        assert_eq!(diags, 0);
+        assert_eq!(method.bounds.len(), 1);
+        assert_eq!(method.bounds[0].name.name, "T");
+        assert!(method.bounds[0].mutable);
+    }
+
+    #[test]
+    fn test_lower_static_method_with_bounds() {
+        let diags = lower_top_expr("class A { fn static a if T: mut {} }").1;
+
+        assert_eq!(diags, 1);
+    }
+
+    #[test]
+    fn test_lower_async_method_with_bounds() {
+        let diags =
+            lower_top_expr("class async A { fn async a if T: mut {} }").1;
+
+        assert_eq!(diags, 1);
+    }
+
+    #[test]
+    fn test_lower_trait_implementation_method_with_bounds() {
+        let diags = lower_top_expr("impl A for B { fn a if T: mut {} }").1;
+
+        assert_eq!(diags, 1);
+    }
+
     #[test]
     fn test_lower_trait() {
         let hir = lower_top_expr("trait A[T]: B {}").0;
@@ -4530,6 +5075,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4568,6 +5114,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: true,
                 trait_id: None,
                 name: Constant {
@@ -4590,6 +5137,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4598,6 +5146,7 @@ mod tests {
                 body: vec![TraitExpression::RequiredMethod(Box::new(
                     DefineRequiredMethod {
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4659,6 +5208,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4667,6 +5217,7 @@ mod tests {
                 body: vec![TraitExpression::RequiredMethod(Box::new(
                     DefineRequiredMethod {
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Moving,
                         name: Identifier {
@@ -4693,6 +5244,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4702,6 +5254,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Moving,
                         name: Identifier {
@@ -4711,6 +5264,7 @@ mod tests {
                         type_parameters: Vec::new(),
                         arguments: Vec::new(),
                         return_type: None,
+                        bounds: Vec::new(),
                         body: Vec::new(),
                         method_id: None,
                         location: cols(11, 22)
@@ -4729,6 +5283,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4738,6 +5293,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4782,6 +5338,7 @@ mod tests {
                             arguments: Vec::new(),
                             location: cols(28, 28)
                         }))),
+                        bounds: Vec::new(),
                         body: vec![Expression::Int(Box::new(IntLiteral {
                             value: 10,
                             resolved_type: types::TypeRef::Unknown,
@@ -4804,6 +5361,7 @@ mod tests {
             hir,
             TopLevelExpression::Trait(Box::new(DefineTrait {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 trait_id: None,
                 name: Constant { name: "A".to_string(), location: cols(7, 7) },
@@ -4813,6 +5371,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: true,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4822,6 +5381,7 @@ mod tests {
                         type_parameters: Vec::new(),
                         arguments: Vec::new(),
                         return_type: None,
+                        bounds: Vec::new(),
                         body: Vec::new(),
                         method_id: None,
                         location: cols(11, 26)
@@ -4888,6 +5448,7 @@ mod tests {
                     DefineInstanceMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         kind: MethodKind::Regular,
                         name: Identifier {
@@ -4897,6 +5458,7 @@ mod tests {
                         type_parameters: Vec::new(),
                         arguments: Vec::new(),
                         return_type: None,
+                        bounds: Vec::new(),
                         body: Vec::new(),
                         method_id: None,
                         location: cols(10, 18)
@@ -4924,6 +5486,7 @@ mod tests {
                     DefineStaticMethod {
                         inline: false,
                         documentation: String::new(),
+                        deprecated: None,
                         public: false,
                         name: Identifier {
                             name: "foo".to_string(),
@@ -4958,6 +5521,7 @@ mod tests {
                 body: vec![ReopenClassExpression::AsyncMethod(Box::new(
                     DefineAsyncMethod {
                         documentation: String::new(),
+                        deprecated: None,
                         mutable: false,
                         public: false,
                         name: Identifier {
@@ -4993,6 +5557,7 @@ mod tests {
                 body: vec![ReopenClassExpression::AsyncMethod(Box::new(
                     DefineAsyncMethod {
                         documentation: String::new(),
+                        deprecated: None,
                         mutable: true,
                         public: false,
                         name: Identifier {
@@ -5155,6 +5720,7 @@ mod tests {
                 body: vec![DefineInstanceMethod {
                     inline: false,
                     documentation: String::new(),
+                    deprecated: None,
                     public: false,
                     kind: MethodKind::Regular,
                     name: Identifier {
@@ -5164,6 +5730,7 @@ mod tests {
                     type_parameters: Vec::new(),
                     arguments: Vec::new(),
                     return_type: None,
+                    bounds: Vec::new(),
                     body: Vec::new(),
                     method_id: None,
                     location: cols(16, 24)
@@ -5200,6 +5767,7 @@ mod tests {
                 body: vec![DefineInstanceMethod {
                     inline: false,
                     documentation: String::new(),
+                    deprecated: None,
                     public: false,
                     kind: MethodKind::Moving,
                     name: Identifier {
@@ -5209,6 +5777,7 @@ mod tests {
                     type_parameters: Vec::new(),
                     arguments: Vec::new(),
                     return_type: None,
+                    bounds: Vec::new(),
                     body: Vec::new(),
                     method_id: None,
                     location: cols(16, 29)
@@ -5904,6 +6473,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_lower_align_of_type() {
+        let hir = lower_expr("fn a { _INKO.align_of_type(Foo) }").0;
+
+        assert_eq!(
+            hir,
+            Expression::SizeOf(Box::new(SizeOf {
+                kind: SizeOfKind::Alignment,
+                argument: Type::Named(Box::new(TypeName {
+                    source: None,
+                    resolved_type: types::TypeRef::Unknown,
+                    name: Constant {
+                        name: "Foo".to_string(),
+                        location: cols(28, 30)
+                    },
+                    arguments: Vec::new(),
+                    location: cols(28, 30)
+                })),
+                resolved_type: types::TypeRef::Unknown,
+                location: cols(8, 31)
+            }))
+        );
+    }
+
     #[test]
     fn test_lower_builtin_call_outside_stdlib() {
         let name = ModuleName::new("foo");
@@ -6775,6 +7368,7 @@ mod tests {
             hir,
             TopLevelExpression::Class(Box::new(DefineClass {
                 documentation: String::new(),
+                deprecated: None,
                 public: false,
                 inline: false,
                 kind: ClassKind::Enum,
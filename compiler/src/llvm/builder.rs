@@ -11,7 +11,7 @@ use inkwell::debug_info::{
     DWARFSourceLanguage, DebugInfoBuilder,
 };
 use inkwell::module::{FlagBehavior, Module as InkwellModule};
-use inkwell::types::{ArrayType, BasicType, FunctionType, StructType};
+use inkwell::types::{ArrayType, BasicType, FunctionType, IntType, StructType};
 use inkwell::values::{
     AggregateValue, ArrayValue, BasicMetadataValueEnum, BasicValue,
     BasicValueEnum, CallSiteValue, FloatValue, FunctionValue,
@@ -322,6 +322,61 @@ impl<'ctx> Builder<'ctx> {
         self.extract_field(res, 1).into_int_value()
     }
 
+    pub(crate) fn atomic_load(
+        &self,
+        typ: IntType<'ctx>,
+        pointer: PointerValue<'ctx>,
+        ordering: AtomicOrdering,
+    ) -> IntValue<'ctx> {
+        let res = self.load(typ, pointer);
+        let ins = res.as_instruction_value().unwrap();
+
+        ins.set_alignment(typ.get_bit_width().div_ceil(8)).unwrap();
+        ins.set_atomic_ordering(ordering).unwrap();
+        res.into_int_value()
+    }
+
+    pub(crate) fn atomic_store(
+        &self,
+        pointer: PointerValue<'ctx>,
+        value: IntValue<'ctx>,
+        ordering: AtomicOrdering,
+    ) {
+        let ins = self.inner.build_store(pointer, value).unwrap();
+        let size = value.get_type().get_bit_width().div_ceil(8);
+
+        ins.set_alignment(size).unwrap();
+        ins.set_atomic_ordering(ordering).unwrap();
+    }
+
+    pub(crate) fn atomic_rmw(
+        &self,
+        operation: AtomicRMWBinOp,
+        pointer: PointerValue<'ctx>,
+        value: IntValue<'ctx>,
+        ordering: AtomicOrdering,
+    ) -> IntValue<'ctx> {
+        self.inner.build_atomicrmw(operation, pointer, value, ordering).unwrap()
+    }
+
+    pub(crate) fn memory_copy(
+        &self,
+        to: PointerValue<'ctx>,
+        from: PointerValue<'ctx>,
+        size: IntValue<'ctx>,
+    ) {
+        self.inner.build_memcpy(to, 1, from, 1, size).unwrap();
+    }
+
+    pub(crate) fn memory_set(
+        &self,
+        pointer: PointerValue<'ctx>,
+        byte: IntValue<'ctx>,
+        size: IntValue<'ctx>,
+    ) {
+        self.inner.build_memset(pointer, 1, byte, size).unwrap();
+    }
+
     pub(crate) fn load_atomic_counter(
         &self,
         variable: PointerValue<'ctx>,
@@ -442,6 +497,18 @@ impl<'ctx> Builder<'ctx> {
         self.inner.build_xor(lhs, rhs, "").unwrap()
     }
 
+    pub(crate) fn select_int(
+        &self,
+        condition: IntValue<'ctx>,
+        then: IntValue<'ctx>,
+        otherwise: IntValue<'ctx>,
+    ) -> IntValue<'ctx> {
+        self.inner
+            .build_select(condition, then, otherwise, "")
+            .unwrap()
+            .into_int_value()
+    }
+
     pub(crate) fn bit_not(&self, value: IntValue<'ctx>) -> IntValue<'ctx> {
         self.inner.build_not(value, "").unwrap()
     }
@@ -475,11 +542,7 @@ impl<'ctx> Builder<'ctx> {
         value: IntValue<'ctx>,
         size: u32,
     ) -> FloatValue<'ctx> {
-        let typ = if size == 32 {
-            self.context.f32_type()
-        } else {
-            self.context.f64_type()
-        };
+        let typ = self.context.float_type(size);
 
         self.inner
             .build_cast(InstructionOpcode::SIToFP, value, typ, "")
@@ -493,13 +556,7 @@ impl<'ctx> Builder<'ctx> {
         size: u32,
         signed: bool,
     ) -> IntValue<'ctx> {
-        let target = match size {
-            1 => self.context.bool_type(),
-            8 => self.context.i8_type(),
-            16 => self.context.i16_type(),
-            32 => self.context.i32_type(),
-            _ => self.context.i64_type(),
-        };
+        let target = self.context.int_type(size);
 
         self.inner.build_int_cast_sign_flag(value, target, signed, "").unwrap()
     }
@@ -509,10 +566,7 @@ impl<'ctx> Builder<'ctx> {
         value: FloatValue<'ctx>,
         size: u32,
     ) -> FloatValue<'ctx> {
-        let target = match size {
-            32 => self.context.f32_type(),
-            _ => self.context.f64_type(),
-        };
+        let target = self.context.float_type(size);
 
         self.inner.build_float_cast(value, target, "").unwrap()
     }
@@ -659,6 +713,14 @@ impl<'ctx> Builder<'ctx> {
         self.inner.build_alloca(typ, "").unwrap()
     }
 
+    pub(crate) fn set_alignment(
+        &self,
+        variable: PointerValue<'ctx>,
+        alignment: u32,
+    ) {
+        variable.as_instruction().unwrap().set_alignment(alignment).unwrap();
+    }
+
     pub(crate) fn jump(&self, block: BasicBlock<'ctx>) {
         self.inner.build_unconditional_branch(block).unwrap();
     }
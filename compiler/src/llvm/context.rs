@@ -13,8 +13,8 @@ use inkwell::values::FunctionValue;
 use inkwell::{context, AddressSpace};
 use std::mem::size_of;
 use types::{
-    Block, Database, ForeignType, MethodId, TypeId, TypeRef, BOOL_ID, FLOAT_ID,
-    INT_ID, NIL_ID,
+    Block, Database, ForeignType, MethodId, TypeId, TypeRef, BOOL_ID, CHAR_ID,
+    FLOAT_ID, INT_ID, NIL_ID,
 };
 
 /// A wrapper around an LLVM Context that provides some additional methods.
@@ -79,6 +79,33 @@ impl Context {
         self.inner.f64_type()
     }
 
+    /// Returns the integer type for an integer of the given number of bits.
+    ///
+    /// Sizes not supported by foreign types default to 64 bits.
+    pub(crate) fn int_type(&self, bits: u32) -> IntType {
+        match bits {
+            1 => self.bool_type(),
+            8 => self.i8_type(),
+            16 => self.i16_type(),
+            32 => self.i32_type(),
+            128 => self.inner.i128_type(),
+            _ => self.i64_type(),
+        }
+    }
+
+    /// Returns the float type for a float of the given number of bits.
+    ///
+    /// 80 bits floats use the x87 extended precision format, used for
+    /// `long double` on x86-64.
+    pub(crate) fn float_type(&self, bits: u32) -> FloatType {
+        match bits {
+            32 => self.f32_type(),
+            80 => self.inner.x86_f80_type(),
+            128 => self.inner.f128_type(),
+            _ => self.f64_type(),
+        }
+    }
+
     pub(crate) fn void_type(&self) -> VoidType {
         self.inner.void_type()
     }
@@ -161,7 +188,7 @@ impl Context {
         layouts: &Layouts<'a>,
         type_ref: TypeRef,
     ) -> BasicTypeEnum<'a> {
-        if let TypeRef::Pointer(_) = type_ref {
+        if let TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) = type_ref {
             return self.pointer_type().as_basic_type_enum();
         }
 
@@ -170,29 +197,21 @@ impl Context {
         };
 
         match id {
-            TypeId::Foreign(ForeignType::Int(8, _)) => {
-                self.i8_type().as_basic_type_enum()
-            }
-            TypeId::Foreign(ForeignType::Int(16, _)) => {
-                self.i16_type().as_basic_type_enum()
-            }
-            TypeId::Foreign(ForeignType::Int(32, _)) => {
-                self.i32_type().as_basic_type_enum()
-            }
-            TypeId::Foreign(ForeignType::Int(_, _)) => {
-                self.i64_type().as_basic_type_enum()
+            TypeId::Foreign(ForeignType::Int(bits, _)) => {
+                self.int_type(bits).as_basic_type_enum()
             }
-            TypeId::Foreign(ForeignType::Float(32)) => {
-                self.f32_type().as_basic_type_enum()
+            TypeId::Foreign(ForeignType::Float(bits)) => {
+                self.float_type(bits).as_basic_type_enum()
             }
-            TypeId::Foreign(ForeignType::Float(_)) => {
-                self.f64_type().as_basic_type_enum()
+            TypeId::Foreign(ForeignType::Function(_)) => {
+                self.pointer_type().as_basic_type_enum()
             }
             TypeId::ClassInstance(ins) => {
                 let cls = ins.instance_of();
 
                 match cls.0 {
                     BOOL_ID | NIL_ID => self.bool_type().as_basic_type_enum(),
+                    CHAR_ID => self.i32_type().as_basic_type_enum(),
                     INT_ID => self.i64_type().as_basic_type_enum(),
                     FLOAT_ID => self.f64_type().as_basic_type_enum(),
                     _ if cls.is_stack_allocated(db) => {
@@ -326,7 +345,7 @@ impl Context {
             })
             .collect();
 
-        self.struct_type(&fields)
+        self.inner.struct_type(&fields, typ.is_packed())
     }
 }
 
@@ -9,7 +9,7 @@ use inkwell::types::{
 use std::collections::VecDeque;
 use types::{
     CallConvention, ClassId, Database, MethodId, TypeId, TypeRef, BOOL_ID,
-    BYTE_ARRAY_ID, FLOAT_ID, INT_ID, NIL_ID, STRING_ID,
+    BYTE_ARRAY_ID, CHAR_ID, FLOAT_ID, INT_ID, NIL_ID,
 };
 
 /// The size of an object header.
@@ -260,7 +260,7 @@ impl<'ctx> Layouts<'ctx> {
         // defined.
         for &id in mir.classes.keys() {
             let instance = match id.0 {
-                INT_ID | FLOAT_ID | BOOL_ID | NIL_ID => {
+                INT_ID | FLOAT_ID | BOOL_ID | NIL_ID | CHAR_ID => {
                     let typ = context.opaque_struct("");
 
                     typ.set_body(&[header.into()], false);
@@ -336,16 +336,19 @@ impl<'ctx> Layouts<'ctx> {
 
         // These types have a fixed size and don't define any fields. To ensure
         // the work loop terminates, we manually flag them as known.
-        for id in [BYTE_ARRAY_ID, INT_ID, FLOAT_ID, BOOL_ID, NIL_ID] {
+        for id in [BYTE_ARRAY_ID, INT_ID, FLOAT_ID, BOOL_ID, NIL_ID, CHAR_ID] {
             sized.set_has_size(ClassId(id as _));
         }
 
         while let Some(id) = queue.pop_front() {
             let kind = id.kind(db);
 
-            // String is a built-in class, but it's defined like a regular one,
-            // so we _don't_ want to skip it here.
-            if id.is_builtin() && id.0 != STRING_ID {
+            // Most builtin classes (e.g. String and the tuples) are defined
+            // like regular classes, so we only skip those with a fixed size.
+            if matches!(
+                id.0,
+                BYTE_ARRAY_ID | INT_ID | FLOAT_ID | BOOL_ID | NIL_ID | CHAR_ID
+            ) {
                 continue;
             }
 
@@ -441,7 +444,23 @@ impl<'ctx> Layouts<'ctx> {
                 types.push(context.llvm_type(db, self, field.value_type(db)));
             }
 
-            layout.set_body(&types, false);
+            let opts = id.layout_options(db);
+
+            // LLVM doesn't allow specifying the alignment of a structure type,
+            // so we pad the structure such that its size is a multiple of the
+            // alignment. The alignment itself is applied to the stack slots
+            // that store values of the type.
+            if let Some(align) = opts.align {
+                let size =
+                    self.target_data.get_abi_size(&context.struct_type(&types));
+                let pad = size.next_multiple_of(align as u64) - size;
+
+                if pad > 0 {
+                    types.push(context.i8_type().array_type(pad as _).into());
+                }
+            }
+
+            layout.set_body(&types, opts.packed);
             sized.set_has_size(id);
         }
     }
@@ -1,4 +1,5 @@
 use crate::config::{BuildDirectories, Opt};
+use crate::hir::SizeOfKind;
 use crate::llvm::builder::Builder;
 use crate::llvm::constants::{
     ARRAY_BUF_INDEX, ARRAY_CAPA_INDEX, ARRAY_LENGTH_INDEX,
@@ -15,8 +16,8 @@ use crate::llvm::methods::Methods;
 use crate::llvm::module::Module;
 use crate::llvm::runtime_function::RuntimeFunction;
 use crate::mir::{
-    CastType, Constant, Instruction, InstructionLocation, Method, Mir,
-    RegisterId,
+    CallBuiltin, CastType, Constant, Instruction, InstructionLocation, Method,
+    Mir, RegisterId,
 };
 use crate::state::State;
 use crate::symbol_names::{SymbolNames, STACK_MASK_GLOBAL, STATE_GLOBAL};
@@ -36,7 +37,7 @@ use inkwell::values::{
     BasicMetadataValueEnum, BasicValue, BasicValueEnum, FloatValue,
     FunctionValue, GlobalValue, IntValue, PointerValue,
 };
-use inkwell::OptimizationLevel;
+use inkwell::{AtomicOrdering, AtomicRMWBinOp, OptimizationLevel};
 use std::collections::HashMap;
 use std::fs::{read, write};
 use std::path::Path;
@@ -46,7 +47,8 @@ use std::thread::scope;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use types::module_name::ModuleName;
 use types::{
-    ClassId, Database, Intrinsic, Shape, TypeRef, BYTE_ARRAY_ID, STRING_ID,
+    ClassId, Database, ForeignType, Intrinsic, MemoryOrdering, Shape, TypeId,
+    TypeRef, BYTE_ARRAY_ID, STRING_ID,
 };
 
 const NIL_VALUE: bool = false;
@@ -73,6 +75,18 @@ fn hash_compile_time_variables(state: &State) -> String {
     hasher.finalize().to_string()
 }
 
+fn llvm_ordering(ordering: MemoryOrdering) -> AtomicOrdering {
+    match ordering {
+        MemoryOrdering::Relaxed => AtomicOrdering::Monotonic,
+        MemoryOrdering::Acquire => AtomicOrdering::Acquire,
+        MemoryOrdering::Release => AtomicOrdering::Release,
+        MemoryOrdering::AcquireRelease => AtomicOrdering::AcquireRelease,
+        MemoryOrdering::SequentiallyConsistent => {
+            AtomicOrdering::SequentiallyConsistent
+        }
+    }
+}
+
 fn check_object_cache(
     state: &mut State,
     symbol_names: &SymbolNames,
@@ -204,15 +218,30 @@ fn check_object_cache(
         // probing changes, we need to update the module's code accordingly. We
         // do this by hashing the collision states of all dynamic calls in the
         // current module, such that if any of them change, so does the hash.
+        //
+        // Devirtualized calls depend on the number of classes implementing a
+        // trait, which may change without the module itself changing, so we
+        // also include these calls in the hash.
         for &mid in &module.methods {
             hasher.update(&methods.info[mid.0 as usize].hash.to_le_bytes());
 
             for block in &mir.methods.get(&mid).unwrap().body.blocks {
                 for ins in &block.instructions {
-                    if let Instruction::CallDynamic(op) = ins {
-                        let val = methods.info[op.method.0 as usize].collision;
+                    match ins {
+                        Instruction::CallDynamic(op) => {
+                            let val =
+                                methods.info[op.method.0 as usize].collision;
 
-                        hasher.update(&[val as u8]);
+                            hasher.update(&[val as u8]);
+                        }
+                        Instruction::CallInstance(op)
+                            if mir.devirtualized.contains(&op.method) =>
+                        {
+                            hasher.update(
+                                symbol_names.methods[&op.method].as_bytes(),
+                            );
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -1402,6 +1431,120 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
 
                         self.builder.store(reg_var, res);
                     }
+                    Intrinsic::FloatSqrt => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.sqrt",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatSin => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.sin",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatCos => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.cos",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatLn => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.log",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatLog10 => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.log10",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatExp => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_float(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.exp",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[val.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::FloatPow => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let lhs = self.builder.load_float(lhs_var);
+                        let rhs = self.builder.load_float(rhs_var);
+                        let func = self.module.intrinsic(
+                            "llvm.pow",
+                            &[self.builder.context.f64_type().into()],
+                        );
+
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[lhs.into(), rhs.into()])
+                            .into_float_value();
+
+                        self.builder.store(reg_var, res);
+                    }
                     Intrinsic::FloatPowi => {
                         let reg_var = self.variables[&ins.register];
                         let lhs_var = self.variables[&ins.arguments[0]];
@@ -1572,6 +1715,161 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
 
                         self.builder.store(reg_var, res);
                     }
+                    Intrinsic::Int128CheckedAdd => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs = self.builder.load(typ, lhs_var);
+                        let rhs = self.builder.load(typ, rhs_var);
+                        let func = self.module.intrinsic(
+                            "llvm.sadd.with.overflow",
+                            &[typ.into()],
+                        );
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[lhs.into(), rhs.into()])
+                            .into_struct_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Int128CheckedMul => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs = self.builder.load(typ, lhs_var);
+                        let rhs = self.builder.load(typ, rhs_var);
+                        let func = self.module.intrinsic(
+                            "llvm.smul.with.overflow",
+                            &[typ.into()],
+                        );
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[lhs.into(), rhs.into()])
+                            .into_struct_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Int128CheckedSub => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs = self.builder.load(typ, lhs_var);
+                        let rhs = self.builder.load(typ, rhs_var);
+                        let func = self.module.intrinsic(
+                            "llvm.ssub.with.overflow",
+                            &[typ.into()],
+                        );
+                        let res = self
+                            .builder
+                            .call_with_return(func, &[lhs.into(), rhs.into()])
+                            .into_struct_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Int128WrappingAdd => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs =
+                            self.builder.load(typ, lhs_var).into_int_value();
+                        let rhs =
+                            self.builder.load(typ, rhs_var).into_int_value();
+                        let res = self.builder.int_add(lhs, rhs);
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Int128WrappingMul => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs =
+                            self.builder.load(typ, lhs_var).into_int_value();
+                        let rhs =
+                            self.builder.load(typ, rhs_var).into_int_value();
+                        let res = self.builder.int_mul(lhs, rhs);
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Int128WrappingSub => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let typ = self.builder.context.int_type(128);
+                        let lhs =
+                            self.builder.load(typ, lhs_var).into_int_value();
+                        let rhs =
+                            self.builder.load(typ, rhs_var).into_int_value();
+                        let res = self.builder.int_sub(lhs, rhs);
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::IntCheckedDiv => {
+                        let reg_var = self.variables[&ins.register];
+                        let reg_typ = self.variable_types[&ins.register]
+                            .into_struct_type();
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let lhs = self.builder.load_int(lhs_var);
+                        let rhs = self.builder.load_int(rhs_var);
+                        let zero = self.builder.i64_literal(0);
+                        let one = self.builder.i64_literal(1);
+                        let min = self.builder.i64_literal(i64::MIN);
+                        let minus_one = self.builder.i64_literal(-1);
+
+                        // LLVM doesn't provide overflow intrinsics for
+                        // division, so we check for a zero divisor and
+                        // MIN / -1 ourselves. The divisor is replaced with 1
+                        // when overflowing, such that the division itself is
+                        // always valid and no branching is necessary.
+                        let is_zero = self.builder.int_eq(rhs, zero);
+                        let is_min = self.builder.int_eq(lhs, min);
+                        let is_minus_one = self.builder.int_eq(rhs, minus_one);
+                        let overflows = self.builder.bit_or(
+                            is_zero,
+                            self.builder.bit_and(is_min, is_minus_one),
+                        );
+                        let safe_rhs =
+                            self.builder.select_int(overflows, one, rhs);
+                        let res = self.builder.int_div(lhs, safe_rhs);
+                        let tag = self.builder.int_to_int(overflows, 8, false);
+
+                        self.builder.store_field(reg_typ, reg_var, 0, res);
+                        self.builder.store_field(reg_typ, reg_var, 1, tag);
+                    }
+                    Intrinsic::IntCheckedRem => {
+                        let reg_var = self.variables[&ins.register];
+                        let reg_typ = self.variable_types[&ins.register]
+                            .into_struct_type();
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let lhs = self.builder.load_int(lhs_var);
+                        let rhs = self.builder.load_int(rhs_var);
+                        let zero = self.builder.i64_literal(0);
+                        let one = self.builder.i64_literal(1);
+                        let min = self.builder.i64_literal(i64::MIN);
+                        let minus_one = self.builder.i64_literal(-1);
+
+                        // See IntCheckedDiv for more details.
+                        let is_zero = self.builder.int_eq(rhs, zero);
+                        let is_min = self.builder.int_eq(lhs, min);
+                        let is_minus_one = self.builder.int_eq(rhs, minus_one);
+                        let overflows = self.builder.bit_or(
+                            is_zero,
+                            self.builder.bit_and(is_min, is_minus_one),
+                        );
+                        let safe_rhs =
+                            self.builder.select_int(overflows, one, rhs);
+                        let res = self.builder.int_rem(lhs, safe_rhs);
+                        let tag = self.builder.int_to_int(overflows, 8, false);
+
+                        self.builder.store_field(reg_typ, reg_var, 0, res);
+                        self.builder.store_field(reg_typ, reg_var, 1, tag);
+                    }
                     Intrinsic::IntSwapBytes => {
                         let reg_var = self.variables[&ins.register];
                         let val_reg = ins.arguments[0];
@@ -1633,6 +1931,218 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
 
                         self.builder.store(reg_var, res);
                     }
+                    Intrinsic::AtomicLoad => {
+                        let reg_var = self.variables[&ins.register];
+                        let ptr_var = self.variables[&ins.arguments[0]];
+                        let (bits, signed) =
+                            self.atomic_int_type(ins.arguments[0]);
+                        let typ = self.builder.context.int_type(bits);
+                        let ptr = self.builder.load_pointer(ptr_var);
+
+                        self.atomic_operation(
+                            ins.arguments[1],
+                            |builder, ord| {
+                                let ord = llvm_ordering(ord.for_load());
+                                let val = builder.atomic_load(typ, ptr, ord);
+                                let res = builder.int_to_int(val, 64, signed);
+
+                                builder.store(reg_var, res);
+                            },
+                        );
+                    }
+                    Intrinsic::AtomicStore => {
+                        let reg_var = self.variables[&ins.register];
+                        let ptr_var = self.variables[&ins.arguments[0]];
+                        let val = self
+                            .atomic_value(ins.arguments[0], ins.arguments[1]);
+                        let ptr = self.builder.load_pointer(ptr_var);
+                        let nil = self.builder.bool_literal(NIL_VALUE);
+
+                        self.atomic_operation(
+                            ins.arguments[2],
+                            |builder, ord| {
+                                let ord = llvm_ordering(ord.for_store());
+
+                                builder.atomic_store(ptr, val, ord);
+                            },
+                        );
+                        self.builder.store(reg_var, nil);
+                    }
+                    Intrinsic::AtomicAdd => {
+                        self.atomic_read_modify_write(ins, AtomicRMWBinOp::Add);
+                    }
+                    Intrinsic::AtomicSub => {
+                        self.atomic_read_modify_write(ins, AtomicRMWBinOp::Sub);
+                    }
+                    Intrinsic::AtomicAnd => {
+                        self.atomic_read_modify_write(ins, AtomicRMWBinOp::And);
+                    }
+                    Intrinsic::AtomicOr => {
+                        self.atomic_read_modify_write(ins, AtomicRMWBinOp::Or);
+                    }
+                    Intrinsic::AtomicXor => {
+                        self.atomic_read_modify_write(ins, AtomicRMWBinOp::Xor);
+                    }
+                    Intrinsic::AtomicSwap => {
+                        self.atomic_read_modify_write(
+                            ins,
+                            AtomicRMWBinOp::Xchg,
+                        );
+                    }
+                    Intrinsic::MemoryCopy => {
+                        let reg_var = self.variables[&ins.register];
+                        let to_var = self.variables[&ins.arguments[0]];
+                        let from_var = self.variables[&ins.arguments[1]];
+                        let size_var = self.variables[&ins.arguments[2]];
+                        let to = self.builder.load_pointer(to_var);
+                        let from = self.builder.load_pointer(from_var);
+                        let size = self.builder.load_int(size_var);
+                        let nil = self.builder.bool_literal(NIL_VALUE);
+
+                        self.builder.memory_copy(to, from, size);
+                        self.builder.store(reg_var, nil);
+                    }
+                    Intrinsic::MemorySet => {
+                        let reg_var = self.variables[&ins.register];
+                        let ptr_var = self.variables[&ins.arguments[0]];
+                        let byte_var = self.variables[&ins.arguments[1]];
+                        let size_var = self.variables[&ins.arguments[2]];
+                        let ptr = self.builder.load_pointer(ptr_var);
+                        let raw_byte = self.builder.load_int(byte_var);
+                        let byte = self.builder.int_to_int(raw_byte, 8, false);
+                        let size = self.builder.load_int(size_var);
+                        let nil = self.builder.bool_literal(NIL_VALUE);
+
+                        self.builder.memory_set(ptr, byte, size);
+                        self.builder.store(reg_var, nil);
+                    }
+                    Intrinsic::MemoryCompare => {
+                        let reg_var = self.variables[&ins.register];
+                        let lhs_var = self.variables[&ins.arguments[0]];
+                        let rhs_var = self.variables[&ins.arguments[1]];
+                        let size_var = self.variables[&ins.arguments[2]];
+                        let lhs = self.builder.load_pointer(lhs_var);
+                        let rhs = self.builder.load_pointer(rhs_var);
+                        let size = self.builder.load_int(size_var);
+                        let func = self
+                            .module
+                            .runtime_function(RuntimeFunction::MemoryCompare);
+                        let raw = self
+                            .builder
+                            .call_with_return(
+                                func,
+                                &[lhs.into(), rhs.into(), size.into()],
+                            )
+                            .into_int_value();
+                        let res = self.builder.int_to_int(raw, 64, true);
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::PrefetchRead => {
+                        let reg_var = self.variables[&ins.register];
+                        let ptr_var = self.variables[&ins.arguments[0]];
+                        let ptr = self.builder.load_pointer(ptr_var);
+                        let func = self.module.intrinsic(
+                            "llvm.prefetch",
+                            &[self.builder.context.pointer_type().into()],
+                        );
+                        let nil = self.builder.bool_literal(NIL_VALUE);
+
+                        // The arguments are the pointer, read (0) or write
+                        // (1), the locality (3 being the highest), and the
+                        // cache type (1 being the data cache).
+                        self.builder.direct_call(
+                            func,
+                            &[
+                                ptr.into(),
+                                self.builder.u32_literal(0).into(),
+                                self.builder.u32_literal(3).into(),
+                                self.builder.u32_literal(1).into(),
+                            ],
+                        );
+                        self.builder.store(reg_var, nil);
+                    }
+                    Intrinsic::PrefetchWrite => {
+                        let reg_var = self.variables[&ins.register];
+                        let ptr_var = self.variables[&ins.arguments[0]];
+                        let ptr = self.builder.load_pointer(ptr_var);
+                        let func = self.module.intrinsic(
+                            "llvm.prefetch",
+                            &[self.builder.context.pointer_type().into()],
+                        );
+                        let nil = self.builder.bool_literal(NIL_VALUE);
+
+                        // See PrefetchRead for more details.
+                        self.builder.direct_call(
+                            func,
+                            &[
+                                ptr.into(),
+                                self.builder.u32_literal(1).into(),
+                                self.builder.u32_literal(3).into(),
+                                self.builder.u32_literal(1).into(),
+                            ],
+                        );
+                        self.builder.store(reg_var, nil);
+                    }
+                    Intrinsic::Likely => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_bool(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.expect",
+                            &[self.builder.context.bool_type().into()],
+                        );
+                        let res = self
+                            .builder
+                            .call_with_return(
+                                func,
+                                &[
+                                    val.into(),
+                                    self.builder.bool_literal(true).into(),
+                                ],
+                            )
+                            .into_int_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::Unlikely => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_bool(val_var);
+                        let func = self.module.intrinsic(
+                            "llvm.expect",
+                            &[self.builder.context.bool_type().into()],
+                        );
+                        let res = self
+                            .builder
+                            .call_with_return(
+                                func,
+                                &[
+                                    val.into(),
+                                    self.builder.bool_literal(false).into(),
+                                ],
+                            )
+                            .into_int_value();
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::StackAlloc => {
+                        // The type checker ensures the size is an Int literal,
+                        // allowing us to allocate the memory in the entry
+                        // block. This way the stack doesn't grow when the
+                        // intrinsic is used in a loop, or when the surrounding
+                        // method is inlined into a loop.
+                        let reg_var = self.variables[&ins.register];
+                        let size = self.int_literal(ins.arguments[0]);
+                        let typ = self
+                            .builder
+                            .context
+                            .i8_type()
+                            .array_type(size as _);
+                        let ptr = self.builder.new_stack_slot(typ);
+
+                        self.builder.store(reg_var, ptr);
+                    }
                     Intrinsic::Panic => {
                         let val_var = self.variables[&ins.arguments[0]];
                         let val = self.builder.load_pointer(val_var);
@@ -1721,6 +2231,25 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
 
                         self.builder.store(reg_var, res);
                     }
+                    Intrinsic::CharToInt => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self
+                            .builder
+                            .load(self.builder.context.i32_type(), val_var)
+                            .into_int_value();
+                        let res = self.builder.int_to_int(val, 64, false);
+
+                        self.builder.store(reg_var, res);
+                    }
+                    Intrinsic::IntToChar => {
+                        let reg_var = self.variables[&ins.register];
+                        let val_var = self.variables[&ins.arguments[0]];
+                        let val = self.builder.load_int(val_var);
+                        let res = self.builder.int_to_int(val, 32, false);
+
+                        self.builder.store(reg_var, res);
+                    }
                     Intrinsic::Moved => unreachable!(),
                 }
             }
@@ -2593,7 +3122,23 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
                     ins.argument,
                 );
 
-                self.builder.store(reg_var, typ.size_of().unwrap());
+                // Sizes and alignments derived from a type's layout are
+                // normally replaced with constants during specialization, so
+                // we only get here if the layout couldn't be determined.
+                match ins.kind {
+                    SizeOfKind::TypeName | SizeOfKind::Size => {
+                        self.builder.store(reg_var, typ.size_of().unwrap());
+                    }
+                    SizeOfKind::Alignment => {
+                        let align =
+                            self.layouts.target_data.get_abi_alignment(&typ);
+
+                        self.builder.store(
+                            reg_var,
+                            self.builder.u64_literal(align as u64),
+                        );
+                    }
+                }
             }
             Instruction::Borrow(_) => unreachable!(),
             Instruction::Drop(_) => unreachable!(),
@@ -2610,7 +3155,22 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
                 raw,
             );
 
-            self.variables.insert(id, self.builder.new_temporary(typ));
+            let var = self.builder.new_temporary(typ);
+
+            // Extern classes may specify an alignment greater than the one
+            // LLVM derives from their fields.
+            if let Some(align) = raw
+                .class_id(&self.shared.state.db)
+                .and_then(|id| id.layout_options(&self.shared.state.db).align)
+            {
+                let abi = self.layouts.target_data.get_abi_alignment(&typ);
+
+                if align > abi {
+                    self.builder.set_alignment(var, align);
+                }
+            }
+
+            self.variables.insert(id, var);
             self.variable_types.insert(id, typ);
         }
     }
@@ -2709,6 +3269,91 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
         }
     }
 
+    fn atomic_read_modify_write(
+        &mut self,
+        ins: &CallBuiltin,
+        operation: AtomicRMWBinOp,
+    ) {
+        let reg_var = self.variables[&ins.register];
+        let ptr_var = self.variables[&ins.arguments[0]];
+        let (_, signed) = self.atomic_int_type(ins.arguments[0]);
+        let val = self.atomic_value(ins.arguments[0], ins.arguments[1]);
+        let ptr = self.builder.load_pointer(ptr_var);
+
+        self.atomic_operation(ins.arguments[2], |builder, ord| {
+            let ord = llvm_ordering(ord);
+            let old = builder.atomic_rmw(operation, ptr, val, ord);
+            let res = builder.int_to_int(old, 64, signed);
+
+            builder.store(reg_var, res);
+        });
+    }
+
+    /// Returns the size and sign of the integer that the pointer passed to an
+    /// atomic intrinsic points to.
+    fn atomic_int_type(&self, pointer: RegisterId) -> (u32, bool) {
+        let db = &self.shared.state.db;
+
+        match self.register_type(pointer).type_id(db) {
+            Ok(TypeId::Foreign(ForeignType::Int(bits, sign))) => {
+                (bits, sign.is_signed())
+            }
+            _ => (64, true),
+        }
+    }
+
+    /// Loads the value to write using an atomic intrinsic, converting it to
+    /// the integer type that the pointer points to.
+    fn atomic_value(
+        &mut self,
+        pointer: RegisterId,
+        value: RegisterId,
+    ) -> IntValue<'ctx> {
+        let (bits, _) = self.atomic_int_type(pointer);
+        let signed =
+            self.register_type(value).is_signed_int(&self.shared.state.db);
+        let var = self.variables[&value];
+        let typ = self.variable_types[&value];
+        let val = self.builder.load(typ, var).into_int_value();
+
+        self.builder.int_to_int(val, bits, signed)
+    }
+
+    /// Generates an atomic operation for a memory ordering that's only known
+    /// at runtime.
+    ///
+    /// LLVM requires memory orderings to be known at compile-time, so we
+    /// generate a switch with a case for every ordering. The ordering is
+    /// almost always a constant, in which case LLVM removes the switch and the
+    /// unused cases.
+    fn atomic_operation<F: FnMut(&Builder<'ctx>, MemoryOrdering)>(
+        &mut self,
+        ordering: RegisterId,
+        mut generate: F,
+    ) {
+        let orderings = MemoryOrdering::all();
+        let ord = self.builder.load_int(self.variables[&ordering]);
+        let after = self.builder.add_block();
+        let blocks: Vec<_> =
+            orderings.iter().map(|_| self.builder.add_block()).collect();
+        let cases: Vec<_> = orderings
+            .iter()
+            .zip(&blocks)
+            .map(|(&ord, &block)| (self.builder.i64_literal(ord as i64), block))
+            .collect();
+
+        // Unknown values use the last and strongest ordering.
+        self.builder.switch(ord, &cases, blocks[blocks.len() - 1]);
+
+        for (&ord, &block) in orderings.iter().zip(&blocks) {
+            self.builder.switch_to_block(block);
+            generate(&self.builder, ord);
+            self.builder.jump(after);
+        }
+
+        self.builder.switch_to_block(after);
+    }
+
     fn set_debug_location(&mut self, location: InstructionLocation) {
         let line = location.line;
         let col = location.column;
@@ -2773,13 +3418,7 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
         source: FloatValue<'ctx>,
         size: u32,
     ) -> IntValue<'ctx> {
-        let target = match size {
-            8 => self.builder.context.i8_type(),
-            16 => self.builder.context.i16_type(),
-            32 => self.builder.context.i32_type(),
-            _ => self.builder.context.i64_type(),
-        };
-
+        let target = self.builder.context.int_type(size);
         let func = self.module.intrinsic(
             "llvm.fptosi.sat",
             &[target.into(), source.get_type().into()],
@@ -2788,6 +3427,22 @@ impl<'shared, 'module, 'ctx> LowerMethod<'shared, 'module, 'ctx> {
         self.builder.call_with_return(func, &[source.into()]).into_int_value()
     }
 
+    /// Returns the value of the Int literal stored in a register.
+    fn int_literal(&self, register: RegisterId) -> i64 {
+        self.method
+            .body
+            .blocks
+            .iter()
+            .flat_map(|b| b.instructions.iter())
+            .find_map(|ins| match ins {
+                Instruction::Int(ins) if ins.register == register => {
+                    Some(ins.value)
+                }
+                _ => None,
+            })
+            .expect("the register must be assigned an Int literal")
+    }
+
     fn load_process(&mut self) -> PointerValue<'ctx> {
         let data = self.process_stack_data_pointer();
         let typ = self.layouts.process_stack_data;
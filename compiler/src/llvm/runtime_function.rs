@@ -20,6 +20,7 @@ pub(crate) enum RuntimeFunction {
     RuntimeStackMask,
     Free,
     AllocationError,
+    MemoryCompare,
 }
 
 impl RuntimeFunction {
@@ -46,6 +47,7 @@ impl RuntimeFunction {
             RuntimeFunction::RuntimeStackMask => "inko_runtime_stack_mask",
             RuntimeFunction::Free => "free",
             RuntimeFunction::AllocationError => "inko_alloc_error",
+            RuntimeFunction::MemoryCompare => "memcmp",
         }
     }
 
@@ -169,6 +171,14 @@ impl RuntimeFunction {
 
                 ret.fn_type(&[size], false)
             }
+            RuntimeFunction::MemoryCompare => {
+                let lhs = context.pointer_type().into();
+                let rhs = context.pointer_type().into();
+                let size = context.i64_type().into();
+                let ret = context.i32_type();
+
+                ret.fn_type(&[lhs, rhs, size], false)
+            }
         };
 
         module.add_function(self.name(), fn_type, None)
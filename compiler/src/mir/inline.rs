@@ -5,22 +5,8 @@ use crate::mir::{
 use crate::state::State;
 use std::cmp::min;
 use std::collections::HashSet;
-use types::{Database, Inline, MethodId, ModuleId};
-
-/// If a method wouldn't be inlined but is called at most this many times, it
-/// will still be inlined.
-///
-/// The goal of this setting is to allow inlining of methods that aren't used
-/// much, such as (large) private helper methods.
-const INLINE_ANYWAY_CALL_COUNT: u16 = 2;
-
-/// The maximum weight a method is allowed to have before we stop inlining other
-/// methods into it.
-///
-/// The current threshold is rather conservative in order to reduce the amount
-/// of LLVM IR we produce, as more LLVM IR results in (drastically) more
-/// compile-time memory usage.
-const MAX_WEIGHT: u16 = 100;
+use types::inline::InlineSize;
+use types::{Database, MethodId, ModuleId};
 
 fn instruction_weight(db: &Database, instruction: &Instruction) -> u16 {
     // The weights are mostly arbitrary and are meant to be a rough resemblance
@@ -895,16 +881,12 @@ impl<'a, 'b, 'c> InlineMethod<'a, 'b, 'c> {
         }
 
         let node = self.graph.node(call.id);
-        let inline = match call.id.inline(&self.state.db) {
-            Inline::Always => true,
-            Inline::Infer => {
-                node.weight == 0
-                    || node.calls <= INLINE_ANYWAY_CALL_COUNT
-                    || caller_weight + node.weight <= MAX_WEIGHT
-            }
-            Inline::Never => false,
+        let size = InlineSize {
+            body: node.weight,
+            caller: caller_weight,
+            calls: node.calls,
         };
 
-        inline.then_some(call)
+        call.id.inline_decision(&self.state.db, size).then_some(call)
     }
 }
@@ -8,6 +8,7 @@ pub(crate) mod pattern_matching;
 pub(crate) mod printer;
 pub(crate) mod specialize;
 
+use crate::hir::SizeOfKind;
 use crate::state::State;
 use crate::symbol_names::{qualified_class_name, SymbolNames};
 use indexmap::IndexMap;
@@ -68,6 +69,10 @@ impl Registers {
         self.values.len()
     }
 
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Register> {
+        self.values.iter()
+    }
+
     pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Register> {
         self.values.iter_mut()
     }
@@ -542,6 +547,7 @@ impl Block {
         method: types::MethodId,
         arguments: Vec<RegisterId>,
         type_arguments: Option<usize>,
+        devirtualize: Option<types::ClassId>,
         location: InstructionLocation,
     ) {
         self.instructions.push(Instruction::CallDynamic(Box::new(
@@ -551,6 +557,7 @@ impl Block {
                 method,
                 arguments,
                 type_arguments,
+                devirtualize,
                 location,
             },
         )));
@@ -763,11 +770,13 @@ impl Block {
     pub(crate) fn size_of(
         &mut self,
         register: RegisterId,
+        kind: SizeOfKind,
         argument: TypeRef,
         location: InstructionLocation,
     ) {
         self.instructions.push(Instruction::SizeOf(Box::new(SizeOf {
             register,
+            kind,
             argument,
             location,
         })));
@@ -1087,6 +1096,10 @@ pub(crate) struct CallDynamic {
     pub(crate) method: types::MethodId,
     pub(crate) arguments: Vec<RegisterId>,
     pub(crate) type_arguments: Option<usize>,
+
+    /// The class to call the method on directly, if the receiver can only
+    /// ever be an instance of this class.
+    pub(crate) devirtualize: Option<types::ClassId>,
     pub(crate) location: InstructionLocation,
 }
 
@@ -1184,7 +1197,7 @@ pub(crate) enum CastType {
 
 impl CastType {
     fn from(db: &Database, typ: TypeRef) -> CastType {
-        if let TypeRef::Pointer(_) = typ {
+        if let TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) = typ {
             CastType::Pointer
         } else {
             match typ.type_id(db) {
@@ -1200,11 +1213,14 @@ impl CastType {
                 Ok(TypeId::Foreign(ForeignType::Int(64, sign))) => {
                     CastType::Int(64, sign)
                 }
-                Ok(TypeId::Foreign(ForeignType::Float(32))) => {
-                    CastType::Float(32)
+                Ok(TypeId::Foreign(ForeignType::Int(128, sign))) => {
+                    CastType::Int(128, sign)
+                }
+                Ok(TypeId::Foreign(ForeignType::Float(size))) => {
+                    CastType::Float(size)
                 }
-                Ok(TypeId::Foreign(ForeignType::Float(64))) => {
-                    CastType::Float(64)
+                Ok(TypeId::Foreign(ForeignType::Function(_))) => {
+                    CastType::Pointer
                 }
                 Ok(TypeId::ClassInstance(ins)) => match ins.instance_of().0 {
                     BOOL_ID | NIL_ID => CastType::Int(1, Sign::Unsigned),
@@ -1258,6 +1274,7 @@ pub(crate) struct WritePointer {
 #[derive(Clone, Copy)]
 pub(crate) struct SizeOf {
     pub(crate) register: RegisterId,
+    pub(crate) kind: SizeOfKind,
     pub(crate) argument: types::TypeRef,
     pub(crate) location: InstructionLocation,
 }
@@ -1565,9 +1582,16 @@ impl Instruction {
                 )
             }
             Instruction::SizeOf(v) => {
+                let name = match v.kind {
+                    SizeOfKind::TypeName => "size_of",
+                    SizeOfKind::Size => "size_of_type",
+                    SizeOfKind::Alignment => "align_of_type",
+                };
+
                 format!(
-                    "r{} = size_of {}",
+                    "r{} = {} {}",
                     v.register.0,
+                    name,
                     types::format::format_type(db, v.argument)
                 )
             }
@@ -2014,6 +2038,14 @@ pub(crate) struct Mir {
     /// dispatch hashes for.
     pub(crate) dynamic_calls:
         HashMap<MethodId, HashSet<(MethodId, Vec<Shape>)>>,
+
+    /// Methods called directly instead of through dynamic dispatch, because
+    /// the receiver's trait is only implemented by a single class.
+    ///
+    /// Whether such a call is devirtualized depends on code outside of the
+    /// calling module, so this is used to flush incremental compilation
+    /// caches when this changes.
+    pub(crate) devirtualized: HashSet<MethodId>,
 }
 
 impl Mir {
@@ -2026,6 +2058,7 @@ impl Mir {
             extern_methods: HashSet::new(),
             type_arguments: Vec::new(),
             dynamic_calls: HashMap::new(),
+            devirtualized: HashSet::new(),
         }
     }
 
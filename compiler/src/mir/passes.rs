@@ -598,7 +598,7 @@ impl<'a> GenerateDropper<'a> {
             );
         }
 
-        for field in class.fields(lower.db()).into_iter().rev() {
+        for field in class.fields_in_drop_order(lower.db()) {
             let typ = field.value_type(lower.db());
 
             if typ.is_stack_allocated(lower.db()) {
@@ -1248,6 +1248,13 @@ pub(crate) struct LowerMethod<'a> {
 
     /// The number of fields that are moved.
     moved_fields: usize,
+
+    /// Registers moved into closures, and the locations of the expressions in
+    /// the closures that captured them.
+    ///
+    /// This is used to point to the closure when reporting the use of such a
+    /// register.
+    closure_moves: HashMap<RegisterId, Location>,
 }
 
 impl<'a> LowerMethod<'a> {
@@ -1276,6 +1283,7 @@ impl<'a> LowerMethod<'a> {
             variable_fields: HashMap::new(),
             used_variables: HashSet::new(),
             moved_fields: 0,
+            closure_moves: HashMap::new(),
         }
     }
 
@@ -1998,8 +2006,15 @@ impl<'a> LowerMethod<'a> {
             self.current_block_mut().send(rec, info.id, args, targs, ins_loc);
             self.current_block_mut().nil_literal(res, ins_loc);
         } else if info.dynamic {
-            self.current_block_mut()
-                .call_dynamic(res, rec, info.id, args, targs, ins_loc);
+            self.current_block_mut().call_dynamic(
+                res,
+                rec,
+                info.id,
+                args,
+                targs,
+                info.devirtualize,
+                ins_loc,
+            );
         } else {
             self.current_block_mut()
                 .call_instance(res, rec, info.id, args, targs, ins_loc);
@@ -2442,7 +2457,12 @@ impl<'a> LowerMethod<'a> {
         let loc = InstructionLocation::new(node.location);
         let reg = self.new_register(TypeRef::int());
 
-        self.current_block_mut().size_of(reg, node.resolved_type, loc);
+        self.current_block_mut().size_of(
+            reg,
+            node.kind,
+            node.resolved_type,
+            loc,
+        );
         reg
     }
 
@@ -3508,12 +3528,16 @@ impl<'a> LowerMethod<'a> {
             );
 
             let raw = self.get_local(var, ins_loc);
+            let capture_loc = closure_id
+                .capture(self.db(), var)
+                .and_then(|c| c.locations.first().cloned())
+                .unwrap_or(node.location);
 
             if !self.register_is_available(raw) {
                 self.state.diagnostics.moved_while_captured(
                     &name,
                     self.file(),
-                    node.location,
+                    capture_loc,
                 );
             }
 
@@ -3532,6 +3556,10 @@ impl<'a> LowerMethod<'a> {
                 self.input_register(raw, captured_as, None, loc)
             };
 
+            if self.register_is_moved(raw) {
+                self.closure_moves.insert(raw, capture_loc);
+            }
+
             self.current_block_mut().set_field(
                 gen_class_reg,
                 class_id,
@@ -3715,7 +3743,16 @@ impl<'a> LowerMethod<'a> {
             return;
         }
 
-        self.state.diagnostics.moved_variable(name, self.file(), location);
+        if let Some(&captured) = self.closure_moves.get(&register) {
+            self.state.diagnostics.moved_into_closure(
+                name,
+                captured,
+                self.file(),
+                location,
+            );
+        } else {
+            self.state.diagnostics.moved_variable(name, self.file(), location);
+        }
     }
 
     fn record_loop_move(&mut self, register: RegisterId, location: Location) {
@@ -4452,6 +4489,7 @@ impl<'a> LowerMethod<'a> {
         register: RegisterId,
         state: RegisterState,
     ) {
+        self.closure_moves.remove(&register);
         self.register_states.set(self.current_block, register, state);
     }
 
@@ -1,17 +1,22 @@
+use crate::diagnostics::DiagnosticId;
+use crate::hir::SizeOfKind;
 use crate::mir::{
     Block, BlockId, Borrow, CallDynamic, CallInstance, CastType,
-    Class as MirClass, Drop, Instruction, InstructionLocation, Method, Mir,
-    RegisterId, SELF_ID,
+    Class as MirClass, Drop, Instruction, InstructionLocation, IntLiteral,
+    Method, Mir, RegisterId, SELF_ID,
 };
 use crate::state::State;
 use indexmap::{IndexMap, IndexSet};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::mem::swap;
-use types::specialize::{ordered_shapes_from_map, TypeSpecializer};
+use types::specialize::{
+    borrowed_shape, ordered_shapes_from_map, uses_type_parameter,
+    TypeSpecializer,
+};
 use types::{
     Block as _, ClassId, ClassInstance, Database, InternedTypeArguments,
-    MethodId, Shape, TypeArguments, TypeParameterId, TypeRef, CALL_METHOD,
-    DROPPER_METHOD,
+    MethodId, Shape, TypeArguments, TypeId, TypeParameterId, TypeRef,
+    CALL_METHOD, DROPPER_METHOD,
 };
 
 fn argument_shape(
@@ -24,6 +29,71 @@ fn argument_shape(
     arguments.get_recursive(db, parameter).unwrap().shape(db, interned, shapes)
 }
 
+/// Returns the type parameters of a method that are only used through borrows.
+///
+/// Specializations of such a method for owned values and borrows of these type
+/// parameters produce the same code, so they can share a single specialization
+/// instead of generating one for each of these shapes.
+fn borrowed_type_parameters(
+    db: &Database,
+    mir: &Mir,
+    method: &Method,
+) -> Vec<TypeParameterId> {
+    let mut params = method.id.type_parameters(db);
+
+    if params.is_empty() {
+        return params;
+    }
+
+    for reg in method.registers.iter() {
+        let typ = reg.value_type;
+
+        match typ {
+            TypeRef::Ref(
+                TypeId::TypeParameter(_) | TypeId::RigidTypeParameter(_),
+            )
+            | TypeRef::UniRef(
+                TypeId::TypeParameter(_) | TypeId::RigidTypeParameter(_),
+            )
+            | TypeRef::Mut(
+                TypeId::TypeParameter(_) | TypeId::RigidTypeParameter(_),
+            )
+            | TypeRef::UniMut(
+                TypeId::TypeParameter(_) | TypeId::RigidTypeParameter(_),
+            ) => {}
+            _ => params.retain(|&p| !uses_type_parameter(db, typ, p)),
+        }
+    }
+
+    for block in &method.body.blocks {
+        for ins in &block.instructions {
+            let args = match ins {
+                Instruction::CallStatic(ins) => ins.type_arguments,
+                Instruction::CallInstance(ins) => ins.type_arguments,
+                Instruction::CallDynamic(ins) => ins.type_arguments,
+                Instruction::Send(ins) => ins.type_arguments,
+                Instruction::SizeOf(ins) => {
+                    params
+                        .retain(|&p| !uses_type_parameter(db, ins.argument, p));
+                    None
+                }
+                _ => None,
+            };
+
+            // Type arguments are used to derive the shapes of the called
+            // method, so a type parameter passed to another method must keep
+            // its original shape.
+            if let Some(idx) = args {
+                for typ in mir.type_arguments[idx].values() {
+                    params.retain(|&p| !uses_type_parameter(db, typ, p));
+                }
+            }
+        }
+    }
+
+    params
+}
+
 fn specialize_constants(
     db: &mut Database,
     mir: &mut Mir,
@@ -236,6 +306,10 @@ pub(crate) struct Specialize<'a, 'b> {
     intern: &'b mut InternedTypeArguments,
     shapes: HashMap<TypeParameterId, Shape>,
 
+    /// The type parameters of generic methods that are only used through
+    /// borrows.
+    borrowed: &'b HashMap<MethodId, Vec<TypeParameterId>>,
+
     /// Regular methods that have been processed.
     regular_methods: Vec<MethodId>,
 
@@ -270,6 +344,12 @@ impl<'a, 'b> Specialize<'a, 'b> {
             module.methods.clear();
         }
 
+        let borrowed: HashMap<_, _> = mir
+            .methods
+            .values()
+            .map(|m| (m.id, borrowed_type_parameters(&state.db, mir, m)))
+            .filter(|(_, params)| !params.is_empty())
+            .collect();
         let mut work = Work::new();
         let mut dcalls = DynamicCalls::new();
         let mut intern = InternedTypeArguments::new();
@@ -285,11 +365,20 @@ impl<'a, 'b> Specialize<'a, 'b> {
         mir.modules.get_mut(&main_mod).unwrap().methods.push(main_method);
 
         while let Some(job) = work.pop() {
+            // When a specialization limit is reached we stop, as continuing
+            // would only produce more errors (or exhaust memory).
+            if state.db.has_specialization_errors()
+                || state.diagnostics.has_errors()
+            {
+                return;
+            }
+
             Specialize {
                 state,
                 intern: &mut intern,
                 method: job.method,
                 shapes: job.shapes,
+                borrowed: &borrowed,
                 work: &mut work,
                 regular_methods: Vec::new(),
                 specialized_methods: Vec::new(),
@@ -455,11 +544,13 @@ impl<'a, 'b> Specialize<'a, 'b> {
 
                         ins.method = self.call_static(cls, ins.method, targs);
                     }
-                    Instruction::CallDynamic(call) => match method
-                        .registers
-                        .value_type(call.receiver)
-                        .as_class_instance(&self.state.db)
-                    {
+                    Instruction::CallDynamic(call) => match (
+                        method
+                            .registers
+                            .value_type(call.receiver)
+                            .as_class_instance(&self.state.db),
+                        call.devirtualize,
+                    ) {
                         // As part of specialization, we may encounter a dynamic
                         // call that's now acting on a class instance. We need
                         // to change the instruction in this case, otherwise we
@@ -468,7 +559,7 @@ impl<'a, 'b> Specialize<'a, 'b> {
                         // This is similar to devirtualization, except it's
                         // _required_ for correct code; not an optional
                         // optimization.
-                        Some(ins) => {
+                        (Some(ins), _) => {
                             let targs = call
                                 .type_arguments
                                 .and_then(|i| mir.type_arguments.get(i));
@@ -476,6 +567,25 @@ impl<'a, 'b> Specialize<'a, 'b> {
                             *instruction = self
                                 .devirtualize_call_dynamic(call, ins, targs);
                         }
+                        // If the receiver's trait is only implemented by a
+                        // single class we call its method directly, removing
+                        // the need for dynamic dispatch.
+                        (None, Some(class)) => {
+                            let targs = call
+                                .type_arguments
+                                .and_then(|i| mir.type_arguments.get(i));
+                            let new = self.devirtualize_call_dynamic(
+                                call,
+                                ClassInstance::new(class),
+                                targs,
+                            );
+
+                            if let Instruction::CallInstance(ins) = &new {
+                                mir.devirtualized.insert(ins.method);
+                            }
+
+                            *instruction = new;
+                        }
                         _ => {
                             let targs = call
                                 .type_arguments
@@ -569,6 +679,28 @@ impl<'a, 'b> Specialize<'a, 'b> {
                             &mut self.classes,
                         )
                         .specialize(ins.argument);
+
+                        // Now that the type is specialized, the sizes derived
+                        // from its layout are known and we can replace the
+                        // instruction with a constant. If the layout can't be
+                        // determined, the size is derived from the LLVM type.
+                        let db = &self.state.db;
+                        let value = match ins.kind {
+                            SizeOfKind::TypeName => None,
+                            SizeOfKind::Size => ins.argument.layout_size(db),
+                            SizeOfKind::Alignment => {
+                                ins.argument.layout_alignment(db)
+                            }
+                        };
+
+                        if let Some(value) = value {
+                            *instruction =
+                                Instruction::Int(Box::new(IntLiteral {
+                                    register: ins.register,
+                                    value: value as i64,
+                                    location: ins.location,
+                                }));
+                        }
                     }
                     _ => {}
                 }
@@ -926,6 +1058,22 @@ impl<'a, 'b> Specialize<'a, 'b> {
             return method;
         }
 
+        let collapsed;
+        let shapes = if let Some(params) = self.borrowed.get(&method) {
+            let mut new = shapes.clone();
+
+            for param in params {
+                if let Some(shape) = new.get_mut(param) {
+                    *shape = borrowed_shape(*shape).unwrap_or(*shape);
+                }
+            }
+
+            collapsed = new;
+            &collapsed
+        } else {
+            shapes
+        };
+
         let key: Vec<Shape> = class
             .type_parameters(&self.state.db)
             .into_iter()
@@ -937,6 +1085,28 @@ impl<'a, 'b> Specialize<'a, 'b> {
             return new;
         }
 
+        let limit = self.state.db.specialization_limits().specializations;
+
+        if method.number_of_specializations(&self.state.db) >= limit {
+            // Only the first method to reach the limit is reported, as
+            // specialization stops after this method is processed.
+            if !self.state.diagnostics.has_errors() {
+                self.state.diagnostics.error(
+                    DiagnosticId::LimitReached,
+                    format!(
+                        "the method '{}' can't be specialized more than {} \
+                        times",
+                        method.name(&self.state.db),
+                        limit
+                    ),
+                    method.module(&self.state.db).file(&self.state.db),
+                    method.location(&self.state.db),
+                );
+            }
+
+            return method;
+        }
+
         let ins = ClassInstance::new(class);
         let new_rec = method.receiver_for_class_instance(&self.state.db, ins);
         let new = self.specialize_method_type(new_rec, method, key, shapes);
@@ -1281,7 +1451,9 @@ impl<'a, 'b, 'c> ExpandDrop<'a, 'b, 'c> {
             | Shape::Float(_)
             | Shape::Nil
             | Shape::Boolean
+            | Shape::Char
             | Shape::Pointer
+            | Shape::Constant(_)
             | Shape::Stack(_) => {
                 self.ignore_value(block_id, after_id);
             }
@@ -1457,7 +1629,9 @@ impl<'a, 'b, 'c> ExpandBorrow<'a, 'b, 'c> {
             | Shape::Float(_)
             | Shape::Nil
             | Shape::Boolean
+            | Shape::Char
             | Shape::Pointer
+            | Shape::Constant(_)
             | Shape::Stack(_) => {
                 // These values should be left as-is.
             }
@@ -7,7 +7,8 @@ use crate::target::{OperatingSystem, Target};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use types::module_name::ModuleName;
-use types::Database;
+use types::packages::{Package, PackageId};
+use types::{Database, ModuleId};
 
 pub(crate) struct BuildTags {
     values: HashSet<String>,
@@ -48,11 +49,18 @@ pub(crate) struct Packages {
     /// A mapping of project roots to a mapping of dependency names with their
     /// source directories.
     sources: HashMap<PathBuf, HashMap<String, PathBuf>>,
+
+    /// A mapping of project roots to the packages defined for them.
+    ids: HashMap<PathBuf, PackageId>,
 }
 
 impl Packages {
     fn new() -> Packages {
-        Packages { roots: HashMap::new(), sources: HashMap::new() }
+        Packages {
+            roots: HashMap::new(),
+            sources: HashMap::new(),
+            ids: HashMap::new(),
+        }
     }
 
     /// Returns the project root directory of the given source file path.
@@ -177,9 +185,12 @@ pub(crate) struct State {
 impl State {
     pub(crate) fn new(config: Config) -> Self {
         let diagnostics = Diagnostics::new();
-        let db = Database::new();
+        let mut db = Database::new();
         let build_tags = BuildTags::new(&config.target);
 
+        db.set_target(config.target.type_target());
+        db.define_build_tag(config.target.abi_name().to_string());
+
         Self {
             config,
             diagnostics,
@@ -192,6 +203,43 @@ impl State {
         }
     }
 
+    /// Adds the module to the package (i.e. the project, a dependency or the
+    /// standard library) its source file resides in.
+    ///
+    /// Modules that don't reside in a project (e.g. a script compiled on its
+    /// own) aren't added to a package.
+    pub(crate) fn assign_package(&mut self, module: ModuleId) {
+        let file = module.file(&self.db);
+        let Some(root) = self.packages.root(&file) else { return };
+        let id = if let Some(&id) = self.packages.ids.get(&root) {
+            id
+        } else {
+            let dir_name = |path: &Path| {
+                path.file_name()
+                    .map(|v| v.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+
+            // Dependencies are stored in `DEPENDENCIES/NAME/vMAJOR`, where the
+            // name is derived from the URL of the package.
+            let (name, version) = match root.parent() {
+                Some(dir) if root.starts_with(&self.config.dependencies) => {
+                    let version = dir_name(&root);
+                    let version = version.strip_prefix('v').map(String::from);
+
+                    (dir_name(dir), version)
+                }
+                _ => (dir_name(&root), None),
+            };
+            let id = Package::alloc(&mut self.db, name, version);
+
+            self.packages.ids.insert(root, id);
+            id
+        };
+
+        id.add_module(&mut self.db, module);
+    }
+
     pub(crate) fn module_path(
         &mut self,
         importing: PathBuf,
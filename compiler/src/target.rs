@@ -1,4 +1,5 @@
 use std::fmt;
+use types::target::Endianness;
 
 /// The minimum SDK version to target when compiling for macOS.
 ///
@@ -233,6 +234,18 @@ impl Target {
         }
     }
 
+    /// Returns the target to store in the type database.
+    ///
+    /// All supported targets are 64 bits little-endian targets.
+    pub(crate) fn type_target(&self) -> types::target::Target {
+        types::target::Target::new(
+            self.os_name().to_string(),
+            self.arch_name().to_string(),
+            64,
+            Endianness::Little,
+        )
+    }
+
     pub fn is_native(&self) -> bool {
         self == &Target::native()
     }
@@ -10,13 +10,15 @@ use crate::type_check::{
 use location::Location;
 use std::path::PathBuf;
 use types::check::TypeChecker;
+use types::coherence;
 use types::format::format_type;
+use types::layout;
 use types::{
     Class, ClassId, ClassInstance, ClassKind, Constant, Database, ModuleId,
     Symbol, Trait, TraitId, TraitImplementation, TypeId, TypeRef, Visibility,
     ARRAY_INTERNAL_NAME, CONSTRUCTORS_LIMIT, ENUM_TAG_FIELD, ENUM_TAG_INDEX,
-    FIELDS_LIMIT, MAIN_CLASS, OPTION_CLASS, OPTION_MODULE, RESULT_CLASS,
-    RESULT_MODULE,
+    FIELDS_LIMIT, MAIN_CLASS, MAP_CLASS, MAP_MODULE, OPTION_CLASS,
+    OPTION_MODULE, RESULT_CLASS, RESULT_MODULE,
 };
 
 /// The maximum number of arguments a single constructor can accept. We subtract
@@ -116,6 +118,10 @@ impl<'a> DefineTypes<'a> {
             cls
         };
 
+        if let Some(val) = node.deprecated.clone() {
+            id.set_deprecated(self.db_mut(), val);
+        }
+
         if self.module.symbol_exists(self.db(), &name) {
             self.state.diagnostics.duplicate_symbol(
                 &name,
@@ -143,6 +149,16 @@ impl<'a> DefineTypes<'a> {
             loc,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            id.set_deprecated(self.db_mut(), val);
+        }
+
+        id.set_layout_options(self.db_mut(), node.layout);
+
+        if let Some(diag) = layout::check_options(self.db(), id) {
+            self.state.diagnostics.add_type_diagnostic(&self.state.db, diag);
+        }
+
         if self.module.symbol_exists(self.db(), &name) {
             self.state.diagnostics.duplicate_symbol(
                 &name,
@@ -167,6 +183,10 @@ impl<'a> DefineTypes<'a> {
             Location::default(),
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            id.set_deprecated(self.db_mut(), val);
+        }
+
         if self.module.symbol_exists(self.db(), &name) {
             self.state.diagnostics.duplicate_symbol(
                 &name,
@@ -199,6 +219,10 @@ impl<'a> DefineTypes<'a> {
         let loc = node.location;
         let id = Constant::alloc(db, module, loc, name, vis, TypeRef::Unknown);
 
+        if let Some(val) = node.deprecated.clone() {
+            id.set_deprecated(self.db_mut(), val);
+        }
+
         node.constant_id = Some(id);
     }
 
@@ -302,23 +326,28 @@ impl<'a> ImplementTraits<'a> {
         {
             let name = &node.trait_name.name.name;
 
-            if class_id
-                .trait_implementation(self.db(), instance.instance_of())
-                .is_some()
+            if let Some(existing) =
+                class_id.trait_implementation(self.db(), instance.instance_of())
             {
-                self.state.diagnostics.error(
-                    DiagnosticId::InvalidImplementation,
-                    format!(
-                        "the trait '{}' is already implemented for class '{}'",
-                        name, class_name
-                    ),
+                let existing =
+                    (existing.module.file(self.db()), existing.location);
+
+                self.state.diagnostics.duplicate_trait_implementation(
+                    name,
+                    class_name,
+                    existing,
                     self.file(),
                     node.location,
                 );
             } else {
                 class_id.add_trait_implementation(
                     self.db_mut(),
-                    TraitImplementation { instance, bounds },
+                    TraitImplementation {
+                        instance,
+                        bounds,
+                        module: self.module,
+                        location: node.location,
+                    },
                 );
             }
 
@@ -816,6 +845,11 @@ impl<'a> DefineTypeParameters<'a> {
                 param.type_parameter_id = Some(pid);
             }
         }
+
+        // This is done after defining the type parameters, as the type
+        // parameter of `Self` requires the trait to be implemented using these
+        // type parameters.
+        id.new_self_type(self.db_mut());
     }
 
     fn db(&self) -> &Database {
@@ -979,7 +1013,7 @@ impl<'a> InsertPrelude<'a> {
 
         self.import_class(OPTION_MODULE, OPTION_CLASS);
         self.import_class(RESULT_MODULE, RESULT_CLASS);
-        self.import_class("std.map", "Map");
+        self.import_class(MAP_MODULE, MAP_CLASS);
         self.import_method("std.process", "panic");
 
         // This name is used when desugaring array literals.
@@ -1229,6 +1263,16 @@ impl<'a> DefineConstructors<'a> {
     }
 }
 
+/// A compiler pass that checks if the trait implementations of all classes
+/// follow the coherence rules.
+pub(crate) fn check_coherence(state: &mut State) -> bool {
+    for diag in coherence::check(&state.db) {
+        state.diagnostics.add_type_diagnostic(&state.db, diag);
+    }
+
+    !state.diagnostics.has_errors()
+}
+
 /// A compiler pass that adds errors for recursive stack allocated classes.
 pub(crate) fn check_recursive_types(
     state: &mut State,
@@ -1713,6 +1757,8 @@ mod tests {
             TraitImplementation {
                 instance: to_str_ins,
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2,6 +2,7 @@
 use crate::diagnostics::DiagnosticId;
 use crate::hir;
 use crate::state::State;
+use crate::type_check::methods::unsendable_field_name;
 use crate::type_check::{DefineAndCheckTypeSignature, Rules, TypeScope};
 use location::Location;
 use std::cell::Cell;
@@ -9,15 +10,18 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use std::mem::swap;
 use std::path::PathBuf;
 use types::check::{Environment, TypeChecker};
+use types::deprecation;
+use types::dyn_compat;
+use types::escape::{self, Escape};
 use types::format::{format_type, format_type_with_arguments};
 use types::resolve::TypeResolver;
 use types::{
     Block, CallInfo, CallKind, ClassId, ClassInstance, Closure,
     ClosureCallInfo, ClosureId, ConstantKind, ConstantPatternKind, Database,
-    FieldId, FieldInfo, IdentifierKind, IntrinsicCall, MethodId, MethodLookup,
-    ModuleId, Receiver, Sign, Symbol, ThrowKind, TraitId, TraitInstance,
-    TypeArguments, TypeBounds, TypeId, TypeRef, Variable, VariableId,
-    CALL_METHOD, DEREF_POINTER_FIELD,
+    FieldId, FieldInfo, IdentifierKind, Intrinsic, IntrinsicCall, MethodId,
+    MethodLookup, ModuleId, Receiver, Sign, Symbol, ThrowKind, TraitId,
+    TraitInstance, TypeArguments, TypeBounds, TypeId, TypeRef, Variable,
+    VariableId, CALL_METHOD, DEREF_POINTER_FIELD,
 };
 
 const IGNORE_VARIABLE: &str = "_";
@@ -30,6 +34,35 @@ const CONST_ARRAY_LIMIT: usize = u16::MAX as usize;
 /// are generated later.
 const METHODS_IN_CLASS_LIMIT: usize = (u16::MAX - 1) as usize;
 
+/// Returns the variable an expression refers to, if any.
+fn escaping_variable(node: &hir::Expression) -> Option<VariableId> {
+    match node {
+        hir::Expression::IdentifierRef(ref n) => match n.kind {
+            IdentifierKind::Variable(id) => Some(id),
+            _ => None,
+        },
+        hir::Expression::TypeCast(ref n) => escaping_variable(&n.value),
+        _ => None,
+    }
+}
+
+/// Produces a warning if a reference to a symbol refers to a deprecated symbol.
+///
+/// The `method` argument is the method the reference occurs in, if any.
+fn check_deprecated(
+    state: &mut State,
+    symbol: Symbol,
+    method: Option<MethodId>,
+    module: ModuleId,
+    location: Location,
+) {
+    if let Some(diag) = deprecation::check_reference(
+        &state.db, symbol, method, module, location,
+    ) {
+        state.diagnostics.add_type_diagnostic(&state.db, diag);
+    }
+}
+
 fn copy_inherited_type_arguments(
     db: &Database,
     source: TraitInstance,
@@ -520,6 +553,25 @@ impl MethodCall {
     }
 
     fn check_sendable(&mut self, state: &mut State, location: Location) {
+        if self.method.is_async(&state.db) {
+            // The value of the future is produced by another process, so it
+            // must be sendable after resolving any type parameters.
+            if let Some(val) = self.return_type.future_value(&state.db) {
+                if !val.is_sendable(&state.db) {
+                    state.diagnostics.unsendable_async_type(
+                        format_type_with_arguments(
+                            &state.db,
+                            &self.type_arguments,
+                            val,
+                        ),
+                        unsendable_field_name(&state.db, val),
+                        self.module.file(&state.db),
+                        location,
+                    );
+                }
+            }
+        }
+
         if self.check_sendable.is_empty() {
             return;
         }
@@ -543,6 +595,7 @@ impl MethodCall {
 
             state.diagnostics.unsendable_argument(
                 format_type_with_arguments(&state.db, targs, given),
+                unsendable_field_name(&state.db, given),
                 self.module.file(&state.db),
                 loc,
             );
@@ -586,6 +639,18 @@ impl MethodCall {
         .with_owned()
         .resolve(raw);
 
+        // Async methods run in a different process, so callers receive a
+        // future that resolves to the returned value.
+        if self.method.is_async(&state.db)
+            && !self.return_type.is_nil(&state.db)
+        {
+            if let Some(typ) =
+                TypeRef::future_type(&mut state.db, self.return_type)
+            {
+                self.return_type = typ;
+            }
+        }
+
         self.return_type
     }
 }
@@ -671,6 +736,7 @@ impl<'a> Expressions<'a> {
             Expressions { state, module: module.module_id }.run(module);
         }
 
+        escape::infer(&mut state.db);
         !state.diagnostics.has_errors()
     }
 
@@ -1076,6 +1142,13 @@ impl<'a> CheckConstant<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        check_deprecated(
+            self.state,
+            Symbol::Method(method),
+            None,
+            self.module,
+            loc,
+        );
         call.check_type_bounds(self.state, loc);
         call.arguments = 1;
 
@@ -1120,6 +1193,13 @@ impl<'a> CheckConstant<'a> {
 
         match symbol {
             Some(Symbol::Constant(id)) => {
+                check_deprecated(
+                    self.state,
+                    Symbol::Constant(id),
+                    None,
+                    self.module,
+                    node.location,
+                );
                 node.kind = ConstantKind::Constant(id);
                 node.resolved_type = id.value_type(self.db());
                 node.resolved_type
@@ -1238,6 +1318,14 @@ impl<'a> CheckConstant<'a> {
                     location,
                 );
             }
+            MethodLookup::RequiredStaticOnTrait => {
+                self.state.diagnostics.required_static_call(
+                    name,
+                    format_type(self.db(), receiver),
+                    self.file(),
+                    location,
+                );
+            }
             MethodLookup::None => {
                 self.state.diagnostics.undefined_method(
                     name,
@@ -1282,6 +1370,13 @@ struct CheckMethodBody<'a> {
 
     /// The type IDs that are or originate from `self`.
     self_types: HashSet<TypeId>,
+
+    /// The variables that store pointers created using the `stack_alloc`
+    /// intrinsic.
+    ///
+    /// Such pointers are only valid for the duration of the surrounding
+    /// method, so we track them to prevent them from escaping it.
+    stack_pointers: HashSet<VariableId>,
 }
 
 impl<'a> CheckMethodBody<'a> {
@@ -1299,7 +1394,85 @@ impl<'a> CheckMethodBody<'a> {
             .collect();
 
         self_types.insert(self_type);
-        Self { state, module, method, self_type, bounds, self_types }
+        Self {
+            state,
+            module,
+            method,
+            self_type,
+            bounds,
+            self_types,
+            stack_pointers: HashSet::new(),
+        }
+    }
+
+    /// Returns `true` if the expression produces a pointer to memory
+    /// allocated using the `stack_alloc` intrinsic.
+    fn is_stack_pointer(&self, node: &hir::Expression) -> bool {
+        match node {
+            hir::Expression::BuiltinCall(ref n) => {
+                n.info.as_ref().map_or(false, |i| i.id == Intrinsic::StackAlloc)
+            }
+            hir::Expression::IdentifierRef(ref n) => match n.kind {
+                IdentifierKind::Variable(id) => {
+                    self.stack_pointers.contains(&id)
+                }
+                _ => false,
+            },
+            hir::Expression::TypeCast(ref n) => self.is_stack_pointer(&n.value),
+            // Calls that produce a pointer or an address (e.g. pointer
+            // arithmetic such as `ptr as Int + 8`) may produce a pointer into
+            // the same memory.
+            hir::Expression::Call(ref n) => match n.kind {
+                CallKind::Call(ref info)
+                    if info.returns.is_pointer(self.db())
+                        || info.returns.is_int(self.db()) =>
+                {
+                    n.receiver
+                        .as_ref()
+                        .map_or(false, |r| self.is_stack_pointer(r))
+                        || n.arguments
+                            .iter()
+                            .any(|a| self.is_stack_pointer(a.value()))
+                }
+                _ => false,
+            },
+            hir::Expression::Scope(ref n) => {
+                n.body.last().map_or(false, |n| self.is_stack_pointer(n))
+            }
+            hir::Expression::Tuple(ref n) => {
+                n.values.iter().any(|n| self.is_stack_pointer(n))
+            }
+            _ => false,
+        }
+    }
+
+    fn check_stack_pointer_escape(&mut self, node: &hir::Expression) {
+        if self.is_stack_pointer(node) {
+            self.state
+                .diagnostics
+                .stack_pointer_escapes(self.file(), node.location());
+        }
+    }
+
+    /// Records that the variable an expression refers to (if any) escapes the
+    /// surrounding method.
+    fn record_escape(&mut self, node: &hir::Expression, escape: Escape) {
+        if let Some(var) = escaping_variable(node) {
+            self.method.add_escape(self.db_mut(), var, escape);
+        }
+    }
+
+    /// Records that the variable an expression refers to (if any) is passed
+    /// as an argument to a method.
+    fn record_escape_flow(
+        &mut self,
+        node: &hir::Expression,
+        callee: MethodId,
+        index: usize,
+    ) {
+        if let Some(var) = escaping_variable(node) {
+            self.method.add_escape_flow(self.db_mut(), var, callee, index);
+        }
     }
 
     fn expressions(
@@ -1344,6 +1517,11 @@ impl<'a> CheckMethodBody<'a> {
             return;
         }
 
+        if let Some(n) = nodes.last() {
+            self.check_stack_pointer_escape(n);
+            self.record_escape(n, Escape::RETURNED);
+        }
+
         if !TypeChecker::check_return(self.db(), typ, returns) {
             let loc =
                 nodes.last().map(|n| n.location()).unwrap_or(fallback_location);
@@ -1659,6 +1837,10 @@ impl<'a> CheckMethodBody<'a> {
             node.name.location,
         );
 
+        if self.is_stack_pointer(&node.value) {
+            self.stack_pointers.insert(id);
+        }
+
         node.variable_id = Some(id);
         rtype
     }
@@ -1835,6 +2017,8 @@ impl<'a> CheckMethodBody<'a> {
             Ok(Some(Symbol::Constant(id))) => {
                 let typ = id.value_type(self.db());
 
+                self.check_deprecated(Symbol::Constant(id), node.location);
+
                 node.kind = if typ.is_int(self.db()) {
                     ConstantPatternKind::Int(id)
                 } else if typ.is_string(self.db()) {
@@ -2016,7 +2200,7 @@ impl<'a> CheckMethodBody<'a> {
 
         for node in &mut node.values {
             let name = &node.field.name;
-            let field = if let Some(f) = class.field(self.db(), name) {
+            let field = if let Some(f) = class.stored_field(self.db(), name) {
                 f
             } else {
                 self.state.diagnostics.error(
@@ -2341,13 +2525,17 @@ impl<'a> CheckMethodBody<'a> {
             return None;
         }
 
+        if self.is_stack_pointer(value_node) {
+            self.stack_pointers.insert(var);
+        }
+
         Some((var, var_type))
     }
 
     fn closure(
         &mut self,
         node: &mut hir::Closure,
-        mut expected: Option<(ClosureId, TypeRef, &TypeArguments)>,
+        expected: Option<(ClosureId, TypeRef, &TypeArguments)>,
         scope: &mut LexicalScope,
     ) -> TypeRef {
         let self_type = self.self_type;
@@ -2358,6 +2546,30 @@ impl<'a> CheckMethodBody<'a> {
 
         let closure = Closure::alloc(self.db_mut(), moving);
         let bounds = self.bounds;
+
+        // If the expected closure is generic, the new closure is generic over
+        // the same type parameters. These parameters are left as-is in the
+        // closure's signature, and are rigid in the closure's body.
+        let generic_args = expected
+            .as_ref()
+            .filter(|(id, _, _)| id.is_generic(self.db()))
+            .map(|&(id, _, targs)| {
+                let mut args = targs.clone();
+
+                for param in id.type_parameters(self.db()) {
+                    closure.add_type_parameter(self.db_mut(), param);
+                    args.assign(
+                        param,
+                        TypeRef::Any(TypeId::TypeParameter(param)),
+                    );
+                }
+
+                args
+            });
+        let mut expected = match (expected, generic_args.as_ref()) {
+            (Some((id, typ, _)), Some(args)) => Some((id, typ, args)),
+            (exp, _) => exp,
+        };
         let return_type = if let Some(n) = node.return_type.as_mut() {
             self.type_signature(n, self_type)
         } else {
@@ -2408,11 +2620,16 @@ impl<'a> CheckMethodBody<'a> {
                     })
                     .unwrap_or_else(|| TypeRef::placeholder(db, None))
             };
+            let var_type = if closure.is_generic(self.db()) {
+                typ.as_rigid_type(self.db_mut(), bounds)
+            } else {
+                typ
+            };
 
             let var = closure.new_argument(
                 self.db_mut(),
                 name.clone(),
-                typ,
+                var_type,
                 typ,
                 arg.location,
             );
@@ -2477,6 +2694,11 @@ impl<'a> CheckMethodBody<'a> {
 
                     return TypeRef::Error;
                 }
+                MethodLookup::RequiredStaticOnTrait => {
+                    self.required_static_call(&node.name, rec, node.location);
+
+                    return TypeRef::Error;
+                }
                 MethodLookup::InstanceOnStatic => {
                     self.invalid_instance_call(&node.name, rec, node.location);
 
@@ -2484,12 +2706,18 @@ impl<'a> CheckMethodBody<'a> {
                 }
                 _ => match module.use_symbol(self.db_mut(), &node.name) {
                     Some(Symbol::Constant(id)) => {
+                        self.check_deprecated(
+                            Symbol::Constant(id),
+                            node.location,
+                        );
                         node.resolved_type = id.value_type(self.db());
                         node.kind = ConstantKind::Constant(id);
 
                         return node.resolved_type;
                     }
                     Some(Symbol::Class(id)) if receiver => {
+                        self.check_deprecated(Symbol::Class(id), node.location);
+
                         return TypeRef::Owned(TypeId::Class(id));
                     }
                     Some(Symbol::Class(_) | Symbol::Trait(_)) if !receiver => {
@@ -2535,6 +2763,7 @@ impl<'a> CheckMethodBody<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        self.check_deprecated(Symbol::Method(method), loc);
         call.check_type_bounds(self.state, loc);
         call.check_arguments(self.state, loc);
         call.resolve_return_type(self.state);
@@ -2542,12 +2771,15 @@ impl<'a> CheckMethodBody<'a> {
 
         let returns = call.return_type;
 
+        self.record_call(rec_id, method);
         node.kind = ConstantKind::Method(CallInfo {
             id: method,
             receiver: rec_kind,
             returns,
             dynamic: rec_id.use_dynamic_dispatch(),
             type_arguments: call.type_arguments,
+            devirtualize: rec_id.sole_implementer(self.db()),
+            qualified: None,
         });
 
         node.resolved_type = returns;
@@ -2596,6 +2828,11 @@ impl<'a> CheckMethodBody<'a> {
 
                     return TypeRef::Error;
                 }
+                MethodLookup::RequiredStaticOnTrait => {
+                    self.required_static_call(name, rec, node.location);
+
+                    return TypeRef::Error;
+                }
                 MethodLookup::InstanceOnStatic => {
                     self.invalid_instance_call(name, rec, node.location);
 
@@ -2658,18 +2895,22 @@ impl<'a> CheckMethodBody<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        self.check_deprecated(Symbol::Method(method), loc);
         call.check_type_bounds(self.state, loc);
         call.check_arguments(self.state, loc);
         call.resolve_return_type(self.state);
         call.check_sendable(self.state, loc);
         let returns = call.return_type;
 
+        self.record_call(rec_id, method);
         node.kind = IdentifierKind::Method(CallInfo {
             id: method,
             receiver: rec_kind,
             returns,
             dynamic: rec_id.use_dynamic_dispatch(),
             type_arguments: call.type_arguments,
+            devirtualize: rec_id.sole_implementer(self.db()),
+            qualified: None,
         });
 
         returns
@@ -2771,6 +3012,9 @@ impl<'a> CheckMethodBody<'a> {
     ) -> Option<(FieldId, TypeRef)> {
         let val_type = self.expression(value_node, scope);
 
+        self.check_stack_pointer_escape(value_node);
+        self.record_escape(value_node, Escape::FIELD);
+
         let (field, var_type) = if let Some(typ) = self.field_type(name) {
             typ
         } else {
@@ -2924,6 +3168,11 @@ impl<'a> CheckMethodBody<'a> {
             .map(|n| self.expression(n, scope))
             .unwrap_or_else(TypeRef::nil);
 
+        if let Some(n) = node.value.as_ref() {
+            self.check_stack_pointer_escape(n);
+            self.record_escape(n, Escape::RETURNED);
+        }
+
         if scope.in_recover() && returned.is_owned(self.db()) {
             returned = returned.as_uni(self.db());
         }
@@ -2954,6 +3203,9 @@ impl<'a> CheckMethodBody<'a> {
             return expr;
         }
 
+        self.check_stack_pointer_escape(&node.value);
+        self.record_escape(&node.value, Escape::RETURNED);
+
         let ret_type = scope.return_type;
         let throw_type = if scope.in_recover() && expr.is_owned(self.db()) {
             expr.as_uni(self.db())
@@ -2965,10 +3217,11 @@ impl<'a> CheckMethodBody<'a> {
         node.resolved_type = throw_type;
 
         match ret_type.throw_kind(self.db()) {
-            ThrowKind::Unknown | ThrowKind::Option(_) => self
-                .state
-                .diagnostics
-                .throw_not_available(self.file(), node.location),
+            ThrowKind::Unknown | ThrowKind::Never | ThrowKind::Option(_) => {
+                self.state
+                    .diagnostics
+                    .throw_not_available(self.file(), node.location)
+            }
             ThrowKind::Infer(pid) => {
                 let var = TypeRef::placeholder(self.db_mut(), None);
                 let typ = TypeRef::result_type(self.db_mut(), var, expr);
@@ -3059,12 +3312,14 @@ impl<'a> CheckMethodBody<'a> {
     ) -> TypeRef {
         let expr = self.expression(&mut node.value, scope);
 
-        if !expr.allow_as_ref(self.db()) {
+        if let Err(err) = expr.try_as_ref(self.db()) {
             self.state.diagnostics.error(
                 DiagnosticId::InvalidType,
                 format!(
-                    "a 'ref T' can't be created from a value of type '{}'",
-                    self.fmt(expr)
+                    "a 'ref T' can't be created from a value of type '{}' \
+                    because {}",
+                    self.fmt(expr),
+                    err.reason(self.db())
                 ),
                 self.file(),
                 node.location,
@@ -3102,12 +3357,14 @@ impl<'a> CheckMethodBody<'a> {
 
         let expr = self.expression(&mut node.value, scope);
 
-        if !expr.allow_mutating(self.db()) {
+        if let Err(err) = expr.try_as_mut(self.db()) {
             self.state.diagnostics.error(
                 DiagnosticId::InvalidType,
                 format!(
-                    "a 'mut T' can't be created from a value of type '{}'",
-                    self.fmt(expr)
+                    "a 'mut T' can't be created from a value of type '{}' \
+                    because {}",
+                    self.fmt(expr),
+                    err.reason(self.db())
                 ),
                 self.file(),
                 node.location,
@@ -3205,6 +3462,11 @@ impl<'a> CheckMethodBody<'a> {
 
                 return TypeRef::Error;
             }
+            MethodLookup::RequiredStaticOnTrait => {
+                self.required_static_call(&setter, receiver, node.location);
+
+                return TypeRef::Error;
+            }
             MethodLookup::None => {
                 if self.assign_field_with_receiver(
                     node, receiver, rec_id, value, scope,
@@ -3213,7 +3475,7 @@ impl<'a> CheckMethodBody<'a> {
                 }
 
                 return match receiver {
-                    TypeRef::Pointer(id)
+                    TypeRef::Pointer(id) | TypeRef::NonNullPointer(id)
                         if node.name.name == DEREF_POINTER_FIELD =>
                     {
                         let exp = id.as_type_for_pointer();
@@ -3227,6 +3489,13 @@ impl<'a> CheckMethodBody<'a> {
                             );
                         }
 
+                        // Writing the pointer to memory that isn't itself
+                        // allocated using `stack_alloc` may result in the
+                        // pointer outliving the memory it points to.
+                        if !self.is_stack_pointer(&node.receiver) {
+                            self.check_stack_pointer_escape(&node.value);
+                        }
+
                         node.kind = CallKind::WritePointer;
                         TypeRef::nil()
                     }
@@ -3255,6 +3524,7 @@ impl<'a> CheckMethodBody<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        self.check_deprecated(Symbol::Method(method), loc);
         call.check_type_bounds(self.state, loc);
         node.expected_type =
             self.positional_argument(&mut call, 0, &mut node.value, scope);
@@ -3266,12 +3536,15 @@ impl<'a> CheckMethodBody<'a> {
         let returns = call.return_type;
         let rec_info = Receiver::with_receiver(self.db(), receiver, method);
 
+        self.record_call(rec_id, method);
         node.kind = CallKind::Call(CallInfo {
             id: method,
             receiver: rec_info,
             returns,
             dynamic: rec_id.use_dynamic_dispatch(),
             type_arguments: call.type_arguments,
+            devirtualize: rec_id.sole_implementer(self.db()),
+            qualified: None,
         });
 
         returns
@@ -3504,13 +3777,18 @@ impl<'a> CheckMethodBody<'a> {
             return TypeRef::Error;
         }
 
-        let targs = TypeArguments::new();
+        // Generic closures are instantiated for every call, such that each
+        // call can assign different types to the closure's type parameters.
+        let targs = closure.instantiate(self.db_mut());
 
         for (index, arg_node) in node.arguments.iter_mut().enumerate() {
-            let exp = closure
+            let raw = closure
                 .positional_argument_input_type(self.db(), index)
-                .unwrap()
-                .as_rigid_type(&mut self.state.db, self.bounds);
+                .unwrap();
+            let exp =
+                TypeResolver::new(&mut self.state.db, &targs, self.bounds)
+                    .with_rigid(true)
+                    .resolve(raw);
 
             let pos_node = match arg_node {
                 hir::Argument::Positional(expr) => expr,
@@ -3539,6 +3817,24 @@ impl<'a> CheckMethodBody<'a> {
             pos_node.expected_type = exp;
         }
 
+        for param in closure.type_parameters(self.db()) {
+            let typ = targs.get(param).unwrap();
+
+            if typ.is_value_type(self.db()) {
+                self.state.diagnostics.error(
+                    DiagnosticId::InvalidCall,
+                    format!(
+                        "the type '{}' can't be assigned to the type \
+                        parameter '{}' of a closure, as it's a value type",
+                        format_type(self.db(), typ),
+                        param.name(self.db())
+                    ),
+                    self.file(),
+                    node.location,
+                );
+            }
+        }
+
         let returns = {
             let raw = closure.return_type(self.db());
 
@@ -3597,6 +3893,15 @@ impl<'a> CheckMethodBody<'a> {
 
                 return TypeRef::Error;
             }
+            MethodLookup::RequiredStaticOnTrait => {
+                self.required_static_call(
+                    &node.name.name,
+                    receiver,
+                    node.location,
+                );
+
+                return TypeRef::Error;
+            }
             MethodLookup::None if node.arguments.is_empty() && !node.parens => {
                 if let Some(typ) =
                     self.field_with_receiver(node, receiver, rec_id)
@@ -3607,11 +3912,20 @@ impl<'a> CheckMethodBody<'a> {
                 if let TypeId::Module(id) = rec_id {
                     match id.use_symbol(self.db_mut(), &node.name.name) {
                         Some(Symbol::Constant(id)) => {
+                            self.check_deprecated(
+                                Symbol::Constant(id),
+                                node.location,
+                            );
                             node.kind = CallKind::GetConstant(id);
 
                             return id.value_type(self.db());
                         }
                         Some(Symbol::Class(id)) if as_receiver => {
+                            self.check_deprecated(
+                                Symbol::Class(id),
+                                node.location,
+                            );
+
                             return TypeRef::Owned(TypeId::Class(id));
                         }
                         Some(Symbol::Class(_) | Symbol::Trait(_))
@@ -3629,8 +3943,20 @@ impl<'a> CheckMethodBody<'a> {
                     }
                 }
 
+                if let TypeId::Class(id) = rec_id {
+                    if let Some(id) = id.constant(self.db(), &node.name.name) {
+                        self.check_deprecated(
+                            Symbol::Constant(id),
+                            node.location,
+                        );
+                        node.kind = CallKind::GetConstant(id);
+
+                        return id.value_type(self.db());
+                    }
+                }
+
                 return match receiver {
-                    TypeRef::Pointer(id)
+                    TypeRef::Pointer(id) | TypeRef::NonNullPointer(id)
                         if node.name.name == DEREF_POINTER_FIELD =>
                     {
                         let ret = id.as_type_for_pointer();
@@ -3681,6 +4007,7 @@ impl<'a> CheckMethodBody<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        self.check_deprecated(Symbol::Method(method), loc);
         call.check_type_bounds(self.state, loc);
         self.call_arguments(&mut node.arguments, &mut call, scope);
         call.check_arguments(self.state, loc);
@@ -3690,12 +4017,15 @@ impl<'a> CheckMethodBody<'a> {
         let returns = call.return_type;
         let rec_info = Receiver::with_receiver(self.db(), receiver, method);
 
+        self.record_call(rec_id, method);
         node.kind = CallKind::Call(CallInfo {
             id: method,
             receiver: rec_info,
             returns,
             dynamic: rec_id.use_dynamic_dispatch(),
             type_arguments: call.type_arguments,
+            devirtualize: rec_id.sole_implementer(self.db()),
+            qualified: None,
         });
 
         returns
@@ -3734,6 +4064,11 @@ impl<'a> CheckMethodBody<'a> {
 
                     return TypeRef::Error;
                 }
+                MethodLookup::RequiredStaticOnTrait => {
+                    self.required_static_call(name, rec, node.location);
+
+                    return TypeRef::Error;
+                }
                 MethodLookup::InstanceOnStatic => {
                     self.invalid_instance_call(name, rec, node.location);
 
@@ -3785,6 +4120,7 @@ impl<'a> CheckMethodBody<'a> {
         );
 
         call.check_mutability(self.state, loc);
+        self.check_deprecated(Symbol::Method(method), loc);
         call.check_type_bounds(self.state, loc);
         self.call_arguments(&mut node.arguments, &mut call, scope);
         call.check_arguments(self.state, loc);
@@ -3793,12 +4129,15 @@ impl<'a> CheckMethodBody<'a> {
 
         let returns = call.return_type;
 
+        self.record_call(rec_id, method);
         node.kind = CallKind::Call(CallInfo {
             id: method,
             receiver: rec_info,
             returns,
             dynamic: rec_id.use_dynamic_dispatch(),
             type_arguments: call.type_arguments,
+            devirtualize: rec_id.sole_implementer(self.db()),
+            qualified: None,
         });
 
         returns
@@ -3810,6 +4149,8 @@ impl<'a> CheckMethodBody<'a> {
         scope: &mut LexicalScope,
         class: ClassId,
     ) -> TypeRef {
+        self.check_deprecated(Symbol::Class(class), node.location);
+
         if class.is_builtin() && !self.module.is_std(self.db()) {
             self.state.diagnostics.error(
                 DiagnosticId::InvalidType,
@@ -3852,22 +4193,23 @@ impl<'a> CheckMethodBody<'a> {
                     (field, &mut n.value)
                 }
                 hir::Argument::Named(n) => {
-                    let field =
-                        if let Some(v) = class.field(self.db(), &n.name.name) {
-                            v
-                        } else {
-                            self.state.diagnostics.error(
-                                DiagnosticId::InvalidSymbol,
-                                format!(
-                                    "the field '{}' is undefined",
-                                    &n.name.name
-                                ),
-                                self.file(),
-                                n.location,
-                            );
+                    let field = if let Some(v) =
+                        class.stored_field(self.db(), &n.name.name)
+                    {
+                        v
+                    } else {
+                        self.state.diagnostics.error(
+                            DiagnosticId::InvalidSymbol,
+                            format!(
+                                "the field '{}' is undefined",
+                                &n.name.name
+                            ),
+                            self.file(),
+                            n.location,
+                        );
 
-                            continue;
-                        };
+                        continue;
+                    };
 
                     (field, &mut n.value)
                 }
@@ -3913,6 +4255,10 @@ impl<'a> CheckMethodBody<'a> {
             };
 
             let value = self.expression(val_expr, scope);
+
+            self.check_stack_pointer_escape(val_expr);
+            self.record_escape(val_expr, Escape::FIELD);
+
             let value_casted = value.cast_according_to(self.db(), expected);
             let checker = TypeChecker::new(self.db());
             let mut env =
@@ -4028,9 +4374,11 @@ impl<'a> CheckMethodBody<'a> {
         node: &mut hir::BuiltinCall,
         scope: &mut LexicalScope,
     ) -> TypeRef {
-        for n in &mut node.arguments {
-            self.expression(n, scope);
-        }
+        let types: Vec<_> = node
+            .arguments
+            .iter_mut()
+            .map(|n| self.expression(n, scope))
+            .collect();
 
         let id = if let Some(id) = self.db().intrinsic(&node.name.name) {
             id
@@ -4044,6 +4392,45 @@ impl<'a> CheckMethodBody<'a> {
             return TypeRef::Error;
         };
 
+        if let Some(expected) = id.argument_types() {
+            if expected.len() != types.len() {
+                self.state.diagnostics.incorrect_call_arguments(
+                    types.len(),
+                    expected.len(),
+                    self.file(),
+                    node.location,
+                );
+            } else {
+                for ((&given, &exp), arg) in
+                    types.iter().zip(expected.iter()).zip(&node.arguments)
+                {
+                    if !TypeChecker::check(self.db(), given, exp) {
+                        self.state.diagnostics.type_error(
+                            format_type(self.db(), given),
+                            format_type(self.db(), exp),
+                            self.file(),
+                            arg.location(),
+                        );
+                    }
+                }
+            }
+        }
+
+        // The memory is allocated in the entry block of the surrounding
+        // function, which requires the size to be known at compile-time.
+        if let (Intrinsic::StackAlloc, [arg]) = (id, node.arguments.as_slice())
+        {
+            match arg {
+                hir::Expression::Int(n)
+                    if n.value > 0 && n.value <= u32::MAX as i64 => {}
+                n => {
+                    self.state
+                        .diagnostics
+                        .invalid_stack_alloc_size(self.file(), n.location());
+                }
+            }
+        }
+
         let returns = id.return_type();
 
         node.info = Some(IntrinsicCall { id, returns });
@@ -4076,6 +4463,28 @@ impl<'a> CheckMethodBody<'a> {
         .define_type(&mut node.cast_to);
 
         if !TypeChecker::check_cast(self.db_mut(), expr_type, cast_type) {
+            if let Ok(TypeId::TraitInstance(ins)) = cast_type.type_id(self.db())
+            {
+                if let Some(diag) = dyn_compat::check(
+                    self.db(),
+                    ins.instance_of(),
+                    self.module,
+                    node.location,
+                ) {
+                    let notes: Vec<_> =
+                        diag.notes.into_iter().map(|n| n.message).collect();
+
+                    self.state.diagnostics.error(
+                        DiagnosticId::InvalidCast,
+                        format!("{}: {}", diag.message, notes.join("; ")),
+                        self.file(),
+                        node.location,
+                    );
+
+                    return TypeRef::Error;
+                }
+            }
+
             self.state.diagnostics.error(
                 DiagnosticId::InvalidCast,
                 format!(
@@ -4154,14 +4563,17 @@ impl<'a> CheckMethodBody<'a> {
                     node.location,
                 );
             }
-            (ThrowKind::Unknown | ThrowKind::Infer(_), _) => {
+            (
+                ThrowKind::Unknown | ThrowKind::Never | ThrowKind::Infer(_),
+                _,
+            ) => {
                 self.state.diagnostics.invalid_try(
                     format_type(self.db(), expr),
                     self.file(),
                     node.expression.location(),
                 );
             }
-            (_, ThrowKind::Unknown) => {
+            (_, ThrowKind::Unknown | ThrowKind::Never) => {
                 self.state
                     .diagnostics
                     .try_not_available(self.file(), node.location);
@@ -4305,6 +4717,7 @@ impl<'a> CheckMethodBody<'a> {
                 &call.type_arguments,
             );
 
+            self.record_escape_flow(node, call.method, index);
             call.check_argument(self.state, given, expected, node.location())
         } else {
             self.expression(node, scope)
@@ -4333,6 +4746,8 @@ impl<'a> CheckMethodBody<'a> {
                 &call.type_arguments,
             );
 
+            self.record_escape_flow(&node.value, call.method, index);
+
             if call.named_arguments.contains(name) {
                 self.state.diagnostics.error(
                     DiagnosticId::InvalidCall,
@@ -4451,6 +4866,16 @@ impl<'a> CheckMethodBody<'a> {
         self.method.field_id_and_type(self.db(), name)
     }
 
+    fn check_deprecated(&mut self, symbol: Symbol, location: Location) {
+        check_deprecated(
+            self.state,
+            symbol,
+            Some(self.method),
+            self.module,
+            location,
+        );
+    }
+
     fn file(&self) -> PathBuf {
         self.module.file(self.db())
     }
@@ -4467,6 +4892,13 @@ impl<'a> CheckMethodBody<'a> {
         format_type(self.db(), typ)
     }
 
+    fn record_call(&mut self, receiver: TypeId, method: MethodId) {
+        let caller = self.method;
+        let dynamic = receiver.use_dynamic_dispatch();
+
+        self.db_mut().record_call(caller, method, dynamic);
+    }
+
     fn invalid_static_call(
         &mut self,
         name: &str,
@@ -4481,6 +4913,20 @@ impl<'a> CheckMethodBody<'a> {
         );
     }
 
+    fn required_static_call(
+        &mut self,
+        name: &str,
+        receiver: TypeRef,
+        location: Location,
+    ) {
+        self.state.diagnostics.required_static_call(
+            name,
+            self.fmt(receiver),
+            self.file(),
+            location,
+        );
+    }
+
     fn invalid_instance_call(
         &mut self,
         name: &str,
@@ -4541,6 +4987,14 @@ impl<'a> CheckMethodBody<'a> {
                 ScopeKind::Closure(closure) => {
                     let moving = closure.is_moving(self.db());
 
+                    // Closures may outlive the surrounding method, so they
+                    // can't capture pointers to its stack memory.
+                    if !captured && self.stack_pointers.contains(&var) {
+                        self.state
+                            .diagnostics
+                            .stack_pointer_captured(self.file(), location);
+                    }
+
                     if (expose_as.is_uni(self.db()) && !moving)
                         || expose_as.is_uni_ref(self.db())
                     {
@@ -4585,7 +5039,17 @@ impl<'a> CheckMethodBody<'a> {
                         expose_as = expose_as.as_mut(self.db());
                     }
 
-                    closure.add_capture(self.db_mut(), var, capture_as);
+                    closure.add_capture(
+                        self.db_mut(),
+                        var,
+                        capture_as,
+                        location,
+                    );
+                    self.method.add_escape(
+                        self.db_mut(),
+                        var,
+                        Escape::CAPTURED,
+                    );
                     captured = true;
 
                     // Captured variables can only be assigned by moving
@@ -4609,7 +5073,7 @@ impl<'a> CheckMethodBody<'a> {
     ) -> Option<(ClassInstance, FieldId)> {
         let (ins, field) = if let TypeId::ClassInstance(ins) = receiver_id {
             ins.instance_of()
-                .field(self.db(), &name.name)
+                .stored_field(self.db(), &name.name)
                 .map(|field| (ins, field))
         } else {
             None
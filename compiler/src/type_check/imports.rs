@@ -88,7 +88,13 @@ impl<'a> DefineImportedTypes<'a> {
                 location,
             );
         } else {
-            self.module.new_symbol(self.db_mut(), name, Symbol::Module(source));
+            self.module.new_imported_symbol(
+                self.db_mut(),
+                name,
+                Symbol::Module(source),
+                source,
+                location,
+            );
         }
     }
 
@@ -118,10 +124,12 @@ impl<'a> DefineImportedTypes<'a> {
                     node.name.location,
                 );
             } else {
-                self.module.new_symbol(
+                self.module.new_imported_symbol(
                     self.db_mut(),
                     import_as.clone(),
                     symbol,
+                    source,
+                    node.import_as.location,
                 );
             }
         } else {
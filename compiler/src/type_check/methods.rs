@@ -3,19 +3,28 @@ use crate::diagnostics::DiagnosticId;
 use crate::hir;
 use crate::state::State;
 use crate::type_check::{
-    define_type_bounds, DefineAndCheckTypeSignature, Rules, TypeScope,
+    define_type_bounds, extend_type_bounds, DefineAndCheckTypeSignature, Rules,
+    TypeScope,
 };
 use location::Location;
 use std::path::PathBuf;
 use types::check::{Environment, TypeChecker};
 use types::format::{format_type, format_type_with_arguments};
 use types::{
-    Block, ClassId, ClassInstance, Database, Method, MethodId, MethodKind,
-    MethodSource, ModuleId, Symbol, TraitId, TraitInstance, TypeArguments,
-    TypeBounds, TypeId, TypeRef, Visibility, DROP_METHOD, MAIN_CLASS,
-    MAIN_METHOD,
+    Block, ClassId, ClassInstance, Database, Inline, Method, MethodId,
+    MethodKind, MethodSource, ModuleId, Symbol, TraitId, TraitInstance,
+    TypeArguments, TypeBounds, TypeId, TypeRef, Visibility, DROP_METHOD,
+    MAIN_CLASS, MAIN_METHOD,
 };
 
+/// Returns the name of the field that makes a type unsendable, if any.
+pub(crate) fn unsendable_field_name(
+    db: &Database,
+    typ: TypeRef,
+) -> Option<String> {
+    typ.unsendable_field(db).map(|f| f.name(db).clone())
+}
+
 fn method_kind(kind: hir::MethodKind) -> MethodKind {
     match kind {
         hir::MethodKind::Regular => MethodKind::Instance,
@@ -177,10 +186,12 @@ trait MethodDefiner {
 
             if require_send && !arg_type.is_sendable(self.db()) {
                 let name = format_type(self.db(), arg_type);
+                let field = unsendable_field_name(self.db(), arg_type);
                 let file = self.file();
 
                 self.state_mut().diagnostics.unsendable_async_type(
                     name,
+                    field,
                     file,
                     node.location,
                 );
@@ -213,10 +224,12 @@ trait MethodDefiner {
 
             if method.is_async(self.db()) && !typ.is_sendable(self.db()) {
                 let name = format_type(self.db(), typ);
+                let field = unsendable_field_name(self.db(), typ);
                 let file = self.file();
 
                 self.state_mut().diagnostics.unsendable_async_type(
                     name,
+                    field,
                     file,
                     node.location(),
                 );
@@ -328,6 +341,10 @@ impl<'a> DefineModuleMethodNames<'a> {
             MethodKind::Static,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if node.inline {
             method.always_inline(self.db_mut());
         }
@@ -367,6 +384,10 @@ impl<'a> DefineModuleMethodNames<'a> {
             MethodKind::Extern,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if node.variadic {
             method.set_variadic(self.db_mut());
         }
@@ -651,6 +672,10 @@ impl<'a> DefineMethods<'a> {
             MethodKind::Static,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if node.inline {
             method.always_inline(self.db_mut());
         }
@@ -697,6 +722,16 @@ impl<'a> DefineMethods<'a> {
         node: &mut hir::DefineInstanceMethod,
         mut bounds: TypeBounds,
     ) {
+        if !node.bounds.is_empty() {
+            bounds = extend_type_bounds(
+                self.state,
+                self.module,
+                class_id,
+                bounds,
+                &mut node.bounds,
+            );
+        }
+
         let async_class = class_id.kind(self.db()).is_async();
 
         if matches!(node.kind, hir::MethodKind::Moving) && async_class {
@@ -735,6 +770,10 @@ impl<'a> DefineMethods<'a> {
             kind,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if node.inline {
             method.always_inline(self.db_mut());
         }
@@ -822,6 +861,10 @@ impl<'a> DefineMethods<'a> {
             kind,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if !method.is_mutable(self.db()) {
             bounds.make_immutable(self.db_mut());
         }
@@ -911,6 +954,10 @@ impl<'a> DefineMethods<'a> {
             kind,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         self.define_type_parameters(&mut node.type_parameters, method, self_id);
 
         let rules = Rules {
@@ -979,6 +1026,10 @@ impl<'a> DefineMethods<'a> {
             kind,
         );
 
+        if let Some(val) = node.deprecated.clone() {
+            method.set_deprecated(self.db_mut(), val);
+        }
+
         if node.inline {
             method.always_inline(self.db_mut());
         }
@@ -1115,6 +1166,55 @@ impl<'a> MethodDefiner for DefineMethods<'a> {
     }
 }
 
+/// A compiler pass that finds recursive methods that must always be inlined.
+///
+/// Inlining such methods would never terminate, so they're changed to be
+/// inlined based on the usual heuristics, and a warning is produced for each
+/// such method.
+pub(crate) struct CheckInlineCycles<'a> {
+    state: &'a mut State,
+}
+
+impl<'a> CheckInlineCycles<'a> {
+    pub(crate) fn run(state: &'a mut State) -> bool {
+        CheckInlineCycles { state }.check();
+        true
+    }
+
+    fn check(&mut self) {
+        let cycles = self.state.db.always_inline_cycles();
+        let always: Vec<_> = cycles
+            .iter()
+            .flatten()
+            .filter(|m| m.inline(&self.state.db) == Inline::Always)
+            .cloned()
+            .collect();
+
+        self.state.db.break_always_inline_cycles(&cycles);
+
+        for cycle in cycles {
+            let names: Vec<_> =
+                cycle.iter().map(|m| m.name(&self.state.db).clone()).collect();
+
+            for method in cycle {
+                if !always.contains(&method)
+                    || method.is_generated(&self.state.db)
+                {
+                    continue;
+                }
+
+                let file = method.source_file(&self.state.db);
+                let loc = method.location(&self.state.db);
+                let name = method.name(&self.state.db).clone();
+
+                self.state
+                    .diagnostics
+                    .recursive_inline_method(&name, &names, file, loc);
+            }
+        }
+    }
+}
+
 /// A compiler pass that checks if the `Main` process and its `main` method are
 /// defined, and marks the main method accordingly.
 pub(crate) struct CheckMainMethod<'a> {
@@ -1430,7 +1530,11 @@ impl<'a> ImplementTraitMethods<'a> {
             &scope,
         );
 
-        let targs = TypeArguments::for_trait(self.db(), trait_instance);
+        let targs = TypeArguments::for_implementation(
+            self.db(),
+            trait_instance,
+            class_instance,
+        );
         let mut env = Environment::new(targs.clone(), targs);
 
         if !TypeChecker::new(self.db()).check_method(method, original, &mut env)
@@ -7,9 +7,9 @@ use std::path::PathBuf;
 use types::check::{Environment, TypeChecker};
 use types::format::format_type;
 use types::{
-    Block, ClassId, ClassInstance, Closure, Database, MethodId, ModuleId,
-    Symbol, TraitId, TraitInstance, TypeArguments, TypeBounds, TypeId,
-    TypeParameterId, TypeRef,
+    Block, ClassId, ClassInstance, Closure, Database, ForeignType, MethodId,
+    ModuleId, Symbol, TraitId, TraitInstance, TypeArguments, TypeBounds,
+    TypeId, TypeParameter, TypeParameterId, TypeRef,
 };
 
 pub(crate) mod define_types;
@@ -141,6 +141,9 @@ pub(crate) struct DefineTypeSignature<'a> {
     module: ModuleId,
     scope: &'a TypeScope<'a>,
     rules: Rules,
+
+    /// The type parameters of the closure types we're defining.
+    parameters: Vec<TypeParameterId>,
 }
 
 impl<'a> DefineTypeSignature<'a> {
@@ -150,7 +153,7 @@ impl<'a> DefineTypeSignature<'a> {
         scope: &'a TypeScope<'a>,
         rules: Rules,
     ) -> Self {
-        Self { state, module, scope, rules }
+        Self { state, module, scope, rules, parameters: Vec::new() }
     }
 
     pub(crate) fn as_trait_instance(
@@ -246,6 +249,10 @@ impl<'a> DefineTypeSignature<'a> {
 
                 return TypeRef::Error;
             }
+        } else if let Some(&id) =
+            self.parameters.iter().rev().find(|p| p.name(self.db()) == name)
+        {
+            Some(Symbol::TypeParameter(id))
         } else {
             self.scope.symbol(self.db_mut(), name)
         };
@@ -419,7 +426,11 @@ impl<'a> DefineTypeSignature<'a> {
         let param_id =
             self.scope.bounds.as_ref().and_then(|b| b.get(id)).unwrap_or(id);
 
-        let type_id = if self.rules.type_parameters_as_rigid {
+        // The type parameters of closures are assigned a value when calling
+        // the closure, so they're never rigid in the closure's signature.
+        let type_id = if self.rules.type_parameters_as_rigid
+            && !self.parameters.contains(&param_id)
+        {
             TypeId::RigidTypeParameter(param_id)
         } else {
             TypeId::TypeParameter(param_id)
@@ -446,6 +457,40 @@ impl<'a> DefineTypeSignature<'a> {
         kind: RefKind,
     ) -> TypeRef {
         let block = Closure::alloc(self.db_mut(), false);
+        let parameters = self.parameters.len();
+
+        for param_node in &mut node.type_parameters {
+            let name = param_node.name.name.clone();
+            let param = TypeParameter::alloc(self.db_mut(), name);
+
+            if param_node.mutable {
+                param.set_mutable(self.db_mut());
+            }
+
+            if param_node.inline {
+                self.state.diagnostics.error(
+                    DiagnosticId::InvalidType,
+                    "the type parameters of closures can't be 'inline'",
+                    self.file(),
+                    param_node.location,
+                );
+            }
+
+            block.add_type_parameter(self.db_mut(), param);
+            param_node.type_parameter_id = Some(param);
+            self.parameters.push(param);
+        }
+
+        for param_node in &mut node.type_parameters {
+            let param = param_node.type_parameter_id.unwrap();
+            let reqs = param_node
+                .requirements
+                .iter_mut()
+                .filter_map(|n| self.as_trait_instance(n))
+                .collect();
+
+            param.add_requirements(self.db_mut(), reqs);
+        }
 
         for arg_node in &mut node.arguments {
             let typ = self.define_type(arg_node);
@@ -460,6 +505,17 @@ impl<'a> DefineTypeSignature<'a> {
         };
 
         block.set_return_type(self.db_mut(), return_type);
+        self.parameters.truncate(parameters);
+
+        if !block.type_parameters_used_as_references(self.db()) {
+            self.state.diagnostics.error(
+                DiagnosticId::InvalidType,
+                "the type parameters of a closure can only be used as the \
+                type of a reference, such as 'ref T' or 'mut T'",
+                self.file(),
+                node.location,
+            );
+        }
 
         let typ = kind.into_type_ref(TypeId::Closure(block));
 
@@ -497,13 +553,35 @@ impl<'a> DefineTypeSignature<'a> {
             "Int16" => Some(TypeRef::foreign_signed_int(16)),
             "Int32" => Some(TypeRef::foreign_signed_int(32)),
             "Int64" => Some(TypeRef::foreign_signed_int(64)),
+            "Int128" => Some(TypeRef::foreign_signed_int(128)),
             "UInt8" => Some(TypeRef::foreign_unsigned_int(8)),
             "UInt16" => Some(TypeRef::foreign_unsigned_int(16)),
             "UInt32" => Some(TypeRef::foreign_unsigned_int(32)),
             "UInt64" => Some(TypeRef::foreign_unsigned_int(64)),
+            "UInt128" => Some(TypeRef::foreign_unsigned_int(128)),
             "Float32" => Some(TypeRef::foreign_float(32)),
             "Float64" => Some(TypeRef::foreign_float(64)),
-            "Pointer" => {
+            "Float80" => {
+                let typ = ForeignType::Float(80);
+
+                if !self.db().target().supports_foreign_type(typ) {
+                    self.state.diagnostics.error(
+                        DiagnosticId::InvalidType,
+                        format!(
+                            "the type '{}' isn't supported by the target '{}'",
+                            name, self.state.config.target
+                        ),
+                        self.file(),
+                        location,
+                    );
+
+                    return None;
+                }
+
+                Some(TypeRef::foreign_float(80))
+            }
+            "Float128" => Some(TypeRef::foreign_float(128)),
+            "Pointer" | "NonNullPointer" => {
                 if arguments.len() != 1 {
                     self.state.diagnostics.incorrect_number_of_type_arguments(
                         1,
@@ -545,7 +623,10 @@ impl<'a> DefineTypeSignature<'a> {
                 }?;
 
                 match arg {
-                    TypeRef::Owned(v) => Some(TypeRef::Pointer(v)),
+                    TypeRef::Owned(v) if name == "Pointer" => {
+                        Some(TypeRef::Pointer(v))
+                    }
+                    TypeRef::Owned(v) => Some(TypeRef::NonNullPointer(v)),
                     _ => {
                         self.state.diagnostics.invalid_c_type(
                             &format_type(self.db(), arg),
@@ -796,6 +877,12 @@ impl<'a> CheckTypeSignature<'a> {
     }
 
     fn check_closure_type(&mut self, node: &hir::ClosureType) {
+        for param in &node.type_parameters {
+            for node in &param.requirements {
+                self.check_type_name(node);
+            }
+        }
+
         for node in &node.arguments {
             self.check(node);
         }
@@ -883,7 +970,21 @@ pub(crate) fn define_type_bounds(
     class: ClassId,
     nodes: &mut [hir::TypeBound],
 ) -> TypeBounds {
-    let mut bounds = TypeBounds::new();
+    extend_type_bounds(state, module, class, TypeBounds::new(), nodes)
+}
+
+/// Adds the bounds of `nodes` to an existing set of bounds.
+///
+/// This is used for bounds defined on individual methods, which build upon the
+/// bounds (if any) of the surrounding `impl` block.
+pub(crate) fn extend_type_bounds(
+    state: &mut State,
+    module: ModuleId,
+    class: ClassId,
+    mut bounds: TypeBounds,
+    nodes: &mut [hir::TypeBound],
+) -> TypeBounds {
+    let mut defined = Vec::new();
 
     for bound in nodes {
         let name = &bound.name.name;
@@ -899,7 +1000,7 @@ pub(crate) fn define_type_bounds(
             continue;
         };
 
-        if bounds.get(param).is_some() {
+        if defined.contains(&param) {
             state.diagnostics.error(
                 DiagnosticId::DuplicateSymbol,
                 format!(
@@ -914,7 +1015,8 @@ pub(crate) fn define_type_bounds(
         }
 
         let mut reqs = Vec::new();
-        let new_param = param.clone_for_bound(&mut state.db);
+        let new_param =
+            bounds.get(param).unwrap_or(param).clone_for_bound(&mut state.db);
 
         for req in &mut bound.requirements {
             let rules = Rules::default();
@@ -937,6 +1039,7 @@ pub(crate) fn define_type_bounds(
 
         new_param.add_requirements(&mut state.db, reqs);
         bounds.set(param, new_param);
+        defined.push(param);
     }
 
     bounds
@@ -1304,6 +1407,7 @@ mod tests {
         let self_type = TypeId::ClassInstance(ClassInstance::new(int));
         let module = module_type(&mut state, "foo");
         let mut node = hir::Type::Closure(Box::new(hir::ClosureType {
+            type_parameters: Vec::new(),
             arguments: Vec::new(),
             return_type: None,
             location: cols(1, 1),
@@ -97,12 +97,29 @@ pub(crate) fn run(arguments: &[String]) -> Result<i32, Error> {
         "Display the time spent compiling code",
         "basic,full",
     );
+    options.optflag(
+        "",
+        "stats",
+        "Display statistics about the memory used for type information",
+    );
     options.optopt(
         "",
         "threads",
         "The number of threads to use for parallel compilation",
         "NUM",
     );
+    options.optopt(
+        "",
+        "specialization-limit",
+        "The maximum number of specializations of a generic type or method",
+        "NUM",
+    );
+    options.optopt(
+        "",
+        "inline-threshold",
+        "The maximum weight of a method before inlining into it stops",
+        "NUM",
+    );
     options.optopt(
         "",
         "linker",
@@ -188,6 +205,30 @@ pub(crate) fn run(arguments: &[String]) -> Result<i32, Error> {
         };
     }
 
+    if let Some(val) = matches.opt_str("specialization-limit") {
+        match val.parse::<usize>() {
+            Ok(0) | Err(_) => {
+                return Err(Error::from(format!(
+                    "'{}' isn't a valid specialization limit",
+                    val
+                )));
+            }
+            Ok(n) => config.specialization_limits.specializations = n,
+        };
+    }
+
+    if let Some(val) = matches.opt_str("inline-threshold") {
+        match val.parse::<u16>() {
+            Ok(n) => config.inline_policy.threshold = n,
+            Err(_) => {
+                return Err(Error::from(format!(
+                    "'{}' isn't a valid inline threshold",
+                    val
+                )));
+            }
+        };
+    }
+
     if let Some(val) = matches.opt_str("linker") {
         config.linker = Linker::parse(&val).ok_or_else(|| {
             Error::from(format!("'{}' isn't a valid linker", val))
@@ -230,6 +271,10 @@ pub(crate) fn run(arguments: &[String]) -> Result<i32, Error> {
         _ => {}
     }
 
+    if matches.opt_present("stats") {
+        compiler.print_statistics();
+    }
+
     match result {
         Ok(_) => Ok(0),
         Err(CompileError::Invalid) => Ok(1),
@@ -0,0 +1,161 @@
+//! Rendering of error messages produced by the runtime.
+//!
+//! Errors produced by the runtime itself (e.g. when dropping a value that's
+//! still referenced) are described using the `Error` type, rather than being
+//! formatted in place. The `Error` is then turned into a `String` using the
+//! current renderer.
+//!
+//! By default messages are rendered in English. Embedders can override (some
+//! of) these messages, such as to localize them, by installing a custom
+//! renderer using `set_renderer()`. Such a renderer receives the structured
+//! error along with its arguments, and may return `None` to fall back to the
+//! default message.
+//!
+//! Allocation failures aren't covered by this module, as rendering their
+//! message would itself require allocating memory.
+use std::fmt;
+use std::sync::RwLock;
+
+/// A function used for rendering error messages.
+pub type Renderer = fn(&Error) -> Option<String>;
+
+/// The custom renderer to use, if any.
+static RENDERER: RwLock<Option<Renderer>> = RwLock::new(None);
+
+/// An error produced by the runtime, along with the data needed to render it.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error<'a> {
+    /// A value is dropped while references to it still exist.
+    ReferenceCount { class: &'a str, references: u32 },
+
+    /// Random bytes couldn't be generated.
+    RandomBytes { error: &'a str },
+
+    /// The first line of a stack trace, shown when a process panics.
+    StackTraceHeader,
+
+    /// A single frame in a stack trace for which the source location is known.
+    StackFrame { path: &'a str, line: i64, name: &'a str },
+
+    /// A single frame in a stack trace without a known source location.
+    UnknownStackFrame { name: &'a str },
+
+    /// A process panicked with the given message.
+    Panic { class: &'a str, process: usize, message: &'a str },
+
+    /// A Rust panic occurred while running a process, terminating just that
+    /// process.
+    Crash { class: &'a str, process: usize, message: &'a str },
+}
+
+impl<'a> fmt::Display for Error<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ReferenceCount { class, references } => write!(
+                f,
+                "can't drop a value of type '{}' as it still has {} \
+                reference(s)",
+                class, references
+            ),
+            Error::RandomBytes { error } => f.write_str(error),
+            Error::StackTraceHeader => {
+                f.write_str("Stack trace (the most recent call comes last):")
+            }
+            Error::StackFrame { path, line, name } => {
+                write!(f, "  {}:{} in {}", path, line, name)
+            }
+            Error::UnknownStackFrame { name } => write!(f, "  ?? in {}", name),
+            Error::Panic { class, process, message } => write!(
+                f,
+                "Process '{}' ({:#x}) panicked: {}",
+                class, process, message
+            ),
+            Error::Crash { class, process, message } => write!(
+                f,
+                "Process '{}' ({:#x}) crashed due to an internal error: {}",
+                class, process, message
+            ),
+        }
+    }
+}
+
+/// Installs a custom renderer for error messages.
+///
+/// The renderer applies to all errors rendered after this function returns,
+/// and replaces any previously installed renderer.
+pub fn set_renderer(renderer: Renderer) {
+    *RENDERER.write().unwrap() = Some(renderer);
+}
+
+/// Removes the custom renderer, restoring the default messages.
+pub fn reset_renderer() {
+    *RENDERER.write().unwrap() = None;
+}
+
+/// Renders an error into a message, using the custom renderer if one is
+/// installed.
+pub(crate) fn render(error: &Error) -> String {
+    // If the lock is poisoned the renderer panicked. In that case we fall back
+    // to the default message, as a runtime error is likely already in progress.
+    let custom = RENDERER.read().ok().and_then(|v| *v);
+
+    custom.and_then(|render| render(error)).unwrap_or_else(|| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(error: &Error) -> Option<String> {
+        match error {
+            Error::ReferenceCount { class, references } => Some(format!(
+                "kan waarde van type '{}' niet opruimen, er zijn nog {} \
+                referentie(s)",
+                class, references
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(&Error::ReferenceCount { class: "Foo", references: 2 }),
+            "can't drop a value of type 'Foo' as it still has 2 reference(s)"
+        );
+        assert_eq!(
+            render(&Error::StackFrame { path: "a.inko", line: 4, name: "foo" }),
+            "  a.inko:4 in foo"
+        );
+        assert_eq!(
+            render(&Error::Panic {
+                class: "Main",
+                process: 16,
+                message: "oops"
+            }),
+            "Process 'Main' (0x10) panicked: oops"
+        );
+    }
+
+    #[test]
+    fn test_render_with_custom_renderer() {
+        set_renderer(custom);
+
+        let error = Error::ReferenceCount { class: "Foo", references: 2 };
+        let drop = render(&error);
+        let other = render(&Error::UnknownStackFrame { name: "foo" });
+
+        reset_renderer();
+
+        assert_eq!(
+            drop,
+            "kan waarde van type 'Foo' niet opruimen, er zijn nog 2 \
+            referentie(s)"
+        );
+        assert_eq!(other, "  ?? in foo");
+        assert_eq!(
+            render(&error),
+            "can't drop a value of type 'Foo' as it still has 2 reference(s)"
+        );
+    }
+}
@@ -8,6 +8,7 @@ mod macros;
 mod arc_without_weak;
 mod config;
 mod context;
+pub mod error_messages;
 mod mem;
 mod memory_map;
 mod network_poller;
@@ -20,5 +21,7 @@ mod socket;
 mod stack;
 mod state;
 
+pub use scheduler::observer;
+
 #[cfg(test)]
 pub mod test;
@@ -14,6 +14,13 @@ use std::ptr::{drop_in_place, null_mut, write, NonNull};
 use std::sync::atomic::Ordering;
 use std::sync::{Mutex, MutexGuard};
 
+#[cfg(feature = "introspection")]
+use std::array::from_fn;
+#[cfg(feature = "introspection")]
+use std::collections::HashSet;
+#[cfg(feature = "introspection")]
+use std::sync::OnceLock;
+
 const INKO_SYMBOL_IDENTIFIER: &str = "_IM_";
 
 /// The type signature for Inko's async methods defined in the native code.
@@ -40,6 +47,103 @@ pub struct StackFrame {
     pub line: i64,
 }
 
+/// The number of bits of a hash used to select a shard of the process
+/// registry.
+#[cfg(feature = "introspection")]
+const REGISTRY_SHARD_BITS: u32 = 6;
+
+/// The number of shards of the process registry.
+#[cfg(feature = "introspection")]
+const REGISTRY_SHARDS: usize = 1 << REGISTRY_SHARD_BITS;
+
+/// The registry of all processes that are still alive.
+#[cfg(feature = "introspection")]
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+/// A registry of all processes that are still alive, used for inspecting
+/// running programs.
+///
+/// Processes are added when they're allocated and removed just before they're
+/// deallocated, so holding the lock of a shard ensures the processes in that
+/// shard remain valid.
+///
+/// The registry is split into shards based on the address of a process, such
+/// that threads spawning and terminating processes rarely contend for the same
+/// lock. Even so, taking a lock for every process is a cost not every program
+/// should pay, so the registry is only available when building the runtime
+/// with the `introspection` feature.
+#[cfg(feature = "introspection")]
+struct Registry {
+    shards: [Mutex<HashSet<ProcessPointer>>; REGISTRY_SHARDS],
+}
+
+#[cfg(feature = "introspection")]
+impl Registry {
+    fn new() -> Self {
+        Self { shards: from_fn(|_| Mutex::new(HashSet::new())) }
+    }
+
+    fn global() -> &'static Registry {
+        REGISTRY.get_or_init(Registry::new)
+    }
+
+    fn shard(
+        &self,
+        process: ProcessPointer,
+    ) -> &Mutex<HashSet<ProcessPointer>> {
+        // Processes of the same class are allocated at addresses that are a
+        // multiple of their size apart, so we hash the address (using
+        // Fibonacci hashing) instead of using its lower bits directly.
+        let hash = process
+            .identifier()
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15_u64 as usize);
+
+        &self.shards[hash >> (usize::BITS - REGISTRY_SHARD_BITS)]
+    }
+
+    fn register(&self, process: ProcessPointer) {
+        self.shard(process).lock().unwrap().insert(process);
+    }
+
+    fn unregister(&self, process: ProcessPointer) {
+        self.shard(process).lock().unwrap().remove(&process);
+    }
+
+    fn list(&self) -> Vec<ProcessInfo> {
+        let mut list = Vec::new();
+
+        for shard in &self.shards {
+            list.extend(shard.lock().unwrap().iter().map(|p| p.info()));
+        }
+
+        list
+    }
+}
+
+/// A summary of the state of a process, used for inspecting running programs.
+///
+/// Processes are scheduled onto either the main thread or the shared pool of
+/// process threads, which is covered by `main`. Processes don't have their own
+/// heaps or bytecode, as they allocate memory using the system allocator and
+/// run native code, so there's no heap or instruction data to report.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ProcessInfo {
+    /// The identifier of the process.
+    pub id: usize,
+
+    /// The name of the class of the process.
+    pub name: String,
+
+    /// A description of the status of the process.
+    pub status: &'static str,
+
+    /// The number of messages waiting to be processed.
+    pub messages: usize,
+
+    /// A flag indicating if this is the main process.
+    pub main: bool,
+}
+
 /// A message sent between two processes.
 pub(crate) struct Message {
     /// A pointer to the method to run.
@@ -178,6 +282,26 @@ impl ProcessStatus {
         self.bit_is_set(Self::TIMEOUT_EXPIRED)
     }
 
+    /// Returns a human readable description of the status.
+    ///
+    /// If multiple bits are set, the most specific status is returned.
+    #[cfg(feature = "introspection")]
+    fn describe(&self) -> &'static str {
+        if self.is_waiting_for_io() {
+            "waiting for IO"
+        } else if self.is_waiting_for_value() {
+            "waiting for a value"
+        } else if self.bit_is_set(Self::SLEEPING) {
+            "sleeping"
+        } else if self.is_running() {
+            "running"
+        } else if self.is_waiting_for_message() {
+            "waiting for a message"
+        } else {
+            "scheduled"
+        }
+    }
+
     fn update_bits(&mut self, mask: u8, enable: bool) {
         self.bits = if enable { self.bits | mask } else { self.bits & !mask };
     }
@@ -410,6 +534,9 @@ pub struct Process {
 
 impl Process {
     pub(crate) fn drop_and_deallocate(ptr: ProcessPointer) {
+        #[cfg(feature = "introspection")]
+        Registry::global().unregister(ptr);
+
         unsafe {
             let raw = ptr.as_ptr();
             let layout = header_of(raw).class.instance_layout();
@@ -450,7 +577,23 @@ impl Process {
         init!(obj.stack => ManuallyDrop::new(stack));
         init!(obj.state => Mutex::new(state));
 
-        unsafe { ProcessPointer::new(ptr) }
+        let process = unsafe { ProcessPointer::new(ptr) };
+
+        #[cfg(feature = "introspection")]
+        Registry::global().register(process);
+        process
+    }
+
+    /// Returns a summary of every process that's still alive.
+    ///
+    /// If the runtime is built without the `introspection` feature, processes
+    /// aren't tracked and a `None` is returned.
+    pub(crate) fn list() -> Option<Vec<ProcessInfo>> {
+        #[cfg(feature = "introspection")]
+        return Some(Registry::global().list());
+
+        #[cfg(not(feature = "introspection"))]
+        None
     }
 
     /// Returns a new Process acting as the main process.
@@ -552,6 +695,20 @@ impl Process {
         }
     }
 
+    /// Returns a summary of the current state of this process.
+    #[cfg(feature = "introspection")]
+    pub(crate) fn info(&self) -> ProcessInfo {
+        let state = self.state.lock().unwrap();
+
+        ProcessInfo {
+            id: self as *const _ as usize,
+            name: self.header.class.name.clone(),
+            status: state.status.describe(),
+            messages: state.mailbox.messages.len(),
+            main: state.status.is_main(),
+        }
+    }
+
     pub(crate) fn state(&self) -> MutexGuard<ProcessState> {
         self.state.lock().unwrap()
     }
@@ -651,7 +808,7 @@ impl Process {
 
 /// A pointer to a process.
 #[repr(transparent)]
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct ProcessPointer(NonNull<Process>);
 
 unsafe impl Sync for ProcessPointer {}
@@ -1093,6 +1250,72 @@ mod tests {
         assert!(process.stack_pointer.is_null());
     }
 
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn test_process_status_describe() {
+        let mut status = ProcessStatus::new();
+
+        assert_eq!(status.describe(), "scheduled");
+
+        status.set_waiting_for_message(true);
+        assert_eq!(status.describe(), "waiting for a message");
+
+        status.set_running(true);
+        assert_eq!(status.describe(), "running");
+
+        status.set_waiting_for_io(true);
+        assert_eq!(status.describe(), "waiting for IO");
+    }
+
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn test_process_info() {
+        let proc_class = empty_process_class("A");
+        let stack = Stack::new(32, page_size());
+        let mut process = OwnedProcess::new(Process::alloc(*proc_class, stack));
+
+        process.send_message(Message { method, data: null_mut() });
+        process.send_message(Message { method, data: null_mut() });
+
+        assert_eq!(
+            process.info(),
+            ProcessInfo {
+                id: process.identifier(),
+                name: "A".to_string(),
+                status: "scheduled",
+                messages: 2,
+                main: false,
+            }
+        );
+    }
+
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn test_process_list() {
+        let proc_class = empty_process_class("A");
+        let stack = Stack::new(32, page_size());
+        let process = OwnedProcess::new(Process::alloc(*proc_class, stack));
+
+        assert!(Process::list().unwrap().contains(&process.info()));
+    }
+
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn test_registry() {
+        let proc_class = empty_process_class("A");
+        let stack = Stack::new(32, page_size());
+        let process = OwnedProcess::new(Process::alloc(*proc_class, stack));
+        let registry = Registry::new();
+
+        assert!(registry.list().is_empty());
+
+        registry.register(*process);
+        assert_eq!(registry.list(), vec![process.info()]);
+
+        registry.unregister(*process);
+        assert!(registry.list().is_empty());
+    }
+
     #[test]
     fn test_process_finish_message() {
         let proc_class = empty_process_class("A");
@@ -62,6 +62,10 @@ pub unsafe extern "system" fn inko_runtime_new(
     // does for us when compiling an executable.
     signal_sched::block_all();
 
+    // Rust panics (e.g. due to a bug in the runtime) can't unwind through the
+    // stacks of processes, so we report them the same way as Inko panics.
+    process::install_panic_hook();
+
     // Configure the TLS provider. This must be done once before we start the
     // program.
     rustls::crypto::ring::default_provider()
@@ -1,3 +1,4 @@
+use crate::error_messages::{render, Error};
 use crate::mem::header_of;
 use crate::process::ProcessPointer;
 use crate::runtime::process::panic;
@@ -13,10 +14,10 @@ pub unsafe extern "system" fn inko_reference_count_error(
 
     panic(
         process,
-        &format!(
-            "can't drop a value of type '{}' as it still has {} reference(s)",
-            &header.class.name, header.references
-        ),
+        &render(&Error::ReferenceCount {
+            class: &header.class.name,
+            references: header.references,
+        }),
     );
 }
 
@@ -24,7 +25,8 @@ pub unsafe extern "system" fn inko_reference_count_error(
 pub unsafe extern "system" fn inko_alloc_error(size: u64) -> ! {
     // When running out of memory, chances are we don't have enough to produce a
     // stack trace or fancy error messages, so we try to keep things as
-    // basic/simple as possible.
+    // basic/simple as possible. For the same reason this message isn't passed
+    // to custom renderers, as those produce a heap allocated String.
     eprintln!("failed to allocate {} bytes", size);
     exit(PANIC_STATUS);
 }
@@ -1,14 +1,16 @@
 use crate::context;
+use crate::error_messages::{render, Error};
 use crate::mem::{ClassPointer, String as InkoString};
 use crate::process::{
-    Message, NativeAsyncMethod, Process, ProcessPointer, RescheduleRights,
-    StackFrame,
+    Message, NativeAsyncMethod, Process, ProcessInfo, ProcessPointer,
+    RescheduleRights, StackFrame,
 };
-use crate::scheduler::process::Action;
+use crate::scheduler::process::{Action, CURRENT_PROCESS};
 use crate::scheduler::timeouts::Timeout;
 use crate::state::State;
-use std::fmt::Write as _;
+use std::panic::{set_hook, take_hook};
 use std::process::exit;
+use std::ptr::null_mut;
 use std::str;
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::time::Duration;
@@ -26,34 +28,98 @@ pub(crate) const PANIC_STATUS: i32 = 101;
 #[inline(never)]
 #[cold]
 pub(crate) fn panic(process: ProcessPointer, message: &str) -> ! {
-    let mut buffer = String::new();
+    terminate(
+        process,
+        Error::Panic {
+            class: &process.header.class.name,
+            process: process.identifier(),
+            message,
+        },
+    );
+}
 
-    buffer.push_str("Stack trace (the most recent call comes last):");
+/// Terminates the current program in response to a Rust panic that occurred
+/// while running a process.
+///
+/// Such panics can't unwind through the stack of a process, and the runtime is
+/// compiled with `panic = "abort"`, so it isn't possible to terminate just the
+/// process that panicked. Instead we treat the panic the same way as an Inko
+/// panic. This also ensures processes waiting for the process that panicked
+/// don't hang forever.
+#[inline(never)]
+#[cold]
+pub(crate) fn crash(process: ProcessPointer, message: &str) -> ! {
+    terminate(
+        process,
+        Error::Crash {
+            class: &process.header.class.name,
+            process: process.identifier(),
+            message,
+        },
+    );
+}
+
+fn terminate(process: ProcessPointer, error: Error) -> ! {
+    let mut buffer = render(&Error::StackTraceHeader);
 
     for frame in process.stacktrace() {
-        let _ = if !frame.path.is_empty() && frame.line > 0 {
-            write!(
-                buffer,
-                "\n  {}:{} in {}",
-                frame.path, frame.line, frame.name,
-            )
+        let error = if !frame.path.is_empty() && frame.line > 0 {
+            Error::StackFrame {
+                path: &frame.path,
+                line: frame.line,
+                name: &frame.name,
+            }
         } else {
-            write!(buffer, "\n  ?? in {}", frame.name)
+            Error::UnknownStackFrame { name: &frame.name }
         };
+
+        buffer.push('\n');
+        buffer.push_str(&render(&error));
     }
 
-    let _ = write!(
-        buffer,
-        "\nProcess '{}' ({:#x}) panicked: {}",
-        process.header.class.name,
-        process.identifier(),
-        message
-    );
+    buffer.push('\n');
+    buffer.push_str(&render(&error));
 
     eprintln!("{}", buffer);
     exit(PANIC_STATUS);
 }
 
+/// Installs a panic hook that reports Rust panics that occur while running a
+/// process using `crash()`.
+///
+/// This terminates the entire program rather than just the process that
+/// panicked, as panics aren't isolated to individual processes (see `crash()`
+/// for more details).
+///
+/// Panics that occur outside of a process (e.g. in the timeout worker) are
+/// handled by the default panic hook.
+pub(crate) fn install_panic_hook() {
+    let default = take_hook();
+
+    set_hook(Box::new(move |info| {
+        let process = CURRENT_PROCESS.get();
+
+        if process.is_null() {
+            return default(info);
+        }
+
+        let payload = info.payload();
+        let message = if let Some(&msg) = payload.downcast_ref::<&str>() {
+            msg
+        } else if let Some(msg) = payload.downcast_ref::<String>() {
+            msg.as_str()
+        } else {
+            "unknown error"
+        };
+        let message = match info.location() {
+            Some(loc) => format!("{}, {}:{}", message, loc.file(), loc.line()),
+            None => message.to_string(),
+        };
+
+        crash(unsafe { ProcessPointer::new(process) }, &message);
+    }));
+}
+
 #[no_mangle]
 pub unsafe extern "system" fn inko_process_panic(
     process: ProcessPointer,
@@ -197,6 +263,73 @@ pub unsafe extern "system" fn inko_process_stacktrace_drop(
     drop(Box::from_raw(trace));
 }
 
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_list() -> *mut Vec<ProcessInfo> {
+    Process::list()
+        .map(|list| Box::into_raw(Box::new(list)))
+        .unwrap_or_else(null_mut)
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_list_size(
+    list: *const Vec<ProcessInfo>,
+) -> i64 {
+    (*list).len() as i64
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_list_drop(
+    list: *mut Vec<ProcessInfo>,
+) {
+    drop(Box::from_raw(list));
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_info_id(
+    list: *const Vec<ProcessInfo>,
+    index: i64,
+) -> i64 {
+    (*list).get_unchecked(index as usize).id as i64
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_info_name(
+    state: *const State,
+    list: *const Vec<ProcessInfo>,
+    index: i64,
+) -> *const InkoString {
+    let val = &(*list).get_unchecked(index as usize).name;
+
+    InkoString::alloc((*state).string_class, val.clone())
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_info_status(
+    state: *const State,
+    list: *const Vec<ProcessInfo>,
+    index: i64,
+) -> *const InkoString {
+    let val = (*list).get_unchecked(index as usize).status;
+
+    InkoString::alloc((*state).string_class, val.to_string())
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_info_messages(
+    list: *const Vec<ProcessInfo>,
+    index: i64,
+) -> i64 {
+    (*list).get_unchecked(index as usize).messages as i64
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn inko_process_info_main(
+    list: *const Vec<ProcessInfo>,
+    index: i64,
+) -> bool {
+    (*list).get_unchecked(index as usize).main
+}
+
 #[no_mangle]
 pub unsafe extern "system" fn inko_process_start_blocking(
     process: ProcessPointer,
@@ -284,3 +417,44 @@ pub unsafe extern "system" fn inko_process_reschedule_for_value(
         process.thread().schedule(waiter);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{empty_process_class, new_process};
+    use std::env;
+    use std::process::Command;
+
+    /// The environment variable used to run a test in a child process.
+    const CHILD_VAR: &str = "INKO_RT_TEST_CHILD";
+
+    #[test]
+    fn test_crash_with_waiting_process() {
+        if env::var_os(CHILD_VAR).is_some() {
+            let class = empty_process_class("A");
+            let process = new_process(*class);
+            let waiter = new_process(*class);
+
+            waiter.state().waiting_for_value(None);
+            install_panic_hook();
+            CURRENT_PROCESS.set(process.as_ptr());
+            panic!("oops");
+        }
+
+        // The crash terminates the program, so we run the test in a separate
+        // process.
+        let output = Command::new(env::current_exe().unwrap())
+            .args([
+                "runtime::process::tests::test_crash_with_waiting_process",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(PANIC_STATUS));
+        assert!(stderr.contains("crashed due to an internal error: oops"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error_messages::{render, Error};
 use crate::mem::ByteArray;
 use crate::process::ProcessPointer;
 use crate::runtime::process::panic;
@@ -51,7 +52,10 @@ pub unsafe extern "system" fn inko_random_bytes(
     let mut bytes = vec![0; size as usize];
 
     if let Err(err) = (*rng).try_fill(&mut bytes[..]) {
-        panic(process, &err.to_string());
+        panic(
+            process,
+            &render(&Error::RandomBytes { error: &err.to_string() }),
+        );
     }
 
     ByteArray::alloc((*state).byte_array_class, bytes)
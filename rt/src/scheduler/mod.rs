@@ -1,3 +1,4 @@
+pub mod observer;
 pub mod process;
 pub mod signal;
 pub mod timeout_worker;
@@ -0,0 +1,73 @@
+//! Instrumentation of the process scheduler.
+//!
+//! Embedders can register an `Observer` before starting the runtime, which is
+//! then notified of scheduling events such as processes being scheduled or
+//! threads going to sleep. This makes it possible to implement custom tracing
+//! or metrics without patching the runtime.
+//!
+//! Only a single observer can be registered, and it can't be removed. When no
+//! observer is registered, the cost of a notification is a single atomic load.
+//!
+//! Processes and threads are identified using the same values as shown when a
+//! process panics (for processes), and the index of the thread in the thread
+//! pool (for threads).
+use std::sync::OnceLock;
+
+/// The observer to notify of scheduling events, if any.
+static OBSERVER: OnceLock<Box<dyn Observer>> = OnceLock::new();
+
+/// A type that's notified of scheduling events.
+///
+/// Observers are called from the scheduler threads while they are scheduling
+/// work, so implementations should be fast and must not block. All methods
+/// default to doing nothing, so implementations only need to implement the
+/// methods they're interested in.
+pub trait Observer: Send + Sync {
+    /// Called when a process is scheduled onto a queue.
+    fn process_scheduled(&self, _process: usize) {}
+
+    /// Called when a process yields control back to the thread running it.
+    fn process_descheduled(&self, _process: usize, _thread: usize) {}
+
+    /// Called when a thread steals a process from the queue of another thread.
+    fn process_migrated(&self, _process: usize, _from: usize, _to: usize) {}
+
+    /// Called when a process terminates, before its memory is released.
+    fn process_terminated(&self, _process: usize) {}
+
+    /// Called when a thread goes to sleep because it ran out of work.
+    fn thread_parked(&self, _thread: usize) {}
+
+    /// Called when a sleeping thread wakes up.
+    fn thread_unparked(&self, _thread: usize) {}
+}
+
+/// Registers the observer to notify of scheduling events.
+///
+/// This function must be called before starting the runtime. If an observer is
+/// already registered, the given observer is returned as an `Err`.
+pub fn register(observer: Box<dyn Observer>) -> Result<(), Box<dyn Observer>> {
+    OBSERVER.set(observer)
+}
+
+/// Notifies the registered observer, if there is any.
+#[inline(always)]
+pub(crate) fn notify<F: FnOnce(&dyn Observer)>(func: F) {
+    if let Some(observer) = OBSERVER.get() {
+        func(observer.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{observe, observed, ObservedEvent};
+
+    #[test]
+    fn test_notify() {
+        observe();
+        notify(|o| o.thread_parked(usize::MAX));
+
+        assert!(observed(ObservedEvent::Parked(usize::MAX)));
+    }
+}
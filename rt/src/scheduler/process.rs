@@ -2,6 +2,7 @@
 use crate::arc_without_weak::ArcWithoutWeak;
 use crate::context;
 use crate::process::{Process, ProcessPointer, Task};
+use crate::scheduler::observer::notify;
 use crate::scheduler::pin_thread_to_core;
 use crate::stack::StackPool;
 use crate::state::State;
@@ -254,6 +255,8 @@ impl Thread {
             return;
         }
 
+        notify(|o| o.process_scheduled(process.identifier()));
+
         if self.work.len() > 1 && self.pool.sleeping() > 0 {
             self.pool.notify_one();
         }
@@ -458,12 +461,27 @@ impl Thread {
                 let len = steal_from.queue.len();
                 let steal = min(len / 2, STEAL_LIMIT);
 
+                notify(|o| {
+                    o.process_migrated(initial.identifier(), index, self.id)
+                });
+
                 for _ in 0..steal {
                     if let Some(process) = steal_from.queue.pop() {
+                        // If our queue is full the process ends up in the
+                        // global queue, in which case it's reported as
+                        // scheduled instead of migrated.
                         if let Err(process) = self.work.push(process) {
                             self.pool.schedule(process);
                             break;
                         }
+
+                        notify(|o| {
+                            o.process_migrated(
+                                process.identifier(),
+                                index,
+                                self.id,
+                            )
+                        });
                     } else {
                         break;
                     }
@@ -513,6 +531,7 @@ impl Thread {
         }
 
         self.pool.sleeping.fetch_add(1, Ordering::AcqRel);
+        notify(|o| o.thread_parked(self.id));
 
         // We don't handle spurious wakeups here because:
         //
@@ -523,6 +542,7 @@ impl Thread {
         let _result = self.pool.sleeping_cvar.wait(global).unwrap();
 
         self.pool.sleeping.fetch_sub(1, Ordering::AcqRel);
+        notify(|o| o.thread_unparked(self.id));
     }
 
     fn sleep_main(&self) {
@@ -532,7 +552,11 @@ impl Thread {
             return;
         }
 
+        notify(|o| o.thread_parked(self.id));
+
         let _result = self.pool.main_thread_cvar.wait(lock).unwrap();
+
+        notify(|o| o.thread_unparked(self.id));
     }
 
     /// Runs a process by calling back into the native code.
@@ -580,6 +604,8 @@ impl Thread {
             CURRENT_PROCESS.set(null_mut());
         }
 
+        notify(|o| o.process_descheduled(process.identifier(), self.id));
+
         // It's possible that we finish work with an uneven number of calls to
         // `start_blocking` and `stop_blocking`, such as when the developer
         // didn't pay attention to the documentation telling them to do just
@@ -598,6 +624,8 @@ impl Thread {
                     state.terminate();
                 }
 
+                notify(|o| o.process_terminated(process.identifier()));
+
                 if let Some(stack) = process.take_stack() {
                     self.stacks.add(stack);
                 }
@@ -900,6 +928,7 @@ impl Pool {
     fn schedule_main(&self, process: ProcessPointer) {
         let mut queue = self.main_thread_queue.lock().unwrap();
 
+        notify(|o| o.process_scheduled(process.identifier()));
         *queue = Some(process);
         self.main_thread_cvar.notify_one();
     }
@@ -907,6 +936,7 @@ impl Pool {
     fn schedule(&self, process: ProcessPointer) {
         let mut queue = self.global.lock().unwrap();
 
+        notify(|o| o.process_scheduled(process.identifier()));
         queue.push(process);
 
         if self.sleeping() > 0 {
@@ -919,6 +949,12 @@ impl Pool {
             return;
         }
 
+        notify(|o| {
+            for process in &processes {
+                o.process_scheduled(process.identifier());
+            }
+        });
+
         let mut queue = self.global.lock().unwrap();
 
         queue.append(&mut processes);
@@ -1062,7 +1098,8 @@ impl Scheduler {
 mod tests {
     use super::*;
     use crate::test::{
-        empty_process_class, new_process, new_process_with_message, setup,
+        empty_process_class, new_process, new_process_with_message, observe,
+        observed, observed_len, observed_since, setup, ObservedEvent,
     };
     use std::thread::sleep;
 
@@ -1081,10 +1118,12 @@ mod tests {
         let scheduler = Scheduler::new(1, 1, 32);
         let mut thread = Thread::new(1, 0, scheduler.pool.clone());
 
+        observe();
         thread.schedule(process);
 
         assert_eq!(thread.work.len(), 1);
         assert!(scheduler.pool.global.lock().unwrap().is_empty());
+        assert!(observed(ObservedEvent::Scheduled(process.identifier())));
     }
 
     #[test]
@@ -1134,11 +1173,15 @@ mod tests {
         let mut thread0 = Thread::new(1, 0, state.scheduler.pool.clone());
         let mut thread1 = Thread::new(2, 0, state.scheduler.pool.clone());
 
+        observe();
         thread1.schedule(process);
         thread0.run(&state);
 
         assert_eq!(thread0.work.len(), 0);
         assert_eq!(thread1.work.len(), 0);
+        assert!(observed(ObservedEvent::Migrated(process.identifier(), 2, 1)));
+        assert!(observed(ObservedEvent::Descheduled(process.identifier(), 1)));
+        assert!(observed(ObservedEvent::Terminated(process.identifier())));
     }
 
     #[test]
@@ -1156,6 +1199,54 @@ mod tests {
         assert!(state.scheduler.pool.global.lock().unwrap().is_empty());
     }
 
+    #[test]
+    fn test_thread_steal_from_thread_with_full_local_queue() {
+        let class = empty_process_class("A");
+        let process = new_process(*class).take_and_forget();
+        let proc1 = new_process(*class).take_and_forget();
+        let proc2 = new_process(*class).take_and_forget();
+        let proc3 = new_process(*class).take_and_forget();
+        let state = setup();
+        let pool = &state.scheduler.pool;
+        let mut thread = Thread::new(1, 0, pool.clone());
+        let start = observed_len();
+
+        observe();
+
+        for _ in 0..LOCAL_QUEUE_CAPACITY {
+            let _ = thread.work.push(process);
+        }
+
+        for proc in [proc1, proc2, proc3] {
+            let _ = pool.threads[2].queue.push(proc);
+        }
+
+        let stolen = thread.steal_from_thread();
+        let global_len = pool.global.lock().unwrap().len();
+
+        // The same process is pushed many times, so we clear the queues before
+        // setting any assertions that may fail.
+        pool.global.lock().unwrap().clear();
+
+        while thread.work.pop().is_some() {}
+        while pool.threads[2].queue.pop().is_some() {}
+
+        assert_eq!(stolen, Some(proc1));
+        assert_eq!(global_len, 1);
+        assert!(observed_since(
+            start,
+            ObservedEvent::Migrated(proc1.identifier(), 2, 1)
+        ));
+        assert!(observed_since(
+            start,
+            ObservedEvent::Scheduled(proc2.identifier())
+        ));
+        assert!(!observed_since(
+            start,
+            ObservedEvent::Migrated(proc2.identifier(), 2, 1)
+        ));
+    }
+
     #[test]
     fn test_thread_steal_from_global_with_full_local_queue() {
         let class = empty_process_class("A");
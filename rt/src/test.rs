@@ -2,11 +2,16 @@
 use crate::config::Config;
 use crate::mem::{Class, ClassPointer};
 use crate::process::{Message, NativeAsyncMethod, Process, ProcessPointer};
+use crate::scheduler::observer::{register, Observer};
 use crate::stack::Stack;
 use crate::state::{MethodCounts, RcState, State};
 use rustix::param::page_size;
 use std::mem::{forget, size_of};
 use std::ops::{Deref, DerefMut, Drop};
+use std::sync::Mutex;
+
+/// The scheduler observer shared by all tests.
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
 
 /// Processes normally drop themselves when they finish running. But in tests we
 /// don't actually run a process.
@@ -114,3 +119,77 @@ pub(crate) fn empty_process_class(name: &str) -> OwnedClass {
         0,
     ))
 }
+
+/// A scheduling event recorded by the scheduler observer used in tests.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum ObservedEvent {
+    Scheduled(usize),
+    Descheduled(usize, usize),
+    Migrated(usize, usize, usize),
+    Terminated(usize),
+    Parked(usize),
+    Unparked(usize),
+}
+
+/// A scheduler observer that records the events it's notified of.
+pub(crate) struct Recorder(Mutex<Vec<ObservedEvent>>);
+
+impl Recorder {
+    fn record(&self, event: ObservedEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+impl Observer for &'static Recorder {
+    fn process_scheduled(&self, process: usize) {
+        self.record(ObservedEvent::Scheduled(process));
+    }
+
+    fn process_descheduled(&self, process: usize, thread: usize) {
+        self.record(ObservedEvent::Descheduled(process, thread));
+    }
+
+    fn process_migrated(&self, process: usize, from: usize, to: usize) {
+        self.record(ObservedEvent::Migrated(process, from, to));
+    }
+
+    fn process_terminated(&self, process: usize) {
+        self.record(ObservedEvent::Terminated(process));
+    }
+
+    fn thread_parked(&self, thread: usize) {
+        self.record(ObservedEvent::Parked(thread));
+    }
+
+    fn thread_unparked(&self, thread: usize) {
+        self.record(ObservedEvent::Unparked(thread));
+    }
+}
+
+/// Registers the scheduler observer used by tests, if this isn't done already.
+///
+/// Tests run in parallel and share the same observer, so tests should only
+/// check for the presence of the events they expect.
+pub(crate) fn observe() {
+    let _ = register(Box::new(&RECORDER));
+}
+
+/// Returns `true` if the scheduler observer recorded the given event.
+pub(crate) fn observed(event: ObservedEvent) -> bool {
+    RECORDER.0.lock().unwrap().contains(&event)
+}
+
+/// Returns the number of events recorded by the scheduler observer.
+pub(crate) fn observed_len() -> usize {
+    RECORDER.0.lock().unwrap().len()
+}
+
+/// Returns `true` if the scheduler observer recorded the given event after the
+/// first `start` events.
+///
+/// Process identifiers are addresses that may be reused, so tests that check
+/// for the absence of an event use this to ignore events recorded before the
+/// test started.
+pub(crate) fn observed_since(start: usize, event: ObservedEvent) -> bool {
+    RECORDER.0.lock().unwrap()[start..].contains(&event)
+}
@@ -0,0 +1,299 @@
+//! Validation of methods that use the C calling convention.
+//!
+//! Methods using the C calling convention (external functions and module
+//! methods defined using `fn extern`) are called from or call into C code, so
+//! their signatures must only use types that have a well-defined C
+//! representation. Without this check such problems would only surface during
+//! code generation, or worse, result in undefined behaviour at runtime.
+//!
+//! The types that can be used are foreign integers and floats, pointers,
+//! instances of extern classes, and the builtin `Int`, `Float` and `Bool`
+//! types. Methods may also return `Nil` (translated to `void`) or `Never`.
+use crate::{
+    Block, Database, MethodId, MethodKind, ModuleId, TypeId, TypeRef, BOOL_ID,
+    FLOAT_ID, INT_ID,
+};
+
+/// An error produced when checking the signature of a method using the C
+/// calling convention.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AbiError {
+    /// The method defines type parameters.
+    Generic(MethodId),
+
+    /// The type of the argument at the given index can't be used in C.
+    InvalidArgument(MethodId, usize),
+
+    /// The return type of the method can't be used in C.
+    InvalidReturnType(MethodId),
+
+    /// The method is variadic but isn't an external function.
+    VariadicMethod(MethodId),
+
+    /// The method is variadic but doesn't define any fixed arguments.
+    MissingFixedArgument(MethodId),
+}
+
+impl AbiError {
+    pub fn method(self) -> MethodId {
+        match self {
+            AbiError::Generic(id)
+            | AbiError::InvalidArgument(id, _)
+            | AbiError::InvalidReturnType(id)
+            | AbiError::VariadicMethod(id)
+            | AbiError::MissingFixedArgument(id) => id,
+        }
+    }
+}
+
+/// Returns `true` if values of the given type can be passed to and from C.
+///
+/// Types that are already invalid are considered valid, such that we don't
+/// produce errors for them twice.
+pub fn is_ffi_safe(db: &Database, typ: TypeRef) -> bool {
+    match typ {
+        TypeRef::Owned(TypeId::ClassInstance(ins)) => {
+            matches!(ins.instance_of().0, INT_ID | FLOAT_ID | BOOL_ID)
+                || typ.is_foreign_type(db)
+        }
+        TypeRef::Error => true,
+        _ => typ.is_foreign_type(db),
+    }
+}
+
+fn is_valid_return_type(db: &Database, typ: TypeRef) -> bool {
+    typ == TypeRef::nil() || typ.is_never(db) || is_ffi_safe(db, typ)
+}
+
+impl MethodId {
+    /// Checks the signature of a method using the C calling convention.
+    ///
+    /// Methods using the Inko calling convention are always valid.
+    pub fn check_abi(self, db: &Database) -> Vec<AbiError> {
+        let mut errors = Vec::new();
+
+        if !self.uses_c_calling_convention(db) {
+            return errors;
+        }
+
+        if !self.type_parameters(db).is_empty() {
+            errors.push(AbiError::Generic(self));
+        }
+
+        if self.is_variadic(db) {
+            if !matches!(self.kind(db), MethodKind::Extern) {
+                errors.push(AbiError::VariadicMethod(self));
+            } else if self.number_of_arguments(db) == 0 {
+                errors.push(AbiError::MissingFixedArgument(self));
+            }
+        }
+
+        for arg in self.arguments(db) {
+            if !is_ffi_safe(db, arg.value_type) {
+                errors.push(AbiError::InvalidArgument(self, arg.index));
+            }
+        }
+
+        if !is_valid_return_type(db, self.return_type(db)) {
+            errors.push(AbiError::InvalidReturnType(self));
+        }
+
+        errors
+    }
+}
+
+impl ModuleId {
+    /// Checks the signatures of the module methods and external functions
+    /// that use the C calling convention, returning the errors in the order
+    /// the methods are defined in.
+    pub fn check_abi(self, db: &Database) -> Vec<AbiError> {
+        let mut methods = self.methods(db);
+
+        methods.extend(self.extern_methods(db).values().cloned());
+
+        // Methods are stored in hash maps, so we sort them to ensure the errors
+        // are produced in a consistent order.
+        methods.sort_by_key(|m| (m.location(db), m.0));
+        methods.into_iter().flat_map(|m| m.check_abi(db)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        instance, new_class, new_extern_class, new_method, new_module,
+    };
+    use crate::Location;
+
+    fn method_with_arguments(
+        db: &mut Database,
+        module: ModuleId,
+        name: &str,
+        kind: MethodKind,
+        arguments: Vec<TypeRef>,
+    ) -> MethodId {
+        let id = new_method(db, module, name, kind);
+
+        for (idx, typ) in arguments.into_iter().enumerate() {
+            id.new_argument(
+                db,
+                format!("arg{}", idx),
+                typ,
+                typ,
+                Location::default(),
+            );
+        }
+
+        id.set_return_type(db, TypeRef::nil());
+        id
+    }
+
+    #[test]
+    fn test_is_ffi_safe() {
+        let mut db = Database::new();
+        let ext = new_extern_class(&mut db, "Ext");
+        let heap = new_class(&mut db, "Heap");
+
+        assert!(is_ffi_safe(&db, TypeRef::int()));
+        assert!(is_ffi_safe(&db, TypeRef::float()));
+        assert!(is_ffi_safe(&db, TypeRef::boolean()));
+        assert!(is_ffi_safe(&db, TypeRef::foreign_signed_int(32)));
+        assert!(is_ffi_safe(&db, TypeRef::foreign_float(64)));
+        assert!(is_ffi_safe(&db, TypeRef::pointer(instance(heap))));
+        assert!(is_ffi_safe(&db, TypeRef::Owned(instance(ext))));
+        assert!(is_ffi_safe(&db, TypeRef::Error));
+        assert!(!is_ffi_safe(&db, TypeRef::string()));
+        assert!(!is_ffi_safe(&db, TypeRef::nil()));
+        assert!(!is_ffi_safe(&db, TypeRef::Owned(instance(heap))));
+        assert!(!is_ffi_safe(&db, TypeRef::Ref(instance(ext))));
+    }
+
+    #[test]
+    fn test_method_id_check_abi() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let valid = method_with_arguments(
+            &mut db,
+            module,
+            "valid",
+            MethodKind::Extern,
+            vec![TypeRef::foreign_signed_int(32), TypeRef::int()],
+        );
+        let args = method_with_arguments(
+            &mut db,
+            module,
+            "args",
+            MethodKind::Extern,
+            vec![TypeRef::int(), TypeRef::string()],
+        );
+        let generic = method_with_arguments(
+            &mut db,
+            module,
+            "generic",
+            MethodKind::Static,
+            vec![],
+        );
+        let ret = method_with_arguments(
+            &mut db,
+            module,
+            "ret",
+            MethodKind::Extern,
+            vec![],
+        );
+        let inko = method_with_arguments(
+            &mut db,
+            module,
+            "inko",
+            MethodKind::Static,
+            vec![TypeRef::string()],
+        );
+
+        generic.use_c_calling_convention(&mut db);
+        generic.new_type_parameter(&mut db, "T".to_string());
+        ret.set_return_type(&mut db, TypeRef::string());
+
+        assert!(valid.check_abi(&db).is_empty());
+        assert!(inko.check_abi(&db).is_empty());
+        assert_eq!(
+            args.check_abi(&db),
+            vec![AbiError::InvalidArgument(args, 1)]
+        );
+        assert_eq!(generic.check_abi(&db), vec![AbiError::Generic(generic)]);
+        assert_eq!(ret.check_abi(&db), vec![AbiError::InvalidReturnType(ret)]);
+    }
+
+    #[test]
+    fn test_method_id_check_abi_with_variadic_method() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let valid = method_with_arguments(
+            &mut db,
+            module,
+            "printf",
+            MethodKind::Extern,
+            vec![TypeRef::foreign_signed_int(32)],
+        );
+        let empty = method_with_arguments(
+            &mut db,
+            module,
+            "empty",
+            MethodKind::Extern,
+            vec![],
+        );
+        let method = method_with_arguments(
+            &mut db,
+            module,
+            "method",
+            MethodKind::Static,
+            vec![],
+        );
+
+        valid.set_variadic(&mut db);
+        empty.set_variadic(&mut db);
+        method.set_variadic(&mut db);
+        method.use_c_calling_convention(&mut db);
+
+        assert!(valid.check_abi(&db).is_empty());
+        assert_eq!(
+            empty.check_abi(&db),
+            vec![AbiError::MissingFixedArgument(empty)]
+        );
+        assert_eq!(
+            method.check_abi(&db),
+            vec![AbiError::VariadicMethod(method)]
+        );
+    }
+
+    #[test]
+    fn test_module_id_check_abi() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let ext = method_with_arguments(
+            &mut db,
+            module,
+            "ext",
+            MethodKind::Extern,
+            vec![TypeRef::string()],
+        );
+        let callback = method_with_arguments(
+            &mut db,
+            module,
+            "callback",
+            MethodKind::Static,
+            vec![TypeRef::string()],
+        );
+
+        callback.use_c_calling_convention(&mut db);
+        module.add_extern_method(&mut db, ext);
+        module.add_method(&mut db, "callback".to_string(), callback);
+
+        assert_eq!(
+            module.check_abi(&db),
+            vec![
+                AbiError::InvalidArgument(ext, 0),
+                AbiError::InvalidArgument(callback, 0)
+            ]
+        );
+    }
+}
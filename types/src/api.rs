@@ -0,0 +1,301 @@
+//! Hashing of the public API of modules.
+//!
+//! Package tooling needs to detect if a new version of a library changes its
+//! public API in a way that may break code depending on it. To support this,
+//! the public API of a module is described as a list of entries (one per class,
+//! field, method, etc), each formatted using the type formatter, and hashed
+//! into a single digest. If the digests of two versions of a module differ,
+//! its public API changed.
+//!
+//! Entries are sorted before hashing, such that changing the order in which
+//! symbols are defined doesn't change the hash. Private symbols, method bodies
+//! and source locations aren't included, so changing those doesn't change the
+//! hash either.
+//!
+//! The hash uses FNV-1a instead of Rust's default hasher, as the latter isn't
+//! guaranteed to produce the same results across Rust versions.
+use crate::format::{format_type_with_options, FormatOptions, FormatType};
+use crate::{ClassId, Database, ModuleId, Symbol, TraitId};
+use std::fmt;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x100_0000_01b3;
+
+/// The byte written after every entry.
+///
+/// This byte never occurs in UTF-8 strings, ensuring that the entries `ab` and
+/// `c` produce a different hash compared to the entries `a` and `bc`.
+const SEPARATOR: u8 = 0xff;
+
+/// The digest of the public API of a module.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApiHash(pub u64);
+
+impl fmt::Display for ApiHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+struct Hasher {
+    state: u64,
+}
+
+impl Hasher {
+    fn new() -> Hasher {
+        Hasher { state: FNV_OFFSET }
+    }
+
+    fn write(&mut self, value: &str) {
+        for byte in value.bytes() {
+            self.round(byte);
+        }
+
+        self.round(SEPARATOR);
+    }
+
+    fn round(&mut self, byte: u8) {
+        self.state = (self.state ^ byte as u64).wrapping_mul(FNV_PRIME);
+    }
+
+    fn finish(self) -> ApiHash {
+        ApiHash(self.state)
+    }
+}
+
+fn format<T: FormatType>(db: &Database, typ: T) -> String {
+    format_type_with_options(db, FormatOptions::qualified(), typ)
+}
+
+fn class_entries(db: &Database, class: ClassId, entries: &mut Vec<String>) {
+    let name = format(db, class);
+
+    entries.push(format!("class {:?} {}", class.kind(db), name));
+
+    for field in class.fields(db) {
+        if field.is_public(db) {
+            entries.push(format!(
+                "field {}.{}: {}",
+                name,
+                field.name(db),
+                format(db, field.value_type(db))
+            ));
+        }
+    }
+
+    for con in class.constructors(db) {
+        let args: Vec<_> =
+            con.arguments(db).iter().map(|&t| format(db, t)).collect();
+
+        entries.push(format!(
+            "constructor {}.{}({})",
+            name,
+            con.name(db),
+            args.join(", ")
+        ));
+    }
+
+    for imp in class.implemented_traits(db) {
+        entries.push(format!("impl {} for {}", format(db, imp.instance), name));
+    }
+
+    for method in class.methods(db) {
+        if method.is_public(db) {
+            entries.push(format!("method {}.{}", name, format(db, method)));
+        }
+    }
+}
+
+fn trait_entries(db: &Database, trait_id: TraitId, entries: &mut Vec<String>) {
+    let name = format(db, trait_id);
+
+    entries.push(format!("trait {}", name));
+
+    for req in trait_id.required_traits(db) {
+        entries.push(format!("requires {}: {}", name, format(db, req)));
+    }
+
+    for method in trait_id.required_methods(db) {
+        entries.push(format!("required {}.{}", name, format(db, method)));
+    }
+
+    for method in trait_id.default_methods(db) {
+        if method.is_public(db) {
+            entries.push(format!("default {}.{}", name, format(db, method)));
+        }
+    }
+}
+
+impl ModuleId {
+    /// Returns the entries describing the public API of the module, sorted
+    /// in ascending order.
+    ///
+    /// This includes the public symbols defined in the module and the symbols
+    /// it re-exports.
+    pub fn api_entries(self, db: &Database) -> Vec<String> {
+        let mut entries = Vec::new();
+
+        for (name, sym) in &self.get(db).symbols {
+            if sym.origin.is_some() && !sym.reexport {
+                continue;
+            }
+
+            match sym.symbol {
+                Symbol::Class(id) if id.is_public(db) => {
+                    class_entries(db, id, &mut entries);
+                }
+                Symbol::Trait(id) if id.is_public(db) => {
+                    trait_entries(db, id, &mut entries);
+                }
+                Symbol::Method(id) if id.is_public(db) => {
+                    entries.push(format!("method {}", format(db, id)));
+                }
+                Symbol::Constant(id) if id.is_public(db) => {
+                    entries.push(format!(
+                        "constant {}: {}",
+                        name,
+                        format(db, id.value_type(db))
+                    ));
+                }
+                Symbol::TypeAlias(id) if id.is_public(db) => {
+                    entries.push(format!(
+                        "alias {} = {}",
+                        format(db, id),
+                        format(db, id.aliased_type(db))
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        entries.sort();
+        entries.dedup();
+        entries
+    }
+
+    /// Returns a hash of the public API of the module.
+    ///
+    /// The hash is stable across compilations and compiler versions, as long
+    /// as the formatting of types doesn't change.
+    pub fn api_hash(self, db: &Database) -> ApiHash {
+        let mut hasher = Hasher::new();
+
+        for entry in self.api_entries(db) {
+            hasher.write(&entry);
+        }
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{instance, new_method, new_module, owned};
+    use crate::{
+        Block, Class, ClassKind, Location, MethodId, MethodKind, Trait,
+        TypeRef, Visibility,
+    };
+
+    fn new_class(
+        db: &mut Database,
+        module: ModuleId,
+        name: &str,
+        visibility: Visibility,
+    ) -> ClassId {
+        Class::alloc(
+            db,
+            name.to_string(),
+            ClassKind::Regular,
+            visibility,
+            module,
+            Location::default(),
+        )
+    }
+
+    fn define_method(
+        db: &mut Database,
+        class: ClassId,
+        name: &str,
+        visibility: Visibility,
+    ) -> MethodId {
+        let module = class.module(db);
+        let id = new_method(db, module, name, MethodKind::Instance);
+
+        id.get_mut(db).visibility = visibility;
+        id.set_receiver(db, owned(instance(class)));
+        id.set_return_type(db, TypeRef::int());
+        class.add_method(db, name.to_string(), id);
+        id
+    }
+
+    #[test]
+    fn test_hasher() {
+        let hash = |values: &[&str]| {
+            let mut hasher = Hasher::new();
+
+            values.iter().for_each(|v| hasher.write(v));
+            hasher.finish()
+        };
+
+        assert_eq!(hash(&[]), ApiHash(FNV_OFFSET));
+        assert_eq!(hash(&["a", "b"]), hash(&["a", "b"]));
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+        assert_eq!(ApiHash(255).to_string(), "00000000000000ff");
+    }
+
+    #[test]
+    fn test_module_id_api_entries() {
+        let mut db = Database::new();
+
+        // Builtin classes such as Int are defined in the first module.
+        new_module(&mut db, "std.int");
+
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, module, "Person", Visibility::Public);
+        let hidden = new_class(&mut db, module, "Hidden", Visibility::Private);
+        let to_string = Trait::alloc(
+            &mut db,
+            "ToString".to_string(),
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+
+        define_method(&mut db, class, "age", Visibility::Public);
+        define_method(&mut db, class, "secret", Visibility::Private);
+        module.new_symbol(&mut db, "Person".to_string(), Symbol::Class(class));
+        module.new_symbol(&mut db, "Hidden".to_string(), Symbol::Class(hidden));
+        module.new_symbol(
+            &mut db,
+            "ToString".to_string(),
+            Symbol::Trait(to_string),
+        );
+
+        assert_eq!(
+            module.api_entries(&db),
+            vec![
+                "class Regular foo.Person".to_string(),
+                "method foo.Person.fn pub age -> std.int.Int".to_string(),
+                "trait foo.ToString".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_id_api_hash() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, module, "Person", Visibility::Public);
+
+        module.new_symbol(&mut db, "Person".to_string(), Symbol::Class(class));
+
+        let before = module.api_hash(&db);
+
+        define_method(&mut db, class, "secret", Visibility::Private);
+        assert_eq!(module.api_hash(&db), before);
+
+        define_method(&mut db, class, "age", Visibility::Public);
+        assert_ne!(module.api_hash(&db), before);
+    }
+}
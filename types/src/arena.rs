@@ -0,0 +1,197 @@
+//! Storage of values that can be freed individually.
+//!
+//! An `Arena` stores values in slots, and refers to them using an `Index`
+//! containing both the slot and the generation of the value. When a value is
+//! removed, its slot is reused for the next value, which is assigned a new
+//! generation. This way indexes to removed values are detected, instead of
+//! them silently referring to whatever value now occupies the slot.
+//!
+//! Generations are assigned using a single counter for the entire arena, in
+//! the order in which values are added. This allows removing all values added
+//! after a certain point in time, such as when rolling back a `Database`.
+
+/// An index to a value stored in an `Arena`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Index {
+    pub(crate) slot: u32,
+    pub(crate) generation: u32,
+}
+
+impl Index {
+    /// An index that never refers to a value.
+    ///
+    /// This is used for types that don't store any values in an arena, such
+    /// as instances of classes that aren't generic.
+    pub const NONE: Index = Index { slot: 0, generation: 0 };
+}
+
+pub(crate) struct Slot<T> {
+    pub(crate) generation: u32,
+    pub(crate) value: Option<T>,
+}
+
+/// A collection of values that can be removed individually.
+pub struct Arena<T> {
+    pub(crate) slots: Vec<Slot<T>>,
+
+    /// The slots that are available for reuse.
+    pub(crate) free: Vec<u32>,
+
+    /// The generation to assign to the next value.
+    ///
+    /// This starts at 1, such that `Index::NONE` never refers to a value.
+    pub(crate) generation: u32,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena { slots: Vec::new(), free: Vec::new(), generation: 1 }
+    }
+
+    pub fn insert(&mut self, value: T) -> Index {
+        assert!(self.generation < u32::MAX, "the arena generation overflowed");
+
+        let generation = self.generation;
+
+        self.generation += 1;
+
+        if let Some(slot) = self.free.pop() {
+            self.slots[slot as usize] = Slot { generation, value: Some(value) };
+
+            return Index { slot, generation };
+        }
+
+        assert!(self.slots.len() < u32::MAX as usize);
+
+        let slot = self.slots.len() as u32;
+
+        self.slots.push(Slot { generation, value: Some(value) });
+        Index { slot, generation }
+    }
+
+    pub fn get(&self, index: Index) -> Option<&T> {
+        self.slots
+            .get(index.slot as usize)
+            .filter(|s| s.generation == index.generation)
+            .and_then(|s| s.value.as_ref())
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        self.slots
+            .get_mut(index.slot as usize)
+            .filter(|s| s.generation == index.generation)
+            .and_then(|s| s.value.as_mut())
+    }
+
+    /// Removes a value, returning it if the index referred to a value.
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(index.slot as usize)
+            .filter(|s| s.generation == index.generation)?;
+        let value = slot.value.take();
+
+        if value.is_some() {
+            self.free.push(index.slot);
+        }
+
+        value
+    }
+
+    /// Returns a marker for the current point in time, for use with
+    /// `Arena::remove_since()`.
+    pub fn mark(&self) -> u32 {
+        self.generation
+    }
+
+    /// Removes all values added since the given marker was created.
+    pub fn remove_since(&mut self, mark: u32) {
+        for (idx, slot) in self.slots.iter_mut().enumerate() {
+            if slot.generation >= mark && slot.value.take().is_some() {
+                self.free.push(idx as u32);
+            }
+        }
+    }
+
+    /// Returns the number of values stored.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Removes all values and frees the memory used for storing them.
+    ///
+    /// Generations aren't reset, so indexes obtained before calling this
+    /// method don't refer to values added afterwards.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.slots.shrink_to_fit();
+        self.free.clear();
+        self.free.shrink_to_fit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_get() {
+        let mut arena = Arena::new();
+        let a = arena.insert(10);
+        let b = arena.insert(20);
+
+        assert_eq!(arena.get(a), Some(&10));
+        assert_eq!(arena.get(b), Some(&20));
+        assert_eq!(arena.get(Index::NONE), None);
+        assert_eq!(arena.len(), 2);
+
+        *arena.get_mut(a).unwrap() = 30;
+
+        assert_eq!(arena.get(a), Some(&30));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut arena = Arena::new();
+        let a = arena.insert(10);
+
+        assert_eq!(arena.remove(a), Some(10));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.len(), 0);
+
+        let b = arena.insert(20);
+
+        assert_eq!(a.slot, b.slot);
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(b), Some(&20));
+    }
+
+    #[test]
+    fn test_remove_since() {
+        let mut arena = Arena::new();
+        let a = arena.insert(10);
+        let mark = arena.mark();
+        let b = arena.insert(20);
+        let c = arena.insert(30);
+
+        arena.remove_since(mark);
+
+        assert_eq!(arena.get(a), Some(&10));
+        assert_eq!(arena.get(b), None);
+        assert_eq!(arena.get(c), None);
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut arena = Arena::new();
+        let a = arena.insert(10);
+
+        arena.clear();
+
+        let b = arena.insert(20);
+
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(b), Some(&20));
+    }
+}
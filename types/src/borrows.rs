@@ -0,0 +1,422 @@
+//! Regions in which borrows of stack allocated values are valid.
+//!
+//! Values allocated on the heap use reference counting, so moving such a value
+//! while it's borrowed is detected at runtime. Values allocated on the stack
+//! don't have a reference count, so borrows of such values must not outlive
+//! the value, and the value can't be moved while it's borrowed. Such borrows
+//! are "second-class": they're only valid within the region (i.e. the scope)
+//! that holds them.
+//!
+//! `BorrowRegions` models these regions for a single method. While walking
+//! the method's code the borrow checker enters and exits regions, defines
+//! variables, creates borrows and moves values. Each of these operations is
+//! recorded as an `Event`, and operations that break the rules above produce a
+//! `Conflict`. The borrow checker can then query which borrows are live at any
+//! given point, instead of approximating this using syntactic checks.
+use crate::{Database, VariableId};
+use location::Location;
+use std::collections::HashMap;
+
+/// The ID of a region.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RegionId(pub u32);
+
+/// The ID of a borrow.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BorrowId(pub u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BorrowKind {
+    Ref,
+    Mut,
+}
+
+/// A borrow of a stack allocated value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Borrow {
+    /// The variable that's borrowed.
+    pub variable: VariableId,
+    pub kind: BorrowKind,
+
+    /// The region that holds the borrow.
+    ///
+    /// This is the region of the variable the borrow is assigned to, which may
+    /// be an outer region of the region the borrow is created in.
+    pub region: RegionId,
+    pub location: Location,
+}
+
+/// An operation recorded while walking the code of a method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Enter(RegionId),
+    Exit(RegionId),
+    Define(VariableId),
+    Borrow(BorrowId),
+    Release(BorrowId),
+    Move(VariableId),
+}
+
+/// An operation that breaks the rules of borrowing stack allocated values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// A variable is moved while it's borrowed.
+    MoveWhileBorrowed(VariableId, BorrowId, Location),
+
+    /// A variable goes out of scope while a borrow held by an outer region is
+    /// still live.
+    BorrowOutlivesValue(VariableId, BorrowId),
+}
+
+struct Region {
+    parent: Option<RegionId>,
+
+    /// The variables defined in this region.
+    variables: Vec<VariableId>,
+}
+
+/// The regions and borrows of a single method.
+pub struct BorrowRegions {
+    regions: Vec<Region>,
+    current: RegionId,
+    borrows: Vec<Borrow>,
+
+    /// The live borrows of each variable, in the order they're created in.
+    live: HashMap<VariableId, Vec<BorrowId>>,
+
+    /// The regions the variables are defined in.
+    variables: HashMap<VariableId, RegionId>,
+    events: Vec<Event>,
+    conflicts: Vec<Conflict>,
+}
+
+impl BorrowRegions {
+    /// Returns a new set of regions, containing only the region of the method
+    /// body.
+    pub fn new() -> BorrowRegions {
+        BorrowRegions {
+            regions: vec![Region { parent: None, variables: Vec::new() }],
+            current: RegionId(0),
+            borrows: Vec::new(),
+            live: HashMap::new(),
+            variables: HashMap::new(),
+            events: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    pub fn current(&self) -> RegionId {
+        self.current
+    }
+
+    pub fn parent(&self, region: RegionId) -> Option<RegionId> {
+        self.regions[region.0 as usize].parent
+    }
+
+    /// Returns `true` if `region` is the same region as `other`, or one of the
+    /// regions `other` is nested in.
+    pub fn contains(&self, region: RegionId, other: RegionId) -> bool {
+        let mut cur = Some(other);
+
+        while let Some(id) = cur {
+            if id == region {
+                return true;
+            }
+
+            cur = self.parent(id);
+        }
+
+        false
+    }
+
+    /// Enters a new region nested in the current region.
+    pub fn enter(&mut self) -> RegionId {
+        assert!(self.regions.len() < u32::MAX as usize);
+
+        let id = RegionId(self.regions.len() as u32);
+
+        self.regions
+            .push(Region { parent: Some(self.current), variables: Vec::new() });
+        self.current = id;
+        self.events.push(Event::Enter(id));
+        id
+    }
+
+    /// Exits the current region.
+    ///
+    /// The borrows held by the region are released. If a variable defined in
+    /// the region is still borrowed by an outer region, a conflict is recorded
+    /// and the borrow is released.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the current region is the region of the method
+    /// body.
+    pub fn exit(&mut self) {
+        let id = self.current;
+        let region = &self.regions[id.0 as usize];
+        let parent = region.parent.expect("the method region can't be exited");
+        let vars = region.variables.clone();
+        let held: Vec<BorrowId> = (0..self.borrows.len())
+            .map(|i| BorrowId(i as u32))
+            .filter(|&b| self.get(b).region == id && self.is_live(b))
+            .collect();
+
+        for borrow in held {
+            self.release(borrow);
+        }
+
+        for var in vars {
+            let Some(live) = self.live.remove(&var) else { continue };
+
+            for borrow in live {
+                self.conflicts.push(Conflict::BorrowOutlivesValue(var, borrow));
+                self.events.push(Event::Release(borrow));
+            }
+        }
+
+        self.current = parent;
+        self.events.push(Event::Exit(id));
+    }
+
+    /// Defines a variable in the current region.
+    pub fn define(&mut self, variable: VariableId) {
+        self.regions[self.current.0 as usize].variables.push(variable);
+        self.variables.insert(variable, self.current);
+        self.events.push(Event::Define(variable));
+    }
+
+    /// Returns the region the variable is defined in, if it's defined.
+    pub fn region_of(&self, variable: VariableId) -> Option<RegionId> {
+        self.variables.get(&variable).cloned()
+    }
+
+    /// Records a borrow of a variable, held by the given region.
+    ///
+    /// Borrows of values allocated on the heap don't need to be tracked, so
+    /// for such values a `None` is returned.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the region isn't the current region or one of
+    /// the regions it's nested in.
+    pub fn add_borrow(
+        &mut self,
+        db: &Database,
+        variable: VariableId,
+        kind: BorrowKind,
+        region: RegionId,
+        location: Location,
+    ) -> Option<BorrowId> {
+        assert!(
+            self.contains(region, self.current),
+            "the region of a borrow must be the current or an outer region"
+        );
+
+        if !variable.value_type(db).is_stack_allocated(db) {
+            return None;
+        }
+
+        let id = BorrowId(self.borrows.len() as u32);
+
+        self.borrows.push(Borrow { variable, kind, region, location });
+        self.live.entry(variable).or_default().push(id);
+        self.events.push(Event::Borrow(id));
+        Some(id)
+    }
+
+    /// Releases a borrow before its region is exited, such as when the
+    /// variable holding the borrow is dropped.
+    pub fn release(&mut self, borrow: BorrowId) {
+        let var = self.get(borrow).variable;
+
+        if let Some(live) = self.live.get_mut(&var) {
+            let len = live.len();
+
+            live.retain(|&b| b != borrow);
+
+            if live.len() != len {
+                self.events.push(Event::Release(borrow));
+            }
+        }
+    }
+
+    /// Records that a variable is moved.
+    ///
+    /// If the variable is borrowed, a conflict is recorded for every live
+    /// borrow and `false` is returned.
+    pub fn move_variable(
+        &mut self,
+        variable: VariableId,
+        location: Location,
+    ) -> bool {
+        let live = self.live_borrows(variable).to_vec();
+
+        self.events.push(Event::Move(variable));
+
+        for &borrow in &live {
+            self.conflicts
+                .push(Conflict::MoveWhileBorrowed(variable, borrow, location));
+        }
+
+        live.is_empty()
+    }
+
+    pub fn get(&self, borrow: BorrowId) -> &Borrow {
+        &self.borrows[borrow.0 as usize]
+    }
+
+    pub fn is_live(&self, borrow: BorrowId) -> bool {
+        self.live_borrows(self.get(borrow).variable).contains(&borrow)
+    }
+
+    /// Returns the live borrows of a variable, in the order they're created
+    /// in.
+    pub fn live_borrows(&self, variable: VariableId) -> &[BorrowId] {
+        self.live.get(&variable).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn is_borrowed(&self, variable: VariableId) -> bool {
+        !self.live_borrows(variable).is_empty()
+    }
+
+    /// Returns `true` if the variable is borrowed mutably.
+    pub fn is_borrowed_mutably(&self, variable: VariableId) -> bool {
+        self.live_borrows(variable)
+            .iter()
+            .any(|&b| self.get(b).kind == BorrowKind::Mut)
+    }
+
+    /// Returns the operations recorded, in the order they're recorded in.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Returns the conflicts found, in the order they're found in.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+}
+
+impl Default for BorrowRegions {
+    fn default() -> BorrowRegions {
+        BorrowRegions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{instance, new_class, owned};
+    use crate::{TypeRef, Variable};
+
+    fn new_variable(db: &mut Database, name: &str, typ: TypeRef) -> VariableId {
+        Variable::alloc(db, name.to_string(), typ, false, Location::default())
+    }
+
+    #[test]
+    fn test_borrow_regions_enter_exit() {
+        let mut regions = BorrowRegions::new();
+        let root = regions.current();
+        let outer = regions.enter();
+        let inner = regions.enter();
+
+        assert_eq!(regions.parent(inner), Some(outer));
+        assert!(regions.contains(root, inner));
+        assert!(regions.contains(inner, inner));
+        assert!(!regions.contains(inner, outer));
+
+        regions.exit();
+        regions.exit();
+
+        assert_eq!(regions.current(), root);
+        assert_eq!(
+            regions.events(),
+            &[
+                Event::Enter(outer),
+                Event::Enter(inner),
+                Event::Exit(inner),
+                Event::Exit(outer)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_borrow_regions_add_borrow() {
+        let mut db = Database::new();
+        let heap = new_class(&mut db, "Heap");
+        let int = new_variable(&mut db, "a", TypeRef::int());
+        let obj = new_variable(&mut db, "b", owned(instance(heap)));
+        let mut regions = BorrowRegions::new();
+        let loc = Location::default();
+
+        regions.define(int);
+        regions.define(obj);
+
+        let region = regions.enter();
+        let borrow =
+            regions.add_borrow(&db, int, BorrowKind::Mut, region, loc).unwrap();
+
+        assert!(regions
+            .add_borrow(&db, obj, BorrowKind::Ref, region, loc)
+            .is_none());
+        assert!(regions.is_borrowed(int));
+        assert!(regions.is_borrowed_mutably(int));
+        assert!(!regions.is_borrowed(obj));
+        assert_eq!(regions.region_of(int), Some(RegionId(0)));
+
+        regions.exit();
+
+        assert!(!regions.is_live(borrow));
+        assert!(!regions.is_borrowed(int));
+        assert!(regions.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_borrow_regions_move_while_borrowed() {
+        let mut db = Database::new();
+        let var = new_variable(&mut db, "a", TypeRef::int());
+        let mut regions = BorrowRegions::new();
+        let root = regions.current();
+        let loc = Location::default();
+
+        regions.define(var);
+
+        let borrow =
+            regions.add_borrow(&db, var, BorrowKind::Ref, root, loc).unwrap();
+
+        assert!(!regions.move_variable(var, loc));
+        assert_eq!(
+            regions.conflicts(),
+            &[Conflict::MoveWhileBorrowed(var, borrow, loc)]
+        );
+
+        regions.release(borrow);
+
+        assert!(regions.move_variable(var, loc));
+        assert_eq!(regions.conflicts().len(), 1);
+    }
+
+    #[test]
+    fn test_borrow_regions_borrow_outlives_value() {
+        let mut db = Database::new();
+        let var = new_variable(&mut db, "a", TypeRef::int());
+        let mut regions = BorrowRegions::new();
+        let root = regions.current();
+        let loc = Location::default();
+
+        regions.enter();
+        regions.define(var);
+
+        let borrow =
+            regions.add_borrow(&db, var, BorrowKind::Ref, root, loc).unwrap();
+
+        regions.exit();
+
+        assert!(!regions.is_live(borrow));
+        assert_eq!(
+            regions.conflicts(),
+            &[Conflict::BorrowOutlivesValue(var, borrow)]
+        );
+    }
+}
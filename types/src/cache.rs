@@ -0,0 +1,2449 @@
+//! Caching of type databases on disk.
+//!
+//! Type-checking a program requires type-checking all modules it imports,
+//! including the standard library. When none of these modules change between
+//! compilations, this work produces the exact same `Database` every time. To
+//! avoid this, a `Database` can be written to a cache file and loaded back in
+//! a future compilation.
+//!
+//! A cache file stores the database's tables verbatim. This means that all IDs
+//! (e.g. `ClassId` and `MethodId`) in a loaded database are the same as those
+//! in the database that was written, allowing other caches to refer to types
+//! using their IDs.
+//!
+//! Along with the database, a cache file stores a hash of the source code of
+//! each module and the modules each module depends on. When loading a cache,
+//! these hashes are compared against the hashes of the current source code.
+//! Since the data of different modules is interleaved in the database's
+//! tables, a single changed, added or removed module invalidates the entire
+//! database. In this case the names of these modules and of the modules that
+//! (directly or indirectly) depend on them are returned, such that the
+//! compiler can decide what to do next (e.g. to invalidate other caches of just
+//! these modules).
+//!
+//! The entries of hash maps and hash sets are sorted before they're written,
+//! such that encoding the same database always produces the same bytes.
+//!
+//! The format is a simple binary format using little-endian integers. Each
+//! file starts with a header containing a version number, and files with a
+//! different version are treated as stale.
+use crate::arena::{Arena, Index, Slot};
+use crate::callgraph::{CallGraph, Callee};
+use crate::coherence::{DisjointTraits, Exclusions, NegativeImplementation};
+use crate::conditions::Conditions;
+use crate::consteval::Value;
+use crate::defaults::FieldDefault;
+use crate::deprecation::Deprecation;
+use crate::docs::Docs;
+use crate::drops::DropOrder;
+use crate::effects::Effects;
+use crate::escape::{Escape, EscapeFlow, EscapeTable};
+use crate::inline::InlinePolicy;
+use crate::layout::LayoutOptions;
+use crate::module_name::ModuleName;
+use crate::packages::{Package, PackageId};
+use crate::specialize::{Niche, SpecializationLimits};
+use crate::target::{Endianness, Target};
+use crate::{
+    Argument, Arguments, BlanketImplementation, CallConvention, Capture,
+    CaptureMode, Class, ClassId, ClassInstance, ClassKind, Closure, ClosureId,
+    Constant, ConstantId, Constructor, ConstructorId, Database, Field, FieldId,
+    ForeignFunction, ForeignFunctionId, ForeignType, Inline, Intrinsic, Method,
+    MethodId, MethodKind, MethodSource, Module, ModuleId, ModuleSymbol,
+    Ownership, Shape, Sign, Storage, Symbol, ThrowKind, Trait, TraitId,
+    TraitImplementation, TraitInstance, Tuple, TupleId, TypeAlias, TypeAliasId,
+    TypeAliasInstance, TypeArguments, TypeBounds, TypeId, TypeParameter,
+    TypeParameterId, TypePlaceholder, TypePlaceholderId, TypeRef, Union,
+    UnionId, Variable, VariableId, Variance, Visibility,
+};
+use indexmap::IndexMap;
+use location::Location;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicU32;
+
+/// The bytes every cache file starts with.
+const SIGNATURE: &[u8] = b"inko-types";
+
+/// The version of the cache format.
+///
+/// This value must be incremented whenever the layout of the cache or of any
+/// of the types stored in it changes.
+const VERSION: u32 = 48;
+
+/// The hashes of the source code of every module, used to determine if a cache
+/// is still valid.
+pub type ModuleHashes = HashMap<ModuleName, u64>;
+
+/// The result of loading a cache file.
+pub enum Loaded {
+    /// The cache is valid, and its database can be used as-is.
+    Valid(Box<Database>),
+
+    /// The cache is stale, and must be discarded.
+    ///
+    /// The value is a list of the modules that were added, removed or changed,
+    /// along with the modules that depend on these modules, sorted by their
+    /// names. If the cache was produced using a different version of the cache
+    /// format, this list is empty.
+    Stale(Vec<ModuleName>),
+}
+
+/// Writes a database, along with the hashes of its modules, to a cache file.
+pub fn save(
+    path: &Path,
+    db: &Database,
+    hashes: &ModuleHashes,
+) -> Result<(), String> {
+    fs::write(path, encode(db, hashes))
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+/// Loads a database from a cache file.
+///
+/// The `hashes` argument contains the hashes of the current source code of
+/// every module, and is used to determine if the cache is still valid.
+pub fn load(path: &Path, hashes: &ModuleHashes) -> Result<Loaded, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+
+    decode(&bytes, hashes)
+        .map_err(|e| format!("the cache {} is invalid: {}", path.display(), e))
+}
+
+/// Encodes a database into a sequence of bytes.
+pub fn encode(db: &Database, hashes: &ModuleHashes) -> Vec<u8> {
+    let mut enc = Encoder::new();
+
+    enc.bytes.extend_from_slice(SIGNATURE);
+    VERSION.encode(&mut enc);
+    hashes.encode(&mut enc);
+    dependencies(db).encode(&mut enc);
+    db.encode(&mut enc);
+    enc.bytes
+}
+
+/// Decodes a database from a sequence of bytes.
+pub fn decode(bytes: &[u8], hashes: &ModuleHashes) -> Result<Loaded, String> {
+    let mut dec = Decoder::new(bytes);
+
+    if dec.take(SIGNATURE.len())? != SIGNATURE {
+        return Err("the file isn't a type cache".to_string());
+    }
+
+    if u32::decode(&mut dec)? != VERSION {
+        return Ok(Loaded::Stale(Vec::new()));
+    }
+
+    let cached = ModuleHashes::decode(&mut dec)?;
+    let deps = Vec::<(ModuleName, Vec<ModuleName>)>::decode(&mut dec)?;
+    let mut changed: HashSet<_> = cached
+        .iter()
+        .filter(|(name, hash)| hashes.get(name) != Some(hash))
+        .map(|(name, _)| name.clone())
+        .chain(hashes.keys().filter(|n| !cached.contains_key(n)).cloned())
+        .collect();
+
+    if !changed.is_empty() {
+        // A module that depends on a changed module is also stale, as e.g.
+        // the types it uses may no longer exist. We repeat this until no new
+        // modules are found, such that indirect dependencies are included.
+        loop {
+            let len = changed.len();
+
+            for (name, imports) in &deps {
+                if imports.iter().any(|n| changed.contains(n)) {
+                    changed.insert(name.clone());
+                }
+            }
+
+            if changed.len() == len {
+                break;
+            }
+        }
+
+        let mut changed: Vec<_> = changed.into_iter().collect();
+
+        changed.sort();
+        return Ok(Loaded::Stale(changed));
+    }
+
+    let db = Database::decode(&mut dec)?;
+
+    if dec.offset != bytes.len() {
+        return Err("trailing bytes after the database".to_string());
+    }
+
+    Ok(Loaded::Valid(Box::new(db)))
+}
+
+/// Returns the name of every module, along with the names of the modules it
+/// depends on.
+fn dependencies(db: &Database) -> Vec<(ModuleName, Vec<ModuleName>)> {
+    db.modules
+        .iter()
+        .map(|module| {
+            let deps = module
+                .dependencies
+                .iter()
+                .map(|id| id.get(db).name.clone())
+                .collect();
+
+            (module.name.clone(), deps)
+        })
+        .collect()
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Encoder {
+        Encoder { bytes: Vec::new() }
+    }
+
+    fn tag(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Encodes the values of an unordered collection (e.g. a `HashMap`), in
+    /// the order of their encoded bytes.
+    ///
+    /// The iteration order of such collections differs between runs, so
+    /// encoding the values in that order would produce different bytes for
+    /// the same data.
+    fn sorted<T, I: Iterator<Item = T>, F: Fn(&mut Encoder, T)>(
+        &mut self,
+        len: usize,
+        values: I,
+        func: F,
+    ) {
+        let mut encoded: Vec<_> = values
+            .map(|val| {
+                let mut enc = Encoder::new();
+
+                func(&mut enc, val);
+                enc.bytes
+            })
+            .collect();
+
+        encoded.sort();
+        len.encode(self);
+
+        for bytes in encoded {
+            self.bytes.extend_from_slice(&bytes);
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Decoder<'a> {
+        Decoder { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, amount: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(amount)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "unexpected end of input".to_string())?;
+        let slice = &self.bytes[self.offset..end];
+
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn tag(&mut self) -> Result<u8, String> {
+        u8::decode(self)
+    }
+
+    fn invalid<T>(&self, tag: u8, kind: &str) -> Result<T, String> {
+        Err(format!(
+            "the tag {} at offset {} isn't a valid {}",
+            tag,
+            self.offset - 1,
+            kind
+        ))
+    }
+}
+
+trait Encode {
+    fn encode(&self, enc: &mut Encoder);
+}
+
+trait Decode: Sized {
+    fn decode(dec: &mut Decoder) -> Result<Self, String>;
+}
+
+macro_rules! integer {
+    ($($kind: ty),*) => {
+        $(
+            impl Encode for $kind {
+                fn encode(&self, enc: &mut Encoder) {
+                    enc.bytes.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $kind {
+                fn decode(dec: &mut Decoder) -> Result<Self, String> {
+                    let bytes = dec.take(std::mem::size_of::<$kind>())?;
+
+                    Ok(<$kind>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+integer!(u8, u16, u32, u64);
+
+impl Encode for usize {
+    fn encode(&self, enc: &mut Encoder) {
+        (*self as u64).encode(enc);
+    }
+}
+
+impl Decode for usize {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        usize::try_from(u64::decode(dec)?)
+            .map_err(|_| "the value doesn't fit in a usize".to_string())
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.tag(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => dec.invalid(tag, "boolean"),
+        }
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, enc: &mut Encoder) {
+        self.len().encode(enc);
+        enc.bytes.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let len = usize::decode(dec)?;
+        let bytes = dec.take(len)?;
+
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| "a string contains invalid UTF-8".to_string())
+    }
+}
+
+impl Encode for PathBuf {
+    fn encode(&self, enc: &mut Encoder) {
+        self.to_string_lossy().into_owned().encode(enc);
+    }
+}
+
+impl Decode for PathBuf {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        String::decode(dec).map(PathBuf::from)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        if let Some(val) = self {
+            enc.tag(1);
+            val.encode(enc);
+        } else {
+            enc.tag(0);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(None),
+            1 => T::decode(dec).map(Some),
+            tag => dec.invalid(tag, "option"),
+        }
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, enc: &mut Encoder) {
+        self.0.encode(enc);
+        self.1.encode(enc);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok((A::decode(dec)?, B::decode(dec)?))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.len().encode(enc);
+
+        for val in self {
+            val.encode(enc);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let len = usize::decode(dec)?;
+
+        // The length is read from the input, so we don't use it to
+        // preallocate memory as it may be invalid.
+        let mut vals = Vec::new();
+
+        for _ in 0..len {
+            vals.push(T::decode(dec)?);
+        }
+
+        Ok(vals)
+    }
+}
+
+impl<T: Encode> Encode for HashSet<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.sorted(self.len(), self.iter(), |enc, val| val.encode(enc));
+    }
+}
+
+impl<T: Decode + Eq + Hash> Decode for HashSet<T> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Vec::decode(dec)?.into_iter().collect())
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for HashMap<K, V> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.sorted(self.len(), self.iter(), |enc, (key, val)| {
+            key.encode(enc);
+            val.encode(enc);
+        });
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode> Decode for HashMap<K, V> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Vec::<(K, V)>::decode(dec)?.into_iter().collect())
+    }
+}
+
+impl<K: Encode, V: Encode> Encode for IndexMap<K, V> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.len().encode(enc);
+
+        for (key, val) in self {
+            key.encode(enc);
+            val.encode(enc);
+        }
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode> Decode for IndexMap<K, V> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Vec::<(K, V)>::decode(dec)?.into_iter().collect())
+    }
+}
+
+impl Encode for ModuleName {
+    fn encode(&self, enc: &mut Encoder) {
+        self.as_str().to_string().encode(enc);
+    }
+}
+
+impl Decode for ModuleName {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        String::decode(dec).map(ModuleName::new)
+    }
+}
+
+impl Encode for Location {
+    fn encode(&self, enc: &mut Encoder) {
+        self.line_start.encode(enc);
+        self.line_end.encode(enc);
+        self.column_start.encode(enc);
+        self.column_end.encode(enc);
+    }
+}
+
+impl Decode for Location {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Location {
+            line_start: u32::decode(dec)?,
+            line_end: u32::decode(dec)?,
+            column_start: u32::decode(dec)?,
+            column_end: u32::decode(dec)?,
+        })
+    }
+}
+
+/// Implements encoding and decoding for ID types that wrap a single integer.
+macro_rules! id {
+    ($($kind: ident),*) => {
+        $(
+            impl Encode for $kind {
+                fn encode(&self, enc: &mut Encoder) {
+                    self.0.encode(enc);
+                }
+            }
+
+            impl Decode for $kind {
+                fn decode(dec: &mut Decoder) -> Result<Self, String> {
+                    Decode::decode(dec).map($kind)
+                }
+            }
+        )*
+    };
+}
+
+id!(
+    ClassId,
+    TraitId,
+    ModuleId,
+    MethodId,
+    TypeParameterId,
+    FieldId,
+    ConstructorId,
+    VariableId,
+    ConstantId,
+    ClosureId,
+    TypeAliasId,
+    UnionId,
+    TupleId,
+    ForeignFunctionId,
+    PackageId
+);
+
+/// Implements encoding and decoding for enums without any values.
+macro_rules! unit_enum {
+    ($kind: ident, $name: expr, [$($variant: ident),*]) => {
+        impl Encode for $kind {
+            fn encode(&self, enc: &mut Encoder) {
+                enc.tag(*self as u8);
+            }
+        }
+
+        impl Decode for $kind {
+            fn decode(dec: &mut Decoder) -> Result<Self, String> {
+                let tag = dec.tag()?;
+
+                $(
+                    if tag == $kind::$variant as u8 {
+                        return Ok($kind::$variant);
+                    }
+                )*
+
+                dec.invalid(tag, $name)
+            }
+        }
+    };
+}
+
+unit_enum!(Visibility, "visibility", [Public, Private, TypePrivate, Namespace]);
+unit_enum!(Storage, "storage", [Heap, Stack]);
+unit_enum!(DropOrder, "drop order", [Reverse, Declaration]);
+unit_enum!(Sign, "sign", [Signed, Unsigned]);
+unit_enum!(Inline, "inline value", [Never, Infer, Always]);
+unit_enum!(CallConvention, "call convention", [Inko, C]);
+unit_enum!(
+    ClassKind,
+    "class kind",
+    [Async, Atomic, Closure, Enum, Extern, Module, Regular, Tuple, Union]
+);
+unit_enum!(
+    MethodKind,
+    "method kind",
+    [
+        Async,
+        AsyncMutable,
+        Static,
+        Constructor,
+        Instance,
+        Moving,
+        Mutable,
+        Destructor,
+        Extern
+    ]
+);
+unit_enum!(Variance, "variance", [Invariant, Covariant, Contravariant]);
+unit_enum!(Endianness, "endianness", [Little, Big]);
+unit_enum!(
+    Ownership,
+    "ownership",
+    [Any, Owned, Uni, Ref, Mut, UniRef, UniMut, Pointer]
+);
+
+impl Encode for ClassInstance {
+    fn encode(&self, enc: &mut Encoder) {
+        self.instance_of.encode(enc);
+        self.type_arguments.encode(enc);
+    }
+}
+
+impl Decode for ClassInstance {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(ClassInstance {
+            instance_of: ClassId::decode(dec)?,
+            type_arguments: Index::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for TraitInstance {
+    fn encode(&self, enc: &mut Encoder) {
+        self.instance_of.encode(enc);
+        self.type_arguments.encode(enc);
+    }
+}
+
+impl Decode for TraitInstance {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TraitInstance {
+            instance_of: TraitId::decode(dec)?,
+            type_arguments: Index::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for TypeAliasInstance {
+    fn encode(&self, enc: &mut Encoder) {
+        self.instance_of.encode(enc);
+        self.type_arguments.encode(enc);
+    }
+}
+
+impl Decode for TypeAliasInstance {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypeAliasInstance {
+            instance_of: TypeAliasId::decode(dec)?,
+            type_arguments: Index::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for TypePlaceholderId {
+    fn encode(&self, enc: &mut Encoder) {
+        self.id.encode(enc);
+        self.ownership.encode(enc);
+    }
+}
+
+impl Decode for TypePlaceholderId {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypePlaceholderId {
+            id: u32::decode(dec)?,
+            ownership: Ownership::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for ForeignType {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ForeignType::Int(size, sign) => {
+                enc.tag(0);
+                size.encode(enc);
+                sign.encode(enc);
+            }
+            ForeignType::Float(size) => {
+                enc.tag(1);
+                size.encode(enc);
+            }
+            ForeignType::Function(id) => {
+                enc.tag(2);
+                id.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for ForeignType {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(ForeignType::Int(u32::decode(dec)?, Sign::decode(dec)?)),
+            1 => Ok(ForeignType::Float(u32::decode(dec)?)),
+            2 => Ok(ForeignType::Function(ForeignFunctionId::decode(dec)?)),
+            tag => dec.invalid(tag, "foreign type"),
+        }
+    }
+}
+
+impl Encode for TypeId {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            TypeId::Class(id) => {
+                enc.tag(0);
+                id.encode(enc);
+            }
+            TypeId::Trait(id) => {
+                enc.tag(1);
+                id.encode(enc);
+            }
+            TypeId::Module(id) => {
+                enc.tag(2);
+                id.encode(enc);
+            }
+            TypeId::ClassInstance(ins) => {
+                enc.tag(3);
+                ins.encode(enc);
+            }
+            TypeId::TraitInstance(ins) => {
+                enc.tag(4);
+                ins.encode(enc);
+            }
+            TypeId::TypeParameter(id) => {
+                enc.tag(5);
+                id.encode(enc);
+            }
+            TypeId::RigidTypeParameter(id) => {
+                enc.tag(6);
+                id.encode(enc);
+            }
+            TypeId::AtomicTypeParameter(id) => {
+                enc.tag(7);
+                id.encode(enc);
+            }
+            TypeId::Closure(id) => {
+                enc.tag(8);
+                id.encode(enc);
+            }
+            TypeId::Foreign(typ) => {
+                enc.tag(9);
+                typ.encode(enc);
+            }
+            TypeId::TypeAlias(ins) => {
+                enc.tag(10);
+                ins.encode(enc);
+            }
+            TypeId::Tuple(id) => {
+                enc.tag(11);
+                id.encode(enc);
+            }
+            TypeId::Constant(val) => {
+                enc.tag(12);
+                (*val as u64).encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for TypeId {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let id = match dec.tag()? {
+            0 => TypeId::Class(ClassId::decode(dec)?),
+            1 => TypeId::Trait(TraitId::decode(dec)?),
+            2 => TypeId::Module(ModuleId::decode(dec)?),
+            3 => TypeId::ClassInstance(ClassInstance::decode(dec)?),
+            4 => TypeId::TraitInstance(TraitInstance::decode(dec)?),
+            5 => TypeId::TypeParameter(TypeParameterId::decode(dec)?),
+            6 => TypeId::RigidTypeParameter(TypeParameterId::decode(dec)?),
+            7 => TypeId::AtomicTypeParameter(TypeParameterId::decode(dec)?),
+            8 => TypeId::Closure(ClosureId::decode(dec)?),
+            9 => TypeId::Foreign(ForeignType::decode(dec)?),
+            10 => TypeId::TypeAlias(TypeAliasInstance::decode(dec)?),
+            11 => TypeId::Tuple(TupleId::decode(dec)?),
+            12 => TypeId::Constant(u64::decode(dec)? as i64),
+            tag => return dec.invalid(tag, "type ID"),
+        };
+
+        Ok(id)
+    }
+}
+
+impl Encode for TypeRef {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            TypeRef::Owned(id) => {
+                enc.tag(0);
+                id.encode(enc);
+            }
+            TypeRef::Uni(id) => {
+                enc.tag(1);
+                id.encode(enc);
+            }
+            TypeRef::Ref(id) => {
+                enc.tag(2);
+                id.encode(enc);
+            }
+            TypeRef::UniRef(id) => {
+                enc.tag(3);
+                id.encode(enc);
+            }
+            TypeRef::Mut(id) => {
+                enc.tag(4);
+                id.encode(enc);
+            }
+            TypeRef::UniMut(id) => {
+                enc.tag(5);
+                id.encode(enc);
+            }
+            TypeRef::Any(id) => {
+                enc.tag(6);
+                id.encode(enc);
+            }
+            TypeRef::Never => enc.tag(7),
+            TypeRef::Error => enc.tag(8),
+            TypeRef::Unknown => enc.tag(9),
+            TypeRef::Placeholder(id) => {
+                enc.tag(10);
+                id.encode(enc);
+            }
+            TypeRef::Pointer(id) => {
+                enc.tag(11);
+                id.encode(enc);
+            }
+            TypeRef::Union(id) => {
+                enc.tag(12);
+                id.encode(enc);
+            }
+            TypeRef::NonNullPointer(id) => {
+                enc.tag(13);
+                id.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for TypeRef {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let typ = match dec.tag()? {
+            0 => TypeRef::Owned(TypeId::decode(dec)?),
+            1 => TypeRef::Uni(TypeId::decode(dec)?),
+            2 => TypeRef::Ref(TypeId::decode(dec)?),
+            3 => TypeRef::UniRef(TypeId::decode(dec)?),
+            4 => TypeRef::Mut(TypeId::decode(dec)?),
+            5 => TypeRef::UniMut(TypeId::decode(dec)?),
+            6 => TypeRef::Any(TypeId::decode(dec)?),
+            7 => TypeRef::Never,
+            8 => TypeRef::Error,
+            9 => TypeRef::Unknown,
+            10 => TypeRef::Placeholder(TypePlaceholderId::decode(dec)?),
+            11 => TypeRef::Pointer(TypeId::decode(dec)?),
+            12 => TypeRef::Union(UnionId::decode(dec)?),
+            13 => TypeRef::NonNullPointer(TypeId::decode(dec)?),
+            tag => return dec.invalid(tag, "type"),
+        };
+
+        Ok(typ)
+    }
+}
+
+impl Encode for Shape {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Shape::Owned => enc.tag(0),
+            Shape::Mut => enc.tag(1),
+            Shape::Ref => enc.tag(2),
+            Shape::Int(size, sign) => {
+                enc.tag(3);
+                size.encode(enc);
+                sign.encode(enc);
+            }
+            Shape::Float(size) => {
+                enc.tag(4);
+                size.encode(enc);
+            }
+            Shape::Boolean => enc.tag(5),
+            Shape::String => enc.tag(6),
+            Shape::Nil => enc.tag(7),
+            Shape::Atomic => enc.tag(8),
+            Shape::Pointer => enc.tag(9),
+            Shape::Char => enc.tag(11),
+            Shape::Stack(ins) => {
+                enc.tag(10);
+                ins.encode(enc);
+            }
+            Shape::Constant(val) => {
+                enc.tag(12);
+                (*val as u64).encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for Shape {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let shape = match dec.tag()? {
+            0 => Shape::Owned,
+            1 => Shape::Mut,
+            2 => Shape::Ref,
+            3 => Shape::Int(u32::decode(dec)?, Sign::decode(dec)?),
+            4 => Shape::Float(u32::decode(dec)?),
+            5 => Shape::Boolean,
+            6 => Shape::String,
+            7 => Shape::Nil,
+            8 => Shape::Atomic,
+            9 => Shape::Pointer,
+            10 => Shape::Stack(ClassInstance::decode(dec)?),
+            11 => Shape::Char,
+            12 => Shape::Constant(u64::decode(dec)? as i64),
+            tag => return dec.invalid(tag, "shape"),
+        };
+
+        Ok(shape)
+    }
+}
+
+impl Encode for Symbol {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Symbol::Class(id) => {
+                enc.tag(0);
+                id.encode(enc);
+            }
+            Symbol::Trait(id) => {
+                enc.tag(1);
+                id.encode(enc);
+            }
+            Symbol::Module(id) => {
+                enc.tag(2);
+                id.encode(enc);
+            }
+            Symbol::TypeParameter(id) => {
+                enc.tag(3);
+                id.encode(enc);
+            }
+            Symbol::Constant(id) => {
+                enc.tag(4);
+                id.encode(enc);
+            }
+            Symbol::Method(id) => {
+                enc.tag(5);
+                id.encode(enc);
+            }
+            Symbol::TypeAlias(id) => {
+                enc.tag(6);
+                id.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for Symbol {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let sym = match dec.tag()? {
+            0 => Symbol::Class(ClassId::decode(dec)?),
+            1 => Symbol::Trait(TraitId::decode(dec)?),
+            2 => Symbol::Module(ModuleId::decode(dec)?),
+            3 => Symbol::TypeParameter(TypeParameterId::decode(dec)?),
+            4 => Symbol::Constant(ConstantId::decode(dec)?),
+            5 => Symbol::Method(MethodId::decode(dec)?),
+            6 => Symbol::TypeAlias(TypeAliasId::decode(dec)?),
+            tag => return dec.invalid(tag, "symbol"),
+        };
+
+        Ok(sym)
+    }
+}
+
+impl Encode for MethodSource {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            MethodSource::Direct => enc.tag(0),
+            MethodSource::Implemented(ins, id) => {
+                enc.tag(1);
+                ins.encode(enc);
+                id.encode(enc);
+            }
+            MethodSource::Inherited(ins, id) => {
+                enc.tag(2);
+                ins.encode(enc);
+                id.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for MethodSource {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let src = match dec.tag()? {
+            0 => MethodSource::Direct,
+            1 => MethodSource::Implemented(
+                TraitInstance::decode(dec)?,
+                MethodId::decode(dec)?,
+            ),
+            2 => MethodSource::Inherited(
+                TraitInstance::decode(dec)?,
+                MethodId::decode(dec)?,
+            ),
+            tag => return dec.invalid(tag, "method source"),
+        };
+
+        Ok(src)
+    }
+}
+
+impl Encode for Index {
+    fn encode(&self, enc: &mut Encoder) {
+        self.slot.encode(enc);
+        self.generation.encode(enc);
+    }
+}
+
+impl Decode for Index {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Index { slot: u32::decode(dec)?, generation: u32::decode(dec)? })
+    }
+}
+
+impl<T: Encode> Encode for Arena<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.slots.len().encode(enc);
+
+        for slot in &self.slots {
+            slot.generation.encode(enc);
+            slot.value.encode(enc);
+        }
+
+        self.free.encode(enc);
+        self.generation.encode(enc);
+    }
+}
+
+impl<T: Decode> Decode for Arena<T> {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let len = usize::decode(dec)?;
+        let mut slots = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            slots.push(Slot {
+                generation: u32::decode(dec)?,
+                value: Option::decode(dec)?,
+            });
+        }
+
+        Ok(Arena {
+            slots,
+            free: Vec::decode(dec)?,
+            generation: u32::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for TypeArguments {
+    fn encode(&self, enc: &mut Encoder) {
+        self.len().encode(enc);
+
+        for (param, value) in self.iter() {
+            param.encode(enc);
+            value.encode(enc);
+        }
+    }
+}
+
+impl Decode for TypeArguments {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let mut args = TypeArguments::new();
+
+        args.extend(Vec::<(TypeParameterId, TypeRef)>::decode(dec)?);
+        Ok(args)
+    }
+}
+
+impl Encode for TypeBounds {
+    fn encode(&self, enc: &mut Encoder) {
+        self.mapping.encode(enc);
+    }
+}
+
+impl Decode for TypeBounds {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypeBounds { mapping: HashMap::decode(dec)? })
+    }
+}
+
+impl Encode for TraitImplementation {
+    fn encode(&self, enc: &mut Encoder) {
+        self.instance.encode(enc);
+        self.bounds.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+    }
+}
+
+impl Decode for TraitImplementation {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TraitImplementation {
+            instance: TraitInstance::decode(dec)?,
+            bounds: TypeBounds::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for BlanketImplementation {
+    fn encode(&self, enc: &mut Encoder) {
+        self.parameter.encode(enc);
+        self.implementation.encode(enc);
+    }
+}
+
+impl Decode for BlanketImplementation {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(BlanketImplementation {
+            parameter: TypeParameterId::decode(dec)?,
+            implementation: TraitImplementation::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for DisjointTraits {
+    fn encode(&self, enc: &mut Encoder) {
+        self.left.encode(enc);
+        self.right.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+    }
+}
+
+impl Decode for DisjointTraits {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(DisjointTraits {
+            left: TraitId::decode(dec)?,
+            right: TraitId::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for NegativeImplementation {
+    fn encode(&self, enc: &mut Encoder) {
+        self.class.encode(enc);
+        self.trait_id.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+    }
+}
+
+impl Decode for NegativeImplementation {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(NegativeImplementation {
+            class: ClassId::decode(dec)?,
+            trait_id: TraitId::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Exclusions {
+    fn encode(&self, enc: &mut Encoder) {
+        self.disjoint.encode(enc);
+        self.negative.encode(enc);
+    }
+}
+
+impl Decode for Exclusions {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Exclusions {
+            disjoint: Vec::decode(dec)?,
+            negative: Vec::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Callee {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Callee::Static(id) => {
+                enc.tag(0);
+                id.encode(enc);
+            }
+            Callee::Dynamic(trait_id, id) => {
+                enc.tag(1);
+                trait_id.encode(enc);
+                id.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for Callee {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let callee = match dec.tag()? {
+            0 => Callee::Static(MethodId::decode(dec)?),
+            1 => Callee::Dynamic(TraitId::decode(dec)?, MethodId::decode(dec)?),
+            tag => return dec.invalid(tag, "callee"),
+        };
+
+        Ok(callee)
+    }
+}
+
+impl Encode for CallGraph {
+    fn encode(&self, enc: &mut Encoder) {
+        self.edges.encode(enc);
+    }
+}
+
+impl Decode for CallGraph {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(CallGraph { edges: HashMap::decode(dec)? })
+    }
+}
+
+impl Encode for Argument {
+    fn encode(&self, enc: &mut Encoder) {
+        self.index.encode(enc);
+        self.name.encode(enc);
+        self.value_type.encode(enc);
+        self.variable.encode(enc);
+    }
+}
+
+impl Decode for Argument {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Argument {
+            index: usize::decode(dec)?,
+            name: String::decode(dec)?,
+            value_type: TypeRef::decode(dec)?,
+            variable: VariableId::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Arguments {
+    fn encode(&self, enc: &mut Encoder) {
+        self.mapping.encode(enc);
+    }
+}
+
+impl Decode for Arguments {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Arguments { mapping: IndexMap::decode(dec)? })
+    }
+}
+
+impl Encode for TypePlaceholder {
+    fn encode(&self, enc: &mut Encoder) {
+        self.value.get().encode(enc);
+        self.parent.get().encode(enc);
+        self.rank.get().encode(enc);
+        self.required.get().encode(enc);
+    }
+}
+
+impl Decode for TypePlaceholder {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypePlaceholder {
+            value: Cell::new(TypeRef::decode(dec)?),
+            parent: Cell::new(u32::decode(dec)?),
+            rank: Cell::new(u8::decode(dec)?),
+            required: Cell::new(Option::decode(dec)?),
+        })
+    }
+}
+
+impl Encode for TypeParameter {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.requirements.encode(enc);
+        self.mutable.encode(enc);
+        self.stack.encode(enc);
+        self.constant.encode(enc);
+        self.original.encode(enc);
+        self.variance.encode(enc);
+        self.explicit_variance.encode(enc);
+    }
+}
+
+impl Decode for TypeParameter {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypeParameter {
+            name: String::decode(dec)?,
+            requirements: Vec::decode(dec)?,
+            mutable: bool::decode(dec)?,
+            stack: bool::decode(dec)?,
+            constant: bool::decode(dec)?,
+            original: Option::decode(dec)?,
+            variance: Variance::decode(dec)?,
+            explicit_variance: bool::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Trait {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.documentation.encode(enc);
+        self.deprecated.encode(enc);
+        self.implemented_by.encode(enc);
+        self.blanket_implementations.encode(enc);
+        self.visibility.encode(enc);
+        self.type_parameters.encode(enc);
+        self.required_traits.encode(enc);
+        self.default_methods.encode(enc);
+        self.required_methods.encode(enc);
+        self.default_constants.encode(enc);
+        self.required_constants.encode(enc);
+        self.associated_types.encode(enc);
+        self.associated_type_defaults.encode(enc);
+        self.self_type.encode(enc);
+        self.inherited_type_arguments.encode(enc);
+    }
+}
+
+impl Decode for Trait {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Trait {
+            name: String::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            deprecated: Option::decode(dec)?,
+            implemented_by: Vec::decode(dec)?,
+            blanket_implementations: Vec::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            type_parameters: IndexMap::decode(dec)?,
+            required_traits: Vec::decode(dec)?,
+            default_methods: IndexMap::decode(dec)?,
+            required_methods: IndexMap::decode(dec)?,
+            default_constants: IndexMap::decode(dec)?,
+            required_constants: IndexMap::decode(dec)?,
+            associated_types: IndexMap::decode(dec)?,
+            associated_type_defaults: TypeArguments::decode(dec)?,
+            self_type: Option::decode(dec)?,
+            inherited_type_arguments: TypeArguments::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for TypeAlias {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.visibility.encode(enc);
+        self.type_parameters.encode(enc);
+        self.aliased.encode(enc);
+    }
+}
+
+impl Decode for TypeAlias {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(TypeAlias {
+            name: String::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            type_parameters: IndexMap::decode(dec)?,
+            aliased: TypeRef::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for ForeignFunction {
+    fn encode(&self, enc: &mut Encoder) {
+        self.arguments.encode(enc);
+        self.return_type.encode(enc);
+        self.variadic.encode(enc);
+    }
+}
+
+impl Decode for ForeignFunction {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(ForeignFunction {
+            arguments: Vec::decode(dec)?,
+            return_type: TypeRef::decode(dec)?,
+            variadic: bool::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Union {
+    fn encode(&self, enc: &mut Encoder) {
+        self.members.encode(enc);
+    }
+}
+
+impl Decode for Union {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Union { members: Vec::decode(dec)? })
+    }
+}
+
+impl Encode for Tuple {
+    fn encode(&self, enc: &mut Encoder) {
+        self.elements.encode(enc);
+    }
+}
+
+impl Decode for Tuple {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Tuple { elements: Vec::decode(dec)? })
+    }
+}
+
+impl Encode for Field {
+    fn encode(&self, enc: &mut Encoder) {
+        self.index.encode(enc);
+        self.name.encode(enc);
+        self.value_type.encode(enc);
+        self.visibility.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.documentation.encode(enc);
+        self.default.encode(enc);
+        self.getter.encode(enc);
+    }
+}
+
+impl Decode for Field {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Field {
+            index: usize::decode(dec)?,
+            name: String::decode(dec)?,
+            value_type: TypeRef::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            default: Option::decode(dec)?,
+            getter: Option::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Constructor {
+    fn encode(&self, enc: &mut Encoder) {
+        self.id.encode(enc);
+        (self.tag as u64).encode(enc);
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.location.encode(enc);
+        self.arguments.encode(enc);
+        self.argument_names.encode(enc);
+    }
+}
+
+impl Decode for Constructor {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Constructor {
+            id: u32::decode(dec)?,
+            tag: u64::decode(dec)? as i64,
+            name: String::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            location: Location::decode(dec)?,
+            arguments: Vec::decode(dec)?,
+            argument_names: Vec::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Class {
+    fn encode(&self, enc: &mut Encoder) {
+        self.kind.encode(enc);
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.deprecated.encode(enc);
+        self.destructor.encode(enc);
+        self.storage.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.visibility.encode(enc);
+        self.fields.encode(enc);
+        self.type_parameters.encode(enc);
+        self.methods.encode(enc);
+        self.implemented_traits.encode(enc);
+        self.overlapping_implementations.encode(enc);
+        self.constructors.encode(enc);
+        self.specializations.encode(enc);
+        self.specialization_source.encode(enc);
+        self.shapes.encode(enc);
+        self.layout_options.encode(enc);
+        self.niche.encode(enc);
+        self.synthesized_constructor.encode(enc);
+        self.computed_fields.encode(enc);
+        self.drop_order.encode(enc);
+        self.constants.encode(enc);
+        self.conditions.encode(enc);
+    }
+}
+
+impl Decode for Class {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Class {
+            kind: ClassKind::decode(dec)?,
+            name: String::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            deprecated: Option::decode(dec)?,
+            destructor: bool::decode(dec)?,
+            storage: Storage::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            fields: IndexMap::decode(dec)?,
+            type_parameters: IndexMap::decode(dec)?,
+            methods: HashMap::decode(dec)?,
+            implemented_traits: HashMap::decode(dec)?,
+            overlapping_implementations: Vec::decode(dec)?,
+            constructors: IndexMap::decode(dec)?,
+            specializations: HashMap::decode(dec)?,
+            specialization_source: Option::decode(dec)?,
+            shapes: Vec::decode(dec)?,
+            layout_options: LayoutOptions::decode(dec)?,
+            niche: Option::decode(dec)?,
+            synthesized_constructor: Option::decode(dec)?,
+            computed_fields: IndexMap::decode(dec)?,
+            drop_order: DropOrder::decode(dec)?,
+            constants: IndexMap::decode(dec)?,
+            conditions: Conditions::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Method {
+    fn encode(&self, enc: &mut Encoder) {
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.deprecated.encode(enc);
+        self.kind.encode(enc);
+        self.call_convention.encode(enc);
+        self.visibility.encode(enc);
+        self.inline.encode(enc);
+        self.inline_threshold.encode(enc);
+        self.type_parameters.encode(enc);
+        self.arguments.encode(enc);
+        self.bounds.encode(enc);
+        self.return_type.encode(enc);
+        self.source.encode(enc);
+        self.main.encode(enc);
+        self.variadic.encode(enc);
+        self.receiver.encode(enc);
+        self.throw_kind.encode(enc);
+        self.effects.encode(enc);
+        self.escapes.encode(enc);
+        self.used.encode(enc);
+        self.field_types.encode(enc);
+        self.specializations.encode(enc);
+        self.shapes.encode(enc);
+        self.moved_fields.encode(enc);
+        self.conditions.encode(enc);
+    }
+}
+
+impl Decode for Method {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Method {
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            name: String::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            deprecated: Option::decode(dec)?,
+            kind: MethodKind::decode(dec)?,
+            call_convention: CallConvention::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            inline: Inline::decode(dec)?,
+            inline_threshold: Option::decode(dec)?,
+            type_parameters: IndexMap::decode(dec)?,
+            arguments: Arguments::decode(dec)?,
+            bounds: TypeBounds::decode(dec)?,
+            return_type: TypeRef::decode(dec)?,
+            source: MethodSource::decode(dec)?,
+            main: bool::decode(dec)?,
+            variadic: bool::decode(dec)?,
+            receiver: TypeRef::decode(dec)?,
+            throw_kind: ThrowKind::decode(dec)?,
+            effects: Effects::decode(dec)?,
+            escapes: EscapeTable::decode(dec)?,
+            used: bool::decode(dec)?,
+            field_types: HashMap::decode(dec)?,
+            specializations: HashMap::decode(dec)?,
+            shapes: Vec::decode(dec)?,
+            moved_fields: Vec::decode(dec)?,
+            conditions: Conditions::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for CaptureMode {
+    fn encode(&self, enc: &mut Encoder) {
+        let tag = match self {
+            CaptureMode::Move => 0,
+            CaptureMode::Ref => 1,
+            CaptureMode::Mut => 2,
+        };
+
+        enc.tag(tag);
+    }
+}
+
+impl Decode for CaptureMode {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let mode = match dec.tag()? {
+            0 => CaptureMode::Move,
+            1 => CaptureMode::Ref,
+            2 => CaptureMode::Mut,
+            tag => return dec.invalid(tag, "capture mode"),
+        };
+
+        Ok(mode)
+    }
+}
+
+impl Encode for Capture {
+    fn encode(&self, enc: &mut Encoder) {
+        self.variable.encode(enc);
+        self.captured_as.encode(enc);
+        self.mode.encode(enc);
+        self.locations.encode(enc);
+    }
+}
+
+impl Decode for Capture {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Capture {
+            variable: VariableId::decode(dec)?,
+            captured_as: TypeRef::decode(dec)?,
+            mode: CaptureMode::decode(dec)?,
+            locations: Vec::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Closure {
+    fn encode(&self, enc: &mut Encoder) {
+        self.moving.encode(enc);
+        self.type_parameters.encode(enc);
+        self.captured.encode(enc);
+        self.captured_self_type.encode(enc);
+        self.arguments.encode(enc);
+        self.return_type.encode(enc);
+    }
+}
+
+impl Decode for Closure {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Closure {
+            moving: bool::decode(dec)?,
+            type_parameters: Vec::decode(dec)?,
+            captured: Vec::decode(dec)?,
+            captured_self_type: Option::decode(dec)?,
+            arguments: Arguments::decode(dec)?,
+            return_type: TypeRef::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Variable {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.value_type.encode(enc);
+        self.mutable.encode(enc);
+        self.location.encode(enc);
+    }
+}
+
+impl Decode for Variable {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Variable {
+            name: String::decode(dec)?,
+            value_type: TypeRef::decode(dec)?,
+            mutable: bool::decode(dec)?,
+            location: Location::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Constant {
+    fn encode(&self, enc: &mut Encoder) {
+        self.id.encode(enc);
+        self.module.encode(enc);
+        self.location.encode(enc);
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.deprecated.encode(enc);
+        self.value_type.encode(enc);
+        self.visibility.encode(enc);
+        self.value.encode(enc);
+    }
+}
+
+impl Decode for Constant {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Constant {
+            id: u16::decode(dec)?,
+            module: ModuleId::decode(dec)?,
+            location: Location::decode(dec)?,
+            name: String::decode(dec)?,
+            documentation: Docs::decode(dec)?,
+            deprecated: Option::decode(dec)?,
+            value_type: TypeRef::decode(dec)?,
+            visibility: Visibility::decode(dec)?,
+            value: Option::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Effects {
+    fn encode(&self, enc: &mut Encoder) {
+        self.0.encode(enc);
+    }
+}
+
+impl Decode for Effects {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Effects(u8::decode(dec)?))
+    }
+}
+
+impl Encode for Escape {
+    fn encode(&self, enc: &mut Encoder) {
+        self.0.encode(enc);
+    }
+}
+
+impl Decode for Escape {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Escape(u8::decode(dec)?))
+    }
+}
+
+impl Encode for EscapeFlow {
+    fn encode(&self, enc: &mut Encoder) {
+        self.variable.encode(enc);
+        self.callee.encode(enc);
+        self.index.encode(enc);
+    }
+}
+
+impl Decode for EscapeFlow {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(EscapeFlow {
+            variable: VariableId::decode(dec)?,
+            callee: MethodId::decode(dec)?,
+            index: usize::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for EscapeTable {
+    fn encode(&self, enc: &mut Encoder) {
+        self.variables.encode(enc);
+        self.flows.encode(enc);
+    }
+}
+
+impl Decode for EscapeTable {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(EscapeTable {
+            variables: HashMap::decode(dec)?,
+            flows: Vec::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for ThrowKind {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ThrowKind::Unknown => enc.tag(0),
+            ThrowKind::Infer(id) => {
+                enc.tag(1);
+                id.encode(enc);
+            }
+            ThrowKind::Option(typ) => {
+                enc.tag(2);
+                typ.encode(enc);
+            }
+            ThrowKind::Result(ok, err) => {
+                enc.tag(3);
+                ok.encode(enc);
+                err.encode(enc);
+            }
+            ThrowKind::Never => enc.tag(4),
+        }
+    }
+}
+
+impl Decode for ThrowKind {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(ThrowKind::Unknown),
+            1 => Ok(ThrowKind::Infer(TypePlaceholderId::decode(dec)?)),
+            2 => Ok(ThrowKind::Option(TypeRef::decode(dec)?)),
+            3 => Ok(ThrowKind::Result(
+                TypeRef::decode(dec)?,
+                TypeRef::decode(dec)?,
+            )),
+            4 => Ok(ThrowKind::Never),
+            tag => dec.invalid(tag, "throw kind"),
+        }
+    }
+}
+
+impl Encode for Value {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Value::Int(v) => {
+                enc.tag(0);
+                (*v as u64).encode(enc);
+            }
+            Value::Float(v) => {
+                enc.tag(1);
+                v.to_bits().encode(enc);
+            }
+            Value::String(v) => {
+                enc.tag(2);
+                v.encode(enc);
+            }
+            Value::Bool(v) => {
+                enc.tag(3);
+                v.encode(enc);
+            }
+            Value::Array(v) => {
+                enc.tag(4);
+                v.encode(enc);
+            }
+            Value::Char(v) => {
+                enc.tag(5);
+                (*v as u32).encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for Value {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(Value::Int(u64::decode(dec)? as i64)),
+            1 => Ok(Value::Float(f64::from_bits(u64::decode(dec)?))),
+            2 => Ok(Value::String(String::decode(dec)?)),
+            3 => Ok(Value::Bool(bool::decode(dec)?)),
+            4 => Ok(Value::Array(Vec::decode(dec)?)),
+            5 => {
+                let val = u32::decode(dec)?;
+
+                char::from_u32(val).map(Value::Char).ok_or_else(|| {
+                    format!("{} isn't a valid Unicode scalar value", val)
+                })
+            }
+            tag => dec.invalid(tag, "constant value"),
+        }
+    }
+}
+
+impl Encode for Conditions {
+    fn encode(&self, enc: &mut Encoder) {
+        self.tags.encode(enc);
+    }
+}
+
+impl Decode for Conditions {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Conditions { tags: Vec::decode(dec)? })
+    }
+}
+
+impl Encode for FieldDefault {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            FieldDefault::Value(v) => {
+                enc.tag(0);
+                v.encode(enc);
+            }
+            FieldDefault::Constant(v) => {
+                enc.tag(1);
+                v.encode(enc);
+            }
+        }
+    }
+}
+
+impl Decode for FieldDefault {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        match dec.tag()? {
+            0 => Ok(FieldDefault::Value(Value::decode(dec)?)),
+            1 => Ok(FieldDefault::Constant(ConstantId::decode(dec)?)),
+            tag => dec.invalid(tag, "field default"),
+        }
+    }
+}
+
+impl Encode for Deprecation {
+    fn encode(&self, enc: &mut Encoder) {
+        self.message.encode(enc);
+        self.since.encode(enc);
+    }
+}
+
+impl Decode for Deprecation {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Deprecation {
+            message: String::decode(dec)?,
+            since: Option::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Target {
+    fn encode(&self, enc: &mut Encoder) {
+        self.os.encode(enc);
+        self.arch.encode(enc);
+        self.pointer_width.encode(enc);
+        self.endianness.encode(enc);
+    }
+}
+
+impl Decode for Target {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Target::new(
+            String::decode(dec)?,
+            String::decode(dec)?,
+            u32::decode(dec)?,
+            Endianness::decode(dec)?,
+        ))
+    }
+}
+
+impl Encode for SpecializationLimits {
+    fn encode(&self, enc: &mut Encoder) {
+        self.specializations.encode(enc);
+        self.depth.encode(enc);
+    }
+}
+
+impl Decode for SpecializationLimits {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(SpecializationLimits {
+            specializations: usize::decode(dec)?,
+            depth: usize::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for InlinePolicy {
+    fn encode(&self, enc: &mut Encoder) {
+        self.threshold.encode(enc);
+        self.call_count.encode(enc);
+        self.argument_weight.encode(enc);
+    }
+}
+
+impl Decode for InlinePolicy {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(InlinePolicy {
+            threshold: u16::decode(dec)?,
+            call_count: u16::decode(dec)?,
+            argument_weight: u16::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for LayoutOptions {
+    fn encode(&self, enc: &mut Encoder) {
+        self.packed.encode(enc);
+        self.align.encode(enc);
+    }
+}
+
+impl Decode for LayoutOptions {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(LayoutOptions {
+            packed: bool::decode(dec)?,
+            align: Option::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Niche {
+    fn encode(&self, enc: &mut Encoder) {
+        self.payload.encode(enc);
+        self.empty.encode(enc);
+    }
+}
+
+impl Decode for Niche {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Niche {
+            payload: ConstructorId::decode(dec)?,
+            empty: ConstructorId::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Docs {
+    fn encode(&self, enc: &mut Encoder) {
+        self.source.encode(enc);
+    }
+}
+
+impl Decode for Docs {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Docs::parse(String::decode(dec)?))
+    }
+}
+
+impl Encode for ModuleSymbol {
+    fn encode(&self, enc: &mut Encoder) {
+        self.symbol.encode(enc);
+        self.used.encode(enc);
+        self.location.encode(enc);
+        self.origin.encode(enc);
+        self.reexport.encode(enc);
+        self.shadowable.encode(enc);
+        self.conditions.encode(enc);
+    }
+}
+
+impl Decode for ModuleSymbol {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(ModuleSymbol {
+            symbol: Symbol::decode(dec)?,
+            used: bool::decode(dec)?,
+            location: Option::decode(dec)?,
+            origin: Option::decode(dec)?,
+            reexport: bool::decode(dec)?,
+            shadowable: bool::decode(dec)?,
+            conditions: Conditions::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Module {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.documentation.encode(enc);
+        self.method_symbol_name.encode(enc);
+        self.class.encode(enc);
+        self.file.encode(enc);
+        self.constants.encode(enc);
+        self.symbols.encode(enc);
+        self.extern_methods.encode(enc);
+        self.dependencies.encode(enc);
+        self.package.encode(enc);
+    }
+}
+
+impl Encode for Package {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.version.encode(enc);
+        self.modules.encode(enc);
+    }
+}
+
+impl Decode for Package {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Package {
+            name: String::decode(dec)?,
+            version: Option::decode(dec)?,
+            modules: Vec::decode(dec)?,
+        })
+    }
+}
+
+impl Decode for Module {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        Ok(Module {
+            name: ModuleName::decode(dec)?,
+            documentation: String::decode(dec)?,
+            method_symbol_name: ModuleName::decode(dec)?,
+            class: ClassId::decode(dec)?,
+            file: PathBuf::decode(dec)?,
+            constants: Vec::decode(dec)?,
+            symbols: HashMap::decode(dec)?,
+            extern_methods: HashMap::decode(dec)?,
+            dependencies: Vec::decode(dec)?,
+            package: Option::decode(dec)?,
+        })
+    }
+}
+
+impl Encode for Database {
+    fn encode(&self, enc: &mut Encoder) {
+        self.modules.encode(enc);
+        self.packages.encode(enc);
+        self.traits.encode(enc);
+        self.classes.encode(enc);
+        self.type_aliases.encode(enc);
+        self.unions.encode(enc);
+        self.tuples.encode(enc);
+        self.builtin_classes.encode(enc);
+        self.builtin_shapes.encode(enc);
+        self.foreign_functions.encode(enc);
+        self.type_parameters.encode(enc);
+        self.type_arguments.encode(enc);
+        self.methods.encode(enc);
+        self.fields.encode(enc);
+        self.closures.encode(enc);
+        self.variables.encode(enc);
+        self.constants.encode(enc);
+        self.type_placeholders.encode(enc);
+        self.constructors.encode(enc);
+        self.specialization_limits.encode(enc);
+        self.inline_policy.encode(enc);
+        self.exclusions.encode(enc);
+        self.call_graph.encode(enc);
+        self.main_module.encode(enc);
+        self.main_method.encode(enc);
+        self.main_class.encode(enc);
+        self.build_tags.encode(enc);
+        self.target.encode(enc);
+    }
+}
+
+impl Decode for Database {
+    fn decode(dec: &mut Decoder) -> Result<Self, String> {
+        let modules: Vec<Module> = Vec::decode(dec)?;
+
+        // The module mapping and intrinsics are derived from other data, so
+        // there's no need to store them.
+        let module_mapping = modules
+            .iter()
+            .enumerate()
+            .map(|(idx, m)| (m.name.to_string(), ModuleId(idx as u32)))
+            .collect();
+        let mut module_versions: HashMap<String, Vec<ModuleId>> =
+            HashMap::new();
+
+        for (idx, m) in modules.iter().enumerate() {
+            module_versions
+                .entry(m.name.logical_name().to_string())
+                .or_default()
+                .push(ModuleId(idx as u32));
+        }
+
+        let mut db = Database {
+            modules,
+            module_mapping,
+            module_versions,
+            packages: Vec::decode(dec)?,
+            traits: Vec::decode(dec)?,
+            classes: Vec::decode(dec)?,
+            type_aliases: Vec::decode(dec)?,
+            unions: Vec::decode(dec)?,
+            tuples: Vec::decode(dec)?,
+            tuple_ids: HashMap::new(),
+            builtin_classes: HashMap::decode(dec)?,
+            builtin_shapes: HashMap::decode(dec)?,
+            foreign_functions: Vec::decode(dec)?,
+            type_parameters: Vec::decode(dec)?,
+            type_arguments: Arena::decode(dec)?,
+            methods: Vec::decode(dec)?,
+            fields: Vec::decode(dec)?,
+            closures: Vec::decode(dec)?,
+            variables: Vec::decode(dec)?,
+            constants: Vec::decode(dec)?,
+            intrinsics: Intrinsic::mapping(),
+            type_placeholders: Vec::decode(dec)?,
+            placeholder_assignments: AtomicU32::new(0),
+            constructors: Vec::decode(dec)?,
+            specialization_limits: SpecializationLimits::decode(dec)?,
+            inline_policy: InlinePolicy::decode(dec)?,
+            exclusions: Exclusions::decode(dec)?,
+            call_graph: CallGraph::decode(dec)?,
+            specialization_chain: Vec::new(),
+            specialization_errors: Vec::new(),
+            origins: None,
+            references: None,
+            main_module: Option::decode(dec)?,
+            main_method: Option::decode(dec)?,
+            main_class: Option::decode(dec)?,
+            build_tags: HashSet::decode(dec)?,
+            pointer_size: 0,
+            target: Target::decode(dec)?,
+        };
+
+        // The pointer size is derived from the target, instead of the host
+        // the cache is loaded on.
+        db.pointer_size = db.target.pointer_size();
+
+        // Like the module mapping, the tuple IDs are derived from the tuples.
+        db.tuple_ids = db
+            .tuples
+            .iter()
+            .enumerate()
+            .map(|(idx, t)| (t.elements.clone(), TupleId(idx as u32)))
+            .collect();
+
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        generic_instance_id, implement, new_class, new_module, new_parameter,
+        new_trait, owned, trait_instance,
+    };
+    use crate::Block;
+    use crate::InternedTypeArguments;
+
+    fn hashes(values: &[(&str, u64)]) -> ModuleHashes {
+        values.iter().map(|&(n, h)| (ModuleName::new(n), h)).collect()
+    }
+
+    fn valid(loaded: Loaded) -> Database {
+        match loaded {
+            Loaded::Valid(db) => *db,
+            Loaded::Stale(_) => panic!("the cache is stale"),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut db = Database::new();
+        let decimal = db.register_builtin(
+            "Decimal".to_string(),
+            ClassKind::Regular,
+            Storage::Stack,
+            Some(Shape::Int(128, Sign::Signed)),
+        );
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Foo");
+        let to_str = new_trait(&mut db, "ToString");
+        let param = new_parameter(&mut db, "T");
+        let array = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![TypeRef::int()],
+        ));
+        let method = Method::alloc(
+            &mut db,
+            module,
+            Location::new(&(1..=2), &(3..=4)),
+            "foo".to_string(),
+            Visibility::Public,
+            MethodKind::Instance,
+        );
+
+        class.set_module(&mut db, module);
+        class.new_field(
+            &mut db,
+            "numbers".to_string(),
+            0,
+            array,
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+        class.add_method(&mut db, "foo".to_string(), method);
+        module.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(class));
+        method.new_argument(
+            &mut db,
+            "value".to_string(),
+            TypeRef::int(),
+            TypeRef::int(),
+            Location::default(),
+        );
+        method.set_return_type(&mut db, array);
+        implement(&mut db, trait_instance(to_str), class);
+        param.add_requirements(&mut db, vec![trait_instance(to_str)]);
+        db.set_main_class(class);
+
+        let tuple = TypeRef::tuple(&mut db, vec![TypeRef::int(); 9]);
+        let hashes = hashes(&[("foo", 42)]);
+        let bytes = encode(&db, &hashes);
+        let mut new = valid(decode(&bytes, &hashes).unwrap());
+
+        assert_eq!(new.module("foo"), module);
+        assert_eq!(
+            new.class_in_module("foo", "Foo").field_names(&new),
+            vec!["numbers".to_string()]
+        );
+        assert_eq!(
+            class.field(&new, "numbers").unwrap().value_type(&new),
+            array
+        );
+        assert_eq!(class.method(&new, "foo"), Some(method));
+        assert_eq!(method.location(&new), Location::new(&(1..=2), &(3..=4)));
+        assert_eq!(method.return_type(&new), array);
+        assert_eq!(method.number_of_arguments(&new), 1);
+        assert!(class.trait_implementation(&new, to_str).is_some());
+        assert_eq!(to_str.implemented_by(&new), &vec![class]);
+        assert_eq!(param.requirements(&new), vec![trait_instance(to_str)]);
+        assert_eq!(new.main_class(), Some(class));
+        assert_eq!(new.number_of_classes(), db.number_of_classes());
+        assert_eq!(
+            new.intrinsic("int_wrapping_add"),
+            db.intrinsic("int_wrapping_add")
+        );
+        assert_eq!(encode(&new, &hashes).len(), bytes.len());
+        assert_eq!(TypeRef::tuple(&mut new, vec![TypeRef::int(); 9]), tuple);
+        assert_eq!(new.number_of_tuples(), 1);
+        assert_eq!(new.builtin_class("Decimal"), Some(decimal));
+        assert_eq!(new.builtin_class("Int"), Some(ClassId::int()));
+        assert_eq!(
+            owned(TypeId::ClassInstance(ClassInstance::new(decimal))).shape(
+                &new,
+                &mut InternedTypeArguments::new(),
+                &HashMap::new()
+            ),
+            Shape::Int(128, Sign::Signed)
+        );
+    }
+
+    #[test]
+    fn test_encode_decode_settings() {
+        let mut db = Database::new();
+        let limits = SpecializationLimits { specializations: 8, depth: 4 };
+        let policy =
+            InlinePolicy { threshold: 10, call_count: 2, argument_weight: 3 };
+
+        db.define_build_tag("foo".to_string());
+        db.set_specialization_limits(limits);
+        db.set_inline_policy(policy);
+
+        let hashes = hashes(&[]);
+        let new = valid(decode(&encode(&db, &hashes), &hashes).unwrap());
+
+        assert!(new.is_build_tag_defined("foo"));
+        assert!(!new.is_build_tag_defined("bar"));
+        assert_eq!(new.specialization_limits(), limits);
+        assert_eq!(new.inline_policy(), policy);
+    }
+
+    #[test]
+    fn test_encode_decode_target() {
+        let mut db = Database::new();
+        let target = Target::new(
+            "linux".to_string(),
+            "arm".to_string(),
+            32,
+            Endianness::Big,
+        );
+
+        db.set_target(target.clone());
+
+        let hashes = hashes(&[]);
+        let new = valid(decode(&encode(&db, &hashes), &hashes).unwrap());
+
+        assert_eq!(new.target(), &target);
+        assert_eq!(new.pointer_size(), 4);
+        assert!(new.is_build_tag_defined("linux"));
+        assert!(new.is_build_tag_defined("arm"));
+        assert!(new.is_build_tag_defined("unix"));
+    }
+
+    #[test]
+    fn test_decode_stale() {
+        let mut db = Database::new();
+
+        new_module(&mut db, "foo");
+        new_module(&mut db, "bar");
+
+        let bytes = encode(&db, &hashes(&[("foo", 1), ("bar", 2)]));
+        let stale = |hashes| match decode(&bytes, &hashes).unwrap() {
+            Loaded::Stale(names) => names,
+            Loaded::Valid(_) => Vec::new(),
+        };
+
+        assert_eq!(
+            stale(hashes(&[("foo", 1), ("bar", 3)])),
+            vec![ModuleName::new("bar")]
+        );
+        assert_eq!(
+            stale(hashes(&[("foo", 1), ("baz", 2)])),
+            vec![ModuleName::new("bar"), ModuleName::new("baz")]
+        );
+        assert!(stale(hashes(&[("foo", 1), ("bar", 2)])).is_empty());
+    }
+
+    #[test]
+    fn test_decode_stale_with_dependencies() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let baz = new_module(&mut db, "baz");
+
+        new_module(&mut db, "quix");
+        bar.add_dependency(&mut db, foo);
+        baz.add_dependency(&mut db, bar);
+
+        let old = hashes(&[("foo", 1), ("bar", 2), ("baz", 3), ("quix", 4)]);
+        let new = hashes(&[("foo", 5), ("bar", 2), ("baz", 3), ("quix", 4)]);
+        let bytes = encode(&db, &old);
+
+        assert!(matches!(
+            decode(&bytes, &new),
+            Ok(Loaded::Stale(names)) if names == vec![
+                ModuleName::new("bar"),
+                ModuleName::new("baz"),
+                ModuleName::new("foo"),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_encode_is_deterministic() {
+        let mut db = Database::new();
+        let names: Vec<_> = (0..32).map(|i| format!("m{}", i)).collect();
+
+        for name in &names {
+            new_module(&mut db, name);
+        }
+
+        let forward: ModuleHashes =
+            names.iter().map(|n| (ModuleName::new(n), 1)).collect();
+        let backward: ModuleHashes =
+            names.iter().rev().map(|n| (ModuleName::new(n), 1)).collect();
+
+        assert_eq!(encode(&db, &forward), encode(&db, &backward));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let db = Database::new();
+        let hashes = ModuleHashes::new();
+        let mut bytes = encode(&db, &hashes);
+
+        assert!(decode(&bytes[..bytes.len() - 1], &hashes).is_err());
+        assert!(decode(b"foo", &hashes).is_err());
+
+        bytes.push(0);
+        assert!(decode(&bytes, &hashes).is_err());
+
+        bytes[SIGNATURE.len()] = 0;
+        assert!(matches!(
+            decode(&bytes, &hashes),
+            Ok(Loaded::Stale(names)) if names.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let db = Database::new();
+        let hashes = ModuleHashes::new();
+        let path = std::env::temp_dir().join("inko-test-types-cache");
+
+        save(&path, &db, &hashes).unwrap();
+
+        let new = valid(load(&path, &hashes).unwrap());
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(new.number_of_classes(), db.number_of_classes());
+        assert!(load(&path, &hashes).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! The graph of calls between methods.
+//!
+//! While type-checking method bodies, the compiler records the methods called
+//! by each method. The resulting graph is used to determine which methods are
+//! reachable from the main method, which methods are (mutually) recursive and
+//! thus shouldn't be inlined indefinitely, and which methods are never used.
+//!
+//! Calls using dynamic dispatch are recorded as calls to the trait method. Such
+//! calls are treated as calls to the methods of every class implementing the
+//! trait, as we don't know which implementation is used at runtime.
+use crate::{Database, MethodId, MethodKind, MethodSource, TraitId, TypeId};
+use std::collections::{HashMap, HashSet};
+
+/// A method called by another method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Callee {
+    /// A method called using static dispatch.
+    Static(MethodId),
+
+    /// A method of a trait called using dynamic dispatch.
+    Dynamic(TraitId, MethodId),
+}
+
+/// A graph mapping methods to the methods they call.
+#[derive(Default)]
+pub struct CallGraph {
+    pub(crate) edges: HashMap<MethodId, Vec<Callee>>,
+}
+
+impl CallGraph {
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Removes the calls made by and to the given methods.
+    pub(crate) fn remove_methods(&mut self, methods: &HashSet<MethodId>) {
+        self.edges.retain(|caller, callees| {
+            callees.retain(|c| match c {
+                Callee::Static(id) | Callee::Dynamic(_, id) => {
+                    !methods.contains(id)
+                }
+            });
+
+            !methods.contains(caller)
+        });
+    }
+
+    /// Returns the methods called by the given method, in the order they are
+    /// first called in.
+    pub fn callees(&self, method: MethodId) -> &[Callee] {
+        self.edges.get(&method).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the methods that may be called at runtime by the given method.
+    ///
+    /// Dynamic calls are expanded into the trait method and the methods of all
+    /// classes implementing the trait.
+    pub fn targets(&self, db: &Database, method: MethodId) -> Vec<MethodId> {
+        let mut targets = Vec::new();
+
+        for &callee in self.callees(method) {
+            match callee {
+                Callee::Static(id) => targets.push(id),
+                Callee::Dynamic(trait_id, id) => {
+                    let name = id.name(db);
+
+                    targets.push(id);
+                    targets.extend(
+                        trait_id
+                            .implemented_by(db)
+                            .iter()
+                            .filter_map(|c| c.method(db, name)),
+                    );
+                }
+            }
+        }
+
+        targets.dedup();
+        targets
+    }
+
+    /// Returns all methods reachable from the given methods, including the
+    /// methods themselves.
+    pub fn reachable(
+        &self,
+        db: &Database,
+        roots: &[MethodId],
+    ) -> HashSet<MethodId> {
+        let mut found: HashSet<MethodId> = roots.iter().cloned().collect();
+        let mut work = roots.to_vec();
+
+        while let Some(method) = work.pop() {
+            for id in self.targets(db, method) {
+                if found.insert(id) {
+                    work.push(id);
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Returns `true` if the given method may call itself, either directly or
+    /// through other methods.
+    pub fn is_recursive(&self, db: &Database, method: MethodId) -> bool {
+        self.reachable(db, &self.targets(db, method)).contains(&method)
+    }
+
+    /// Returns the groups of methods that call each other recursively.
+    ///
+    /// Each group is sorted, and the groups are sorted by their first method.
+    /// Methods that only call themselves are included as a group of one
+    /// method.
+    pub fn cycles(&self, db: &Database) -> Vec<Vec<MethodId>> {
+        let mut methods: Vec<_> = self.edges.keys().cloned().collect();
+
+        methods.sort_by_key(|m| m.0);
+
+        let mut tarjan = Tarjan::new(self, db);
+
+        for method in methods {
+            if !tarjan.indexes.contains_key(&method) {
+                tarjan.visit(method);
+            }
+        }
+
+        let mut cycles: Vec<_> = tarjan
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.targets(db, c[0]).contains(&c[0]))
+            .map(|mut c| {
+                c.sort_by_key(|m| m.0);
+                c
+            })
+            .collect();
+
+        cycles.sort_by_key(|c| c[0].0);
+        cycles
+    }
+}
+
+/// Tarjan's algorithm for finding the strongly connected components of the
+/// call graph.
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    db: &'a Database,
+    index: usize,
+    indexes: HashMap<MethodId, usize>,
+    lowlinks: HashMap<MethodId, usize>,
+    stack: Vec<MethodId>,
+    on_stack: HashSet<MethodId>,
+    components: Vec<Vec<MethodId>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn new(graph: &'a CallGraph, db: &'a Database) -> Tarjan<'a> {
+        Tarjan {
+            graph,
+            db,
+            index: 0,
+            indexes: HashMap::new(),
+            lowlinks: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn visit(&mut self, method: MethodId) {
+        self.indexes.insert(method, self.index);
+        self.lowlinks.insert(method, self.index);
+        self.index += 1;
+        self.stack.push(method);
+        self.on_stack.insert(method);
+
+        for target in self.graph.targets(self.db, method) {
+            if !self.indexes.contains_key(&target) {
+                self.visit(target);
+
+                let low = self.lowlinks[&method].min(self.lowlinks[&target]);
+
+                self.lowlinks.insert(method, low);
+            } else if self.on_stack.contains(&target) {
+                let low = self.lowlinks[&method].min(self.indexes[&target]);
+
+                self.lowlinks.insert(method, low);
+            }
+        }
+
+        if self.lowlinks[&method] != self.indexes[&method] {
+            return;
+        }
+
+        let mut component = Vec::new();
+
+        while let Some(id) = self.stack.pop() {
+            self.on_stack.remove(&id);
+            component.push(id);
+
+            if id == method {
+                break;
+            }
+        }
+
+        self.components.push(component);
+    }
+}
+
+impl Database {
+    /// Records a call from one method to another.
+    ///
+    /// The `dynamic` argument specifies if the call uses dynamic dispatch. For
+    /// such calls the callee is expected to be a trait method.
+    pub fn record_call(
+        &mut self,
+        caller: MethodId,
+        callee: MethodId,
+        dynamic: bool,
+    ) {
+        let trait_id = match callee.receiver(self).type_id(self) {
+            Ok(TypeId::Trait(id)) => Some(id),
+            Ok(TypeId::TraitInstance(ins)) => Some(ins.instance_of()),
+            _ => None,
+        };
+        let callee = match trait_id {
+            Some(id) if dynamic => Callee::Dynamic(id, callee),
+            _ => Callee::Static(callee),
+        };
+        let edges = self.call_graph.edges.entry(caller).or_default();
+
+        if !edges.contains(&callee) {
+            edges.push(callee);
+        }
+    }
+
+    pub fn call_graph(&self) -> &CallGraph {
+        &self.call_graph
+    }
+
+    /// Returns the methods reachable from the main method.
+    ///
+    /// If no main method is defined, an empty set is returned.
+    pub fn reachable_methods(&self) -> HashSet<MethodId> {
+        self.main_method
+            .map(|m| self.call_graph.reachable(self, &[m]))
+            .unwrap_or_default()
+    }
+
+    /// Returns the methods that are never called, sorted by their IDs.
+    ///
+    /// Only methods directly defined in the source code are included, as
+    /// methods implementing a trait or defined in a trait may be called
+    /// implicitly (e.g. destructors) or through dynamic dispatch. If no main
+    /// method is defined, an empty list is returned.
+    pub fn dead_methods(&self) -> Vec<MethodId> {
+        if self.main_method.is_none() {
+            return Vec::new();
+        }
+
+        let reachable = self.reachable_methods();
+
+        (0..self.methods.len())
+            .map(|i| MethodId(i as u32))
+            .filter(|&id| {
+                let method = id.get(self);
+
+                !reachable.contains(&id)
+                    && matches!(method.source, MethodSource::Direct)
+                    && !matches!(
+                        method.kind,
+                        MethodKind::Extern | MethodKind::Destructor
+                    )
+                    && !method.name.starts_with('$')
+                    && !matches!(
+                        method.receiver.type_id(self),
+                        Ok(TypeId::Trait(_) | TypeId::TraitInstance(_))
+                    )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        implement, instance, new_class, new_method, new_module, new_trait,
+        owned, trait_instance,
+    };
+    use crate::ModuleId;
+
+    fn method_for(
+        db: &mut Database,
+        module: ModuleId,
+        name: &str,
+        receiver: TypeId,
+    ) -> MethodId {
+        let id = new_method(db, module, name, MethodKind::Instance);
+
+        id.set_receiver(db, owned(receiver));
+        id
+    }
+
+    #[test]
+    fn test_record_call() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let to_string = new_trait(&mut db, "ToString");
+        let main = method_for(&mut db, module, "main", instance(class));
+        let name = method_for(&mut db, module, "name", instance(class));
+        let req = method_for(
+            &mut db,
+            module,
+            "to_string",
+            TypeId::TraitInstance(trait_instance(to_string)),
+        );
+
+        db.record_call(main, name, false);
+        db.record_call(main, name, false);
+        db.record_call(main, req, true);
+        db.record_call(main, name, true);
+
+        assert_eq!(db.call_graph().len(), 1);
+        assert_eq!(
+            db.call_graph().callees(main),
+            &[Callee::Static(name), Callee::Dynamic(to_string, req)]
+        );
+        assert!(db.call_graph().callees(name).is_empty());
+    }
+
+    #[test]
+    fn test_reachable_and_dead_methods() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let to_string = new_trait(&mut db, "ToString");
+        let main = method_for(&mut db, module, "main", instance(class));
+        let used = method_for(&mut db, module, "used", instance(class));
+        let unused = method_for(&mut db, module, "unused", instance(class));
+        let req = method_for(
+            &mut db,
+            module,
+            "to_string",
+            TypeId::TraitInstance(trait_instance(to_string)),
+        );
+        let imp = method_for(&mut db, module, "to_string", instance(class));
+
+        implement(&mut db, trait_instance(to_string), class);
+        class.add_method(&mut db, "to_string".to_string(), imp);
+        db.record_call(main, used, false);
+        db.record_call(used, req, true);
+
+        assert!(db.dead_methods().is_empty());
+
+        db.set_main_method(main);
+
+        let reachable = db.reachable_methods();
+
+        assert!(reachable.contains(&main));
+        assert!(reachable.contains(&used));
+        assert!(reachable.contains(&req));
+        assert!(reachable.contains(&imp));
+        assert!(!reachable.contains(&unused));
+        assert_eq!(db.dead_methods(), vec![unused]);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let a = method_for(&mut db, module, "a", instance(class));
+        let b = method_for(&mut db, module, "b", instance(class));
+        let c = method_for(&mut db, module, "c", instance(class));
+        let d = method_for(&mut db, module, "d", instance(class));
+
+        db.record_call(a, b, false);
+        db.record_call(b, a, false);
+        db.record_call(b, c, false);
+        db.record_call(d, d, false);
+
+        let graph = db.call_graph();
+
+        assert!(graph.is_recursive(&db, a));
+        assert!(graph.is_recursive(&db, b));
+        assert!(!graph.is_recursive(&db, c));
+        assert!(graph.is_recursive(&db, d));
+        assert_eq!(graph.cycles(&db), vec![vec![a, b], vec![d]]);
+    }
+}
@@ -1,18 +1,22 @@
+use crate::diagnostics::Diagnostic;
+use crate::origin::ExpressionKey;
 use crate::{
-    Arguments, ClassInstance, Database, ForeignType, MethodId, Ownership,
+    Arguments, ClassInstance, ClosureId, Database, ForeignFunctionId,
+    ForeignType, MethodId, ModuleId, Ownership, ThrowKind, TraitId,
     TraitInstance, TypeArguments, TypeBounds, TypeId, TypeParameterId,
-    TypePlaceholderId, TypeRef, FLOAT_ID, INT_ID,
+    TypePlaceholderId, TypeRef, Variance, FLOAT_ID, INT_ID,
 };
-use std::collections::HashSet;
+use location::Location;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Subtyping {
     No,
     Yes,
     Once,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum Kind {
     /// A regular type check.
     Regular,
@@ -34,7 +38,7 @@ impl Kind {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 struct Rules {
     /// The rules to apply when performing sub-typing checks.
     subtyping: Subtyping,
@@ -109,10 +113,58 @@ impl Rules {
     }
 }
 
+/// The results of type checks performed using the same environment.
+///
+/// The results of checks may depend on the values assigned to type
+/// placeholders, so the cache is cleared whenever a placeholder is assigned a
+/// value.
+#[derive(Clone, Default)]
+struct Cache {
+    /// The number of placeholder assignments at the time the cache was last
+    /// cleared.
+    assignments: u32,
+    results: HashMap<(TypeRef, TypeRef, Rules), bool>,
+}
+
+impl Cache {
+    fn get(
+        &mut self,
+        db: &Database,
+        key: &(TypeRef, TypeRef, Rules),
+    ) -> Option<bool> {
+        self.invalidate_if_outdated(db);
+        self.results.get(key).cloned()
+    }
+
+    fn insert(
+        &mut self,
+        db: &Database,
+        key: (TypeRef, TypeRef, Rules),
+        value: bool,
+    ) {
+        self.invalidate_if_outdated(db);
+        self.results.insert(key, value);
+    }
+
+    fn invalidate_if_outdated(&mut self, db: &Database) {
+        let assignments = db.placeholder_assignments();
+
+        if self.assignments != assignments {
+            self.assignments = assignments;
+            self.results.clear();
+        }
+    }
+}
+
 /// The type-checking environment.
 ///
 /// This structure contains the type arguments to expose to types that are
 /// checked.
+///
+/// The results of type checks are cached in the environment, so checking the
+/// same types again (e.g. deeply nested generic types) is cheap. If the type
+/// arguments are changed after performing a check, the cache must be cleared
+/// using `Environment::clear_cache()`.
 #[derive(Clone)]
 pub struct Environment {
     /// The type arguments to expose to types on the left-hand side of the
@@ -122,6 +174,8 @@ pub struct Environment {
     /// The type arguments to expose to types on the right-hand side of the
     /// check.
     pub right: TypeArguments,
+
+    cache: Cache,
 }
 
 impl Environment {
@@ -137,11 +191,19 @@ impl Environment {
         left_arguments: TypeArguments,
         right_arguments: TypeArguments,
     ) -> Environment {
-        Environment { left: left_arguments, right: right_arguments }
+        Environment {
+            left: left_arguments,
+            right: right_arguments,
+            cache: Cache::default(),
+        }
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.results.clear();
     }
 
     fn with_left_as_right(&self) -> Environment {
-        Environment { left: self.left.clone(), right: self.left.clone() }
+        Environment::new(self.left.clone(), self.left.clone())
     }
 }
 
@@ -149,6 +211,9 @@ impl Environment {
 pub struct TypeChecker<'a> {
     db: &'a Database,
     checked: HashSet<(TypeRef, TypeRef)>,
+
+    /// The traits of which we're checking the blanket implementations.
+    blanket: Vec<TraitId>,
 }
 
 impl<'a> TypeChecker<'a> {
@@ -182,7 +247,7 @@ impl<'a> TypeChecker<'a> {
     }
 
     pub fn new(db: &'a Database) -> TypeChecker<'a> {
-        TypeChecker { db, checked: HashSet::new() }
+        TypeChecker { db, checked: HashSet::new(), blanket: Vec::new() }
     }
 
     pub fn run(
@@ -282,6 +347,7 @@ impl<'a> TypeChecker<'a> {
             let val = env.left.get(param).unwrap();
 
             env.left.assign(bound, val);
+            env.clear_cache();
 
             let mut env = env.with_left_as_right();
             let rules = Rules::new().with_subtyping();
@@ -302,17 +368,225 @@ impl<'a> TypeChecker<'a> {
         })
     }
 
+    /// Checks if `left` is compatible with `right`, returning a diagnostic if
+    /// this isn't the case.
+    /// Returns `true` if errors of the `thrown` kind can be propagated (e.g.
+    /// using `try`) by a method with the throw kind `method`.
+    pub fn check_throw_propagation(
+        db: &'a Database,
+        thrown: ThrowKind,
+        method: ThrowKind,
+    ) -> bool {
+        match (thrown, method) {
+            (ThrowKind::Option(_), ThrowKind::Option(_)) => true,
+            (
+                ThrowKind::Option(_) | ThrowKind::Result(_, _),
+                ThrowKind::Infer(_),
+            ) => true,
+            (ThrowKind::Result(_, err), ThrowKind::Result(_, ret_err)) => {
+                TypeChecker::check_return(db, err, ret_err)
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if `left` is compatible with `right`, returning a
+    /// `type-mismatch` diagnostic if this isn't the case.
+    ///
+    /// Most of the compiler still uses `TypeChecker::check` and produces its
+    /// own error messages, so this method is only used by code that reports
+    /// diagnostics through `Diagnostics::add_type_diagnostic`.
+    pub fn check_or_diagnose(
+        db: &'a Database,
+        left: TypeRef,
+        right: TypeRef,
+        module: ModuleId,
+        location: Location,
+    ) -> Result<(), Diagnostic> {
+        if TypeChecker::check(db, left, right) {
+            Ok(())
+        } else {
+            Err(Diagnostic::type_mismatch(db, left, right, module, location))
+        }
+    }
+
+    /// Checks if the type of an expression is compatible with the type
+    /// expected by another expression, returning a diagnostic if this isn't
+    /// the case.
+    ///
+    /// The diagnostic is reported at the location of the `given` expression,
+    /// and includes notes pointing to the annotations that produced both
+    /// types, provided their origins are recorded.
+    pub fn check_expression_or_diagnose(
+        db: &'a Database,
+        left: TypeRef,
+        right: TypeRef,
+        given: ExpressionKey,
+        expected: ExpressionKey,
+    ) -> Result<(), Diagnostic> {
+        TypeChecker::check_or_diagnose(
+            db,
+            left,
+            right,
+            given.module,
+            given.location,
+        )
+        .map_err(|diag| {
+            diag.with_type_origins(db, left, given, right, expected)
+        })
+    }
+
+    /// Infers the values of type placeholders in a type using the type it's
+    /// expected to be compatible with.
+    ///
+    /// This allows checking types bidirectionally: the expected type (e.g. the
+    /// type of a variable annotation) is pushed into the type produced by an
+    /// expression (e.g. the return type of a generic method) before the
+    /// expression is checked further. For example, given the expected type
+    /// `Array[Int]` and the found type `Array[$T]`, the placeholder `$T` is
+    /// assigned `Int`.
+    ///
+    /// Placeholders are only assigned if all of them can be assigned. If the
+    /// types conflict (e.g. the same placeholder would be assigned two
+    /// different types), or a value doesn't meet the requirements of its
+    /// placeholder, any assignments made are rolled back and `false` is
+    /// returned. Types that don't have the same shape (e.g. a class and a
+    /// trait) don't produce any assignments, as the regular type check
+    /// handles those.
+    pub fn infer_from_expected(
+        db: &'a Database,
+        found: TypeRef,
+        expected: TypeRef,
+    ) -> bool {
+        let mut seeds = Vec::new();
+
+        if !collect_seeds(db, found, expected, &mut seeds) {
+            return false;
+        }
+
+        let mut assigned = Vec::with_capacity(seeds.len());
+
+        for (id, value) in seeds {
+            let root = id.root(db);
+
+            assigned
+                .push((root, db.type_placeholders[root as usize].value.get()));
+
+            if !TypeChecker::check(db, value, TypeRef::Placeholder(id)) {
+                for (root, value) in assigned.into_iter().rev() {
+                    db.type_placeholders[root as usize].value.set(value);
+                }
+
+                // Cached results may depend on the values we just reverted.
+                db.placeholder_assignments
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Checks if the values assigned to the associated types of a trait
+    /// implementation meet the requirements of these associated types.
+    ///
+    /// Associated types without a value are ignored, as these are reported
+    /// using `TraitInstance::missing_associated_types()`.
+    pub fn check_associated_types(&mut self, instance: TraitInstance) -> bool {
+        instance
+            .instance_of()
+            .associated_type_names(self.db)
+            .into_iter()
+            .all(|name| self.check_associated_type(instance, &name))
+    }
+
+    /// Checks the associated types of a trait implementation, returning a
+    /// diagnostic for every associated type that's either missing or doesn't
+    /// meet its requirements.
+    pub fn associated_type_diagnostics(
+        &mut self,
+        instance: TraitInstance,
+        module: ModuleId,
+        location: Location,
+    ) -> Vec<Diagnostic> {
+        let mut diags = Vec::new();
+
+        for name in instance.instance_of().associated_type_names(self.db) {
+            if instance.associated_type(self.db, &name).is_none() {
+                diags.push(Diagnostic::missing_associated_type(
+                    self.db, instance, &name, module, location,
+                ));
+            } else if !self.check_associated_type(instance, &name) {
+                diags.push(Diagnostic::invalid_associated_type(
+                    self.db, instance, &name, module, location,
+                ));
+            }
+        }
+
+        diags
+    }
+
+    fn check_associated_type(
+        &mut self,
+        instance: TraitInstance,
+        name: &str,
+    ) -> bool {
+        let param =
+            instance.instance_of().associated_type(self.db, name).unwrap();
+        let Some(val) = instance.associated_type(self.db, name) else {
+            return true;
+        };
+        let args = TypeArguments::for_trait(self.db, instance);
+        let mut env = Environment::new(args.clone(), args);
+        let rules = Rules::new().with_subtyping();
+
+        if param.is_mutable(self.db) && !val.allow_mutating(self.db) {
+            return false;
+        }
+
+        if param.is_stack_allocated(self.db) && !val.is_stack_allocated(self.db)
+        {
+            return false;
+        }
+
+        param
+            .requirements(self.db)
+            .into_iter()
+            .all(|r| self.check_type_ref_with_trait(val, r, &mut env, rules))
+    }
+
     fn check_type_ref(
         &mut self,
         left: TypeRef,
         right: TypeRef,
         env: &mut Environment,
         rules: Rules,
+    ) -> bool {
+        let key = (left, right, rules);
+
+        if let Some(res) = env.cache.get(self.db, &key) {
+            return res;
+        }
+
+        let res = self.check_type_ref_uncached(left, right, env, rules);
+
+        env.cache.insert(self.db, key, res);
+        res
+    }
+
+    fn check_type_ref_uncached(
+        &mut self,
+        left: TypeRef,
+        right: TypeRef,
+        env: &mut Environment,
+        rules: Rules,
     ) -> bool {
         if !self.checked.insert((left, right)) {
             return true;
         }
 
+        let initial_rules = rules;
+
         // Resolve any assigned type parameters/placeholders to the types
         // they're assigned to.
         let left = self.resolve(left, &env.left, rules);
@@ -328,6 +602,17 @@ impl<'a> TypeChecker<'a> {
         let orig_right = right;
         let right = self.resolve(right, &env.right, rules);
 
+        // Type aliases are compatible with whatever they alias. The alias'
+        // type parameters are exposed to the expanded types through the
+        // environment, similar to the type parameters of a generic class.
+        if let Some((left, right)) = self.expand_aliases(left, right, env) {
+            return self.check_type_ref(left, right, env, initial_rules);
+        }
+
+        if let Some(res) = self.check_unions(left, right, env, initial_rules) {
+            return res;
+        }
+
         // This indicates if the value on the left of the check is a value type
         // (e.g. Int or String).
         let is_val = left.is_value_type(self.db);
@@ -391,11 +676,17 @@ impl<'a> TypeChecker<'a> {
                             left, left_id, orig_right, id, env, rules,
                         )
                 }
-                TypeRef::Pointer(_) if rules.kind.is_cast() => match left_id {
-                    TypeId::ClassInstance(ins) => ins.instance_of().0 == INT_ID,
-                    TypeId::Foreign(ForeignType::Int(_, _)) => true,
-                    _ => false,
-                },
+                TypeRef::Pointer(_) | TypeRef::NonNullPointer(_)
+                    if rules.kind.is_cast() =>
+                {
+                    match left_id {
+                        TypeId::ClassInstance(ins) => {
+                            ins.instance_of().0 == INT_ID
+                        }
+                        TypeId::Foreign(ForeignType::Int(_, _)) => true,
+                        _ => false,
+                    }
+                }
                 TypeRef::Error => true,
                 _ => false,
             },
@@ -560,6 +851,7 @@ impl<'a> TypeChecker<'a> {
                     (Any, _) => true,
                     (Owned, TypeRef::Any(_)) => !rules.kind.is_return(),
                     (Owned, TypeRef::Owned(_)) => true,
+                    (Owned, TypeRef::Union(_)) => true,
                     (Owned, TypeRef::Ref(_) | TypeRef::Mut(_)) => {
                         allow_ref || rval
                     }
@@ -580,39 +872,49 @@ impl<'a> TypeChecker<'a> {
                     (Mut, TypeRef::Any(_)) => !rules.kind.is_return(),
                     (Mut, TypeRef::Ref(_) | TypeRef::Mut(_)) => true,
                     (Mut, TypeRef::Owned(_) | TypeRef::Uni(_)) => rval,
-                    (Pointer, TypeRef::Pointer(_)) => true,
+                    (
+                        Pointer,
+                        TypeRef::Pointer(_) | TypeRef::NonNullPointer(_),
+                    ) => true,
                     _ => false,
                 };
 
-                if allow {
-                    left_id.assign_internal(self.db, right);
-                }
-
-                allow
+                allow && left_id.assign_internal(self.db, right)
             }
-            TypeRef::Pointer(left_id) => match right {
-                TypeRef::Pointer(right_id) => {
-                    rules.kind.is_cast()
-                        || self.check_type_id(left_id, right_id, env, rules)
-                }
-                TypeRef::Owned(TypeId::Foreign(ForeignType::Int(_, _))) => {
-                    rules.kind.is_cast()
-                }
-                TypeRef::Owned(TypeId::ClassInstance(ins)) => {
-                    rules.kind.is_cast() && ins.instance_of().0 == INT_ID
-                }
-                TypeRef::Placeholder(right_id) => {
-                    match right_id.ownership {
-                        Ownership::Any | Ownership::Pointer => {}
-                        _ => return false,
+            TypeRef::Pointer(left_id) | TypeRef::NonNullPointer(left_id) => {
+                match right {
+                    TypeRef::Pointer(right_id) => {
+                        rules.kind.is_cast()
+                            || self.check_type_id(left_id, right_id, env, rules)
                     }
+                    // A pointer that may be NULL can only be turned into a non-NULL
+                    // pointer using an explicit cast.
+                    TypeRef::NonNullPointer(right_id) => {
+                        rules.kind.is_cast()
+                            || (matches!(left, TypeRef::NonNullPointer(_))
+                                && self.check_type_id(
+                                    left_id, right_id, env, rules,
+                                ))
+                    }
+                    TypeRef::Owned(TypeId::Foreign(ForeignType::Int(_, _))) => {
+                        rules.kind.is_cast()
+                    }
+                    TypeRef::Owned(TypeId::ClassInstance(ins)) => {
+                        rules.kind.is_cast() && ins.instance_of().0 == INT_ID
+                    }
+                    TypeRef::Placeholder(right_id) => {
+                        match right_id.ownership {
+                            Ownership::Any | Ownership::Pointer => {}
+                            _ => return false,
+                        }
 
-                    self.check_type_id_with_placeholder(
-                        left, left_id, orig_right, right_id, env, rules,
-                    )
+                        self.check_type_id_with_placeholder(
+                            left, left_id, orig_right, right_id, env, rules,
+                        )
+                    }
+                    _ => false,
                 }
-                _ => false,
-            },
+            }
             _ => false,
         }
     }
@@ -630,6 +932,14 @@ impl<'a> TypeChecker<'a> {
             rules.subtyping = Subtyping::No;
         }
 
+        // Constant type parameters are only compatible with constants, and
+        // constants are only compatible with constant type parameters.
+        if let TypeId::TypeParameter(rhs) = right_id {
+            if rhs.is_constant(self.db) != left_id.is_constant(self.db) {
+                return false;
+            }
+        }
+
         match left_id {
             TypeId::Class(_) | TypeId::Trait(_) | TypeId::Module(_) => {
                 // Classes, traits and modules themselves aren't treated as
@@ -638,6 +948,35 @@ impl<'a> TypeChecker<'a> {
                 // aren't compatible with anything.
                 false
             }
+            // Aliases are expanded by check_type_ref(), so we never encounter
+            // them here.
+            TypeId::TypeAlias(_) => false,
+            TypeId::Tuple(lhs) => match right_id {
+                TypeId::Tuple(rhs) => {
+                    let lhs = lhs.elements(self.db).clone();
+                    let rhs = rhs.elements(self.db).clone();
+
+                    // Like the values of the builtin tuple classes, the values
+                    // are invariant.
+                    lhs.len() == rhs.len()
+                        && lhs.into_iter().zip(rhs).all(|(ours, theirs)| {
+                            self.check_type_ref(ours, theirs, env, rules)
+                        })
+                }
+                TypeId::TypeParameter(rhs)
+                    if rhs.requirements(self.db).is_empty() =>
+                {
+                    // These tuples don't have a class, so they can't implement
+                    // any traits.
+                    !rhs.is_stack_allocated(self.db)
+                }
+                _ => false,
+            },
+            TypeId::Constant(lhs) => match right_id {
+                TypeId::Constant(rhs) => lhs == rhs,
+                TypeId::TypeParameter(_) => !rules.kind.is_cast(),
+                _ => false,
+            },
             TypeId::ClassInstance(lhs) => match right_id {
                 TypeId::ClassInstance(rhs) => {
                     if lhs.instance_of != rhs.instance_of {
@@ -663,7 +1002,14 @@ impl<'a> TypeChecker<'a> {
                             lhs_args.get(param).zip(rhs_args.get(param)).map_or(
                                 false,
                                 |(lhs, rhs)| {
-                                    self.check_type_ref(lhs, rhs, env, rules)
+                                    self.check_type_argument(
+                                        param,
+                                        lhs,
+                                        rhs,
+                                        env,
+                                        rules,
+                                        trait_rules,
+                                    )
                                 },
                             )
                         },
@@ -671,7 +1017,8 @@ impl<'a> TypeChecker<'a> {
                 }
                 TypeId::TraitInstance(rhs) => {
                     if rules.kind.is_cast()
-                        && !lhs.instance_of().allow_cast_to_trait(self.db)
+                        && (!lhs.instance_of().allow_cast_to_trait(self.db)
+                            || !rhs.instance_of().is_dyn_compatible(self.db))
                     {
                         return false;
                     }
@@ -733,6 +1080,10 @@ impl<'a> TypeChecker<'a> {
             }
             TypeId::Closure(lhs) => match right_id {
                 TypeId::Closure(rhs) => {
+                    if !self.check_closure_parameters(lhs, rhs, env, rules) {
+                        return false;
+                    }
+
                     let lhs_obj = lhs.get(self.db);
                     let rhs_obj = rhs.get(self.db);
 
@@ -757,6 +1108,26 @@ impl<'a> TypeChecker<'a> {
                     // requirements.
                     !rhs.is_stack_allocated(self.db)
                 }
+                TypeId::Foreign(ForeignType::Function(rhs))
+                    if lhs.allow_as_foreign_function(self.db) =>
+                {
+                    let lhs_obj = lhs.get(self.db);
+                    let args = lhs_obj
+                        .arguments
+                        .mapping
+                        .values()
+                        .map(|a| a.value_type)
+                        .collect();
+
+                    self.check_foreign_function(
+                        args,
+                        lhs_obj.return_type,
+                        false,
+                        rhs,
+                        env,
+                        rules,
+                    )
+                }
                 _ => false,
             },
             TypeId::Foreign(ForeignType::Int(lsize, lsigned)) => {
@@ -798,6 +1169,19 @@ impl<'a> TypeChecker<'a> {
                     }
                 }
             }
+            TypeId::Foreign(ForeignType::Function(lhs)) => match right_id {
+                TypeId::Foreign(ForeignType::Function(rhs)) => self
+                    .check_foreign_function(
+                        lhs.arguments(self.db).clone(),
+                        lhs.return_type(self.db),
+                        lhs.is_variadic(self.db),
+                        rhs,
+                        env,
+                        rules,
+                    ),
+                TypeId::Foreign(_) => rules.kind.is_cast(),
+                _ => false,
+            },
         }
     }
 
@@ -839,13 +1223,19 @@ impl<'a> TypeChecker<'a> {
         env: &mut Environment,
         rules: Rules,
     ) -> bool {
+        // The set of the placeholder may contain other placeholders, or be
+        // linked to a placeholder with an ownership. If the requirements
+        // aren't met we restore this value, instead of resetting the set and
+        // thus un-inferring the other placeholders.
+        let previous = placeholder.binding(self.db);
+
         // By assigning the placeholder first, recursive checks against the same
         // placeholder don't keep recursing into this method, instead checking
         // against the value on the left.
         //
         // When comparing `ref A` with `ref B` or `mut A` with `mut B`, we want
         // to assign `B` to `A`, not `ref A`/`mut A`.
-        if left.has_ownership(self.db)
+        let assigned = if left.has_ownership(self.db)
             && (original_right.has_ownership(self.db)
                 || placeholder.has_ownership())
         {
@@ -861,9 +1251,15 @@ impl<'a> TypeChecker<'a> {
                 TypeRef::Owned(left_id)
             };
 
-            placeholder.assign_internal(self.db, assign);
+            placeholder.assign_internal(self.db, assign)
         } else {
-            placeholder.assign_internal(self.db, left);
+            placeholder.assign_internal(self.db, left)
+        };
+
+        // The placeholder can't be assigned a type it occurs in, as that would
+        // result in a type that expands infinitely.
+        if !assigned {
+            return false;
         }
 
         let req = if let Some(req) = placeholder.required(self.db) {
@@ -875,8 +1271,9 @@ impl<'a> TypeChecker<'a> {
         if (req.is_mutable(self.db) && !left.allow_mutating(self.db))
             || (req.is_stack_allocated(self.db)
                 && !left.is_stack_allocated(self.db))
+            || req.is_constant(self.db) != left_id.is_constant(self.db)
         {
-            placeholder.assign_internal(self.db, TypeRef::Unknown);
+            placeholder.restore(self.db, previous);
             return false;
         }
 
@@ -908,7 +1305,7 @@ impl<'a> TypeChecker<'a> {
         // errors may be confusing as they would report the left-hand side as
         // the expected value, rather than the underlying type parameter.
         if !res {
-            placeholder.assign_internal(self.db, TypeRef::Unknown);
+            placeholder.restore(self.db, previous);
         }
 
         res
@@ -972,7 +1369,12 @@ impl<'a> TypeChecker<'a> {
         {
             found
         } else {
-            return false;
+            return self.check_blanket_implementations(
+                TypeRef::Owned(TypeId::ClassInstance(left)),
+                right,
+                env,
+                rules,
+            );
         };
 
         if left.instance_of.is_generic(self.db) {
@@ -983,6 +1385,7 @@ impl<'a> TypeChecker<'a> {
             left.type_arguments(self.db)
                 .unwrap()
                 .copy_into(&mut sub_scope.left);
+            sub_scope.clear_cache();
 
             self.check_bounds(&imp.bounds, &mut sub_scope)
                 && self.check_traits(imp.instance, right, &mut sub_scope, rules)
@@ -1044,6 +1447,56 @@ impl<'a> TypeChecker<'a> {
         left.requirements(self.db)
             .into_iter()
             .any(|left| self.check_traits(left, right, env, rules))
+            || self.check_blanket_implementations(
+                TypeRef::Any(TypeId::TypeParameter(left)),
+                right,
+                env,
+                rules,
+            )
+    }
+
+    /// Checks if a type implements a trait through one of the blanket
+    /// implementations of the trait.
+    fn check_blanket_implementations(
+        &mut self,
+        left: TypeRef,
+        right: TraitInstance,
+        env: &mut Environment,
+        rules: Rules,
+    ) -> bool {
+        let db = self.db;
+        let trait_id = right.instance_of;
+
+        // The requirements of a blanket implementation may in turn be met
+        // using the same blanket implementation (e.g. `impl A for T if T: B`
+        // and `impl B for T if T: A`), so we don't check the same trait
+        // recursively.
+        if self.blanket.contains(&trait_id) {
+            return false;
+        }
+
+        self.blanket.push(trait_id);
+
+        let found = trait_id.blanket_implementations(db).iter().any(|imp| {
+            let mut scope = env.clone();
+            let req_rules = rules.with_one_time_subtyping();
+
+            scope.left.assign(imp.parameter, left);
+            scope.right.assign(imp.parameter, left);
+            scope.clear_cache();
+
+            imp.parameter.requirements(db).into_iter().all(|req| {
+                self.check_type_ref_with_trait(left, req, &mut scope, req_rules)
+            }) && self.check_traits(
+                imp.implementation.instance,
+                right,
+                &mut scope,
+                rules,
+            )
+        });
+
+        self.blanket.pop();
+        found
     }
 
     fn check_parameters(
@@ -1097,17 +1550,51 @@ impl<'a> TypeChecker<'a> {
 
         let lhs_args = left.type_arguments(self.db).unwrap();
         let rhs_args = right.type_arguments(self.db).unwrap();
+        let mut params = left.instance_of.type_parameters(self.db);
 
-        left.instance_of.type_parameters(self.db).into_iter().all(|param| {
-            lhs_args
-                .get(param)
-                .zip(rhs_args.get(param))
-                .map_or(false, |(l, r)| {
-                    self.check_type_ref(l, r, env, rules.infer_as_rigid())
-                })
+        params.append(&mut left.instance_of.associated_types(self.db));
+        params.into_iter().all(|param| {
+            lhs_args.get(param).zip(rhs_args.get(param)).map_or(
+                false,
+                |(l, r)| {
+                    let rules = rules.infer_as_rigid();
+
+                    self.check_type_argument(param, l, r, env, rules, rules)
+                },
+            )
         })
     }
 
+    /// Checks if the type argument `left` is compatible with the type argument
+    /// `right`, based on the variance of the type parameter they're assigned
+    /// to.
+    ///
+    /// The `variant_rules` argument specifies the rules to use for covariant
+    /// and contravariant parameters, allowing the same form of subtyping as the
+    /// type that the arguments belong to.
+    fn check_type_argument(
+        &mut self,
+        param: TypeParameterId,
+        left: TypeRef,
+        right: TypeRef,
+        env: &mut Environment,
+        rules: Rules,
+        variant_rules: Rules,
+    ) -> bool {
+        match param.variance(self.db) {
+            Variance::Invariant => self.check_type_ref(left, right, env, rules),
+            Variance::Covariant => {
+                self.check_type_ref(left, right, env, variant_rules)
+            }
+            Variance::Contravariant => {
+                let mut swapped =
+                    Environment::new(env.right.clone(), env.left.clone());
+
+                self.check_type_ref(right, left, &mut swapped, variant_rules)
+            }
+        }
+    }
+
     fn check_arguments(
         &mut self,
         left: &Arguments,
@@ -1136,19 +1623,171 @@ impl<'a> TypeChecker<'a> {
         )
     }
 
-    fn resolve(
+    /// Checks if the type parameters of two closures are compatible, exposing
+    /// them to the closures' signatures through the environment.
+    ///
+    /// The type parameters of the closure on the right are treated as rigid
+    /// parameters, as the closure on the left must work for any type assigned
+    /// to them. The parameters are matched by their position, and those on the
+    /// left must accept all types accepted by those on the right.
+    fn check_closure_parameters(
+        &mut self,
+        left: ClosureId,
+        right: ClosureId,
+        env: &mut Environment,
+        rules: Rules,
+    ) -> bool {
+        let lhs = left.type_parameters(self.db);
+        let rhs = right.type_parameters(self.db);
+
+        if !lhs.is_empty() && lhs.len() != rhs.len() {
+            return false;
+        }
+
+        for &param in &rhs {
+            env.right
+                .assign(param, TypeRef::Any(TypeId::RigidTypeParameter(param)));
+        }
+
+        lhs.into_iter().zip(rhs).all(|(ours, theirs)| {
+            env.left
+                .assign(ours, TypeRef::Any(TypeId::RigidTypeParameter(theirs)));
+
+            self.check_parameters(theirs, ours, env, rules)
+        })
+    }
+
+    fn check_foreign_function(
+        &mut self,
+        arguments: Vec<TypeRef>,
+        return_type: TypeRef,
+        variadic: bool,
+        right: ForeignFunctionId,
+        env: &mut Environment,
+        rules: Rules,
+    ) -> bool {
+        let rhs_args = right.arguments(self.db);
+
+        if variadic != right.is_variadic(self.db)
+            || arguments.len() != rhs_args.len()
+        {
+            return false;
+        }
+
+        arguments
+            .into_iter()
+            .zip(rhs_args.clone())
+            .all(|(ours, theirs)| self.check_type_ref(ours, theirs, env, rules))
+            && self.check_type_ref(
+                return_type,
+                right.return_type(self.db),
+                env,
+                rules,
+            )
+    }
+
+    fn expand_aliases(
         &self,
-        typ: TypeRef,
-        arguments: &TypeArguments,
+        left: TypeRef,
+        right: TypeRef,
+        env: &mut Environment,
+    ) -> Option<(TypeRef, TypeRef)> {
+        let lhs = left.expand_alias(self.db);
+        let rhs = right.expand_alias(self.db);
+
+        if lhs.is_none() && rhs.is_none() {
+            return None;
+        }
+
+        if let Ok(TypeId::TypeAlias(ins)) = left.type_id(self.db) {
+            ins.copy_type_arguments_into(self.db, &mut env.left);
+        }
+
+        if let Ok(TypeId::TypeAlias(ins)) = right.type_id(self.db) {
+            ins.copy_type_arguments_into(self.db, &mut env.right);
+        }
+
+        env.clear_cache();
+
+        Some((lhs.unwrap_or(left), rhs.unwrap_or(right)))
+    }
+
+    /// Checks if a union type is compatible with another type.
+    ///
+    /// A union on the left is compatible with the type on the right if all its
+    /// members are compatible with that type. A type on the left is compatible
+    /// with a union on the right if it's compatible with at least one of its
+    /// members.
+    ///
+    /// If neither type is a union, a `None` is returned.
+    fn check_unions(
+        &mut self,
+        left: TypeRef,
+        right: TypeRef,
+        env: &mut Environment,
         rules: Rules,
-    ) -> TypeRef {
-        let result = match typ {
-            TypeRef::Owned(TypeId::TypeParameter(id)) => {
-                // Owned type parameters should only be assigned owned types.
-                // This check ensures that if we have e.g. `move T` and
-                // `T = ref User`, we don't turn that into `User`, as this could
-                // allow certain invalid type-checks to pass. An example of that
-                // is this:
+    ) -> Option<bool> {
+        match (left, right) {
+            (TypeRef::Union(_), TypeRef::Error) => Some(true),
+            (TypeRef::Union(_), TypeRef::Placeholder(id)) => {
+                // Unions are always owned, and we don't support inferring
+                // type parameters with requirements as unions (yet).
+                let allow =
+                    matches!(id.ownership, Ownership::Any | Ownership::Owned)
+                        && id.required(self.db).map_or(true, |p| {
+                            !p.is_mutable(self.db)
+                                && !p.is_stack_allocated(self.db)
+                                && p.requirements(self.db).is_empty()
+                        });
+
+                Some(allow && id.assign_internal(self.db, left))
+            }
+            (TypeRef::Union(id), _) => {
+                let members = id.members(self.db).clone();
+
+                Some(
+                    members
+                        .into_iter()
+                        .all(|typ| self.check_type_ref(typ, right, env, rules)),
+                )
+            }
+            (
+                TypeRef::Never | TypeRef::Error | TypeRef::Placeholder(_),
+                TypeRef::Union(_),
+            ) => None,
+            (_, TypeRef::Union(id)) => {
+                let members = id.members(self.db).clone();
+
+                Some(members.into_iter().any(|typ| {
+                    // A failed check may still record sub checks as done, so
+                    // we have to undo that before checking the next member.
+                    let checked = self.checked.clone();
+                    let res = self.check_type_ref(left, typ, env, rules);
+
+                    if !res {
+                        self.checked = checked;
+                    }
+
+                    res
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve(
+        &self,
+        typ: TypeRef,
+        arguments: &TypeArguments,
+        rules: Rules,
+    ) -> TypeRef {
+        let result = match typ {
+            TypeRef::Owned(TypeId::TypeParameter(id)) => {
+                // Owned type parameters should only be assigned owned types.
+                // This check ensures that if we have e.g. `move T` and
+                // `T = ref User`, we don't turn that into `User`, as this could
+                // allow certain invalid type-checks to pass. An example of that
+                // is this:
                 //
                 //     trait Foo[T] {
                 //       fn foo -> move T
@@ -1195,6 +1834,10 @@ impl<'a> TypeChecker<'a> {
             TypeRef::Pointer(TypeId::TypeParameter(id)) => self
                 .resolve_type_parameter(typ, id, arguments, rules)
                 .as_pointer(self.db),
+            TypeRef::NonNullPointer(TypeId::TypeParameter(id)) => self
+                .resolve_type_parameter(typ, id, arguments, rules)
+                .as_pointer(self.db)
+                .as_non_null(self.db),
             TypeRef::Placeholder(id) => id
                 .value(self.db)
                 .map_or(typ, |v| self.resolve(v, arguments, rules)),
@@ -1226,21 +1869,119 @@ impl<'a> TypeChecker<'a> {
     }
 }
 
+/// Collects the placeholders in `found` along with the types from `expected`
+/// to assign them.
+///
+/// Placeholders that already have a value aren't collected, and neither are
+/// types from `expected` that are unassigned placeholders themselves. If a
+/// placeholder would be assigned different types, `false` is returned.
+fn collect_seeds(
+    db: &Database,
+    found: TypeRef,
+    expected: TypeRef,
+    seeds: &mut Vec<(TypePlaceholderId, TypeRef)>,
+) -> bool {
+    let expected = match expected {
+        TypeRef::Placeholder(id) => match id.value(db) {
+            Some(v) => v,
+            None => return true,
+        },
+        typ => typ,
+    };
+
+    let found_id = match found {
+        TypeRef::Placeholder(id) => {
+            if let Some(val) = id.value(db) {
+                return collect_seeds(db, val, expected, seeds);
+            }
+
+            let root = id.root(db);
+
+            return match seeds.iter().find(|(i, _)| i.root(db) == root) {
+                Some(&(_, existing)) => existing == expected,
+                None => {
+                    seeds.push((id, expected));
+                    true
+                }
+            };
+        }
+        TypeRef::Owned(id)
+        | TypeRef::Uni(id)
+        | TypeRef::Ref(id)
+        | TypeRef::Mut(id)
+        | TypeRef::UniRef(id)
+        | TypeRef::UniMut(id)
+        | TypeRef::Any(id)
+        | TypeRef::Pointer(id)
+        | TypeRef::NonNullPointer(id) => id,
+        _ => return true,
+    };
+
+    let Ok(exp_id) = expected.type_id(db) else { return true };
+    let (found_args, exp_args, params) = match (found_id, exp_id) {
+        (TypeId::ClassInstance(lhs), TypeId::ClassInstance(rhs))
+            if lhs.instance_of() == rhs.instance_of()
+                && lhs.instance_of().is_generic(db) =>
+        {
+            (
+                lhs.type_arguments(db),
+                rhs.type_arguments(db),
+                lhs.instance_of().type_parameters(db),
+            )
+        }
+        (TypeId::TraitInstance(lhs), TypeId::TraitInstance(rhs))
+            if lhs.instance_of() == rhs.instance_of()
+                && lhs.instance_of().is_generic(db) =>
+        {
+            (
+                lhs.type_arguments(db),
+                rhs.type_arguments(db),
+                lhs.instance_of().type_parameters(db),
+            )
+        }
+        (TypeId::Tuple(lhs), TypeId::Tuple(rhs)) => {
+            let lhs = lhs.elements(db);
+            let rhs = rhs.elements(db);
+
+            return lhs.len() != rhs.len()
+                || lhs
+                    .iter()
+                    .zip(rhs.iter())
+                    .all(|(&l, &r)| collect_seeds(db, l, r, seeds));
+        }
+        _ => return true,
+    };
+
+    let (Some(found_args), Some(exp_args)) = (found_args, exp_args) else {
+        return true;
+    };
+
+    params.into_iter().all(|param| {
+        match (found_args.get(param), exp_args.get(param)) {
+            (Some(l), Some(r)) => collect_seeds(db, l, r, seeds),
+            _ => true,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::diagnostics::Code;
     use crate::format::format_type;
+    use crate::origin::Origin;
     use crate::test::{
-        any, closure, generic_instance_id, generic_trait_instance,
-        generic_trait_instance_id, immutable, immutable_uni, implement,
-        instance, mutable, mutable_uni, new_class, new_extern_class,
-        new_parameter, new_trait, owned, parameter, placeholder, pointer,
-        rigid, trait_instance, trait_instance_id, type_arguments, type_bounds,
-        uni,
+        alias_instance_id, any, closure, generic_instance_id,
+        generic_trait_instance, generic_trait_instance_id, immutable,
+        immutable_uni, implement, instance, mutable, mutable_uni, new_class,
+        new_extern_class, new_parameter, new_trait, new_type_alias, owned,
+        parameter, placeholder, pointer, rigid, trait_instance,
+        trait_instance_id, type_arguments, type_bounds, uni,
     };
     use crate::{
-        Block, Class, ClassId, ClassKind, Closure, Location, ModuleId, Sign,
-        TraitImplementation, TypePlaceholder, Visibility,
+        Block, Class, ClassId, ClassKind, Closure, ForeignFunction, Location,
+        Method, MethodKind, ModuleId, Sign, TraitImplementation, TraitInstance,
+        TypePlaceholder, VariableId, Visibility,
     };
 
     #[track_caller]
@@ -1461,6 +2202,8 @@ mod tests {
             let trait_impl = TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: type_bounds(vec![(array_param, bound)]),
+                module: ModuleId(0),
+                location: Location::default(),
             };
 
             // impl ToString for Array if T: ToString
@@ -1473,6 +2216,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(length),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -1482,6 +2227,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -1495,7 +2242,12 @@ mod tests {
 
             thing.add_trait_implementation(
                 &mut db,
-                TraitImplementation { instance: eq, bounds: TypeBounds::new() },
+                TraitImplementation {
+                    instance: eq,
+                    bounds: TypeBounds::new(),
+                    module: ModuleId(0),
+                    location: Location::default(),
+                },
             );
         }
 
@@ -1521,6 +2273,8 @@ mod tests {
             let trait_impl = TraitImplementation {
                 instance: impl_ins,
                 bounds: type_bounds(vec![(array_param, bound)]),
+                module: ModuleId(0),
+                location: Location::default(),
             };
 
             array.add_trait_implementation(&mut db, trait_impl);
@@ -1641,6 +2395,8 @@ mod tests {
             let trait_impl = TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: type_bounds(vec![(array_param, bound)]),
+                module: ModuleId(0),
+                location: Location::default(),
             };
 
             // impl ToString for Array if T: ToString
@@ -1653,6 +2409,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(length),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -1662,6 +2420,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -1675,7 +2435,12 @@ mod tests {
 
             thing.add_trait_implementation(
                 &mut db,
-                TraitImplementation { instance: eq, bounds: TypeBounds::new() },
+                TraitImplementation {
+                    instance: eq,
+                    bounds: TypeBounds::new(),
+                    module: ModuleId(0),
+                    location: Location::default(),
+                },
             );
         }
 
@@ -1701,6 +2466,8 @@ mod tests {
             let trait_impl = TraitImplementation {
                 instance: impl_ins,
                 bounds: type_bounds(vec![(array_param, bound)]),
+                module: ModuleId(0),
+                location: Location::default(),
             };
 
             array.add_trait_implementation(&mut db, trait_impl);
@@ -1953,6 +2720,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(to_foo),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2078,6 +2847,85 @@ mod tests {
         check_ok_placeholder(&db, p1.as_mut(), placeholder(p2.as_mut()));
     }
 
+    #[test]
+    fn test_cached_results() {
+        let mut db = Database::new();
+        let array = ClassId::array();
+
+        array.new_type_parameter(&mut db, "T".to_string());
+
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let ints =
+            owned(generic_instance_id(&mut db, array, vec![TypeRef::int()]));
+        let floats =
+            owned(generic_instance_id(&mut db, array, vec![TypeRef::float()]));
+        let mut env =
+            Environment::new(TypeArguments::new(), TypeArguments::new());
+        let rules = Rules::new();
+
+        assert!(TypeChecker::new(&db).run(ints, ints, &mut env));
+        assert_eq!(env.cache.get(&db, &(ints, ints, rules)), Some(true));
+
+        // Assigning a placeholder invalidates the cache, as results may depend
+        // on the values of placeholders.
+        var.assign(&mut db, TypeRef::int());
+        assert!(env.cache.get(&db, &(ints, ints, rules)).is_none());
+
+        assert!(!TypeChecker::new(&db).run(ints, floats, &mut env));
+        env.clear_cache();
+        assert!(env.cache.results.is_empty());
+    }
+
+    #[test]
+    fn test_placeholder_with_type_containing_placeholder() {
+        let mut db = Database::new();
+        let array = ClassId::array();
+
+        array.new_type_parameter(&mut db, "T".to_string());
+
+        let p1 = TypePlaceholder::alloc(&mut db, None);
+        let p2 = TypePlaceholder::alloc(&mut db, None);
+        let nested =
+            owned(generic_instance_id(&mut db, array, vec![placeholder(p1)]));
+        let other =
+            owned(generic_instance_id(&mut db, array, vec![placeholder(p2)]));
+
+        check_err(&db, nested, placeholder(p1));
+        check_err(&db, placeholder(p1), nested);
+        assert!(p1.value(&db).is_none());
+
+        // The placeholder also occurs in the type if it's in the same set as
+        // a placeholder in the type.
+        p1.assign_internal(&db, placeholder(p2));
+        check_err(&db, other, placeholder(p1));
+        assert!(p1.value(&db).is_none());
+    }
+
+    #[test]
+    fn test_placeholder_with_unmet_requirements_in_set() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let param = new_parameter(&mut db, "T");
+        let p1 = TypePlaceholder::alloc(&mut db, Some(param));
+        let p2 = TypePlaceholder::alloc(&mut db, None);
+        let p3 = TypePlaceholder::alloc(&mut db, None);
+
+        param.add_requirements(&mut db, vec![trait_instance(to_string)]);
+        p2.assign_internal(&db, placeholder(p3.as_ref()));
+        p1.assign_internal(&db, placeholder(p2));
+
+        // Foo doesn't implement ToString, so the check fails. This mustn't
+        // discard the value of the set p1 and p2 are in.
+        check_err(&db, owned(instance(foo)), placeholder(p1));
+        assert!(p1.value(&db).is_none());
+        assert!(p2.value(&db).is_none());
+
+        p3.assign_internal(&db, owned(instance(foo)));
+        assert_eq!(p1.value(&db), Some(immutable(instance(foo))));
+        assert_eq!(p2.value(&db), Some(immutable(instance(foo))));
+    }
+
     #[test]
     fn test_placeholder_with_type() {
         let mut db = Database::new();
@@ -2116,6 +2964,60 @@ mod tests {
         check_err_placeholder(&db, p1.as_ref(), owned(instance(thing)));
     }
 
+    #[test]
+    fn test_infer_from_expected() {
+        let mut db = Database::new();
+        let array = new_class(&mut db, "Array");
+        let pair = new_class(&mut db, "Pair");
+        let to_string = new_trait(&mut db, "ToString");
+        let req = new_parameter(&mut db, "R");
+
+        array.new_type_parameter(&mut db, "T".to_string());
+        pair.new_type_parameter(&mut db, "A".to_string());
+        pair.new_type_parameter(&mut db, "B".to_string());
+        req.add_requirements(&mut db, vec![trait_instance(to_string)]);
+
+        let int = owned(instance(ClassId::int()));
+        let float = owned(instance(ClassId::float()));
+        let p1 = TypePlaceholder::alloc(&mut db, None);
+        let p2 = TypePlaceholder::alloc(&mut db, None);
+        let p3 = TypePlaceholder::alloc(&mut db, Some(req));
+        let found =
+            owned(generic_instance_id(&mut db, array, vec![placeholder(p1)]));
+        let expected = owned(generic_instance_id(&mut db, array, vec![int]));
+
+        assert!(TypeChecker::infer_from_expected(&db, found, expected));
+        assert_eq!(p1.value(&db), Some(int));
+
+        // The same placeholder can't be assigned two different types.
+        let found = owned(generic_instance_id(
+            &mut db,
+            pair,
+            vec![placeholder(p2), placeholder(p2)],
+        ));
+        let expected =
+            owned(generic_instance_id(&mut db, pair, vec![int, float]));
+
+        assert!(!TypeChecker::infer_from_expected(&db, found, expected));
+        assert_eq!(p2.value(&db), None);
+
+        // If a value doesn't meet the requirements of its placeholder, all
+        // assignments are rolled back.
+        let found = owned(generic_instance_id(
+            &mut db,
+            pair,
+            vec![placeholder(p2), placeholder(p3)],
+        ));
+
+        assert!(!TypeChecker::infer_from_expected(&db, found, expected));
+        assert_eq!(p2.value(&db), None);
+        assert_eq!(p3.value(&db), None);
+
+        // Types of a different shape are left to the regular type check.
+        assert!(TypeChecker::infer_from_expected(&db, found, int));
+        assert_eq!(p2.value(&db), None);
+    }
+
     #[test]
     fn test_pointer_with_placeholder() {
         let mut db = Database::new();
@@ -2129,6 +3031,21 @@ mod tests {
         assert_eq!(var2.value(&db), Some(int_ptr));
     }
 
+    #[test]
+    fn test_non_null_pointer() {
+        let db = Database::new();
+        let int_ptr = pointer(instance(ClassId::int()));
+        let non_null = TypeRef::non_null_pointer(instance(ClassId::int()));
+        let float_ptr = TypeRef::non_null_pointer(instance(ClassId::float()));
+
+        check_ok(&db, non_null, non_null);
+        check_ok(&db, non_null, int_ptr);
+        check_err(&db, int_ptr, non_null);
+        check_err(&db, non_null, float_ptr);
+        check_ok_cast(&db, int_ptr, non_null);
+        check_ok_cast(&db, TypeRef::int(), non_null);
+    }
+
     #[test]
     fn test_struct_with_placeholder() {
         let mut db = Database::new();
@@ -2208,6 +3125,75 @@ mod tests {
         check_err(&db, mut_cats, mut_animals);
     }
 
+    #[test]
+    fn test_class_with_blanket_implementation() {
+        let mut db = Database::new();
+        let format = new_trait(&mut db, "Format");
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+        let blanket = new_parameter(&mut db, "T");
+        let p1 = new_parameter(&mut db, "A");
+        let p2 = new_parameter(&mut db, "B");
+
+        blanket.add_requirements(&mut db, vec![trait_instance(format)]);
+        p1.add_requirements(&mut db, vec![trait_instance(format)]);
+        p2.add_requirements(&mut db, vec![trait_instance(to_string)]);
+        to_string.add_blanket_implementation(
+            &mut db,
+            blanket,
+            TraitImplementation {
+                instance: trait_instance(to_string),
+                bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
+            },
+        );
+        implement(&mut db, trait_instance(format), foo);
+
+        check_ok_cast(
+            &db,
+            owned(instance(foo)),
+            owned(trait_instance_id(to_string)),
+        );
+        check_ok(&db, owned(instance(foo)), owned(parameter(p2)));
+        check_ok(&db, owned(parameter(p1)), owned(parameter(p2)));
+        check_err_cast(
+            &db,
+            owned(instance(bar)),
+            owned(trait_instance_id(to_string)),
+        );
+        check_err(&db, owned(instance(bar)), owned(parameter(p2)));
+    }
+
+    #[test]
+    fn test_recursive_blanket_implementations() {
+        let mut db = Database::new();
+        let a = new_trait(&mut db, "A");
+        let b = new_trait(&mut db, "B");
+        let foo = new_class(&mut db, "Foo");
+        let p1 = new_parameter(&mut db, "T");
+        let p2 = new_parameter(&mut db, "T");
+
+        p1.add_requirements(&mut db, vec![trait_instance(b)]);
+        p2.add_requirements(&mut db, vec![trait_instance(a)]);
+
+        for (trait_id, param) in [(a, p1), (b, p2)] {
+            trait_id.add_blanket_implementation(
+                &mut db,
+                param,
+                TraitImplementation {
+                    instance: trait_instance(trait_id),
+                    bounds: TypeBounds::new(),
+                    module: ModuleId(0),
+                    location: Location::default(),
+                },
+            );
+        }
+
+        check_err_cast(&db, owned(instance(foo)), owned(trait_instance_id(a)));
+    }
+
     #[test]
     fn test_traits() {
         let mut db = Database::new();
@@ -2518,6 +3504,126 @@ mod tests {
         check_ok(&db, owned(closure(fun)), placeholder(var));
     }
 
+    #[test]
+    fn test_generic_closures() {
+        let mut db = Database::new();
+        let equal = new_trait(&mut db, "Equal");
+        let p1 = new_parameter(&mut db, "A");
+        let p2 = new_parameter(&mut db, "B");
+        let p3 = new_parameter(&mut db, "C");
+        let fun1 = Closure::alloc(&mut db, false);
+        let fun2 = Closure::alloc(&mut db, false);
+        let fun3 = Closure::alloc(&mut db, false);
+        let fun4 = Closure::alloc(&mut db, false);
+        let thing = new_class(&mut db, "Thing");
+        let loc = Location::default();
+
+        p3.add_requirements(&mut db, vec![trait_instance(equal)]);
+        fun1.add_type_parameter(&mut db, p1);
+        fun2.add_type_parameter(&mut db, p2);
+        fun3.add_type_parameter(&mut db, p3);
+
+        for (fun, param) in [(fun1, p1), (fun2, p2), (fun3, p3)] {
+            let typ = immutable(parameter(param));
+
+            fun.new_argument(&mut db, "a".to_string(), typ, typ, loc);
+            fun.set_return_type(&mut db, TypeRef::nil());
+        }
+
+        let typ = immutable(instance(thing));
+
+        fun4.new_argument(&mut db, "a".to_string(), typ, typ, loc);
+        fun4.set_return_type(&mut db, TypeRef::nil());
+
+        // The parameters are matched by their position, not their names.
+        check_ok(&db, owned(closure(fun1)), owned(closure(fun2)));
+
+        // A closure that accepts any type can be used where a closure is
+        // expected that only accepts types that implement Equal, but not the
+        // other way around.
+        check_ok(&db, owned(closure(fun1)), owned(closure(fun3)));
+        check_err(&db, owned(closure(fun3)), owned(closure(fun1)));
+
+        // A closure that only accepts a specific type can't be used as a
+        // closure that accepts any type.
+        check_err(&db, owned(closure(fun4)), owned(closure(fun1)));
+
+        // Generic closures must be instantiated before they can be used as
+        // non-generic closures.
+        check_err(&db, owned(closure(fun1)), owned(closure(fun4)));
+    }
+
+    #[test]
+    fn test_foreign_functions() {
+        let mut db = Database::new();
+        let int32 = TypeRef::foreign_signed_int(32);
+        let int64 = TypeRef::foreign_signed_int(64);
+        let fun1 = ForeignFunction::alloc(&mut db, vec![int32], int32, false);
+        let fun2 = ForeignFunction::alloc(&mut db, vec![int32], int32, false);
+        let fun3 = ForeignFunction::alloc(&mut db, vec![int64], int32, false);
+        let fun4 = ForeignFunction::alloc(&mut db, vec![int32], int32, true);
+        let fun5 = ForeignFunction::alloc(&mut db, Vec::new(), int64, false);
+
+        check_ok(
+            &db,
+            TypeRef::foreign_function(fun1),
+            TypeRef::foreign_function(fun2),
+        );
+        check_err(
+            &db,
+            TypeRef::foreign_function(fun1),
+            TypeRef::foreign_function(fun3),
+        );
+        check_err(
+            &db,
+            TypeRef::foreign_function(fun1),
+            TypeRef::foreign_function(fun4),
+        );
+        check_err(
+            &db,
+            TypeRef::foreign_function(fun1),
+            TypeRef::foreign_function(fun5),
+        );
+        check_err(&db, TypeRef::foreign_function(fun1), int64);
+        check_ok_cast(&db, TypeRef::foreign_function(fun1), int64);
+    }
+
+    #[test]
+    fn test_closure_as_foreign_function() {
+        let mut db = Database::new();
+        let int32 = TypeRef::foreign_signed_int(32);
+        let int64 = TypeRef::foreign_signed_int(64);
+        let foreign =
+            ForeignFunction::alloc(&mut db, vec![int32], int32, false);
+        let fun1 = Closure::alloc(&mut db, false);
+        let fun2 = Closure::alloc(&mut db, false);
+        let fun3 = Closure::alloc(&mut db, false);
+        let loc = Location::default();
+
+        for fun in [fun1, fun2, fun3] {
+            fun.set_return_type(&mut db, int32);
+        }
+
+        fun1.new_argument(&mut db, "a".to_string(), int32, int32, loc);
+        fun2.new_argument(&mut db, "a".to_string(), int64, int64, loc);
+        fun3.new_argument(&mut db, "a".to_string(), int32, int32, loc);
+        fun3.add_capture(&mut db, VariableId(0), TypeRef::int(), loc);
+
+        check_ok(&db, owned(closure(fun1)), TypeRef::foreign_function(foreign));
+        check_err(
+            &db,
+            owned(closure(fun2)),
+            TypeRef::foreign_function(foreign),
+        );
+
+        // Closures that capture variables can't be lowered to C functions.
+        check_err(
+            &db,
+            owned(closure(fun3)),
+            TypeRef::foreign_function(foreign),
+        );
+    }
+
     #[test]
     fn test_recursive_type() {
         let mut db = Database::new();
@@ -2532,8 +3638,11 @@ mod tests {
             owned(generic_instance_id(&mut db, array, vec![TypeRef::int()]));
         let exp = owned(generic_instance_id(&mut db, array, vec![ints]));
 
-        var.assign(&mut db, given);
-        check_err(&db, given, exp);
+        // The placeholder can't be assigned a type it occurs in, so it's
+        // instead inferred when checking the types.
+        assert!(!var.assign(&mut db, given));
+        check_ok(&db, given, exp);
+        assert_eq!(var.value(&db), Some(ints));
     }
 
     #[test]
@@ -2553,6 +3662,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(update),
                 bounds: type_bounds(vec![(array_param, array_bounds)]),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2597,6 +3708,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(update),
                 bounds: type_bounds(vec![(array_param, array_bounds)]),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2637,6 +3750,8 @@ mod tests {
                 vec![any(parameter(iterator_param))],
             ),
             bounds: TypeBounds::new(),
+            module: ModuleId(0),
+            location: Location::default(),
         };
 
         iterator.add_trait_implementation(&mut db, iter_impl);
@@ -2724,6 +3839,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2751,6 +3868,8 @@ mod tests {
             TraitImplementation {
                 instance: trait_instance(to_string),
                 bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
             },
         );
 
@@ -2940,6 +4059,8 @@ mod tests {
                 TraitImplementation {
                     instance: trait_instance(to_string),
                     bounds: TypeBounds::new(),
+                    module: ModuleId(0),
+                    location: Location::default(),
                 },
             );
         }
@@ -2960,6 +4081,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cast_to_dyn_incompatible_trait() {
+        let mut db = Database::new();
+        let hash = new_trait(&mut db, "Hash");
+        let foo = new_class(&mut db, "Foo");
+        let method = Method::alloc(
+            &mut db,
+            ModuleId(0),
+            Location::default(),
+            "hash".to_string(),
+            Visibility::Public,
+            MethodKind::Instance,
+        );
+
+        hash.add_required_method(&mut db, "hash".to_string(), method);
+        implement(&mut db, trait_instance(hash), foo);
+
+        let hash_ins = owned(trait_instance_id(hash));
+
+        check_ok_cast(&db, owned(instance(foo)), hash_ins);
+
+        method.new_type_parameter(&mut db, "H".to_string());
+        check_err_cast(&db, owned(instance(foo)), hash_ins);
+    }
+
     #[test]
     fn test_ref_value_type_with_uni_reference() {
         let db = Database::new();
@@ -3069,4 +4215,373 @@ mod tests {
         check_err_return(&db, placeholder(ref_var), any(instance(thing)));
         check_err_return(&db, placeholder(mut_var), any(instance(thing)));
     }
+
+    #[test]
+    fn test_type_alias() {
+        let mut db = Database::new();
+        let string = ClassId::string();
+        let ints = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![TypeRef::int()],
+        ));
+        let alias = new_type_alias(&mut db, "Ints", ints);
+        let alias_ins = alias_instance_id(&mut db, alias, Vec::new());
+
+        check_ok(&db, owned(alias_ins), ints);
+        check_ok(&db, ints, owned(alias_ins));
+        check_ok(&db, owned(alias_ins), owned(alias_ins));
+        check_err(&db, immutable(alias_ins), owned(alias_ins));
+        check_err(&db, owned(alias_ins), owned(instance(string)));
+    }
+
+    #[test]
+    fn test_generic_type_alias() {
+        let mut db = Database::new();
+        ClassId::array().new_type_parameter(&mut db, "T".to_string());
+
+        let list = new_type_alias(&mut db, "List", TypeRef::Unknown);
+        let param = list.new_type_parameter(&mut db, "V".to_string());
+        let aliased = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![any(parameter(param))],
+        ));
+
+        list.set_aliased_type(&mut db, aliased);
+
+        let ints = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![TypeRef::int()],
+        ));
+        let floats = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![TypeRef::float()],
+        ));
+        let int_list = alias_instance_id(&mut db, list, vec![TypeRef::int()]);
+
+        check_ok(&db, owned(int_list), ints);
+        check_ok(&db, ints, owned(int_list));
+        check_err(&db, owned(int_list), floats);
+    }
+
+    #[test]
+    fn test_union() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+        let baz = new_class(&mut db, "Baz");
+
+        implement(&mut db, trait_instance(to_string), foo);
+        implement(&mut db, trait_instance(to_string), bar);
+
+        let foo_bar = TypeRef::union(
+            &mut db,
+            vec![owned(instance(foo)), owned(instance(bar))],
+        );
+        let foo_baz = TypeRef::union(
+            &mut db,
+            vec![owned(instance(foo)), owned(instance(baz))],
+        );
+        let all = TypeRef::union(&mut db, vec![foo_bar, owned(instance(baz))]);
+        let var = TypePlaceholder::alloc(&mut db, None);
+
+        check_ok(&db, owned(instance(foo)), foo_bar);
+        check_ok(&db, owned(instance(bar)), foo_bar);
+        check_ok(&db, foo_bar, foo_bar);
+        check_ok(&db, foo_bar, all);
+        check_ok_cast(&db, foo_bar, owned(trait_instance_id(to_string)));
+        check_ok(&db, TypeRef::Never, foo_bar);
+        check_ok(&db, foo_bar, placeholder(var));
+
+        assert_eq!(var.value(&db), Some(foo_bar));
+
+        check_err(&db, owned(instance(baz)), foo_bar);
+        check_err(&db, foo_bar, owned(instance(foo)));
+        check_err(&db, foo_bar, foo_baz);
+        check_err(&db, all, foo_bar);
+        check_err_cast(&db, foo_baz, owned(trait_instance_id(to_string)));
+    }
+
+    #[test]
+    fn test_variance() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bx = new_class(&mut db, "Box");
+        let param = bx.new_type_parameter(&mut db, "T".to_string());
+
+        implement(&mut db, trait_instance(to_string), foo);
+
+        let box_foo =
+            owned(generic_instance_id(&mut db, bx, vec![owned(instance(foo))]));
+        let box_to_string = owned(generic_instance_id(
+            &mut db,
+            bx,
+            vec![owned(trait_instance_id(to_string))],
+        ));
+
+        check_err_cast(&db, box_foo, box_to_string);
+        check_err_cast(&db, box_to_string, box_foo);
+
+        param.set_variance(&mut db, Variance::Covariant);
+        check_ok_cast(&db, box_foo, box_to_string);
+        check_err_cast(&db, box_to_string, box_foo);
+
+        param.set_variance(&mut db, Variance::Contravariant);
+        check_err_cast(&db, box_foo, box_to_string);
+        check_ok_cast(&db, box_to_string, box_foo);
+    }
+
+    #[test]
+    fn test_check_throw_propagation() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let int = TypeRef::int();
+        let foo_err = owned(instance(foo));
+        let trait_err = owned(trait_instance_id(to_string));
+
+        // Error types are compared as return types, so subtyping isn't
+        // allowed.
+        implement(&mut db, trait_instance(to_string), foo);
+
+        let ok = |db: &Database, thrown, method| {
+            TypeChecker::check_throw_propagation(db, thrown, method)
+        };
+
+        assert!(ok(&db, ThrowKind::Option(int), ThrowKind::Option(int)));
+        assert!(ok(&db, ThrowKind::Option(int), ThrowKind::Infer(var)));
+        assert!(ok(
+            &db,
+            ThrowKind::Result(int, foo_err),
+            ThrowKind::Result(int, foo_err)
+        ));
+        assert!(!ok(
+            &db,
+            ThrowKind::Result(int, foo_err),
+            ThrowKind::Result(int, trait_err)
+        ));
+        assert!(!ok(&db, ThrowKind::Option(int), ThrowKind::Result(int, int)));
+        assert!(!ok(&db, ThrowKind::Result(int, int), ThrowKind::Option(int)));
+        assert!(!ok(&db, ThrowKind::Never, ThrowKind::Option(int)));
+        assert!(!ok(&db, ThrowKind::Option(int), ThrowKind::Never));
+        assert!(!ok(&db, ThrowKind::Option(int), ThrowKind::Unknown));
+    }
+
+    #[test]
+    fn test_check_or_diagnose() {
+        let db = Database::new();
+        let module = ModuleId(0);
+        let loc = Location::default();
+
+        assert!(TypeChecker::check_or_diagnose(
+            &db,
+            TypeRef::int(),
+            TypeRef::int(),
+            module,
+            loc
+        )
+        .is_ok());
+        assert_eq!(
+            TypeChecker::check_or_diagnose(
+                &db,
+                TypeRef::int(),
+                TypeRef::string(),
+                module,
+                loc
+            )
+            .map_err(|d| d.code),
+            Err(Code::TypeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_check_expression_or_diagnose() {
+        let mut db = Database::new();
+        let line = |line| Location::new(&(line..=line), &(1..=1));
+        let given = ExpressionKey::new(ModuleId(0), line(1));
+        let expected = ExpressionKey::new(ModuleId(0), line(2));
+
+        db.track_origins();
+        db.record_origin(expected, Origin::new(ModuleId(1), line(3)));
+
+        assert!(TypeChecker::check_expression_or_diagnose(
+            &db,
+            TypeRef::int(),
+            TypeRef::int(),
+            given,
+            expected
+        )
+        .is_ok());
+
+        let diag = TypeChecker::check_expression_or_diagnose(
+            &db,
+            TypeRef::int(),
+            TypeRef::string(),
+            given,
+            expected,
+        )
+        .unwrap_err();
+
+        assert_eq!(diag.code, Code::TypeMismatch);
+        assert_eq!(diag.location, line(1));
+        assert_eq!(diag.notes.len(), 1);
+        assert_eq!(
+            diag.notes[0].message,
+            "the type 'String' is expected because of this annotation"
+        );
+        assert_eq!(diag.notes[0].location, Some((ModuleId(1), line(3))));
+
+        db.record_origin(given, Origin::new(ModuleId(0), line(4)));
+
+        let diag = TypeChecker::check_expression_or_diagnose(
+            &db,
+            TypeRef::int(),
+            TypeRef::string(),
+            given,
+            expected,
+        )
+        .unwrap_err();
+
+        assert_eq!(diag.notes.len(), 2);
+        assert_eq!(
+            diag.notes[0].message,
+            "the type 'Int' originates from here"
+        );
+        assert_eq!(diag.notes[0].location, Some((ModuleId(0), line(4))));
+    }
+
+    #[test]
+    fn test_check_associated_types() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let iter = new_trait(&mut db, "Iter");
+        let item = iter.new_associated_type(&mut db, "Item".to_string());
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+
+        item.add_requirements(&mut db, vec![trait_instance(to_string)]);
+        implement(&mut db, trait_instance(to_string), foo);
+
+        let foos = TraitInstance::generic(
+            &mut db,
+            iter,
+            type_arguments(vec![(item, owned(instance(foo)))]),
+        );
+        let bars = TraitInstance::generic(
+            &mut db,
+            iter,
+            type_arguments(vec![(item, owned(instance(bar)))]),
+        );
+        let empty = TraitInstance::generic(&mut db, iter, TypeArguments::new());
+
+        assert!(TypeChecker::new(&db).check_associated_types(foos));
+        assert!(!TypeChecker::new(&db).check_associated_types(bars));
+        assert!(TypeChecker::new(&db).check_associated_types(empty));
+
+        let diags = TypeChecker::new(&db).associated_type_diagnostics(
+            bars,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::InvalidAssociatedType);
+
+        let diags = TypeChecker::new(&db).associated_type_diagnostics(
+            empty,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::MissingAssociatedType);
+        assert!(TypeChecker::new(&db)
+            .associated_type_diagnostics(foos, ModuleId(0), Location::default())
+            .is_empty());
+
+        check_ok(
+            &db,
+            owned(TypeId::TraitInstance(foos)),
+            owned(TypeId::TraitInstance(foos)),
+        );
+        check_err(
+            &db,
+            owned(TypeId::TraitInstance(foos)),
+            owned(TypeId::TraitInstance(bars)),
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let param = new_parameter(&mut db, "T");
+        let req_param = new_parameter(&mut db, "R");
+
+        req_param.add_requirements(&mut db, vec![trait_instance(to_string)]);
+
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let mut vars = vec![TypeRef::int(); 8];
+
+        vars.push(placeholder(var));
+
+        let ints = TypeRef::tuple(&mut db, vec![TypeRef::int(); 9]);
+        let floats = TypeRef::tuple(&mut db, vec![TypeRef::float(); 9]);
+        let more = TypeRef::tuple(&mut db, vec![TypeRef::int(); 10]);
+        let infer = TypeRef::tuple(&mut db, vars);
+
+        check_ok(&db, ints, ints);
+        check_ok(&db, infer, ints);
+        check_ok(&db, ints, any(parameter(param)));
+        check_err(&db, ints, floats);
+        check_err(&db, ints, more);
+        check_err(&db, more, ints);
+        check_err(&db, ints, any(parameter(req_param)));
+        check_err(&db, ints, TypeRef::int());
+        assert_eq!(var.value(&db), Some(TypeRef::int()));
+    }
+
+    #[test]
+    fn test_constants() {
+        let mut db = Database::new();
+        let matrix = new_class(&mut db, "Matrix");
+        let size = matrix.new_type_parameter(&mut db, "N".to_string());
+        let param = new_parameter(&mut db, "T");
+
+        size.set_constant(&mut db);
+
+        let three = TypeRef::constant(3);
+        let four = TypeRef::constant(4);
+        let matrix3 = owned(generic_instance_id(&mut db, matrix, vec![three]));
+        let matrix4 = owned(generic_instance_id(&mut db, matrix, vec![four]));
+        let var = TypePlaceholder::alloc(&mut db, Some(size));
+        let regular = TypePlaceholder::alloc(&mut db, Some(param));
+        let int_var = TypePlaceholder::alloc(&mut db, Some(size));
+
+        check_ok(&db, three, three);
+        check_ok(&db, three, any(parameter(size)));
+        check_ok(&db, any(rigid(size)), any(parameter(size)));
+        check_ok(&db, matrix3, matrix3);
+        check_ok(&db, three, placeholder(var));
+        check_err(&db, three, four);
+        check_err(&db, matrix3, matrix4);
+        check_err(&db, three, any(parameter(param)));
+        check_err(&db, three, TypeRef::int());
+        check_err(&db, TypeRef::int(), three);
+        check_err(&db, TypeRef::int(), any(parameter(size)));
+        check_err(&db, any(rigid(size)), any(parameter(param)));
+        check_err(&db, three, placeholder(regular));
+        check_err(&db, TypeRef::int(), placeholder(int_var));
+        assert_eq!(var.value(&db), Some(three));
+        assert_eq!(regular.value(&db), None);
+        assert_eq!(int_var.value(&db), None);
+        assert_eq!(three.as_constant(&db), Some(3));
+        assert_eq!(TypeRef::int().as_constant(&db), None);
+    }
 }
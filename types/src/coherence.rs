@@ -0,0 +1,583 @@
+//! Coherence checking of trait implementations.
+//!
+//! A class can implement a trait only once, regardless of the module the
+//! implementation is defined in. In addition, a trait can only be implemented
+//! for a class if either the class or the trait is defined in the same package
+//! as the implementation. Without this rule, two unrelated packages could both
+//! implement the same trait for the same class, and importing both would
+//! result in conflicting implementations.
+//!
+//! Modules belong to the same package if they're assigned the same
+//! `PackageId`. If either module isn't assigned a package, the first
+//! component of their names is used instead, such that `std.string` and
+//! `std.fmt` belong to the same package.
+//!
+//! Blanket implementations (e.g. `impl ToString for T if T: Format`) overlap
+//! with an explicit implementation if the implementing class meets the
+//! requirements of the type parameter. Since the type arguments of a class may
+//! differ per instance, only the implemented traits are considered, ignoring
+//! their type arguments.
+//!
+//! Two blanket implementations of the same trait overlap, unless the traits
+//! required by their type parameters are known to be mutually exclusive. Such
+//! knowledge is recorded in the `Database` in the form of exclusions: a pair
+//! of traits that can't both be implemented by the same class, or a class
+//! that doesn't implement a trait. Exclusions that contradict the actual
+//! implementations of a class are reported as errors.
+use crate::diagnostics::Diagnostic;
+use crate::{
+    BlanketImplementation, ClassId, Database, ModuleId, TraitId,
+    TraitImplementation, TypeParameterId,
+};
+use location::Location;
+
+/// A pair of traits that can't both be implemented by the same class.
+#[derive(Clone, Debug)]
+pub struct DisjointTraits {
+    pub left: TraitId,
+    pub right: TraitId,
+    pub module: ModuleId,
+    pub location: Location,
+}
+
+/// A class that doesn't implement a trait, now or in the future.
+#[derive(Clone, Debug)]
+pub struct NegativeImplementation {
+    pub class: ClassId,
+    pub trait_id: TraitId,
+    pub module: ModuleId,
+    pub location: Location,
+}
+
+/// The exclusions known to the type system.
+#[derive(Clone, Debug, Default)]
+pub struct Exclusions {
+    pub(crate) disjoint: Vec<DisjointTraits>,
+    pub(crate) negative: Vec<NegativeImplementation>,
+}
+
+impl Database {
+    /// Records that `left` and `right` can't both be implemented by the same
+    /// class.
+    pub fn add_disjoint_traits(
+        &mut self,
+        left: TraitId,
+        right: TraitId,
+        module: ModuleId,
+        location: Location,
+    ) {
+        self.exclusions.disjoint.push(DisjointTraits {
+            left,
+            right,
+            module,
+            location,
+        });
+    }
+
+    /// Records that `class` doesn't implement the trait `trait_id`.
+    pub fn add_negative_implementation(
+        &mut self,
+        class: ClassId,
+        trait_id: TraitId,
+        module: ModuleId,
+        location: Location,
+    ) {
+        self.exclusions.negative.push(NegativeImplementation {
+            class,
+            trait_id,
+            module,
+            location,
+        });
+    }
+
+    pub fn exclusions(&self) -> &Exclusions {
+        &self.exclusions
+    }
+}
+
+impl TraitId {
+    /// Returns `true` if no class can implement both `self` and `other`.
+    ///
+    /// Traits are also disjoint if any of the traits they require are
+    /// disjoint, as implementing a trait requires implementing the traits it
+    /// depends on.
+    pub fn is_disjoint_with(self, db: &Database, other: TraitId) -> bool {
+        let lhs = self.with_required_traits(db);
+        let rhs = other.with_required_traits(db);
+
+        db.exclusions.disjoint.iter().any(|pair| {
+            (lhs.contains(&pair.left) && rhs.contains(&pair.right))
+                || (lhs.contains(&pair.right) && rhs.contains(&pair.left))
+        })
+    }
+
+    /// Returns this trait and all the traits it (indirectly) requires.
+    fn with_required_traits(self, db: &Database) -> Vec<TraitId> {
+        let mut traits = vec![self];
+        let mut idx = 0;
+
+        while let Some(&id) = traits.get(idx) {
+            for req in id.required_traits(db) {
+                if !traits.contains(&req.instance_of()) {
+                    traits.push(req.instance_of());
+                }
+            }
+
+            idx += 1;
+        }
+
+        traits
+    }
+}
+
+impl ClassId {
+    /// Returns `true` if this class is known to not implement the given trait.
+    pub fn excludes_trait(self, db: &Database, trait_id: TraitId) -> bool {
+        db.exclusions
+            .negative
+            .iter()
+            .any(|neg| neg.class == self && neg.trait_id == trait_id)
+            || self.implemented_traits(db).any(|imp| {
+                imp.instance.instance_of().is_disjoint_with(db, trait_id)
+            })
+    }
+}
+
+/// Checks the trait implementations of all classes, returning a diagnostic
+/// for every implementation that violates the coherence rules.
+pub fn check(db: &Database) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for idx in 0..db.number_of_classes() {
+        let class = ClassId(idx as u32);
+
+        // Specializations share the implementations of their source class, so
+        // we'd just produce the same diagnostics multiple times.
+        if class.specialization_source(db).is_some() {
+            continue;
+        }
+
+        for (existing, imp) in class.duplicate_trait_implementations(db) {
+            diagnostics.push(Diagnostic::overlapping_implementation(
+                db, class, existing, imp,
+            ));
+        }
+
+        let mut imps: Vec<_> = class.implemented_traits(db).collect();
+
+        // The implementations are stored in a hash map, so we sort them to
+        // ensure the diagnostics are produced in a consistent order.
+        imps.sort_by_key(|imp| imp.instance.instance_of().0);
+
+        for imp in imps {
+            if is_orphan(db, class, imp) {
+                diagnostics
+                    .push(Diagnostic::orphan_implementation(db, class, imp));
+            }
+        }
+    }
+
+    for idx in 0..db.number_of_traits() {
+        let trait_id = TraitId(idx as u32);
+        let blankets = trait_id.blanket_implementations(db);
+
+        for (idx, blanket) in blankets.iter().enumerate() {
+            for existing in &blankets[0..idx] {
+                if !are_disjoint(db, existing, blanket) {
+                    diagnostics.push(
+                        Diagnostic::overlapping_blanket_implementation(
+                            db,
+                            &existing.implementation,
+                            &blanket.implementation,
+                        ),
+                    );
+                }
+            }
+        }
+
+        for blanket in blankets {
+            for &class in trait_id.implemented_by(db) {
+                if class.specialization_source(db).is_some()
+                    || !meets_requirements(db, class, blanket.parameter)
+                {
+                    continue;
+                }
+
+                let imp = class.trait_implementation(db, trait_id).unwrap();
+
+                diagnostics.push(Diagnostic::overlapping_implementation(
+                    db,
+                    class,
+                    &blanket.implementation,
+                    imp,
+                ));
+            }
+        }
+    }
+
+    check_exclusions(db, &mut diagnostics);
+    diagnostics
+}
+
+fn check_exclusions(db: &Database, diagnostics: &mut Vec<Diagnostic>) {
+    for pair in &db.exclusions.disjoint {
+        if pair.left == pair.right
+            || pair.left.with_required_traits(db).contains(&pair.right)
+            || pair.right.with_required_traits(db).contains(&pair.left)
+        {
+            diagnostics.push(Diagnostic::self_excluding_traits(db, pair));
+            continue;
+        }
+
+        for &class in pair.left.implemented_by(db) {
+            if class.specialization_source(db).is_some() {
+                continue;
+            }
+
+            if let Some(imp) = class.trait_implementation(db, pair.right) {
+                diagnostics.push(Diagnostic::contradicting_disjoint_traits(
+                    db, class, pair, imp,
+                ));
+            }
+        }
+    }
+
+    for neg in &db.exclusions.negative {
+        if let Some(imp) = neg.class.trait_implementation(db, neg.trait_id) {
+            diagnostics.push(
+                Diagnostic::contradicting_negative_implementation(db, neg, imp),
+            );
+        }
+    }
+}
+
+/// Returns `true` if no type can meet the requirements of the type parameters
+/// of both blanket implementations.
+fn are_disjoint(
+    db: &Database,
+    left: &BlanketImplementation,
+    right: &BlanketImplementation,
+) -> bool {
+    let lhs = left.parameter.requirements(db);
+    let rhs = right.parameter.requirements(db);
+
+    lhs.iter().any(|l| {
+        rhs.iter()
+            .any(|r| l.instance_of().is_disjoint_with(db, r.instance_of()))
+    })
+}
+
+fn meets_requirements(
+    db: &Database,
+    class: ClassId,
+    parameter: TypeParameterId,
+) -> bool {
+    parameter
+        .requirements(db)
+        .into_iter()
+        .all(|req| class.trait_implementation(db, req.instance_of()).is_some())
+}
+
+fn is_orphan(db: &Database, class: ClassId, imp: &TraitImplementation) -> bool {
+    let trait_id = imp.instance.instance_of();
+
+    !same_package(db, imp.module, class.module(db))
+        && !same_package(db, imp.module, trait_id.module(db))
+}
+
+fn same_package(db: &Database, left: ModuleId, right: ModuleId) -> bool {
+    left == right
+        || left
+            .is_same_package(db, right)
+            .unwrap_or_else(|| left.name(db).head() == right.name(db).head())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Code;
+    use crate::packages::Package;
+    use crate::test::{
+        new_class, new_module, new_parameter, new_trait, trait_instance,
+    };
+    use crate::TypeBounds;
+    use location::Location;
+
+    fn implement_in(
+        db: &mut Database,
+        module: ModuleId,
+        line: u32,
+        trait_id: TraitId,
+        class: ClassId,
+    ) {
+        class.add_trait_implementation(
+            db,
+            TraitImplementation {
+                instance: trait_instance(trait_id),
+                bounds: TypeBounds::new(),
+                module,
+                location: Location::new(&(line..=line), &(1..=1)),
+            },
+        );
+    }
+
+    #[test]
+    fn test_overlapping_implementations() {
+        let mut db = Database::new();
+
+        // Traits created using new_trait() are defined in the first module.
+        let foo_mod = new_module(&mut db, "app.foo");
+        let bar_mod = new_module(&mut db, "app.bar");
+        let to_string = new_trait(&mut db, "ToString");
+        let class = new_class(&mut db, "Foo");
+
+        class.set_module(&mut db, foo_mod);
+        implement_in(&mut db, foo_mod, 1, to_string, class);
+        implement_in(&mut db, bar_mod, 2, to_string, class);
+
+        // The first implementation must not be overwritten.
+        assert_eq!(
+            class.trait_implementation(&db, to_string).unwrap().module,
+            foo_mod
+        );
+        assert_eq!(to_string.implemented_by(&db), &vec![class]);
+
+        let dups = class.duplicate_trait_implementations(&db);
+
+        assert_eq!(dups.len(), 1);
+        assert_eq!(dups[0].0.module, foo_mod);
+        assert_eq!(dups[0].1.module, bar_mod);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OverlappingImplementation);
+        assert_eq!(diags[0].module, bar_mod);
+        assert_eq!(
+            diags[0].notes[0].location,
+            Some((foo_mod, Location::new(&(1..=1), &(1..=1))))
+        );
+    }
+
+    #[test]
+    fn test_orphan_implementations() {
+        let mut db = Database::new();
+
+        // Traits created using new_trait() are defined in the first module.
+        let std_mod = new_module(&mut db, "std.string");
+        let app_mod = new_module(&mut db, "app.foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let equal = new_trait(&mut db, "Equal");
+        let string = new_class(&mut db, "String");
+        let foo = new_class(&mut db, "Foo");
+
+        string.set_module(&mut db, std_mod);
+        foo.set_module(&mut db, app_mod);
+        implement_in(&mut db, app_mod, 1, to_string, string);
+        implement_in(&mut db, app_mod, 2, to_string, foo);
+        implement_in(&mut db, std_mod, 3, equal, string);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OrphanImplementation);
+        assert_eq!(diags[0].module, app_mod);
+        assert_eq!(diags[0].location, Location::new(&(1..=1), &(1..=1)));
+        assert_eq!(diags[0].notes.len(), 2);
+    }
+
+    #[test]
+    fn test_orphan_implementations_with_packages() {
+        let mut db = Database::new();
+
+        // Traits created using new_trait() are defined in the first module.
+        new_module(&mut db, "std.string");
+
+        let json_mod = new_module(&mut db, "json.parser");
+        let app_mod = new_module(&mut db, "json.app");
+        let to_string = new_trait(&mut db, "ToString");
+        let json = new_class(&mut db, "Json");
+        let json_pkg = Package::alloc(&mut db, "json".to_string(), None);
+        let app_pkg = Package::alloc(&mut db, "app".to_string(), None);
+
+        json.set_module(&mut db, json_mod);
+        implement_in(&mut db, app_mod, 1, to_string, json);
+
+        // Without packages the modules are considered part of the same
+        // package, as their names share the same first component.
+        assert!(check(&db).is_empty());
+
+        json_pkg.add_module(&mut db, json_mod);
+        app_pkg.add_module(&mut db, app_mod);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OrphanImplementation);
+        assert_eq!(diags[0].module, app_mod);
+    }
+
+    #[test]
+    fn test_blanket_implementations() {
+        let mut db = Database::new();
+        let app_mod = new_module(&mut db, "app");
+        let format = new_trait(&mut db, "Format");
+        let to_string = new_trait(&mut db, "ToString");
+        let param = new_parameter(&mut db, "T");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+
+        foo.set_module(&mut db, app_mod);
+        bar.set_module(&mut db, app_mod);
+        param.add_requirements(&mut db, vec![trait_instance(format)]);
+        to_string.add_blanket_implementation(
+            &mut db,
+            param,
+            TraitImplementation {
+                instance: trait_instance(to_string),
+                bounds: TypeBounds::new(),
+                module: app_mod,
+                location: Location::new(&(1..=1), &(1..=1)),
+            },
+        );
+
+        // Bar doesn't implement Format, so its implementation doesn't overlap
+        // with the blanket implementation.
+        implement_in(&mut db, app_mod, 2, format, foo);
+        implement_in(&mut db, app_mod, 3, to_string, foo);
+        implement_in(&mut db, app_mod, 4, to_string, bar);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OverlappingImplementation);
+        assert_eq!(diags[0].location, Location::new(&(3..=3), &(1..=1)));
+        assert_eq!(
+            diags[0].notes[0].location,
+            Some((app_mod, Location::new(&(1..=1), &(1..=1))))
+        );
+    }
+
+    fn blanket(
+        db: &mut Database,
+        module: ModuleId,
+        line: u32,
+        trait_id: TraitId,
+        requirement: TraitId,
+    ) {
+        let param = new_parameter(db, "T");
+
+        param.add_requirements(db, vec![trait_instance(requirement)]);
+        trait_id.add_blanket_implementation(
+            db,
+            param,
+            TraitImplementation {
+                instance: trait_instance(trait_id),
+                bounds: TypeBounds::new(),
+                module,
+                location: Location::new(&(line..=line), &(1..=1)),
+            },
+        );
+    }
+
+    #[test]
+    fn test_trait_is_disjoint_with() {
+        let mut db = Database::new();
+        let app_mod = new_module(&mut db, "app");
+        let foo = new_trait(&mut db, "Foo");
+        let bar = new_trait(&mut db, "Bar");
+        let baz = new_trait(&mut db, "Baz");
+
+        assert!(!foo.is_disjoint_with(&db, bar));
+
+        baz.add_required_trait(&mut db, trait_instance(foo));
+        db.add_disjoint_traits(foo, bar, app_mod, Location::default());
+
+        assert!(foo.is_disjoint_with(&db, bar));
+        assert!(bar.is_disjoint_with(&db, foo));
+        assert!(baz.is_disjoint_with(&db, bar));
+        assert!(bar.is_disjoint_with(&db, baz));
+        assert!(!foo.is_disjoint_with(&db, baz));
+    }
+
+    #[test]
+    fn test_class_excludes_trait() {
+        let mut db = Database::new();
+        let app_mod = new_module(&mut db, "app");
+        let foo = new_trait(&mut db, "Foo");
+        let bar = new_trait(&mut db, "Bar");
+        let baz = new_trait(&mut db, "Baz");
+        let class = new_class(&mut db, "A");
+
+        class.set_module(&mut db, app_mod);
+        implement_in(&mut db, app_mod, 1, foo, class);
+
+        assert!(!class.excludes_trait(&db, bar));
+        assert!(!class.excludes_trait(&db, baz));
+
+        db.add_disjoint_traits(foo, bar, app_mod, Location::default());
+        db.add_negative_implementation(
+            class,
+            baz,
+            app_mod,
+            Location::default(),
+        );
+
+        assert!(class.excludes_trait(&db, bar));
+        assert!(class.excludes_trait(&db, baz));
+        assert!(!class.excludes_trait(&db, foo));
+    }
+
+    #[test]
+    fn test_overlapping_blanket_implementations() {
+        let mut db = Database::new();
+        let app_mod = new_module(&mut db, "app");
+        let format = new_trait(&mut db, "Format");
+        let debug = new_trait(&mut db, "Debug");
+        let to_string = new_trait(&mut db, "ToString");
+
+        blanket(&mut db, app_mod, 1, to_string, format);
+        blanket(&mut db, app_mod, 2, to_string, debug);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Code::OverlappingImplementation);
+        assert_eq!(diags[0].location, Location::new(&(2..=2), &(1..=1)));
+        assert_eq!(
+            diags[0].notes[0].location,
+            Some((app_mod, Location::new(&(1..=1), &(1..=1))))
+        );
+
+        db.add_disjoint_traits(format, debug, app_mod, Location::default());
+        assert!(check(&db).is_empty());
+    }
+
+    #[test]
+    fn test_contradicting_exclusions() {
+        let mut db = Database::new();
+        let app_mod = new_module(&mut db, "app");
+        let foo = new_trait(&mut db, "Foo");
+        let bar = new_trait(&mut db, "Bar");
+        let baz = new_trait(&mut db, "Baz");
+        let class = new_class(&mut db, "A");
+        let loc = Location::new(&(10..=10), &(1..=1));
+
+        class.set_module(&mut db, app_mod);
+        baz.add_required_trait(&mut db, trait_instance(foo));
+        implement_in(&mut db, app_mod, 1, foo, class);
+        implement_in(&mut db, app_mod, 2, bar, class);
+        db.add_disjoint_traits(foo, bar, app_mod, loc);
+        db.add_disjoint_traits(baz, foo, app_mod, loc);
+        db.add_negative_implementation(class, foo, app_mod, loc);
+
+        let diags = check(&db);
+
+        assert_eq!(diags.len(), 3);
+        assert!(diags.iter().all(|d| d.code == Code::ContradictingExclusion));
+        assert_eq!(diags[0].location, Location::new(&(2..=2), &(1..=1)));
+        assert_eq!(diags[0].notes[0].location, Some((app_mod, loc)));
+        assert_eq!(diags[1].location, loc);
+        assert_eq!(diags[2].location, Location::new(&(1..=1), &(1..=1)));
+    }
+}
@@ -0,0 +1,124 @@
+//! Fields computed using a getter method.
+//!
+//! A computed field is a field-like symbol that doesn't use any storage,
+//! instead its value is produced by calling a getter method. This allows a
+//! public field to be replaced by a method without breaking code that reads
+//! the field, as such code still finds the field using `ClassId::field()`.
+//!
+//! Computed fields are stored separately from regular fields, such that they
+//! don't affect the layout of instances of the class.
+use crate::{
+    Block, ClassId, Database, Field, FieldId, MethodId, ModuleId, Visibility,
+};
+use location::Location;
+
+impl ClassId {
+    /// Defines a new field of which the value is computed by calling the
+    /// getter method.
+    ///
+    /// The type of the field is the return type of the getter, and its index
+    /// is the position of the field in the list of computed fields.
+    pub fn new_computed_field(
+        self,
+        db: &mut Database,
+        name: String,
+        getter: MethodId,
+        visibility: Visibility,
+        module: ModuleId,
+        location: Location,
+    ) -> FieldId {
+        let index = self.get(db).computed_fields.len();
+        let typ = getter.return_type(db);
+        let id = Field::alloc(
+            db,
+            name.clone(),
+            index,
+            typ,
+            visibility,
+            module,
+            location,
+        );
+
+        id.get_mut(db).getter = Some(getter);
+        self.get_mut(db).computed_fields.insert(name, id);
+        id
+    }
+
+    /// Returns the computed fields of the class, in the order they're defined
+    /// in.
+    pub fn computed_fields(self, db: &Database) -> Vec<FieldId> {
+        self.get(db).computed_fields.values().cloned().collect()
+    }
+}
+
+impl FieldId {
+    /// Returns the method used to compute the value of the field, if it's a
+    /// computed field.
+    pub fn getter(self, db: &Database) -> Option<MethodId> {
+        self.get(db).getter
+    }
+
+    pub fn is_computed(self, db: &Database) -> bool {
+        self.get(db).getter.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{instance, new_class, owned};
+    use crate::{Method, MethodKind, TypeRef};
+
+    fn new_getter(db: &mut Database, class: ClassId, name: &str) -> MethodId {
+        let method = Method::alloc(
+            db,
+            ModuleId(0),
+            Location::default(),
+            name.to_string(),
+            Visibility::Public,
+            MethodKind::Instance,
+        );
+
+        method.set_receiver(db, owned(instance(class)));
+        method.set_return_type(db, TypeRef::int());
+        class.add_method(db, name.to_string(), method);
+        method
+    }
+
+    #[test]
+    fn test_new_computed_field() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Person");
+        let getter = new_getter(&mut db, class, "age");
+        let stored = class.new_field(
+            &mut db,
+            "name".to_string(),
+            0,
+            TypeRef::string(),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+        let computed = class.new_computed_field(
+            &mut db,
+            "age".to_string(),
+            getter,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert!(computed.is_computed(&db));
+        assert!(!stored.is_computed(&db));
+        assert_eq!(computed.getter(&db), Some(getter));
+        assert_eq!(computed.value_type(&db), TypeRef::int());
+        assert_eq!(computed.index(&db), 0);
+        assert_eq!(class.field(&db, "age"), Some(computed));
+        assert_eq!(class.field(&db, "name"), Some(stored));
+        assert_eq!(class.stored_field(&db, "age"), None);
+        assert_eq!(class.stored_field(&db, "name"), Some(stored));
+        assert_eq!(class.fields(&db), vec![stored]);
+        assert_eq!(class.computed_fields(&db), vec![computed]);
+        assert_eq!(class.number_of_fields(&db), 1);
+    }
+}
@@ -0,0 +1,270 @@
+//! Conditional compilation of symbols.
+//!
+//! Classes, methods and imports can specify a set of conditions that must be
+//! met for the symbol to be included in the program, such as the target
+//! operating system or architecture, or a feature flag. This allows
+//! platform-specific code to be defined in the same module, instead of
+//! requiring a separate module per platform with duplicate signatures.
+//!
+//! Conditions use the same build tags as conditional imports: the target's
+//! operating system (e.g. `linux` and `unix`), architecture (e.g. `amd64`) and
+//! ABI (e.g. `gnu`) are defined as tags, as are any enabled features. A set of
+//! conditions is met if all of its tags are defined.
+//!
+//! Once all symbols are defined, `resolve()` deactivates the symbols of which
+//! the conditions aren't met by removing them from the symbol tables they're
+//! defined in, such that they can no longer be looked up.
+use crate::{ClassId, Database, MethodId, ModuleId, Symbol};
+
+/// A set of build tags that must all be defined for a symbol to be included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Conditions {
+    pub(crate) tags: Vec<String>,
+}
+
+impl Conditions {
+    pub fn new(tags: Vec<String>) -> Conditions {
+        Conditions { tags }
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Returns `true` if all tags are defined for the current build.
+    pub fn is_met(&self, db: &Database) -> bool {
+        self.tags.iter().all(|t| db.is_build_tag_defined(t))
+    }
+}
+
+impl Database {
+    /// Defines a build tag, such as the name of the target operating system
+    /// or an enabled feature.
+    pub fn define_build_tag(&mut self, name: String) {
+        self.build_tags.insert(name);
+    }
+
+    pub fn is_build_tag_defined(&self, name: &str) -> bool {
+        self.build_tags.contains(name)
+    }
+}
+
+impl ClassId {
+    pub fn set_conditions(self, db: &mut Database, conditions: Conditions) {
+        self.get_mut(db).conditions = conditions;
+    }
+
+    pub fn conditions(self, db: &Database) -> &Conditions {
+        &self.get(db).conditions
+    }
+
+    /// Returns `true` if the class is included in the current build.
+    pub fn is_active(self, db: &Database) -> bool {
+        self.conditions(db).is_met(db)
+    }
+}
+
+impl MethodId {
+    pub fn set_conditions(self, db: &mut Database, conditions: Conditions) {
+        self.get_mut(db).conditions = conditions;
+    }
+
+    pub fn conditions(self, db: &Database) -> &Conditions {
+        &self.get(db).conditions
+    }
+
+    /// Returns `true` if the method is included in the current build.
+    pub fn is_active(self, db: &Database) -> bool {
+        self.conditions(db).is_met(db)
+    }
+}
+
+impl ModuleId {
+    /// Sets the conditions of the import of the symbol with the given name.
+    ///
+    /// If no symbol with the given name is imported, this method does nothing.
+    pub fn set_import_conditions(
+        self,
+        db: &mut Database,
+        name: &str,
+        conditions: Conditions,
+    ) {
+        if let Some(sym) = self.get_mut(db).symbols.get_mut(name) {
+            sym.conditions = conditions;
+        }
+    }
+
+    pub fn import_conditions<'a>(
+        self,
+        db: &'a Database,
+        name: &str,
+    ) -> Option<&'a Conditions> {
+        self.get(db).symbols.get(name).map(|s| &s.conditions)
+    }
+}
+
+impl Symbol {
+    /// Returns `true` if the symbol is included in the current build.
+    ///
+    /// Only classes and methods support conditions, so other symbols are
+    /// always active.
+    pub fn is_active(self, db: &Database) -> bool {
+        match self {
+            Symbol::Class(id) => id.is_active(db),
+            Symbol::Method(id) => id.is_active(db),
+            _ => true,
+        }
+    }
+}
+
+/// Deactivates all symbols of which the conditions aren't met, returning the
+/// deactivated symbols.
+///
+/// Symbols are deactivated by removing them from the modules that define or
+/// import them, while methods are also removed from the classes that define
+/// them.
+pub fn resolve(db: &mut Database) -> Vec<Symbol> {
+    let mut removed = Vec::new();
+
+    for idx in 0..db.modules.len() {
+        let module = ModuleId(idx as u32);
+        let mut names: Vec<_> = module
+            .get(db)
+            .symbols
+            .iter()
+            .filter(|(_, s)| {
+                !s.conditions.is_met(db) || !s.symbol.is_active(db)
+            })
+            .map(|(name, s)| (name.clone(), s.symbol))
+            .collect();
+
+        // Symbols are stored in a hash map, so we sort them to produce the
+        // results in a consistent order.
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, sym) in names {
+            module.get_mut(db).symbols.remove(&name);
+
+            if !removed.contains(&sym) {
+                removed.push(sym);
+            }
+        }
+    }
+
+    for idx in 0..db.classes.len() {
+        let class = ClassId(idx as u32);
+        let mut names: Vec<_> = class
+            .get(db)
+            .methods
+            .iter()
+            .filter(|(_, m)| !m.is_active(db))
+            .map(|(name, &m)| (name.clone(), m))
+            .collect();
+
+        names.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, method) in names {
+            class.get_mut(db).methods.remove(&name);
+
+            let sym = Symbol::Method(method);
+
+            if !removed.contains(&sym) {
+                removed.push(sym);
+            }
+        }
+    }
+
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_method, new_module};
+    use crate::{Location, MethodKind};
+
+    fn conditions(tags: &[&str]) -> Conditions {
+        Conditions::new(tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn test_conditions_is_met() {
+        let mut db = Database::new();
+
+        db.define_build_tag("linux".to_string());
+        db.define_build_tag("amd64".to_string());
+
+        assert!(Conditions::default().is_met(&db));
+        assert!(conditions(&["linux"]).is_met(&db));
+        assert!(conditions(&["linux", "amd64"]).is_met(&db));
+        assert!(!conditions(&["linux", "arm64"]).is_met(&db));
+        assert!(!conditions(&["mac"]).is_met(&db));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "std.sys");
+        let other = new_module(&mut db, "std.fs");
+        let linux = new_class(&mut db, "Linux");
+        let mac = new_class(&mut db, "Mac");
+        let shared = new_class(&mut db, "Shared");
+        let linux_method =
+            new_method(&mut db, module, "linux", MethodKind::Instance);
+        let mac_method =
+            new_method(&mut db, module, "mac", MethodKind::Instance);
+
+        shared.add_method(&mut db, "linux".to_string(), linux_method);
+        shared.add_method(&mut db, "mac".to_string(), mac_method);
+
+        db.define_build_tag("linux".to_string());
+        linux.set_conditions(&mut db, conditions(&["linux"]));
+        mac.set_conditions(&mut db, conditions(&["mac"]));
+        linux_method.set_conditions(&mut db, conditions(&["linux"]));
+        mac_method.set_conditions(&mut db, conditions(&["mac"]));
+
+        module.new_symbol(&mut db, "Linux".to_string(), Symbol::Class(linux));
+        module.new_symbol(&mut db, "Mac".to_string(), Symbol::Class(mac));
+        module.new_symbol(
+            &mut db,
+            "mac".to_string(),
+            Symbol::Method(mac_method),
+        );
+        other.new_imported_symbol(
+            &mut db,
+            "Shared".to_string(),
+            Symbol::Class(shared),
+            module,
+            Location::default(),
+        );
+        other.set_import_conditions(&mut db, "Shared", conditions(&["bsd"]));
+
+        assert!(linux.is_active(&db));
+        assert!(!mac.is_active(&db));
+        assert_eq!(
+            other.import_conditions(&db, "Shared"),
+            Some(&conditions(&["bsd"]))
+        );
+
+        let removed = resolve(&mut db);
+
+        assert_eq!(
+            removed,
+            vec![
+                Symbol::Class(mac),
+                Symbol::Method(mac_method),
+                Symbol::Class(shared)
+            ]
+        );
+        assert!(module.symbol_exists(&db, "Linux"));
+        assert!(!module.symbol_exists(&db, "Mac"));
+        assert!(!module.symbol_exists(&db, "mac"));
+        assert!(!other.symbol_exists(&db, "Shared"));
+        assert_eq!(shared.method(&db, "linux"), Some(linux_method));
+        assert_eq!(shared.method(&db, "mac"), None);
+    }
+}
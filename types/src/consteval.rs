@@ -0,0 +1,453 @@
+//! Evaluation of constant expressions.
+//!
+//! The values of module constants are limited to literals, references to
+//! other constants, arrays, and binary operations on these values. This module
+//! evaluates such expressions at type-checking time, such that the resulting
+//! values can be stored alongside their constants and used by later stages.
+use crate::{ConstantId, Database, TypeRef, ARRAY_LIMIT};
+use std::fmt;
+
+/// The value of a constant.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    Array(Vec<Value>),
+    Char(char),
+}
+
+impl Value {
+    /// Returns the type of the value.
+    ///
+    /// For arrays `None` is returned, as the type of an array depends on the
+    /// `Array` type being defined in the type database.
+    pub fn value_type(&self) -> Option<TypeRef> {
+        match self {
+            Value::Int(_) => Some(TypeRef::int()),
+            Value::Float(_) => Some(TypeRef::float()),
+            Value::String(_) => Some(TypeRef::string()),
+            Value::Bool(_) => Some(TypeRef::boolean()),
+            Value::Char(_) => Some(TypeRef::char()),
+            Value::Array(_) => None,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            // -0.0 and 0.0 are different constants, as treating them as the
+            // same would result in e.g. `-0.0.to_string` producing "0.0".
+            (Value::Float(a), Value::Float(b))
+                if a.is_sign_positive() == b.is_sign_positive() =>
+            {
+                a == b
+            }
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::String(v) => write!(f, "{:?}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Char(v) => write!(f, "{:?}", v),
+            Value::Array(vals) => {
+                f.write_str("[")?;
+
+                for (idx, val) in vals.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    val.fmt(f)?;
+                }
+
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// A binary operator supported in constant expressions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    And,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Div,
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Mod,
+    Mul,
+    Ne,
+    Or,
+    Pow,
+    Shl,
+    Shr,
+    Sub,
+    UnsignedShr,
+}
+
+impl Operator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::And => "and",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "^",
+            Operator::Div => "/",
+            Operator::Eq => "==",
+            Operator::Ge => ">=",
+            Operator::Gt => ">",
+            Operator::Le => "<=",
+            Operator::Lt => "<",
+            Operator::Mod => "%",
+            Operator::Mul => "*",
+            Operator::Ne => "!=",
+            Operator::Or => "or",
+            Operator::Pow => "**",
+            Operator::Shl => "<<",
+            Operator::Shr => ">>",
+            Operator::Sub => "-",
+            Operator::UnsignedShr => ">>>",
+        }
+    }
+}
+
+/// A constant expression to evaluate.
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    Char(char),
+    Constant(ConstantId),
+    Array(Vec<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+/// An error produced when evaluating a constant expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// A constant is referred to of which the value isn't known (yet).
+    ///
+    /// Constants may refer to constants defined later on, so this error
+    /// typically means the expression should be evaluated again once the
+    /// value of the constant is known.
+    Unknown(ConstantId),
+
+    /// The operator isn't supported for the operands, or the operation
+    /// overflowed.
+    Invalid(Value, Operator, Value),
+
+    /// An array contains more values than supported.
+    ArrayTooLarge(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unknown(_) => {
+                f.write_str("the value of the constant isn't known")
+            }
+            Error::Invalid(lhs, op, rhs) => write!(
+                f,
+                "the constant expression '{} {} {}' is invalid",
+                lhs,
+                op.as_str(),
+                rhs
+            ),
+            Error::ArrayTooLarge(size) => write!(
+                f,
+                "constant arrays are limited to at most {} values, \
+                but this array contains {} values",
+                ARRAY_LIMIT, size
+            ),
+        }
+    }
+}
+
+/// Evaluates a constant expression.
+pub fn evaluate(
+    db: &Database,
+    expression: &Expression,
+) -> Result<Value, Error> {
+    match expression {
+        Expression::Int(v) => Ok(Value::Int(*v)),
+        Expression::Float(v) => Ok(Value::Float(*v)),
+        Expression::String(v) => Ok(Value::String(v.clone())),
+        Expression::Bool(v) => Ok(Value::Bool(*v)),
+        Expression::Char(v) => Ok(Value::Char(*v)),
+        Expression::Constant(id) => {
+            id.value(db).cloned().ok_or(Error::Unknown(*id))
+        }
+        Expression::Array(exprs) => {
+            if exprs.len() > ARRAY_LIMIT {
+                return Err(Error::ArrayTooLarge(exprs.len()));
+            }
+
+            exprs
+                .iter()
+                .map(|e| evaluate(db, e))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array)
+        }
+        Expression::Binary(lhs, op, rhs) => {
+            binary(evaluate(db, lhs)?, *op, evaluate(db, rhs)?)
+        }
+    }
+}
+
+/// Evaluates a constant expression and stores the result in the constant.
+pub fn define(
+    db: &mut Database,
+    constant: ConstantId,
+    expression: &Expression,
+) -> Result<(), Error> {
+    let value = evaluate(db, expression)?;
+
+    constant.set_value(db, value);
+    Ok(())
+}
+
+fn binary(lhs: Value, op: Operator, rhs: Value) -> Result<Value, Error> {
+    let res = match (&lhs, &rhs) {
+        (&Value::Int(a), &Value::Int(b)) => int(a, op, b),
+        (&Value::Float(a), &Value::Float(b)) => float(a, op, b),
+        (Value::String(a), Value::String(b)) => match op {
+            Operator::Add => Some(Value::String(format!("{}{}", a, b))),
+            Operator::Eq => Some(Value::Bool(a == b)),
+            Operator::Ne => Some(Value::Bool(a != b)),
+            _ => None,
+        },
+        (&Value::Bool(a), &Value::Bool(b)) => match op {
+            Operator::And => Some(Value::Bool(a && b)),
+            Operator::Or => Some(Value::Bool(a || b)),
+            Operator::Eq => Some(Value::Bool(a == b)),
+            Operator::Ne => Some(Value::Bool(a != b)),
+            _ => None,
+        },
+        (&Value::Char(a), &Value::Char(b)) => compare(a.cmp(&b), op),
+        _ => None,
+    };
+
+    res.ok_or(Error::Invalid(lhs, op, rhs))
+}
+
+fn int(lhs: i64, op: Operator, rhs: i64) -> Option<Value> {
+    let val = match op {
+        Operator::Add => lhs.checked_add(rhs),
+        Operator::BitAnd => Some(lhs & rhs),
+        Operator::BitOr => Some(lhs | rhs),
+        Operator::BitXor => Some(lhs ^ rhs),
+        Operator::Div => lhs.checked_div(rhs),
+        Operator::Mod => lhs
+            .checked_rem(rhs)
+            .and_then(|v| v.checked_add(rhs))
+            .and_then(|v| v.checked_rem(rhs)),
+        Operator::Mul => lhs.checked_mul(rhs),
+        Operator::Pow => {
+            u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_pow(rhs))
+        }
+        Operator::Shl => {
+            u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shl(rhs))
+        }
+        Operator::Shr => {
+            u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs))
+        }
+        Operator::UnsignedShr => u32::try_from(rhs)
+            .ok()
+            .and_then(|rhs| (lhs as u64).checked_shr(rhs))
+            .map(|v| v as i64),
+        Operator::Sub => lhs.checked_sub(rhs),
+        _ => return compare(lhs.cmp(&rhs), op),
+    };
+
+    val.map(Value::Int)
+}
+
+fn float(lhs: f64, op: Operator, rhs: f64) -> Option<Value> {
+    let val = match op {
+        Operator::Add => lhs + rhs,
+        Operator::Div => lhs / rhs,
+        Operator::Mod => ((lhs % rhs) + rhs) % rhs,
+        Operator::Mul => lhs * rhs,
+        Operator::Pow => lhs.powf(rhs),
+        Operator::Sub => lhs - rhs,
+        _ => return compare(lhs.partial_cmp(&rhs)?, op),
+    };
+
+    Some(Value::Float(val))
+}
+
+fn compare(ordering: std::cmp::Ordering, op: Operator) -> Option<Value> {
+    let val = match op {
+        Operator::Eq => ordering.is_eq(),
+        Operator::Ne => ordering.is_ne(),
+        Operator::Ge => ordering.is_ge(),
+        Operator::Gt => ordering.is_gt(),
+        Operator::Le => ordering.is_le(),
+        Operator::Lt => ordering.is_lt(),
+        _ => return None,
+    };
+
+    Some(Value::Bool(val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_module;
+    use crate::{Constant, Visibility};
+    use location::Location;
+
+    fn bin(lhs: Expression, op: Operator, rhs: Expression) -> Expression {
+        Expression::Binary(Box::new(lhs), op, Box::new(rhs))
+    }
+
+    #[test]
+    fn test_evaluate_int() {
+        let db = Database::new();
+        let eval = |lhs, op, rhs| {
+            evaluate(&db, &bin(Expression::Int(lhs), op, Expression::Int(rhs)))
+        };
+
+        assert_eq!(eval(1, Operator::Add, 2), Ok(Value::Int(3)));
+        assert_eq!(eval(-5, Operator::Mod, 3), Ok(Value::Int(1)));
+        assert_eq!(eval(2, Operator::Pow, 3), Ok(Value::Int(8)));
+        assert_eq!(eval(-1, Operator::UnsignedShr, 60), Ok(Value::Int(15)));
+        assert_eq!(eval(1, Operator::Lt, 2), Ok(Value::Bool(true)));
+        assert_eq!(
+            eval(i64::MAX, Operator::Add, 1),
+            Err(Error::Invalid(
+                Value::Int(i64::MAX),
+                Operator::Add,
+                Value::Int(1)
+            ))
+        );
+        assert!(eval(1, Operator::Div, 0).is_err());
+        assert!(eval(1, Operator::And, 0).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_float() {
+        let db = Database::new();
+        let expr =
+            bin(Expression::Float(1.5), Operator::Mul, Expression::Float(2.0));
+
+        assert_eq!(evaluate(&db, &expr), Ok(Value::Float(3.0)));
+        assert_ne!(Value::Float(0.0), Value::Float(-0.0));
+    }
+
+    #[test]
+    fn test_evaluate_string_and_bool() {
+        let db = Database::new();
+        let concat = bin(
+            Expression::String("foo".to_string()),
+            Operator::Add,
+            Expression::String("bar".to_string()),
+        );
+        let logic = bin(
+            Expression::Bool(true),
+            Operator::And,
+            bin(Expression::Int(1), Operator::Eq, Expression::Int(2)),
+        );
+        let invalid = bin(
+            Expression::String("foo".to_string()),
+            Operator::Add,
+            Expression::Int(1),
+        );
+
+        assert_eq!(
+            evaluate(&db, &concat),
+            Ok(Value::String("foobar".to_string()))
+        );
+        assert_eq!(evaluate(&db, &logic), Ok(Value::Bool(false)));
+        assert_eq!(
+            evaluate(&db, &invalid).unwrap_err().to_string(),
+            "the constant expression '\"foo\" + 1' is invalid"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_char() {
+        let db = Database::new();
+        let cmp =
+            bin(Expression::Char('a'), Operator::Lt, Expression::Char('b'));
+        let add =
+            bin(Expression::Char('a'), Operator::Add, Expression::Char('b'));
+
+        assert_eq!(evaluate(&db, &Expression::Char('a')), Ok(Value::Char('a')));
+        assert_eq!(evaluate(&db, &cmp), Ok(Value::Bool(true)));
+        assert_eq!(
+            evaluate(&db, &add).unwrap_err().to_string(),
+            "the constant expression ''a' + 'b'' is invalid"
+        );
+        assert_eq!(Value::Char('a').value_type(), Some(TypeRef::char()));
+    }
+
+    #[test]
+    fn test_define() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let a = Constant::alloc(
+            &mut db,
+            module,
+            Location::default(),
+            "A".to_string(),
+            Visibility::Public,
+            TypeRef::int(),
+        );
+        let b = Constant::alloc(
+            &mut db,
+            module,
+            Location::default(),
+            "B".to_string(),
+            Visibility::Public,
+            TypeRef::Unknown,
+        );
+        let b_expr = Expression::Array(vec![
+            Expression::Constant(a),
+            bin(Expression::Constant(a), Operator::Mul, Expression::Int(2)),
+        ]);
+
+        assert_eq!(define(&mut db, b, &b_expr), Err(Error::Unknown(a)));
+        assert_eq!(define(&mut db, a, &Expression::Int(4)), Ok(()));
+        assert_eq!(define(&mut db, b, &b_expr), Ok(()));
+        assert_eq!(
+            b.value(&db),
+            Some(&Value::Array(vec![Value::Int(4), Value::Int(8)]))
+        );
+        assert_eq!(b.value(&db).unwrap().to_string(), "[4, 8]");
+    }
+
+    #[test]
+    fn test_evaluate_array_limit() {
+        let db = Database::new();
+        let expr = Expression::Array(vec![Expression::Int(1); ARRAY_LIMIT + 1]);
+
+        assert_eq!(
+            evaluate(&db, &expr),
+            Err(Error::ArrayTooLarge(ARRAY_LIMIT + 1))
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Default values of fields and constructors synthesized from them.
+//!
+//! Many classes define a static `new` method that does nothing but assign its
+//! arguments to the fields of the same name. Instead of writing such methods
+//! by hand, a class can have its constructor synthesized: the resulting `new`
+//! method defines an argument for every field without a default value, while
+//! fields with a default value are assigned that value.
+//!
+//! The body of the synthesized method is generated when lowering to MIR, the
+//! type database only stores its signature.
+use crate::consteval::Value;
+use crate::{
+    Block, ClassId, ClassInstance, ConstantId, Database, FieldId, Method,
+    MethodId, MethodKind, TypeBounds, TypeId, TypeRef, Visibility,
+};
+
+/// The name of the static method synthesized for a class.
+pub const SYNTHESIZED_CONSTRUCTOR: &str = "new";
+
+/// The default value of a field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldDefault {
+    /// The field defaults to a value known at compile-time.
+    Value(Value),
+
+    /// The field defaults to the value of a constant.
+    Constant(ConstantId),
+}
+
+impl FieldId {
+    pub fn set_default_value(self, db: &mut Database, value: FieldDefault) {
+        self.get_mut(db).default = Some(value);
+    }
+
+    pub fn default_value(self, db: &Database) -> Option<&FieldDefault> {
+        self.get(db).default.as_ref()
+    }
+
+    pub fn has_default_value(self, db: &Database) -> bool {
+        self.get(db).default.is_some()
+    }
+}
+
+impl ClassId {
+    /// Returns the fields to define as arguments of the synthesized
+    /// constructor, in the order they're defined in.
+    pub fn constructor_fields(self, db: &Database) -> Vec<FieldId> {
+        self.fields(db)
+            .into_iter()
+            .filter(|f| !f.has_default_value(db))
+            .collect()
+    }
+
+    /// Synthesizes the static `new` method for the class.
+    ///
+    /// The method defines an argument for every field without a default
+    /// value, using the name and type of the field. If the class is an enum or
+    /// already defines a `new` method, no method is synthesized and `None` is
+    /// returned.
+    pub fn synthesize_constructor(self, db: &mut Database) -> Option<MethodId> {
+        if self.kind(db).is_enum()
+            || self.method(db, SYNTHESIZED_CONSTRUCTOR).is_some()
+        {
+            return None;
+        }
+
+        let module = self.module(db);
+        let loc = self.location(db);
+        let bounds = TypeBounds::new();
+        let method = Method::alloc(
+            db,
+            module,
+            loc,
+            SYNTHESIZED_CONSTRUCTOR.to_string(),
+            Visibility::Public,
+            MethodKind::Static,
+        );
+
+        // Just like enum constructors these methods only assign fields, so we
+        // always inline them.
+        method.always_inline(db);
+
+        for field in self.constructor_fields(db) {
+            let name = field.name(db).clone();
+            let typ = field.value_type(db);
+            let var_type = typ.as_rigid_type(db, &bounds);
+
+            method.new_argument(db, name, var_type, typ, field.location(db));
+        }
+
+        let ret = if self.is_generic(db) {
+            let args = self
+                .type_parameters(db)
+                .into_iter()
+                .map(|param| TypeRef::Any(TypeId::TypeParameter(param)))
+                .collect();
+
+            ClassInstance::with_types(db, self, args)
+        } else {
+            ClassInstance::new(self)
+        };
+
+        method.set_receiver(db, TypeRef::Owned(TypeId::Class(self)));
+        method.set_return_type(db, TypeRef::Owned(TypeId::ClassInstance(ret)));
+        self.add_method(db, SYNTHESIZED_CONSTRUCTOR.to_string(), method);
+        self.get_mut(db).synthesized_constructor = Some(method);
+        Some(method)
+    }
+
+    /// Returns the synthesized constructor of the class, if any.
+    pub fn synthesized_constructor(self, db: &Database) -> Option<MethodId> {
+        self.get(db).synthesized_constructor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_enum_class, parameter};
+    use crate::{Location, ModuleId};
+
+    fn new_field(db: &mut Database, class: ClassId, name: &str, typ: TypeRef) {
+        let index = class.number_of_fields(db);
+
+        class.new_field(
+            db,
+            name.to_string(),
+            index,
+            typ,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+    }
+
+    #[test]
+    fn test_field_default_value() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Point");
+
+        new_field(&mut db, class, "x", TypeRef::int());
+
+        let field = class.field(&db, "x").unwrap();
+
+        assert!(!field.has_default_value(&db));
+        field.set_default_value(&mut db, FieldDefault::Value(Value::Int(1)));
+        assert_eq!(
+            field.default_value(&db),
+            Some(&FieldDefault::Value(Value::Int(1)))
+        );
+    }
+
+    #[test]
+    fn test_synthesize_constructor() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Point");
+
+        new_field(&mut db, class, "x", TypeRef::int());
+        new_field(&mut db, class, "y", TypeRef::int());
+        new_field(&mut db, class, "z", TypeRef::int());
+        class
+            .field(&db, "y")
+            .unwrap()
+            .set_default_value(&mut db, FieldDefault::Value(Value::Int(0)));
+
+        let method = class.synthesize_constructor(&mut db).unwrap();
+        let names: Vec<_> =
+            method.arguments(&db).into_iter().map(|a| a.name).collect();
+
+        assert_eq!(names, vec!["x".to_string(), "z".to_string()]);
+        assert_eq!(method.kind(&db), MethodKind::Static);
+        assert_eq!(class.method(&db, "new"), Some(method));
+        assert_eq!(class.synthesized_constructor(&db), Some(method));
+        assert_eq!(
+            method.return_type(&db),
+            TypeRef::Owned(TypeId::ClassInstance(ClassInstance::new(class)))
+        );
+        assert_eq!(class.synthesize_constructor(&mut db), None);
+    }
+
+    #[test]
+    fn test_synthesize_constructor_with_generic_class() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Box");
+        let param = class.new_type_parameter(&mut db, "T".to_string());
+
+        new_field(&mut db, class, "value", TypeRef::Any(parameter(param)));
+
+        let method = class.synthesize_constructor(&mut db).unwrap();
+        let arg = method.arguments(&db).pop().unwrap();
+
+        assert_eq!(arg.value_type, TypeRef::Any(parameter(param)));
+        assert_eq!(method.return_type(&db).class_id(&db), Some(class));
+    }
+
+    #[test]
+    fn test_synthesize_constructor_with_enum() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Option");
+
+        assert_eq!(class.synthesize_constructor(&mut db), None);
+        assert_eq!(class.synthesized_constructor(&db), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Deprecation of symbols.
+//!
+//! Classes, traits, methods and constants can be marked as deprecated. When
+//! such a symbol is referred to, a warning is produced, unless the code
+//! referring to the symbol is itself deprecated. This allows deprecated code
+//! to keep using other deprecated code, without producing a flood of
+//! warnings.
+use crate::diagnostics::Diagnostic;
+use crate::{Database, MethodId, ModuleId, Symbol, TypeId};
+use location::Location;
+
+/// Information about why and when a symbol is deprecated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deprecation {
+    /// A message explaining the deprecation, such as what to use instead.
+    pub message: String,
+
+    /// The version in which the symbol is deprecated, if known.
+    pub since: Option<String>,
+}
+
+/// Returns the deprecation of a symbol and its name, if the symbol is
+/// deprecated.
+fn deprecation(db: &Database, symbol: Symbol) -> Option<(&Deprecation, &str)> {
+    match symbol {
+        Symbol::Class(id) => id.deprecated(db).map(|d| (d, id.name(db))),
+        Symbol::Trait(id) => id.deprecated(db).map(|d| (d, id.name(db))),
+        Symbol::Method(id) => id.deprecated(db).map(|d| (d, id.name(db))),
+        Symbol::Constant(id) => id.deprecated(db).map(|d| (d, id.name(db))),
+        _ => None,
+    }
+    .map(|(dep, name)| (dep, name.as_str()))
+}
+
+/// Returns `true` if code in the given method is considered deprecated.
+///
+/// This is the case if either the method is deprecated, or the type it's
+/// defined on is deprecated.
+pub fn in_deprecated_code(db: &Database, method: MethodId) -> bool {
+    if method.is_deprecated(db) {
+        return true;
+    }
+
+    match method.receiver(db).type_id(db) {
+        Ok(TypeId::Class(id)) => id.is_deprecated(db),
+        Ok(TypeId::ClassInstance(ins)) => ins.instance_of().is_deprecated(db),
+        Ok(TypeId::Trait(id)) => id.is_deprecated(db),
+        Ok(TypeId::TraitInstance(ins)) => ins.instance_of().is_deprecated(db),
+        _ => false,
+    }
+}
+
+/// Checks a reference to a symbol, returning a warning if the symbol is
+/// deprecated.
+///
+/// The `method` argument is the method in which the reference occurs, or
+/// `None` if it occurs outside of a method (e.g. in a constant definition).
+pub fn check_reference(
+    db: &Database,
+    symbol: Symbol,
+    method: Option<MethodId>,
+    module: ModuleId,
+    location: Location,
+) -> Option<Diagnostic> {
+    let (dep, name) = deprecation(db, symbol)?;
+
+    if method.map_or(false, |m| in_deprecated_code(db, m)) {
+        return None;
+    }
+
+    Some(Diagnostic::deprecated_symbol(name, dep, module, location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{Code, Severity};
+    use crate::test::{instance, new_class, new_method, new_module, owned};
+    use crate::MethodKind;
+
+    fn deprecation() -> Deprecation {
+        Deprecation {
+            message: "use Bar instead".to_string(),
+            since: Some("1.2.0".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_check_reference() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let old = new_class(&mut db, "Old");
+        let new = new_class(&mut db, "New");
+        let in_old =
+            new_method(&mut db, module, "in_old", MethodKind::Instance);
+        let in_new =
+            new_method(&mut db, module, "in_new", MethodKind::Instance);
+        let deprecated =
+            new_method(&mut db, module, "deprecated", MethodKind::Instance);
+        let loc = Location::default();
+
+        in_old.set_receiver(&mut db, owned(instance(old)));
+        in_new.set_receiver(&mut db, owned(instance(new)));
+        deprecated.set_receiver(&mut db, owned(instance(new)));
+
+        old.set_deprecated(&mut db, deprecation());
+        deprecated.set_deprecated(&mut db, deprecation());
+
+        let diag =
+            check_reference(&db, Symbol::Class(old), Some(in_new), module, loc)
+                .unwrap();
+
+        assert_eq!(diag.code, Code::DeprecatedSymbol);
+        assert_eq!(diag.severity, Severity::Warning);
+        assert_eq!(diag.message, "'Old' is deprecated since version 1.2.0");
+        assert_eq!(diag.notes[0].message, "use Bar instead");
+
+        assert!(check_reference(&db, Symbol::Class(old), None, module, loc)
+            .is_some());
+        assert!(check_reference(
+            &db,
+            Symbol::Class(old),
+            Some(in_old),
+            module,
+            loc
+        )
+        .is_none());
+        assert!(check_reference(
+            &db,
+            Symbol::Class(old),
+            Some(deprecated),
+            module,
+            loc
+        )
+        .is_none());
+        assert!(check_reference(
+            &db,
+            Symbol::Class(new),
+            Some(in_new),
+            module,
+            loc
+        )
+        .is_none());
+    }
+}
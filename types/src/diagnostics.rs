@@ -0,0 +1,1068 @@
+//! Structured diagnostics produced by the type system.
+//!
+//! The type checker and related types mostly return booleans, leaving it up to
+//! the caller to produce an error message. This module provides the types used
+//! to describe such errors in a structured manner, such that tools (e.g. an
+//! editor or CI tooling) can filter and deduplicate them based on their codes,
+//! instead of having to parse messages.
+use crate::abi::AbiError;
+use crate::coherence::{DisjointTraits, NegativeImplementation};
+use crate::deprecation::Deprecation;
+use crate::format::format_type;
+use crate::lookup::Ambiguity;
+use crate::operators::OperatorError;
+use crate::origin::ExpressionKey;
+use crate::patterns::NamedArgumentError;
+use crate::suggest::SuggestionScope;
+use crate::tags::TagError;
+use crate::{
+    Block, ClassId, ClassInstance, ConstructorId, Database, FieldId, ModuleId,
+    TraitId, TraitImplementation, TraitInstance, TypeAliasId, TypeRef,
+};
+use location::Location;
+use std::fmt;
+
+/// The unique and stable code of a diagnostic.
+///
+/// The string representation of a code is part of the public interface, and
+/// must not change once introduced.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Code {
+    TypeMismatch,
+    CyclicTypeAlias,
+    MissingAssociatedType,
+    InvalidAssociatedType,
+    OverlappingImplementation,
+    OrphanImplementation,
+    DeprecatedSymbol,
+    InvalidLayout,
+    UnsafeFieldRead,
+    NullablePointerDereference,
+    DynIncompatibleTrait,
+    SpecializationLimit,
+    ContradictingExclusion,
+    InvalidPattern,
+    InvalidEnumTag,
+    InvalidOperator,
+    InvalidAbi,
+    MissingTraitConstant,
+    AmbiguousMethod,
+}
+
+impl Code {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Code::TypeMismatch => "type-mismatch",
+            Code::CyclicTypeAlias => "cyclic-type-alias",
+            Code::MissingAssociatedType => "missing-associated-type",
+            Code::InvalidAssociatedType => "invalid-associated-type",
+            Code::OverlappingImplementation => "overlapping-implementation",
+            Code::OrphanImplementation => "orphan-implementation",
+            Code::DeprecatedSymbol => "deprecated-symbol",
+            Code::InvalidLayout => "invalid-layout",
+            Code::UnsafeFieldRead => "unsafe-field-read",
+            Code::NullablePointerDereference => "nullable-pointer-dereference",
+            Code::DynIncompatibleTrait => "dyn-incompatible-trait",
+            Code::SpecializationLimit => "specialization-limit",
+            Code::ContradictingExclusion => "contradicting-exclusion",
+            Code::InvalidPattern => "invalid-pattern",
+            Code::InvalidEnumTag => "invalid-enum-tag",
+            Code::InvalidOperator => "invalid-operator",
+            Code::InvalidAbi => "invalid-abi",
+            Code::MissingTraitConstant => "missing-trait-constant",
+            Code::AmbiguousMethod => "ambiguous-method",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The severity of a diagnostic.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Additional information attached to a diagnostic.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Note {
+    pub message: String,
+
+    /// The location the note refers to, if any.
+    pub location: Option<(ModuleId, Location)>,
+}
+
+/// A single warning or error.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub code: Code,
+    pub severity: Severity,
+    pub message: String,
+    pub module: ModuleId,
+    pub location: Location,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    pub fn error(
+        code: Code,
+        message: String,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic {
+            code,
+            severity: Severity::Error,
+            message,
+            module,
+            location,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn warning(
+        code: Code,
+        message: String,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic {
+            code,
+            severity: Severity::Warning,
+            message,
+            module,
+            location,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn type_mismatch(
+        db: &Database,
+        given: TypeRef,
+        expected: TypeRef,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::TypeMismatch,
+            format!(
+                "expected a value of type '{}', found '{}'",
+                format_type(db, expected),
+                format_type(db, given)
+            ),
+            module,
+            location,
+        )
+    }
+
+    pub fn cyclic_type_alias(db: &Database, alias: TypeAliasId) -> Diagnostic {
+        Diagnostic::error(
+            Code::CyclicTypeAlias,
+            format!(
+                "the type alias '{}' can't be expanded because it refers to \
+                itself",
+                alias.name(db)
+            ),
+            alias.module(db),
+            alias.location(db),
+        )
+    }
+
+    pub fn missing_associated_type(
+        db: &Database,
+        instance: TraitInstance,
+        name: &str,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let trait_id = instance.instance_of();
+
+        Diagnostic::error(
+            Code::MissingAssociatedType,
+            format!(
+                "the associated type '{}' of trait '{}' must be assigned a \
+                value",
+                name,
+                format_type(db, instance)
+            ),
+            module,
+            location,
+        )
+        .with_note(
+            format!("the trait '{}' is defined here", trait_id.name(db)),
+            Some((trait_id.module(db), trait_id.location(db))),
+        )
+    }
+
+    pub fn missing_trait_constant(
+        db: &Database,
+        instance: TraitInstance,
+        name: &str,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let trait_id = instance.instance_of();
+
+        Diagnostic::error(
+            Code::MissingTraitConstant,
+            format!(
+                "the constant '{}' of trait '{}' must be defined",
+                name,
+                format_type(db, instance)
+            ),
+            module,
+            location,
+        )
+        .with_note(
+            format!("the trait '{}' is defined here", trait_id.name(db)),
+            Some((trait_id.module(db), trait_id.location(db))),
+        )
+    }
+
+    pub fn invalid_associated_type(
+        db: &Database,
+        instance: TraitInstance,
+        name: &str,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let value =
+            instance.associated_type(db, name).unwrap_or(TypeRef::Error);
+
+        Diagnostic::error(
+            Code::InvalidAssociatedType,
+            format!(
+                "the type '{}' doesn't meet the requirements of the \
+                associated type '{}'",
+                format_type(db, value),
+                name
+            ),
+            module,
+            location,
+        )
+    }
+
+    pub fn overlapping_implementation(
+        db: &Database,
+        class: ClassId,
+        existing: &TraitImplementation,
+        overlapping: &TraitImplementation,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::OverlappingImplementation,
+            format!(
+                "the trait '{}' is already implemented for class '{}'",
+                format_type(db, overlapping.instance),
+                class.name(db)
+            ),
+            overlapping.module,
+            overlapping.location,
+        )
+        .with_note(
+            "the existing implementation is defined here".to_string(),
+            Some((existing.module, existing.location)),
+        )
+    }
+
+    pub fn overlapping_blanket_implementation(
+        db: &Database,
+        existing: &TraitImplementation,
+        overlapping: &TraitImplementation,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::OverlappingImplementation,
+            format!(
+                "this implementation of trait '{}' overlaps with an existing \
+                blanket implementation",
+                format_type(db, overlapping.instance),
+            ),
+            overlapping.module,
+            overlapping.location,
+        )
+        .with_note(
+            "the existing implementation is defined here".to_string(),
+            Some((existing.module, existing.location)),
+        )
+        .with_note(
+            "the implementations don't overlap if the traits required by \
+            their type parameters are mutually exclusive"
+                .to_string(),
+            None,
+        )
+    }
+
+    pub fn self_excluding_traits(
+        db: &Database,
+        pair: &DisjointTraits,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::ContradictingExclusion,
+            format!(
+                "the traits '{}' and '{}' can't be mutually exclusive, as \
+                one requires the other",
+                pair.left.name(db),
+                pair.right.name(db)
+            ),
+            pair.module,
+            pair.location,
+        )
+    }
+
+    pub fn contradicting_disjoint_traits(
+        db: &Database,
+        class: ClassId,
+        pair: &DisjointTraits,
+        implementation: &TraitImplementation,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::ContradictingExclusion,
+            format!(
+                "the traits '{}' and '{}' are mutually exclusive, but both \
+                are implemented for class '{}'",
+                pair.left.name(db),
+                pair.right.name(db),
+                class.name(db)
+            ),
+            implementation.module,
+            implementation.location,
+        )
+        .with_note(
+            "the traits are marked as mutually exclusive here".to_string(),
+            Some((pair.module, pair.location)),
+        )
+    }
+
+    pub fn contradicting_negative_implementation(
+        db: &Database,
+        negative: &NegativeImplementation,
+        implementation: &TraitImplementation,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::ContradictingExclusion,
+            format!(
+                "the trait '{}' is implemented for class '{}', but the class \
+                is marked as not implementing it",
+                negative.trait_id.name(db),
+                negative.class.name(db)
+            ),
+            implementation.module,
+            implementation.location,
+        )
+        .with_note(
+            "the class is marked as not implementing the trait here"
+                .to_string(),
+            Some((negative.module, negative.location)),
+        )
+    }
+
+    pub fn orphan_implementation(
+        db: &Database,
+        class: ClassId,
+        implementation: &TraitImplementation,
+    ) -> Diagnostic {
+        let trait_id = implementation.instance.instance_of();
+
+        Diagnostic::error(
+            Code::OrphanImplementation,
+            format!(
+                "the trait '{}' can't be implemented for class '{}', as \
+                neither are defined in the current package",
+                format_type(db, implementation.instance),
+                class.name(db)
+            ),
+            implementation.module,
+            implementation.location,
+        )
+        .with_note(
+            format!("the class '{}' is defined here", class.name(db)),
+            Some((class.module(db), class.location(db))),
+        )
+        .with_note(
+            format!("the trait '{}' is defined here", trait_id.name(db)),
+            Some((trait_id.module(db), trait_id.location(db))),
+        )
+    }
+
+    pub fn ambiguous_method(
+        db: &Database,
+        class: ClassId,
+        ambiguity: &Ambiguity,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let method = ambiguity.method;
+        let name = method.name(db);
+        let mut diag = Diagnostic::warning(
+            Code::AmbiguousMethod,
+            format!(
+                "the method '{}' of '{}' shadows the method '{}' of one or \
+                more traits it implements, and is used when calling the \
+                method through these traits",
+                name,
+                format_type(db, class),
+                name
+            ),
+            module,
+            location,
+        )
+        .with_note(
+            format!("the method '{}' is defined here", name),
+            Some((method.module(db), method.location(db))),
+        );
+
+        for &id in &ambiguity.traits {
+            diag = diag.with_note(
+                format!(
+                    "use '{}.{}(value)' to call the method of trait '{}'",
+                    id.name(db),
+                    name,
+                    id.name(db)
+                ),
+                Some((id.module(db), id.location(db))),
+            );
+        }
+
+        diag
+    }
+
+    pub fn deprecated_symbol(
+        name: &str,
+        deprecation: &Deprecation,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let since = deprecation
+            .since
+            .as_ref()
+            .map(|v| format!(" since version {}", v))
+            .unwrap_or_default();
+        let diag = Diagnostic::warning(
+            Code::DeprecatedSymbol,
+            format!("'{}' is deprecated{}", name, since),
+            module,
+            location,
+        );
+
+        if deprecation.message.is_empty() {
+            diag
+        } else {
+            diag.with_note(deprecation.message.clone(), None)
+        }
+    }
+
+    pub fn invalid_named_argument(
+        db: &Database,
+        constructor: ConstructorId,
+        error: &NamedArgumentError,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        let cons = constructor.name(db);
+
+        match error {
+            NamedArgumentError::Undefined(name) => {
+                let diag = Diagnostic::error(
+                    Code::InvalidPattern,
+                    format!(
+                        "the constructor '{}' doesn't define an argument \
+                        named '{}'",
+                        cons, name
+                    ),
+                    module,
+                    location,
+                );
+                let scope = SuggestionScope::Constructor(constructor);
+
+                match db.similar_symbols(scope, name, 1).pop() {
+                    Some(similar) => diag.with_note(
+                        format!("did you mean '{}'?", similar),
+                        None,
+                    ),
+                    None => diag,
+                }
+            }
+            NamedArgumentError::Duplicate(name) => Diagnostic::error(
+                Code::InvalidPattern,
+                format!(
+                    "the argument '{}' of constructor '{}' is already \
+                    specified",
+                    name, cons
+                ),
+                module,
+                location,
+            ),
+        }
+    }
+
+    pub fn invalid_enum_tag(
+        db: &Database,
+        class: ClassId,
+        error: TagError,
+    ) -> Diagnostic {
+        let (id, message) = match error {
+            TagError::Duplicate(id, existing) => (
+                id,
+                format!(
+                    "the tag value {} of constructor '{}' is already used by \
+                    constructor '{}'",
+                    id.tag_value(db),
+                    id.name(db),
+                    existing.name(db)
+                ),
+            ),
+            TagError::OutOfRange(id, typ) => (
+                id,
+                format!(
+                    "the tag value {} of constructor '{}' doesn't fit in \
+                    type '{}'",
+                    id.tag_value(db),
+                    id.name(db),
+                    format_type(db, typ)
+                ),
+            ),
+        };
+
+        Diagnostic::error(
+            Code::InvalidEnumTag,
+            message,
+            class.module(db),
+            id.location(db),
+        )
+    }
+
+    pub fn invalid_operator_method(
+        db: &Database,
+        error: OperatorError,
+    ) -> Diagnostic {
+        let (id, message) = match error {
+            OperatorError::InvalidArguments(id, op) => (
+                id,
+                format!(
+                    "the operator method '{}' must define {} argument(s), \
+                    but it defines {}",
+                    op.name,
+                    op.arguments,
+                    id.number_of_arguments(db)
+                ),
+            ),
+            OperatorError::InvalidReceiver(id, op) => (
+                id,
+                format!(
+                    "the operator method '{}' must be an instance method",
+                    op.name
+                ),
+            ),
+        };
+
+        Diagnostic::error(
+            Code::InvalidOperator,
+            message,
+            id.module(db),
+            id.location(db),
+        )
+    }
+
+    pub fn invalid_abi(db: &Database, error: AbiError) -> Diagnostic {
+        let id = error.method();
+        let name = id.name(db);
+        let mut location = id.location(db);
+        let message = match error {
+            AbiError::Generic(_) => format!(
+                "the method '{}' uses the C calling convention and can't \
+                define type parameters",
+                name
+            ),
+            AbiError::InvalidArgument(_, index) => {
+                let arg = id.arguments(db).swap_remove(index);
+
+                location = arg.variable.location(db);
+                format!(
+                    "the argument '{}' of type '{}' isn't a valid C type",
+                    arg.name,
+                    format_type(db, arg.value_type)
+                )
+            }
+            AbiError::InvalidReturnType(_) => format!(
+                "the return type '{}' of method '{}' isn't a valid C type",
+                format_type(db, id.return_type(db)),
+                name
+            ),
+            AbiError::VariadicMethod(_) => format!(
+                "the method '{}' can't be variadic, as only external \
+                functions can be variadic",
+                name
+            ),
+            AbiError::MissingFixedArgument(_) => format!(
+                "the variadic function '{}' must define at least one \
+                non-variadic argument",
+                name
+            ),
+        };
+
+        Diagnostic::error(Code::InvalidAbi, message, id.module(db), location)
+    }
+
+    pub fn invalid_layout(
+        db: &Database,
+        class: ClassId,
+        message: String,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::InvalidLayout,
+            format!(
+                "the layout of class '{}' is invalid: {}",
+                class.name(db),
+                message
+            ),
+            class.module(db),
+            class.location(db),
+        )
+    }
+
+    pub fn unsafe_field_read(
+        db: &Database,
+        class: ClassId,
+        field: FieldId,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic::warning(
+            Code::UnsafeFieldRead,
+            format!(
+                "reading the field '{}' of union '{}' is unsafe, as it may \
+                not be the field that was last written to",
+                field.name(db),
+                class.name(db)
+            ),
+            module,
+            location,
+        )
+    }
+
+    pub fn nullable_pointer_dereference(
+        db: &Database,
+        pointer: TypeRef,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::NullablePointerDereference,
+            format!(
+                "a pointer of type '{}' may be NULL, and must be checked \
+                before it's dereferenced",
+                format_type(db, pointer)
+            ),
+            module,
+            location,
+        )
+        .with_note(
+            "cast the pointer to a NonNullPointer after checking it"
+                .to_string(),
+            None,
+        )
+    }
+
+    pub fn dyn_incompatible_trait(
+        db: &Database,
+        trait_id: TraitId,
+        module: ModuleId,
+        location: Location,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::DynIncompatibleTrait,
+            format!(
+                "the trait '{}' can't be used as a trait object",
+                trait_id.name(db)
+            ),
+            module,
+            location,
+        )
+    }
+
+    pub fn too_many_specializations(
+        db: &Database,
+        class: ClassId,
+        limit: usize,
+    ) -> Diagnostic {
+        Diagnostic::error(
+            Code::SpecializationLimit,
+            format!(
+                "the type '{}' can't be specialized more than {} times",
+                class.name(db),
+                limit
+            ),
+            class.module(db),
+            class.location(db),
+        )
+    }
+
+    /// Returns a diagnostic for a type that's specialized recursively, such as
+    /// a type that contains itself with ever growing type arguments.
+    ///
+    /// The `chain` argument is the list of types being specialized, starting
+    /// with the outermost type.
+    pub fn recursive_specialization(
+        db: &Database,
+        instance: ClassInstance,
+        chain: &[ClassInstance],
+    ) -> Diagnostic {
+        let class = instance.instance_of();
+        let mut diag = Diagnostic::error(
+            Code::SpecializationLimit,
+            format!(
+                "the type '{}' is specialized recursively, exceeding the \
+                limit of {} nested specializations",
+                format_type(db, instance),
+                chain.len()
+            ),
+            class.module(db),
+            class.location(db),
+        );
+
+        for &ins in chain.iter().rev() {
+            let class = ins.instance_of();
+
+            diag = diag.with_note(
+                format!("required by '{}'", format_type(db, ins)),
+                Some((class.module(db), class.location(db))),
+            );
+        }
+
+        diag
+    }
+
+    /// Adds notes pointing to the origins of the types of a type mismatch.
+    ///
+    /// Notes are only added for the types of which an origin is recorded.
+    pub fn with_type_origins(
+        mut self,
+        db: &Database,
+        given: TypeRef,
+        given_expression: ExpressionKey,
+        expected: TypeRef,
+        expected_expression: ExpressionKey,
+    ) -> Diagnostic {
+        if let Some(origin) = db.origin(given_expression) {
+            self = self.with_note(
+                format!(
+                    "the type '{}' originates from here",
+                    format_type(db, given)
+                ),
+                Some((origin.module, origin.location)),
+            );
+        }
+
+        if let Some(origin) = db.origin(expected_expression) {
+            self = self.with_note(
+                format!(
+                    "the type '{}' is expected because of this annotation",
+                    format_type(db, expected)
+                ),
+                Some((origin.module, origin.location)),
+            );
+        }
+
+        self
+    }
+
+    pub fn with_note(
+        mut self,
+        message: String,
+        location: Option<(ModuleId, Location)>,
+    ) -> Diagnostic {
+        self.notes.push(Note { message, location });
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// A collection of diagnostics.
+///
+/// Diagnostics are deduplicated when added, as the same type may be checked
+/// many times in different places, each producing the same diagnostic.
+#[derive(Default)]
+pub struct Diagnostics {
+    values: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics { values: Vec::new() }
+    }
+
+    pub fn add(&mut self, diagnostic: Diagnostic) {
+        if !self.values.contains(&diagnostic) {
+            self.values.push(diagnostic);
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.values.iter().any(|d| d.is_error())
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.values.iter()
+    }
+
+    /// Returns the diagnostics with the given code.
+    pub fn with_code(&self, code: Code) -> impl Iterator<Item = &Diagnostic> {
+        self.values.iter().filter(move |d| d.code == code)
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_enum_class, new_module, new_type_alias};
+    use crate::{Method, MethodKind, Visibility};
+
+    #[test]
+    fn test_code_as_str() {
+        assert_eq!(Code::TypeMismatch.as_str(), "type-mismatch");
+        assert_eq!(Code::CyclicTypeAlias.to_string(), "cyclic-type-alias");
+    }
+
+    #[test]
+    fn test_diagnostics_add() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let mut diags = Diagnostics::new();
+        let loc = Location::default();
+
+        diags.add(Diagnostic::type_mismatch(
+            &db,
+            TypeRef::int(),
+            TypeRef::string(),
+            module,
+            loc,
+        ));
+        diags.add(Diagnostic::type_mismatch(
+            &db,
+            TypeRef::int(),
+            TypeRef::string(),
+            module,
+            loc,
+        ));
+        diags.add(Diagnostic::warning(
+            Code::TypeMismatch,
+            "foo".to_string(),
+            module,
+            loc,
+        ));
+
+        let alias = new_type_alias(&mut db, "A", TypeRef::int());
+
+        diags.add(Diagnostic::cyclic_type_alias(&db, alias));
+
+        assert_eq!(diags.len(), 3);
+        assert!(diags.has_errors());
+        assert_eq!(diags.with_code(Code::TypeMismatch).count(), 2);
+        assert_eq!(
+            diags.iter().next().unwrap().message,
+            "expected a value of type 'String', found 'Int'"
+        );
+    }
+
+    #[test]
+    fn test_invalid_named_argument() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_enum_class(&mut db, "Shape");
+        let loc = Location::default();
+        let circle = class.new_constructor(
+            &mut db,
+            "Circle".to_string(),
+            vec![TypeRef::float()],
+            loc,
+        );
+
+        circle.set_argument_names(&mut db, vec!["radius".to_string()]);
+
+        let typo = Diagnostic::invalid_named_argument(
+            &db,
+            circle,
+            &NamedArgumentError::Undefined("radus".to_string()),
+            module,
+            loc,
+        );
+        let undefined = Diagnostic::invalid_named_argument(
+            &db,
+            circle,
+            &NamedArgumentError::Undefined("size".to_string()),
+            module,
+            loc,
+        );
+        let duplicate = Diagnostic::invalid_named_argument(
+            &db,
+            circle,
+            &NamedArgumentError::Duplicate("radius".to_string()),
+            module,
+            loc,
+        );
+
+        assert_eq!(typo.code, Code::InvalidPattern);
+        assert_eq!(
+            typo.message,
+            "the constructor 'Circle' doesn't define an argument named 'radus'"
+        );
+        assert_eq!(typo.notes[0].message, "did you mean 'radius'?");
+        assert!(undefined.notes.is_empty());
+        assert_eq!(
+            duplicate.message,
+            "the argument 'radius' of constructor 'Circle' is already specified"
+        );
+    }
+
+    #[test]
+    fn test_invalid_enum_tag() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Color");
+        let loc = Location::default();
+        let red =
+            class.new_constructor(&mut db, "Red".to_string(), Vec::new(), loc);
+        let green = class.new_constructor(
+            &mut db,
+            "Green".to_string(),
+            Vec::new(),
+            loc,
+        );
+
+        green.set_tag_value(&mut db, 300);
+
+        let range = Diagnostic::invalid_enum_tag(
+            &db,
+            class,
+            TagError::OutOfRange(green, TypeRef::foreign_unsigned_int(8)),
+        );
+
+        green.set_tag_value(&mut db, 0);
+
+        let dupe = Diagnostic::invalid_enum_tag(
+            &db,
+            class,
+            TagError::Duplicate(green, red),
+        );
+
+        assert_eq!(range.code, Code::InvalidEnumTag);
+        assert_eq!(
+            range.message,
+            "the tag value 300 of constructor 'Green' doesn't fit in type 'UInt8'"
+        );
+        assert_eq!(
+            dupe.message,
+            "the tag value 0 of constructor 'Green' is already used by \
+            constructor 'Red'"
+        );
+    }
+
+    #[test]
+    fn test_invalid_operator_method() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let loc = Location::default();
+        let add = Method::alloc(
+            &mut db,
+            module,
+            loc,
+            "+".to_string(),
+            Visibility::Public,
+            MethodKind::Instance,
+        );
+        let eq = Method::alloc(
+            &mut db,
+            module,
+            loc,
+            "==".to_string(),
+            Visibility::Public,
+            MethodKind::Static,
+        );
+        let args = Diagnostic::invalid_operator_method(
+            &db,
+            add.check_operator(&db).unwrap_err(),
+        );
+        let receiver = Diagnostic::invalid_operator_method(
+            &db,
+            eq.check_operator(&db).unwrap_err(),
+        );
+
+        assert_eq!(args.code, Code::InvalidOperator);
+        assert_eq!(
+            args.message,
+            "the operator method '+' must define 1 argument(s), but it \
+            defines 0"
+        );
+        assert_eq!(
+            receiver.message,
+            "the operator method '==' must be an instance method"
+        );
+    }
+
+    #[test]
+    fn test_invalid_abi() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let method = Method::alloc(
+            &mut db,
+            module,
+            Location::new(&(1..=1), &(1..=1)),
+            "puts".to_string(),
+            Visibility::Public,
+            MethodKind::Extern,
+        );
+        let arg_loc = Location::new(&(1..=1), &(11..=20));
+
+        method.new_argument(
+            &mut db,
+            "value".to_string(),
+            TypeRef::string(),
+            TypeRef::string(),
+            arg_loc,
+        );
+        method.set_return_type(&mut db, TypeRef::string());
+
+        let errors = method.check_abi(&db);
+        let arg = Diagnostic::invalid_abi(&db, errors[0]);
+        let ret = Diagnostic::invalid_abi(&db, errors[1]);
+
+        assert_eq!(arg.code, Code::InvalidAbi);
+        assert_eq!(
+            arg.message,
+            "the argument 'value' of type 'String' isn't a valid C type"
+        );
+        assert_eq!(arg.location, arg_loc);
+        assert_eq!(
+            ret.message,
+            "the return type 'String' of method 'puts' isn't a valid C type"
+        );
+        assert_eq!(ret.location, Location::new(&(1..=1), &(1..=1)));
+    }
+}
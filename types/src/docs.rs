@@ -0,0 +1,281 @@
+//! Structured documentation of symbols.
+//!
+//! Documentation comments are written in Markdown. Besides the introduction,
+//! a comment may contain sections started using a level one heading, such as
+//! `# Examples`. Some of these sections have a special meaning and are parsed
+//! into dedicated fields, while all other sections (e.g. `# Panics`) are kept
+//! as-is.
+const FENCE: &str = "```";
+
+/// A documented parameter of a method.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub description: String,
+}
+
+/// A section of documentation without a special meaning.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub title: String,
+    pub body: String,
+}
+
+/// The documentation of a symbol, parsed from its documentation comment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Docs {
+    /// The text of the documentation comment.
+    pub source: String,
+
+    /// The first paragraph of the documentation.
+    pub summary: String,
+
+    /// The paragraphs following the summary, up to the first section.
+    pub description: String,
+
+    /// The parameters documented in the "Arguments" or "Parameters" section.
+    pub params: Vec<Parameter>,
+
+    /// The contents of the "Returns" section.
+    pub returns: Option<String>,
+
+    /// The code blocks of the "Examples" section.
+    pub examples: Vec<String>,
+
+    /// The contents of the "Deprecated" section.
+    ///
+    /// This is `Some` if the section is present, even if it's empty.
+    pub deprecated: Option<String>,
+
+    /// All other sections, in the order they are defined in.
+    pub sections: Vec<Section>,
+}
+
+impl Docs {
+    pub fn parse(source: String) -> Docs {
+        let mut intro = Vec::new();
+        let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+        let mut in_code = false;
+
+        for line in source.lines() {
+            if line.trim_start().starts_with(FENCE) {
+                in_code = !in_code;
+            }
+
+            if !in_code {
+                if let Some(title) = line.strip_prefix("# ") {
+                    sections.push((title.trim(), Vec::new()));
+                    continue;
+                }
+            }
+
+            match sections.last_mut() {
+                Some((_, lines)) => lines.push(line),
+                None => intro.push(line),
+            }
+        }
+
+        let intro = intro.join("\n");
+        let intro = intro.trim();
+        let (summary, description) =
+            intro.split_once("\n\n").unwrap_or((intro, ""));
+        let mut docs = Docs {
+            summary: summary
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join(" "),
+            description: description.trim().to_string(),
+            ..Default::default()
+        };
+
+        for (title, lines) in sections {
+            let body = lines.join("\n").trim().to_string();
+
+            match title.to_lowercase().as_str() {
+                "example" | "examples" => {
+                    docs.examples.append(&mut code_blocks(&body));
+                }
+                "arguments" | "parameters" => {
+                    docs.params.append(&mut parameters(&body));
+                }
+                "returns" => docs.returns = Some(body),
+                "deprecated" | "deprecation" => docs.deprecated = Some(body),
+                _ => docs
+                    .sections
+                    .push(Section { title: title.to_string(), body }),
+            }
+        }
+
+        docs.source = source;
+        docs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.source.is_empty()
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated.is_some()
+    }
+}
+
+/// Returns the contents of the fenced code blocks in `text`.
+///
+/// If the text doesn't contain any code blocks, the text as a whole is
+/// treated as a single example.
+fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with(FENCE) {
+            if let Some(lines) = current.take() {
+                blocks.push(lines.join("\n"));
+            } else {
+                current = Some(Vec::new());
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+
+    if blocks.is_empty() && !text.is_empty() {
+        blocks.push(text.to_string());
+    }
+
+    blocks
+}
+
+/// Parses a list of parameters in the form `- name: description`.
+///
+/// The names may be wrapped in backticks, and descriptions may continue on
+/// the lines that follow.
+fn parameters(text: &str) -> Vec<Parameter> {
+    let mut params: Vec<Parameter> = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let item =
+            trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* "));
+
+        if let Some(item) = item {
+            let (name, desc) = item.split_once(':').unwrap_or((item, ""));
+
+            params.push(Parameter {
+                name: name.trim().trim_matches('`').to_string(),
+                description: desc.trim().to_string(),
+            });
+        } else if let Some(param) = params.last_mut() {
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            if !param.description.is_empty() {
+                param.description.push(' ');
+            }
+
+            param.description.push_str(trimmed);
+        }
+    }
+
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty() {
+        let docs = Docs::parse(String::new());
+
+        assert!(docs.is_empty());
+        assert_eq!(docs, Docs::default());
+    }
+
+    #[test]
+    fn test_parse() {
+        let source = "\
+Returns an array filled with a
+certain amount of values.
+
+The `times` argument specifies how many times
+the value must exist.
+
+# Arguments
+
+- `with`: the value to fill
+  the array with
+- times: the number of values
+
+# Returns
+
+A new array.
+
+# Panics
+
+This method panics if `times` is negative.
+
+# Examples
+
+```inko
+# This isn't a heading.
+Array.filled(with: 0, times: 4) # => [0, 0, 0, 0]
+```
+
+# Deprecated"
+            .to_string();
+        let docs = Docs::parse(source.clone());
+
+        assert_eq!(docs.source, source);
+        assert_eq!(
+            docs.summary,
+            "Returns an array filled with a certain amount of values."
+        );
+        assert_eq!(
+            docs.description,
+            "The `times` argument specifies how many times\nthe value must exist."
+        );
+        assert_eq!(
+            docs.params,
+            vec![
+                Parameter {
+                    name: "with".to_string(),
+                    description: "the value to fill the array with".to_string()
+                },
+                Parameter {
+                    name: "times".to_string(),
+                    description: "the number of values".to_string()
+                },
+            ]
+        );
+        assert_eq!(docs.returns, Some("A new array.".to_string()));
+        assert_eq!(
+            docs.examples,
+            vec![
+                "# This isn't a heading.\nArray.filled(with: 0, times: 4) # => [0, 0, 0, 0]"
+                    .to_string()
+            ]
+        );
+        assert_eq!(
+            docs.sections,
+            vec![Section {
+                title: "Panics".to_string(),
+                body: "This method panics if `times` is negative.".to_string()
+            }]
+        );
+        assert!(docs.is_deprecated());
+        assert_eq!(docs.deprecated, Some(String::new()));
+    }
+
+    #[test]
+    fn test_parse_examples_without_code_blocks() {
+        let docs = Docs::parse("Foo\n\n# Example\n\nfoo.bar".to_string());
+
+        assert_eq!(docs.summary, "Foo");
+        assert_eq!(docs.description, "");
+        assert_eq!(docs.examples, vec!["foo.bar".to_string()]);
+        assert!(!docs.is_deprecated());
+    }
+}
@@ -0,0 +1,160 @@
+//! The order in which fields are dropped, and fields moved out of `self`.
+//!
+//! When an instance of a class is dropped, its dropper method drops the values
+//! of its fields. By default this is done in the reverse order of the field
+//! definitions, similar to how local variables are dropped in the reverse
+//! order of their definitions. This allows a field to refer to fields defined
+//! before it, such as a buffer and a cursor into that buffer.
+//!
+//! Methods that take ownership of `self` may move fields out of it, such as
+//! when destructuring `self` using a pattern. The fields moved out of `self`
+//! are recorded per method, such that the code that drops `self` at the end of
+//! the method can skip these fields, and the checker can reject uses of such
+//! fields after they're moved.
+use crate::{ClassId, Database, FieldId, MethodId};
+use location::Location;
+
+/// The order in which the fields of a class are dropped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DropOrder {
+    /// Fields are dropped in the reverse order in which they're defined.
+    Reverse,
+
+    /// Fields are dropped in the order in which they're defined.
+    Declaration,
+}
+
+impl ClassId {
+    pub fn set_drop_order(self, db: &mut Database, order: DropOrder) {
+        self.get_mut(db).drop_order = order;
+    }
+
+    pub fn drop_order(self, db: &Database) -> DropOrder {
+        self.get(db).drop_order
+    }
+
+    /// Returns the fields of the class in the order in which they're dropped.
+    pub fn fields_in_drop_order(self, db: &Database) -> Vec<FieldId> {
+        let mut fields = self.fields(db);
+
+        if let DropOrder::Reverse = self.drop_order(db) {
+            fields.reverse();
+        }
+
+        fields
+    }
+}
+
+impl MethodId {
+    /// Records that a field is moved out of `self` at the given location.
+    ///
+    /// If the field is already moved, the existing location is retained and
+    /// `false` is returned.
+    pub fn mark_field_as_moved(
+        self,
+        db: &mut Database,
+        field: FieldId,
+        location: Location,
+    ) -> bool {
+        if self.field_moved_at(db, field).is_some() {
+            return false;
+        }
+
+        self.get_mut(db).moved_fields.push((field, location));
+        true
+    }
+
+    /// Returns the location at which the field is moved out of `self`, if it's
+    /// moved.
+    pub fn field_moved_at(
+        self,
+        db: &Database,
+        field: FieldId,
+    ) -> Option<Location> {
+        self.get(db)
+            .moved_fields
+            .iter()
+            .find(|(id, _)| *id == field)
+            .map(|(_, loc)| *loc)
+    }
+
+    /// Returns the fields moved out of `self`, in the order they're moved in.
+    pub fn moved_fields(self, db: &Database) -> &[(FieldId, Location)] {
+        &self.get(db).moved_fields
+    }
+
+    pub fn is_partially_moved(self, db: &Database) -> bool {
+        !self.get(db).moved_fields.is_empty()
+    }
+
+    /// Returns the fields of `class` to drop when dropping `self` at the end of
+    /// the method, in the order in which to drop them.
+    ///
+    /// Fields moved out of `self` are excluded, as these are dropped by
+    /// whatever they're moved into.
+    pub fn fields_to_drop(self, db: &Database, class: ClassId) -> Vec<FieldId> {
+        class
+            .fields_in_drop_order(db)
+            .into_iter()
+            .filter(|&f| self.field_moved_at(db, f).is_none())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_method};
+    use crate::{MethodKind, ModuleId, TypeRef, Visibility};
+
+    fn new_field(db: &mut Database, class: ClassId, name: &str) -> FieldId {
+        let index = class.number_of_fields(db);
+
+        class.new_field(
+            db,
+            name.to_string(),
+            index,
+            TypeRef::int(),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        )
+    }
+
+    #[test]
+    fn test_class_id_fields_in_drop_order() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Person");
+        let a = new_field(&mut db, class, "a");
+        let b = new_field(&mut db, class, "b");
+
+        assert_eq!(class.drop_order(&db), DropOrder::Reverse);
+        assert_eq!(class.fields_in_drop_order(&db), vec![b, a]);
+
+        class.set_drop_order(&mut db, DropOrder::Declaration);
+
+        assert_eq!(class.fields_in_drop_order(&db), vec![a, b]);
+    }
+
+    #[test]
+    fn test_method_id_mark_field_as_moved() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Person");
+        let a = new_field(&mut db, class, "a");
+        let b = new_field(&mut db, class, "b");
+        let c = new_field(&mut db, class, "c");
+        let method =
+            new_method(&mut db, ModuleId(0), "foo", MethodKind::Moving);
+        let loc1 = Location::new(&(1..=1), &(1..=1));
+        let loc2 = Location::new(&(2..=2), &(1..=1));
+
+        assert!(!method.is_partially_moved(&db));
+        assert!(method.mark_field_as_moved(&mut db, b, loc1));
+        assert!(!method.mark_field_as_moved(&mut db, b, loc2));
+        assert!(method.is_partially_moved(&db));
+        assert_eq!(method.field_moved_at(&db, b), Some(loc1));
+        assert_eq!(method.field_moved_at(&db, a), None);
+        assert_eq!(method.moved_fields(&db), &[(b, loc1)]);
+        assert_eq!(method.fields_to_drop(&db, class), vec![c, a]);
+    }
+}
@@ -0,0 +1,300 @@
+//! Checking if traits can be used as trait objects.
+//!
+//! When a value is cast to a trait, methods are called through the trait
+//! without knowing the type of the value. This means some methods can't be
+//! called, such as static methods (as there's no value to call them on) and
+//! generic methods (as they're specialized at compile-time for every type they
+//! are used with). A trait is "dyn compatible" if none of its required methods,
+//! or those of the traits it requires, have such a restriction.
+use crate::diagnostics::Diagnostic;
+use crate::specialize::uses_type_parameter;
+use crate::{Block, Database, MethodId, ModuleId, TraitId, TypeId, TypeRef};
+use location::Location;
+
+/// The reason a method prevents a trait from being used as a trait object.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The method is a static method.
+    StaticMethod,
+
+    /// The method returns the trait itself by value, which for a trait object
+    /// would mean returning a value of an unknown type.
+    ReturnsSelf,
+
+    /// The method defines its own type parameters.
+    Generic,
+
+    /// The method has an argument of which the type refers to `Self`, which
+    /// for a trait object is unknown.
+    SelfArgument,
+}
+
+impl Violation {
+    pub fn description(self) -> &'static str {
+        match self {
+            Violation::StaticMethod => "it's a static method",
+            Violation::ReturnsSelf => "it returns the trait by value",
+            Violation::Generic => "it defines type parameters",
+            Violation::SelfArgument => "it takes an argument of type 'Self'",
+        }
+    }
+}
+
+/// A method that prevents a trait from being used as a trait object.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Incompatibility {
+    pub method: MethodId,
+    pub violation: Violation,
+}
+
+fn violation(
+    db: &Database,
+    trait_id: TraitId,
+    method: MethodId,
+) -> Option<Violation> {
+    if method.is_static(db) {
+        return Some(Violation::StaticMethod);
+    }
+
+    if !method.type_parameters(db).is_empty() {
+        return Some(Violation::Generic);
+    }
+
+    let self_type = trait_id.self_type(db);
+
+    if let Some(param) = self_type {
+        if method
+            .arguments(db)
+            .into_iter()
+            .any(|arg| uses_type_parameter(db, arg.value_type, param))
+        {
+            return Some(Violation::SelfArgument);
+        }
+    }
+
+    match method.return_type(db) {
+        TypeRef::Owned(TypeId::TraitInstance(ins))
+        | TypeRef::Uni(TypeId::TraitInstance(ins))
+            if ins.instance_of() == trait_id =>
+        {
+            Some(Violation::ReturnsSelf)
+        }
+        TypeRef::Owned(TypeId::TypeParameter(id))
+        | TypeRef::Uni(TypeId::TypeParameter(id))
+        | TypeRef::Any(TypeId::TypeParameter(id))
+            if Some(id) == self_type =>
+        {
+            Some(Violation::ReturnsSelf)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the required methods that prevent a trait from being used as a
+/// trait object, including those of the traits it requires.
+///
+/// The methods are returned in the order in which they are defined, with
+/// methods of the trait itself coming before those of required traits.
+pub fn incompatibilities(
+    db: &Database,
+    trait_id: TraitId,
+) -> Vec<Incompatibility> {
+    let mut result = Vec::new();
+    let mut stack = vec![trait_id];
+    let mut visited = Vec::new();
+
+    while let Some(id) = stack.pop() {
+        if visited.contains(&id) {
+            continue;
+        }
+
+        visited.push(id);
+
+        for method in id.required_methods(db) {
+            if let Some(violation) = violation(db, id, method) {
+                result.push(Incompatibility { method, violation });
+            }
+        }
+
+        for req in id.required_traits(db).into_iter().rev() {
+            stack.push(req.instance_of());
+        }
+    }
+
+    result
+}
+
+/// Checks if a trait can be used as a trait object, returning a diagnostic
+/// listing the offending methods if this isn't the case.
+pub fn check(
+    db: &Database,
+    trait_id: TraitId,
+    module: ModuleId,
+    location: Location,
+) -> Option<Diagnostic> {
+    let found = incompatibilities(db, trait_id);
+
+    if found.is_empty() {
+        return None;
+    }
+
+    let mut diag =
+        Diagnostic::dyn_incompatible_trait(db, trait_id, module, location);
+
+    for inc in found {
+        diag = diag.with_note(
+            format!(
+                "the method '{}' can't be called through a trait object, as {}",
+                inc.method.name(db),
+                inc.violation.description()
+            ),
+            Some((inc.method.module(db), inc.method.location(db))),
+        );
+    }
+
+    Some(diag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Code;
+    use crate::test::{
+        any, immutable, new_method, new_module, new_trait, owned, parameter,
+        trait_instance, trait_instance_id,
+    };
+    use crate::MethodKind;
+
+    fn required_method(
+        db: &mut Database,
+        trait_id: TraitId,
+        name: &str,
+        kind: MethodKind,
+    ) -> MethodId {
+        let method = new_method(db, ModuleId(0), name, kind);
+
+        trait_id.add_required_method(db, name.to_string(), method);
+        method
+    }
+
+    #[test]
+    fn test_incompatibilities() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let clone = new_trait(&mut db, "Clone");
+        let hash = new_trait(&mut db, "Hash");
+        let parse = new_trait(&mut db, "Parse");
+
+        required_method(&mut db, to_string, "to_string", MethodKind::Instance);
+
+        let clone_method =
+            required_method(&mut db, clone, "clone", MethodKind::Instance);
+
+        clone_method.set_return_type(&mut db, owned(trait_instance_id(clone)));
+
+        let hash_method =
+            required_method(&mut db, hash, "hash", MethodKind::Instance);
+
+        hash_method.new_type_parameter(&mut db, "H".to_string());
+
+        let parse_method =
+            required_method(&mut db, parse, "parse", MethodKind::Static);
+
+        parse.add_required_trait(&mut db, trait_instance(clone));
+
+        assert!(to_string.is_dyn_compatible(&db));
+        assert!(!clone.is_dyn_compatible(&db));
+        assert!(!hash.is_dyn_compatible(&db));
+        assert_eq!(
+            incompatibilities(&db, hash),
+            vec![Incompatibility {
+                method: hash_method,
+                violation: Violation::Generic
+            }]
+        );
+        assert_eq!(
+            incompatibilities(&db, parse),
+            vec![
+                Incompatibility {
+                    method: parse_method,
+                    violation: Violation::StaticMethod
+                },
+                Incompatibility {
+                    method: clone_method,
+                    violation: Violation::ReturnsSelf
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let hash = new_trait(&mut db, "Hash");
+        let loc = Location::default();
+
+        required_method(&mut db, to_string, "to_string", MethodKind::Instance);
+        required_method(&mut db, hash, "hash", MethodKind::Instance)
+            .new_type_parameter(&mut db, "H".to_string());
+
+        assert!(check(&db, to_string, module, loc).is_none());
+
+        let diag = check(&db, hash, module, loc).unwrap();
+
+        assert_eq!(diag.code, Code::DynIncompatibleTrait);
+        assert_eq!(
+            diag.message,
+            "the trait 'Hash' can't be used as a trait object"
+        );
+        assert_eq!(
+            diag.notes[0].message,
+            "the method 'hash' can't be called through a trait object, as \
+            it defines type parameters"
+        );
+    }
+
+    #[test]
+    fn test_incompatibilities_with_self_type() {
+        let mut db = Database::new();
+        let clone = new_trait(&mut db, "Clone");
+        let equal = new_trait(&mut db, "Equal");
+        let to_string = new_trait(&mut db, "ToString");
+        let clone_self = clone.new_self_type(&mut db);
+        let equal_self = equal.new_self_type(&mut db);
+
+        to_string.new_self_type(&mut db);
+
+        let clone_method =
+            required_method(&mut db, clone, "clone", MethodKind::Instance);
+        let equal_method =
+            required_method(&mut db, equal, "==", MethodKind::Instance);
+
+        required_method(&mut db, to_string, "to_string", MethodKind::Instance);
+        clone_method.set_return_type(&mut db, any(parameter(clone_self)));
+        equal_method.new_argument(
+            &mut db,
+            "other".to_string(),
+            immutable(parameter(equal_self)),
+            immutable(parameter(equal_self)),
+            Location::default(),
+        );
+
+        assert!(to_string.is_dyn_compatible(&db));
+        assert_eq!(
+            incompatibilities(&db, clone),
+            vec![Incompatibility {
+                method: clone_method,
+                violation: Violation::ReturnsSelf
+            }]
+        );
+        assert_eq!(
+            incompatibilities(&db, equal),
+            vec![Incompatibility {
+                method: equal_method,
+                violation: Violation::SelfArgument
+            }]
+        );
+    }
+}
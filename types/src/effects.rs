@@ -0,0 +1,165 @@
+//! Inference of the side effects of methods.
+//!
+//! The effects of a method form a set, with the empty set meaning the method
+//! is pure. The effects of a method include the effects of the methods it
+//! calls, such that e.g. a method that calls a method performing I/O is also
+//! considered to perform I/O.
+//!
+//! The type database doesn't know which methods call which other methods, so
+//! the compiler is expected to record the direct effects of each method using
+//! `MethodId::add_effects()`, then call `infer()` with the edges of the call
+//! graph.
+use crate::{Database, MethodId};
+use std::fmt;
+use std::ops::BitOr;
+
+/// A set of side effects produced by a method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Effects(pub(crate) u8);
+
+impl Effects {
+    /// The method has no side effects.
+    pub const PURE: Effects = Effects(0);
+
+    /// The method reads mutable state, such as fields or the contents of
+    /// pointers.
+    pub const READS: Effects = Effects(0b0001);
+
+    /// The method writes to mutable state.
+    pub const WRITES: Effects = Effects(0b0010);
+
+    /// The method performs I/O, such as reading from a socket.
+    pub const IO: Effects = Effects(0b0100);
+
+    /// The method spawns processes.
+    pub const SPAWNS: Effects = Effects(0b1000);
+
+    /// All possible effects.
+    pub const ALL: Effects = Effects(0b1111);
+
+    const NAMES: [(Effects, &'static str); 4] = [
+        (Effects::READS, "reads"),
+        (Effects::WRITES, "writes"),
+        (Effects::IO, "io"),
+        (Effects::SPAWNS, "spawns"),
+    ];
+
+    pub fn is_pure(self) -> bool {
+        self == Effects::PURE
+    }
+
+    /// Returns `true` if `self` includes all the effects of `other`.
+    pub fn contains(self, other: Effects) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the names of the effects in this set.
+    pub fn names(self) -> Vec<&'static str> {
+        Effects::NAMES
+            .iter()
+            .filter(|(eff, _)| self.contains(*eff))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl BitOr for Effects {
+    type Output = Effects;
+
+    fn bitor(self, rhs: Effects) -> Effects {
+        Effects(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Effects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_pure() {
+            f.write_str("pure")
+        } else {
+            f.write_str(&self.names().join(", "))
+        }
+    }
+}
+
+/// Infers the effects of all methods.
+///
+/// The `calls` argument contains pairs of methods, where the first method calls
+/// the second method.
+///
+/// External functions may do anything, so they're treated as producing all
+/// possible effects. The effects are propagated from callees to callers until
+/// they no longer change, such that (mutually) recursive methods are handled
+/// correctly.
+pub fn infer(db: &mut Database, calls: &[(MethodId, MethodId)]) {
+    for idx in 0..db.number_of_methods() {
+        let id = MethodId(idx as u32);
+
+        if id.is_extern(db) {
+            id.add_effects(db, Effects::ALL);
+        }
+    }
+
+    loop {
+        let mut changed = false;
+
+        for &(caller, callee) in calls {
+            let effects = callee.effects(db);
+
+            if !caller.effects(db).contains(effects) {
+                caller.add_effects(db, effects);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_method;
+    use crate::{MethodKind, ModuleId};
+
+    #[test]
+    fn test_effects() {
+        let eff = Effects::READS | Effects::IO;
+
+        assert!(Effects::PURE.is_pure());
+        assert!(!eff.is_pure());
+        assert!(eff.contains(Effects::IO));
+        assert!(!eff.contains(Effects::WRITES));
+        assert!(eff.contains(Effects::PURE));
+        assert_eq!(eff.to_string(), "reads, io");
+        assert_eq!(Effects::PURE.to_string(), "pure");
+    }
+
+    #[test]
+    fn test_infer() {
+        let mut db = Database::new();
+        let pure =
+            new_method(&mut db, ModuleId(0), "pure", MethodKind::Instance);
+        let reads =
+            new_method(&mut db, ModuleId(0), "reads", MethodKind::Instance);
+        let a = new_method(&mut db, ModuleId(0), "a", MethodKind::Instance);
+        let b = new_method(&mut db, ModuleId(0), "b", MethodKind::Instance);
+        let ext = new_method(&mut db, ModuleId(0), "ext", MethodKind::Extern);
+        let calls_ext =
+            new_method(&mut db, ModuleId(0), "calls_ext", MethodKind::Static);
+
+        reads.add_effects(&mut db, Effects::READS);
+        b.add_effects(&mut db, Effects::WRITES);
+        infer(
+            &mut db,
+            &[(a, reads), (a, b), (b, a), (pure, pure), (calls_ext, ext)],
+        );
+
+        assert!(pure.effects(&db).is_pure());
+        assert_eq!(reads.effects(&db), Effects::READS);
+        assert_eq!(a.effects(&db), Effects::READS | Effects::WRITES);
+        assert_eq!(b.effects(&db), Effects::READS | Effects::WRITES);
+        assert_eq!(calls_ext.effects(&db), Effects::ALL);
+    }
+}
@@ -0,0 +1,370 @@
+//! Analysis of the arguments and local variables that escape a method.
+//!
+//! A value escapes a method when it outlives the method's call, such as when
+//! it's stored in a field, captured by a closure, or returned. Values that
+//! don't escape can be allocated on the stack, and borrows of such values can't
+//! outlive the method, allowing checks of `uni` values to be less strict.
+//!
+//! While type-checking a method, the compiler records the direct escapes of
+//! its variables using `MethodId::add_escape()`, and the variables passed as
+//! arguments to other methods using `MethodId::add_escape_flow()`. Once all
+//! methods are checked, `infer()` is used to propagate the escapes of
+//! arguments to the variables passed to them.
+use crate::{Database, MethodId, TypeId, VariableId};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::BitOr;
+
+/// A set of ways in which a variable escapes a method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Escape(pub(crate) u8);
+
+impl Escape {
+    /// The variable doesn't escape the method.
+    pub const NONE: Escape = Escape(0);
+
+    /// The variable is stored in a field, including the fields of newly
+    /// created instances.
+    pub const FIELD: Escape = Escape(0b001);
+
+    /// The variable is captured by a closure.
+    pub const CAPTURED: Escape = Escape(0b010);
+
+    /// The variable is returned or thrown.
+    pub const RETURNED: Escape = Escape(0b100);
+
+    /// All possible ways of escaping.
+    pub const ALL: Escape = Escape(0b111);
+
+    const NAMES: [(Escape, &'static str); 3] = [
+        (Escape::FIELD, "field"),
+        (Escape::CAPTURED, "captured"),
+        (Escape::RETURNED, "returned"),
+    ];
+
+    pub fn is_none(self) -> bool {
+        self == Escape::NONE
+    }
+
+    /// Returns `true` if `self` includes all the escapes of `other`.
+    pub fn contains(self, other: Escape) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the names of the escapes in this set.
+    pub fn names(self) -> Vec<&'static str> {
+        Escape::NAMES
+            .iter()
+            .filter(|(esc, _)| self.contains(*esc))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl BitOr for Escape {
+    type Output = Escape;
+
+    fn bitor(self, rhs: Escape) -> Escape {
+        Escape(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Escape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_none() {
+            f.write_str("none")
+        } else {
+            f.write_str(&self.names().join(", "))
+        }
+    }
+}
+
+/// A variable passed as an argument to a method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EscapeFlow {
+    /// The variable passed as the argument.
+    pub variable: VariableId,
+
+    /// The method called.
+    pub callee: MethodId,
+
+    /// The index of the argument the variable is passed as.
+    pub index: usize,
+}
+
+/// The escapes of the variables of a single method.
+#[derive(Clone, Debug, Default)]
+pub struct EscapeTable {
+    pub(crate) variables: HashMap<VariableId, Escape>,
+    pub(crate) flows: Vec<EscapeFlow>,
+}
+
+impl MethodId {
+    /// Records that a variable escapes the method in the given way.
+    pub fn add_escape(
+        self,
+        db: &mut Database,
+        variable: VariableId,
+        escape: Escape,
+    ) {
+        let table = &mut self.get_mut(db).escapes;
+        let current = table.variables.entry(variable).or_default();
+
+        *current = *current | escape;
+    }
+
+    /// Records that a variable is passed as an argument to another method.
+    pub fn add_escape_flow(
+        self,
+        db: &mut Database,
+        variable: VariableId,
+        callee: MethodId,
+        index: usize,
+    ) {
+        let flow = EscapeFlow { variable, callee, index };
+        let flows = &mut self.get_mut(db).escapes.flows;
+
+        if !flows.contains(&flow) {
+            flows.push(flow);
+        }
+    }
+
+    /// Returns the ways in which a variable escapes the method.
+    pub fn escape(self, db: &Database, variable: VariableId) -> Escape {
+        self.get(db)
+            .escapes
+            .variables
+            .get(&variable)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn is_escaping(self, db: &Database, variable: VariableId) -> bool {
+        !self.escape(db, variable).is_none()
+    }
+
+    /// Returns the variables of the arguments that don't escape the method, in
+    /// the order the arguments are defined in.
+    pub fn non_escaping_arguments(self, db: &Database) -> Vec<VariableId> {
+        self.get(db)
+            .arguments
+            .iter()
+            .map(|a| a.variable)
+            .filter(|&v| !self.is_escaping(db, v))
+            .collect()
+    }
+
+    /// Returns the ways in which the argument at the given index escapes.
+    ///
+    /// External functions may do anything with their arguments, so all their
+    /// arguments are treated as escaping in every way.
+    fn argument_escape(self, db: &Database, index: usize) -> Escape {
+        if self.is_extern(db) {
+            return Escape::ALL;
+        }
+
+        self.get(db)
+            .arguments
+            .mapping
+            .get_index(index)
+            .map_or(Escape::NONE, |(_, a)| self.escape(db, a.variable))
+    }
+}
+
+/// Returns the methods that may run when calling the given method.
+///
+/// Methods defined in traits may be called using dynamic dispatch, in which
+/// case any implementation of the method may run.
+fn targets(db: &Database, method: MethodId) -> Vec<MethodId> {
+    let mut targets = vec![method];
+    let trait_id = match method.receiver(db).type_id(db) {
+        Ok(TypeId::Trait(id)) => id,
+        Ok(TypeId::TraitInstance(ins)) => ins.instance_of(),
+        _ => return targets,
+    };
+    let name = method.name(db);
+
+    targets.extend(
+        trait_id.implemented_by(db).iter().filter_map(|c| c.method(db, name)),
+    );
+    targets
+}
+
+/// Infers the escapes of the variables of all methods.
+///
+/// A variable passed as an argument escapes in the same way as the argument.
+/// The escapes are propagated until they no longer change, such that
+/// (mutually) recursive methods are handled correctly.
+pub fn infer(db: &mut Database) {
+    let mut flows = Vec::new();
+
+    for idx in 0..db.number_of_methods() {
+        let id = MethodId(idx as u32);
+
+        for &flow in &id.get(db).escapes.flows {
+            flows.push((id, flow, targets(db, flow.callee)));
+        }
+    }
+
+    loop {
+        let mut changed = false;
+
+        for (caller, flow, targets) in &flows {
+            let escape = targets.iter().fold(Escape::NONE, |acc, m| {
+                acc | m.argument_escape(db, flow.index)
+            });
+
+            if !caller.escape(db, flow.variable).contains(escape) {
+                caller.add_escape(db, flow.variable, escape);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        implement, instance, new_class, new_method, new_module, new_trait,
+        owned, trait_instance,
+    };
+    use crate::{Block, Location, MethodKind, TypeRef};
+
+    fn method_for(
+        db: &mut Database,
+        kind: MethodKind,
+        receiver: TypeId,
+        name: &str,
+    ) -> MethodId {
+        let module = new_module(db, "foo");
+        let id = new_method(db, module, name, kind);
+
+        id.set_receiver(db, owned(receiver));
+        id
+    }
+
+    fn new_argument(
+        db: &mut Database,
+        method: MethodId,
+        name: &str,
+    ) -> VariableId {
+        method.new_argument(
+            db,
+            name.to_string(),
+            TypeRef::int(),
+            TypeRef::int(),
+            Location::default(),
+        )
+    }
+
+    #[test]
+    fn test_escape() {
+        let esc = Escape::FIELD | Escape::RETURNED;
+
+        assert!(Escape::NONE.is_none());
+        assert!(!esc.is_none());
+        assert!(esc.contains(Escape::FIELD));
+        assert!(!esc.contains(Escape::CAPTURED));
+        assert_eq!(esc.to_string(), "field, returned");
+        assert_eq!(Escape::NONE.to_string(), "none");
+    }
+
+    #[test]
+    fn test_method_id_add_escape() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "A");
+        let method =
+            method_for(&mut db, MethodKind::Instance, instance(class), "foo");
+        let a = new_argument(&mut db, method, "a");
+        let b = new_argument(&mut db, method, "b");
+        let c = new_argument(&mut db, method, "c");
+
+        method.add_escape(&mut db, a, Escape::FIELD);
+        method.add_escape(&mut db, a, Escape::CAPTURED);
+        method.add_escape(&mut db, b, Escape::NONE);
+
+        assert_eq!(method.escape(&db, a), Escape::FIELD | Escape::CAPTURED);
+        assert!(method.is_escaping(&db, a));
+        assert!(!method.is_escaping(&db, b));
+        assert_eq!(method.non_escaping_arguments(&db), vec![b, c]);
+    }
+
+    #[test]
+    fn test_infer() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "A");
+        let typ = instance(class);
+        let caller = method_for(&mut db, MethodKind::Instance, typ, "caller");
+        let stores = method_for(&mut db, MethodKind::Instance, typ, "stores");
+        let a = method_for(&mut db, MethodKind::Instance, typ, "a");
+        let b = method_for(&mut db, MethodKind::Instance, typ, "b");
+        let ext = method_for(&mut db, MethodKind::Extern, typ, "ext");
+        let x = new_argument(&mut db, caller, "x");
+        let y = new_argument(&mut db, caller, "y");
+        let z = new_argument(&mut db, caller, "z");
+        let stored = new_argument(&mut db, stores, "value");
+        let a_arg = new_argument(&mut db, a, "value");
+        let b_arg = new_argument(&mut db, b, "value");
+
+        new_argument(&mut db, ext, "value");
+        stores.add_escape(&mut db, stored, Escape::FIELD);
+
+        // `a` and `b` call each other, and `b` passes its argument to
+        // `stores`, thus the argument of `a` escapes as well.
+        a.add_escape_flow(&mut db, a_arg, b, 0);
+        b.add_escape_flow(&mut db, b_arg, a, 0);
+        b.add_escape_flow(&mut db, b_arg, stores, 0);
+        caller.add_escape_flow(&mut db, x, a, 0);
+        caller.add_escape_flow(&mut db, x, a, 0);
+        caller.add_escape_flow(&mut db, z, ext, 0);
+        infer(&mut db);
+
+        assert_eq!(caller.get(&db).escapes.flows.len(), 2);
+        assert_eq!(a.escape(&db, a_arg), Escape::FIELD);
+        assert_eq!(b.escape(&db, b_arg), Escape::FIELD);
+        assert_eq!(caller.escape(&db, x), Escape::FIELD);
+        assert_eq!(caller.escape(&db, z), Escape::ALL);
+        assert_eq!(caller.non_escaping_arguments(&db), vec![y]);
+    }
+
+    #[test]
+    fn test_infer_with_trait_method() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let class = new_class(&mut db, "A");
+        let caller = method_for(
+            &mut db,
+            MethodKind::Instance,
+            instance(class),
+            "caller",
+        );
+        let req = method_for(
+            &mut db,
+            MethodKind::Instance,
+            TypeId::TraitInstance(trait_instance(to_string)),
+            "to_string",
+        );
+        let imp = method_for(
+            &mut db,
+            MethodKind::Instance,
+            instance(class),
+            "to_string",
+        );
+        let var = new_argument(&mut db, caller, "x");
+        let imp_arg = new_argument(&mut db, imp, "value");
+
+        new_argument(&mut db, req, "value");
+        implement(&mut db, trait_instance(to_string), class);
+        class.add_method(&mut db, "to_string".to_string(), imp);
+        imp.add_escape(&mut db, imp_arg, Escape::RETURNED);
+        caller.add_escape_flow(&mut db, var, req, 0);
+        infer(&mut db);
+
+        assert_eq!(caller.escape(&db, var), Escape::RETURNED);
+    }
+}
@@ -1,13 +1,17 @@
 //! Formatting of types.
 use crate::{
     Arguments, ClassId, ClassInstance, ClassKind, ClosureId, Database,
-    ForeignType, Inline, MethodId, MethodKind, ModuleId, Ownership, Sign,
-    TraitId, TraitInstance, TypeArguments, TypeId, TypeParameterId,
-    TypePlaceholderId, TypeRef, Visibility,
+    ForeignFunctionId, ForeignType, Inline, MethodId, MethodKind, ModuleId,
+    Ownership, Sign, TraitId, TraitInstance, TupleId, TypeAliasId,
+    TypeAliasInstance, TypeArguments, TypeId, TypeParameterId,
+    TypePlaceholderId, TypeRef, UnionId, Visibility,
 };
 
 const MAX_FORMATTING_DEPTH: usize = 8;
 
+/// The marker used to highlight the parts of two types that differ.
+pub const DIFF_MARKER: &str = "┃";
+
 pub fn format_type<T: FormatType>(db: &Database, typ: T) -> String {
     TypeFormatter::new(db, None).format(typ)
 }
@@ -20,6 +24,135 @@ pub fn format_type_with_arguments<T: FormatType>(
     TypeFormatter::new(db, Some(arguments)).format(typ)
 }
 
+pub fn format_type_with_options<T: FormatType>(
+    db: &Database,
+    options: FormatOptions,
+    typ: T,
+) -> String {
+    TypeFormatter::with_options(db, None, options).format(typ)
+}
+
+/// How to format type placeholders that aren't assigned a value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    /// Placeholders are formatted as `?`.
+    Question,
+
+    /// Placeholders are formatted as `?` followed by their ID, such as `?4`.
+    ///
+    /// This is useful when debugging type inference, as it makes it possible
+    /// to tell different placeholders apart.
+    Numbered,
+
+    /// Placeholders are formatted as `_`.
+    Underscore,
+}
+
+/// How to format the ownership of types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OwnershipStyle {
+    /// Ownership is formatted using keywords, such as `ref T` and `uni T`.
+    Keywords,
+
+    /// Ownership is formatted using sigils: `&T` for `ref T`, `&mut T` for
+    /// `mut T`, and `^T` for `uni T`.
+    ///
+    /// The `move` keyword doesn't have a sigil and is formatted as-is.
+    Sigils,
+
+    /// Ownership isn't formatted at all.
+    Hidden,
+}
+
+impl OwnershipStyle {
+    fn apply(self, keyword: &str) -> &str {
+        match self {
+            OwnershipStyle::Keywords => keyword,
+            OwnershipStyle::Hidden => "",
+            OwnershipStyle::Sigils => match keyword {
+                "ref " => "&",
+                "mut " => "&mut ",
+                "uni " => "^",
+                "uni ref " => "^&",
+                "uni mut " => "^&mut ",
+                _ => keyword,
+            },
+        }
+    }
+}
+
+/// Options that control how types are formatted.
+///
+/// Different consumers have different needs: error messages benefit from
+/// fully qualified names, while an editor showing the type of a symbol
+/// benefits from compact types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// If `true`, the names of classes, traits and type aliases are prefixed
+    /// with the name of the module they're defined in, such as
+    /// `std.array.Array` instead of `Array`.
+    pub qualified: bool,
+
+    /// The maximum depth of nested types to format, after which the nested
+    /// types are formatted as `...`.
+    pub max_depth: usize,
+
+    /// How to format type placeholders that aren't assigned a value.
+    pub placeholders: PlaceholderStyle,
+
+    /// How to format the ownership of types.
+    pub ownership: OwnershipStyle,
+}
+
+impl FormatOptions {
+    /// Returns the options for formatting types using fully qualified names.
+    pub fn qualified() -> FormatOptions {
+        FormatOptions { qualified: true, ..FormatOptions::default() }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            qualified: false,
+            max_depth: MAX_FORMATTING_DEPTH,
+            placeholders: PlaceholderStyle::Question,
+            ownership: OwnershipStyle::Keywords,
+        }
+    }
+}
+
+/// Formats two types such that only the parts that differ are highlighted.
+///
+/// Instead of formatting both types in full, the nested type arguments that
+/// differ are surrounded by `DIFF_MARKER`. Type arguments that are the same in
+/// both types and have type arguments of their own are elided. For example,
+/// comparing `Result[Array[Int], String]` with `Result[Array[Int], Error]`
+/// produces `Result[Array[...], ┃String┃]` and
+/// `Result[Array[...], ┃Error┃]`.
+///
+/// The return value is a tuple containing the formatted expected and found
+/// types, in that order. If the types are formatted the same, both are
+/// formatted in full and without any highlighting.
+pub fn format_type_diff(
+    db: &Database,
+    expected: TypeRef,
+    found: TypeRef,
+) -> (String, String) {
+    let exp = format_type(db, expected);
+    let fnd = format_type(db, found);
+
+    if exp == fnd {
+        return (exp, fnd);
+    }
+
+    let mut diff =
+        TypeDiff { db, expected: String::new(), found: String::new() };
+
+    diff.diff(expected, found, 0);
+    (diff.expected, diff.found)
+}
+
 pub fn type_parameter_capabilities(
     db: &Database,
     id: TypeParameterId,
@@ -45,6 +178,20 @@ fn format_type_parameter_without_argument(
 ) {
     let param = id.get(buffer.db);
 
+    // Constant type parameters can only be assigned integers, so they don't
+    // have any ownership, capabilities or requirements.
+    if param.constant {
+        if requirements {
+            buffer.write("let ");
+            buffer.write(&param.name);
+            buffer.write(": Int");
+        } else {
+            buffer.write(&param.name);
+        }
+
+        return;
+    }
+
     if owned {
         buffer.write_ownership("move ");
     }
@@ -124,6 +271,7 @@ pub struct TypeFormatter<'a> {
     type_arguments: Option<&'a TypeArguments>,
     buffer: String,
     depth: usize,
+    options: FormatOptions,
 }
 
 impl<'a> TypeFormatter<'a> {
@@ -131,14 +279,23 @@ impl<'a> TypeFormatter<'a> {
         db: &'a Database,
         type_arguments: Option<&'a TypeArguments>,
     ) -> Self {
-        Self { db, type_arguments, buffer: String::new(), depth: 0 }
+        Self::with_options(db, type_arguments, FormatOptions::default())
     }
 
+    /// Returns a formatter that formats types using fully qualified names.
     pub fn verbose(
         db: &'a Database,
         type_arguments: Option<&'a TypeArguments>,
     ) -> Self {
-        Self { db, type_arguments, buffer: String::new(), depth: 0 }
+        Self::with_options(db, type_arguments, FormatOptions::qualified())
+    }
+
+    pub fn with_options(
+        db: &'a Database,
+        type_arguments: Option<&'a TypeArguments>,
+        options: FormatOptions,
+    ) -> Self {
+        Self { db, type_arguments, buffer: String::new(), depth: 0, options }
     }
 
     pub fn format<T: FormatType>(mut self, typ: T) -> String {
@@ -147,7 +304,7 @@ impl<'a> TypeFormatter<'a> {
     }
 
     pub(crate) fn descend<F: FnOnce(&mut TypeFormatter)>(&mut self, block: F) {
-        if self.depth == MAX_FORMATTING_DEPTH {
+        if self.depth >= self.options.max_depth {
             self.write("...");
         } else {
             self.depth += 1;
@@ -167,11 +324,37 @@ impl<'a> TypeFormatter<'a> {
     /// T` or `uni uni T`. This method provides a simple way of preventing this
     /// from happening, without complicating the type formatting process.
     pub(crate) fn write_ownership(&mut self, thing: &str) {
-        if !self.buffer.ends_with(thing) {
+        let thing = self.options.ownership.apply(thing);
+
+        if !thing.is_empty() && !self.buffer.ends_with(thing) {
             self.write(thing);
         }
     }
 
+    /// Writes the name of a symbol defined in a module, qualifying it with the
+    /// module name if necessary.
+    pub(crate) fn write_name(&mut self, module: ModuleId, name: &str) {
+        // Builtin types may not be defined in an actual module (e.g. when
+        // type-checking without the standard library), so we have to check if
+        // the module exists.
+        if self.options.qualified
+            && (module.0 as usize) < self.db.number_of_modules()
+        {
+            self.write(&module.name(self.db).to_string());
+            self.write(".");
+        }
+
+        self.write(name);
+    }
+
+    fn write_unknown_placeholder(&mut self, id: TypePlaceholderId) {
+        match self.options.placeholders {
+            PlaceholderStyle::Question => self.write("?"),
+            PlaceholderStyle::Numbered => self.write(&format!("?{}", id.id)),
+            PlaceholderStyle::Underscore => self.write("_"),
+        }
+    }
+
     pub(crate) fn type_parameters(&mut self, parameters: &[TypeParameterId]) {
         if parameters.is_empty() {
             return;
@@ -252,6 +435,158 @@ impl<'a> TypeFormatter<'a> {
     }
 }
 
+/// A generic type of which the type arguments can be compared one by one.
+struct Generic {
+    /// The class or trait the type is an instance of.
+    ///
+    /// Structural tuples don't have a source, and are compared using the
+    /// number of values instead.
+    source: Option<TypeId>,
+
+    /// The text to produce before the type arguments, such as `ref Array[`.
+    head: String,
+
+    /// The text to produce after the type arguments.
+    close: &'static str,
+    arguments: Vec<TypeRef>,
+}
+
+impl Generic {
+    fn new(db: &Database, typ: TypeRef) -> Option<Generic> {
+        let id = typ.type_id(db).ok()?;
+
+        // The ownership of the type (e.g. `ref`) is whatever is produced in
+        // front of the type ID.
+        let full = format_type(db, typ);
+        let prefix = full.strip_suffix(&format_type(db, id))?;
+        let (source, name, open, close, params, args) = match id {
+            TypeId::ClassInstance(ins) => {
+                let class = ins.instance_of();
+                let (name, open, close) = if class.kind(db).is_tuple() {
+                    ("", "(", ")")
+                } else {
+                    (class.name(db).as_str(), "[", "]")
+                };
+
+                (
+                    Some(TypeId::Class(class)),
+                    name,
+                    open,
+                    close,
+                    class.type_parameters(db),
+                    ins.type_arguments(db),
+                )
+            }
+            TypeId::TraitInstance(ins) => {
+                let trait_id = ins.instance_of();
+
+                (
+                    Some(TypeId::Trait(trait_id)),
+                    trait_id.name(db).as_str(),
+                    "[",
+                    "]",
+                    trait_id.type_parameters(db),
+                    ins.type_arguments(db),
+                )
+            }
+            TypeId::Tuple(tuple) => {
+                return Some(Generic {
+                    source: None,
+                    head: format!("{}(", prefix),
+                    close: ")",
+                    arguments: tuple.elements(db).clone(),
+                });
+            }
+            _ => return None,
+        };
+
+        if params.is_empty() {
+            return None;
+        }
+
+        let arguments = params
+            .into_iter()
+            .map(|p| {
+                args.and_then(|a| a.get(p))
+                    .unwrap_or(TypeRef::Any(TypeId::TypeParameter(p)))
+            })
+            .collect();
+
+        Some(Generic {
+            source,
+            head: format!("{}{}{}", prefix, name, open),
+            close,
+            arguments,
+        })
+    }
+}
+
+/// A type used for formatting the differences between two types.
+struct TypeDiff<'a> {
+    db: &'a Database,
+    expected: String,
+    found: String,
+}
+
+impl<'a> TypeDiff<'a> {
+    fn diff(&mut self, expected: TypeRef, found: TypeRef, depth: usize) {
+        let exp = format_type(self.db, expected);
+        let fnd = format_type(self.db, found);
+
+        if exp == fnd {
+            let elided = self.elide(expected, exp);
+
+            self.expected.push_str(&elided);
+            self.found.push_str(&elided);
+            return;
+        }
+
+        match (Generic::new(self.db, expected), Generic::new(self.db, found)) {
+            (Some(exp_gen), Some(fnd_gen))
+                if depth < MAX_FORMATTING_DEPTH
+                    && exp_gen.source == fnd_gen.source
+                    && exp_gen.head == fnd_gen.head
+                    && exp_gen.arguments.len() == fnd_gen.arguments.len() =>
+            {
+                self.expected.push_str(&exp_gen.head);
+                self.found.push_str(&fnd_gen.head);
+
+                for (idx, (&exp_arg, &fnd_arg)) in exp_gen
+                    .arguments
+                    .iter()
+                    .zip(fnd_gen.arguments.iter())
+                    .enumerate()
+                {
+                    if idx > 0 {
+                        self.expected.push_str(", ");
+                        self.found.push_str(", ");
+                    }
+
+                    self.diff(exp_arg, fnd_arg, depth + 1);
+                }
+
+                self.expected.push_str(exp_gen.close);
+                self.found.push_str(fnd_gen.close);
+            }
+            _ => {
+                self.expected.push_str(DIFF_MARKER);
+                self.expected.push_str(&exp);
+                self.expected.push_str(DIFF_MARKER);
+                self.found.push_str(DIFF_MARKER);
+                self.found.push_str(&fnd);
+                self.found.push_str(DIFF_MARKER);
+            }
+        }
+    }
+
+    fn elide(&self, typ: TypeRef, formatted: String) -> String {
+        match Generic::new(self.db, typ) {
+            Some(gen) => format!("{}...{}", gen.head, gen.close),
+            None => formatted,
+        }
+    }
+}
+
 /// A type of which the name can be formatted into something human-readable.
 pub trait FormatType {
     fn format_type(&self, buffer: &mut TypeFormatter);
@@ -278,7 +613,7 @@ impl FormatType for TypePlaceholderId {
                 if let Some(req) = self.required(buffer.db) {
                     req.format_type(buffer);
                 } else {
-                    buffer.write("?");
+                    buffer.write_unknown_placeholder(*self);
                 }
 
                 buffer.write("]");
@@ -293,7 +628,7 @@ impl FormatType for TypePlaceholderId {
         if let Some(req) = self.required(buffer.db) {
             req.format_type(buffer);
         } else {
-            buffer.write("?");
+            buffer.write_unknown_placeholder(*self);
         }
     }
 }
@@ -306,7 +641,7 @@ impl FormatType for TypeParameterId {
 
 impl FormatType for TraitId {
     fn format_type(&self, buffer: &mut TypeFormatter) {
-        buffer.write(&self.get(buffer.db).name);
+        buffer.write_name(self.module(buffer.db), &self.get(buffer.db).name);
         buffer.type_parameters(&self.type_parameters(buffer.db));
     }
 }
@@ -316,7 +651,7 @@ impl FormatType for TraitInstance {
         buffer.descend(|buffer| {
             let ins_of = self.instance_of.get(buffer.db);
 
-            buffer.write(&ins_of.name);
+            buffer.write_name(ins_of.module, &ins_of.name);
 
             if !ins_of.type_parameters.is_empty() {
                 let params: Vec<_> =
@@ -332,7 +667,7 @@ impl FormatType for TraitInstance {
 
 impl FormatType for ClassId {
     fn format_type(&self, buffer: &mut TypeFormatter) {
-        buffer.write(&self.get(buffer.db).name);
+        buffer.write_name(self.module(buffer.db), &self.get(buffer.db).name);
         buffer.type_parameters(&self.type_parameters(buffer.db));
     }
 }
@@ -343,7 +678,7 @@ impl FormatType for ClassInstance {
             let ins_of = self.instance_of.get(buffer.db);
 
             if !matches!(ins_of.kind, ClassKind::Tuple) {
-                buffer.write(&ins_of.name);
+                buffer.write_name(ins_of.module, &ins_of.name);
             }
 
             if !ins_of.type_parameters.is_empty() {
@@ -364,6 +699,63 @@ impl FormatType for ClassInstance {
     }
 }
 
+impl FormatType for TypeAliasId {
+    fn format_type(&self, buffer: &mut TypeFormatter) {
+        buffer.write_name(self.module(buffer.db), self.name(buffer.db));
+        buffer.type_parameters(&self.type_parameters(buffer.db));
+    }
+}
+
+impl FormatType for TypeAliasInstance {
+    fn format_type(&self, buffer: &mut TypeFormatter) {
+        buffer.descend(|buffer| {
+            let ins_of = self.instance_of();
+
+            buffer.write_name(ins_of.module(buffer.db), ins_of.name(buffer.db));
+
+            if ins_of.is_generic(buffer.db) {
+                let params = ins_of.type_parameters(buffer.db);
+
+                buffer.write("[");
+                buffer.type_arguments(&params, self.type_arguments(buffer.db));
+                buffer.write("]");
+            }
+        });
+    }
+}
+
+impl FormatType for UnionId {
+    fn format_type(&self, buffer: &mut TypeFormatter) {
+        buffer.descend(|buffer| {
+            for (index, typ) in self.members(buffer.db).iter().enumerate() {
+                if index > 0 {
+                    buffer.write(" | ");
+                }
+
+                typ.format_type(buffer);
+            }
+        });
+    }
+}
+
+impl FormatType for TupleId {
+    fn format_type(&self, buffer: &mut TypeFormatter) {
+        buffer.descend(|buffer| {
+            buffer.write("(");
+
+            for (index, typ) in self.elements(buffer.db).iter().enumerate() {
+                if index > 0 {
+                    buffer.write(", ");
+                }
+
+                typ.format_type(buffer);
+            }
+
+            buffer.write(")");
+        });
+    }
+}
+
 impl FormatType for MethodId {
     fn format_type(&self, buffer: &mut TypeFormatter) {
         let block = self.get(buffer.db);
@@ -418,6 +810,11 @@ impl FormatType for ClosureId {
                 buffer.write("fn");
             }
 
+            if !fun.type_parameters.is_empty() {
+                buffer.write(" ");
+                buffer.type_parameters(&fun.type_parameters);
+            }
+
             if fun.arguments.len() > 0 {
                 buffer.write(" ");
             }
@@ -428,6 +825,35 @@ impl FormatType for ClosureId {
     }
 }
 
+impl FormatType for ForeignFunctionId {
+    fn format_type(&self, buffer: &mut TypeFormatter) {
+        buffer.descend(|buffer| {
+            let fun = self.get(buffer.db);
+
+            buffer.write("fn extern (");
+
+            for (index, typ) in fun.arguments.iter().enumerate() {
+                if index > 0 {
+                    buffer.write(", ");
+                }
+
+                typ.format_type(buffer);
+            }
+
+            if fun.variadic {
+                if !fun.arguments.is_empty() {
+                    buffer.write(", ");
+                }
+
+                buffer.write("...");
+            }
+
+            buffer.write(")");
+            buffer.return_type(fun.return_type);
+        });
+    }
+}
+
 impl FormatType for TypeRef {
     fn format_type(&self, buffer: &mut TypeFormatter) {
         match self {
@@ -485,6 +911,12 @@ impl FormatType for TypeRef {
                 typ.format_type(buffer);
                 buffer.write("]");
             }
+            TypeRef::NonNullPointer(typ) => {
+                buffer.write("NonNullPointer[");
+                typ.format_type(buffer);
+                buffer.write("]");
+            }
+            TypeRef::Union(id) => id.format_type(buffer),
         };
     }
 }
@@ -514,6 +946,12 @@ impl FormatType for TypeId {
             TypeId::Foreign(ForeignType::Float(size)) => {
                 buffer.write(&format!("Float{}", size))
             }
+            TypeId::Foreign(ForeignType::Function(id)) => {
+                id.format_type(buffer)
+            }
+            TypeId::TypeAlias(ins) => ins.format_type(buffer),
+            TypeId::Tuple(id) => id.format_type(buffer),
+            TypeId::Constant(val) => buffer.write(&val.to_string()),
         }
     }
 }
@@ -522,14 +960,16 @@ impl FormatType for TypeId {
 mod tests {
     use super::*;
     use crate::test::{
-        any, immutable, immutable_uni, instance, mutable, mutable_uni,
-        new_class, new_parameter, owned, placeholder, uni,
+        any, generic_instance_id, immutable, immutable_uni, instance, mutable,
+        mutable_uni, new_class, new_module, new_parameter, owned, parameter,
+        placeholder, uni,
     };
     use crate::{
-        Block, Class, ClassInstance, ClassKind, Closure, Database, Inline,
-        Location, Method, MethodKind, Module, ModuleId, ModuleName, Trait,
-        TraitInstance, TypeArguments, TypeId, TypeParameter, TypePlaceholder,
-        TypeRef, Visibility,
+        Block, Class, ClassInstance, ClassKind, Closure, Database,
+        ForeignFunction, Inline, Location, Method, MethodKind, Module,
+        ModuleId, ModuleName, Trait, TraitInstance, TypeAlias,
+        TypeAliasInstance, TypeArguments, TypeId, TypeParameter,
+        TypePlaceholder, TypeRef, Visibility,
     };
 
     #[test]
@@ -751,6 +1191,26 @@ mod tests {
         assert_eq!(format_type(&db, block), "fn -> Never");
     }
 
+    #[test]
+    fn test_closure_id_format_type_with_type_parameters() {
+        let mut db = Database::new();
+        let block = Closure::alloc(&mut db, false);
+        let param = TypeParameter::alloc(&mut db, "T".to_string());
+        let typ = TypeRef::Ref(TypeId::TypeParameter(param));
+
+        block.add_type_parameter(&mut db, param);
+        block.new_argument(
+            &mut db,
+            "a".to_string(),
+            typ,
+            typ,
+            Location::default(),
+        );
+        block.set_return_type(&mut db, TypeRef::int());
+
+        assert_eq!(format_type(&db, block), "fn [T] (ref T) -> Int");
+    }
+
     #[test]
     fn test_type_id_format_type_with_class() {
         let mut db = Database::new();
@@ -1032,6 +1492,33 @@ mod tests {
         assert_eq!(format_type(&db, param_ins), "T");
     }
 
+    #[test]
+    fn test_type_id_format_type_with_foreign_function() {
+        let mut db = Database::new();
+        let int = TypeRef::foreign_signed_int(32);
+        let ptr = TypeRef::pointer(TypeId::Foreign(ForeignType::Int(
+            8,
+            Sign::Unsigned,
+        )));
+        let fun1 = ForeignFunction::alloc(&mut db, vec![int, ptr], int, false);
+        let fun2 = ForeignFunction::alloc(&mut db, vec![ptr], int, true);
+        let fun3 =
+            ForeignFunction::alloc(&mut db, Vec::new(), TypeRef::nil(), true);
+
+        assert_eq!(
+            format_type(&db, TypeRef::foreign_function(fun1)),
+            "fn extern (Int32, Pointer[UInt8]) -> Int32"
+        );
+        assert_eq!(
+            format_type(&db, TypeRef::foreign_function(fun2)),
+            "fn extern (Pointer[UInt8], ...) -> Int32"
+        );
+        assert_eq!(
+            format_type(&db, TypeRef::foreign_function(fun3)),
+            "fn extern (...)"
+        );
+    }
+
     #[test]
     fn test_type_id_format_type_with_closure() {
         let mut db = Database::new();
@@ -1129,6 +1616,26 @@ mod tests {
         assert_eq!(format_type(&db, TypeRef::foreign_signed_int(16)), "Int16");
         assert_eq!(format_type(&db, TypeRef::foreign_signed_int(32)), "Int32");
         assert_eq!(format_type(&db, TypeRef::foreign_signed_int(64)), "Int64");
+        assert_eq!(
+            format_type(&db, TypeRef::foreign_signed_int(128)),
+            "Int128"
+        );
+        assert_eq!(
+            format_type(&db, TypeRef::foreign_unsigned_int(128)),
+            "UInt128"
+        );
+        assert_eq!(format_type(&db, TypeRef::foreign_float(80)), "Float80");
+        assert_eq!(
+            format_type(
+                &db,
+                TypeRef::non_null_pointer(TypeId::Foreign(ForeignType::Int(
+                    8,
+                    Sign::Signed
+                )))
+            ),
+            "NonNullPointer[Int8]"
+        );
+        assert_eq!(format_type(&db, TypeRef::foreign_float(128)), "Float128");
         assert_eq!(format_type(&db, TypeRef::foreign_unsigned_int(8)), "UInt8");
         assert_eq!(
             format_type(&db, TypeRef::foreign_unsigned_int(16)),
@@ -1237,4 +1744,267 @@ mod tests {
             assert_eq!(format_type(&db, placeholder(p1)), format);
         }
     }
+
+    #[test]
+    fn test_format_type_with_qualified_names() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "std.array");
+        let array = Class::alloc(
+            &mut db,
+            "Array".to_string(),
+            ClassKind::Regular,
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+        let param = array.new_type_parameter(&mut db, "T".to_string());
+        let mut targs = TypeArguments::new();
+
+        targs.assign(param, TypeRef::foreign_signed_int(32));
+
+        let ins = ClassInstance::generic(&mut db, array, targs);
+        let typ = immutable(TypeId::ClassInstance(ins));
+
+        assert_eq!(format_type(&db, typ), "ref Array[Int32]");
+        assert_eq!(
+            format_type_with_options(&db, FormatOptions::qualified(), typ),
+            "ref std.array.Array[Int32]"
+        );
+        assert_eq!(
+            TypeFormatter::verbose(&db, None).format(array),
+            "std.array.Array[T]"
+        );
+    }
+
+    #[test]
+    fn test_format_type_with_max_depth() {
+        let mut db = Database::new();
+        let array = new_class(&mut db, "Array");
+        let param = array.new_type_parameter(&mut db, "T".to_string());
+        let mut targs = TypeArguments::new();
+
+        targs.assign(param, TypeRef::int());
+
+        let inner = ClassInstance::generic(&mut db, array, targs);
+        let mut targs = TypeArguments::new();
+
+        targs.assign(param, owned(TypeId::ClassInstance(inner)));
+
+        let outer = ClassInstance::generic(&mut db, array, targs);
+        let typ = owned(TypeId::ClassInstance(outer));
+        let opts = FormatOptions { max_depth: 1, ..FormatOptions::default() };
+
+        assert_eq!(format_type(&db, typ), "Array[Array[Int]]");
+        assert_eq!(format_type_with_options(&db, opts, typ), "Array[...]");
+    }
+
+    #[test]
+    fn test_format_type_with_placeholder_styles() {
+        let mut db = Database::new();
+        let id = TypePlaceholder::alloc(&mut db, None);
+        let typ = placeholder(id);
+        let style = |placeholders| FormatOptions {
+            placeholders,
+            ..FormatOptions::default()
+        };
+
+        assert_eq!(
+            format_type_with_options(
+                &db,
+                style(PlaceholderStyle::Question),
+                typ
+            ),
+            "?"
+        );
+        assert_eq!(
+            format_type_with_options(
+                &db,
+                style(PlaceholderStyle::Numbered),
+                typ
+            ),
+            format!("?{}", id.id)
+        );
+        assert_eq!(
+            format_type_with_options(
+                &db,
+                style(PlaceholderStyle::Underscore),
+                typ
+            ),
+            "_"
+        );
+    }
+
+    #[test]
+    fn test_format_type_with_ownership_styles() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let sigils = FormatOptions {
+            ownership: OwnershipStyle::Sigils,
+            ..FormatOptions::default()
+        };
+        let hidden = FormatOptions {
+            ownership: OwnershipStyle::Hidden,
+            ..FormatOptions::default()
+        };
+        let tests = vec![
+            (owned(instance(foo)), "Foo", "Foo"),
+            (immutable(instance(foo)), "&Foo", "Foo"),
+            (mutable(instance(foo)), "&mut Foo", "Foo"),
+            (uni(instance(foo)), "^Foo", "Foo"),
+            (immutable_uni(instance(foo)), "^&Foo", "Foo"),
+            (mutable_uni(instance(foo)), "^&mut Foo", "Foo"),
+        ];
+
+        for (typ, sigil, none) in tests {
+            assert_eq!(format_type_with_options(&db, sigils, typ), sigil);
+            assert_eq!(format_type_with_options(&db, hidden, typ), none);
+        }
+    }
+
+    #[test]
+    fn test_type_alias_format_type() {
+        let mut db = Database::new();
+        let alias = TypeAlias::alloc(
+            &mut db,
+            "Pair".to_string(),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+        let param1 = alias.new_type_parameter(&mut db, "A".to_string());
+
+        alias.new_type_parameter(&mut db, "B".to_string());
+
+        let mut targs = TypeArguments::new();
+
+        targs.assign(param1, TypeRef::int());
+
+        let ins = TypeAliasInstance::generic(&mut db, alias, targs);
+
+        assert_eq!(format_type(&db, alias), "Pair[A, B]");
+        assert_eq!(
+            format_type(&db, immutable(TypeId::TypeAlias(ins))),
+            "ref Pair[Int, B]"
+        );
+    }
+
+    #[test]
+    fn test_union_format_type() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let typ = TypeRef::union(
+            &mut db,
+            vec![TypeRef::int(), immutable(instance(foo)), TypeRef::nil()],
+        );
+
+        assert_eq!(format_type(&db, typ), "Int | ref Foo | Nil");
+    }
+
+    #[test]
+    fn test_format_type_diff() {
+        let mut db = Database::new();
+        let result = new_class(&mut db, "Result");
+        let array = new_class(&mut db, "Array");
+        let error = new_class(&mut db, "Error");
+
+        result.new_type_parameter(&mut db, "T".to_string());
+        result.new_type_parameter(&mut db, "E".to_string());
+        array.new_type_parameter(&mut db, "T".to_string());
+
+        let ints =
+            owned(generic_instance_id(&mut db, array, vec![TypeRef::int()]));
+        let exp = owned(generic_instance_id(
+            &mut db,
+            result,
+            vec![ints, TypeRef::string()],
+        ));
+        let fnd = owned(generic_instance_id(
+            &mut db,
+            result,
+            vec![ints, owned(instance(error))],
+        ));
+
+        assert_eq!(
+            format_type_diff(&db, exp, fnd),
+            (
+                "Result[Array[...], ┃String┃]".to_string(),
+                "Result[Array[...], ┃Error┃]".to_string()
+            )
+        );
+        assert_eq!(
+            format_type_diff(&db, exp, exp),
+            (
+                "Result[Array[Int], String]".to_string(),
+                "Result[Array[Int], String]".to_string()
+            )
+        );
+        assert_eq!(
+            format_type_diff(&db, TypeRef::int(), immutable(instance(error))),
+            ("┃Int┃".to_string(), "┃ref Error┃".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_type_diff_with_different_ownership() {
+        let mut db = Database::new();
+        let array = new_class(&mut db, "Array");
+
+        array.new_type_parameter(&mut db, "T".to_string());
+
+        let id = generic_instance_id(&mut db, array, vec![TypeRef::int()]);
+
+        assert_eq!(
+            format_type_diff(&db, owned(id), immutable(id)),
+            ("┃Array[Int]┃".to_string(), "┃ref Array[Int]┃".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_type_diff_with_tuples() {
+        let mut db = Database::new();
+        let exp = TypeRef::tuple(&mut db, vec![TypeRef::int(), TypeRef::int()]);
+        let fnd =
+            TypeRef::tuple(&mut db, vec![TypeRef::int(), TypeRef::float()]);
+
+        assert_eq!(
+            format_type_diff(&db, exp, fnd),
+            ("(Int, ┃Int┃)".to_string(), "(Int, ┃Float┃)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tuple_format_type() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let mut values = vec![TypeRef::int(); 8];
+
+        values.push(immutable(instance(foo)));
+
+        let typ = TypeRef::tuple(&mut db, values);
+
+        assert_eq!(
+            format_type(&db, typ),
+            "(Int, Int, Int, Int, Int, Int, Int, Int, ref Foo)"
+        );
+    }
+
+    #[test]
+    fn test_constant_format_type() {
+        let mut db = Database::new();
+        let matrix = new_class(&mut db, "Matrix");
+        let size = matrix.new_type_parameter(&mut db, "N".to_string());
+
+        matrix.new_type_parameter(&mut db, "T".to_string());
+        size.set_constant(&mut db);
+
+        let typ = owned(generic_instance_id(
+            &mut db,
+            matrix,
+            vec![TypeRef::constant(3), TypeRef::int()],
+        ));
+
+        assert_eq!(format_type(&db, matrix), "Matrix[let N: Int, T]");
+        assert_eq!(format_type(&db, typ), "Matrix[3, Int]");
+        assert_eq!(format_type(&db, owned(parameter(size))), "N");
+    }
 }
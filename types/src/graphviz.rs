@@ -0,0 +1,200 @@
+//! Generating Graphviz graphs of classes and traits.
+//!
+//! The graphs produced by this module show the traits implemented by classes,
+//! and the traits required by other traits. Classes and traits are grouped by
+//! the module they are defined in.
+use crate::{ClassId, Database, ModuleId, TraitId};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Class(u32),
+    Trait(u32),
+}
+
+impl Node {
+    fn id(self) -> String {
+        match self {
+            Node::Class(id) => format!("c{}", id),
+            Node::Trait(id) => format!("t{}", id),
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Returns a String containing Dot/graphviz code for visualising the classes
+/// and traits defined in the given modules.
+///
+/// If `modules` is empty, all modules are included. Traits defined outside of
+/// the given modules are included if they are implemented or required by a
+/// class or trait that is included.
+pub fn to_dot(db: &Database, modules: &[ModuleId]) -> String {
+    // Builtin classes default to the first module until they're defined, so
+    // we have to make sure the module actually exists.
+    let included = |module: ModuleId| {
+        (module.0 as usize) < db.number_of_modules()
+            && (modules.is_empty() || modules.contains(&module))
+    };
+    let mut nodes = BTreeSet::new();
+    let mut edges = Vec::new();
+
+    for idx in 0..db.number_of_classes() {
+        let id = ClassId(idx as u32);
+        let kind = id.kind(db);
+
+        // Specializations share the implementations of their source class,
+        // and module and closure classes can't implement traits.
+        if kind.is_module()
+            || kind.is_closure()
+            || id.specialization_source(db).is_some()
+            || !included(id.module(db))
+        {
+            continue;
+        }
+
+        let mut traits: Vec<_> = id
+            .implemented_traits(db)
+            .map(|imp| imp.instance.instance_of().0)
+            .collect();
+
+        traits.sort_unstable();
+        nodes.insert(Node::Class(id.0));
+
+        for trait_id in traits {
+            nodes.insert(Node::Trait(trait_id));
+            edges.push((Node::Class(id.0), Node::Trait(trait_id), false));
+        }
+    }
+
+    for idx in 0..db.number_of_traits() {
+        let id = TraitId(idx as u32);
+
+        if !included(id.module(db)) {
+            continue;
+        }
+
+        nodes.insert(Node::Trait(id.0));
+
+        for req in id.required_traits(db) {
+            let req_id = req.instance_of().0;
+
+            nodes.insert(Node::Trait(req_id));
+            edges.push((Node::Trait(id.0), Node::Trait(req_id), true));
+        }
+    }
+
+    let mut clusters: BTreeMap<u32, Vec<Node>> = BTreeMap::new();
+
+    for &node in &nodes {
+        let module = match node {
+            Node::Class(id) => ClassId(id).module(db),
+            Node::Trait(id) => TraitId(id).module(db),
+        };
+
+        clusters.entry(module.0).or_default().push(node);
+    }
+
+    let mut buffer = String::new();
+
+    buffer.push_str("digraph Types {\n");
+    buffer.push_str("graph[fontname=\"monospace\", fontsize=10];\n");
+    buffer.push_str("node[fontname=\"monospace\", fontsize=10];\n");
+    buffer.push_str("edge[fontname=\"monospace\", fontsize=10];\n");
+
+    for (module, nodes) in clusters {
+        let _ = writeln!(buffer, "subgraph cluster_{} {{", module);
+        let _ = writeln!(
+            buffer,
+            "  label=\"{}\";",
+            escape(&ModuleId(module).name(db).to_string())
+        );
+
+        for node in nodes {
+            let (name, shape) = match node {
+                Node::Class(id) => (ClassId(id).name(db), "box"),
+                Node::Trait(id) => (TraitId(id).name(db), "ellipse"),
+            };
+
+            let _ = writeln!(
+                buffer,
+                "  {}[shape=\"{}\" label=\"{}\"];",
+                node.id(),
+                shape,
+                escape(name)
+            );
+        }
+
+        buffer.push_str("}\n");
+    }
+
+    for (from, to, requirement) in edges {
+        let _ = writeln!(
+            buffer,
+            "{} -> {}{};",
+            from.id(),
+            to.id(),
+            if requirement { " [style=dashed]" } else { "" }
+        );
+    }
+
+    buffer.push_str("}\n");
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        implement, new_class, new_module, new_trait, trait_instance,
+    };
+
+    #[test]
+    fn test_to_dot() {
+        let mut db = Database::new();
+
+        // Traits created using new_trait() are defined in the first module.
+        let std_mod = new_module(&mut db, "std");
+        let app_mod = new_module(&mut db, "app");
+        let to_string = new_trait(&mut db, "ToString");
+        let format = new_trait(&mut db, "Format");
+        let class = new_class(&mut db, "Foo");
+        let other = new_class(&mut db, "Bar");
+
+        format.add_required_trait(&mut db, trait_instance(to_string));
+        class.set_module(&mut db, app_mod);
+        other.set_module(&mut db, app_mod);
+        implement(&mut db, trait_instance(format), class);
+
+        let dot = to_dot(&db, &[app_mod]);
+        let foo = format!("c{}", class.0);
+        let bar = format!("c{}", other.0);
+        let fmt = format!("t{}", format.0);
+        let to_s = format!("t{}", to_string.0);
+
+        assert!(dot.starts_with("digraph Types {\n"));
+        assert!(dot.contains(&format!("subgraph cluster_{} {{", std_mod.0)));
+        assert!(dot.contains("  label=\"app\";"));
+        assert!(
+            dot.contains(&format!("  {}[shape=\"box\" label=\"Foo\"];", foo))
+        );
+        assert!(
+            dot.contains(&format!("  {}[shape=\"box\" label=\"Bar\"];", bar))
+        );
+        assert!(dot.contains(&format!("{} -> {};", foo, fmt)));
+
+        // Requirements are only included for traits in the selected modules.
+        assert!(!dot.contains(&to_s));
+
+        let dot = to_dot(&db, &[]);
+
+        assert!(dot.contains(&format!("{} -> {} [style=dashed];", fmt, to_s)));
+        assert!(dot.contains(&format!(
+            "  {}[shape=\"ellipse\" label=\"ToString\"];",
+            to_s
+        )));
+    }
+}
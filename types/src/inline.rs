@@ -0,0 +1,236 @@
+//! Deciding which methods to inline.
+//!
+//! The decision to inline a method depends on the method's `Inline` setting,
+//! and for methods using `Inline::Infer` on a cost model: a method is inlined
+//! if the combined weight of the caller and callee doesn't exceed a threshold.
+//! The weight of a method is derived from the size of its body (as determined
+//! by the caller, such as the MIR inliner) and its number of arguments.
+//!
+//! The policy is stored in the `Database` such that it can be tuned using
+//! compiler flags, while individual methods may override the threshold using
+//! `MethodId::set_inline_threshold()`.
+//!
+//! Methods using `Inline::Always` that (indirectly) call themselves can't be
+//! inlined, as doing so would never terminate. Such methods are found using
+//! the call graph, and are changed to use `Inline::Infer` instead.
+use crate::{Database, Inline, MethodId};
+
+/// The settings used for deciding which methods to inline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InlinePolicy {
+    /// The maximum weight a method is allowed to have before we stop inlining
+    /// other methods into it.
+    pub threshold: u16,
+
+    /// If a method wouldn't be inlined but is called at most this many times,
+    /// it's still inlined.
+    ///
+    /// The goal of this setting is to allow inlining of methods that aren't
+    /// used much, such as (large) private helper methods.
+    pub call_count: u16,
+
+    /// The weight added for every argument of a method.
+    ///
+    /// Arguments are cheap to pass, so this defaults to zero, but it can be
+    /// increased to favour inlining methods with few arguments.
+    pub argument_weight: u16,
+}
+
+impl Default for InlinePolicy {
+    fn default() -> InlinePolicy {
+        // The threshold is rather conservative in order to reduce the amount
+        // of LLVM IR we produce, as more LLVM IR results in (drastically) more
+        // compile-time memory usage.
+        InlinePolicy { threshold: 100, call_count: 2, argument_weight: 0 }
+    }
+}
+
+/// The sizes to consider when deciding whether to inline a call.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InlineSize {
+    /// The size of the body of the method to inline.
+    pub body: u16,
+
+    /// The current weight of the method the call resides in.
+    pub caller: u16,
+
+    /// The number of call sites of the method to inline.
+    pub calls: u16,
+}
+
+impl MethodId {
+    /// Returns the weight of this method, given the size of its body.
+    pub fn inline_weight(self, db: &Database, body: u16) -> u16 {
+        let args = self.number_of_arguments(db).min(u16::MAX as usize) as u16;
+
+        body.saturating_add(
+            args.saturating_mul(db.inline_policy.argument_weight),
+        )
+    }
+
+    /// Sets the threshold to use when inlining this method, overriding the
+    /// threshold of the database's inline policy.
+    pub fn set_inline_threshold(self, db: &mut Database, threshold: u16) {
+        self.get_mut(db).inline_threshold = Some(threshold);
+    }
+
+    pub fn inline_threshold(self, db: &Database) -> u16 {
+        self.get(db).inline_threshold.unwrap_or(db.inline_policy.threshold)
+    }
+
+    /// Returns `true` if a call to this method should be inlined.
+    pub fn inline_decision(self, db: &Database, size: InlineSize) -> bool {
+        match self.inline(db) {
+            Inline::Always => true,
+            Inline::Never => false,
+            Inline::Infer => {
+                let weight = self.inline_weight(db, size.body);
+
+                weight == 0
+                    || size.calls <= db.inline_policy.call_count
+                    || size.caller.saturating_add(weight)
+                        <= self.inline_threshold(db)
+            }
+        }
+    }
+}
+
+impl Database {
+    pub fn set_inline_policy(&mut self, policy: InlinePolicy) {
+        self.inline_policy = policy;
+    }
+
+    pub fn inline_policy(&self) -> InlinePolicy {
+        self.inline_policy
+    }
+
+    /// Returns the groups of (mutually) recursive methods that include at
+    /// least one method using `Inline::Always`.
+    pub fn always_inline_cycles(&self) -> Vec<Vec<MethodId>> {
+        self.call_graph()
+            .cycles(self)
+            .into_iter()
+            .filter(|c| c.iter().any(|m| m.inline(self) == Inline::Always))
+            .collect()
+    }
+
+    /// Changes the methods using `Inline::Always` in the given cycles (as
+    /// returned by `Database::always_inline_cycles`) to use `Inline::Infer`.
+    ///
+    /// Specializations of these methods are changed as well, such that this
+    /// method can be used both before and after specializing methods.
+    pub fn break_always_inline_cycles(&mut self, cycles: &[Vec<MethodId>]) {
+        for &method in cycles.iter().flatten() {
+            if method.inline(self) != Inline::Always {
+                continue;
+            }
+
+            method.set_inline(self, Inline::Infer);
+
+            for id in method.specializations(self) {
+                if id.inline(self) == Inline::Always {
+                    id.set_inline(self, Inline::Infer);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_method, new_module};
+    use crate::{Block, Location, MethodKind, TypeRef};
+
+    #[test]
+    fn test_inline_weight() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let method = new_method(&mut db, module, "foo", MethodKind::Instance);
+
+        for name in ["a", "b"] {
+            method.new_argument(
+                &mut db,
+                name.to_string(),
+                TypeRef::int(),
+                TypeRef::int(),
+                Location::default(),
+            );
+        }
+
+        assert_eq!(method.inline_weight(&db, 10), 10);
+
+        db.set_inline_policy(InlinePolicy {
+            argument_weight: 3,
+            ..InlinePolicy::default()
+        });
+
+        assert_eq!(method.inline_weight(&db, 10), 16);
+        assert_eq!(method.inline_weight(&db, u16::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn test_inline_decision() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let method = new_method(&mut db, module, "foo", MethodKind::Instance);
+        let small = InlineSize { body: 10, caller: 50, calls: 10 };
+        let large = InlineSize { body: 60, caller: 50, calls: 10 };
+
+        assert!(method.inline_decision(&db, small));
+        assert!(!method.inline_decision(&db, large));
+        assert!(method.inline_decision(&db, InlineSize { calls: 2, ..large }));
+        assert!(method.inline_decision(&db, InlineSize { body: 0, ..large }));
+
+        method.set_inline_threshold(&mut db, 200);
+        assert_eq!(method.inline_threshold(&db), 200);
+        assert!(method.inline_decision(&db, large));
+
+        method.set_inline(&mut db, Inline::Never);
+        assert!(!method.inline_decision(&db, small));
+
+        method.set_inline(&mut db, Inline::Always);
+        assert!(method.inline_decision(&db, large));
+    }
+
+    #[test]
+    fn test_break_always_inline_cycles() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let a = new_method(&mut db, module, "a", MethodKind::Instance);
+        let b = new_method(&mut db, module, "b", MethodKind::Instance);
+        let c = new_method(&mut db, module, "c", MethodKind::Instance);
+        let d = new_method(&mut db, module, "d", MethodKind::Instance);
+        let e = new_method(&mut db, module, "e", MethodKind::Instance);
+        let a_spec =
+            new_method(&mut db, module, "a_spec", MethodKind::Instance);
+
+        a.always_inline(&mut db);
+        a_spec.always_inline(&mut db);
+        a.add_specialization(&mut db, Vec::new(), a_spec);
+        c.always_inline(&mut db);
+        d.always_inline(&mut db);
+
+        // `a` and `b` form a cycle that includes an always inlined method, `c`
+        // is always inlined but not recursive, and `e` is recursive but not
+        // always inlined.
+        db.record_call(a, b, false);
+        db.record_call(b, a, false);
+        db.record_call(b, c, false);
+        db.record_call(d, d, false);
+        db.record_call(e, e, false);
+
+        let cycles = db.always_inline_cycles();
+
+        assert_eq!(cycles, vec![vec![a, b], vec![d]]);
+
+        db.break_always_inline_cycles(&cycles);
+        assert_eq!(a.inline(&db), Inline::Infer);
+        assert_eq!(a_spec.inline(&db), Inline::Infer);
+        assert_eq!(b.inline(&db), Inline::Infer);
+        assert_eq!(c.inline(&db), Inline::Always);
+        assert_eq!(d.inline(&db), Inline::Infer);
+        assert_eq!(e.inline(&db), Inline::Infer);
+        assert!(db.always_inline_cycles().is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Invalidating modules such that they can be checked again.
+//!
+//! Language servers check a project once, then check individual files again as
+//! they're edited. Rebuilding the entire database for every edit is too slow
+//! for large projects, so instead the edited module is invalidated: the data
+//! defined by the module is removed from the database, after which the module
+//! can be checked again against the data of the remaining modules.
+//!
+//! Entities such as classes and methods are stored in vectors and referred to
+//! using their indexes, so they can't be removed without invalidating the IDs
+//! of unrelated entities. Instead, the entities defined by the module are made
+//! unreachable: they're removed from the symbol tables, classes and traits
+//! that refer to them, and checking the module again allocates new entities.
+use crate::module_graph::ModuleGraph;
+use crate::{ClassId, Database, MethodId, ModuleId, TraitId};
+use std::collections::HashSet;
+
+impl Database {
+    /// Removes the classes, methods, constants, trait implementations and
+    /// symbols defined by the module, along with any cached data recorded
+    /// while checking it.
+    ///
+    /// The module itself remains defined, such that its ID can be reused when
+    /// checking it again.
+    ///
+    /// The return value is the list of modules that import symbols from the
+    /// module, sorted by their IDs. Such modules may refer to the removed
+    /// entities, and thus must be invalidated and checked again as well.
+    pub fn invalidate_module(&mut self, module: ModuleId) -> Vec<ModuleId> {
+        let dependents = ModuleGraph::new(self).dependents(module).to_vec();
+        let methods: HashSet<MethodId> = (0..self.methods.len())
+            .map(|i| MethodId(i as u32))
+            .filter(|m| m.module(self) == module)
+            .collect();
+
+        for idx in 0..self.classes.len() {
+            let id = ClassId(idx as u32);
+
+            if id.module(self) == module {
+                id.remove_trait_implementations(self, |_| true);
+                id.get_mut(self).methods.clear();
+                id.get_mut(self).constants.clear();
+            } else {
+                id.remove_trait_implementations(self, |m| m == module);
+                id.get_mut(self).methods.retain(|_, m| !methods.contains(m));
+            }
+        }
+
+        for idx in 0..self.traits.len() {
+            let id = TraitId(idx as u32);
+            let typ = id.get_mut(self);
+
+            typ.blanket_implementations
+                .retain(|b| b.implementation.module != module);
+
+            if typ.module == module {
+                typ.default_methods.clear();
+                typ.required_methods.clear();
+                typ.required_traits.clear();
+                typ.default_constants.clear();
+                typ.required_constants.clear();
+            }
+        }
+
+        let data = module.get_mut(self);
+
+        data.symbols.clear();
+        data.constants.clear();
+        data.extern_methods.clear();
+        data.dependencies.clear();
+
+        if self.main_method.map_or(false, |m| methods.contains(&m)) {
+            self.main_method = None;
+        }
+
+        if self.main_class.map_or(false, |c| c.module(self) == module) {
+            self.main_class = None;
+        }
+
+        self.call_graph.remove_methods(&methods);
+
+        if let Some(refs) = self.references.as_mut() {
+            refs.remove_module(module);
+        }
+
+        if let Some(origins) = self.origins.as_mut() {
+            origins.remove_module(module);
+        }
+
+        dependents
+    }
+}
+
+impl ClassId {
+    /// Removes the trait implementations for which `remove` returns `true`
+    /// when given the module the implementation is defined in.
+    ///
+    /// If an implementation is removed and an overlapping implementation of
+    /// the same trait remains, the overlapping implementation takes its place.
+    fn remove_trait_implementations<F: Fn(ModuleId) -> bool>(
+        self,
+        db: &mut Database,
+        remove: F,
+    ) {
+        let class = self.get_mut(db);
+        let removed: Vec<TraitId> = class
+            .implemented_traits
+            .iter()
+            .filter(|(_, imp)| remove(imp.module))
+            .map(|(&id, _)| id)
+            .collect();
+
+        class.overlapping_implementations.retain(|imp| !remove(imp.module));
+
+        for &trait_id in &removed {
+            class.implemented_traits.remove(&trait_id);
+
+            if let Some(idx) = class
+                .overlapping_implementations
+                .iter()
+                .position(|imp| imp.instance.instance_of() == trait_id)
+            {
+                let imp = class.overlapping_implementations.remove(idx);
+
+                class.implemented_traits.insert(trait_id, imp);
+            }
+        }
+
+        for trait_id in removed {
+            if !self.get(db).implemented_traits.contains_key(&trait_id) {
+                trait_id.get_mut(db).implemented_by.retain(|&c| c != self);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::origin::{ExpressionKey, Origin};
+    use crate::references::Reference;
+    use crate::test::{new_class, new_method, new_module, new_trait};
+    use crate::{
+        Class, ClassKind, Constant, Location, MethodKind, Symbol,
+        TraitImplementation, TraitInstance, TypeBounds, TypeRef, Visibility,
+    };
+
+    fn implement(
+        db: &mut Database,
+        module: ModuleId,
+        class: ClassId,
+        trait_id: TraitId,
+    ) {
+        class.add_trait_implementation(
+            db,
+            TraitImplementation {
+                instance: TraitInstance::new(trait_id),
+                bounds: TypeBounds::new(),
+                module,
+                location: Location::default(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_database_invalidate_module() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let baz = new_module(&mut db, "baz");
+        let class = Class::alloc(
+            &mut db,
+            "Person".to_string(),
+            ClassKind::Regular,
+            Visibility::Public,
+            foo,
+            Location::default(),
+        );
+        let constant = Constant::alloc(
+            &mut db,
+            foo,
+            Location::default(),
+            "A".to_string(),
+            Visibility::Public,
+            TypeRef::int(),
+        );
+        let method = new_method(&mut db, foo, "name", MethodKind::Instance);
+
+        class.add_method(&mut db, "name".to_string(), method);
+        foo.new_symbol(&mut db, "Person".to_string(), Symbol::Class(class));
+        foo.new_symbol(&mut db, "A".to_string(), Symbol::Constant(constant));
+        bar.new_symbol(&mut db, "Person".to_string(), Symbol::Class(class));
+        baz.new_symbol(&mut db, "A".to_string(), Symbol::Constant(constant));
+        db.set_main_method(method);
+
+        assert_eq!(db.invalidate_module(foo), vec![bar, baz]);
+        assert!(foo.symbols(&db).is_empty());
+        assert!(foo.constants(&db).is_empty());
+        assert!(class.methods(&db).is_empty());
+        assert_eq!(db.main_method(), None);
+
+        // Other modules are left as-is, and must be invalidated separately.
+        assert!(bar.symbol_exists(&db, "Person"));
+    }
+
+    #[test]
+    fn test_database_invalidate_module_with_trait_implementations() {
+        let mut db = Database::new();
+
+        // Classes created using new_class() are defined in the first module.
+        let std = new_module(&mut db, "std");
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let class = new_class(&mut db, "Person");
+        let to_string = new_trait(&mut db, "ToString");
+        let equal = new_trait(&mut db, "Equal");
+        let class_method =
+            new_method(&mut db, bar, "to_string", MethodKind::Instance);
+        let foo_method = new_method(&mut db, foo, "==", MethodKind::Instance);
+
+        class.add_method(&mut db, "to_string".to_string(), class_method);
+        class.add_method(&mut db, "==".to_string(), foo_method);
+        implement(&mut db, foo, class, to_string);
+        implement(&mut db, bar, class, to_string);
+        implement(&mut db, foo, class, equal);
+        db.invalidate_module(foo);
+
+        assert_eq!(class.module(&db), std);
+        assert_eq!(class.methods(&db), vec![class_method]);
+        assert!(!class.methods(&db).contains(&foo_method));
+        assert_eq!(
+            class.trait_implementation(&db, to_string).map(|i| i.module),
+            Some(bar)
+        );
+        assert!(class.trait_implementation(&db, equal).is_none());
+        assert!(class.overlapping_implementations(&db).is_empty());
+        assert_eq!(to_string.implemented_by(&db), &vec![class]);
+        assert!(equal.implemented_by(&db).is_empty());
+    }
+
+    #[test]
+    fn test_database_invalidate_module_with_caches() {
+        let mut db = Database::new();
+        new_module(&mut db, "std");
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let class = new_class(&mut db, "Person");
+        let foo_method = new_method(&mut db, foo, "a", MethodKind::Instance);
+        let bar_method = new_method(&mut db, bar, "b", MethodKind::Instance);
+        let loc = Location::default();
+
+        class.add_method(&mut db, "a".to_string(), foo_method);
+        class.add_method(&mut db, "b".to_string(), bar_method);
+        db.track_references();
+        db.track_origins();
+        db.record_call(foo_method, bar_method, false);
+        db.record_call(bar_method, foo_method, false);
+        db.record_call(bar_method, bar_method, false);
+        db.record_reference(Symbol::Class(class), Reference::new(foo, loc));
+        db.record_reference(Symbol::Class(class), Reference::new(bar, loc));
+        db.record_origin(ExpressionKey::new(foo, loc), Origin::new(bar, loc));
+        db.record_origin(ExpressionKey::new(bar, loc), Origin::new(bar, loc));
+        db.invalidate_module(foo);
+
+        let graph = db.call_graph();
+
+        assert!(graph.callees(foo_method).is_empty());
+        assert_eq!(
+            graph.callees(bar_method),
+            &[crate::callgraph::Callee::Static(bar_method)]
+        );
+        assert_eq!(
+            db.references(Symbol::Class(class)),
+            &[Reference::new(bar, loc)]
+        );
+        assert_eq!(db.origin(ExpressionKey::new(foo, loc)), None);
+        assert!(db.origin(ExpressionKey::new(bar, loc)).is_some());
+    }
+}
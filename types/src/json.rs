@@ -0,0 +1,317 @@
+//! Exporting of the type database as JSON.
+//!
+//! The JSON document produced by `Database::to_json()` describes the modules,
+//! constants, classes, traits and methods in the database, such that external
+//! tools (e.g. documentation generators and search engines) can use this data
+//! without having to link against the compiler.
+//!
+//! The schema of the document is versioned using the top-level "version" key,
+//! which must be incremented whenever the schema changes in a backwards
+//! incompatible manner. Symbols are sorted by their names, such that the
+//! output is the same for the same database.
+use crate::deprecation::Deprecation;
+use crate::docs::Docs;
+use crate::format::format_type;
+use crate::{ClassId, ConstantId, Database, MethodId, ModuleId, TraitId};
+use location::Location;
+use std::fmt::{self, Write as _};
+
+/// The version of the JSON schema.
+const VERSION: i64 = 1;
+
+enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn string<S: Into<String>>(value: S) -> Json {
+        Json::String(value.into())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(v) => write!(f, "{}", v),
+            Json::Int(v) => write!(f, "{}", v),
+            Json::String(v) => write_string(f, v),
+            Json::Array(vals) => {
+                f.write_char('[')?;
+
+                for (idx, val) in vals.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_char(',')?;
+                    }
+
+                    write!(f, "{}", val)?;
+                }
+
+                f.write_char(']')
+            }
+            Json::Object(pairs) => {
+                f.write_char('{')?;
+
+                for (idx, (key, val)) in pairs.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_char(',')?;
+                    }
+
+                    write_string(f, key)?;
+                    write!(f, ":{}", val)?;
+                }
+
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for chr in value.chars() {
+        match chr {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+fn location(location: Location) -> Json {
+    Json::Object(vec![
+        ("line_start", Json::Int(location.line_start as i64)),
+        ("line_end", Json::Int(location.line_end as i64)),
+        ("column_start", Json::Int(location.column_start as i64)),
+        ("column_end", Json::Int(location.column_end as i64)),
+    ])
+}
+
+fn documentation(docs: &Docs) -> Json {
+    Json::Object(vec![
+        ("source", Json::string(docs.source.clone())),
+        ("summary", Json::string(docs.summary.clone())),
+    ])
+}
+
+fn deprecated(deprecation: Option<&Deprecation>) -> Json {
+    match deprecation {
+        Some(dep) => Json::Object(vec![
+            ("message", Json::string(dep.message.clone())),
+            ("since", dep.since.clone().map_or(Json::Null, Json::String)),
+        ]),
+        None => Json::Null,
+    }
+}
+
+fn sorted_by_name<T: Copy>(
+    mut values: Vec<T>,
+    name: impl Fn(T) -> String,
+) -> Vec<T> {
+    values.sort_by_key(|&v| name(v));
+    values
+}
+
+fn module(db: &Database, id: ModuleId) -> Json {
+    let constants =
+        sorted_by_name(id.constants(db).clone(), |c| c.name(db).clone());
+    let classes = sorted_by_name(id.classes(db), |c| c.name(db).clone())
+        .into_iter()
+        .filter(|c| {
+            let kind = c.kind(db);
+
+            !kind.is_closure() && !kind.is_module()
+        });
+    let traits = sorted_by_name(id.traits(db), |t| t.name(db).clone());
+    let mut methods: Vec<_> = id.extern_methods(db).values().cloned().collect();
+
+    methods.append(&mut id.methods(db));
+
+    Json::Object(vec![
+        ("name", Json::string(id.name(db).to_string())),
+        ("file", Json::string(id.file(db).to_string_lossy())),
+        ("documentation", Json::string(id.documentation(db).clone())),
+        (
+            "constants",
+            Json::Array(
+                constants.into_iter().map(|c| constant(db, c)).collect(),
+            ),
+        ),
+        ("classes", Json::Array(classes.map(|c| class(db, c)).collect())),
+        (
+            "traits",
+            Json::Array(traits.into_iter().map(|t| trait_(db, t)).collect()),
+        ),
+        ("methods", methods_array(db, methods)),
+    ])
+}
+
+fn constant(db: &Database, id: ConstantId) -> Json {
+    Json::Object(vec![
+        ("name", Json::string(id.name(db).clone())),
+        ("location", location(id.location(db))),
+        ("public", Json::Bool(id.is_public(db))),
+        ("type", Json::string(format_type(db, id.value_type(db).as_owned(db)))),
+        ("documentation", documentation(id.documentation(db))),
+        ("deprecated", deprecated(id.deprecated(db))),
+    ])
+}
+
+fn class(db: &Database, id: ClassId) -> Json {
+    let mut traits: Vec<_> = id
+        .implemented_traits(db)
+        .map(|imp| format_type(db, imp.instance))
+        .collect();
+
+    traits.sort();
+
+    let fields = id
+        .fields(db)
+        .into_iter()
+        .map(|field| {
+            Json::Object(vec![
+                ("name", Json::string(field.name(db).clone())),
+                ("location", location(field.location(db))),
+                ("public", Json::Bool(field.is_public(db))),
+                ("type", Json::string(format_type(db, field.value_type(db)))),
+                ("documentation", documentation(field.documentation(db))),
+            ])
+        })
+        .collect();
+    let constructors = id
+        .constructors(db)
+        .into_iter()
+        .map(|con| {
+            let args = con
+                .arguments(db)
+                .iter()
+                .map(|&t| Json::string(format_type(db, t)))
+                .collect();
+
+            Json::Object(vec![
+                ("name", Json::string(con.name(db).clone())),
+                ("location", location(con.location(db))),
+                ("arguments", Json::Array(args)),
+                ("documentation", documentation(con.documentation(db))),
+            ])
+        })
+        .collect();
+
+    Json::Object(vec![
+        ("name", Json::string(id.name(db).clone())),
+        ("kind", Json::string(format!("{:?}", id.kind(db)).to_lowercase())),
+        ("location", location(id.location(db))),
+        ("public", Json::Bool(id.is_public(db))),
+        ("type", Json::string(format_type(db, id))),
+        ("documentation", documentation(id.documentation(db))),
+        ("deprecated", deprecated(id.deprecated(db))),
+        ("fields", Json::Array(fields)),
+        ("constructors", Json::Array(constructors)),
+        ("static_methods", methods_array(db, id.static_methods(db))),
+        ("instance_methods", methods_array(db, id.instance_methods(db))),
+        (
+            "implemented_traits",
+            Json::Array(traits.into_iter().map(Json::String).collect()),
+        ),
+    ])
+}
+
+fn trait_(db: &Database, id: TraitId) -> Json {
+    Json::Object(vec![
+        ("name", Json::string(id.name(db).clone())),
+        ("location", location(id.location(db))),
+        ("public", Json::Bool(id.is_public(db))),
+        ("type", Json::string(format_type(db, id))),
+        ("documentation", documentation(id.documentation(db))),
+        ("deprecated", deprecated(id.deprecated(db))),
+        ("required_methods", methods_array(db, id.required_methods(db))),
+        ("default_methods", methods_array(db, id.default_methods(db))),
+    ])
+}
+
+fn methods_array(db: &Database, methods: Vec<MethodId>) -> Json {
+    let methods = sorted_by_name(methods, |m| m.name(db).clone())
+        .into_iter()
+        // Generated methods and constructors are implementation details, so
+        // we don't include them.
+        .filter(|m| !m.is_generated(db) && !m.kind(db).is_constructor())
+        .map(|id| method(db, id))
+        .collect();
+
+    Json::Array(methods)
+}
+
+fn method(db: &Database, id: MethodId) -> Json {
+    Json::Object(vec![
+        ("name", Json::string(id.name(db).clone())),
+        ("location", location(id.location(db))),
+        ("public", Json::Bool(id.is_public(db))),
+        ("type", Json::string(format_type(db, id))),
+        ("documentation", documentation(id.documentation(db))),
+        ("deprecated", deprecated(id.deprecated(db))),
+    ])
+}
+
+impl Database {
+    /// Returns a JSON document describing all modules in this database.
+    pub fn to_json(&self) -> String {
+        let modules = (0..self.number_of_modules())
+            .map(|idx| module(self, ModuleId(idx as u32)))
+            .collect();
+
+        Json::Object(vec![
+            ("version", Json::Int(VERSION)),
+            ("modules", Json::Array(modules)),
+        ])
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_module};
+    use crate::Symbol;
+
+    #[test]
+    fn test_json_string() {
+        let json = Json::string("a\"b\\c\nd\u{1}");
+
+        assert_eq!(json.to_string(), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+
+    #[test]
+    fn test_database_to_json() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Foo");
+
+        class.set_module(&mut db, module);
+        class.set_documentation(&mut db, "The summary.\n\nMore".to_string());
+        module.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(class));
+
+        let json = db.to_json();
+
+        assert!(
+            json.starts_with("{\"version\":1,\"modules\":[{\"name\":\"foo\"")
+        );
+        assert!(json
+            .contains("\"classes\":[{\"name\":\"Foo\",\"kind\":\"regular\""));
+        assert!(json.contains(
+            "\"documentation\":{\"source\":\"The summary.\\n\\nMore\",\
+            \"summary\":\"The summary.\"},\"deprecated\":null"
+        ));
+    }
+}
@@ -0,0 +1,766 @@
+//! Memory layouts of stack allocated classes.
+//!
+//! The layout of a class is computed using the C layout rules: fields are
+//! stored in the order they are defined in, each field is aligned to its
+//! natural alignment, and the size of the class is rounded up to a multiple of
+//! its alignment.
+//!
+//! Enums are laid out as a tag followed by one slot per constructor argument,
+//! with each slot being large enough to store the largest value that may be
+//! stored in it. Just like the code generated by the compiler, these slots
+//! use an alignment of one byte.
+//!
+//! Unions store all their fields at offset zero, and are as large as their
+//! largest field.
+//!
+//! Extern classes can opt out of these rules using `LayoutOptions`: packed
+//! classes store their fields without any padding, while classes with an
+//! explicit alignment use the largest of that alignment and their natural
+//! alignment.
+use crate::diagnostics::Diagnostic;
+use crate::{
+    ClassId, Database, FieldId, ForeignType, Shape, TypeId, TypeRef, BOOL_ID,
+    CHAR_ID, FLOAT_ID, INT_ID, NIL_ID,
+};
+
+/// The size and alignment of a single value.
+#[derive(Copy, Clone)]
+struct Size {
+    size: u32,
+    alignment: u32,
+}
+
+impl Size {
+    fn new(size: u32, alignment: u32) -> Size {
+        Size { size, alignment }
+    }
+
+    fn int(bits: u32) -> Size {
+        Size::new(bits / 8, bits / 8)
+    }
+
+    fn float(bits: u32) -> Size {
+        match bits {
+            // x87 extended precision floats are padded to 16 bytes.
+            80 => Size::new(16, 16),
+            _ => Size::new(bits / 8, bits / 8),
+        }
+    }
+
+    /// Returns the size of a pointer for the target compiled for.
+    fn pointer(db: &Database) -> Size {
+        let size = db.pointer_size();
+
+        Size::new(size, size)
+    }
+}
+
+/// The position of a field in a class.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub field: FieldId,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// The memory layout of a class.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u32,
+    pub alignment: u32,
+
+    /// The fields of the class, in the order they are stored in.
+    pub fields: Vec<FieldLayout>,
+}
+
+/// Options that change the layout of an extern class.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayoutOptions {
+    /// If `true`, fields are stored without any padding between them.
+    pub packed: bool,
+
+    /// The minimum alignment of the class, in bytes.
+    pub align: Option<u32>,
+}
+
+/// The largest alignment that can be specified, matching the limit imposed
+/// by LLVM.
+const MAX_ALIGNMENT: u32 = 1 << 29;
+
+/// Checks the layout options of a class, returning a diagnostic if they're
+/// invalid.
+pub fn check_options(db: &Database, class: ClassId) -> Option<Diagnostic> {
+    let opts = class.layout_options(db);
+    let error = |msg: &str| {
+        Some(Diagnostic::invalid_layout(db, class, msg.to_string()))
+    };
+
+    if opts == LayoutOptions::default() {
+        return None;
+    }
+
+    if !class.kind(db).is_extern() {
+        return error("layout options are only supported for extern classes");
+    }
+
+    match opts.align {
+        Some(_) if opts.packed => {
+            error("a packed class can't specify an alignment")
+        }
+        Some(val) if !val.is_power_of_two() => {
+            error("the alignment must be a power of two")
+        }
+        Some(val) if val > MAX_ALIGNMENT => error(&format!(
+            "the alignment can't be greater than {}",
+            MAX_ALIGNMENT
+        )),
+        _ => None,
+    }
+}
+
+fn align_to(value: u32, alignment: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+struct Builder {
+    offset: u32,
+    alignment: u32,
+    packed: bool,
+    fields: Vec<FieldLayout>,
+}
+
+impl Builder {
+    fn new(options: LayoutOptions) -> Builder {
+        Builder {
+            offset: 0,
+            alignment: options.align.unwrap_or(1),
+            packed: options.packed,
+            fields: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, field: FieldId, mut size: Size) {
+        if self.packed {
+            size.alignment = 1;
+        }
+
+        let offset = align_to(self.offset, size.alignment);
+
+        self.fields.push(FieldLayout { field, offset, size: size.size });
+        self.offset = offset + size.size;
+        self.alignment = self.alignment.max(size.alignment);
+    }
+
+    /// Adds a field that overlaps with all other fields.
+    fn overlap(&mut self, field: FieldId, mut size: Size) {
+        if self.packed {
+            size.alignment = 1;
+        }
+
+        self.fields.push(FieldLayout { field, offset: 0, size: size.size });
+        self.offset = self.offset.max(size.size);
+        self.alignment = self.alignment.max(size.alignment);
+    }
+
+    fn finish(self) -> Layout {
+        Layout {
+            size: align_to(self.offset, self.alignment),
+            alignment: self.alignment,
+            fields: self.fields,
+        }
+    }
+}
+
+/// Computes the layout of a class.
+///
+/// The `stack` argument contains the classes of which we're computing the
+/// layout, used to detect (invalid) recursive classes.
+pub(crate) fn class_layout(
+    db: &Database,
+    class: ClassId,
+    stack: &mut Vec<ClassId>,
+) -> Option<Layout> {
+    if !class.is_stack_allocated(db) || stack.contains(&class) {
+        return None;
+    }
+
+    stack.push(class);
+
+    let kind = class.kind(db);
+    let result = if kind.is_enum() {
+        enum_layout(db, class, stack)
+    } else if kind.is_union() {
+        union_layout(db, class, stack)
+    } else {
+        struct_layout(db, class, stack)
+    };
+
+    stack.pop();
+    result
+}
+
+fn struct_layout(
+    db: &Database,
+    class: ClassId,
+    stack: &mut Vec<ClassId>,
+) -> Option<Layout> {
+    let mut builder = Builder::new(class.layout_options(db));
+
+    for field in class.fields(db) {
+        builder.add(field, type_size(db, class, field.value_type(db), stack)?);
+    }
+
+    Some(builder.finish())
+}
+
+fn union_layout(
+    db: &Database,
+    class: ClassId,
+    stack: &mut Vec<ClassId>,
+) -> Option<Layout> {
+    let mut builder = Builder::new(class.layout_options(db));
+
+    for field in class.fields(db) {
+        builder
+            .overlap(field, type_size(db, class, field.value_type(db), stack)?);
+    }
+
+    Some(builder.finish())
+}
+
+fn enum_layout(
+    db: &Database,
+    class: ClassId,
+    stack: &mut Vec<ClassId>,
+) -> Option<Layout> {
+    // The first field is the tag, while the remaining fields are the slots for
+    // the constructor arguments.
+    let fields = class.fields(db);
+    let (&tag, args) = fields.split_first()?;
+    let mut slots = vec![0; args.len()];
+    let mut builder = Builder::new(LayoutOptions::default());
+
+    for con in class.constructors(db) {
+        for (idx, &typ) in con.arguments(db).iter().enumerate() {
+            let size = type_size(db, class, typ, stack)?.size;
+            let slot = slots.get_mut(idx)?;
+
+            *slot = (*slot).max(size);
+        }
+    }
+
+    builder.add(tag, type_size(db, class, tag.value_type(db), stack)?);
+
+    for (&field, size) in args.iter().zip(slots) {
+        builder.add(field, Size::new(size, 1));
+    }
+
+    Some(builder.finish())
+}
+
+fn type_size(
+    db: &Database,
+    class: ClassId,
+    typ: TypeRef,
+    stack: &mut Vec<ClassId>,
+) -> Option<Size> {
+    if let TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) = typ {
+        return Some(Size::pointer(db));
+    }
+
+    match typ.type_id(db) {
+        Ok(TypeId::TypeParameter(id) | TypeId::RigidTypeParameter(id)) => {
+            // The type parameters of a class are mapped to the shapes in the
+            // same order, so we can use the index to find the shape.
+            let source = class.specialization_source(db).unwrap_or(class);
+            let idx =
+                source.type_parameters(db).iter().position(|&p| p == id)?;
+
+            shape_size(db, *class.shapes(db).get(idx)?, stack)
+        }
+        _ => value_size(db, typ, stack),
+    }
+}
+
+/// Returns the size of a value of the given type.
+///
+/// Type parameters aren't resolved, so for types that are or contain type
+/// parameters this returns `None`.
+fn value_size(
+    db: &Database,
+    typ: TypeRef,
+    stack: &mut Vec<ClassId>,
+) -> Option<Size> {
+    if let TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) = typ {
+        return Some(Size::pointer(db));
+    }
+
+    match typ.type_id(db) {
+        Ok(TypeId::Foreign(ForeignType::Int(bits, _))) => Some(Size::int(bits)),
+        Ok(TypeId::Foreign(ForeignType::Float(bits))) => {
+            Some(Size::float(bits))
+        }
+        Ok(TypeId::ClassInstance(ins)) => {
+            class_instance_size(db, ins.instance_of(), stack)
+        }
+        Ok(TypeId::TypeParameter(_) | TypeId::RigidTypeParameter(_)) => None,
+        Ok(_) => Some(Size::pointer(db)),
+        Err(_) => None,
+    }
+}
+
+fn class_instance_size(
+    db: &Database,
+    class: ClassId,
+    stack: &mut Vec<ClassId>,
+) -> Option<Size> {
+    match class.0 {
+        BOOL_ID | NIL_ID => Some(Size::new(1, 1)),
+        CHAR_ID => Some(Size::new(4, 4)),
+        INT_ID | FLOAT_ID => Some(Size::new(8, 8)),
+        _ if class.is_stack_allocated(db) => class_layout(db, class, stack)
+            .map(|l| Size::new(l.size, l.alignment)),
+        _ => Some(Size::pointer(db)),
+    }
+}
+
+fn shape_size(
+    db: &Database,
+    shape: Shape,
+    stack: &mut Vec<ClassId>,
+) -> Option<Size> {
+    match shape {
+        Shape::Int(bits, _) => Some(Size::int(bits)),
+        Shape::Constant(_) => Some(Size::int(64)),
+        Shape::Float(bits) => Some(Size::float(bits)),
+        Shape::Boolean | Shape::Nil => Some(Size::new(1, 1)),
+        Shape::Char => Some(Size::new(4, 4)),
+        Shape::Stack(ins) => class_instance_size(db, ins.instance_of(), stack),
+        Shape::Owned
+        | Shape::Mut
+        | Shape::Ref
+        | Shape::String
+        | Shape::Atomic
+        | Shape::Pointer => Some(Size::pointer(db)),
+    }
+}
+
+impl TypeRef {
+    /// Returns the size in bytes of values of this type.
+    ///
+    /// For heap allocated types this is the size of a pointer. If the size
+    /// depends on type parameters that aren't specialized, `None` is returned.
+    pub fn layout_size(self, db: &Database) -> Option<u32> {
+        value_size(db, self, &mut Vec::new()).map(|s| s.size)
+    }
+
+    /// Returns the alignment in bytes of values of this type.
+    ///
+    /// The rules for which `None` is returned are the same as those of
+    /// `TypeRef::layout_size()`.
+    pub fn layout_alignment(self, db: &Database) -> Option<u32> {
+        value_size(db, self, &mut Vec::new()).map(|s| s.alignment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Code;
+    use crate::target::{Endianness, Target};
+    use crate::test::{
+        instance, new_class, new_enum_class, new_parameter, owned, parameter,
+    };
+    use crate::{Class, ClassKind, ModuleId, Sign, Visibility};
+    use location::Location;
+
+    fn foreign_int(bits: u32) -> TypeRef {
+        TypeRef::foreign_signed_int(bits)
+    }
+
+    fn add_field(db: &mut Database, class: ClassId, name: &str, typ: TypeRef) {
+        let idx = class.number_of_fields(db);
+
+        class.new_field(
+            db,
+            name.to_string(),
+            idx,
+            typ,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+    }
+
+    fn class_set_options(
+        db: &mut Database,
+        class: ClassId,
+        packed: bool,
+        align: Option<u32>,
+    ) {
+        class.set_layout_options(db, LayoutOptions { packed, align });
+    }
+
+    fn extern_class(db: &mut Database, name: &str) -> ClassId {
+        Class::alloc(
+            db,
+            name.to_string(),
+            ClassKind::Extern,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        )
+    }
+
+    #[test]
+    fn test_heap_class_layout() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "A");
+
+        assert_eq!(class.layout(&db), None);
+    }
+
+    #[test]
+    fn test_extern_class_layout() {
+        let mut db = Database::new();
+        let inner = extern_class(&mut db, "Inner");
+        let outer = extern_class(&mut db, "Outer");
+
+        add_field(&mut db, inner, "a", foreign_int(8));
+        add_field(&mut db, inner, "b", foreign_int(32));
+        add_field(&mut db, outer, "a", foreign_int(16));
+        add_field(&mut db, outer, "b", owned(instance(inner)));
+        add_field(&mut db, outer, "c", TypeRef::pointer(instance(inner)));
+        add_field(&mut db, outer, "d", TypeRef::boolean());
+
+        let inner_fields = inner.fields(&db);
+        let outer_fields = outer.fields(&db);
+
+        assert_eq!(
+            inner.layout(&db),
+            Some(Layout {
+                size: 8,
+                alignment: 4,
+                fields: vec![
+                    FieldLayout { field: inner_fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: inner_fields[1], offset: 4, size: 4 },
+                ]
+            })
+        );
+        assert_eq!(
+            outer.layout(&db),
+            Some(Layout {
+                size: 32,
+                alignment: 8,
+                fields: vec![
+                    FieldLayout { field: outer_fields[0], offset: 0, size: 2 },
+                    FieldLayout { field: outer_fields[1], offset: 4, size: 8 },
+                    FieldLayout { field: outer_fields[2], offset: 16, size: 8 },
+                    FieldLayout { field: outer_fields[3], offset: 24, size: 1 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_extern_class_layout_with_32_bits_target() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        db.set_target(Target::new(
+            "linux".to_string(),
+            "arm".to_string(),
+            32,
+            Endianness::Little,
+        ));
+        add_field(&mut db, class, "a", foreign_int(8));
+        add_field(&mut db, class, "b", TypeRef::pointer(instance(class)));
+
+        let fields = class.fields(&db);
+
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 8,
+                alignment: 4,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: fields[1], offset: 4, size: 4 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_wide_foreign_types_layout() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        add_field(&mut db, class, "a", foreign_int(8));
+        add_field(&mut db, class, "b", foreign_int(128));
+        add_field(&mut db, class, "c", TypeRef::foreign_float(80));
+        add_field(&mut db, class, "d", TypeRef::foreign_float(128));
+
+        let fields = class.fields(&db);
+
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 64,
+                alignment: 16,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: fields[1], offset: 16, size: 16 },
+                    FieldLayout { field: fields[2], offset: 32, size: 16 },
+                    FieldLayout { field: fields[3], offset: 48, size: 16 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_char_layout() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        add_field(&mut db, class, "a", TypeRef::boolean());
+        add_field(&mut db, class, "b", TypeRef::char());
+
+        let fields = class.fields(&db);
+
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 8,
+                alignment: 4,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: fields[1], offset: 4, size: 4 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_recursive_class_layout() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        add_field(&mut db, class, "a", owned(instance(class)));
+
+        assert_eq!(class.layout(&db), None);
+    }
+
+    #[test]
+    fn test_generic_class_layout() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "A");
+        let param = class.new_type_parameter(&mut db, "T".to_string());
+
+        class.set_stack_allocated(&mut db);
+        add_field(&mut db, class, "a", TypeRef::int());
+        add_field(&mut db, class, "b", owned(TypeId::TypeParameter(param)));
+
+        // Without shapes we don't know the size of the type parameter.
+        assert_eq!(class.layout(&db), None);
+
+        class.set_shapes(&mut db, vec![Shape::Int(32, Sign::Signed)]);
+        assert_eq!(class.layout(&db).map(|l| l.size), Some(16));
+    }
+
+    #[test]
+    fn test_packed_class_layout() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        add_field(&mut db, class, "a", foreign_int(8));
+        add_field(&mut db, class, "b", foreign_int(32));
+        class.set_layout_options(
+            &mut db,
+            LayoutOptions { packed: true, align: None },
+        );
+
+        let fields = class.fields(&db);
+
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 5,
+                alignment: 1,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: fields[1], offset: 1, size: 4 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_aligned_class_layout() {
+        let mut db = Database::new();
+        let class = extern_class(&mut db, "A");
+
+        add_field(&mut db, class, "a", foreign_int(8));
+        add_field(&mut db, class, "b", foreign_int(32));
+        class.set_layout_options(
+            &mut db,
+            LayoutOptions { packed: false, align: Some(16) },
+        );
+
+        assert_eq!(
+            class.layout(&db).map(|l| (l.size, l.alignment)),
+            Some((16, 16))
+        );
+
+        // An alignment smaller than the natural alignment has no effect.
+        class.set_layout_options(
+            &mut db,
+            LayoutOptions { packed: false, align: Some(2) },
+        );
+
+        assert_eq!(
+            class.layout(&db).map(|l| (l.size, l.alignment)),
+            Some((8, 4))
+        );
+    }
+
+    #[test]
+    fn test_check_options() {
+        let mut db = Database::new();
+        let ext = extern_class(&mut db, "A");
+        let regular = new_class(&mut db, "B");
+        let check = |db: &mut Database, class, packed, align| {
+            class_set_options(db, class, packed, align);
+            check_options(db, class).map(|d| d.message)
+        };
+
+        assert_eq!(check(&mut db, ext, false, None), None);
+        assert_eq!(check(&mut db, ext, true, None), None);
+        assert_eq!(check(&mut db, ext, false, Some(8)), None);
+        assert_eq!(
+            check(&mut db, ext, true, Some(8)),
+            Some(
+                "the layout of class 'A' is invalid: a packed class can't \
+                specify an alignment"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check(&mut db, ext, false, Some(3)),
+            Some(
+                "the layout of class 'A' is invalid: the alignment must be a \
+                power of two"
+                    .to_string()
+            )
+        );
+        assert!(check(&mut db, ext, false, Some(0)).is_some());
+        assert!(check(&mut db, ext, false, Some(1 << 30)).is_some());
+        assert_eq!(
+            check_options(&db, ext).map(|d| d.code),
+            Some(Code::InvalidLayout)
+        );
+        assert_eq!(
+            check(&mut db, regular, true, None),
+            Some(
+                "the layout of class 'B' is invalid: layout options are only \
+                supported for extern classes"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_union_layout() {
+        let mut db = Database::new();
+        let class = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Union,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        add_field(&mut db, class, "a", foreign_int(8));
+        add_field(&mut db, class, "b", foreign_int(32));
+        add_field(&mut db, class, "c", TypeRef::pointer(instance(class)));
+
+        let fields = class.fields(&db);
+
+        assert!(class.is_stack_allocated(&db));
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 8,
+                alignment: 8,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 1 },
+                    FieldLayout { field: fields[1], offset: 0, size: 4 },
+                    FieldLayout { field: fields[2], offset: 0, size: 8 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_enum_layout() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Option");
+        let loc = Location::default();
+
+        class.set_stack_allocated(&mut db);
+        add_field(&mut db, class, "tag", foreign_int(16));
+        add_field(&mut db, class, "0", TypeRef::Unknown);
+        add_field(&mut db, class, "1", TypeRef::Unknown);
+        class.new_constructor(
+            &mut db,
+            "A".to_string(),
+            vec![TypeRef::int(), TypeRef::boolean()],
+            loc,
+        );
+        class.new_constructor(
+            &mut db,
+            "B".to_string(),
+            vec![TypeRef::boolean()],
+            loc,
+        );
+
+        let fields = class.fields(&db);
+
+        assert_eq!(
+            class.layout(&db),
+            Some(Layout {
+                size: 12,
+                alignment: 2,
+                fields: vec![
+                    FieldLayout { field: fields[0], offset: 0, size: 2 },
+                    FieldLayout { field: fields[1], offset: 2, size: 8 },
+                    FieldLayout { field: fields[2], offset: 10, size: 1 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_type_ref_layout_size() {
+        let mut db = Database::new();
+        let heap = new_class(&mut db, "A");
+        let ext = extern_class(&mut db, "B");
+        let param = new_parameter(&mut db, "T");
+
+        add_field(&mut db, ext, "a", foreign_int(8));
+        add_field(&mut db, ext, "b", foreign_int(32));
+
+        let ext_type = owned(instance(ext));
+
+        assert_eq!(ext_type.layout_size(&db), Some(8));
+        assert_eq!(ext_type.layout_alignment(&db), Some(4));
+        assert_eq!(foreign_int(16).layout_size(&db), Some(2));
+        assert_eq!(TypeRef::int().layout_size(&db), Some(8));
+        assert_eq!(TypeRef::boolean().layout_alignment(&db), Some(1));
+        assert_eq!(owned(instance(heap)).layout_size(&db), Some(8));
+        assert_eq!(TypeRef::pointer(instance(ext)).layout_size(&db), Some(8));
+        assert_eq!(owned(parameter(param)).layout_size(&db), None);
+        assert_eq!(owned(parameter(param)).layout_alignment(&db), None);
+    }
+}
@@ -6,20 +6,89 @@
 #[cfg(test)]
 pub mod test;
 
+pub mod abi;
+pub mod api;
+pub mod arena;
+pub mod borrows;
+pub mod cache;
+pub mod callgraph;
 pub mod check;
+pub mod coherence;
+pub mod computed;
+pub mod conditions;
+pub mod consteval;
+pub mod defaults;
+pub mod deprecation;
+pub mod diagnostics;
+pub mod docs;
+pub mod drops;
+pub mod dyn_compat;
+pub mod effects;
 pub mod either;
+pub mod escape;
 pub mod format;
+pub mod graphviz;
+pub mod inline;
+pub mod invalidate;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod layout;
+pub mod lookup;
+pub mod mangle;
+pub mod module_graph;
 pub mod module_name;
+pub mod operators;
+pub mod origin;
+pub mod ownership;
+pub mod packages;
+pub mod parallel;
+pub mod patterns;
+pub mod query;
+pub mod references;
+pub mod rename;
 pub mod resolve;
+pub mod self_type;
+pub mod sendable;
+pub mod snapshot;
 pub mod specialize;
-
-use crate::module_name::ModuleName;
+pub mod statistics;
+pub mod suggest;
+pub mod tags;
+pub mod target;
+pub mod trait_constants;
+pub mod validate;
+pub mod variance;
+pub mod versions;
+pub mod weak;
+
+use crate::arena::{Arena, Index};
+use crate::callgraph::CallGraph;
+use crate::coherence::Exclusions;
+use crate::conditions::Conditions;
+use crate::consteval::Value;
+use crate::defaults::FieldDefault;
+use crate::deprecation::Deprecation;
+use crate::diagnostics::Diagnostic;
+use crate::docs::Docs;
+use crate::drops::DropOrder;
+use crate::effects::Effects;
+use crate::escape::EscapeTable;
+use crate::inline::InlinePolicy;
+use crate::layout::{Layout, LayoutOptions};
+use crate::module_name::{ModuleName, SEPARATOR};
+use crate::origin::Origins;
+use crate::packages::{Package, PackageId};
+use crate::references::ReferenceIndex;
 use crate::resolve::TypeResolver;
+use crate::specialize::{uses_type_parameter, Niche, SpecializationLimits};
+use crate::target::Target;
 use indexmap::IndexMap;
 use location::Location;
 use std::cell::Cell;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
 
 // The IDs of these built-in types must match the order of the fields in the
 // State type.
@@ -40,8 +109,12 @@ const TUPLE7_ID: u32 = 12;
 const TUPLE8_ID: u32 = 13;
 const ARRAY_ID: u32 = 14;
 const CHECKED_INT_RESULT_ID: u32 = 15;
+const INCLUSIVE_RANGE_ID: u32 = 16;
+const EXCLUSIVE_RANGE_ID: u32 = 17;
+const CHECKED_INT128_RESULT_ID: u32 = 18;
+pub const CHAR_ID: u32 = 19;
 
-pub const FIRST_USER_CLASS_ID: u32 = CHECKED_INT_RESULT_ID + 1;
+pub const FIRST_USER_CLASS_ID: u32 = CHAR_ID + 1;
 
 /// The default module ID to assign to builtin types.
 ///
@@ -54,6 +127,7 @@ const STRING_NAME: &str = "String";
 const ARRAY_NAME: &str = "Array";
 const BOOL_NAME: &str = "Bool";
 const NIL_NAME: &str = "Nil";
+const CHAR_NAME: &str = "Char";
 const BYTE_ARRAY_NAME: &str = "ByteArray";
 const TUPLE1_NAME: &str = "Tuple1";
 const TUPLE2_NAME: &str = "Tuple2";
@@ -64,6 +138,9 @@ const TUPLE6_NAME: &str = "Tuple6";
 const TUPLE7_NAME: &str = "Tuple7";
 const TUPLE8_NAME: &str = "Tuple8";
 const CHECKED_INT_RESULT_NAME: &str = "CheckedIntResult";
+const CHECKED_INT128_RESULT_NAME: &str = "CheckedInt128Result";
+const INCLUSIVE_RANGE_NAME: &str = "InclusiveRange";
+const EXCLUSIVE_RANGE_NAME: &str = "ExclusiveRange";
 
 pub const STRING_MODULE: &str = "std.string";
 pub const TO_STRING_TRAIT: &str = "ToString";
@@ -81,6 +158,17 @@ pub const OPTION_MODULE: &str = "std.option";
 pub const OPTION_CLASS: &str = "Option";
 pub const RESULT_MODULE: &str = "std.result";
 pub const RESULT_CLASS: &str = "Result";
+pub const MAP_MODULE: &str = "std.map";
+pub const MAP_CLASS: &str = "Map";
+pub const SET_MODULE: &str = "std.set";
+pub const SET_CLASS: &str = "Set";
+pub const SYNC_MODULE: &str = "std.sync";
+pub const FUTURE_CLASS: &str = "Future";
+pub const PROMISE_CLASS: &str = "Promise";
+pub const WEAK_MODULE: &str = "std.weak";
+pub const WEAK_CLASS: &str = "Weak";
+pub const SENDABLE_MODULE: &str = "std.process";
+pub const SENDABLE_TRAIT: &str = "Sendable";
 pub const OPTION_SOME: &str = "Some";
 pub const OPTION_NONE: &str = "None";
 pub const RESULT_OK: &str = "Ok";
@@ -89,6 +177,9 @@ pub const ARRAY_WITH_CAPACITY: &str = "with_capacity";
 pub const ARRAY_PUSH: &str = "push";
 pub const ARRAY_INTERNAL_NAME: &str = "$Array";
 
+/// The name of the type that refers to the type implementing a trait.
+pub const SELF_TYPE: &str = "Self";
+
 /// The name of the pseudo field used to deference a pointer.
 pub const DEREF_POINTER_FIELD: &str = "0";
 
@@ -97,7 +188,11 @@ pub const ENUM_TAG_INDEX: usize = 0;
 
 /// The maximum number of enum constructors that can be defined in a single
 /// class.
-pub const CONSTRUCTORS_LIMIT: usize = u16::MAX as usize;
+///
+/// The type used for storing tags is derived from the tag values (see
+/// `ClassId::enum_tag_type()`), so the only limit is the largest supported tag
+/// type.
+pub const CONSTRUCTORS_LIMIT: usize = u32::MAX as usize;
 
 /// The maximum number of fields a class can define.
 pub const FIELDS_LIMIT: usize = u8::MAX as usize;
@@ -152,11 +247,26 @@ pub struct TypePlaceholder {
     /// type-checking functions expect/depend on an immutable database, and
     /// can't work with a mutable one (e.g. due to having to borrow multiple
     /// fields).
+    ///
+    /// Placeholders assigned to each other form a set, represented as a tree
+    /// using `parent`. Only the value of the root of such a tree is used.
     value: Cell<TypeRef>,
 
+    /// The ID of the parent placeholder, or the ID of this placeholder if it's
+    /// the root of its set.
+    parent: Cell<u32>,
+
+    /// The upper bound of the height of the tree of which this placeholder is
+    /// the root, used to keep the tree shallow when merging sets.
+    rank: Cell<u8>,
+
     /// The type parameter requirement that must be met before a type is
     /// compatible with this placeholder.
-    required: Option<TypeParameterId>,
+    ///
+    /// This is wrapped in a Cell such that placeholders reserved using
+    /// `Database::reserve_placeholders()` can be initialized without a
+    /// mutable borrow to the Database.
+    required: Cell<Option<TypeParameterId>>,
 }
 
 impl TypePlaceholder {
@@ -167,8 +277,12 @@ impl TypePlaceholder {
         assert!(db.type_placeholders.len() < u32::MAX as usize);
 
         let id = db.type_placeholders.len() as u32;
-        let typ =
-            TypePlaceholder { value: Cell::new(TypeRef::Unknown), required };
+        let typ = TypePlaceholder {
+            value: Cell::new(TypeRef::Unknown),
+            parent: Cell::new(id),
+            rank: Cell::new(0),
+            required: Cell::new(required),
+        };
 
         db.type_placeholders.push(typ);
         TypePlaceholderId { id, ownership: Ownership::Any }
@@ -240,13 +354,12 @@ impl TypePlaceholderId {
     }
 
     pub fn value(self, db: &Database) -> Option<TypeRef> {
-        // Chains of type variables are very rare in practise, but they _can_
-        // occur and thus must be handled. Because they are so rare and unlikely
-        // to be more than 2-3 levels deep, we just use recursion here instead
-        // of a loop.
-        let typ = self.get(db).value.get();
+        let typ = db.type_placeholders[self.root(db) as usize].value.get();
 
         match typ {
+            // Placeholders with an ownership aren't merged into the same set,
+            // as they may resolve to a different type, so we have to resolve
+            // these separately.
             TypeRef::Placeholder(id) => id.value(db),
             TypeRef::Unknown => None,
             _ => {
@@ -267,7 +380,7 @@ impl TypePlaceholderId {
     }
 
     fn required(self, db: &Database) -> Option<TypeParameterId> {
-        self.get(db).required
+        self.get(db).required.get()
     }
 
     /// Assigns the placeholder the given value, relying on interior mutability.
@@ -275,16 +388,178 @@ impl TypePlaceholderId {
     /// This method exists so we can assign a placeholder a type during type
     /// checking. We can't use a `&mut Database` there as doing so results in
     /// borrowing errors.
-    pub(crate) fn assign_internal(self, db: &Database, value: TypeRef) {
-        // Assigning placeholders to themselves isn't useful and results in
-        // resolve() getting stuck.
-        if let TypeRef::Placeholder(id) = value {
-            if id.id == self.id {
-                return;
+    ///
+    /// If the placeholder occurs in the value (e.g. when assigning `Array[V]`
+    /// to `V`), the value isn't assigned and `false` is returned.
+    pub(crate) fn assign_internal(self, db: &Database, value: TypeRef) -> bool {
+        match value {
+            TypeRef::Placeholder(id) if !id.has_ownership() => {
+                db.placeholder_assignments
+                    .fetch_add(1, AtomicOrdering::Relaxed);
+                self.union(db, id);
+                true
+            }
+            // Assigning placeholders to (placeholders in) their own set isn't
+            // useful and results in value() getting stuck.
+            TypeRef::Placeholder(id) if id.root(db) == self.root(db) => true,
+            _ if self.occurs_in(db, value) => false,
+            _ => {
+                db.placeholder_assignments
+                    .fetch_add(1, AtomicOrdering::Relaxed);
+                db.type_placeholders[self.root(db) as usize].value.set(value);
+                true
+            }
+        }
+    }
+
+    /// Returns the value assigned to the set of this placeholder, without
+    /// resolving it.
+    pub(crate) fn binding(self, db: &Database) -> TypeRef {
+        db.type_placeholders[self.root(db) as usize].value.get()
+    }
+
+    /// Restores the value of the set of this placeholder to a value obtained
+    /// using `binding()`.
+    ///
+    /// Unlike `assign_internal`, this doesn't merge sets when given a
+    /// placeholder, as the value is assigned as-is.
+    pub(crate) fn restore(self, db: &Database, value: TypeRef) {
+        db.placeholder_assignments.fetch_add(1, AtomicOrdering::Relaxed);
+        db.type_placeholders[self.root(db) as usize].value.set(value);
+    }
+
+    /// Returns `true` if this placeholder (or a placeholder in the same set)
+    /// occurs in the given type.
+    ///
+    /// Assigning a placeholder a type it occurs in would produce a type that
+    /// expands infinitely, so such assignments must be rejected.
+    pub(crate) fn occurs_in(self, db: &Database, typ: TypeRef) -> bool {
+        let root = self.root(db);
+        let mut stack = vec![typ];
+        let mut visited = HashSet::new();
+
+        while let Some(typ) = stack.pop() {
+            let id = match typ {
+                TypeRef::Owned(id)
+                | TypeRef::Uni(id)
+                | TypeRef::Ref(id)
+                | TypeRef::Mut(id)
+                | TypeRef::UniRef(id)
+                | TypeRef::UniMut(id)
+                | TypeRef::Any(id)
+                | TypeRef::Pointer(id)
+                | TypeRef::NonNullPointer(id) => id,
+                TypeRef::Placeholder(id) => {
+                    let id_root = id.root(db);
+
+                    if id_root == root {
+                        return true;
+                    }
+
+                    if visited.insert(id_root) {
+                        stack.push(
+                            db.type_placeholders[id_root as usize].value.get(),
+                        );
+                    }
+
+                    continue;
+                }
+                TypeRef::Union(id) => {
+                    stack.extend(id.members(db).iter().cloned());
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let args = match id {
+                TypeId::ClassInstance(ins)
+                    if ins.instance_of().is_generic(db) =>
+                {
+                    ins.type_arguments(db)
+                }
+                TypeId::TraitInstance(ins)
+                    if ins.instance_of().is_generic(db) =>
+                {
+                    ins.type_arguments(db)
+                }
+                TypeId::TypeAlias(ins) => ins.type_arguments(db),
+                TypeId::Tuple(id) => {
+                    stack.extend(id.elements(db).iter().cloned());
+                    None
+                }
+                TypeId::Closure(id) => {
+                    stack.extend(
+                        id.arguments(db).into_iter().map(|a| a.value_type),
+                    );
+                    stack.push(id.return_type(db));
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(args) = args {
+                stack.extend(args.pairs().into_iter().map(|(_, v)| v));
+            }
+        }
+
+        false
+    }
+
+    /// Returns the ID of the root of the set this placeholder belongs to.
+    ///
+    /// Placeholders visited along the way are updated to point directly to
+    /// the root, such that future lookups don't need to visit them again.
+    fn root(self, db: &Database) -> u32 {
+        let mut root = self.id;
+
+        loop {
+            let parent = db.type_placeholders[root as usize].parent.get();
+
+            if parent == root {
+                break;
             }
+
+            root = parent;
+        }
+
+        let mut current = self.id;
+
+        while current != root {
+            let node = &db.type_placeholders[current as usize];
+
+            current = node.parent.get();
+            node.parent.set(root);
+        }
+
+        root
+    }
+
+    /// Merges the set of this placeholder with the set of `other`.
+    ///
+    /// Just as assigning a placeholder a new value overwrites its existing
+    /// value, the merged set uses the value of the set of `other`.
+    fn union(self, db: &Database, other: TypePlaceholderId) {
+        let lhs = self.root(db);
+        let rhs = other.root(db);
+
+        if lhs == rhs {
+            return;
         }
 
-        self.get(db).value.set(value);
+        let nodes = &db.type_placeholders;
+        let lhs_rank = nodes[lhs as usize].rank.get();
+        let rhs_rank = nodes[rhs as usize].rank.get();
+        let (root, child) = match lhs_rank.cmp(&rhs_rank) {
+            Ordering::Less => (rhs, lhs),
+            Ordering::Greater => (lhs, rhs),
+            Ordering::Equal => {
+                nodes[lhs as usize].rank.set(lhs_rank.saturating_add(1));
+                (lhs, rhs)
+            }
+        };
+
+        nodes[root as usize].value.set(nodes[rhs as usize].value.get());
+        nodes[child as usize].parent.set(root);
     }
 
     /// Assigns the placeholder the given value.
@@ -292,8 +567,8 @@ impl TypePlaceholderId {
     /// This method differs from `assign_internal` in that it requires a
     /// `&mut Database`. This is meant to be used outside of this crate and
     /// ensures one can't concurrently modify a `TypePlaceholder`.
-    pub fn assign(self, db: &mut Database, value: TypeRef) {
-        self.assign_internal(db, value);
+    pub fn assign(self, db: &mut Database, value: TypeRef) -> bool {
+        self.assign_internal(db, value)
     }
 
     pub(crate) fn has_ownership(self) -> bool {
@@ -313,8 +588,59 @@ impl TypePlaceholderId {
 // To make this possible and safe, only code in this crate can assign types
 // through a `&Database`, while code in other crates must go through
 // `TypePlaceholder::assign()`, which requires a `&mut Database`.
+//
+// When type checking in parallel, each thread allocates placeholders from its
+// own `PlaceholderReservation`, and the types produced by one thread aren't
+// shared with other threads until checking finishes. This ensures that a
+// placeholder is only ever modified by a single thread. See the `parallel`
+// module for more details.
 unsafe impl Sync for TypePlaceholder {}
 
+/// The variance of a type parameter.
+///
+/// The variance determines if and how a generic type `A[X]` may be assigned to
+/// `A[Y]` when `X` and `Y` are different types.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Variance {
+    /// The type arguments must be compatible without any form of subtyping.
+    Invariant,
+
+    /// `A[X]` is compatible with `A[Y]` if `X` is compatible with `Y`.
+    Covariant,
+
+    /// `A[X]` is compatible with `A[Y]` if `Y` is compatible with `X`.
+    Contravariant,
+}
+
+impl Variance {
+    /// Returns the variance of a position nested in a position with the
+    /// current variance.
+    pub fn compose(self, other: Variance) -> Variance {
+        match (self, other) {
+            (Variance::Invariant, _) | (_, Variance::Invariant) => {
+                Variance::Invariant
+            }
+            (Variance::Covariant, other) => other,
+            (Variance::Contravariant, Variance::Covariant) => {
+                Variance::Contravariant
+            }
+            (Variance::Contravariant, Variance::Contravariant) => {
+                Variance::Covariant
+            }
+        }
+    }
+
+    /// Returns the variance that satisfies both the current and the given
+    /// variance.
+    pub fn join(self, other: Variance) -> Variance {
+        if self == other {
+            self
+        } else {
+            Variance::Invariant
+        }
+    }
+}
+
 /// A type parameter for a method or class.
 #[derive(Clone)]
 pub struct TypeParameter {
@@ -331,9 +657,19 @@ pub struct TypeParameter {
     /// If types assigned to this parameter must be allocated on the stack.
     stack: bool,
 
+    /// If the parameter is assigned constant integers (e.g. `let N: Int`)
+    /// instead of types.
+    constant: bool,
+
     /// The ID of the original type parameter in case the current one is a
     /// parameter introduced through additional type bounds.
     original: Option<TypeParameterId>,
+
+    /// The variance of the type parameter.
+    variance: Variance,
+
+    /// If the variance is specified explicitly, instead of being inferred.
+    explicit_variance: bool,
 }
 
 impl TypeParameter {
@@ -354,7 +690,10 @@ impl TypeParameter {
             requirements: Vec::new(),
             mutable: false,
             stack: false,
+            constant: false,
             original: None,
+            variance: Variance::Invariant,
+            explicit_variance: false,
         }
     }
 }
@@ -380,15 +719,12 @@ impl TypeParameterId {
     }
 
     pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
-        let typ = self.get(db);
-
-        for &req in &typ.requirements {
-            if let Some(m) = req.method(db, name) {
-                return Some(m);
-            }
-        }
-
-        None
+        lookup::requirements_method(
+            db,
+            &self.get(db).requirements,
+            name,
+            &mut None,
+        )
     }
 
     pub fn set_original(self, db: &mut Database, parameter: TypeParameterId) {
@@ -415,6 +751,41 @@ impl TypeParameterId {
         self.get(db).stack
     }
 
+    pub fn set_constant(self, db: &mut Database) {
+        self.get_mut(db).constant = true;
+    }
+
+    pub fn is_constant(self, db: &Database) -> bool {
+        self.get(db).constant
+    }
+
+    pub fn variance(self, db: &Database) -> Variance {
+        self.get(db).variance
+    }
+
+    /// Sets the variance of the type parameter as specified in the source
+    /// code.
+    ///
+    /// Such variances aren't overwritten by the variance inference pass.
+    pub fn set_variance(self, db: &mut Database, variance: Variance) {
+        let param = self.get_mut(db);
+
+        param.variance = variance;
+        param.explicit_variance = true;
+    }
+
+    pub fn has_explicit_variance(self, db: &Database) -> bool {
+        self.get(db).explicit_variance
+    }
+
+    pub(crate) fn set_inferred_variance(
+        self,
+        db: &mut Database,
+        variance: Variance,
+    ) {
+        self.get_mut(db).variance = variance;
+    }
+
     pub fn as_immutable(self, db: &mut Database) -> TypeParameterId {
         let mut copy = self.get(db).clone();
 
@@ -425,7 +796,7 @@ impl TypeParameterId {
     pub fn clone_for_bound(self, db: &mut Database) -> TypeParameterId {
         let mut copy = self.get(db).clone();
 
-        copy.original = Some(self);
+        copy.original = copy.original.or(Some(self));
         TypeParameter::add(db, copy)
     }
 
@@ -446,12 +817,65 @@ impl TypeParameterId {
     }
 }
 
+/// The number of type arguments stored inline, before they're moved to the
+/// heap.
+///
+/// Most generic types only have a few type parameters, so this allows us to
+/// avoid heap allocations for the majority of type arguments.
+const INLINE_TYPE_ARGUMENTS: usize = 3;
+
+/// The storage of type arguments.
+#[derive(Clone, Debug)]
+enum TypeArgumentsStorage {
+    Inline(u8, [(TypeParameterId, TypeRef); INLINE_TYPE_ARGUMENTS]),
+    Heap(Vec<(TypeParameterId, TypeRef)>),
+}
+
+impl TypeArgumentsStorage {
+    fn as_slice(&self) -> &[(TypeParameterId, TypeRef)] {
+        match self {
+            TypeArgumentsStorage::Inline(len, pairs) => &pairs[..*len as usize],
+            TypeArgumentsStorage::Heap(pairs) => pairs,
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [(TypeParameterId, TypeRef)] {
+        match self {
+            TypeArgumentsStorage::Inline(len, pairs) => {
+                &mut pairs[..*len as usize]
+            }
+            TypeArgumentsStorage::Heap(pairs) => pairs,
+        }
+    }
+
+    fn push(&mut self, pair: (TypeParameterId, TypeRef)) {
+        match self {
+            TypeArgumentsStorage::Inline(len, pairs)
+                if (*len as usize) < INLINE_TYPE_ARGUMENTS =>
+            {
+                pairs[*len as usize] = pair;
+                *len += 1;
+            }
+            TypeArgumentsStorage::Inline(_, pairs) => {
+                let mut heap = Vec::with_capacity(INLINE_TYPE_ARGUMENTS * 2);
+
+                heap.extend_from_slice(pairs);
+                heap.push(pair);
+                *self = TypeArgumentsStorage::Heap(heap);
+            }
+            TypeArgumentsStorage::Heap(pairs) => pairs.push(pair),
+        }
+    }
+}
+
 /// Type parameters and the types assigned to them.
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// Parameters can be assigned in any order, and some may not be assigned at
+/// all. Since most types only have a few type parameters, the pairs are stored
+/// inline and looked up using a linear search, instead of using a HashMap.
+#[derive(Clone, Debug)]
 pub struct TypeArguments {
-    /// We use a HashMap as parameters can be assigned in any order, and some
-    /// may not be assigned at all.
-    mapping: HashMap<TypeParameterId, TypeRef>,
+    storage: TypeArgumentsStorage,
 }
 
 impl TypeArguments {
@@ -472,15 +896,43 @@ impl TypeArguments {
     }
 
     pub fn new() -> Self {
-        Self { mapping: HashMap::default() }
+        Self {
+            storage: TypeArgumentsStorage::Inline(
+                0,
+                [(TypeParameterId(0), TypeRef::Unknown); INLINE_TYPE_ARGUMENTS],
+            ),
+        }
     }
 
     pub fn assign(&mut self, parameter: TypeParameterId, value: TypeRef) {
-        self.mapping.insert(parameter, value);
+        if let Some(pair) = self
+            .storage
+            .as_mut_slice()
+            .iter_mut()
+            .find(|(param, _)| *param == parameter)
+        {
+            pair.1 = value;
+        } else {
+            self.storage.push((parameter, value));
+        }
+    }
+
+    /// Assigns multiple type parameters at once.
+    pub fn extend(
+        &mut self,
+        pairs: impl IntoIterator<Item = (TypeParameterId, TypeRef)>,
+    ) {
+        for (param, value) in pairs {
+            self.assign(param, value);
+        }
     }
 
     pub fn get(&self, parameter: TypeParameterId) -> Option<TypeRef> {
-        self.mapping.get(&parameter).cloned()
+        self.storage
+            .as_slice()
+            .iter()
+            .find(|(param, _)| *param == parameter)
+            .map(|&(_, value)| value)
     }
 
     pub fn get_recursive(
@@ -508,22 +960,26 @@ impl TypeArguments {
     }
 
     pub fn pairs(&self) -> Vec<(TypeParameterId, TypeRef)> {
-        self.mapping.iter().map(|(&a, &b)| (a, b)).collect()
+        self.storage.as_slice().to_vec()
     }
 
     pub fn keys(&self) -> impl Iterator<Item = &TypeParameterId> {
-        self.mapping.keys()
+        self.storage.as_slice().iter().map(|(param, _)| param)
     }
 
     pub fn copy_into(&self, other: &mut Self) {
-        for (&key, &value) in &self.mapping {
-            other.assign(key, value);
+        if other.is_empty() {
+            other.storage = self.storage.clone();
+        } else {
+            other.extend(self.storage.as_slice().iter().cloned());
         }
     }
 
     pub fn move_into(self, other: &mut Self) {
-        for (key, value) in self.mapping {
-            other.assign(key, value);
+        if other.is_empty() {
+            other.storage = self.storage;
+        } else {
+            other.extend(self.storage.as_slice().iter().cloned());
         }
     }
 
@@ -539,21 +995,51 @@ impl TypeArguments {
         }
     }
 
+    pub fn values(&self) -> impl Iterator<Item = TypeRef> + '_ {
+        self.storage.as_slice().iter().map(|&(_, value)| value)
+    }
+
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut TypeRef> {
-        self.mapping.values_mut()
+        self.storage.as_mut_slice().iter_mut().map(|(_, value)| value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.as_slice().len()
+    }
+
+    /// Returns the number of bytes allocated on the heap for the pairs.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        match &self.storage {
+            TypeArgumentsStorage::Inline(..) => 0,
+            TypeArgumentsStorage::Heap(pairs) => {
+                pairs.capacity()
+                    * std::mem::size_of::<(TypeParameterId, TypeRef)>()
+            }
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.mapping.is_empty()
+        self.len() == 0
     }
 
-    pub fn iter(
-        &self,
-    ) -> std::collections::hash_map::Iter<TypeParameterId, TypeRef> {
-        self.mapping.iter()
+    pub fn iter(&self) -> impl Iterator<Item = (&TypeParameterId, &TypeRef)> {
+        self.storage.as_slice().iter().map(|(param, value)| (param, value))
+    }
+}
+
+impl PartialEq for TypeArguments {
+    fn eq(&self, other: &Self) -> bool {
+        // The order in which parameters are assigned doesn't matter, so we
+        // can't just compare the underlying storage.
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(&param, &value)| other.get(param) == Some(value))
     }
 }
 
+impl Eq for TypeArguments {}
+
 /// A type that maps/interns type arguments, such that structurually different
 /// but semantically equivalent type arguments all map to the same type arguments
 /// ID.
@@ -569,14 +1055,14 @@ pub struct InternedTypeArguments {
     ///
     /// This cache is used to avoid the more expensive key generation process
     /// when comparing the exact same type many times.
-    cache: HashMap<ClassInstance, u32>,
+    cache: HashMap<ClassInstance, Index>,
 
     /// A mapping of the flattened type IDs from a class instance to the common
     /// type arguments ID.
     ///
     /// For ClassInstance and TraitInstance types, the TypeId is stripped of its
     /// TypeArguments ID such that it's consistent when hashed.
-    mapping: HashMap<Vec<TypeId>, u32>,
+    mapping: HashMap<Vec<TypeId>, Index>,
 }
 
 impl InternedTypeArguments {
@@ -584,7 +1070,7 @@ impl InternedTypeArguments {
         InternedTypeArguments { cache: HashMap::new(), mapping: HashMap::new() }
     }
 
-    pub fn intern(&mut self, db: &Database, instance: ClassInstance) -> u32 {
+    pub fn intern(&mut self, db: &Database, instance: ClassInstance) -> Index {
         // The cache is used such that if we use the exact same type N times, we
         // only perform the more expensive type walking once.
         if let Some(&id) = self.cache.get(&instance) {
@@ -630,14 +1116,42 @@ pub struct Trait {
     name: String,
     module: ModuleId,
     location: Location,
-    documentation: String,
+    documentation: Docs,
+    deprecated: Option<Deprecation>,
     implemented_by: Vec<ClassId>,
+
+    /// The implementations of this trait for type parameters, applying to
+    /// every type that meets the requirements of the type parameter.
+    blanket_implementations: Vec<BlanketImplementation>,
     visibility: Visibility,
     type_parameters: IndexMap<String, TypeParameterId>,
     required_traits: Vec<TraitInstance>,
     default_methods: IndexMap<String, MethodId>,
     required_methods: IndexMap<String, MethodId>,
 
+    /// The constants of this trait that have a default value.
+    default_constants: IndexMap<String, ConstantId>,
+
+    /// The constants an implementation of this trait must define.
+    required_constants: IndexMap<String, ConstantId>,
+
+    /// The associated types of this trait, such as `type Item`.
+    ///
+    /// Associated types are type parameters that are assigned a value by the
+    /// implementation of a trait, rather than by the user of a trait instance.
+    /// Their bounds are stored as the requirements of the type parameter.
+    ///
+    /// This includes the associated types inherited from required traits,
+    /// unless this trait declares an associated type with the same name.
+    associated_types: IndexMap<String, TypeParameterId>,
+
+    /// The default values of the associated types, used when an
+    /// implementation doesn't assign a value.
+    associated_type_defaults: TypeArguments,
+
+    /// The type parameter that `Self` refers to, if it's defined.
+    self_type: Option<TypeParameterId>,
+
     /// The type arguments inherited from any of the required traits.
     ///
     /// Traits may require generic traits, which in turn can require other
@@ -700,18 +1214,27 @@ impl Trait {
             visibility,
             module,
             location,
-            documentation: String::new(),
+            documentation: Docs::default(),
+            deprecated: None,
             implemented_by: Vec::new(),
+            blanket_implementations: Vec::new(),
             type_parameters: IndexMap::new(),
             required_traits: Vec::new(),
             default_methods: IndexMap::new(),
             required_methods: IndexMap::new(),
+            default_constants: IndexMap::new(),
+            required_constants: IndexMap::new(),
+            associated_types: IndexMap::new(),
+            associated_type_defaults: TypeArguments::new(),
+            self_type: None,
             inherited_type_arguments: TypeArguments::new(),
         }
     }
 
     fn is_generic(&self) -> bool {
-        !self.type_parameters.is_empty()
+        // Associated types are stored in the type arguments of a trait
+        // instance, so traits with associated types are generic too.
+        !self.type_parameters.is_empty() || !self.associated_types.is_empty()
     }
 }
 
@@ -751,8 +1274,24 @@ impl TraitId {
             requirement.type_arguments(db).unwrap().copy_into(&mut base);
         }
 
+        // Associated types of the required trait are either assigned the
+        // associated type of the same name declared by this trait, or are
+        // inherited as-is.
+        let assoc = requirement.instance_of.get(db).associated_types.clone();
         let self_typ = self.get_mut(db);
 
+        for (name, req) in assoc {
+            match self_typ.associated_types.get(&name) {
+                Some(&param) if param != req => {
+                    base.assign(req, TypeRef::Any(TypeId::TypeParameter(param)))
+                }
+                Some(_) => {}
+                None => {
+                    self_typ.associated_types.insert(name, req);
+                }
+            }
+        }
+
         base.move_into(&mut self_typ.inherited_type_arguments);
         self_typ.required_traits.push(requirement);
     }
@@ -761,29 +1300,58 @@ impl TraitId {
         &self.get(db).implemented_by
     }
 
-    pub fn method_exists(self, db: &Database, name: &str) -> bool {
-        self.get(db).default_methods.contains_key(name)
-            || self.get(db).required_methods.contains_key(name)
-    }
-
-    pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
+    /// Returns the only class that implements this trait, if there is exactly
+    /// one such class and calls through the trait can be replaced with calls
+    /// to the class' methods.
+    ///
+    /// Generic classes and value types aren't returned, as calls on those
+    /// require extra work (i.e. specialization or boxing) when used as a trait
+    /// object. The same applies to traits with blanket implementations, as
+    /// these may apply to types other than the implementing class.
+    pub fn sole_implementer(self, db: &Database) -> Option<ClassId> {
         let typ = self.get(db);
 
-        if let Some(&id) = typ
-            .default_methods
-            .get(name)
-            .or_else(|| typ.required_methods.get(name))
-        {
-            return Some(id);
+        if !typ.blanket_implementations.is_empty() {
+            return None;
         }
 
-        for &req in &typ.required_traits {
-            if let Some(id) = req.method(db, name) {
-                return Some(id);
+        match typ.implemented_by.as_slice() {
+            &[class]
+                if !class.is_generic(db)
+                    && class.is_heap_allocated(db)
+                    && !class.is_value_type(db) =>
+            {
+                Some(class)
             }
+            _ => None,
         }
+    }
 
-        None
+    pub fn add_blanket_implementation(
+        self,
+        db: &mut Database,
+        parameter: TypeParameterId,
+        implementation: TraitImplementation,
+    ) {
+        self.get_mut(db)
+            .blanket_implementations
+            .push(BlanketImplementation { parameter, implementation });
+    }
+
+    pub fn blanket_implementations(
+        self,
+        db: &Database,
+    ) -> &Vec<BlanketImplementation> {
+        &self.get(db).blanket_implementations
+    }
+
+    pub fn method_exists(self, db: &Database, name: &str) -> bool {
+        self.get(db).default_methods.contains_key(name)
+            || self.get(db).required_methods.contains_key(name)
+    }
+
+    pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
+        lookup::trait_method(db, self, name, &mut None)
     }
 
     pub fn add_default_method(
@@ -804,19 +1372,82 @@ impl TraitId {
         self.get_mut(db).required_methods.insert(name, method);
     }
 
-    pub fn is_generic(self, db: &Database) -> bool {
-        self.get(db).is_generic()
+    /// Defines a new static method for this trait.
+    ///
+    /// If `required` is `true` the method is a required method that each
+    /// implementation must define, otherwise it's a default method.
+    pub fn new_static_method(
+        self,
+        db: &mut Database,
+        name: String,
+        visibility: Visibility,
+        location: Location,
+        required: bool,
+    ) -> MethodId {
+        let module = self.module(db);
+        let method = Method::alloc(
+            db,
+            module,
+            location,
+            name.clone(),
+            visibility,
+            MethodKind::Static,
+        );
+
+        method.set_receiver(db, TypeRef::Owned(TypeId::Trait(self)));
+
+        if required {
+            self.add_required_method(db, name, method);
+        } else {
+            self.add_default_method(db, name, method);
+        }
+
+        method
     }
 
-    pub fn number_of_type_parameters(&self, db: &Database) -> usize {
-        self.get(db).type_parameters.len()
+    /// Returns the static methods of this trait, excluding those of its
+    /// required traits.
+    pub fn static_methods(self, db: &Database) -> Vec<MethodId> {
+        let typ = self.get(db);
+
+        typ.required_methods
+            .values()
+            .chain(typ.default_methods.values())
+            .filter(|m| m.is_static(db))
+            .cloned()
+            .collect()
     }
 
-    pub fn type_parameter_exists(&self, db: &Database, name: &str) -> bool {
-        self.get(db).type_parameters.contains_key(name)
+    /// Returns `true` if the method is a required method of this trait or any
+    /// of its required traits.
+    pub fn is_required_method(self, db: &Database, method: MethodId) -> bool {
+        let typ = self.get(db);
+
+        typ.required_methods.values().any(|&m| m == method)
+            || typ
+                .required_traits
+                .iter()
+                .any(|r| r.instance_of.is_required_method(db, method))
     }
 
-    pub fn new_type_parameter(
+    /// Returns `true` if values can be cast to this trait.
+    pub fn is_dyn_compatible(self, db: &Database) -> bool {
+        dyn_compat::incompatibilities(db, self).is_empty()
+    }
+
+    pub fn is_generic(self, db: &Database) -> bool {
+        self.get(db).is_generic()
+    }
+
+    pub fn number_of_type_parameters(&self, db: &Database) -> usize {
+        self.get(db).type_parameters.len()
+    }
+
+    pub fn type_parameter_exists(&self, db: &Database, name: &str) -> bool {
+        self.get(db).type_parameters.contains_key(name)
+    }
+
+    pub fn new_type_parameter(
         &self,
         db: &mut Database,
         name: String,
@@ -827,6 +1458,77 @@ impl TraitId {
         param
     }
 
+    /// Declares a new associated type.
+    ///
+    /// If any of the required traits declare an associated type with the same
+    /// name, that associated type is assigned the new one. This way the
+    /// implementation of this trait assigns a value to both associated types.
+    pub fn new_associated_type(
+        self,
+        db: &mut Database,
+        name: String,
+    ) -> TypeParameterId {
+        let param = TypeParameter::alloc(db, name.clone());
+        let inherited: Vec<_> = self
+            .get(db)
+            .required_traits
+            .iter()
+            .filter_map(|req| req.instance_of.associated_type(db, &name))
+            .collect();
+        let typ = self.get_mut(db);
+
+        for req in inherited {
+            typ.inherited_type_arguments
+                .assign(req, TypeRef::Any(TypeId::TypeParameter(param)));
+        }
+
+        typ.associated_types.insert(name, param);
+        param
+    }
+
+    pub fn associated_types(self, db: &Database) -> Vec<TypeParameterId> {
+        self.get(db).associated_types.values().cloned().collect()
+    }
+
+    pub fn associated_type(
+        self,
+        db: &Database,
+        name: &str,
+    ) -> Option<TypeParameterId> {
+        self.get(db).associated_types.get(name).cloned()
+    }
+
+    /// Returns the names of all associated types an implementation of this
+    /// trait must assign a value to.
+    pub fn associated_type_names(self, db: &Database) -> Vec<String> {
+        self.get(db).associated_types.keys().cloned().collect()
+    }
+
+    pub fn set_associated_type_default(
+        self,
+        db: &mut Database,
+        parameter: TypeParameterId,
+        value: TypeRef,
+    ) {
+        self.get_mut(db).associated_type_defaults.assign(parameter, value);
+    }
+
+    /// Returns the default value of an associated type declared by this trait
+    /// or any of its required traits.
+    pub fn associated_type_default(
+        self,
+        db: &Database,
+        parameter: TypeParameterId,
+    ) -> Option<TypeRef> {
+        let typ = self.get(db);
+
+        typ.associated_type_defaults.get(parameter).or_else(|| {
+            typ.required_traits.iter().find_map(|req| {
+                req.instance_of.associated_type_default(db, parameter)
+            })
+        })
+    }
+
     pub fn is_public(self, db: &Database) -> bool {
         self.get(db).visibility == Visibility::Public
     }
@@ -844,17 +1546,32 @@ impl TraitId {
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn set_deprecated(self, db: &mut Database, value: Deprecation) {
+        self.get_mut(db).deprecated = Some(value);
+    }
+
+    pub fn deprecated(self, db: &Database) -> Option<&Deprecation> {
+        self.get(db).deprecated.as_ref()
+    }
+
+    pub fn is_deprecated(self, db: &Database) -> bool {
+        self.get(db).deprecated.is_some()
+    }
+
+    pub fn documentation(self, db: &Database) -> &Docs {
         &self.get(db).documentation
     }
 
     fn named_type(self, db: &Database, name: &str) -> Option<Symbol> {
-        self.get(db)
-            .type_parameters
+        let typ = self.get(db);
+
+        typ.type_parameters
             .get(name)
+            .or_else(|| typ.associated_types.get(name))
+            .or_else(|| typ.self_type.as_ref().filter(|_| name == SELF_TYPE))
             .map(|&id| Symbol::TypeParameter(id))
     }
 
@@ -881,17 +1598,16 @@ pub struct TraitInstance {
     /// The index to the TypeArguments structure that belongs to this trait
     /// instance.
     ///
-    /// If the trait is a regular trait, this ID is always 0 and shouldn't be
-    /// used.
+    /// If the trait is a regular trait, this index is always `Index::NONE`.
     ///
     /// After type specialization takes place, this value shouldn't be used any
     /// more as specialized types won't have their type arguments set.
-    type_arguments: u32,
+    type_arguments: Index,
 }
 
 impl TraitInstance {
     pub fn new(instance_of: TraitId) -> Self {
-        Self { instance_of, type_arguments: 0 }
+        Self { instance_of, type_arguments: Index::NONE }
     }
 
     pub fn rigid(
@@ -901,8 +1617,11 @@ impl TraitInstance {
     ) -> Self {
         if instance_of.is_generic(db) {
             let mut arguments = TypeArguments::new();
+            let mut params = instance_of.type_parameters(db);
 
-            for param in instance_of.type_parameters(db) {
+            params.append(&mut instance_of.associated_types(db));
+
+            for param in params {
                 arguments.assign(
                     param,
                     bounds.get(param).unwrap_or(param).as_rigid(),
@@ -920,12 +1639,9 @@ impl TraitInstance {
         instance_of: TraitId,
         arguments: TypeArguments,
     ) -> Self {
-        assert!(db.type_arguments.len() < u32::MAX as usize);
-
-        let type_args_id = db.type_arguments.len() as u32;
+        let type_arguments = db.type_arguments.insert(arguments);
 
-        db.type_arguments.push(arguments);
-        TraitInstance { instance_of, type_arguments: type_args_id }
+        TraitInstance { instance_of, type_arguments }
     }
 
     pub fn instance_of(self) -> TraitId {
@@ -933,7 +1649,7 @@ impl TraitInstance {
     }
 
     pub fn type_arguments(self, db: &Database) -> Option<&TypeArguments> {
-        db.type_arguments.get(self.type_arguments as usize)
+        db.type_arguments.get(self.type_arguments)
     }
 
     pub fn copy_new_arguments_from(
@@ -946,9 +1662,10 @@ impl TraitInstance {
         }
 
         let params = self.instance_of.type_parameters(db);
-        let targs = &mut db.type_arguments[self.type_arguments as usize];
 
-        from.copy_assigned_into(params, targs);
+        if let Some(targs) = db.type_arguments.get_mut(self.type_arguments) {
+            from.copy_assigned_into(params, targs);
+        }
     }
 
     pub fn copy_type_arguments_into(
@@ -967,6 +1684,28 @@ impl TraitInstance {
         self.instance_of.method(db, name)
     }
 
+    /// Returns the value of the associated type with the given name.
+    ///
+    /// If no value is assigned, the default value of the associated type is
+    /// returned.
+    pub fn associated_type(self, db: &Database, name: &str) -> Option<TypeRef> {
+        let param = self.instance_of.associated_type(db, name)?;
+
+        self.type_arguments(db)
+            .and_then(|args| args.get(param))
+            .or_else(|| self.instance_of.associated_type_default(db, param))
+    }
+
+    /// Returns the names of the associated types that aren't assigned a value
+    /// and don't have a default value.
+    pub fn missing_associated_types(self, db: &Database) -> Vec<String> {
+        self.instance_of
+            .associated_type_names(db)
+            .into_iter()
+            .filter(|name| self.associated_type(db, name).is_none())
+            .collect()
+    }
+
     fn named_type(self, db: &Database, name: &str) -> Option<Symbol> {
         self.instance_of.named_type(db, name)
     }
@@ -980,7 +1719,13 @@ pub struct Field {
     visibility: Visibility,
     module: ModuleId,
     location: Location,
-    documentation: String,
+    documentation: Docs,
+
+    /// The value to assign to the field if none is given explicitly.
+    default: Option<FieldDefault>,
+
+    /// The method used to compute the value of a computed field.
+    getter: Option<MethodId>,
 }
 
 impl Field {
@@ -1002,7 +1747,9 @@ impl Field {
             visibility,
             module,
             location,
-            documentation: String::new(),
+            documentation: Docs::default(),
+            default: None,
+            getter: None,
         });
         FieldId(id)
     }
@@ -1036,16 +1783,10 @@ impl FieldId {
     pub fn is_visible_to(self, db: &Database, module: ModuleId) -> bool {
         let field = self.get(db);
 
-        match field.visibility {
-            Visibility::Public => true,
-            Visibility::Private => {
-                field.module.has_same_root_namespace(db, module)
-            }
-            // TypePrivate fields can only be accessed using the `@name` syntax,
-            // which in turn is only available inside a class, thus not needing
-            // any extra checks.
-            Visibility::TypePrivate => false,
-        }
+        // TypePrivate fields can only be accessed using the `@name` syntax,
+        // which in turn is only available inside a class, thus not needing any
+        // extra checks.
+        field.visibility.allows_access(db, field.module, module)
     }
 
     pub fn location(self, db: &Database) -> Location {
@@ -1053,10 +1794,10 @@ impl FieldId {
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn documentation(self, db: &Database) -> &Docs {
         &self.get(db).documentation
     }
 
@@ -1135,21 +1876,48 @@ impl TypeBounds {
 pub struct TraitImplementation {
     pub instance: TraitInstance,
     pub bounds: TypeBounds,
+
+    /// The module the implementation is defined in.
+    pub module: ModuleId,
+
+    /// The location of the implementation.
+    pub location: Location,
+}
+
+/// An implementation of a trait for a type parameter, such as `impl ToString
+/// for T if T: Format`.
+///
+/// Such an implementation applies to every type that meets the requirements
+/// of the type parameter, unless the type explicitly implements the trait.
+#[derive(Clone)]
+pub struct BlanketImplementation {
+    /// The type parameter the trait is implemented for.
+    pub parameter: TypeParameterId,
+    pub implementation: TraitImplementation,
 }
 
 /// A single constructor defined in a enum class.
 pub struct Constructor {
-    id: u16,
+    id: u32,
+
+    /// The value stored in the tag field for this constructor.
+    tag: i64,
     name: String,
-    documentation: String,
+    documentation: Docs,
     location: Location,
     arguments: Vec<TypeRef>,
+
+    /// The names of the arguments, in the same order as the arguments.
+    ///
+    /// This list is empty for constructors that only define positional
+    /// arguments, such as `Some(T)`.
+    argument_names: Vec<String>,
 }
 
 impl Constructor {
     pub fn alloc(
         db: &mut Database,
-        id: u16,
+        id: u32,
         name: String,
         members: Vec<TypeRef>,
         location: Location,
@@ -1158,20 +1926,22 @@ impl Constructor {
 
         db.constructors.push(Constructor {
             id,
+            tag: id as i64,
             name,
             arguments: members,
+            argument_names: Vec::new(),
             location,
-            documentation: String::new(),
+            documentation: Docs::default(),
         });
         ConstructorId(global_id)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct ConstructorId(pub usize);
 
 impl ConstructorId {
-    pub fn id(self, db: &Database) -> u16 {
+    pub fn id(self, db: &Database) -> u32 {
         self.get(db).id
     }
 
@@ -1195,15 +1965,46 @@ impl ConstructorId {
         self.get(db).arguments.len()
     }
 
+    /// Sets the names of the arguments, such as `radius` for
+    /// `Circle(radius: Float)`.
+    ///
+    /// The names must be given in the same order as the arguments.
+    pub fn set_argument_names(self, db: &mut Database, names: Vec<String>) {
+        let cons = self.get_mut(db);
+
+        assert_eq!(cons.arguments.len(), names.len());
+        cons.argument_names = names;
+    }
+
+    /// Returns the names of the arguments, or an empty slice if the arguments
+    /// don't have names.
+    pub fn argument_names(self, db: &Database) -> &[String] {
+        &self.get(db).argument_names
+    }
+
+    pub fn has_named_arguments(self, db: &Database) -> bool {
+        !self.get(db).argument_names.is_empty()
+    }
+
+    /// Returns the index of the argument with the given name.
+    pub fn argument_index(self, db: &Database, name: &str) -> Option<usize> {
+        self.get(db).argument_names.iter().position(|n| n == name)
+    }
+
+    /// Returns the type of the argument with the given name.
+    pub fn named_argument(self, db: &Database, name: &str) -> Option<TypeRef> {
+        self.argument_index(db, name).map(|i| self.get(db).arguments[i])
+    }
+
     pub fn location(self, db: &Database) -> Location {
         self.get(db).location
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn documentation(self, db: &Database) -> &Docs {
         &self.get(db).documentation
     }
 
@@ -1251,6 +2052,9 @@ pub enum ClassKind {
 
     /// The type is a N-arity tuple.
     Tuple,
+
+    /// The type is a C union.
+    Union,
 }
 
 impl ClassKind {
@@ -1274,8 +2078,13 @@ impl ClassKind {
         matches!(self, ClassKind::Module)
     }
 
+    /// Returns `true` if the type is a C type, i.e. a structure or a union.
     pub fn is_extern(self) -> bool {
-        matches!(self, ClassKind::Extern)
+        matches!(self, ClassKind::Extern | ClassKind::Union)
+    }
+
+    pub fn is_union(self) -> bool {
+        matches!(self, ClassKind::Union)
     }
 
     pub fn allow_pattern_matching(self) -> bool {
@@ -1291,7 +2100,8 @@ impl ClassKind {
 pub struct Class {
     kind: ClassKind,
     name: String,
-    documentation: String,
+    documentation: Docs,
+    deprecated: Option<Deprecation>,
 
     // A flag indicating the presence of a custom destructor.
     //
@@ -1309,6 +2119,12 @@ pub struct Class {
     type_parameters: IndexMap<String, TypeParameterId>,
     methods: HashMap<String, MethodId>,
     implemented_traits: HashMap<TraitId, TraitImplementation>,
+
+    /// Implementations of traits that are already implemented for this class.
+    ///
+    /// These implementations are ignored when looking up trait
+    /// implementations, but are kept such that they can be reported as errors.
+    overlapping_implementations: Vec<TraitImplementation>,
     constructors: IndexMap<String, ConstructorId>,
     specializations: HashMap<Vec<Shape>, ClassId>,
 
@@ -1318,6 +2134,30 @@ pub struct Class {
     /// The shapes of the type parameters of this class, in the same order as
     /// the parameters.
     shapes: Vec<Shape>,
+
+    /// Options controlling the memory layout of the class.
+    ///
+    /// These options are only supported for extern classes.
+    layout_options: LayoutOptions,
+
+    /// The niche layout of a specialized enum, if it can use one.
+    niche: Option<Niche>,
+
+    /// The static `new` method synthesized from the fields of the class.
+    synthesized_constructor: Option<MethodId>,
+
+    /// The fields of which the value is computed using a getter method.
+    computed_fields: IndexMap<String, FieldId>,
+
+    /// The order in which the fields are dropped.
+    drop_order: DropOrder,
+
+    /// The constants defined by the class, such as those required by the
+    /// traits it implements.
+    constants: IndexMap<String, ConstantId>,
+
+    /// The conditions that must be met for the class to be included.
+    conditions: Conditions,
 }
 
 impl Class {
@@ -1350,15 +2190,13 @@ impl Class {
         module: ModuleId,
         location: Location,
     ) -> Self {
-        let storage = if let ClassKind::Extern = kind {
-            Storage::Stack
-        } else {
-            Storage::Heap
-        };
+        let storage =
+            if kind.is_extern() { Storage::Stack } else { Storage::Heap };
 
         Self {
             name,
-            documentation: String::new(),
+            documentation: Docs::default(),
+            deprecated: None,
             kind,
             visibility,
             storage,
@@ -1367,12 +2205,20 @@ impl Class {
             type_parameters: IndexMap::new(),
             methods: HashMap::new(),
             implemented_traits: HashMap::new(),
+            overlapping_implementations: Vec::new(),
             constructors: IndexMap::new(),
             module,
             location,
             specializations: HashMap::new(),
             specialization_source: None,
             shapes: Vec::new(),
+            layout_options: LayoutOptions::default(),
+            niche: None,
+            synthesized_constructor: None,
+            computed_fields: IndexMap::new(),
+            drop_order: DropOrder::Reverse,
+            constants: IndexMap::new(),
+            conditions: Conditions::default(),
         }
     }
 
@@ -1419,6 +2265,19 @@ impl Class {
         )
     }
 
+    fn range(name: String) -> Self {
+        let mut cls = Self::new(
+            name,
+            ClassKind::Regular,
+            Visibility::Public,
+            ModuleId(DEFAULT_BUILTIN_MODULE_ID),
+            Location::default(),
+        );
+
+        cls.storage = Storage::Stack;
+        cls
+    }
+
     fn is_generic(&self) -> bool {
         !self.type_parameters.is_empty()
     }
@@ -1448,6 +2307,10 @@ impl ClassId {
         ClassId(NIL_ID)
     }
 
+    pub fn char() -> ClassId {
+        ClassId(CHAR_ID)
+    }
+
     pub fn array() -> ClassId {
         ClassId(ARRAY_ID)
     }
@@ -1492,6 +2355,22 @@ impl ClassId {
         ClassId(CHECKED_INT_RESULT_ID)
     }
 
+    pub fn checked_int128_result() -> ClassId {
+        ClassId(CHECKED_INT128_RESULT_ID)
+    }
+
+    pub fn inclusive_range() -> ClassId {
+        ClassId(INCLUSIVE_RANGE_ID)
+    }
+
+    pub fn exclusive_range() -> ClassId {
+        ClassId(EXCLUSIVE_RANGE_ID)
+    }
+
+    pub fn is_range(self) -> bool {
+        matches!(self.0, INCLUSIVE_RANGE_ID | EXCLUSIVE_RANGE_ID)
+    }
+
     pub fn tuple(len: usize) -> Option<ClassId> {
         match len {
             1 => Some(ClassId::tuple1()),
@@ -1515,7 +2394,10 @@ impl ClassId {
     }
 
     pub fn allow_trait_implementations(self, db: &Database) -> bool {
-        !matches!(self.kind(db), ClassKind::Async | ClassKind::Extern)
+        !matches!(
+            self.kind(db),
+            ClassKind::Async | ClassKind::Extern | ClassKind::Union
+        )
     }
 
     pub fn type_parameters(self, db: &Database) -> Vec<TypeParameterId> {
@@ -1528,11 +2410,50 @@ impl ClassId {
         implementation: TraitImplementation,
     ) {
         let trait_id = implementation.instance.instance_of();
+        let class = self.get_mut(db);
+
+        // If the trait is already implemented we keep the existing
+        // implementation, and record the new one such that the overlap can be
+        // reported.
+        if class.implemented_traits.contains_key(&trait_id) {
+            class.overlapping_implementations.push(implementation);
+            return;
+        }
 
-        self.get_mut(db).implemented_traits.insert(trait_id, implementation);
+        class.implemented_traits.insert(trait_id, implementation);
         trait_id.get_mut(db).implemented_by.push(self);
     }
 
+    pub fn overlapping_implementations(
+        self,
+        db: &Database,
+    ) -> &[TraitImplementation] {
+        &self.get(db).overlapping_implementations
+    }
+
+    /// Returns the implementations of traits that are implemented more than
+    /// once, paired with the implementation they duplicate.
+    ///
+    /// The pairs are returned in the order in which the duplicates are
+    /// defined, with the first value being the original implementation.
+    pub fn duplicate_trait_implementations(
+        self,
+        db: &Database,
+    ) -> Vec<(&TraitImplementation, &TraitImplementation)> {
+        let class = self.get(db);
+
+        class
+            .overlapping_implementations
+            .iter()
+            .filter_map(|dup| {
+                class
+                    .implemented_traits
+                    .get(&dup.instance.instance_of())
+                    .map(|orig| (orig, dup))
+            })
+            .collect()
+    }
+
     pub fn trait_implementation(
         self,
         db: &Database,
@@ -1555,10 +2476,16 @@ impl ClassId {
         members: Vec<TypeRef>,
         location: Location,
     ) -> ConstructorId {
-        let id = self.get(db).constructors.len() as u16;
+        let id = self.get(db).constructors.len() as u32;
         let constructor =
             Constructor::alloc(db, id, name.clone(), members, location);
 
+        // Similar to C, constructors without an explicit tag value use the
+        // value of the previous constructor plus one.
+        if let Some(&prev) = self.get(db).constructors.values().last() {
+            constructor.set_tag_value(db, prev.tag_value(db).wrapping_add(1));
+        }
+
         self.get_mut(db).constructors.insert(name, constructor);
         constructor
     }
@@ -1575,7 +2502,19 @@ impl ClassId {
         self.get(db).type_parameters.get(name).cloned()
     }
 
+    /// Returns the field with the given name, including computed fields.
     pub fn field(self, db: &Database, name: &str) -> Option<FieldId> {
+        let class = self.get(db);
+
+        class
+            .fields
+            .get(name)
+            .or_else(|| class.computed_fields.get(name))
+            .cloned()
+    }
+
+    /// Returns the field with the given name, excluding computed fields.
+    pub fn stored_field(self, db: &Database, name: &str) -> Option<FieldId> {
         self.get(db).fields.get(name).cloned()
     }
 
@@ -1763,7 +2702,16 @@ impl ClassId {
     }
 
     pub fn is_builtin(self) -> bool {
-        self.0 <= NIL_ID
+        self.0 < FIRST_USER_CLASS_ID
+    }
+
+    /// Returns `true` if reading a field of this class is unsafe.
+    ///
+    /// The fields of a union overlap, and only one of them is valid at any
+    /// given time. Since we don't know which field that is, reading any field
+    /// of a union is unsafe.
+    pub fn requires_unsafe_field_reads(self, db: &Database) -> bool {
+        self.kind(db).is_union()
     }
 
     pub fn is_value_type(self, db: &Database) -> bool {
@@ -1784,6 +2732,31 @@ impl ClassId {
         matches!(self.get(db).storage, Storage::Stack)
     }
 
+    pub fn set_layout_options(self, db: &mut Database, value: LayoutOptions) {
+        self.get_mut(db).layout_options = value;
+    }
+
+    pub fn layout_options(self, db: &Database) -> LayoutOptions {
+        self.get(db).layout_options
+    }
+
+    pub fn set_niche(self, db: &mut Database, value: Niche) {
+        self.get_mut(db).niche = Some(value);
+    }
+
+    /// Returns the niche layout of the enum, if it uses one.
+    pub fn niche(self, db: &Database) -> Option<Niche> {
+        self.get(db).niche
+    }
+
+    /// Returns the memory layout of a stack allocated class.
+    ///
+    /// For heap allocated classes, and classes of which the layout depends on
+    /// type parameters without known shapes, `None` is returned.
+    pub fn layout(self, db: &Database) -> Option<Layout> {
+        layout::class_layout(db, self, &mut Vec::new())
+    }
+
     pub fn has_object_header(self, db: &Database) -> bool {
         // Currently heap objects always have an object header and stack objects
         // never have one, but this may change at some point. For example, if an
@@ -1821,12 +2794,24 @@ impl ClassId {
             || matches!(self.0, INT_ID | FLOAT_ID | BOOL_ID)
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn set_deprecated(self, db: &mut Database, value: Deprecation) {
+        self.get_mut(db).deprecated = Some(value);
+    }
+
+    pub fn deprecated(self, db: &Database) -> Option<&Deprecation> {
+        self.get(db).deprecated.as_ref()
+    }
+
+    pub fn is_deprecated(self, db: &Database) -> bool {
+        self.get(db).deprecated.is_some()
+    }
+
+    pub fn documentation(self, db: &Database) -> &Docs {
         &self.get(db).documentation
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
     pub fn location(self, db: &Database) -> Location {
@@ -1852,6 +2837,8 @@ impl ClassId {
         );
 
         new.storage = src.storage;
+        new.layout_options = src.layout_options;
+        new.drop_order = src.drop_order;
         Class::add(db, new)
     }
 
@@ -1859,7 +2846,7 @@ impl ClassId {
         let obj = self.get(db);
 
         match obj.kind {
-            ClassKind::Extern => true,
+            ClassKind::Extern | ClassKind::Union => true,
             ClassKind::Atomic => false,
             _ => matches!(obj.storage, Storage::Heap),
         }
@@ -1889,12 +2876,12 @@ pub struct ClassInstance {
     ///
     /// After type specialization takes place, this value shouldn't be used any
     /// more as specialized types won't have their type arguments set.
-    type_arguments: u32,
+    type_arguments: Index,
 }
 
 impl ClassInstance {
     pub fn new(instance_of: ClassId) -> Self {
-        Self { instance_of, type_arguments: 0 }
+        Self { instance_of, type_arguments: Index::NONE }
     }
 
     pub fn rigid(
@@ -1923,12 +2910,9 @@ impl ClassInstance {
         instance_of: ClassId,
         arguments: TypeArguments,
     ) -> Self {
-        assert!(db.type_arguments.len() < u32::MAX as usize);
-
-        let args_id = db.type_arguments.len() as u32;
+        let type_arguments = db.type_arguments.insert(arguments);
 
-        db.type_arguments.push(arguments);
-        ClassInstance { instance_of, type_arguments: args_id }
+        ClassInstance { instance_of, type_arguments }
     }
 
     pub fn with_types(
@@ -1970,13 +2954,19 @@ impl ClassInstance {
     }
 
     pub fn type_arguments(self, db: &Database) -> Option<&TypeArguments> {
-        db.type_arguments.get(self.type_arguments as usize)
+        db.type_arguments.get(self.type_arguments)
     }
 
     pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
         self.instance_of.method(db, name)
     }
 
+    /// Returns a method provided by a blanket implementation that applies to
+    /// this instance.
+    pub fn blanket_method(self, db: &Database, name: &str) -> Option<MethodId> {
+        lookup::blanket_method(db, self, name, &mut None)
+    }
+
     pub fn ordered_type_arguments(self, db: &Database) -> Vec<TypeRef> {
         let params = self.instance_of.type_parameters(db);
         let args = self.type_arguments(db).unwrap();
@@ -1998,7 +2988,11 @@ impl ClassInstance {
             FLOAT_ID => Shape::float(),
             BOOL_ID => Shape::Boolean,
             NIL_ID => Shape::Nil,
+            CHAR_ID => Shape::Char,
             STRING_ID => Shape::String,
+            _ if db.builtin_shapes.contains_key(&self.instance_of) => {
+                db.builtin_shapes[&self.instance_of]
+            }
             _ if self.instance_of.kind(db).is_atomic() => Shape::Atomic,
             _ if self.instance_of.is_stack_allocated(db) => {
                 let targs = if self.instance_of.is_generic(db) {
@@ -2008,7 +3002,7 @@ impl ClassInstance {
                     // returned Shape.
                     interned.intern(db, self)
                 } else {
-                    0
+                    Index::NONE
                 };
 
                 Shape::Stack(ClassInstance {
@@ -2025,39 +3019,403 @@ impl ClassInstance {
     }
 }
 
-/// A collection of arguments.
-#[derive(Clone)]
-struct Arguments {
-    mapping: IndexMap<String, Argument>,
-}
+/// A type alias, such as `type Numbers = Array[Int]`.
+///
+/// Aliases are expanded into the type they alias when resolving and
+/// type-checking types, but are formatted using the name of the alias.
+pub struct TypeAlias {
+    name: String,
+    documentation: Docs,
+    module: ModuleId,
+    location: Location,
+    visibility: Visibility,
+    type_parameters: IndexMap<String, TypeParameterId>,
 
-impl Arguments {
-    fn new() -> Self {
-        Self { mapping: IndexMap::new() }
-    }
+    /// The type this alias expands to.
+    aliased: TypeRef,
+}
 
-    fn new_argument(
-        &mut self,
+impl TypeAlias {
+    pub fn alloc(
+        db: &mut Database,
         name: String,
-        value_type: TypeRef,
-        variable: VariableId,
-    ) {
-        let index = self.mapping.len();
-        let arg = Argument { index, name: name.clone(), value_type, variable };
+        visibility: Visibility,
+        module: ModuleId,
+        location: Location,
+    ) -> TypeAliasId {
+        assert!(db.type_aliases.len() < u32::MAX as usize);
 
-        // Since indexes of arguments are incremented with insertions, we need
-        // to make sure we're not updating an existing argument by mistake.
-        debug_assert!(self.mapping.get(&name).is_none());
-        self.mapping.insert(name, arg);
-    }
+        let id = db.type_aliases.len() as u32;
 
-    fn get(&self, name: &str) -> Option<&Argument> {
-        self.mapping.get(name)
+        db.type_aliases.push(TypeAlias {
+            name,
+            documentation: Docs::default(),
+            module,
+            location,
+            visibility,
+            type_parameters: IndexMap::new(),
+            aliased: TypeRef::Unknown,
+        });
+        TypeAliasId(id)
     }
+}
 
-    fn iter(&self) -> impl Iterator<Item = &Argument> {
-        self.mapping.values()
-    }
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TypeAliasId(pub u32);
+
+impl TypeAliasId {
+    pub fn name(self, db: &Database) -> &String {
+        &self.get(db).name
+    }
+
+    pub fn module(self, db: &Database) -> ModuleId {
+        self.get(db).module
+    }
+
+    pub fn location(self, db: &Database) -> Location {
+        self.get(db).location
+    }
+
+    pub fn set_documentation(self, db: &mut Database, value: String) {
+        self.get_mut(db).documentation = Docs::parse(value);
+    }
+
+    pub fn documentation(self, db: &Database) -> &Docs {
+        &self.get(db).documentation
+    }
+
+    pub fn is_public(self, db: &Database) -> bool {
+        self.get(db).visibility == Visibility::Public
+    }
+
+    pub fn is_private(self, db: &Database) -> bool {
+        !self.is_public(db)
+    }
+
+    pub fn aliased_type(self, db: &Database) -> TypeRef {
+        self.get(db).aliased
+    }
+
+    pub fn set_aliased_type(self, db: &mut Database, typ: TypeRef) {
+        self.get_mut(db).aliased = typ;
+    }
+
+    pub fn type_parameters(self, db: &Database) -> Vec<TypeParameterId> {
+        self.get(db).type_parameters.values().cloned().collect()
+    }
+
+    pub fn new_type_parameter(
+        self,
+        db: &mut Database,
+        name: String,
+    ) -> TypeParameterId {
+        let param = TypeParameter::alloc(db, name.clone());
+
+        self.get_mut(db).type_parameters.insert(name, param);
+        param
+    }
+
+    pub fn number_of_type_parameters(self, db: &Database) -> usize {
+        self.get(db).type_parameters.len()
+    }
+
+    pub fn is_generic(self, db: &Database) -> bool {
+        !self.get(db).type_parameters.is_empty()
+    }
+
+    pub fn named_type(self, db: &Database, name: &str) -> Option<Symbol> {
+        self.get(db)
+            .type_parameters
+            .get(name)
+            .map(|&id| Symbol::TypeParameter(id))
+    }
+
+    /// Returns `true` if expanding the alias requires expanding the alias
+    /// itself, such as for `type A = Array[A]`.
+    ///
+    /// Such aliases can't be expanded, and must be rejected before they are
+    /// used.
+    pub fn is_cyclic(self, db: &Database) -> bool {
+        let mut stack = vec![self.aliased_type(db)];
+        let mut visited = HashSet::new();
+
+        while let Some(typ) = stack.pop() {
+            let Ok(id) = typ.type_id(db) else { continue };
+            let args = match id {
+                TypeId::TypeAlias(ins) if ins.instance_of == self => {
+                    return true;
+                }
+                TypeId::TypeAlias(ins) => {
+                    if visited.insert(ins.instance_of) {
+                        stack.push(ins.instance_of.aliased_type(db));
+                    }
+
+                    ins.type_arguments(db)
+                }
+                TypeId::ClassInstance(ins)
+                    if ins.instance_of.is_generic(db) =>
+                {
+                    ins.type_arguments(db)
+                }
+                TypeId::TraitInstance(ins)
+                    if ins.instance_of.is_generic(db) =>
+                {
+                    ins.type_arguments(db)
+                }
+                TypeId::Tuple(id) => {
+                    stack.extend(id.elements(db).iter().cloned());
+                    None
+                }
+                TypeId::Closure(id) => {
+                    let closure = id.get(db);
+
+                    stack.push(closure.return_type);
+                    stack
+                        .extend(closure.arguments.iter().map(|a| a.value_type));
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(args) = args {
+                stack.extend(args.values());
+            }
+        }
+
+        false
+    }
+
+    fn get(self, db: &Database) -> &TypeAlias {
+        &db.type_aliases[self.0 as usize]
+    }
+
+    fn get_mut(self, db: &mut Database) -> &mut TypeAlias {
+        &mut db.type_aliases[self.0 as usize]
+    }
+}
+
+/// An instance of a type alias, along with its type arguments in case the
+/// alias is generic.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeAliasInstance {
+    /// The ID of the alias we're an instance of.
+    instance_of: TypeAliasId,
+
+    /// The index to the TypeArguments structure that belongs to this instance.
+    ///
+    /// If the alias isn't generic, this index shouldn't be used.
+    type_arguments: Index,
+}
+
+impl TypeAliasInstance {
+    pub fn new(instance_of: TypeAliasId) -> Self {
+        Self { instance_of, type_arguments: Index::NONE }
+    }
+
+    pub fn generic(
+        db: &mut Database,
+        instance_of: TypeAliasId,
+        arguments: TypeArguments,
+    ) -> Self {
+        let type_arguments = db.type_arguments.insert(arguments);
+
+        TypeAliasInstance { instance_of, type_arguments }
+    }
+
+    pub fn instance_of(self) -> TypeAliasId {
+        self.instance_of
+    }
+
+    pub fn type_arguments(self, db: &Database) -> Option<&TypeArguments> {
+        if self.instance_of.is_generic(db) {
+            db.type_arguments.get(self.type_arguments)
+        } else {
+            None
+        }
+    }
+
+    pub fn copy_type_arguments_into(
+        self,
+        db: &Database,
+        target: &mut TypeArguments,
+    ) {
+        if let Some(args) = self.type_arguments(db) {
+            args.copy_into(target);
+        }
+    }
+}
+
+/// A union of types, such as `Int | String`.
+///
+/// A value of a union type is a value of one of its members. Unions are
+/// created using `TypeRef::union()`, which takes care of flattening nested
+/// unions and removing duplicate members.
+pub struct Union {
+    /// The types that make up the union, in the order they are defined in.
+    members: Vec<TypeRef>,
+}
+
+impl Union {
+    fn alloc(db: &mut Database, members: Vec<TypeRef>) -> UnionId {
+        assert!(db.unions.len() < u32::MAX as usize);
+
+        let id = db.unions.len() as u32;
+
+        db.unions.push(Union { members });
+        UnionId(id)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct UnionId(pub u32);
+
+impl UnionId {
+    pub fn members(self, db: &Database) -> &Vec<TypeRef> {
+        &self.get(db).members
+    }
+
+    fn get(self, db: &Database) -> &Union {
+        &db.unions[self.0 as usize]
+    }
+}
+
+/// A tuple of an arbitrary number of values, such as `(Int, String)`.
+///
+/// Tuples of up to eight values are instances of the builtin `Tuple1` to
+/// `Tuple8` classes, as these classes provide the methods and trait
+/// implementations tuples are expected to have. Tuples with more values are
+/// represented using this type instead. Tuples are created using
+/// `TypeRef::tuple()`, which takes care of picking the right representation.
+///
+/// The lists of values are interned, such that tuples with the same values
+/// share the same `TupleId`.
+pub struct Tuple {
+    /// The types of the values, in the order they are defined in.
+    elements: Vec<TypeRef>,
+}
+
+impl Tuple {
+    pub fn alloc(db: &mut Database, elements: Vec<TypeRef>) -> TupleId {
+        if let Some(&id) = db.tuple_ids.get(&elements) {
+            return id;
+        }
+
+        assert!(db.tuples.len() < u32::MAX as usize);
+
+        let id = TupleId(db.tuples.len() as u32);
+
+        db.tuple_ids.insert(elements.clone(), id);
+        db.tuples.push(Tuple { elements });
+        id
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TupleId(pub u32);
+
+impl TupleId {
+    pub fn elements(self, db: &Database) -> &Vec<TypeRef> {
+        &self.get(db).elements
+    }
+
+    pub fn len(self, db: &Database) -> usize {
+        self.get(db).elements.len()
+    }
+
+    pub fn is_empty(self, db: &Database) -> bool {
+        self.get(db).elements.is_empty()
+    }
+
+    fn get(self, db: &Database) -> &Tuple {
+        &db.tuples[self.0 as usize]
+    }
+}
+
+/// The signature of a C function, used for function pointers such as
+/// callbacks.
+pub struct ForeignFunction {
+    arguments: Vec<TypeRef>,
+
+    /// The return type of the function, which is `Nil` for functions that
+    /// don't return a value.
+    return_type: TypeRef,
+    variadic: bool,
+}
+
+impl ForeignFunction {
+    pub fn alloc(
+        db: &mut Database,
+        arguments: Vec<TypeRef>,
+        return_type: TypeRef,
+        variadic: bool,
+    ) -> ForeignFunctionId {
+        assert!(db.foreign_functions.len() < u32::MAX as usize);
+
+        let id = db.foreign_functions.len() as u32;
+
+        db.foreign_functions.push(ForeignFunction {
+            arguments,
+            return_type,
+            variadic,
+        });
+        ForeignFunctionId(id)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ForeignFunctionId(pub u32);
+
+impl ForeignFunctionId {
+    pub fn arguments(self, db: &Database) -> &Vec<TypeRef> {
+        &self.get(db).arguments
+    }
+
+    pub fn return_type(self, db: &Database) -> TypeRef {
+        self.get(db).return_type
+    }
+
+    pub fn is_variadic(self, db: &Database) -> bool {
+        self.get(db).variadic
+    }
+
+    fn get(self, db: &Database) -> &ForeignFunction {
+        &db.foreign_functions[self.0 as usize]
+    }
+}
+
+/// A collection of arguments.
+#[derive(Clone)]
+struct Arguments {
+    mapping: IndexMap<String, Argument>,
+}
+
+impl Arguments {
+    fn new() -> Self {
+        Self { mapping: IndexMap::new() }
+    }
+
+    fn new_argument(
+        &mut self,
+        name: String,
+        value_type: TypeRef,
+        variable: VariableId,
+    ) {
+        let index = self.mapping.len();
+        let arg = Argument { index, name: name.clone(), value_type, variable };
+
+        // Since indexes of arguments are incremented with insertions, we need
+        // to make sure we're not updating an existing argument by mistake.
+        debug_assert!(self.mapping.get(&name).is_none());
+        self.mapping.insert(name, arg);
+    }
+
+    fn get(&self, name: &str) -> Option<&Argument> {
+        self.mapping.get(name)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Argument> {
+        self.mapping.values()
+    }
 
     fn len(&self) -> usize {
         self.mapping.len()
@@ -2092,11 +3450,19 @@ pub enum Visibility {
     /// A public symbol can be used by any other module.
     Public,
 
-    /// A symbol only available to the module in which it's defined.
+    /// A symbol only available to the module in which it's defined, and the
+    /// module's tests.
     Private,
 
     /// A symbol only available inside the type that defined it.
     TypePrivate,
+
+    /// A symbol available to all modules in the same root namespace (e.g. all
+    /// `std.*` modules), but not to modules outside of it.
+    ///
+    /// This allows libraries to share internals between their own modules,
+    /// without exposing these internals to their users.
+    Namespace,
 }
 
 impl Visibility {
@@ -2104,13 +3470,82 @@ impl Visibility {
         if public {
             Self::Public
         } else {
-            Self::Private
+            Self::Namespace
         }
     }
 
     pub fn is_private(self) -> bool {
         self != Self::Public
     }
+
+    /// Returns `true` if a symbol with this visibility that's defined in the
+    /// module `defined_in` is available to the module `module`.
+    ///
+    /// Type private symbols are only available through the type that defines
+    /// them, which this method can't determine, so for such symbols `false` is
+    /// returned.
+    fn allows_access(
+        self,
+        db: &Database,
+        defined_in: ModuleId,
+        module: ModuleId,
+    ) -> bool {
+        match self {
+            Visibility::Public => true,
+            Visibility::Namespace => {
+                defined_in.has_same_root_namespace(db, module)
+            }
+            Visibility::Private => {
+                defined_in.is_same_or_test_module(db, module)
+            }
+            Visibility::TypePrivate => false,
+        }
+    }
+}
+
+/// The memory orderings supported by the atomic intrinsics.
+///
+/// The ordering is passed to these intrinsics as an `Int`, using the values of
+/// this enum. Orderings that aren't valid for an operation (e.g. `Release` for
+/// a load) are replaced with the closest valid ordering, and unknown values
+/// are treated as `SequentiallyConsistent`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MemoryOrdering {
+    Relaxed = 0,
+    Acquire = 1,
+    Release = 2,
+    AcquireRelease = 3,
+    SequentiallyConsistent = 4,
+}
+
+impl MemoryOrdering {
+    pub fn all() -> [MemoryOrdering; 5] {
+        [
+            MemoryOrdering::Relaxed,
+            MemoryOrdering::Acquire,
+            MemoryOrdering::Release,
+            MemoryOrdering::AcquireRelease,
+            MemoryOrdering::SequentiallyConsistent,
+        ]
+    }
+
+    /// Returns the ordering to use for an atomic load.
+    pub fn for_load(self) -> MemoryOrdering {
+        match self {
+            MemoryOrdering::Release => MemoryOrdering::Relaxed,
+            MemoryOrdering::AcquireRelease => MemoryOrdering::Acquire,
+            ord => ord,
+        }
+    }
+
+    /// Returns the ordering to use for an atomic store.
+    pub fn for_store(self) -> MemoryOrdering {
+        match self {
+            MemoryOrdering::Acquire => MemoryOrdering::Relaxed,
+            MemoryOrdering::AcquireRelease => MemoryOrdering::Release,
+            ord => ord,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -2160,11 +3595,55 @@ pub enum Intrinsic {
     IntCheckedAdd,
     IntCheckedMul,
     IntCheckedSub,
+    IntCheckedDiv,
+    IntCheckedRem,
     IntSwapBytes,
     IntAbsolute,
     IntCompareSwap,
     SpinLoopHint,
     BoolEq,
+    CharToInt,
+    IntToChar,
+    Int128CheckedAdd,
+    Int128CheckedMul,
+    Int128CheckedSub,
+    Int128WrappingAdd,
+    Int128WrappingMul,
+    Int128WrappingSub,
+    FloatSqrt,
+    FloatSin,
+    FloatCos,
+    FloatLn,
+    FloatLog10,
+    FloatExp,
+    FloatPow,
+    AtomicLoad,
+    AtomicStore,
+    AtomicAdd,
+    AtomicSub,
+    AtomicAnd,
+    AtomicOr,
+    AtomicXor,
+    AtomicSwap,
+    MemoryCopy,
+    MemorySet,
+    MemoryCompare,
+    PrefetchRead,
+    PrefetchWrite,
+    Likely,
+    Unlikely,
+
+    /// Allocates a fixed number of bytes on the stack of the surrounding
+    /// function, returning a pointer to the memory.
+    ///
+    /// The size must be an Int literal. The memory is only valid until the
+    /// surrounding method returns. The type checker rejects pointers to this
+    /// memory (including pointers derived from it using casts or arithmetic)
+    /// that are returned, thrown, stored in fields, tuples or memory not
+    /// allocated using `stack_alloc`, or captured by closures. Pointers passed
+    /// as arguments to other methods aren't tracked, so such methods must not
+    /// store the pointer in a place that outlives the caller.
+    StackAlloc,
 }
 
 impl Intrinsic {
@@ -2208,6 +3687,8 @@ impl Intrinsic {
             Intrinsic::IntCheckedAdd,
             Intrinsic::IntCheckedMul,
             Intrinsic::IntCheckedSub,
+            Intrinsic::IntCheckedDiv,
+            Intrinsic::IntCheckedRem,
             Intrinsic::Moved,
             Intrinsic::Panic,
             Intrinsic::StringConcat,
@@ -2220,6 +3701,37 @@ impl Intrinsic {
             Intrinsic::IntCompareSwap,
             Intrinsic::SpinLoopHint,
             Intrinsic::BoolEq,
+            Intrinsic::CharToInt,
+            Intrinsic::IntToChar,
+            Intrinsic::Int128CheckedAdd,
+            Intrinsic::Int128CheckedMul,
+            Intrinsic::Int128CheckedSub,
+            Intrinsic::Int128WrappingAdd,
+            Intrinsic::Int128WrappingMul,
+            Intrinsic::Int128WrappingSub,
+            Intrinsic::FloatSqrt,
+            Intrinsic::FloatSin,
+            Intrinsic::FloatCos,
+            Intrinsic::FloatLn,
+            Intrinsic::FloatLog10,
+            Intrinsic::FloatExp,
+            Intrinsic::FloatPow,
+            Intrinsic::AtomicLoad,
+            Intrinsic::AtomicStore,
+            Intrinsic::AtomicAdd,
+            Intrinsic::AtomicSub,
+            Intrinsic::AtomicAnd,
+            Intrinsic::AtomicOr,
+            Intrinsic::AtomicXor,
+            Intrinsic::AtomicSwap,
+            Intrinsic::MemoryCopy,
+            Intrinsic::MemorySet,
+            Intrinsic::MemoryCompare,
+            Intrinsic::PrefetchRead,
+            Intrinsic::PrefetchWrite,
+            Intrinsic::Likely,
+            Intrinsic::Unlikely,
+            Intrinsic::StackAlloc,
         ]
         .into_iter()
         .fold(HashMap::new(), |mut map, func| {
@@ -2268,6 +3780,8 @@ impl Intrinsic {
             Intrinsic::IntCheckedAdd => "int_checked_add",
             Intrinsic::IntCheckedMul => "int_checked_mul",
             Intrinsic::IntCheckedSub => "int_checked_sub",
+            Intrinsic::IntCheckedDiv => "int_checked_div",
+            Intrinsic::IntCheckedRem => "int_checked_rem",
             Intrinsic::Moved => "moved",
             Intrinsic::Panic => "panic",
             Intrinsic::StringConcat => "string_concat",
@@ -2280,6 +3794,37 @@ impl Intrinsic {
             Intrinsic::IntCompareSwap => "int_compare_swap",
             Intrinsic::SpinLoopHint => "spin_loop_hint",
             Intrinsic::BoolEq => "bool_eq",
+            Intrinsic::CharToInt => "char_to_int",
+            Intrinsic::IntToChar => "int_to_char",
+            Intrinsic::Int128CheckedAdd => "int128_checked_add",
+            Intrinsic::Int128CheckedMul => "int128_checked_mul",
+            Intrinsic::Int128CheckedSub => "int128_checked_sub",
+            Intrinsic::Int128WrappingAdd => "int128_wrapping_add",
+            Intrinsic::Int128WrappingMul => "int128_wrapping_mul",
+            Intrinsic::Int128WrappingSub => "int128_wrapping_sub",
+            Intrinsic::FloatSqrt => "float_sqrt",
+            Intrinsic::FloatSin => "float_sin",
+            Intrinsic::FloatCos => "float_cos",
+            Intrinsic::FloatLn => "float_ln",
+            Intrinsic::FloatLog10 => "float_log10",
+            Intrinsic::FloatExp => "float_exp",
+            Intrinsic::FloatPow => "float_pow",
+            Intrinsic::AtomicLoad => "atomic_load",
+            Intrinsic::AtomicStore => "atomic_store",
+            Intrinsic::AtomicAdd => "atomic_add",
+            Intrinsic::AtomicSub => "atomic_sub",
+            Intrinsic::AtomicAnd => "atomic_and",
+            Intrinsic::AtomicOr => "atomic_or",
+            Intrinsic::AtomicXor => "atomic_xor",
+            Intrinsic::AtomicSwap => "atomic_swap",
+            Intrinsic::MemoryCopy => "memory_copy",
+            Intrinsic::MemorySet => "memory_set",
+            Intrinsic::MemoryCompare => "memory_compare",
+            Intrinsic::PrefetchRead => "prefetch_read",
+            Intrinsic::PrefetchWrite => "prefetch_write",
+            Intrinsic::Likely => "likely",
+            Intrinsic::Unlikely => "unlikely",
+            Intrinsic::StackAlloc => "stack_alloc",
         }
     }
 
@@ -2287,6 +3832,9 @@ impl Intrinsic {
         let checked_result = TypeRef::Owned(TypeId::ClassInstance(
             ClassInstance::new(ClassId::checked_int_result()),
         ));
+        let checked_result128 = TypeRef::Owned(TypeId::ClassInstance(
+            ClassInstance::new(ClassId::checked_int128_result()),
+        ));
 
         match self {
             Intrinsic::FloatAdd => TypeRef::float(),
@@ -2327,6 +3875,8 @@ impl Intrinsic {
             Intrinsic::IntCheckedAdd => checked_result,
             Intrinsic::IntCheckedMul => checked_result,
             Intrinsic::IntCheckedSub => checked_result,
+            Intrinsic::IntCheckedDiv => checked_result,
+            Intrinsic::IntCheckedRem => checked_result,
             Intrinsic::Moved => TypeRef::nil(),
             Intrinsic::Panic => TypeRef::Never,
             Intrinsic::StringConcat => TypeRef::string(),
@@ -2343,6 +3893,72 @@ impl Intrinsic {
             Intrinsic::IntCompareSwap => TypeRef::boolean(),
             Intrinsic::SpinLoopHint => TypeRef::nil(),
             Intrinsic::BoolEq => TypeRef::boolean(),
+            Intrinsic::CharToInt => TypeRef::int(),
+            // The caller is expected to check if the integer is a valid
+            // Unicode scalar value.
+            Intrinsic::IntToChar => TypeRef::char(),
+            Intrinsic::Int128CheckedAdd => checked_result128,
+            Intrinsic::Int128CheckedMul => checked_result128,
+            Intrinsic::Int128CheckedSub => checked_result128,
+            Intrinsic::Int128WrappingAdd => TypeRef::foreign_signed_int(128),
+            Intrinsic::Int128WrappingMul => TypeRef::foreign_signed_int(128),
+            Intrinsic::Int128WrappingSub => TypeRef::foreign_signed_int(128),
+            Intrinsic::FloatSqrt => TypeRef::float(),
+            Intrinsic::FloatSin => TypeRef::float(),
+            Intrinsic::FloatCos => TypeRef::float(),
+            Intrinsic::FloatLn => TypeRef::float(),
+            Intrinsic::FloatLog10 => TypeRef::float(),
+            Intrinsic::FloatExp => TypeRef::float(),
+            Intrinsic::FloatPow => TypeRef::float(),
+            // The atomic intrinsics operate on integers of different sizes.
+            // The values they return are extended to an Int, based on the sign
+            // of the type the pointer points to.
+            Intrinsic::AtomicLoad => TypeRef::int(),
+            Intrinsic::AtomicStore => TypeRef::nil(),
+            Intrinsic::AtomicAdd => TypeRef::int(),
+            Intrinsic::AtomicSub => TypeRef::int(),
+            Intrinsic::AtomicAnd => TypeRef::int(),
+            Intrinsic::AtomicOr => TypeRef::int(),
+            Intrinsic::AtomicXor => TypeRef::int(),
+            Intrinsic::AtomicSwap => TypeRef::int(),
+            Intrinsic::MemoryCopy => TypeRef::nil(),
+            Intrinsic::MemorySet => TypeRef::nil(),
+            Intrinsic::MemoryCompare => TypeRef::int(),
+            Intrinsic::PrefetchRead => TypeRef::nil(),
+            Intrinsic::PrefetchWrite => TypeRef::nil(),
+            Intrinsic::Likely => TypeRef::boolean(),
+            Intrinsic::Unlikely => TypeRef::boolean(),
+            // The memory is only valid for the duration of the surrounding
+            // method call, which the type checker enforces by disallowing the
+            // pointer from escaping the method.
+            Intrinsic::StackAlloc => TypeRef::pointer(TypeId::Foreign(
+                ForeignType::Int(8, Sign::Unsigned),
+            )),
+        }
+    }
+
+    /// Returns the types of the arguments of this intrinsic, if these are to
+    /// be checked.
+    ///
+    /// Most intrinsics accept arguments of different types (e.g. integers of
+    /// different sizes), and aren't checked.
+    pub fn argument_types(self) -> Option<Vec<TypeRef>> {
+        let ptr = TypeRef::pointer(TypeId::Foreign(ForeignType::Int(
+            8,
+            Sign::Unsigned,
+        )));
+
+        match self {
+            Intrinsic::MemoryCopy => Some(vec![ptr, ptr, TypeRef::int()]),
+            Intrinsic::MemorySet => {
+                Some(vec![ptr, TypeRef::int(), TypeRef::int()])
+            }
+            Intrinsic::MemoryCompare => Some(vec![ptr, ptr, TypeRef::int()]),
+            Intrinsic::Likely | Intrinsic::Unlikely => {
+                Some(vec![TypeRef::boolean()])
+            }
+            Intrinsic::StackAlloc => Some(vec![TypeRef::int()]),
+            _ => None,
         }
     }
 }
@@ -2414,6 +4030,13 @@ pub enum MethodLookup {
     /// instance.
     StaticOnInstance,
 
+    /// The method exists, but it's a required static method of a trait and
+    /// the receiver is the trait itself.
+    ///
+    /// Such methods don't have a body, so they can only be called through a
+    /// class that implements the trait.
+    RequiredStaticOnTrait,
+
     /// The method doesn't exist.
     None,
 }
@@ -2454,11 +4077,15 @@ pub struct Method {
     module: ModuleId,
     location: Location,
     name: String,
-    documentation: String,
+    documentation: Docs,
+    deprecated: Option<Deprecation>,
     kind: MethodKind,
     call_convention: CallConvention,
     visibility: Visibility,
     inline: Inline,
+
+    /// The inline threshold to use instead of the one of the inline policy.
+    inline_threshold: Option<u16>,
     type_parameters: IndexMap<String, TypeParameterId>,
     arguments: Arguments,
     bounds: TypeBounds,
@@ -2470,6 +4097,19 @@ pub struct Method {
     /// The type of the receiver of the method.
     receiver: TypeRef,
 
+    /// The kind of errors the method may produce, as inferred from its return
+    /// type.
+    throw_kind: ThrowKind,
+
+    /// The side effects the method may produce.
+    effects: Effects,
+
+    /// The arguments and local variables that escape the method.
+    escapes: EscapeTable,
+
+    /// If the method is called or otherwise referred to.
+    used: bool,
+
     /// The fields this method has access to, along with their types.
     field_types: HashMap<String, (FieldId, TypeRef)>,
 
@@ -2485,6 +4125,13 @@ pub struct Method {
     /// class' type parameters, if any. For instance methods, we only include
     /// the shapes of the method's type parameters.
     shapes: Vec<Shape>,
+
+    /// The fields moved out of `self`, and the locations at which they're
+    /// moved.
+    moved_fields: Vec<(FieldId, Location)>,
+
+    /// The conditions that must be met for the method to be included.
+    conditions: Conditions,
 }
 
 impl Method {
@@ -2519,19 +4166,27 @@ impl Method {
             kind,
             call_convention,
             visibility,
-            documentation: String::new(),
+            documentation: Docs::default(),
+            deprecated: None,
             type_parameters: IndexMap::new(),
             bounds: TypeBounds::new(),
             arguments: Arguments::new(),
             return_type: TypeRef::Unknown,
             source: MethodSource::Direct,
             receiver: TypeRef::Unknown,
+            throw_kind: ThrowKind::Unknown,
+            effects: Effects::PURE,
+            escapes: EscapeTable::default(),
+            used: false,
             field_types: HashMap::new(),
             main: false,
             variadic: false,
             specializations: HashMap::new(),
             shapes: Vec::new(),
             inline,
+            inline_threshold: None,
+            moved_fields: Vec::new(),
+            conditions: Conditions::default(),
         };
 
         db.methods.push(method);
@@ -2644,10 +4299,22 @@ impl MethodId {
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn set_deprecated(self, db: &mut Database, value: Deprecation) {
+        self.get_mut(db).deprecated = Some(value);
+    }
+
+    pub fn deprecated(self, db: &Database) -> Option<&Deprecation> {
+        self.get(db).deprecated.as_ref()
+    }
+
+    pub fn is_deprecated(self, db: &Database) -> bool {
+        self.get(db).deprecated.is_some()
+    }
+
+    pub fn documentation(self, db: &Database) -> &Docs {
         let method = self.get(db);
 
         if method.documentation.is_empty() {
@@ -2788,8 +4455,36 @@ impl MethodId {
         self.get(db).module
     }
 
+    /// Infers the throw kind of the method from its return type, and records
+    /// it in the method.
+    ///
+    /// This requires that the `Option` and `Result` types are defined.
+    pub fn infer_throw_kind(self, db: &mut Database) -> ThrowKind {
+        let kind = ThrowKind::for_return_type(db, self.return_type(db));
+
+        self.get_mut(db).throw_kind = kind;
+        kind
+    }
+
+    /// Returns the throw kind of the method.
+    ///
+    /// If the throw kind hasn't been inferred yet, `ThrowKind::Unknown` is
+    /// returned.
+    pub fn throw_kind(self, db: &Database) -> ThrowKind {
+        match self.get(db).throw_kind {
+            ThrowKind::Infer(id) if id.value(db).is_some() => {
+                ThrowKind::for_return_type(db, TypeRef::Placeholder(id))
+            }
+            kind => kind,
+        }
+    }
+
+    pub fn is_fallible(self, db: &Database) -> bool {
+        self.throw_kind(db).is_fallible()
+    }
+
     pub fn ignore_return_value(self, db: &Database) -> bool {
-        self.get(db).return_type == TypeRef::nil()
+        self.return_type(db) == TypeRef::nil()
     }
 
     pub fn set_field_type(
@@ -2826,6 +4521,14 @@ impl MethodId {
         self.get_mut(db).main = true;
     }
 
+    pub fn mark_as_used(self, db: &mut Database) {
+        self.get_mut(db).used = true;
+    }
+
+    pub fn is_used(self, db: &Database) -> bool {
+        self.get(db).used
+    }
+
     pub fn is_main(self, db: &Database) -> bool {
         self.get(db).main
     }
@@ -2881,8 +4584,12 @@ impl MethodId {
         self.get(db).specializations.values().cloned().collect()
     }
 
+    pub fn number_of_specializations(self, db: &Database) -> usize {
+        self.get(db).specializations.len()
+    }
+
     pub fn clone_for_specialization(self, db: &mut Database) -> MethodId {
-        let (module, location, name, vis, kind, source, inline) = {
+        let (module, location, name, vis, kind, source, inline, threshold) = {
             let old = self.get(db);
 
             (
@@ -2893,6 +4600,7 @@ impl MethodId {
                 old.kind,
                 old.source,
                 old.inline,
+                old.inline_threshold,
             )
         };
 
@@ -2900,6 +4608,7 @@ impl MethodId {
 
         new.set_source(db, source);
         new.set_inline(db, inline);
+        new.get_mut(db).inline_threshold = threshold;
         new
     }
 
@@ -2974,6 +4683,16 @@ impl MethodId {
         self.get(db).inline
     }
 
+    pub fn add_effects(self, db: &mut Database, effects: Effects) {
+        let method = self.get_mut(db);
+
+        method.effects = method.effects | effects;
+    }
+
+    pub fn effects(self, db: &Database) -> Effects {
+        self.get(db).effects
+    }
+
     fn get(self, db: &Database) -> &Method {
         &db.methods[self.0 as usize]
     }
@@ -3083,6 +4802,17 @@ pub struct CallInfo {
     pub returns: TypeRef,
     pub dynamic: bool,
     pub type_arguments: TypeArguments,
+
+    /// The class to call the method on instead of using dynamic dispatch, if
+    /// the receiver can only ever be an instance of this class.
+    pub devirtualize: Option<ClassId>,
+
+    /// The trait the method is called through, for qualified calls such as
+    /// `Trait.method(receiver)`.
+    ///
+    /// For such calls `id` is the method provided by the trait, even if the
+    /// class of the receiver defines a method with the same name.
+    pub qualified: Option<TraitInstance>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -3153,6 +4883,10 @@ pub enum ThrowKind {
     Infer(TypePlaceholderId),
     Option(TypeRef),
     Result(TypeRef, TypeRef),
+
+    /// The type isn't used to signal errors, meaning a method returning it
+    /// can't fail.
+    Never,
 }
 
 impl ThrowKind {
@@ -3172,6 +4906,21 @@ impl ThrowKind {
         }
     }
 
+    /// Returns the throw kind of a method with the given return type.
+    fn for_return_type(db: &Database, typ: TypeRef) -> ThrowKind {
+        match typ {
+            TypeRef::Unknown | TypeRef::Error => ThrowKind::Unknown,
+            _ => match typ.throw_kind(db) {
+                ThrowKind::Unknown => ThrowKind::Never,
+                kind => kind,
+            },
+        }
+    }
+
+    pub fn is_fallible(self) -> bool {
+        matches!(self, ThrowKind::Option(_) | ThrowKind::Result(_, _))
+    }
+
     pub fn as_uni(self, db: &Database) -> ThrowKind {
         match self {
             ThrowKind::Result(ok, err) if err.is_owned(db) => {
@@ -3190,6 +4939,7 @@ pub enum Symbol {
     TypeParameter(TypeParameterId),
     Constant(ConstantId),
     Method(MethodId),
+    TypeAlias(TypeAliasId),
 }
 
 impl Symbol {
@@ -3199,6 +4949,7 @@ impl Symbol {
             Symbol::Class(id) => id.is_public(db),
             Symbol::Trait(id) => id.is_public(db),
             Symbol::Constant(id) => id.is_public(db),
+            Symbol::TypeAlias(id) => id.is_public(db),
             _ => true,
         }
     }
@@ -3207,26 +4958,118 @@ impl Symbol {
         !self.is_public(db)
     }
 
-    pub fn is_visible_to(self, db: &Database, module: ModuleId) -> bool {
-        if self.is_public(db) {
-            return true;
+    /// Returns the visibility of the symbol.
+    ///
+    /// Modules and type parameters don't have a visibility, so for these
+    /// symbols `Visibility::Public` is returned.
+    pub fn visibility(self, db: &Database) -> Visibility {
+        match self {
+            Symbol::Method(id) => id.get(db).visibility,
+            Symbol::Class(id) => id.get(db).visibility,
+            Symbol::Trait(id) => id.get(db).visibility,
+            Symbol::Constant(id) => id.get(db).visibility,
+            Symbol::TypeAlias(id) => id.get(db).visibility,
+            Symbol::Module(_) | Symbol::TypeParameter(_) => Visibility::Public,
         }
+    }
 
-        let mod_id = match self {
-            Symbol::Method(id) => id.module(db),
-            Symbol::Class(id) => id.module(db),
-            Symbol::Trait(id) => id.module(db),
-            Symbol::Constant(id) => id.module(db),
-            _ => return true,
-        };
+    /// Returns the module the symbol is defined in.
+    ///
+    /// Type parameters aren't defined in a module, so `None` is returned for
+    /// such symbols.
+    pub fn module(self, db: &Database) -> Option<ModuleId> {
+        match self {
+            Symbol::Class(id) => Some(id.module(db)),
+            Symbol::Trait(id) => Some(id.module(db)),
+            Symbol::Constant(id) => Some(id.module(db)),
+            Symbol::Method(id) => Some(id.module(db)),
+            Symbol::TypeAlias(id) => Some(id.module(db)),
+            Symbol::Module(id) => Some(id),
+            Symbol::TypeParameter(_) => None,
+        }
+    }
+
+    /// Returns the location the symbol is defined at.
+    pub fn location(self, db: &Database) -> Location {
+        match self {
+            Symbol::Class(id) => id.location(db),
+            Symbol::Trait(id) => id.location(db),
+            Symbol::Constant(id) => id.location(db),
+            Symbol::Method(id) => id.location(db),
+            Symbol::TypeAlias(id) => id.location(db),
+            Symbol::Module(_) | Symbol::TypeParameter(_) => Location::default(),
+        }
+    }
 
-        mod_id.has_same_root_namespace(db, module)
+    pub fn is_visible_to(self, db: &Database, module: ModuleId) -> bool {
+        match self.visibility(db) {
+            Visibility::Public => true,
+            // Module symbols are never type private, but if this does happen
+            // we treat them the same as symbols private to the namespace.
+            Visibility::TypePrivate => self
+                .module(db)
+                .map_or(true, |m| m.has_same_root_namespace(db, module)),
+            vis => self
+                .module(db)
+                .map_or(true, |m| vis.allows_access(db, m, module)),
+        }
     }
 }
 
 struct ModuleSymbol {
     symbol: Symbol,
     used: bool,
+
+    /// The location at which the symbol is imported, if it's imported from
+    /// another module.
+    location: Option<Location>,
+
+    /// The module the symbol is imported from, if it's imported.
+    ///
+    /// This is the module named in the import, which isn't necessarily the
+    /// module that defines the symbol.
+    origin: Option<ModuleId>,
+
+    /// A flag indicating that the symbol is re-exported, allowing other
+    /// modules to import it from this module.
+    reexport: bool,
+
+    /// A flag indicating that the symbol is imported using a glob import, and
+    /// may be replaced by a symbol defined or imported explicitly.
+    shadowable: bool,
+
+    /// The conditions that must be met for the import of the symbol to be
+    /// included.
+    conditions: Conditions,
+}
+
+impl ModuleSymbol {
+    fn new(symbol: Symbol) -> ModuleSymbol {
+        ModuleSymbol {
+            symbol,
+            used: false,
+            location: None,
+            origin: None,
+            reexport: false,
+            shadowable: false,
+            conditions: Conditions::default(),
+        }
+    }
+}
+
+/// A symbol that's defined or imported, but never used.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnusedSymbol {
+    /// The module the symbol is defined or imported in.
+    pub module: ModuleId,
+
+    /// The name of the symbol in the module.
+    pub name: String,
+
+    pub symbol: Symbol,
+
+    /// The location of the definition or import.
+    pub location: Location,
 }
 
 /// An Inko module.
@@ -3258,6 +5101,13 @@ pub struct Module {
 
     /// The external methods defined in this module.
     extern_methods: HashMap<String, MethodId>,
+
+    /// The modules this module imports symbols from, in the order they are
+    /// first imported.
+    dependencies: Vec<ModuleId>,
+
+    /// The package this module belongs to.
+    package: Option<PackageId>,
 }
 
 impl Module {
@@ -3279,6 +5129,10 @@ impl Module {
         );
 
         db.module_mapping.insert(name.to_string(), id);
+        db.module_versions
+            .entry(name.logical_name().to_string())
+            .or_default()
+            .push(id);
         db.modules.push(Module {
             name: name.clone(),
             documentation: String::new(),
@@ -3288,6 +5142,8 @@ impl Module {
             constants: Vec::new(),
             symbols: HashMap::default(),
             extern_methods: HashMap::new(),
+            dependencies: Vec::new(),
+            package: None,
         });
         id
     }
@@ -3352,8 +5208,17 @@ impl ModuleId {
             .collect()
     }
 
+    /// Returns `true` if a symbol with the given name is defined or imported.
+    ///
+    /// Symbols imported using a glob import are ignored, as defining or
+    /// importing a symbol with the same name shadows such symbols.
     pub fn symbol_exists(self, db: &Database, name: &str) -> bool {
-        self.get(db).symbols.contains_key(name)
+        self.get(db).symbols.get(name).map_or(false, |v| !v.shadowable)
+    }
+
+    /// Returns `true` if the symbol is imported using a glob import.
+    pub fn symbol_is_shadowable(self, db: &Database, name: &str) -> bool {
+        self.get(db).symbols.get(name).map_or(false, |v| v.shadowable)
     }
 
     pub fn import_symbol(
@@ -3362,17 +5227,11 @@ impl ModuleId {
         name: &str,
     ) -> Option<Symbol> {
         let symbol = self.use_symbol(db, name)?;
-        let module_id = match symbol {
-            Symbol::Class(id) => id.module(db),
-            Symbol::Trait(id) => id.module(db),
-            Symbol::Constant(id) => id.module(db),
-            Symbol::Method(id) => id.module(db),
-            Symbol::Module(id) => id,
-            // Type parameters can't be imported.
-            Symbol::TypeParameter(_) => return None,
-        };
 
-        if self == module_id {
+        // Type parameters can't be imported.
+        let module_id = symbol.module(db)?;
+
+        if self == module_id || self.is_reexported(db, name) {
             Some(symbol)
         } else {
             None
@@ -3380,9 +5239,208 @@ impl ModuleId {
     }
 
     pub fn new_symbol(self, db: &mut Database, name: String, symbol: Symbol) {
-        self.get_mut(db)
+        if let Some(module) = symbol.module(db) {
+            self.add_dependency(db, module);
+        }
+
+        self.get_mut(db).symbols.insert(name, ModuleSymbol::new(symbol));
+    }
+
+    /// Adds a symbol imported from the module `origin`, along with the
+    /// location of the import.
+    pub fn new_imported_symbol(
+        self,
+        db: &mut Database,
+        name: String,
+        symbol: Symbol,
+        origin: ModuleId,
+        location: Location,
+    ) {
+        self.add_imported_symbol(db, name, symbol, origin, location, false);
+    }
+
+    /// Adds a symbol imported from the module `origin`, and re-exports it such
+    /// that other modules can import it from this module.
+    ///
+    /// It's up to the caller to ensure the symbol is visible to the modules
+    /// importing it.
+    pub fn new_reexported_symbol(
+        self,
+        db: &mut Database,
+        name: String,
+        symbol: Symbol,
+        origin: ModuleId,
+        location: Location,
+    ) {
+        self.add_imported_symbol(db, name, symbol, origin, location, true);
+    }
+
+    /// Imports all public symbols defined or re-exported by the module `from`.
+    ///
+    /// The imported symbols are shadowable, meaning that symbols defined or
+    /// imported explicitly take precedence over them. If a symbol with the
+    /// same name is already imported from a different glob import, the
+    /// existing symbol is kept. The names of such conflicting symbols are
+    /// returned, sorted by their names.
+    pub fn import_all_public_symbols(
+        self,
+        db: &mut Database,
+        from: ModuleId,
+    ) -> Vec<String> {
+        let mut symbols: Vec<_> = from
+            .get(db)
+            .symbols
+            .iter()
+            .filter(|(name, value)| {
+                !name.starts_with('$')
+                    && value.symbol.is_public(db)
+                    && (value.reexport || value.symbol.module(db) == Some(from))
+            })
+            .map(|(name, value)| (name.clone(), value.symbol))
+            .collect();
+        let mut conflicts = Vec::new();
+
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        self.add_dependency(db, from);
+
+        for (name, symbol) in symbols {
+            let module = self.get_mut(db);
+
+            match module.symbols.get(&name) {
+                Some(ex) if !ex.shadowable || ex.symbol == symbol => continue,
+                Some(_) => {
+                    conflicts.push(name);
+                    continue;
+                }
+                None => {}
+            }
+
+            module.symbols.insert(
+                name,
+                ModuleSymbol {
+                    origin: Some(from),
+                    shadowable: true,
+                    ..ModuleSymbol::new(symbol)
+                },
+            );
+        }
+
+        conflicts
+    }
+
+    /// Returns `true` if the symbol is re-exported by this module.
+    pub fn is_reexported(self, db: &Database, name: &str) -> bool {
+        self.get(db).symbols.get(name).map_or(false, |v| v.reexport)
+    }
+
+    /// Returns the module the symbol is imported from, if it's imported.
+    pub fn symbol_origin(self, db: &Database, name: &str) -> Option<ModuleId> {
+        self.get(db).symbols.get(name).and_then(|v| v.origin)
+    }
+
+    /// Returns the symbols re-exported by this module, sorted by their names.
+    pub fn reexports(self, db: &Database) -> Vec<(String, Symbol)> {
+        let mut symbols: Vec<_> = self
+            .get(db)
+            .symbols
+            .iter()
+            .filter(|(_, value)| value.reexport)
+            .map(|(name, value)| (name.clone(), value.symbol))
+            .collect();
+
+        symbols.sort_by(|a, b| a.0.cmp(&b.0));
+        symbols
+    }
+
+    fn add_imported_symbol(
+        self,
+        db: &mut Database,
+        name: String,
+        symbol: Symbol,
+        origin: ModuleId,
+        location: Location,
+        reexport: bool,
+    ) {
+        // Symbols imported through a re-export depend on the re-exporting
+        // module, as changing the re-exports affects this module.
+        self.add_dependency(db, origin);
+        self.get_mut(db).symbols.insert(
+            name,
+            ModuleSymbol {
+                location: Some(location),
+                origin: Some(origin),
+                reexport,
+                ..ModuleSymbol::new(symbol)
+            },
+        );
+    }
+
+    /// Returns the modules this module imports symbols from.
+    pub fn dependencies(self, db: &Database) -> &Vec<ModuleId> {
+        &self.get(db).dependencies
+    }
+
+    fn add_dependency(self, db: &mut Database, module: ModuleId) {
+        let deps = &mut self.get_mut(db).dependencies;
+
+        if module != self && !deps.contains(&module) {
+            deps.push(module);
+        }
+    }
+
+    /// Returns the symbols imported into this module that are never used,
+    /// sorted by their names.
+    pub fn unused_imports(self, db: &Database) -> Vec<UnusedSymbol> {
+        let mut unused: Vec<_> = self
+            .get(db)
+            .symbols
+            .iter()
+            .filter(|(name, sym)| {
+                !sym.used
+                    && !sym.reexport
+                    && !sym.shadowable
+                    && !name.starts_with('$')
+                    && sym.symbol.module(db).map_or(false, |m| m != self)
+            })
+            .map(|(name, sym)| UnusedSymbol {
+                module: self,
+                name: name.clone(),
+                symbol: sym.symbol,
+                location: sym.location.unwrap_or_default(),
+            })
+            .collect();
+
+        unused.sort_by(|a, b| a.name.cmp(&b.name));
+        unused
+    }
+
+    /// Returns the private symbols defined in this module that are never
+    /// used, sorted by their names.
+    ///
+    /// Methods are excluded, as these are handled separately by
+    /// `Database::unused_symbols()`.
+    fn unused_definitions(self, db: &Database) -> Vec<UnusedSymbol> {
+        let mut unused: Vec<_> = self
+            .get(db)
             .symbols
-            .insert(name, ModuleSymbol { symbol, used: false });
+            .iter()
+            .filter(|(name, sym)| {
+                !sym.used
+                    && !name.starts_with('$')
+                    && !matches!(sym.symbol, Symbol::Method(_))
+                    && sym.symbol.is_private(db)
+                    && sym.symbol.module(db) == Some(self)
+            })
+            .map(|(name, sym)| UnusedSymbol {
+                module: self,
+                name: name.clone(),
+                symbol: sym.symbol,
+                location: sym.symbol.location(db),
+            })
+            .collect();
+
+        unused.sort_by(|a, b| a.name.cmp(&b.name));
+        unused
     }
 
     pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
@@ -3447,10 +5505,55 @@ impl ModuleId {
         self.get(db).class
     }
 
+    /// Returns `true` if `other` is the same module as `self`, or the module
+    /// containing the tests for `self`.
+    ///
+    /// The tests of a module are found in a module of which the last
+    /// component is prefixed with `test_`, such that the tests for `std.array`
+    /// are found in `std.test_array`.
+    fn is_same_or_test_module(self, db: &Database, other: ModuleId) -> bool {
+        if self == other {
+            return true;
+        }
+
+        let ours = self.name(db);
+        let theirs = other.name(db);
+
+        if ours.version() != theirs.version() {
+            return false;
+        }
+
+        let ours = ours.logical_name();
+        let theirs = theirs.logical_name();
+        let (our_parent, our_name) = match ours.rsplit_once(SEPARATOR) {
+            Some((parent, name)) => (Some(parent), name),
+            None => (None, ours),
+        };
+        let (their_parent, their_name) = match theirs.rsplit_once(SEPARATOR) {
+            Some((parent, name)) => (Some(parent), name),
+            None => (None, theirs),
+        };
+
+        our_parent == their_parent
+            && their_name.strip_prefix("test_") == Some(our_name)
+    }
+
     fn has_same_root_namespace(self, db: &Database, other: ModuleId) -> bool {
+        // Unrelated packages may use the same root namespace, so when the
+        // packages are known we use those instead of the module names.
+        if let Some(same) = self.is_same_package(db, other) {
+            return same;
+        }
+
         let ours = self.name(db);
         let theirs = other.name(db);
 
+        // Different versions of the same package are different packages, and
+        // thus can't access each other's private symbols.
+        if ours.version() != theirs.version() {
+            return false;
+        }
+
         if ours.head() == theirs.head() {
             return true;
         }
@@ -3463,7 +5566,7 @@ impl ModuleId {
         // symbols from the top-level module `foo`, but not the other way
         // around.
         theirs
-            .as_str()
+            .logical_name()
             .strip_prefix("test_")
             .map_or(false, |name| ours.head() == name)
     }
@@ -3549,9 +5652,13 @@ pub struct Constant {
     module: ModuleId,
     location: Location,
     name: String,
-    documentation: String,
+    documentation: Docs,
+    deprecated: Option<Deprecation>,
     value_type: TypeRef,
     visibility: Visibility,
+
+    /// The value of the constant, if it has been evaluated.
+    value: Option<Value>,
 }
 
 impl Constant {
@@ -3562,6 +5669,31 @@ impl Constant {
         name: String,
         visibility: Visibility,
         value_type: TypeRef,
+    ) -> ConstantId {
+        let id = Constant::alloc_associated(
+            db,
+            module,
+            location,
+            name.clone(),
+            visibility,
+            value_type,
+        );
+
+        module.new_symbol(db, name, Symbol::Constant(id));
+        id
+    }
+
+    /// Allocates a constant defined by a trait or class.
+    ///
+    /// Unlike `Constant::alloc()`, this doesn't define a symbol in the module,
+    /// as such constants are accessed through the type that defines them.
+    pub fn alloc_associated(
+        db: &mut Database,
+        module: ModuleId,
+        location: Location,
+        name: String,
+        visibility: Visibility,
+        value_type: TypeRef,
     ) -> ConstantId {
         let global_id = db.constants.len();
         let local_id = module.get(db).constants.len();
@@ -3572,17 +5704,18 @@ impl Constant {
             id: local_id as u16,
             module,
             location,
-            name: name.clone(),
-            documentation: String::new(),
+            name,
+            documentation: Docs::default(),
+            deprecated: None,
             value_type,
             visibility,
+            value: None,
         };
 
         let const_id = ConstantId(global_id);
 
-        module.get_mut(db).constants.push(const_id);
-        module.new_symbol(db, name, Symbol::Constant(const_id));
         db.constants.push(constant);
+        module.get_mut(db).constants.push(const_id);
         const_id
     }
 }
@@ -3620,13 +5753,34 @@ impl ConstantId {
     }
 
     pub fn set_documentation(self, db: &mut Database, value: String) {
-        self.get_mut(db).documentation = value;
+        self.get_mut(db).documentation = Docs::parse(value);
     }
 
-    pub fn documentation(self, db: &Database) -> &String {
+    pub fn set_deprecated(self, db: &mut Database, value: Deprecation) {
+        self.get_mut(db).deprecated = Some(value);
+    }
+
+    pub fn deprecated(self, db: &Database) -> Option<&Deprecation> {
+        self.get(db).deprecated.as_ref()
+    }
+
+    pub fn is_deprecated(self, db: &Database) -> bool {
+        self.get(db).deprecated.is_some()
+    }
+
+    pub fn documentation(self, db: &Database) -> &Docs {
         &self.get(db).documentation
     }
 
+    pub fn set_value(self, db: &mut Database, value: Value) {
+        self.get_mut(db).value = Some(value);
+    }
+
+    /// Returns the value of the constant, if it has been evaluated.
+    pub fn value(self, db: &Database) -> Option<&Value> {
+        self.get(db).value.as_ref()
+    }
+
     fn get(self, db: &Database) -> &Constant {
         &db.constants[self.0]
     }
@@ -3636,19 +5790,74 @@ impl ConstantId {
     }
 }
 
+/// The way a closure captures a variable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// The value is moved into the closure.
+    ///
+    /// Value types are always captured this way, as these are copied.
+    Move,
+
+    /// The closure captures an immutable borrow of the value.
+    Ref,
+
+    /// The closure captures a mutable borrow of the value.
+    Mut,
+}
+
+impl CaptureMode {
+    /// Returns the capture mode for a variable captured as the given type.
+    pub fn for_type(db: &Database, captured_as: TypeRef) -> CaptureMode {
+        match captured_as {
+            TypeRef::Ref(_) | TypeRef::UniRef(_) => CaptureMode::Ref,
+            TypeRef::Mut(_) | TypeRef::UniMut(_) => CaptureMode::Mut,
+            TypeRef::Placeholder(id) => id
+                .value(db)
+                .map_or(CaptureMode::Move, |v| CaptureMode::for_type(db, v)),
+            _ => CaptureMode::Move,
+        }
+    }
+
+    pub fn is_borrow(self) -> bool {
+        matches!(self, CaptureMode::Ref | CaptureMode::Mut)
+    }
+}
+
+/// A variable captured by a closure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capture {
+    pub variable: VariableId,
+
+    /// The type the variable is captured as.
+    pub captured_as: TypeRef,
+    pub mode: CaptureMode,
+
+    /// The locations of the expressions in the closure that capture the
+    /// variable, in the order they are checked in.
+    pub locations: Vec<Location>,
+}
+
 /// An anonymous function that can optionally capture outer variables.
 ///
-/// Unlike methods, closures don't support type parameters. This makes it easier
-/// to implement them, and generic closures aren't that useful to begin with. Of
-/// course closures _can_ refer to type parameters defined in the surrounding
-/// method or type.
+/// Closures can refer to type parameters defined in the surrounding method or
+/// type. In addition, closure _types_ may define their own type parameters
+/// (e.g. `fn [T] (ref T, ref T) -> Bool`), in which case the closure is
+/// polymorphic and its type parameters are assigned new values every time the
+/// closure is called.
+///
+/// Unlike methods, closures aren't specialized for the types they're called
+/// with. For this reason the type parameters of a closure can only be used as
+/// the type of a reference, and are never assigned value types.
 #[derive(Clone)]
 pub struct Closure {
     moving: bool,
 
-    /// The variables captured by this closure, and the types the variables are
-    /// captured as.
-    captured: HashSet<(VariableId, TypeRef)>,
+    /// The type parameters the closure is polymorphic over.
+    type_parameters: Vec<TypeParameterId>,
+
+    /// The variables captured by this closure, in the order they are first
+    /// captured in.
+    captured: Vec<Capture>,
 
     /// The type of `self` as captured by the closure.
     captured_self_type: Option<TypeRef>,
@@ -3673,8 +5882,9 @@ impl Closure {
     fn new(moving: bool) -> Self {
         Self {
             moving,
+            type_parameters: Vec::new(),
             captured_self_type: None,
-            captured: HashSet::new(),
+            captured: Vec::new(),
             arguments: Arguments::new(),
             return_type: TypeRef::Unknown,
         }
@@ -3721,6 +5931,64 @@ impl ClosureId {
         self.get(db).moving
     }
 
+    pub fn add_type_parameter(
+        self,
+        db: &mut Database,
+        parameter: TypeParameterId,
+    ) {
+        self.get_mut(db).type_parameters.push(parameter);
+    }
+
+    pub fn type_parameters(self, db: &Database) -> Vec<TypeParameterId> {
+        self.get(db).type_parameters.clone()
+    }
+
+    pub fn is_generic(self, db: &Database) -> bool {
+        !self.get(db).type_parameters.is_empty()
+    }
+
+    /// Returns the type arguments to use for a single call to this closure.
+    ///
+    /// Each type parameter is assigned a new placeholder, such that every call
+    /// can infer different types for the parameters.
+    pub fn instantiate(self, db: &mut Database) -> TypeArguments {
+        let mut args = TypeArguments::new();
+
+        for param in self.type_parameters(db) {
+            let val = TypeRef::placeholder(db, Some(param));
+
+            args.assign(param, val);
+        }
+
+        args
+    }
+
+    /// Returns `true` if the type parameters of this closure are only used as
+    /// the types of references (e.g. `ref T`) of arguments and the return type.
+    ///
+    /// Types such as `T` and `Array[ref T]` require specializing the closure
+    /// for the types assigned to `T`, which isn't supported.
+    pub fn type_parameters_used_as_references(self, db: &Database) -> bool {
+        let closure = self.get(db);
+        let params = &closure.type_parameters;
+
+        closure
+            .arguments
+            .mapping
+            .values()
+            .map(|a| a.value_type)
+            .chain([closure.return_type])
+            .all(|typ| match typ {
+                TypeRef::Ref(TypeId::TypeParameter(id))
+                | TypeRef::Mut(TypeId::TypeParameter(id))
+                    if params.contains(&id) =>
+                {
+                    true
+                }
+                _ => !params.iter().any(|&p| uses_type_parameter(db, typ, p)),
+            })
+    }
+
     pub fn set_captured_self_type(
         self,
         db: &mut Database,
@@ -3733,27 +6001,88 @@ impl ClosureId {
         self.get(db).captured_self_type
     }
 
+    /// Records the capture of a variable by an expression at the given
+    /// location.
+    ///
+    /// Capturing the same variable multiple times only records the additional
+    /// locations.
     pub fn add_capture(
         self,
         db: &mut Database,
         variable: VariableId,
         captured_as: TypeRef,
+        location: Location,
     ) {
-        self.get_mut(db).captured.insert((variable, captured_as));
+        let mode = CaptureMode::for_type(db, captured_as);
+        let captured = &mut self.get_mut(db).captured;
+
+        if let Some(cap) = captured
+            .iter_mut()
+            .find(|c| c.variable == variable && c.captured_as == captured_as)
+        {
+            if !cap.locations.contains(&location) {
+                cap.locations.push(location);
+            }
+
+            return;
+        }
+
+        captured.push(Capture {
+            variable,
+            captured_as,
+            mode,
+            locations: vec![location],
+        });
     }
 
     pub fn captured(self, db: &Database) -> Vec<(VariableId, TypeRef)> {
-        self.get(db).captured.iter().cloned().collect()
+        self.get(db)
+            .captured
+            .iter()
+            .map(|c| (c.variable, c.captured_as))
+            .collect()
+    }
+
+    pub fn captures(self, db: &Database) -> &[Capture] {
+        &self.get(db).captured
+    }
+
+    /// Returns the capture of the given variable, if it's captured.
+    pub fn capture(
+        self,
+        db: &Database,
+        variable: VariableId,
+    ) -> Option<&Capture> {
+        self.get(db).captured.iter().find(|c| c.variable == variable)
     }
 
     pub fn arguments(self, db: &Database) -> Vec<Argument> {
         self.get(db).arguments.mapping.values().cloned().collect()
     }
 
+    /// Returns `true` if the closure can be lowered to a C function.
+    ///
+    /// This is only possible for closures that don't capture any variables or
+    /// `self`, as C function pointers don't have an environment.
+    pub fn allow_as_foreign_function(self, db: &Database) -> bool {
+        let closure = self.get(db);
+
+        closure.captured.is_empty()
+            && closure.captured_self_type.is_none()
+            && closure.type_parameters.is_empty()
+    }
+
     pub fn can_infer_as_uni(self, db: &Database) -> bool {
         let closure = self.get(db);
 
-        if !closure.captured.iter().all(|(_, typ)| typ.is_sendable(db)) {
+        // Borrowed values may be used outside of the closure, so these can only
+        // be captured if the values are copied.
+        if !closure.captured.iter().all(|c| match c.mode {
+            CaptureMode::Move => c.captured_as.is_sendable(db),
+            CaptureMode::Ref | CaptureMode::Mut => {
+                c.captured_as.is_value_type(db)
+            }
+        }) {
             return false;
         }
 
@@ -3845,6 +6174,9 @@ pub enum Shape {
     /// The nil singleton.
     Nil,
 
+    /// A Unicode scalar value, stored as a 32-bits unsigned integer.
+    Char,
+
     /// An owned value that uses atomic reference counting.
     Atomic,
 
@@ -3866,6 +6198,12 @@ pub enum Shape {
     /// values into a single common `ClassInstance`, such that the comparison
     /// _is_ reliable.
     Stack(ClassInstance),
+
+    /// A constant integer assigned to a constant type parameter.
+    ///
+    /// Each constant results in a separate specialization, such that
+    /// `Matrix[2]` and `Matrix[3]` are different types at runtime.
+    Constant(i64),
 }
 
 impl Shape {
@@ -3882,7 +6220,8 @@ impl Shape {
             Shape::Int(64, Sign::Signed) => false,
             Shape::Int(_, Sign::Unsigned) => true,
             Shape::Int(_, Sign::Signed) => true,
-            Shape::Float(32) => true,
+            Shape::Float(64) => false,
+            Shape::Float(_) => true,
             _ => false,
         }
     }
@@ -3936,7 +6275,16 @@ pub enum TypeRef {
     Placeholder(TypePlaceholderId),
 
     /// A pointer to a value.
+    ///
+    /// Such pointers may be NULL, and thus must be checked before they're
+    /// dereferenced.
     Pointer(TypeId),
+
+    /// A pointer to a value that is known to not be NULL.
+    NonNullPointer(TypeId),
+
+    /// A value of one of several types.
+    Union(UnionId),
 }
 
 impl TypeRef {
@@ -3958,9 +6306,15 @@ impl TypeRef {
         ))
     }
 
-    pub fn float() -> TypeRef {
+    pub fn float() -> TypeRef {
+        TypeRef::Owned(TypeId::ClassInstance(ClassInstance::new(
+            ClassId::float(),
+        )))
+    }
+
+    pub fn char() -> TypeRef {
         TypeRef::Owned(TypeId::ClassInstance(ClassInstance::new(
-            ClassId::float(),
+            ClassId::char(),
         )))
     }
 
@@ -4004,10 +6358,18 @@ impl TypeRef {
         TypeRef::Owned(TypeId::Foreign(ForeignType::Float(size)))
     }
 
+    pub fn foreign_function(id: ForeignFunctionId) -> TypeRef {
+        TypeRef::Owned(TypeId::Foreign(ForeignType::Function(id)))
+    }
+
     pub fn pointer(of: TypeId) -> TypeRef {
         TypeRef::Pointer(of)
     }
 
+    pub fn non_null_pointer(of: TypeId) -> TypeRef {
+        TypeRef::NonNullPointer(of)
+    }
+
     pub fn module(id: ModuleId) -> TypeRef {
         TypeRef::Owned(TypeId::Module(id))
     }
@@ -4019,9 +6381,107 @@ impl TypeRef {
         TypeRef::Placeholder(TypePlaceholder::alloc(db, required))
     }
 
+    /// Returns a union of the given types.
+    ///
+    /// Members that are unions themselves are flattened into the new union,
+    /// and duplicate members are removed. If only a single member remains,
+    /// that member is returned as-is instead of a union. An empty list of
+    /// members produces the `Never` type, as there are no values of such a
+    /// type.
+    pub fn union(db: &mut Database, members: Vec<TypeRef>) -> TypeRef {
+        let mut flat = Vec::with_capacity(members.len());
+
+        for typ in members {
+            let new = match typ {
+                TypeRef::Union(id) => id.members(db).clone(),
+                _ => vec![typ],
+            };
+
+            for typ in new {
+                if !flat.contains(&typ) {
+                    flat.push(typ);
+                }
+            }
+        }
+
+        match flat.len() {
+            0 => TypeRef::Never,
+            1 => flat[0],
+            _ => TypeRef::Union(Union::alloc(db, flat)),
+        }
+    }
+
+    /// Returns an owned tuple of the given types.
+    ///
+    /// If there's a builtin tuple class for the number of types, an instance
+    /// of that class is returned. Otherwise the tuple is represented using
+    /// `TypeId::Tuple`.
+    pub fn tuple(db: &mut Database, elements: Vec<TypeRef>) -> TypeRef {
+        let class = ClassId::tuple(elements.len())
+            .filter(|id| id.number_of_type_parameters(db) == elements.len());
+        let id = if let Some(class) = class {
+            let mut args = TypeArguments::new();
+
+            for (param, typ) in
+                class.type_parameters(db).into_iter().zip(elements)
+            {
+                args.assign(param, typ);
+            }
+
+            TypeId::ClassInstance(ClassInstance::generic(db, class, args))
+        } else {
+            TypeId::Tuple(Tuple::alloc(db, elements))
+        };
+
+        TypeRef::Owned(id)
+    }
+
+    /// Returns a constant to assign to a constant type parameter.
+    pub fn constant(value: i64) -> TypeRef {
+        TypeRef::Owned(TypeId::Constant(value))
+    }
+
+    /// Returns the value of a constant type argument.
+    pub fn as_constant(self, db: &Database) -> Option<i64> {
+        match self.type_id(db) {
+            Ok(TypeId::Constant(val)) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the types of the values of a tuple.
+    pub fn tuple_elements(self, db: &Database) -> Option<Vec<TypeRef>> {
+        match self.type_id(db) {
+            Ok(TypeId::Tuple(id)) => Some(id.elements(db).clone()),
+            Ok(TypeId::ClassInstance(ins))
+                if ins.instance_of().kind(db).is_tuple() =>
+            {
+                let args = ins.type_arguments(db)?;
+
+                ins.instance_of()
+                    .type_parameters(db)
+                    .into_iter()
+                    .map(|p| args.get(p))
+                    .collect()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn union_id(self, db: &Database) -> Option<UnionId> {
+        match self {
+            TypeRef::Union(id) => Some(id),
+            TypeRef::Placeholder(id) => {
+                id.value(db).and_then(|v| v.union_id(db))
+            }
+            _ => None,
+        }
+    }
+
     pub fn type_id(self, db: &Database) -> Result<TypeId, TypeRef> {
         match self {
             TypeRef::Pointer(id)
+            | TypeRef::NonNullPointer(id)
             | TypeRef::Owned(id)
             | TypeRef::Uni(id)
             | TypeRef::Ref(id)
@@ -4036,6 +6496,44 @@ impl TypeRef {
         }
     }
 
+    /// Expands a type alias into the type it aliases, retaining the ownership
+    /// of `self`.
+    ///
+    /// The type parameters of the alias are _not_ replaced with the alias'
+    /// type arguments, as this is up to the caller.
+    ///
+    /// If `self` isn't an alias, a `None` is returned.
+    pub fn expand_alias(self, db: &Database) -> Option<TypeRef> {
+        let ins = match self {
+            TypeRef::Owned(TypeId::TypeAlias(ins))
+            | TypeRef::Uni(TypeId::TypeAlias(ins))
+            | TypeRef::Ref(TypeId::TypeAlias(ins))
+            | TypeRef::Mut(TypeId::TypeAlias(ins))
+            | TypeRef::UniRef(TypeId::TypeAlias(ins))
+            | TypeRef::UniMut(TypeId::TypeAlias(ins))
+            | TypeRef::Any(TypeId::TypeAlias(ins))
+            | TypeRef::Pointer(TypeId::TypeAlias(ins))
+            | TypeRef::NonNullPointer(TypeId::TypeAlias(ins)) => ins,
+            TypeRef::Placeholder(p) => {
+                return p.value(db).and_then(|v| v.expand_alias(db));
+            }
+            _ => return None,
+        };
+        let typ = ins.instance_of.aliased_type(db);
+        let expanded = match self {
+            TypeRef::Uni(_) => typ.as_uni(db),
+            TypeRef::Ref(_) => typ.as_ref(db),
+            TypeRef::Mut(_) => typ.as_mut(db),
+            TypeRef::UniRef(_) => typ.as_uni_ref(db),
+            TypeRef::UniMut(_) => typ.as_uni_mut(db),
+            TypeRef::Pointer(_) => typ.as_pointer(db),
+            TypeRef::NonNullPointer(_) => typ.as_pointer(db).as_non_null(db),
+            _ => typ,
+        };
+
+        Some(expanded)
+    }
+
     pub fn closure_id(self, db: &Database) -> Option<ClosureId> {
         if let Ok(TypeId::Closure(id)) = self.type_id(db) {
             Some(id)
@@ -4072,7 +6570,7 @@ impl TypeRef {
                 true
             }
             TypeRef::Owned(TypeId::Foreign(_)) => true,
-            TypeRef::Pointer(_) => true,
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => true,
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.is_foreign_type(db))
             }
@@ -4092,7 +6590,7 @@ impl TypeRef {
 
     pub fn is_pointer(self, db: &Database) -> bool {
         match self {
-            TypeRef::Pointer(_) => true,
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => true,
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.is_pointer(db))
             }
@@ -4100,6 +6598,43 @@ impl TypeRef {
         }
     }
 
+    /// Returns `true` if `self` is a pointer that may be NULL.
+    pub fn is_nullable_pointer(self, db: &Database) -> bool {
+        match self {
+            TypeRef::Pointer(_) => true,
+            TypeRef::Placeholder(id) => {
+                id.value(db).map_or(false, |v| v.is_nullable_pointer(db))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a pointer of this type can be dereferenced without
+    /// first checking if it's NULL.
+    pub fn allow_dereference(self, db: &Database) -> bool {
+        match self {
+            TypeRef::NonNullPointer(_) => true,
+            TypeRef::Placeholder(id) => {
+                id.value(db).map_or(false, |v| v.allow_dereference(db))
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns a non-NULL version of a pointer type.
+    ///
+    /// This is used after a pointer is explicitly checked for NULL, such that
+    /// it can be dereferenced.
+    pub fn as_non_null(self, db: &Database) -> TypeRef {
+        match self {
+            TypeRef::Pointer(id) => TypeRef::NonNullPointer(id),
+            TypeRef::Placeholder(id) => {
+                id.value(db).map_or(self, |v| v.as_non_null(db))
+            }
+            _ => self,
+        }
+    }
+
     pub fn is_error(self, db: &Database) -> bool {
         match self {
             TypeRef::Error => true,
@@ -4299,7 +6834,8 @@ impl TypeRef {
             | TypeRef::Uni(_)
             | TypeRef::Ref(_)
             | TypeRef::Mut(_)
-            | TypeRef::Pointer(_) => true,
+            | TypeRef::Pointer(_)
+            | TypeRef::NonNullPointer(_) => true,
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.has_ownership(db))
             }
@@ -4364,7 +6900,8 @@ impl TypeRef {
             | TypeRef::Uni(_)
             | TypeRef::Mut(_)
             | TypeRef::UniMut(_)
-            | TypeRef::Pointer(_) => true,
+            | TypeRef::Pointer(_)
+            | TypeRef::NonNullPointer(_) => true,
             TypeRef::Any(
                 TypeId::TypeParameter(id) | TypeId::RigidTypeParameter(id),
             ) => id.is_mutable(db),
@@ -4416,7 +6953,7 @@ impl TypeRef {
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(true, |v| v.is_sendable(db))
             }
-            _ => self.is_value_type(db),
+            _ => self.is_value_type(db) && self.unsendable_field(db).is_none(),
         }
     }
 
@@ -4426,6 +6963,10 @@ impl TypeRef {
             TypeRef::Owned(TypeId::ClassInstance(id)) => {
                 let class = id.instance_of;
 
+                if class.unsendable_field(db).is_some() {
+                    return false;
+                }
+
                 if class.is_generic(db)
                     && !id
                         .type_arguments(db)
@@ -4441,6 +6982,9 @@ impl TypeRef {
                     .into_iter()
                     .all(|f| f.value_type(db).is_sendable_output(db))
             }
+            TypeRef::Owned(TypeId::Tuple(id)) => {
+                id.elements(db).iter().all(|v| v.is_sendable_output(db))
+            }
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(true, |v| v.is_sendable_output(db))
             }
@@ -4449,6 +6993,16 @@ impl TypeRef {
     }
 
     pub fn cast_according_to(self, db: &Database, other: TypeRef) -> Self {
+        // A non-NULL pointer is coerced into a nullable pointer if that's what
+        // is expected, but the reverse isn't allowed.
+        if let TypeRef::NonNullPointer(id) = self {
+            return if other.is_nullable_pointer(db) {
+                TypeRef::Pointer(id)
+            } else {
+                self
+            };
+        }
+
         if self.is_value_type(db) {
             return if other.is_uni(db) {
                 self.as_uni(db)
@@ -4893,8 +7447,8 @@ impl TypeRef {
             TypeRef::Owned(TypeId::Module(_))
             | TypeRef::Ref(TypeId::Module(_))
             | TypeRef::Mut(TypeId::Module(_)) => true,
-            TypeRef::Owned(TypeId::Foreign(_)) => true,
-            TypeRef::Pointer(_) => true,
+            TypeRef::Owned(TypeId::Foreign(_) | TypeId::Constant(_)) => true,
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => true,
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.is_value_type(db))
             }
@@ -4917,14 +7471,18 @@ impl TypeRef {
                 }
                 TypeId::TypeParameter(tid)
                 | TypeId::RigidTypeParameter(tid) => tid.is_stack_allocated(db),
-                TypeId::Foreign(_) => true,
+                TypeId::Foreign(_) | TypeId::Constant(_) => true,
                 _ => false,
             },
-            TypeRef::Error | TypeRef::Pointer(_) => true,
+            TypeRef::Error
+            | TypeRef::Pointer(_)
+            | TypeRef::NonNullPointer(_) => true,
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.is_stack_allocated(db))
             }
-            TypeRef::Never | TypeRef::Unknown => false,
+            // Values of a union type may be of different sizes, so they're
+            // always boxed.
+            TypeRef::Never | TypeRef::Unknown | TypeRef::Union(_) => false,
         }
     }
 
@@ -4942,7 +7500,6 @@ impl TypeRef {
                 {
                     ins.type_arguments(db)
                         .unwrap()
-                        .mapping
                         .values()
                         .all(|v| v.is_inferred(db))
                 }
@@ -4951,7 +7508,6 @@ impl TypeRef {
                 {
                     ins.type_arguments(db)
                         .unwrap()
-                        .mapping
                         .values()
                         .all(|v| v.is_inferred(db))
                 }
@@ -4961,11 +7517,17 @@ impl TypeRef {
                         .all(|arg| arg.value_type.is_inferred(db))
                         && id.return_type(db).is_inferred(db)
                 }
+                TypeId::Tuple(id) => {
+                    id.elements(db).iter().all(|v| v.is_inferred(db))
+                }
                 _ => true,
             },
             TypeRef::Placeholder(id) => {
                 id.value(db).map_or(false, |v| v.is_inferred(db))
             }
+            TypeRef::Union(id) => {
+                id.members(db).iter().all(|v| v.is_inferred(db))
+            }
             _ => true,
         }
     }
@@ -4978,7 +7540,8 @@ impl TypeRef {
             | TypeRef::Mut(TypeId::ClassInstance(ins))
             | TypeRef::UniMut(TypeId::ClassInstance(ins))
             | TypeRef::UniRef(TypeId::ClassInstance(ins))
-            | TypeRef::Pointer(TypeId::ClassInstance(ins)) => {
+            | TypeRef::Pointer(TypeId::ClassInstance(ins))
+            | TypeRef::NonNullPointer(TypeId::ClassInstance(ins)) => {
                 Some(ins.instance_of)
             }
             TypeRef::Owned(TypeId::Class(id)) => Some(id),
@@ -5039,6 +7602,123 @@ impl TypeRef {
         )))
     }
 
+    pub fn map_type(
+        db: &mut Database,
+        key: TypeRef,
+        value: TypeRef,
+    ) -> TypeRef {
+        let class = db.map_class();
+        let params = class.type_parameters(db);
+        let mut args = TypeArguments::new();
+
+        args.assign(params[0], key);
+        args.assign(params[1], value);
+
+        TypeRef::Owned(TypeId::ClassInstance(ClassInstance::generic(
+            db, class, args,
+        )))
+    }
+
+    pub fn set_type(db: &mut Database, value: TypeRef) -> TypeRef {
+        let class = db.set_class();
+        let params = class.type_parameters(db);
+        let mut args = TypeArguments::new();
+
+        args.assign(params[0], value);
+
+        TypeRef::Owned(TypeId::ClassInstance(ClassInstance::generic(
+            db, class, args,
+        )))
+    }
+
+    /// Returns an owned `Future` resolving to a value of the given type.
+    ///
+    /// Calls to async methods that return a value produce such a future. If
+    /// the `std.sync` module isn't loaded, `None` is returned.
+    pub fn future_type(db: &mut Database, value: TypeRef) -> Option<TypeRef> {
+        TypeRef::sync_type(db, db.future_class()?, value)
+    }
+
+    /// Returns an owned `Promise` used to resolve a `Future` to a value of the
+    /// given type.
+    ///
+    /// If the `std.sync` module isn't loaded, `None` is returned.
+    pub fn promise_type(db: &mut Database, value: TypeRef) -> Option<TypeRef> {
+        TypeRef::sync_type(db, db.promise_class()?, value)
+    }
+
+    fn sync_type(
+        db: &mut Database,
+        class: ClassId,
+        value: TypeRef,
+    ) -> Option<TypeRef> {
+        let param = *class.type_parameters(db).first()?;
+        let mut args = TypeArguments::new();
+
+        args.assign(param, value);
+
+        Some(TypeRef::Owned(TypeId::ClassInstance(ClassInstance::generic(
+            db, class, args,
+        ))))
+    }
+
+    /// Returns the type of the value a `Future` resolves to, if `self` is a
+    /// `Future`.
+    ///
+    /// If the `std.sync` module isn't loaded, `None` is returned.
+    pub fn future_value(self, db: &Database) -> Option<TypeRef> {
+        let future = db.future_class()?;
+        let ins = match self.type_id(db) {
+            Ok(TypeId::ClassInstance(ins)) if ins.instance_of == future => ins,
+            _ => return None,
+        };
+        let param = *future.type_parameters(db).first()?;
+
+        ins.type_arguments(db).and_then(|a| a.get(param))
+    }
+
+    /// Returns an owned range over values of the given type.
+    ///
+    /// The range classes are builtin classes defined by the standard library.
+    /// If the standard library defines them as generic classes, `element` is
+    /// assigned to their type parameter. If not, the ranges only support
+    /// integers and `element` is ignored.
+    pub fn range(
+        db: &mut Database,
+        inclusive: bool,
+        element: TypeRef,
+    ) -> TypeRef {
+        let class = if inclusive {
+            ClassId::inclusive_range()
+        } else {
+            ClassId::exclusive_range()
+        };
+        let ins = match class.type_parameters(db).first() {
+            Some(&param) => {
+                let mut args = TypeArguments::new();
+
+                args.assign(param, element);
+                ClassInstance::generic(db, class, args)
+            }
+            None => ClassInstance::new(class),
+        };
+
+        TypeRef::Owned(TypeId::ClassInstance(ins))
+    }
+
+    /// Returns the type of the values of a range, if `self` is a range.
+    pub fn range_element(self, db: &Database) -> Option<TypeRef> {
+        let ins = match self.type_id(db) {
+            Ok(TypeId::ClassInstance(ins)) if ins.instance_of.is_range() => ins,
+            _ => return None,
+        };
+
+        match ins.instance_of.type_parameters(db).first() {
+            Some(&param) => ins.type_arguments(db).and_then(|a| a.get(param)),
+            None => Some(TypeRef::int()),
+        }
+    }
+
     pub fn option_type(db: &mut Database, some: TypeRef) -> TypeRef {
         let class = db.class_in_module(OPTION_MODULE, OPTION_CLASS);
         let params = class.type_parameters(db);
@@ -5121,11 +7801,30 @@ impl TypeRef {
             | TypeRef::Uni(TypeId::Foreign(ForeignType::Int(size, sign))) => {
                 Shape::Int(size, sign)
             }
+            TypeRef::Owned(TypeId::Constant(val)) => Shape::Constant(val),
             TypeRef::Owned(TypeId::Foreign(ForeignType::Float(size)))
             | TypeRef::Uni(TypeId::Foreign(ForeignType::Float(size))) => {
                 Shape::Float(size)
             }
-            TypeRef::Pointer(_) => Shape::Pointer,
+            TypeRef::Owned(TypeId::Foreign(ForeignType::Function(_)))
+            | TypeRef::Uni(TypeId::Foreign(ForeignType::Function(_)))
+            | TypeRef::Pointer(_)
+            | TypeRef::NonNullPointer(_) => Shape::Pointer,
+            // If all members share the same shape we can reuse it. If not, the
+            // value is boxed and thus treated as an owned value.
+            TypeRef::Union(id) => {
+                let mut iter = id
+                    .members(db)
+                    .iter()
+                    .map(|v| v.shape(db, interned, shapes));
+                let first = iter.next().unwrap_or(Shape::Owned);
+
+                if iter.all(|v| v == first) {
+                    first
+                } else {
+                    Shape::Owned
+                }
+            }
             _ => Shape::Owned,
         }
     }
@@ -5149,6 +7848,9 @@ impl TypeRef {
 pub enum ForeignType {
     Int(u32, Sign),
     Float(u32),
+
+    /// A pointer to a C function with the given signature.
+    Function(ForeignFunctionId),
 }
 
 /// An ID pointing to a type.
@@ -5170,9 +7872,28 @@ pub enum TypeId {
     AtomicTypeParameter(TypeParameterId),
     Closure(ClosureId),
     Foreign(ForeignType),
+    TypeAlias(TypeAliasInstance),
+
+    /// A tuple with more values than supported by the builtin tuple classes.
+    Tuple(TupleId),
+
+    /// A constant integer assigned to a constant type parameter, such as the
+    /// `3` in `Matrix[3]`.
+    Constant(i64),
 }
 
 impl TypeId {
+    /// Returns `true` if `self` is a constant or a constant type parameter.
+    pub fn is_constant(self, db: &Database) -> bool {
+        match self {
+            TypeId::Constant(_) => true,
+            TypeId::TypeParameter(id)
+            | TypeId::RigidTypeParameter(id)
+            | TypeId::AtomicTypeParameter(id) => id.is_constant(db),
+            _ => false,
+        }
+    }
+
     pub fn named_type(self, db: &mut Database, name: &str) -> Option<Symbol> {
         match self {
             TypeId::Module(id) => id.use_symbol(db, name),
@@ -5191,40 +7912,19 @@ impl TypeId {
         module: ModuleId,
         allow_type_private: bool,
     ) -> MethodLookup {
-        if let Some(id) = self.method(db, name) {
-            let kind = id.kind(db);
-            let is_ins = !matches!(
-                self,
-                TypeId::Class(_) | TypeId::Trait(_) | TypeId::Module(_)
-            );
-
-            if is_ins && kind.is_static() {
-                MethodLookup::StaticOnInstance
-            } else if !is_ins && !kind.is_static() {
-                MethodLookup::InstanceOnStatic
-            } else if self.can_call(db, id, module, allow_type_private) {
-                MethodLookup::Ok(id)
-            } else {
-                MethodLookup::Private
-            }
-        } else if let TypeId::Module(id) = self {
-            id.extern_method(db, name)
-                .map(MethodLookup::Ok)
-                .unwrap_or(MethodLookup::None)
-        } else {
-            MethodLookup::None
-        }
+        self.resolve_method(db, name, module, allow_type_private, &mut None)
     }
 
     pub fn method(self, db: &Database, name: &str) -> Option<MethodId> {
+        lookup::type_method(db, self, name, &mut None)
+    }
+
+    /// Returns the class that values of this type are always an instance of,
+    /// if the type uses dynamic dispatch and there's only one such class.
+    pub fn sole_implementer(self, db: &Database) -> Option<ClassId> {
         match self {
-            TypeId::Class(id) => id.method(db, name),
-            TypeId::Trait(id) => id.method(db, name),
-            TypeId::Module(id) => id.method(db, name),
-            TypeId::ClassInstance(id) => id.method(db, name),
-            TypeId::TraitInstance(id) => id.method(db, name),
-            TypeId::TypeParameter(id) | TypeId::RigidTypeParameter(id) => {
-                id.method(db, name)
+            TypeId::TraitInstance(ins) => {
+                ins.instance_of().sole_implementer(db)
             }
             _ => None,
         }
@@ -5269,9 +7969,8 @@ impl TypeId {
         }
 
         match m.visibility {
-            Visibility::Public => true,
-            Visibility::Private => m.module.has_same_root_namespace(db, module),
             Visibility::TypePrivate => allow_type_private,
+            vis => vis.allows_access(db, m.module, module),
         }
     }
 }
@@ -5280,10 +7979,30 @@ impl TypeId {
 pub struct Database {
     modules: Vec<Module>,
     module_mapping: HashMap<String, ModuleId>,
+
+    /// A mapping of logical module names to the IDs of all versions of these
+    /// modules.
+    module_versions: HashMap<String, Vec<ModuleId>>,
+    packages: Vec<Package>,
     traits: Vec<Trait>,
     classes: Vec<Class>,
+    type_aliases: Vec<TypeAlias>,
+    unions: Vec<Union>,
+    tuples: Vec<Tuple>,
+
+    /// A mapping of the values of tuples to their IDs, used to deduplicate
+    /// tuples.
+    tuple_ids: HashMap<Vec<TypeRef>, TupleId>,
+
+    /// A mapping of the names of builtin classes to their IDs.
+    builtin_classes: HashMap<String, ClassId>,
+
+    /// The shapes of builtin classes registered using
+    /// `Database::register_builtin()`.
+    builtin_shapes: HashMap<ClassId, Shape>,
+    foreign_functions: Vec<ForeignFunction>,
     type_parameters: Vec<TypeParameter>,
-    type_arguments: Vec<TypeArguments>,
+    type_arguments: Arena<TypeArguments>,
     methods: Vec<Method>,
     fields: Vec<Field>,
     closures: Vec<Closure>,
@@ -5291,8 +8010,43 @@ pub struct Database {
     constants: Vec<Constant>,
     intrinsics: HashMap<String, Intrinsic>,
     type_placeholders: Vec<TypePlaceholder>,
+
+    /// The number of times a type placeholder is assigned a value.
+    ///
+    /// This is used to invalidate cached type checking results, as these may
+    /// depend on the values of placeholders.
+    placeholder_assignments: AtomicU32,
     constructors: Vec<Constructor>,
 
+    /// The limits to apply when specializing types.
+    specialization_limits: SpecializationLimits,
+
+    /// The settings used for deciding which methods to inline.
+    inline_policy: InlinePolicy,
+
+    /// The traits and classes known to exclude each other.
+    exclusions: Exclusions,
+
+    /// The generic types that are in the process of being specialized, in the
+    /// order in which their specialization started.
+    specialization_chain: Vec<ClassInstance>,
+
+    /// Errors produced when a specialization limit is reached.
+    specialization_errors: Vec<Diagnostic>,
+
+    /// The source locations that produced the types of expressions.
+    ///
+    /// This is `None` unless enabled using `Database::track_origins()`.
+    origins: Option<Origins>,
+
+    /// The locations at which symbols are referred to.
+    ///
+    /// This is `None` unless enabled using `Database::track_references()`.
+    references: Option<ReferenceIndex>,
+
+    /// The methods called by each method, recorded while type-checking.
+    call_graph: CallGraph,
+
     /// The module that acts as the entry point of the program.
     ///
     /// For executables this will be set based on the file that is built/run.
@@ -5300,40 +8054,80 @@ pub struct Database {
     main_module: Option<ModuleName>,
     main_method: Option<MethodId>,
     main_class: Option<ClassId>,
+
+    /// The build tags defined for the current build, used to determine which
+    /// conditional symbols to include.
+    build_tags: HashSet<String>,
+
+    /// The platform the program is compiled for.
+    target: Target,
+
+    /// The size of a pointer for the target, in bytes.
+    ///
+    /// This is cached when setting the target, as computing type layouts
+    /// requires it for every pointer.
+    pointer_size: u32,
 }
 
 impl Database {
     pub fn new() -> Self {
+        let target = Target::default();
+        let classes = vec![
+            Class::atomic(STRING_NAME.to_string()),
+            Class::regular(BYTE_ARRAY_NAME.to_string()),
+            Class::value_type(INT_NAME.to_string()),
+            Class::value_type(FLOAT_NAME.to_string()),
+            Class::value_type(BOOL_NAME.to_string()),
+            Class::value_type(NIL_NAME.to_string()),
+            Class::tuple(TUPLE1_NAME.to_string()),
+            Class::tuple(TUPLE2_NAME.to_string()),
+            Class::tuple(TUPLE3_NAME.to_string()),
+            Class::tuple(TUPLE4_NAME.to_string()),
+            Class::tuple(TUPLE5_NAME.to_string()),
+            Class::tuple(TUPLE6_NAME.to_string()),
+            Class::tuple(TUPLE7_NAME.to_string()),
+            Class::tuple(TUPLE8_NAME.to_string()),
+            Class::regular(ARRAY_NAME.to_string()),
+            Class::new(
+                CHECKED_INT_RESULT_NAME.to_string(),
+                ClassKind::Extern,
+                Visibility::Private,
+                ModuleId(DEFAULT_BUILTIN_MODULE_ID),
+                Location::default(),
+            ),
+            Class::range(INCLUSIVE_RANGE_NAME.to_string()),
+            Class::range(EXCLUSIVE_RANGE_NAME.to_string()),
+            Class::new(
+                CHECKED_INT128_RESULT_NAME.to_string(),
+                ClassKind::Extern,
+                Visibility::Private,
+                ModuleId(DEFAULT_BUILTIN_MODULE_ID),
+                Location::default(),
+            ),
+            Class::value_type(CHAR_NAME.to_string()),
+        ];
+        let builtin_classes = classes
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| (c.name.clone(), ClassId(idx as u32)))
+            .collect();
+
         Self {
             modules: Vec::new(),
             module_mapping: HashMap::new(),
+            module_versions: HashMap::new(),
+            packages: Vec::new(),
             traits: Vec::new(),
-            classes: vec![
-                Class::atomic(STRING_NAME.to_string()),
-                Class::regular(BYTE_ARRAY_NAME.to_string()),
-                Class::value_type(INT_NAME.to_string()),
-                Class::value_type(FLOAT_NAME.to_string()),
-                Class::value_type(BOOL_NAME.to_string()),
-                Class::value_type(NIL_NAME.to_string()),
-                Class::tuple(TUPLE1_NAME.to_string()),
-                Class::tuple(TUPLE2_NAME.to_string()),
-                Class::tuple(TUPLE3_NAME.to_string()),
-                Class::tuple(TUPLE4_NAME.to_string()),
-                Class::tuple(TUPLE5_NAME.to_string()),
-                Class::tuple(TUPLE6_NAME.to_string()),
-                Class::tuple(TUPLE7_NAME.to_string()),
-                Class::tuple(TUPLE8_NAME.to_string()),
-                Class::regular(ARRAY_NAME.to_string()),
-                Class::new(
-                    CHECKED_INT_RESULT_NAME.to_string(),
-                    ClassKind::Extern,
-                    Visibility::Private,
-                    ModuleId(DEFAULT_BUILTIN_MODULE_ID),
-                    Location::default(),
-                ),
-            ],
+            classes,
+            type_aliases: Vec::new(),
+            unions: Vec::new(),
+            tuples: Vec::new(),
+            tuple_ids: HashMap::new(),
+            builtin_classes,
+            builtin_shapes: HashMap::new(),
+            foreign_functions: Vec::new(),
             type_parameters: Vec::new(),
-            type_arguments: Vec::new(),
+            type_arguments: Arena::new(),
             fields: Vec::new(),
             methods: Vec::new(),
             closures: Vec::new(),
@@ -5341,41 +8135,110 @@ impl Database {
             constants: Vec::new(),
             intrinsics: Intrinsic::mapping(),
             type_placeholders: Vec::new(),
+            placeholder_assignments: AtomicU32::new(0),
             constructors: Vec::new(),
+            specialization_limits: SpecializationLimits::default(),
+            inline_policy: InlinePolicy::default(),
+            exclusions: Exclusions::default(),
+            specialization_chain: Vec::new(),
+            specialization_errors: Vec::new(),
+            origins: None,
+            references: None,
+            call_graph: CallGraph::default(),
             main_module: None,
             main_method: None,
             main_class: None,
+            build_tags: HashSet::new(),
+            pointer_size: target.pointer_size(),
+            target,
         }
     }
 
+    pub fn set_specialization_limits(&mut self, limits: SpecializationLimits) {
+        self.specialization_limits = limits;
+    }
+
+    pub fn specialization_limits(&self) -> SpecializationLimits {
+        self.specialization_limits
+    }
+
+    pub fn has_specialization_errors(&self) -> bool {
+        !self.specialization_errors.is_empty()
+    }
+
+    /// Returns the errors produced when specializing types, removing them from
+    /// the database.
+    pub fn take_specialization_errors(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.specialization_errors)
+    }
+
     pub fn compact(&mut self) {
         // After specialization, the type arguments are no longer in use.
         // Removing them here frees the memory, and ensures we don't continue to
         // use them by mistake.
         self.type_arguments.clear();
-        self.type_arguments.shrink_to_fit();
     }
 
     pub fn builtin_class(&self, name: &str) -> Option<ClassId> {
-        match name {
-            INT_NAME => Some(ClassId::int()),
-            FLOAT_NAME => Some(ClassId::float()),
-            STRING_NAME => Some(ClassId::string()),
-            ARRAY_NAME => Some(ClassId::array()),
-            BOOL_NAME => Some(ClassId::boolean()),
-            NIL_NAME => Some(ClassId::nil()),
-            BYTE_ARRAY_NAME => Some(ClassId::byte_array()),
-            TUPLE1_NAME => Some(ClassId::tuple1()),
-            TUPLE2_NAME => Some(ClassId::tuple2()),
-            TUPLE3_NAME => Some(ClassId::tuple3()),
-            TUPLE4_NAME => Some(ClassId::tuple4()),
-            TUPLE5_NAME => Some(ClassId::tuple5()),
-            TUPLE6_NAME => Some(ClassId::tuple6()),
-            TUPLE7_NAME => Some(ClassId::tuple7()),
-            TUPLE8_NAME => Some(ClassId::tuple8()),
-            CHECKED_INT_RESULT_NAME => Some(ClassId::checked_int_result()),
-            _ => None,
+        self.builtin_classes.get(name).cloned()
+    }
+
+    /// Registers a new builtin class.
+    ///
+    /// This allows embedders and forks of the standard library to define
+    /// additional builtin classes (e.g. a `Decimal` type), which the standard
+    /// library then defines using `class builtin`.
+    ///
+    /// If a shape is given, instances of the class use that shape instead of
+    /// the shape derived from the class' kind and storage.
+    ///
+    /// Builtin classes must be registered before any other classes are
+    /// defined, such that all builtin classes come before user-defined
+    /// classes.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if a builtin class with the same name already
+    /// exists, or if other classes are already defined.
+    pub fn register_builtin(
+        &mut self,
+        name: String,
+        kind: ClassKind,
+        storage: Storage,
+        shape: Option<Shape>,
+    ) -> ClassId {
+        if self.builtin_classes.contains_key(&name) {
+            panic!("The builtin class {} is already defined", name);
+        }
+
+        if self.classes.len() != self.builtin_classes.len() {
+            panic!(
+                "The builtin class {} must be registered before defining \
+                other classes",
+                name
+            );
+        }
+
+        let mut class = Class::new(
+            name.clone(),
+            kind,
+            Visibility::Public,
+            ModuleId(DEFAULT_BUILTIN_MODULE_ID),
+            Location::default(),
+        );
+
+        class.storage = storage;
+
+        let id = ClassId(self.classes.len() as u32);
+
+        self.classes.push(class);
+        self.builtin_classes.insert(name, id);
+
+        if let Some(shape) = shape {
+            self.builtin_shapes.insert(id, shape);
         }
+
+        id
     }
 
     pub fn intrinsic(&self, name: &str) -> Option<Intrinsic> {
@@ -5390,6 +8253,53 @@ impl Database {
         panic!("The module '{}' isn't registered in the type database", name);
     }
 
+    /// Returns all symbols that are defined or imported but never used.
+    ///
+    /// This includes unused imports, private symbols defined in a module that
+    /// are never used, and private methods that are never called. Public
+    /// symbols may be used by other packages, and thus aren't included.
+    pub fn unused_symbols(&self) -> Vec<UnusedSymbol> {
+        let mut unused = Vec::new();
+
+        for idx in 0..self.modules.len() {
+            let module = ModuleId(idx as _);
+
+            unused.append(&mut module.unused_imports(self));
+            unused.append(&mut module.unused_definitions(self));
+        }
+
+        for idx in 0..self.classes.len() {
+            let class = ClassId(idx as _);
+            let module = class.module(self);
+            let is_module = class.kind(self).is_module();
+            let mut methods: Vec<_> = class
+                .methods(self)
+                .into_iter()
+                .filter(|&m| {
+                    let name = m.name(self);
+
+                    m.is_private(self)
+                        && !m.is_used(self)
+                        && !m.is_main(self)
+                        && !m.is_generated(self)
+                        && m.kind(self) != MethodKind::Destructor
+                        && matches!(m.source(self), MethodSource::Direct)
+                        && !(is_module && module.symbol_is_used(self, name))
+                })
+                .collect();
+
+            methods.sort_by_key(|m| m.0);
+            unused.extend(methods.into_iter().map(|m| UnusedSymbol {
+                module: m.module(self),
+                name: m.name(self).clone(),
+                symbol: Symbol::Method(m),
+                location: m.location(self),
+            }));
+        }
+
+        unused
+    }
+
     pub fn optional_module(&self, name: &str) -> Option<ModuleId> {
         self.module_mapping.get(name).cloned()
     }
@@ -5403,6 +8313,17 @@ impl Database {
         }
     }
 
+    pub fn optional_class_in_module(
+        &self,
+        module: &str,
+        name: &str,
+    ) -> Option<ClassId> {
+        match self.optional_module(module)?.symbol(self, name) {
+            Some(Symbol::Class(id)) => Some(id),
+            _ => None,
+        }
+    }
+
     pub fn trait_in_module(&self, module: &str, name: &str) -> TraitId {
         if let Some(Symbol::Trait(id)) = self.module(module).symbol(self, name)
         {
@@ -5412,14 +8333,39 @@ impl Database {
         }
     }
 
-    pub fn drop_trait(&self) -> TraitId {
-        self.trait_in_module(DROP_MODULE, DROP_TRAIT)
+    pub fn drop_trait(&self) -> TraitId {
+        self.trait_in_module(DROP_MODULE, DROP_TRAIT)
+    }
+
+    pub fn map_class(&self) -> ClassId {
+        self.class_in_module(MAP_MODULE, MAP_CLASS)
+    }
+
+    pub fn set_class(&self) -> ClassId {
+        self.class_in_module(SET_MODULE, SET_CLASS)
+    }
+
+    /// Returns the `Future` class, if the `std.sync` module is loaded.
+    ///
+    /// Unlike most other standard library classes this module isn't imported
+    /// by every program, so the class is looked up when it's needed.
+    pub fn future_class(&self) -> Option<ClassId> {
+        self.optional_class_in_module(SYNC_MODULE, FUTURE_CLASS)
+    }
+
+    /// Returns the `Promise` class, if the `std.sync` module is loaded.
+    pub fn promise_class(&self) -> Option<ClassId> {
+        self.optional_class_in_module(SYNC_MODULE, PROMISE_CLASS)
     }
 
     pub fn number_of_traits(&self) -> usize {
         self.traits.len()
     }
 
+    pub(crate) fn placeholder_assignments(&self) -> u32 {
+        self.placeholder_assignments.load(AtomicOrdering::Relaxed)
+    }
+
     pub fn number_of_modules(&self) -> usize {
         self.modules.len()
     }
@@ -5428,6 +8374,18 @@ impl Database {
         self.classes.len()
     }
 
+    pub fn number_of_type_aliases(&self) -> usize {
+        self.type_aliases.len()
+    }
+
+    pub fn number_of_unions(&self) -> usize {
+        self.unions.len()
+    }
+
+    pub fn number_of_tuples(&self) -> usize {
+        self.tuples.len()
+    }
+
     pub fn number_of_methods(&self) -> usize {
         self.methods.len()
     }
@@ -5461,11 +8419,13 @@ impl Database {
 mod tests {
     use super::*;
     use crate::test::{
-        any, closure, generic_instance_id, generic_trait_instance, immutable,
-        immutable_uni, instance, mutable, mutable_uni, new_async_class,
-        new_class, new_enum_class, new_extern_class, new_module, new_parameter,
-        new_trait, owned, parameter, placeholder, pointer, rigid,
-        trait_instance, uni,
+        alias_instance_id, any, closure, define_future_and_promise,
+        define_map_and_set, define_option_and_result, generic_instance_id,
+        generic_trait_instance, immutable, immutable_uni, implement, instance,
+        mutable, mutable_uni, new_async_class, new_class, new_enum_class,
+        new_extern_class, new_module, new_parameter, new_trait, new_type_alias,
+        owned, parameter, placeholder, pointer, rigid, trait_instance,
+        type_arguments, uni,
     };
     use std::mem::size_of;
 
@@ -5555,7 +8515,85 @@ mod tests {
 
         assert_eq!(targs.get(param1), Some(TypeRef::Never));
         assert_eq!(targs.get(param2), None);
-        assert_eq!(targs.mapping.len(), 1);
+        assert_eq!(targs.len(), 1);
+    }
+
+    #[test]
+    fn test_type_arguments_assign_many() {
+        let mut db = Database::new();
+        let mut targs = TypeArguments::new();
+        let params: Vec<_> = (0..5)
+            .map(|i| new_parameter(&mut db, &format!("T{}", i)))
+            .collect();
+
+        for &param in &params {
+            targs.assign(param, TypeRef::int());
+        }
+
+        targs.assign(params[1], TypeRef::float());
+        targs.assign(params[4], TypeRef::string());
+
+        assert!(matches!(targs.storage, TypeArgumentsStorage::Heap(_)));
+        assert_eq!(targs.len(), 5);
+        assert_eq!(targs.get(params[0]), Some(TypeRef::int()));
+        assert_eq!(targs.get(params[1]), Some(TypeRef::float()));
+        assert_eq!(targs.get(params[4]), Some(TypeRef::string()));
+    }
+
+    #[test]
+    fn test_type_arguments_extend() {
+        let mut db = Database::new();
+        let mut targs = TypeArguments::new();
+        let param1 = new_parameter(&mut db, "A");
+        let param2 = new_parameter(&mut db, "B");
+
+        targs.assign(param1, TypeRef::int());
+        targs.extend([(param1, TypeRef::float()), (param2, TypeRef::int())]);
+
+        assert!(matches!(targs.storage, TypeArgumentsStorage::Inline(2, _)));
+        assert_eq!(targs.get(param1), Some(TypeRef::float()));
+        assert_eq!(targs.get(param2), Some(TypeRef::int()));
+    }
+
+    #[test]
+    fn test_type_arguments_copy_into() {
+        let mut db = Database::new();
+        let mut source = TypeArguments::new();
+        let mut target = TypeArguments::new();
+        let param1 = new_parameter(&mut db, "A");
+        let param2 = new_parameter(&mut db, "B");
+
+        source.assign(param1, TypeRef::int());
+        source.copy_into(&mut target);
+
+        assert_eq!(target.get(param1), Some(TypeRef::int()));
+
+        source.assign(param1, TypeRef::float());
+        source.assign(param2, TypeRef::int());
+        source.copy_into(&mut target);
+
+        assert_eq!(target.get(param1), Some(TypeRef::float()));
+        assert_eq!(target.get(param2), Some(TypeRef::int()));
+    }
+
+    #[test]
+    fn test_type_arguments_eq() {
+        let mut db = Database::new();
+        let mut targs1 = TypeArguments::new();
+        let mut targs2 = TypeArguments::new();
+        let param1 = new_parameter(&mut db, "A");
+        let param2 = new_parameter(&mut db, "B");
+
+        targs1.assign(param1, TypeRef::int());
+        targs1.assign(param2, TypeRef::float());
+        targs2.assign(param2, TypeRef::float());
+        targs2.assign(param1, TypeRef::int());
+
+        assert_eq!(targs1, targs2);
+
+        targs2.assign(param1, TypeRef::string());
+
+        assert_ne!(targs1, targs2);
     }
 
     #[test]
@@ -5636,146 +8674,516 @@ mod tests {
         assert_eq!(id.type_parameters(&db), vec![param]);
     }
 
+    #[test]
+    fn test_trait_id_sole_implementer() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let hash = new_trait(&mut db, "Hash");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+        let proc = new_async_class(&mut db, "Proc");
+
+        assert_eq!(to_string.sole_implementer(&db), None);
+
+        implement(&mut db, trait_instance(to_string), foo);
+
+        assert_eq!(to_string.sole_implementer(&db), Some(foo));
+        assert_eq!(
+            TypeId::TraitInstance(trait_instance(to_string))
+                .sole_implementer(&db),
+            Some(foo)
+        );
+        assert_eq!(instance(foo).sole_implementer(&db), None);
+
+        implement(&mut db, trait_instance(to_string), bar);
+        implement(&mut db, trait_instance(hash), proc);
+
+        assert_eq!(to_string.sole_implementer(&db), None);
+        assert_eq!(hash.sole_implementer(&db), None);
+    }
+
     #[test]
     fn test_trait_instance_new() {
         let mut db = Database::new();
         let id = Trait::alloc(
             &mut db,
-            "A".to_string(),
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
+            "A".to_string(),
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+        let ins = TraitInstance::new(id);
+        let index = db.traits.len() as u32 - 1;
+
+        assert_eq!(ins.instance_of.0, index);
+        assert_eq!(ins.type_arguments, Index::NONE);
+    }
+
+    #[test]
+    fn test_trait_instance_generic() {
+        let mut db = Database::new();
+        let id = Trait::alloc(
+            &mut db,
+            "A".to_string(),
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+        let ins1 = TraitInstance::generic(&mut db, id, TypeArguments::new());
+        let ins2 = TraitInstance::generic(&mut db, id, TypeArguments::new());
+        let index = db.traits.len() as u32 - 1;
+
+        assert_eq!(ins1.instance_of.0, index);
+        assert_eq!(ins1.type_arguments.slot, 0);
+
+        assert_eq!(ins2.instance_of.0, index);
+        assert_eq!(ins2.type_arguments.slot, 1);
+    }
+
+    #[test]
+    fn test_class_alloc() {
+        let mut db = Database::new();
+        let id = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(id.0, FIRST_USER_CLASS_ID);
+        assert_eq!(
+            &db.classes[FIRST_USER_CLASS_ID as usize].name,
+            &"A".to_string()
+        );
+        assert_eq!(
+            db.classes[FIRST_USER_CLASS_ID as usize].kind,
+            ClassKind::Regular
+        );
+    }
+
+    #[test]
+    fn test_class_clone_for_specialization() {
+        let mut db = Database::new();
+        let class1 = new_class(&mut db, "A");
+
+        class1.set_stack_allocated(&mut db);
+
+        let class2 = class1.clone_for_specialization(&mut db);
+
+        assert!(class2.is_stack_allocated(&db));
+    }
+
+    #[test]
+    fn test_class_new() {
+        let class = Class::new(
+            "A".to_string(),
+            ClassKind::Async,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(&class.name, &"A");
+        assert_eq!(class.kind, ClassKind::Async);
+    }
+
+    #[test]
+    fn test_class_id_union() {
+        let mut db = Database::new();
+        let union = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Union,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+        let regular = new_class(&mut db, "B");
+
+        assert!(union.is_stack_allocated(&db));
+        assert!(union.kind(&db).is_extern());
+        assert!(union.allow_mutating(&db));
+        assert!(!union.allow_trait_implementations(&db));
+        assert!(!union.allow_cast_to_trait(&db));
+        assert!(!union.kind(&db).allow_pattern_matching());
+        assert!(union.requires_unsafe_field_reads(&db));
+        assert!(!regular.requires_unsafe_field_reads(&db));
+    }
+
+    #[test]
+    fn test_class_id_name() {
+        let mut db = Database::new();
+        let id = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(id.name(&db), &"A");
+    }
+
+    #[test]
+    fn test_class_id_is_async() {
+        let mut db = Database::new();
+        let regular_class = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+        let async_class = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Async,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert!(!regular_class.kind(&db).is_async());
+        assert!(async_class.kind(&db).is_async());
+    }
+
+    #[test]
+    fn test_class_id_new_type_parameter() {
+        let mut db = Database::new();
+        let id = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            ModuleId(0),
+            Location::default(),
+        );
+        let param = id.new_type_parameter(&mut db, "A".to_string());
+
+        assert_eq!(id.type_parameters(&db), vec![param]);
+    }
+
+    #[test]
+    fn test_type_alias_is_cyclic() {
+        let mut db = Database::new();
+
+        ClassId::array().new_type_parameter(&mut db, "T".to_string());
+
+        let ints = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![TypeRef::int()],
+        ));
+        let valid = new_type_alias(&mut db, "Ints", ints);
+        let direct = new_type_alias(&mut db, "A", TypeRef::Unknown);
+        let indirect = new_type_alias(&mut db, "B", TypeRef::Unknown);
+        let via_c = new_type_alias(&mut db, "C", TypeRef::Unknown);
+        let direct_ins = alias_instance_id(&mut db, direct, Vec::new());
+        let in_array = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![owned(direct_ins)],
+        ));
+        let b_ins = owned(alias_instance_id(&mut db, indirect, Vec::new()));
+        let c_ins = owned(alias_instance_id(&mut db, via_c, Vec::new()));
+
+        direct.set_aliased_type(&mut db, in_array);
+        indirect.set_aliased_type(&mut db, c_ins);
+        via_c.set_aliased_type(&mut db, b_ins);
+
+        assert!(!valid.is_cyclic(&db));
+        assert!(direct.is_cyclic(&db));
+        assert!(indirect.is_cyclic(&db));
+    }
+
+    #[test]
+    fn test_type_ref_expand_alias() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let alias = new_type_alias(&mut db, "Foo", owned(instance(foo)));
+        let ins = alias_instance_id(&mut db, alias, Vec::new());
+
+        assert_eq!(owned(ins).expand_alias(&db), Some(owned(instance(foo))));
+        assert_eq!(
+            immutable(ins).expand_alias(&db),
+            Some(immutable(instance(foo)))
+        );
+        assert_eq!(
+            mutable(ins).expand_alias(&db),
+            Some(mutable(instance(foo)))
+        );
+        assert_eq!(owned(instance(foo)).expand_alias(&db), None);
+    }
+
+    #[test]
+    fn test_trait_associated_types() {
+        let mut db = Database::new();
+        let iter = new_trait(&mut db, "Iter");
+        let item = iter.new_associated_type(&mut db, "Item".to_string());
+        let error = iter.new_associated_type(&mut db, "Error".to_string());
+
+        iter.set_associated_type_default(&mut db, error, TypeRef::nil());
+
+        let ints = TraitInstance::generic(
+            &mut db,
+            iter,
+            type_arguments(vec![(item, TypeRef::int())]),
+        );
+        let empty = TraitInstance::generic(&mut db, iter, TypeArguments::new());
+
+        assert!(iter.is_generic(&db));
+        assert!(iter.type_parameters(&db).is_empty());
+        assert_eq!(iter.associated_type(&db, "Item"), Some(item));
+        assert_eq!(
+            iter.named_type(&db, "Item"),
+            Some(Symbol::TypeParameter(item))
+        );
+        assert_eq!(ints.associated_type(&db, "Item"), Some(TypeRef::int()));
+        assert_eq!(ints.associated_type(&db, "Error"), Some(TypeRef::nil()));
+        assert_eq!(ints.associated_type(&db, "Foo"), None);
+        assert!(ints.missing_associated_types(&db).is_empty());
+        assert_eq!(empty.missing_associated_types(&db), vec!["Item"]);
+    }
+
+    #[test]
+    fn test_trait_inherited_associated_types() {
+        let mut db = Database::new();
+        let base = new_trait(&mut db, "Base");
+        let base_item = base.new_associated_type(&mut db, "Item".to_string());
+        let error = base.new_associated_type(&mut db, "Error".to_string());
+        let base_ins =
+            TraitInstance::generic(&mut db, base, TypeArguments::new());
+
+        base.set_associated_type_default(&mut db, error, TypeRef::nil());
+
+        // The associated type is declared after adding the requirement.
+        let foo = new_trait(&mut db, "Foo");
+
+        foo.add_required_trait(&mut db, base_ins);
+
+        let foo_item = foo.new_associated_type(&mut db, "Item".to_string());
+
+        // The associated type is declared before adding the requirement.
+        let bar = new_trait(&mut db, "Bar");
+        let bar_item = bar.new_associated_type(&mut db, "Item".to_string());
+
+        bar.add_required_trait(&mut db, base_ins);
+
+        // The associated types are inherited as-is.
+        let baz = new_trait(&mut db, "Baz");
+
+        baz.add_required_trait(&mut db, base_ins);
+
+        let foo_ins = TraitInstance::generic(
+            &mut db,
+            foo,
+            type_arguments(vec![(foo_item, TypeRef::int())]),
+        );
+        let bar_ins = TraitInstance::generic(
+            &mut db,
+            bar,
+            type_arguments(vec![(bar_item, TypeRef::float())]),
         );
-        let ins = TraitInstance::new(id);
-        let index = db.traits.len() as u32 - 1;
+        let baz_ins = TraitInstance::generic(
+            &mut db,
+            baz,
+            type_arguments(vec![(base_item, TypeRef::string())]),
+        );
+        let empty = TraitInstance::generic(&mut db, foo, TypeArguments::new());
 
-        assert_eq!(ins.instance_of.0, index);
-        assert_eq!(ins.type_arguments, 0);
+        assert_eq!(foo.associated_type(&db, "Item"), Some(foo_item));
+        assert_eq!(baz.associated_type(&db, "Item"), Some(base_item));
+        assert_eq!(foo.associated_type_names(&db), vec!["Item", "Error"]);
+        assert_eq!(baz.associated_type_names(&db), vec!["Item", "Error"]);
+        assert_eq!(foo_ins.associated_type(&db, "Item"), Some(TypeRef::int()));
+        assert_eq!(
+            bar_ins.associated_type(&db, "Item"),
+            Some(TypeRef::float())
+        );
+        assert_eq!(
+            baz_ins.associated_type(&db, "Item"),
+            Some(TypeRef::string())
+        );
+        assert_eq!(foo_ins.associated_type(&db, "Error"), Some(TypeRef::nil()));
+        assert_eq!(
+            foo.inherited_type_arguments(&db).get(base_item),
+            Some(any(parameter(foo_item)))
+        );
+        assert_eq!(empty.missing_associated_types(&db), vec!["Item"]);
     }
 
     #[test]
-    fn test_trait_instance_generic() {
+    fn test_type_ref_union() {
         let mut db = Database::new();
-        let id = Trait::alloc(
-            &mut db,
-            "A".to_string(),
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
-        let ins1 = TraitInstance::generic(&mut db, id, TypeArguments::new());
-        let ins2 = TraitInstance::generic(&mut db, id, TypeArguments::new());
-        let index = db.traits.len() as u32 - 1;
-
-        assert_eq!(ins1.instance_of.0, index);
-        assert_eq!(ins1.type_arguments, 0);
+        let foo = owned(instance(new_class(&mut db, "Foo")));
+        let bar = owned(instance(new_class(&mut db, "Bar")));
+        let baz = owned(instance(new_class(&mut db, "Baz")));
+        let foo_bar = TypeRef::union(&mut db, vec![foo, bar, foo]);
+        let all = TypeRef::union(&mut db, vec![foo_bar, baz, bar]);
 
-        assert_eq!(ins2.instance_of.0, index);
-        assert_eq!(ins2.type_arguments, 1);
+        assert_eq!(
+            foo_bar.union_id(&db).unwrap().members(&db),
+            &vec![foo, bar]
+        );
+        assert_eq!(
+            all.union_id(&db).unwrap().members(&db),
+            &vec![foo, bar, baz]
+        );
+        assert_eq!(TypeRef::union(&mut db, vec![foo, foo]), foo);
+        assert_eq!(TypeRef::union(&mut db, Vec::new()), TypeRef::Never);
+        assert_eq!(foo.union_id(&db), None);
+        assert!(!foo_bar.is_stack_allocated(&db));
     }
 
     #[test]
-    fn test_class_alloc() {
+    fn test_type_ref_union_shape() {
         let mut db = Database::new();
-        let id = Class::alloc(
+        let foo = owned(instance(new_class(&mut db, "Foo")));
+        let bar = owned(instance(new_class(&mut db, "Bar")));
+        let ints = TypeRef::union(
             &mut db,
-            "A".to_string(),
-            ClassKind::Regular,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
+            vec![TypeRef::int(), TypeRef::foreign_signed_int(64)],
         );
-
-        assert_eq!(id.0, FIRST_USER_CLASS_ID);
-        assert_eq!(
-            &db.classes[FIRST_USER_CLASS_ID as usize].name,
-            &"A".to_string()
+        let objects = TypeRef::union(&mut db, vec![foo, bar]);
+        let mixed = TypeRef::union(&mut db, vec![TypeRef::int(), foo]);
+        let float = TypeRef::union(
+            &mut db,
+            vec![TypeRef::float(), TypeRef::foreign_float(32)],
         );
+        let mut interned = InternedTypeArguments::new();
+        let shapes = HashMap::new();
+
         assert_eq!(
-            db.classes[FIRST_USER_CLASS_ID as usize].kind,
-            ClassKind::Regular
+            ints.shape(&db, &mut interned, &shapes),
+            Shape::Int(64, Sign::Signed)
         );
+        assert_eq!(objects.shape(&db, &mut interned, &shapes), Shape::Owned);
+        assert_eq!(mixed.shape(&db, &mut interned, &shapes), Shape::Owned);
+        assert_eq!(float.shape(&db, &mut interned, &shapes), Shape::Owned);
     }
 
     #[test]
-    fn test_class_clone_for_specialization() {
+    fn test_type_ref_tuple() {
         let mut db = Database::new();
-        let class1 = new_class(&mut db, "A");
+        let pair = ClassId::tuple2();
 
-        class1.set_stack_allocated(&mut db);
+        pair.new_type_parameter(&mut db, "A".to_string());
+        pair.new_type_parameter(&mut db, "B".to_string());
 
-        let class2 = class1.clone_for_specialization(&mut db);
+        let ints = vec![TypeRef::int(); 9];
+        let small = TypeRef::tuple(&mut db, vec![TypeRef::int(); 2]);
+        let large = TypeRef::tuple(&mut db, ints.clone());
+        let same = TypeRef::tuple(&mut db, ints.clone());
+        let other = TypeRef::tuple(&mut db, vec![TypeRef::float(); 9]);
 
-        assert!(class2.is_stack_allocated(&db));
+        // Tuple3 doesn't define any type parameters, so it can't be used.
+        let triple = TypeRef::tuple(&mut db, vec![TypeRef::int(); 3]);
+
+        assert_eq!(small.class_id(&db), Some(pair));
+        assert!(matches!(large, TypeRef::Owned(TypeId::Tuple(_))));
+        assert!(matches!(triple, TypeRef::Owned(TypeId::Tuple(_))));
+        assert_eq!(large, same);
+        assert_ne!(large, other);
+        assert_eq!(db.number_of_tuples(), 3);
+        assert_eq!(small.tuple_elements(&db), Some(vec![TypeRef::int(); 2]));
+        assert_eq!(large.tuple_elements(&db), Some(ints));
+        assert_eq!(TypeRef::int().tuple_elements(&db), None);
     }
 
     #[test]
-    fn test_class_new() {
-        let class = Class::new(
-            "A".to_string(),
-            ClassKind::Async,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
+    fn test_type_ref_map_and_set() {
+        let mut db = Database::new();
+        let (map, set) = define_map_and_set(&mut db);
+        let map_ins =
+            TypeRef::map_type(&mut db, TypeRef::string(), TypeRef::int());
+        let set_ins = TypeRef::set_type(&mut db, TypeRef::int());
+        let map_params = map.type_parameters(&db);
+        let set_params = set.type_parameters(&db);
+
+        assert_eq!(db.map_class(), map);
+        assert_eq!(db.set_class(), set);
+        assert_eq!(map_ins.class_id(&db), Some(map));
+        assert_eq!(set_ins.class_id(&db), Some(set));
+
+        let TypeRef::Owned(TypeId::ClassInstance(map_ins)) = map_ins else {
+            panic!("expected a Map instance");
+        };
+        let TypeRef::Owned(TypeId::ClassInstance(set_ins)) = set_ins else {
+            panic!("expected a Set instance");
+        };
+        let map_args = map_ins.type_arguments(&db).unwrap();
+        let set_args = set_ins.type_arguments(&db).unwrap();
 
-        assert_eq!(&class.name, &"A");
-        assert_eq!(class.kind, ClassKind::Async);
+        assert_eq!(map_args.get(map_params[0]), Some(TypeRef::string()));
+        assert_eq!(map_args.get(map_params[1]), Some(TypeRef::int()));
+        assert_eq!(set_args.get(set_params[0]), Some(TypeRef::int()));
     }
 
     #[test]
-    fn test_class_id_name() {
+    fn test_type_ref_future_and_promise() {
         let mut db = Database::new();
-        let id = Class::alloc(
-            &mut db,
-            "A".to_string(),
-            ClassKind::Regular,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
 
-        assert_eq!(id.name(&db), &"A");
+        assert_eq!(TypeRef::int().future_value(&db), None);
+        assert_eq!(TypeRef::future_type(&mut db, TypeRef::int()), None);
+        assert_eq!(db.future_class(), None);
+
+        let (future, promise) = define_future_and_promise(&mut db);
+        let future_ins = TypeRef::future_type(&mut db, TypeRef::int()).unwrap();
+        let promise_ins =
+            TypeRef::promise_type(&mut db, TypeRef::int()).unwrap();
+
+        assert_eq!(db.future_class(), Some(future));
+        assert_eq!(db.promise_class(), Some(promise));
+        assert_eq!(future_ins.class_id(&db), Some(future));
+        assert_eq!(promise_ins.class_id(&db), Some(promise));
+        assert_eq!(future_ins.future_value(&db), Some(TypeRef::int()));
+        assert_eq!(promise_ins.future_value(&db), None);
+        assert_eq!(TypeRef::int().future_value(&db), None);
     }
 
     #[test]
-    fn test_class_id_is_async() {
+    fn test_type_ref_range() {
         let mut db = Database::new();
-        let regular_class = Class::alloc(
-            &mut db,
-            "A".to_string(),
-            ClassKind::Regular,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
-        let async_class = Class::alloc(
-            &mut db,
-            "A".to_string(),
-            ClassKind::Async,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
+        let ints = TypeRef::range(&mut db, true, TypeRef::int());
+        let mut interned = InternedTypeArguments::new();
+        let shapes = HashMap::new();
+
+        assert_eq!(ints.class_id(&db), Some(ClassId::inclusive_range()));
+        assert_eq!(ints.range_element(&db), Some(TypeRef::int()));
+        assert!(ints.is_value_type(&db));
+        assert!(matches!(
+            ints.shape(&db, &mut interned, &shapes),
+            Shape::Stack(_)
+        ));
+        assert_eq!(TypeRef::int().range_element(&db), None);
 
-        assert!(!regular_class.kind(&db).is_async());
-        assert!(async_class.kind(&db).is_async());
+        ClassId::exclusive_range().new_type_parameter(&mut db, "T".to_string());
+
+        let floats = TypeRef::range(&mut db, false, TypeRef::float());
+
+        assert_eq!(floats.class_id(&db), Some(ClassId::exclusive_range()));
+        assert_eq!(floats.range_element(&db), Some(TypeRef::float()));
     }
 
     #[test]
-    fn test_class_id_new_type_parameter() {
+    fn test_type_ref_tuple_shape() {
         let mut db = Database::new();
-        let id = Class::alloc(
-            &mut db,
-            "A".to_string(),
-            ClassKind::Regular,
-            Visibility::Private,
-            ModuleId(0),
-            Location::default(),
-        );
-        let param = id.new_type_parameter(&mut db, "A".to_string());
+        let tuple = TypeRef::tuple(&mut db, vec![TypeRef::int(); 9]);
+        let TypeRef::Owned(id) = tuple else { panic!("invalid tuple") };
+        let mut interned = InternedTypeArguments::new();
+        let shapes = HashMap::new();
 
-        assert_eq!(id.type_parameters(&db), vec![param]);
+        assert_eq!(tuple.shape(&db, &mut interned, &shapes), Shape::Owned);
+        assert_eq!(
+            immutable(id).shape(&db, &mut interned, &shapes),
+            Shape::Ref
+        );
+        assert_eq!(mutable(id).shape(&db, &mut interned, &shapes), Shape::Mut);
+        assert!(!tuple.is_stack_allocated(&db));
     }
 
     #[test]
@@ -5792,7 +9200,7 @@ mod tests {
         let ins = ClassInstance::new(id);
 
         assert_eq!(ins.instance_of.0, FIRST_USER_CLASS_ID);
-        assert_eq!(ins.type_arguments, 0);
+        assert_eq!(ins.type_arguments, Index::NONE);
     }
 
     #[test]
@@ -5810,10 +9218,10 @@ mod tests {
         let ins2 = ClassInstance::generic(&mut db, id, TypeArguments::new());
 
         assert_eq!(ins1.instance_of.0, FIRST_USER_CLASS_ID);
-        assert_eq!(ins1.type_arguments, 0);
+        assert_eq!(ins1.type_arguments.slot, 0);
 
         assert_eq!(ins2.instance_of.0, FIRST_USER_CLASS_ID);
-        assert_eq!(ins2.type_arguments, 1);
+        assert_eq!(ins2.type_arguments.slot, 1);
     }
 
     #[test]
@@ -5864,6 +9272,59 @@ mod tests {
         assert_eq!(id.inline(&db), Inline::Never);
     }
 
+    #[test]
+    fn test_method_throw_kind() {
+        let mut db = Database::new();
+        let (opt, res) = define_option_and_result(&mut db);
+        let new_method = |db: &mut Database, returns: TypeRef| {
+            let id = Method::alloc(
+                db,
+                ModuleId(0),
+                Location::default(),
+                "foo".to_string(),
+                Visibility::Private,
+                MethodKind::Instance,
+            );
+
+            id.set_return_type(db, returns);
+            id
+        };
+
+        let opt_ins =
+            owned(generic_instance_id(&mut db, opt, vec![TypeRef::int()]));
+        let res_ins = owned(generic_instance_id(
+            &mut db,
+            res,
+            vec![TypeRef::int(), TypeRef::string()],
+        ));
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let unknown = new_method(&mut db, TypeRef::Unknown);
+        let int = new_method(&mut db, TypeRef::int());
+        let option = new_method(&mut db, opt_ins);
+        let result = new_method(&mut db, res_ins);
+        let infer = new_method(&mut db, placeholder(var));
+
+        assert_eq!(int.throw_kind(&db), ThrowKind::Unknown);
+        assert_eq!(unknown.infer_throw_kind(&mut db), ThrowKind::Unknown);
+        assert_eq!(int.infer_throw_kind(&mut db), ThrowKind::Never);
+        assert_eq!(
+            option.infer_throw_kind(&mut db),
+            ThrowKind::Option(TypeRef::int())
+        );
+        assert_eq!(
+            result.infer_throw_kind(&mut db),
+            ThrowKind::Result(TypeRef::int(), TypeRef::string())
+        );
+        assert_eq!(infer.infer_throw_kind(&mut db), ThrowKind::Infer(var));
+
+        var.assign(&mut db, opt_ins);
+
+        assert_eq!(infer.throw_kind(&db), ThrowKind::Option(TypeRef::int()));
+        assert!(infer.is_fallible(&db));
+        assert!(result.is_fallible(&db));
+        assert!(!int.is_fallible(&db));
+    }
+
     #[test]
     fn test_method_id_named_type() {
         let mut db = Database::new();
@@ -6059,6 +9520,220 @@ mod tests {
         assert_eq!(foo.import_symbol(&mut db, "fizz"), None);
     }
 
+    #[test]
+    fn test_module_id_reexports() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let facade = new_module(&mut db, "facade");
+        let class1 = new_class(&mut db, "A");
+        let class2 = new_class(&mut db, "B");
+        let loc = Location::default();
+
+        class1.set_module(&mut db, foo);
+        class2.set_module(&mut db, foo);
+        foo.new_symbol(&mut db, "A".to_string(), Symbol::Class(class1));
+        foo.new_symbol(&mut db, "B".to_string(), Symbol::Class(class2));
+        facade.new_reexported_symbol(
+            &mut db,
+            "A".to_string(),
+            Symbol::Class(class1),
+            foo,
+            loc,
+        );
+        facade.new_imported_symbol(
+            &mut db,
+            "B".to_string(),
+            Symbol::Class(class2),
+            foo,
+            loc,
+        );
+
+        assert!(facade.is_reexported(&db, "A"));
+        assert!(!facade.is_reexported(&db, "B"));
+        assert!(!foo.is_reexported(&db, "A"));
+        assert_eq!(facade.symbol_origin(&db, "A"), Some(foo));
+        assert_eq!(foo.symbol_origin(&db, "A"), None);
+        assert_eq!(
+            facade.reexports(&db),
+            vec![("A".to_string(), Symbol::Class(class1))]
+        );
+        assert_eq!(
+            facade.import_symbol(&mut db, "A"),
+            Some(Symbol::Class(class1))
+        );
+        assert_eq!(facade.import_symbol(&mut db, "B"), None);
+        assert_eq!(facade.dependencies(&db), &vec![foo]);
+        assert!(facade.unused_imports(&db).is_empty());
+    }
+
+    #[test]
+    fn test_module_id_import_all_public_symbols() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let baz = new_module(&mut db, "baz");
+        let loc = Location::default();
+        let new_class = |db: &mut Database, name: &str, vis, module| {
+            let id = Class::alloc(
+                db,
+                name.to_string(),
+                ClassKind::Regular,
+                vis,
+                module,
+                loc,
+            );
+
+            module.new_symbol(db, name.to_string(), Symbol::Class(id));
+            id
+        };
+
+        new_class(&mut db, "A", Visibility::Public, foo);
+
+        let b = new_class(&mut db, "B", Visibility::Public, foo);
+
+        new_class(&mut db, "C", Visibility::Private, foo);
+
+        let bar_b = new_class(&mut db, "B", Visibility::Public, bar);
+
+        foo.new_symbol(&mut db, "bar".to_string(), Symbol::Module(bar));
+
+        assert!(baz.import_all_public_symbols(&mut db, foo).is_empty());
+        assert_eq!(
+            baz.symbols(&db).len(),
+            2,
+            "private and imported symbols must not be imported"
+        );
+        assert!(!baz.symbol_exists(&db, "A"));
+        assert!(baz.symbol_is_shadowable(&db, "A"));
+        assert_eq!(baz.symbol_origin(&db, "A"), Some(foo));
+        assert!(baz.unused_imports(&db).is_empty());
+        assert_eq!(baz.dependencies(&db), &vec![foo]);
+
+        // Importing the same symbols again isn't a conflict.
+        assert!(baz.import_all_public_symbols(&mut db, foo).is_empty());
+        assert_eq!(
+            baz.import_all_public_symbols(&mut db, bar),
+            vec!["B".to_string()]
+        );
+        assert_eq!(baz.use_symbol(&mut db, "B"), Some(Symbol::Class(b)));
+
+        // Explicitly defined symbols shadow glob imports.
+        baz.new_symbol(&mut db, "A".to_string(), Symbol::Class(bar_b));
+
+        assert!(baz.symbol_exists(&db, "A"));
+        assert!(baz.import_all_public_symbols(&mut db, foo).is_empty());
+        assert_eq!(baz.use_symbol(&mut db, "A"), Some(Symbol::Class(bar_b)));
+    }
+
+    #[test]
+    fn test_module_id_unused_imports() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let class1 = new_class(&mut db, "A");
+        let class2 = new_class(&mut db, "B");
+        let loc = Location::new(&(1..=1), &(2..=2));
+
+        class1.set_module(&mut db, foo);
+        class2.set_module(&mut db, foo);
+        bar.new_imported_symbol(
+            &mut db,
+            "B".to_string(),
+            Symbol::Class(class2),
+            foo,
+            loc,
+        );
+        bar.new_symbol(&mut db, "A".to_string(), Symbol::Class(class1));
+        bar.new_symbol(&mut db, "foo".to_string(), Symbol::Module(foo));
+        bar.use_symbol(&mut db, "foo");
+
+        assert_eq!(
+            bar.unused_imports(&db),
+            vec![
+                UnusedSymbol {
+                    module: bar,
+                    name: "A".to_string(),
+                    symbol: Symbol::Class(class1),
+                    location: Location::default(),
+                },
+                UnusedSymbol {
+                    module: bar,
+                    name: "B".to_string(),
+                    symbol: Symbol::Class(class2),
+                    location: loc,
+                },
+            ]
+        );
+        assert!(foo.unused_imports(&db).is_empty());
+    }
+
+    #[test]
+    fn test_database_unused_symbols() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let loc = Location::new(&(1..=1), &(2..=2));
+        let private = Class::alloc(
+            &mut db,
+            "A".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            foo,
+            loc,
+        );
+        let public = Class::alloc(
+            &mut db,
+            "B".to_string(),
+            ClassKind::Regular,
+            Visibility::Public,
+            foo,
+            loc,
+        );
+        let new_method = |db: &mut Database, name: &str, vis| {
+            let id = Method::alloc(
+                db,
+                foo,
+                loc,
+                name.to_string(),
+                vis,
+                MethodKind::Instance,
+            );
+
+            public.add_method(db, name.to_string(), id);
+            id
+        };
+
+        let unused = new_method(&mut db, "unused", Visibility::Private);
+        let used = new_method(&mut db, "used", Visibility::Private);
+
+        new_method(&mut db, "public", Visibility::Public);
+        used.mark_as_used(&mut db);
+        foo.new_symbol(&mut db, "A".to_string(), Symbol::Class(private));
+        foo.new_symbol(&mut db, "B".to_string(), Symbol::Class(public));
+        bar.new_imported_symbol(
+            &mut db,
+            "B".to_string(),
+            Symbol::Class(public),
+            foo,
+            loc,
+        );
+
+        let symbols: Vec<_> = db
+            .unused_symbols()
+            .into_iter()
+            .map(|s| (s.module, s.name, s.symbol))
+            .collect();
+
+        assert_eq!(
+            symbols,
+            vec![
+                (foo, "A".to_string(), Symbol::Class(private)),
+                (bar, "B".to_string(), Symbol::Class(public)),
+                (foo, "unused".to_string(), Symbol::Method(unused)),
+            ]
+        );
+    }
+
     #[test]
     fn test_module_id_symbols() {
         let mut db = Database::new();
@@ -6096,7 +9771,76 @@ mod tests {
         let mut db = Database::new();
         let id = Closure::alloc(&mut db, false);
 
-        assert_eq!(id.0, 0);
+        assert_eq!(id.0, 0);
+    }
+
+    #[test]
+    fn test_closure_instantiate() {
+        let mut db = Database::new();
+        let fun = Closure::alloc(&mut db, false);
+        let param = new_parameter(&mut db, "T");
+
+        assert!(fun.instantiate(&mut db).is_empty());
+
+        fun.add_type_parameter(&mut db, param);
+
+        let args1 = fun.instantiate(&mut db);
+        let args2 = fun.instantiate(&mut db);
+
+        assert!(fun.is_generic(&db));
+        assert!(matches!(args1.get(param), Some(TypeRef::Placeholder(_))));
+        assert_ne!(args1.get(param), args2.get(param));
+    }
+
+    #[test]
+    fn test_closure_type_parameters_used_as_references() {
+        let mut db = Database::new();
+        let fun1 = Closure::alloc(&mut db, false);
+        let fun2 = Closure::alloc(&mut db, false);
+        let fun3 = Closure::alloc(&mut db, false);
+        let param = new_parameter(&mut db, "T");
+        let array = Class::alloc(
+            &mut db,
+            "Array".to_string(),
+            ClassKind::Regular,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+        array.new_type_parameter(&mut db, "A".to_string());
+
+        let array_ins = generic_instance_id(
+            &mut db,
+            array,
+            vec![immutable(parameter(param))],
+        );
+        let loc = Location::default();
+
+        for fun in [fun1, fun2, fun3] {
+            fun.add_type_parameter(&mut db, param);
+        }
+
+        fun1.new_argument(
+            &mut db,
+            "a".to_string(),
+            mutable(parameter(param)),
+            mutable(parameter(param)),
+            loc,
+        );
+        fun1.set_return_type(&mut db, immutable(parameter(param)));
+        fun2.set_return_type(&mut db, owned(parameter(param)));
+        fun3.new_argument(
+            &mut db,
+            "a".to_string(),
+            owned(array_ins),
+            owned(array_ins),
+            loc,
+        );
+        fun3.set_return_type(&mut db, TypeRef::nil());
+
+        assert!(fun1.type_parameters_used_as_references(&db));
+        assert!(!fun2.type_parameters_used_as_references(&db));
+        assert!(!fun3.type_parameters_used_as_references(&db));
     }
 
     #[test]
@@ -6227,6 +9971,49 @@ mod tests {
         assert!(block.named_type(&mut db, "T").is_none());
     }
 
+    #[test]
+    fn test_type_id_method_with_blanket_implementation() {
+        let mut db = Database::new();
+        let format = new_trait(&mut db, "Format");
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+        let param = new_parameter(&mut db, "T");
+        let method = Method::alloc(
+            &mut db,
+            ModuleId(0),
+            Location::default(),
+            "to_string".to_string(),
+            Visibility::Public,
+            MethodKind::Instance,
+        );
+
+        to_string.add_default_method(&mut db, "to_string".to_string(), method);
+        param.add_requirements(&mut db, vec![trait_instance(format)]);
+        to_string.add_blanket_implementation(
+            &mut db,
+            param,
+            TraitImplementation {
+                instance: trait_instance(to_string),
+                bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
+            },
+        );
+        foo.add_trait_implementation(
+            &mut db,
+            TraitImplementation {
+                instance: trait_instance(format),
+                bounds: TypeBounds::new(),
+                module: ModuleId(0),
+                location: Location::default(),
+            },
+        );
+
+        assert_eq!(instance(foo).method(&db, "to_string"), Some(method));
+        assert_eq!(instance(bar).method(&db, "to_string"), None);
+    }
+
     #[test]
     fn test_database_new() {
         let db = Database::new();
@@ -6237,7 +10024,215 @@ mod tests {
         assert_eq!(&db.classes[ARRAY_ID as usize].name, ARRAY_NAME);
         assert_eq!(&db.classes[BOOL_ID as usize].name, BOOL_NAME);
         assert_eq!(&db.classes[NIL_ID as usize].name, NIL_NAME);
+        assert_eq!(&db.classes[CHAR_ID as usize].name, CHAR_NAME);
+        assert_eq!(
+            &db.classes[INCLUSIVE_RANGE_ID as usize].name,
+            INCLUSIVE_RANGE_NAME
+        );
+        assert_eq!(
+            &db.classes[EXCLUSIVE_RANGE_ID as usize].name,
+            EXCLUSIVE_RANGE_NAME
+        );
         assert_eq!(&db.classes[BYTE_ARRAY_ID as usize].name, BYTE_ARRAY_NAME);
+        assert_eq!(
+            &db.classes[CHECKED_INT128_RESULT_ID as usize].name,
+            CHECKED_INT128_RESULT_NAME
+        );
+    }
+
+    #[test]
+    fn test_database_builtin_class() {
+        let db = Database::new();
+
+        assert_eq!(db.builtin_class(INT_NAME), Some(ClassId::int()));
+        assert_eq!(db.builtin_class(TUPLE8_NAME), Some(ClassId::tuple8()));
+        assert_eq!(
+            db.builtin_class(EXCLUSIVE_RANGE_NAME),
+            Some(ClassId::exclusive_range())
+        );
+        assert_eq!(db.builtin_class("Foo"), None);
+    }
+
+    #[test]
+    fn test_database_register_builtin() {
+        let mut db = Database::new();
+        let decimal = db.register_builtin(
+            "Decimal".to_string(),
+            ClassKind::Regular,
+            Storage::Stack,
+            Some(Shape::Int(128, Sign::Signed)),
+        );
+        let vector = db.register_builtin(
+            "Vector4".to_string(),
+            ClassKind::Regular,
+            Storage::Stack,
+            None,
+        );
+        let user = new_class(&mut db, "Foo");
+        let mut interned = InternedTypeArguments::new();
+        let shapes = HashMap::new();
+
+        assert_eq!(decimal.0, FIRST_USER_CLASS_ID);
+        assert_eq!(user.0, FIRST_USER_CLASS_ID + 2);
+        assert_eq!(db.builtin_class("Decimal"), Some(decimal));
+        assert_eq!(db.builtin_class("Vector4"), Some(vector));
+        assert_eq!(db.builtin_class("Foo"), None);
+        assert!(decimal.is_stack_allocated(&db));
+        assert_eq!(
+            owned(instance(decimal)).shape(&db, &mut interned, &shapes),
+            Shape::Int(128, Sign::Signed)
+        );
+        assert_eq!(
+            owned(instance(vector)).shape(&db, &mut interned, &shapes),
+            Shape::Stack(ClassInstance::new(vector))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_database_register_existing_builtin() {
+        let mut db = Database::new();
+
+        db.register_builtin(
+            INT_NAME.to_string(),
+            ClassKind::Regular,
+            Storage::Stack,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_database_register_builtin_after_other_classes() {
+        let mut db = Database::new();
+
+        new_class(&mut db, "Foo");
+        db.register_builtin(
+            "Decimal".to_string(),
+            ClassKind::Regular,
+            Storage::Stack,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_intrinsic_checked_div_rem() {
+        let db = Database::new();
+        let div = db.intrinsic("int_checked_div").unwrap();
+        let rem = db.intrinsic("int_checked_rem").unwrap();
+
+        assert_eq!(div, Intrinsic::IntCheckedDiv);
+        assert_eq!(rem, Intrinsic::IntCheckedRem);
+        assert_eq!(
+            div.return_type().class_id(&db),
+            Some(ClassId::checked_int_result())
+        );
+        assert_eq!(
+            rem.return_type().class_id(&db),
+            Some(ClassId::checked_int_result())
+        );
+    }
+
+    #[test]
+    fn test_intrinsic_float_math() {
+        let db = Database::new();
+
+        for name in [
+            "float_sqrt",
+            "float_sin",
+            "float_cos",
+            "float_ln",
+            "float_log10",
+            "float_exp",
+            "float_pow",
+        ] {
+            let func = db.intrinsic(name).unwrap();
+
+            assert_eq!(func.name(), name);
+            assert_eq!(func.return_type(), TypeRef::float());
+        }
+    }
+
+    #[test]
+    fn test_intrinsic_atomics() {
+        let db = Database::new();
+
+        assert_eq!(
+            db.intrinsic("atomic_load").unwrap().return_type(),
+            TypeRef::int()
+        );
+        assert_eq!(
+            db.intrinsic("atomic_store").unwrap().return_type(),
+            TypeRef::nil()
+        );
+        assert_eq!(db.intrinsic("atomic_swap"), Some(Intrinsic::AtomicSwap));
+    }
+
+    #[test]
+    fn test_intrinsic_argument_types() {
+        let ptr = TypeRef::pointer(TypeId::Foreign(ForeignType::Int(
+            8,
+            Sign::Unsigned,
+        )));
+
+        assert_eq!(
+            Intrinsic::MemoryCopy.argument_types(),
+            Some(vec![ptr, ptr, TypeRef::int()])
+        );
+        assert_eq!(
+            Intrinsic::MemorySet.argument_types(),
+            Some(vec![ptr, TypeRef::int(), TypeRef::int()])
+        );
+        assert_eq!(Intrinsic::MemoryCompare.return_type(), TypeRef::int());
+        assert_eq!(
+            Intrinsic::Likely.argument_types(),
+            Some(vec![TypeRef::boolean()])
+        );
+        assert_eq!(Intrinsic::Unlikely.return_type(), TypeRef::boolean());
+        assert_eq!(
+            Intrinsic::StackAlloc.argument_types(),
+            Some(vec![TypeRef::int()])
+        );
+        assert_eq!(Intrinsic::StackAlloc.return_type(), ptr);
+        assert_eq!(Intrinsic::PrefetchRead.argument_types(), None);
+        assert_eq!(Intrinsic::IntDiv.argument_types(), None);
+    }
+
+    #[test]
+    fn test_memory_ordering() {
+        assert_eq!(MemoryOrdering::Release.for_load(), MemoryOrdering::Relaxed);
+        assert_eq!(
+            MemoryOrdering::AcquireRelease.for_load(),
+            MemoryOrdering::Acquire
+        );
+        assert_eq!(
+            MemoryOrdering::Acquire.for_store(),
+            MemoryOrdering::Relaxed
+        );
+        assert_eq!(
+            MemoryOrdering::AcquireRelease.for_store(),
+            MemoryOrdering::Release
+        );
+        assert_eq!(
+            MemoryOrdering::SequentiallyConsistent.for_load(),
+            MemoryOrdering::SequentiallyConsistent
+        );
+        assert_eq!(MemoryOrdering::all()[4] as i64, 4);
+    }
+
+    #[test]
+    fn test_intrinsic_int128() {
+        let db = Database::new();
+        let add = db.intrinsic("int128_checked_add").unwrap();
+        let mul = db.intrinsic("int128_wrapping_mul").unwrap();
+
+        assert_eq!(add, Intrinsic::Int128CheckedAdd);
+        assert_eq!(
+            add.return_type().class_id(&db),
+            Some(ClassId::checked_int128_result())
+        );
+        assert_eq!(mul.return_type(), TypeRef::foreign_signed_int(128));
+        assert!(ClassId::checked_int128_result().kind(&db).is_extern());
     }
 
     #[test]
@@ -6260,7 +10255,12 @@ mod tests {
     #[test]
     fn test_class_id_is_builtin() {
         assert!(ClassId::int().is_builtin());
-        assert!(!ClassId::tuple8().is_builtin());
+        assert!(ClassId::char().is_builtin());
+        assert!(ClassId::tuple1().is_builtin());
+        assert!(ClassId::tuple8().is_builtin());
+        assert!(ClassId::inclusive_range().is_builtin());
+        assert!(ClassId::exclusive_range().is_builtin());
+        assert!(!ClassId(FIRST_USER_CLASS_ID).is_builtin());
         assert!(!ClassId(42).is_builtin());
     }
 
@@ -6325,6 +10325,87 @@ mod tests {
         assert_eq!(var3.value(&db), Some(TypeRef::int()));
     }
 
+    #[test]
+    fn test_type_placeholder_id_assign_placeholder() {
+        let mut db = Database::new();
+        let var1 = TypePlaceholder::alloc(&mut db, None);
+        let var2 = TypePlaceholder::alloc(&mut db, None);
+        let var3 = TypePlaceholder::alloc(&mut db, None);
+
+        var1.assign(&mut db, TypeRef::Placeholder(var2));
+        var2.assign(&mut db, TypeRef::Placeholder(var3));
+        var3.assign(&mut db, TypeRef::Placeholder(var1));
+
+        assert_eq!(var1.root(&db), var3.root(&db));
+        assert!(var1.value(&db).is_none());
+
+        var2.assign(&mut db, TypeRef::int());
+
+        assert_eq!(var1.value(&db), Some(TypeRef::int()));
+        assert_eq!(var3.value(&db), Some(TypeRef::int()));
+        assert_eq!(var3.as_ref().value(&db), Some(TypeRef::int().as_ref(&db)));
+    }
+
+    #[test]
+    fn test_type_placeholder_id_assign_placeholder_with_ownership() {
+        let mut db = Database::new();
+        let thing = new_class(&mut db, "Thing");
+        let var1 = TypePlaceholder::alloc(&mut db, None);
+        let var2 = TypePlaceholder::alloc(&mut db, None);
+
+        var1.assign(&mut db, TypeRef::Placeholder(var2.as_ref()));
+        var2.assign(&mut db, TypeRef::Placeholder(var1.as_mut()));
+        var2.assign(&mut db, owned(instance(thing)));
+
+        assert_ne!(var1.root(&db), var2.root(&db));
+        assert_eq!(var1.value(&db), Some(immutable(instance(thing))));
+    }
+
+    #[test]
+    fn test_type_placeholder_id_assign_recursive_type() {
+        let mut db = Database::new();
+        let array = ClassId::array();
+
+        array.new_type_parameter(&mut db, "T".to_string());
+
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let nested = owned(generic_instance_id(
+            &mut db,
+            array,
+            vec![placeholder(var.as_ref())],
+        ));
+        let closure = Closure::alloc(&mut db, false);
+
+        closure.set_return_type(&mut db, placeholder(var));
+
+        assert!(!var.assign(&mut db, nested));
+        assert!(!var.assign(&mut db, owned(TypeId::Closure(closure))));
+        assert!(var.value(&db).is_none());
+        assert!(var.assign(&mut db, TypeRef::int()));
+    }
+
+    #[test]
+    fn test_type_placeholder_id_root_with_long_chain() {
+        let mut db = Database::new();
+        let vars: Vec<_> =
+            (0..64).map(|_| TypePlaceholder::alloc(&mut db, None)).collect();
+
+        for pair in vars.windows(2) {
+            pair[0].assign(&mut db, TypeRef::Placeholder(pair[1]));
+        }
+
+        vars[63].assign(&mut db, TypeRef::int());
+
+        let root = vars[0].root(&db);
+
+        assert!(vars.iter().all(|v| db.type_placeholders[v.id as usize]
+            .parent
+            .get()
+            == root
+            || v.id == root));
+        assert!(vars.iter().all(|v| v.value(&db) == Some(TypeRef::int())));
+    }
+
     #[test]
     fn test_type_ref_allow_as_ref() {
         let mut db = Database::new();
@@ -6398,6 +10479,29 @@ mod tests {
         assert_eq!(owned(rigid(p3)).as_mut(&db), owned(rigid(p3)));
     }
 
+    #[test]
+    fn test_type_ref_non_null_pointer() {
+        let mut db = Database::new();
+        let int = instance(ClassId::int());
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let non_null = TypeRef::non_null_pointer(int);
+
+        var.assign(&mut db, pointer(int));
+
+        assert!(pointer(int).is_pointer(&db));
+        assert!(non_null.is_pointer(&db));
+        assert!(pointer(int).is_nullable_pointer(&db));
+        assert!(!non_null.is_nullable_pointer(&db));
+        assert!(!pointer(int).allow_dereference(&db));
+        assert!(non_null.allow_dereference(&db));
+        assert_eq!(pointer(int).as_non_null(&db), non_null);
+        assert_eq!(placeholder(var).as_non_null(&db), non_null);
+        assert_eq!(non_null.as_pointer(&db), non_null);
+        assert_eq!(non_null.cast_according_to(&db, pointer(int)), pointer(int));
+        assert_eq!(non_null.cast_according_to(&db, non_null), non_null);
+        assert_eq!(pointer(int).cast_according_to(&db, non_null), pointer(int));
+    }
+
     #[test]
     fn test_type_ref_as_pointer() {
         let mut db = Database::new();
@@ -6433,12 +10537,68 @@ mod tests {
         let var =
             Variable::alloc(&mut db, "thing".to_string(), var_type, false, loc);
 
-        func2.add_capture(&mut db, var, var_type);
+        func2.add_capture(&mut db, var, var_type, loc);
 
         assert!(owned(closure(func1)).is_sendable(&db));
         assert!(!owned(closure(func2)).is_sendable(&db));
     }
 
+    #[test]
+    fn test_closure_id_add_capture() {
+        let mut db = Database::new();
+        let func = Closure::alloc(&mut db, false);
+        let thing = new_class(&mut db, "Thing");
+        let loc1 = Location::new(&(1..=1), &(1..=1));
+        let loc2 = Location::new(&(2..=2), &(1..=1));
+        let var1 = Variable::alloc(
+            &mut db,
+            "a".to_string(),
+            owned(instance(thing)),
+            false,
+            loc1,
+        );
+        let var2 = Variable::alloc(
+            &mut db,
+            "b".to_string(),
+            TypeRef::int(),
+            false,
+            loc1,
+        );
+
+        func.add_capture(&mut db, var1, mutable(instance(thing)), loc1);
+        func.add_capture(&mut db, var1, mutable(instance(thing)), loc2);
+        func.add_capture(&mut db, var1, mutable(instance(thing)), loc2);
+        func.add_capture(&mut db, var2, TypeRef::int(), loc2);
+
+        let cap = func.capture(&db, var1).unwrap();
+
+        assert_eq!(func.captures(&db).len(), 2);
+        assert_eq!(cap.mode, CaptureMode::Mut);
+        assert_eq!(cap.locations, vec![loc1, loc2]);
+        assert_eq!(func.capture(&db, var2).unwrap().mode, CaptureMode::Move);
+        assert_eq!(
+            func.captured(&db),
+            vec![(var1, mutable(instance(thing))), (var2, TypeRef::int())]
+        );
+        assert!(!func.can_infer_as_uni(&db));
+    }
+
+    #[test]
+    fn test_capture_mode_for_type() {
+        let db = Database::new();
+        let int = instance(ClassId::int());
+
+        assert_eq!(CaptureMode::for_type(&db, owned(int)), CaptureMode::Move);
+        assert_eq!(CaptureMode::for_type(&db, uni(int)), CaptureMode::Move);
+        assert_eq!(
+            CaptureMode::for_type(&db, immutable(int)),
+            CaptureMode::Ref
+        );
+        assert_eq!(CaptureMode::for_type(&db, mutable(int)), CaptureMode::Mut);
+        assert!(CaptureMode::Ref.is_borrow());
+        assert!(!CaptureMode::Move.is_borrow());
+    }
+
     #[test]
     fn test_type_ref_as_owned_with_placeholder() {
         let mut db = Database::new();
@@ -6680,6 +10840,107 @@ mod tests {
         assert!(!test_mod.has_same_root_namespace(&db, bla_mod));
     }
 
+    #[test]
+    fn test_module_id_is_same_or_test_module() {
+        let mut db = Database::new();
+        let array = new_module(&mut db, "std.array");
+        let test_array = new_module(&mut db, "std.test_array");
+        let test_other = new_module(&mut db, "std.test_other");
+        let nested = new_module(&mut db, "std.net.test_array");
+        let root = new_module(&mut db, "bla");
+        let test_root = new_module(&mut db, "test_bla");
+        let json1 = new_module(&mut db, "json.parser@1.0.0");
+        let test_json1 = new_module(&mut db, "json.test_parser@1.0.0");
+        let test_json2 = new_module(&mut db, "json.test_parser@2.0.0");
+
+        assert!(array.is_same_or_test_module(&db, array));
+        assert!(array.is_same_or_test_module(&db, test_array));
+        assert!(!test_array.is_same_or_test_module(&db, array));
+        assert!(!array.is_same_or_test_module(&db, test_other));
+        assert!(!array.is_same_or_test_module(&db, nested));
+        assert!(root.is_same_or_test_module(&db, test_root));
+        assert!(json1.is_same_or_test_module(&db, test_json1));
+        assert!(!json1.is_same_or_test_module(&db, test_json2));
+    }
+
+    #[test]
+    fn test_visibility_allows_access() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "std.foo");
+        let bar = new_module(&mut db, "std.bar");
+        let bla = new_module(&mut db, "bla");
+
+        assert_eq!(Visibility::public(false), Visibility::Namespace);
+        assert!(Visibility::Public.allows_access(&db, foo, bla));
+        assert!(Visibility::Namespace.allows_access(&db, foo, bar));
+        assert!(!Visibility::Namespace.allows_access(&db, foo, bla));
+        assert!(Visibility::Private.allows_access(&db, foo, foo));
+        assert!(!Visibility::Private.allows_access(&db, foo, bar));
+        assert!(!Visibility::TypePrivate.allows_access(&db, foo, foo));
+    }
+
+    #[test]
+    fn test_visibility_allows_access_with_packages() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "json.foo");
+        let bar = new_module(&mut db, "json.bar");
+        let bla = new_module(&mut db, "bla");
+        let json = Package::alloc(&mut db, "json".to_string(), None);
+        let other = Package::alloc(&mut db, "other".to_string(), None);
+
+        json.add_module(&mut db, foo);
+        json.add_module(&mut db, bla);
+        other.add_module(&mut db, bar);
+
+        assert!(Visibility::Namespace.allows_access(&db, foo, bla));
+        assert!(!Visibility::Namespace.allows_access(&db, foo, bar));
+    }
+
+    #[test]
+    fn test_symbol_is_visible_to() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "std.foo");
+        let bar = new_module(&mut db, "std.bar");
+        let bla = new_module(&mut db, "bla");
+        let shared = Class::alloc(
+            &mut db,
+            "Shared".to_string(),
+            ClassKind::Regular,
+            Visibility::Namespace,
+            foo,
+            Location::default(),
+        );
+        let hidden = Class::alloc(
+            &mut db,
+            "Hidden".to_string(),
+            ClassKind::Regular,
+            Visibility::Private,
+            foo,
+            Location::default(),
+        );
+        let field = shared.new_field(
+            &mut db,
+            "value".to_string(),
+            0,
+            TypeRef::int(),
+            Visibility::Namespace,
+            foo,
+            Location::default(),
+        );
+
+        assert_eq!(
+            Symbol::Class(shared).visibility(&db),
+            Visibility::Namespace
+        );
+        assert!(Symbol::Class(shared).is_visible_to(&db, bar));
+        assert!(!Symbol::Class(shared).is_visible_to(&db, bla));
+        assert!(Symbol::Class(hidden).is_visible_to(&db, foo));
+        assert!(!Symbol::Class(hidden).is_visible_to(&db, bar));
+        assert!(Symbol::Module(bla).is_visible_to(&db, foo));
+        assert!(field.is_visible_to(&db, bar));
+        assert!(!field.is_visible_to(&db, bla));
+    }
+
     #[test]
     fn test_type_ref_type_arguments_with_type_parameter() {
         let mut db = Database::new();
@@ -6739,6 +11000,10 @@ mod tests {
             Shape::Boolean
         );
         assert_eq!(TypeRef::nil().shape(&db, &mut inter, &shapes), Shape::Nil);
+        assert_eq!(
+            TypeRef::char().shape(&db, &mut inter, &shapes),
+            Shape::Char
+        );
         assert_eq!(
             TypeRef::string().shape(&db, &mut inter, &shapes),
             Shape::String
@@ -6832,6 +11097,10 @@ mod tests {
                 .shape(&db, &mut inter, &shapes),
             Shape::Int(32, Sign::Signed)
         );
+        assert_eq!(
+            TypeRef::constant(3).shape(&db, &mut inter, &shapes),
+            Shape::Constant(3)
+        );
         assert_eq!(
             owned(TypeId::Foreign(ForeignType::Int(32, Sign::Unsigned)))
                 .shape(&db, &mut inter, &shapes),
@@ -6877,6 +11146,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_shape_is_foreign() {
+        assert!(!Shape::int().is_foreign());
+        assert!(!Shape::float().is_foreign());
+        assert!(Shape::Int(32, Sign::Signed).is_foreign());
+        assert!(Shape::Int(128, Sign::Unsigned).is_foreign());
+        assert!(Shape::Float(32).is_foreign());
+        assert!(Shape::Float(80).is_foreign());
+        assert!(Shape::Float(128).is_foreign());
+        assert!(!Shape::Owned.is_foreign());
+    }
+
     #[test]
     fn test_type_ref_class_id() {
         let db = Database::new();
@@ -6988,4 +11269,35 @@ mod tests {
         assert_eq!(id3, id1);
         assert_eq!(id4, id1);
     }
+
+    #[test]
+    fn test_constructor_argument_names() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Shape");
+        let circle = class.new_constructor(
+            &mut db,
+            "Circle".to_string(),
+            vec![TypeRef::float()],
+            Location::default(),
+        );
+        let point = class.new_constructor(
+            &mut db,
+            "Point".to_string(),
+            vec![TypeRef::int(), TypeRef::int()],
+            Location::default(),
+        );
+
+        circle.set_argument_names(&mut db, vec!["radius".to_string()]);
+
+        assert!(circle.has_named_arguments(&db));
+        assert!(!point.has_named_arguments(&db));
+        assert_eq!(circle.argument_names(&db), &["radius".to_string()]);
+        assert_eq!(circle.argument_index(&db, "radius"), Some(0));
+        assert_eq!(circle.argument_index(&db, "size"), None);
+        assert_eq!(
+            circle.named_argument(&db, "radius"),
+            Some(TypeRef::float())
+        );
+        assert_eq!(point.named_argument(&db, "x"), None);
+    }
 }
@@ -0,0 +1,541 @@
+//! Looking up methods, optionally tracing the candidates considered.
+//!
+//! Looking up a method involves searching several places: the methods of a
+//! class, the default and required methods of traits, the traits required by
+//! type parameters and other traits, and blanket implementations. When a
+//! lookup fails or produces an unexpected method, it can be difficult to tell
+//! why.
+//!
+//! To help with this, a lookup can record every candidate it considers in a
+//! `MethodTrace`. Tracing is optional, as regular lookups don't need this
+//! information.
+//!
+//! For instances of classes, methods are looked up in the following order:
+//!
+//! 1. The methods defined for the class, including methods implemented for or
+//!    inherited from the traits it implements. A class can't define two
+//!    methods with the same name, so there's at most one such method.
+//! 2. The methods of traits implemented through a blanket implementation,
+//!    in the order the traits are defined in.
+//!
+//! This means a method defined for a class takes precedence over a method
+//! with the same name provided by a blanket implementation. This is ambiguous
+//! when the method is called through the trait using dynamic dispatch, as the
+//! call then uses the method of the class instead of the method of the trait.
+//! `ClassInstance::method_ambiguity()` detects such cases, and
+//! `ClassInstance::qualified_method()` resolves calls such as
+//! `Trait.method(receiver)` that explicitly select the method of a trait.
+use crate::check::TypeChecker;
+use crate::{
+    ClassId, ClassInstance, Database, MethodId, MethodLookup, MethodSource,
+    ModuleId, TraitId, TraitInstance, TypeId,
+};
+
+/// A place searched when looking up a method.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Candidate {
+    /// The methods defined in a class, including the class of a module.
+    Class(ClassId, Option<MethodId>),
+
+    /// The default and required methods of a trait.
+    Trait(TraitId, Option<MethodId>),
+
+    /// A trait searched because it's a requirement of a type parameter or
+    /// another trait.
+    Requirement(TraitInstance),
+
+    /// A trait searched because a blanket implementation of the trait applies
+    /// to the receiver.
+    BlanketImplementation(TraitId),
+
+    /// The external methods imported into a module.
+    ExternMethods(ModuleId, Option<MethodId>),
+}
+
+/// The candidates considered when looking up a method, in the order they are
+/// considered in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MethodTrace {
+    pub candidates: Vec<Candidate>,
+}
+
+impl MethodTrace {
+    /// Returns the methods found while looking up a method.
+    ///
+    /// Only the first method is used by the lookup, but the lookup may fail
+    /// for it (e.g. because it's private), in which case the other methods may
+    /// be used to suggest alternatives.
+    pub fn found(&self) -> Vec<MethodId> {
+        self.candidates
+            .iter()
+            .filter_map(|c| match c {
+                Candidate::Class(_, found)
+                | Candidate::Trait(_, found)
+                | Candidate::ExternMethods(_, found) => *found,
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn record(trace: &mut Option<&mut MethodTrace>, candidate: Candidate) {
+    if let Some(trace) = trace {
+        trace.candidates.push(candidate);
+    }
+}
+
+pub(crate) fn type_method(
+    db: &Database,
+    typ: TypeId,
+    name: &str,
+    trace: &mut Option<&mut MethodTrace>,
+) -> Option<MethodId> {
+    match typ {
+        TypeId::Class(id) => class_method(db, id, name, trace),
+        TypeId::Trait(id) => trait_method(db, id, name, trace),
+        TypeId::Module(id) => class_method(db, id.class(db), name, trace),
+        TypeId::ClassInstance(ins) => {
+            class_method(db, ins.instance_of(), name, trace)
+                .or_else(|| blanket_method(db, ins, name, trace))
+        }
+        TypeId::TraitInstance(ins) => {
+            trait_method(db, ins.instance_of(), name, trace)
+        }
+        TypeId::TypeParameter(id) | TypeId::RigidTypeParameter(id) => {
+            requirements_method(db, &id.get(db).requirements, name, trace)
+        }
+        _ => None,
+    }
+}
+
+fn class_method(
+    db: &Database,
+    class: ClassId,
+    name: &str,
+    trace: &mut Option<&mut MethodTrace>,
+) -> Option<MethodId> {
+    let found = class.method(db, name);
+
+    record(trace, Candidate::Class(class, found));
+    found
+}
+
+pub(crate) fn trait_method(
+    db: &Database,
+    trait_id: TraitId,
+    name: &str,
+    trace: &mut Option<&mut MethodTrace>,
+) -> Option<MethodId> {
+    let typ = trait_id.get(db);
+    let found = typ
+        .default_methods
+        .get(name)
+        .or_else(|| typ.required_methods.get(name))
+        .cloned();
+
+    record(trace, Candidate::Trait(trait_id, found));
+
+    if found.is_some() {
+        return found;
+    }
+
+    requirements_method(db, &typ.required_traits, name, trace)
+}
+
+pub(crate) fn requirements_method(
+    db: &Database,
+    requirements: &[TraitInstance],
+    name: &str,
+    trace: &mut Option<&mut MethodTrace>,
+) -> Option<MethodId> {
+    for &req in requirements {
+        record(trace, Candidate::Requirement(req));
+
+        if let Some(id) = trait_method(db, req.instance_of(), name, trace) {
+            return Some(id);
+        }
+    }
+
+    None
+}
+
+pub(crate) fn blanket_method(
+    db: &Database,
+    instance: ClassInstance,
+    name: &str,
+    trace: &mut Option<&mut MethodTrace>,
+) -> Option<MethodId> {
+    for trait_id in (0..db.traits.len()).map(|i| TraitId(i as u32)) {
+        if !trait_id.method_exists(db, name) {
+            continue;
+        }
+
+        if blanket_implementation_applies(db, instance, trait_id) {
+            record(trace, Candidate::BlanketImplementation(trait_id));
+            return trait_method(db, trait_id, name, trace);
+        }
+    }
+
+    None
+}
+
+fn blanket_implementation_applies(
+    db: &Database,
+    instance: ClassInstance,
+    trait_id: TraitId,
+) -> bool {
+    trait_id.blanket_implementations(db).iter().any(|imp| {
+        TypeChecker::new(db)
+            .class_implements_trait(instance, imp.implementation.instance)
+    })
+}
+
+/// A method defined for a class that has the same name as a method of a trait
+/// implemented through a blanket implementation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ambiguity {
+    /// The method defined for the class, which takes precedence.
+    pub method: MethodId,
+
+    /// The traits of which the method is shadowed by `method`.
+    pub traits: Vec<TraitId>,
+}
+
+impl ClassInstance {
+    /// Returns the ambiguity between the method of the class and the methods
+    /// of traits applying to the class, if there is any.
+    pub fn method_ambiguity(
+        self,
+        db: &Database,
+        name: &str,
+    ) -> Option<Ambiguity> {
+        let method = self.instance_of.method(db, name)?;
+        let source = match method.source(db) {
+            MethodSource::Direct => None,
+            MethodSource::Implemented(ins, _)
+            | MethodSource::Inherited(ins, _) => Some(ins.instance_of),
+        };
+        let traits: Vec<_> = (0..db.traits.len())
+            .map(|i| TraitId(i as u32))
+            .filter(|&id| {
+                Some(id) != source
+                    && id.method_exists(db, name)
+                    && blanket_implementation_applies(db, self, id)
+            })
+            .collect();
+
+        if traits.is_empty() {
+            None
+        } else {
+            Some(Ambiguity { method, traits })
+        }
+    }
+
+    /// Returns the method to use for a qualified call such as
+    /// `Trait.method(receiver)`, where `self` is the receiver.
+    ///
+    /// This ignores the methods of the class that aren't provided by the
+    /// trait, such that the call always uses the method of the trait.
+    pub fn qualified_method(
+        self,
+        db: &Database,
+        trait_id: TraitId,
+        name: &str,
+    ) -> Option<MethodId> {
+        let found =
+            self.instance_of.method(db, name).filter(|m| match m.source(db) {
+                MethodSource::Implemented(ins, _)
+                | MethodSource::Inherited(ins, _) => {
+                    ins.instance_of == trait_id
+                }
+                MethodSource::Direct => false,
+            });
+
+        if found.is_some() {
+            return found;
+        }
+
+        if blanket_implementation_applies(db, self, trait_id) {
+            trait_method(db, trait_id, name, &mut None)
+        } else {
+            None
+        }
+    }
+}
+
+impl TypeId {
+    /// Looks up a method, returning the result along with the candidates
+    /// considered.
+    pub fn lookup_method_with_trace(
+        self,
+        db: &Database,
+        name: &str,
+        module: ModuleId,
+        allow_type_private: bool,
+    ) -> (MethodLookup, MethodTrace) {
+        let mut trace = MethodTrace::default();
+        let result = self.resolve_method(
+            db,
+            name,
+            module,
+            allow_type_private,
+            &mut Some(&mut trace),
+        );
+
+        (result, trace)
+    }
+
+    pub(crate) fn resolve_method(
+        self,
+        db: &Database,
+        name: &str,
+        module: ModuleId,
+        allow_type_private: bool,
+        trace: &mut Option<&mut MethodTrace>,
+    ) -> MethodLookup {
+        if let Some(id) = type_method(db, self, name, trace) {
+            let kind = id.kind(db);
+            let is_ins = !matches!(
+                self,
+                TypeId::Class(_) | TypeId::Trait(_) | TypeId::Module(_)
+            );
+
+            if is_ins && kind.is_static() {
+                MethodLookup::StaticOnInstance
+            } else if !is_ins && !kind.is_static() {
+                MethodLookup::InstanceOnStatic
+            } else if matches!(self, TypeId::Trait(t) if t.is_required_method(db, id))
+            {
+                MethodLookup::RequiredStaticOnTrait
+            } else if self.can_call(db, id, module, allow_type_private) {
+                MethodLookup::Ok(id)
+            } else {
+                MethodLookup::Private
+            }
+        } else if let TypeId::Module(id) = self {
+            let found = id.extern_method(db, name);
+
+            record(trace, Candidate::ExternMethods(id, found));
+            found.map(MethodLookup::Ok).unwrap_or(MethodLookup::None)
+        } else {
+            MethodLookup::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{Code, Diagnostic};
+    use crate::test::{
+        new_class, new_method, new_module, new_parameter, new_trait, parameter,
+        trait_instance,
+    };
+    use crate::{
+        Location, MethodKind, TraitImplementation, TypeBounds, Visibility,
+    };
+
+    #[test]
+    fn test_lookup_method_with_trace_requirements() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let format = new_trait(&mut db, "Format");
+        let method =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+        let param = new_parameter(&mut db, "T");
+
+        to_string.add_default_method(&mut db, "to_string".to_string(), method);
+        format.add_required_trait(&mut db, trait_instance(to_string));
+        param.add_requirements(&mut db, vec![trait_instance(format)]);
+
+        let (res, trace) = parameter(param).lookup_method_with_trace(
+            &db,
+            "to_string",
+            module,
+            false,
+        );
+
+        assert!(matches!(res, MethodLookup::Ok(id) if id == method));
+        assert_eq!(
+            trace.candidates,
+            vec![
+                Candidate::Requirement(trait_instance(format)),
+                Candidate::Trait(format, None),
+                Candidate::Requirement(trait_instance(to_string)),
+                Candidate::Trait(to_string, Some(method)),
+            ]
+        );
+        assert_eq!(trace.found(), vec![method]);
+    }
+
+    #[test]
+    fn test_lookup_method_with_trace_missing() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+
+        let (res, trace) = TypeId::Class(class)
+            .lookup_method_with_trace(&db, "name", module, false);
+
+        assert!(matches!(res, MethodLookup::None));
+        assert_eq!(trace.candidates, vec![Candidate::Class(class, None)]);
+        assert!(trace.found().is_empty());
+
+        let (res, trace) = TypeId::Module(module)
+            .lookup_method_with_trace(&db, "name", module, false);
+
+        assert!(matches!(res, MethodLookup::None));
+        assert_eq!(
+            trace.candidates,
+            vec![
+                Candidate::Class(module.class(&db), None),
+                Candidate::ExternMethods(module, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_static_trait_methods() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let from_string = new_trait(&mut db, "FromString");
+        let parse = from_string.new_static_method(
+            &mut db,
+            "parse".to_string(),
+            Visibility::Public,
+            Location::default(),
+            true,
+        );
+        let default = from_string.new_static_method(
+            &mut db,
+            "default".to_string(),
+            Visibility::Public,
+            Location::default(),
+            false,
+        );
+        let to_string =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+        let typ = TypeId::Trait(from_string);
+
+        from_string.add_default_method(
+            &mut db,
+            "to_string".to_string(),
+            to_string,
+        );
+
+        assert_eq!(from_string.static_methods(&db), vec![parse, default]);
+        assert!(from_string.is_required_method(&db, parse));
+        assert!(!from_string.is_required_method(&db, default));
+        assert!(matches!(
+            typ.lookup_method(&db, "parse", module, false),
+            MethodLookup::RequiredStaticOnTrait
+        ));
+        assert!(matches!(
+            typ.lookup_method(&db, "default", module, false),
+            MethodLookup::Ok(id) if id == default
+        ));
+        assert!(matches!(
+            typ.lookup_method(&db, "to_string", module, false),
+            MethodLookup::InstanceOnStatic
+        ));
+        assert!(matches!(
+            TypeId::TraitInstance(trait_instance(from_string))
+                .lookup_method(&db, "default", module, false),
+            MethodLookup::StaticOnInstance
+        ));
+    }
+
+    #[test]
+    fn test_class_instance_method_ambiguity() {
+        let mut db = Database::new();
+        let format = new_trait(&mut db, "Format");
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+        let param = new_parameter(&mut db, "T");
+        let module = new_module(&mut db, "foo");
+        let trait_method =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+        let foo_method =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+        let bar_method =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+        let imp = |instance| TraitImplementation {
+            instance,
+            bounds: TypeBounds::new(),
+            module: ModuleId(0),
+            location: Location::default(),
+        };
+
+        to_string.add_default_method(
+            &mut db,
+            "to_string".to_string(),
+            trait_method,
+        );
+        param.add_requirements(&mut db, vec![trait_instance(format)]);
+        to_string.add_blanket_implementation(
+            &mut db,
+            param,
+            imp(trait_instance(to_string)),
+        );
+        foo.add_trait_implementation(&mut db, imp(trait_instance(format)));
+        foo.add_method(&mut db, "to_string".to_string(), foo_method);
+        bar.add_method(&mut db, "to_string".to_string(), bar_method);
+
+        let foo_ins = ClassInstance::new(foo);
+        let bar_ins = ClassInstance::new(bar);
+
+        assert_eq!(
+            foo_ins.method_ambiguity(&db, "to_string"),
+            Some(Ambiguity { method: foo_method, traits: vec![to_string] })
+        );
+
+        let ambiguity = foo_ins.method_ambiguity(&db, "to_string").unwrap();
+        let diag = Diagnostic::ambiguous_method(
+            &db,
+            foo,
+            &ambiguity,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert_eq!(diag.code, Code::AmbiguousMethod);
+        assert_eq!(diag.notes.len(), 2);
+        assert_eq!(bar_ins.method_ambiguity(&db, "to_string"), None);
+        assert_eq!(foo_ins.method_ambiguity(&db, "foo"), None);
+        assert_eq!(
+            TypeId::ClassInstance(foo_ins).method(&db, "to_string"),
+            Some(foo_method)
+        );
+        assert_eq!(
+            foo_ins.qualified_method(&db, to_string, "to_string"),
+            Some(trait_method)
+        );
+        assert_eq!(bar_ins.qualified_method(&db, to_string, "to_string"), None);
+
+        foo_method.set_source(
+            &mut db,
+            MethodSource::Implemented(trait_instance(to_string), trait_method),
+        );
+
+        assert_eq!(foo_ins.method_ambiguity(&db, "to_string"), None);
+        assert_eq!(
+            foo_ins.qualified_method(&db, to_string, "to_string"),
+            Some(foo_method)
+        );
+    }
+
+    #[test]
+    fn test_type_id_method_without_trace() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let method = new_method(&mut db, module, "name", MethodKind::Instance);
+
+        class.add_method(&mut db, "name".to_string(), method);
+
+        assert_eq!(TypeId::Class(class).method(&db, "name"), Some(method));
+        assert_eq!(TypeId::Class(class).method(&db, "age"), None);
+    }
+}
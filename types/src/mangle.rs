@@ -0,0 +1,366 @@
+//! Mangling and demangling of symbol names.
+//!
+//! Symbol names are produced for methods, classes and constants such that they
+//! are unique, and deterministic across compilations: they don't include any
+//! type IDs, as these depend on the order in which modules are processed.
+//!
+//! Symbol names use the following format:
+//!
+//!     _I{kind}_{module}.{class}.{name}#{shapes}
+//!
+//! Here `kind` is `M` for methods, `T` for classes and `C` for constants. The
+//! class is only included for methods defined in a class (i.e. not for module
+//! methods), and the shapes are only included for specialized methods and
+//! classes.
+//!
+//! The symbol names don't explicitly separate the module name from the class
+//! name. Instead, `demangle()` relies on class names starting with an
+//! uppercase letter and module names starting with a lowercase letter.
+use crate::{ClassId, ConstantId, Database, MethodId, ModuleId, Shape, Sign};
+use std::fmt;
+use std::fmt::Write as _;
+
+/// The prefix used for all symbol names.
+pub const SYMBOL_PREFIX: &str = "_I";
+
+/// The character that separates a name from its shapes.
+const SHAPES_SEPARATOR: char = '#';
+
+pub fn format_shape(db: &Database, shape: Shape, buf: &mut String) {
+    let _ = match shape {
+        Shape::Owned => write!(buf, "o"),
+        Shape::Mut => write!(buf, "m"),
+        Shape::Ref => write!(buf, "r"),
+        Shape::Int(s, Sign::Signed) => write!(buf, "i{}", s),
+        Shape::Int(s, Sign::Unsigned) => write!(buf, "u{}", s),
+        Shape::Float(s) => write!(buf, "f{}", s),
+        Shape::Boolean => write!(buf, "b"),
+        Shape::String => write!(buf, "s"),
+        Shape::Atomic => write!(buf, "a"),
+        Shape::Nil => write!(buf, "n"),
+        Shape::Char => write!(buf, "c"),
+        Shape::Pointer => write!(buf, "p"),
+        Shape::Constant(val) => write!(buf, "k{}", val),
+        Shape::Stack(ins) => {
+            let cls = ins.instance_of();
+            let _ = write!(buf, "S{}.", cls.module(db).name(db));
+
+            format_class_name(db, cls, buf);
+            Ok(())
+        }
+    };
+}
+
+pub fn format_shapes(db: &Database, shapes: &[Shape], buf: &mut String) {
+    for &shape in shapes {
+        format_shape(db, shape, buf);
+    }
+}
+
+pub fn format_class_name(db: &Database, id: ClassId, buf: &mut String) {
+    buf.push_str(id.name(db));
+
+    let is_stack = id.is_stack_allocated(db);
+    let shapes = id.shapes(db);
+
+    if !shapes.is_empty() || is_stack {
+        buf.push(SHAPES_SEPARATOR);
+    }
+
+    // In case we infer a type to be stack allocated (or we did so in the past
+    // but it's no longer the case), this ensures we flush the object cache.
+    if is_stack {
+        buf.push('S');
+    }
+
+    if !shapes.is_empty() {
+        format_shapes(db, shapes, buf);
+    }
+}
+
+pub fn qualified_class_name(
+    db: &Database,
+    module: ModuleId,
+    class: ClassId,
+) -> String {
+    let mut name = format!("{}.", module.name(db));
+
+    format_class_name(db, class, &mut name);
+    name
+}
+
+pub fn method_name(db: &Database, class: ClassId, id: MethodId) -> String {
+    let mut name = id.name(db).to_string();
+    let cshapes = class.shapes(db);
+    let mshapes = id.shapes(db);
+
+    if !cshapes.is_empty() || !mshapes.is_empty() {
+        name.push(SHAPES_SEPARATOR);
+        format_shapes(db, cshapes, &mut name);
+        format_shapes(db, mshapes, &mut name);
+    }
+
+    name
+}
+
+/// Returns the symbol name of a (specialized) method.
+pub fn method_symbol_name(db: &Database, method: MethodId) -> String {
+    let class = method.receiver(db).class_id(db).unwrap();
+
+    // We don't use MethodId::source_module() here as for default methods that
+    // may point to the module that defined the trait, rather than the module
+    // the trait is implemented in. That could result in symbol name conflicts
+    // when two different modules implement the same trait.
+    let mod_name = method.module(db).method_symbol_name(db).as_str();
+
+    // We don't use type IDs in the name as this would couple the symbol names
+    // to the order in which modules are processed.
+    if class.kind(db).is_module() {
+        // This ensures that methods such as `std::process.sleep` aren't
+        // formatted as `std::process::std::process.sleep`. This in turn makes
+        // stack traces easier to read.
+        format!(
+            "{}M_{}.{}",
+            SYMBOL_PREFIX,
+            mod_name,
+            method_name(db, class, method)
+        )
+    } else {
+        format!(
+            "{}M_{}.{}.{}",
+            SYMBOL_PREFIX,
+            mod_name,
+            class.name(db),
+            method_name(db, class, method)
+        )
+    }
+}
+
+/// Returns the symbol name of a class defined in the given module.
+pub fn class_symbol_name(
+    db: &Database,
+    module: ModuleId,
+    class: ClassId,
+) -> String {
+    format!("{}T_{}", SYMBOL_PREFIX, qualified_class_name(db, module, class))
+}
+
+/// Returns the symbol name of a constant.
+pub fn constant_symbol_name(db: &Database, id: ConstantId) -> String {
+    let mod_name = id.module(db).name(db).as_str();
+
+    format!("{}C_{}.{}", SYMBOL_PREFIX, mod_name, id.name(db))
+}
+
+/// The kind of symbol a demangled name refers to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Method,
+    Class,
+    Constant,
+}
+
+/// A symbol name split into its components.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Demangled {
+    pub kind: SymbolKind,
+
+    /// The name of the module the symbol is defined in.
+    pub module: String,
+
+    /// The name of the class a method is defined in.
+    ///
+    /// This is `None` for module methods, classes and constants.
+    pub class: Option<String>,
+
+    /// The name of the method, class or constant.
+    pub name: String,
+
+    /// The mangled shapes of a specialized method or class, if any.
+    pub shapes: Option<String>,
+}
+
+impl fmt::Display for Demangled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.", self.module)?;
+
+        if let Some(class) = &self.class {
+            write!(f, "{}.", class)?;
+        }
+
+        f.write_str(&self.name)
+    }
+}
+
+/// Splits a symbol name produced by this module into its components.
+///
+/// If the name isn't an Inko symbol name, `None` is returned.
+pub fn demangle(symbol: &str) -> Option<Demangled> {
+    let rest = symbol.strip_prefix(SYMBOL_PREFIX)?;
+    let (kind, rest) = match rest.split_once('_')? {
+        ("M", rest) => (SymbolKind::Method, rest),
+        ("T", rest) => (SymbolKind::Class, rest),
+        ("C", rest) => (SymbolKind::Constant, rest),
+        _ => return None,
+    };
+
+    // Shapes may include stack allocated types, which in turn contain the
+    // separator. Only the first separator marks the start of the shapes.
+    let (path, shapes) = match rest.split_once(SHAPES_SEPARATOR) {
+        Some((path, shapes)) => (path, Some(shapes.to_string())),
+        None => (rest, None),
+    };
+    let segments: Vec<_> = path.split('.').collect();
+    let (&name, parents) = segments.split_last()?;
+    let (module, class) = match kind {
+        SymbolKind::Method => {
+            match parents.iter().position(|s| starts_uppercase(s)) {
+                Some(idx) if idx + 1 == parents.len() && idx > 0 => {
+                    (parents[..idx].join("."), Some(parents[idx].to_string()))
+                }
+                Some(_) => return None,
+                None => (parents.join("."), None),
+            }
+        }
+        _ => (parents.join("."), None),
+    };
+
+    if module.is_empty() || name.is_empty() {
+        return None;
+    }
+
+    Some(Demangled { kind, module, class, name: name.to_string(), shapes })
+}
+
+fn starts_uppercase(value: &str) -> bool {
+    value.chars().next().map_or(false, |c| c.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_name::ModuleName;
+    use crate::{
+        Class, ClassInstance, ClassKind, Location, Method, MethodKind, Module,
+        TypeId, TypeRef, Visibility,
+    };
+
+    fn name(db: &Database, shape: Shape) -> String {
+        let mut buf = String::new();
+
+        format_shape(db, shape, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_format_shape() {
+        let mut db = Database::new();
+        let mid =
+            Module::alloc(&mut db, ModuleName::new("a.b.c"), "c.inko".into());
+        let kind = ClassKind::Regular;
+        let vis = Visibility::Public;
+        let loc = Location::default();
+        let cls1 = Class::alloc(&mut db, "A".to_string(), kind, vis, mid, loc);
+        let cls2 = Class::alloc(&mut db, "B".to_string(), kind, vis, mid, loc);
+
+        cls1.set_shapes(
+            &mut db,
+            vec![
+                Shape::Int(64, Sign::Signed),
+                Shape::Stack(ClassInstance::new(cls2)),
+            ],
+        );
+        cls2.set_shapes(&mut db, vec![Shape::String]);
+
+        assert_eq!(name(&db, Shape::Owned), "o");
+        assert_eq!(name(&db, Shape::Mut), "m");
+        assert_eq!(name(&db, Shape::Ref), "r");
+        assert_eq!(name(&db, Shape::Int(32, Sign::Signed)), "i32");
+        assert_eq!(name(&db, Shape::Int(32, Sign::Unsigned)), "u32");
+        assert_eq!(name(&db, Shape::Float(32)), "f32");
+        assert_eq!(name(&db, Shape::Boolean), "b");
+        assert_eq!(name(&db, Shape::String), "s");
+        assert_eq!(name(&db, Shape::Atomic), "a");
+        assert_eq!(name(&db, Shape::Nil), "n");
+        assert_eq!(name(&db, Shape::Char), "c");
+        assert_eq!(name(&db, Shape::Pointer), "p");
+        assert_eq!(name(&db, Shape::Constant(3)), "k3");
+        assert_eq!(
+            name(&db, Shape::Stack(ClassInstance::new(cls1))),
+            "Sa.b.c.A#i64Sa.b.c.B#s"
+        );
+    }
+
+    #[test]
+    fn test_method_symbol_name() {
+        let mut db = Database::new();
+        let mid =
+            Module::alloc(&mut db, ModuleName::new("a.b"), "b.inko".into());
+        let kind = ClassKind::Regular;
+        let vis = Visibility::Public;
+        let loc = Location::default();
+        let cls = Class::alloc(&mut db, "A".to_string(), kind, vis, mid, loc);
+        let method = Method::alloc(
+            &mut db,
+            mid,
+            loc,
+            "foo".to_string(),
+            vis,
+            MethodKind::Instance,
+        );
+
+        cls.set_shapes(&mut db, vec![Shape::Int(64, Sign::Signed)]);
+        method.set_receiver(&mut db, TypeRef::Owned(TypeId::Class(cls)));
+        method.set_shapes(&mut db, vec![Shape::String]);
+
+        let symbol = method_symbol_name(&db, method);
+
+        assert_eq!(symbol, "_IM_a.b.A.foo#i64s");
+        assert_eq!(method_symbol_name(&db, method), symbol);
+        assert_eq!(
+            demangle(&symbol),
+            Some(Demangled {
+                kind: SymbolKind::Method,
+                module: "a.b".to_string(),
+                class: Some("A".to_string()),
+                name: "foo".to_string(),
+                shapes: Some("i64s".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_demangle() {
+        let module_method = demangle("_IM_std.process.sleep").unwrap();
+        let class = demangle("_IT_a.b.A#i64Sa.b.c.B#s").unwrap();
+        let constant = demangle("_IC_std.int.MAX").unwrap();
+
+        assert_eq!(module_method.kind, SymbolKind::Method);
+        assert_eq!(module_method.module, "std.process");
+        assert_eq!(module_method.class, None);
+        assert_eq!(module_method.name, "sleep");
+        assert_eq!(module_method.shapes, None);
+        assert_eq!(module_method.to_string(), "std.process.sleep");
+
+        assert_eq!(class.kind, SymbolKind::Class);
+        assert_eq!(class.module, "a.b");
+        assert_eq!(class.name, "A");
+        assert_eq!(class.shapes, Some("i64Sa.b.c.B#s".to_string()));
+
+        assert_eq!(constant.kind, SymbolKind::Constant);
+        assert_eq!(constant.module, "std.int");
+        assert_eq!(constant.name, "MAX");
+
+        assert_eq!(
+            demangle("_IM_std.option.Option.Some").map(|d| d.to_string()),
+            Some("std.option.Option.Some".to_string())
+        );
+        assert_eq!(
+            demangle("_IM_std.int.$constants").unwrap().name,
+            "$constants"
+        );
+        assert_eq!(demangle("malloc"), None);
+        assert_eq!(demangle("_IX_foo.bar"), None);
+        assert_eq!(demangle("_IM_foo"), None);
+    }
+}
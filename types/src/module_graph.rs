@@ -0,0 +1,274 @@
+//! The dependency graph of modules.
+//!
+//! Edges are recorded when a module registers a symbol defined in another
+//! module (see `ModuleId::new_symbol()`), such that an edge from A to B means
+//! that A imports one or more symbols from B.
+use crate::{Database, ModuleId};
+
+/// A snapshot of the import relations between all modules.
+pub struct ModuleGraph {
+    /// The modules each module depends on, indexed by the module IDs.
+    dependencies: Vec<Vec<ModuleId>>,
+
+    /// The modules that depend on each module, indexed by the module IDs.
+    dependents: Vec<Vec<ModuleId>>,
+}
+
+impl ModuleGraph {
+    pub fn new(db: &Database) -> ModuleGraph {
+        let len = db.number_of_modules();
+        let mut dependencies = Vec::with_capacity(len);
+        let mut dependents = vec![Vec::new(); len];
+
+        for idx in 0..len {
+            let id = ModuleId(idx as u32);
+            let deps = id.dependencies(db).clone();
+
+            for &dep in &deps {
+                dependents[dep.0 as usize].push(id);
+            }
+
+            dependencies.push(deps);
+        }
+
+        ModuleGraph { dependencies, dependents }
+    }
+
+    /// Returns the modules that `module` imports symbols from.
+    pub fn dependencies(&self, module: ModuleId) -> &[ModuleId] {
+        &self.dependencies[module.0 as usize]
+    }
+
+    /// Returns the modules that import symbols from `module`.
+    pub fn dependents(&self, module: ModuleId) -> &[ModuleId] {
+        &self.dependents[module.0 as usize]
+    }
+
+    /// Returns the given modules and all modules that directly or indirectly
+    /// depend on them, sorted by their IDs.
+    ///
+    /// When the given modules change, these are the modules that must be
+    /// checked again.
+    pub fn affected_by(&self, modules: &[ModuleId]) -> Vec<ModuleId> {
+        let mut seen = vec![false; self.dependencies.len()];
+        let mut work = modules.to_vec();
+
+        while let Some(id) = work.pop() {
+            if seen[id.0 as usize] {
+                continue;
+            }
+
+            seen[id.0 as usize] = true;
+            work.extend(self.dependents(id).iter().cloned());
+        }
+
+        seen.iter()
+            .enumerate()
+            .filter(|(_, &seen)| seen)
+            .map(|(idx, _)| ModuleId(idx as u32))
+            .collect()
+    }
+
+    /// Returns the strongly connected components of the graph.
+    ///
+    /// The components are ordered such that a component comes after the
+    /// components it depends on.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<ModuleId>> {
+        Tarjan::new(self).run()
+    }
+
+    /// Returns the groups of modules that (indirectly) import each other.
+    pub fn cycles(&self) -> Vec<Vec<ModuleId>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|scc| scc.len() > 1)
+            .collect()
+    }
+
+    /// Returns the modules such that every module comes after the modules it
+    /// depends on.
+    ///
+    /// If the graph contains cycles, an `Err` is returned containing the
+    /// modules involved in each cycle.
+    pub fn topological_order(
+        &self,
+    ) -> Result<Vec<ModuleId>, Vec<Vec<ModuleId>>> {
+        let cycles = self.cycles();
+
+        if cycles.is_empty() {
+            Ok(self
+                .strongly_connected_components()
+                .into_iter()
+                .flatten()
+                .collect())
+        } else {
+            Err(cycles)
+        }
+    }
+}
+
+/// Tarjan's algorithm for finding strongly connected components.
+///
+/// This uses an explicit stack instead of recursion, such that large graphs
+/// don't overflow the native stack.
+struct Tarjan<'a> {
+    graph: &'a ModuleGraph,
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<ModuleId>,
+    next_index: usize,
+    components: Vec<Vec<ModuleId>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn new(graph: &'a ModuleGraph) -> Tarjan<'a> {
+        let len = graph.dependencies.len();
+
+        Tarjan {
+            graph,
+            index: vec![None; len],
+            lowlink: vec![0; len],
+            on_stack: vec![false; len],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Vec<Vec<ModuleId>> {
+        for idx in 0..self.index.len() {
+            if self.index[idx].is_none() {
+                self.visit(ModuleId(idx as u32));
+            }
+        }
+
+        self.components
+    }
+
+    fn visit(&mut self, root: ModuleId) {
+        // Each entry is a module along with the index of the next dependency
+        // to process.
+        let mut work = vec![(root, 0)];
+
+        self.enter(root);
+
+        while let Some((id, edge)) = work.pop() {
+            let node = id.0 as usize;
+
+            if let Some(&dep) = self.graph.dependencies(id).get(edge) {
+                let dep_node = dep.0 as usize;
+
+                work.push((id, edge + 1));
+
+                match self.index[dep_node] {
+                    None => {
+                        self.enter(dep);
+                        work.push((dep, 0));
+                    }
+                    Some(idx) if self.on_stack[dep_node] => {
+                        self.lowlink[node] = self.lowlink[node].min(idx);
+                    }
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            if let Some(&(parent, _)) = work.last() {
+                let parent = parent.0 as usize;
+
+                self.lowlink[parent] =
+                    self.lowlink[parent].min(self.lowlink[node]);
+            }
+
+            if Some(self.lowlink[node]) == self.index[node] {
+                let mut component = Vec::new();
+
+                loop {
+                    let member = self.stack.pop().unwrap();
+
+                    self.on_stack[member.0 as usize] = false;
+                    component.push(member);
+
+                    if member == id {
+                        break;
+                    }
+                }
+
+                component.sort_by_key(|m| m.0);
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn enter(&mut self, id: ModuleId) {
+        let node = id.0 as usize;
+
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.on_stack[node] = true;
+        self.next_index += 1;
+        self.stack.push(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_module;
+    use crate::Symbol;
+
+    fn import(db: &mut Database, from: ModuleId, to: ModuleId) {
+        let name = to.name(db).to_string();
+
+        from.new_symbol(db, name, Symbol::Module(to));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut db = Database::new();
+        let a = new_module(&mut db, "a");
+        let b = new_module(&mut db, "b");
+        let c = new_module(&mut db, "c");
+
+        import(&mut db, a, b);
+        import(&mut db, a, b);
+        import(&mut db, b, c);
+        a.new_symbol(&mut db, "a".to_string(), Symbol::Module(a));
+
+        let graph = ModuleGraph::new(&db);
+
+        assert_eq!(graph.dependencies(a), &[b]);
+        assert_eq!(graph.dependencies(c), &[]);
+        assert_eq!(graph.dependents(b), &[a]);
+        assert_eq!(graph.dependents(c), &[b]);
+        assert_eq!(graph.affected_by(&[c]), vec![a, b, c]);
+        assert_eq!(graph.affected_by(&[a]), vec![a]);
+        assert_eq!(graph.topological_order(), Ok(vec![c, b, a]));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut db = Database::new();
+        let a = new_module(&mut db, "a");
+        let b = new_module(&mut db, "b");
+        let c = new_module(&mut db, "c");
+        let d = new_module(&mut db, "d");
+
+        import(&mut db, a, b);
+        import(&mut db, b, c);
+        import(&mut db, c, b);
+        import(&mut db, b, d);
+
+        let graph = ModuleGraph::new(&db);
+
+        assert_eq!(
+            graph.strongly_connected_components(),
+            vec![vec![d], vec![b, c], vec![a]]
+        );
+        assert_eq!(graph.cycles(), vec![vec![b, c]]);
+        assert_eq!(graph.topological_order(), Err(vec![vec![b, c]]));
+        assert_eq!(graph.affected_by(&[c]), vec![a, b, c]);
+    }
+}
@@ -6,7 +6,16 @@ const MAIN_MODULE: &str = "main";
 const SOURCE_EXT: &str = "inko";
 pub const SEPARATOR: &str = ".";
 
+/// The separator between the name of a module and its package version.
+pub const VERSION_SEPARATOR: char = '@';
+
 /// The fully qualified name of a module.
+///
+/// A name may include the version of the package that provides the module,
+/// such as `json.parser@1.2.0`. This allows multiple versions of the same
+/// module to coexist, such as when two dependencies depend on different
+/// versions of the same library. The name without the version is referred to
+/// as the logical name, and is the same for all versions of a module.
 #[derive(Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
 pub struct ModuleName {
     value: String,
@@ -33,21 +42,53 @@ impl ModuleName {
         Self { value: value.into() }
     }
 
+    /// Returns a name for the given version of a module.
+    pub fn versioned<S: Into<String>>(name: S, version: &str) -> Self {
+        Self::new(format!("{}{}{}", name.into(), VERSION_SEPARATOR, version))
+    }
+
+    /// Returns the name without the package version.
+    pub fn logical_name(&self) -> &str {
+        self.value
+            .split_once(VERSION_SEPARATOR)
+            .map_or(self.value.as_str(), |(name, _)| name)
+    }
+
+    /// Returns the package version included in the name, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.value.split_once(VERSION_SEPARATOR).map(|(_, ver)| ver)
+    }
+
+    pub fn is_versioned(&self) -> bool {
+        self.version().is_some()
+    }
+
+    /// Returns the name without the package version.
+    pub fn without_version(&self) -> Self {
+        Self::new(self.logical_name())
+    }
+
     pub fn is_std(&self) -> bool {
         self.value.starts_with("std.")
     }
 
     pub fn head(&self) -> &str {
-        self.value.split(SEPARATOR).next().unwrap()
+        self.logical_name().split(SEPARATOR).next().unwrap()
     }
 
     pub fn tail(&self) -> &str {
-        self.value.split(SEPARATOR).last().unwrap()
+        self.logical_name().split(SEPARATOR).last().unwrap()
     }
 
+    /// Returns the path of the module's source file, relative to the source
+    /// directory of its package.
+    ///
+    /// The version isn't included, as it's determined by the directory the
+    /// package is stored in.
     pub fn to_path(&self) -> PathBuf {
-        let mut path =
-            PathBuf::from(self.value.replace(SEPARATOR, MAIN_SEPARATOR_STR));
+        let mut path = PathBuf::from(
+            self.logical_name().replace(SEPARATOR, MAIN_SEPARATOR_STR),
+        );
 
         path.set_extension(SOURCE_EXT);
         path
@@ -57,12 +98,13 @@ impl ModuleName {
         self.value.replace(SEPARATOR, "_")
     }
 
+    /// Returns the full name, including the package version (if any).
     pub fn as_str(&self) -> &str {
         self.value.as_str()
     }
 
     pub fn is_root(&self) -> bool {
-        !self.value.contains(SEPARATOR)
+        !self.logical_name().contains(SEPARATOR)
     }
 }
 
@@ -164,5 +206,22 @@ mod tests {
     fn test_is_root() {
         assert!(!ModuleName::new("foo.bar").is_root());
         assert!(ModuleName::new("foo").is_root());
+        assert!(ModuleName::versioned("foo", "1.2.0").is_root());
+    }
+
+    #[test]
+    fn test_versioned() {
+        let name = ModuleName::versioned("foo.bar", "1.2.0");
+
+        assert_eq!(name.as_str(), "foo.bar@1.2.0");
+        assert_eq!(name.logical_name(), "foo.bar");
+        assert_eq!(name.version(), Some("1.2.0"));
+        assert_eq!(name.head(), "foo");
+        assert_eq!(name.tail(), "bar");
+        assert_eq!(name.without_version(), ModuleName::new("foo.bar"));
+        assert_eq!(name.to_path(), ModuleName::new("foo.bar").to_path());
+        assert!(name.is_versioned());
+        assert!(!ModuleName::new("foo.bar").is_versioned());
+        assert_eq!(ModuleName::new("foo.bar").version(), None);
     }
 }
@@ -0,0 +1,247 @@
+//! Metadata about methods used to overload operators.
+//!
+//! Binary operators such as `+` and `==` are syntax sugar for calling a method
+//! of the same name on the left-hand side, with the right-hand side as the
+//! only argument. Each operator has a trait in the standard library that
+//! defines its method (e.g. `std.ops.Add` for `+`), but classes may also
+//! define these methods without implementing the trait.
+//!
+//! Indexing isn't an operator in Inko (`get` and `set` methods are used
+//! instead), so it isn't included here.
+use crate::{ClassId, Database, MethodId, MethodKind, Symbol, TraitId};
+
+/// The module containing the traits for arithmetic and bitwise operators.
+const OPS_MODULE: &str = "std.ops";
+
+/// The module containing the traits for comparison operators.
+const CMP_MODULE: &str = "std.cmp";
+
+/// Information about a single overloadable operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Operator {
+    /// The name of the method called for the operator.
+    pub name: &'static str,
+
+    /// The number of arguments the method must define, excluding the
+    /// receiver.
+    pub arguments: usize,
+
+    /// The module that defines the trait of the operator.
+    pub module: &'static str,
+
+    /// The name of the trait that defines the operator's method.
+    pub trait_name: &'static str,
+}
+
+impl Operator {
+    const fn binary(
+        name: &'static str,
+        module: &'static str,
+        trait_name: &'static str,
+    ) -> Operator {
+        Operator { name, arguments: 1, module, trait_name }
+    }
+
+    /// Returns the operator for the given method name, if any.
+    pub fn from_method_name(name: &str) -> Option<&'static Operator> {
+        OPERATORS.iter().find(|op| op.name == name)
+    }
+
+    /// Returns the trait that defines the method of this operator, if its
+    /// module is loaded.
+    pub fn trait_id(&self, db: &Database) -> Option<TraitId> {
+        match db.optional_module(self.module)?.symbol(db, self.trait_name) {
+            Some(Symbol::Trait(id)) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// All operators that can be overloaded.
+pub const OPERATORS: [Operator; 18] = [
+    Operator::binary("+", OPS_MODULE, "Add"),
+    Operator::binary("-", OPS_MODULE, "Subtract"),
+    Operator::binary("/", OPS_MODULE, "Divide"),
+    Operator::binary("*", OPS_MODULE, "Multiply"),
+    Operator::binary("%", OPS_MODULE, "Modulo"),
+    Operator::binary("**", OPS_MODULE, "Power"),
+    Operator::binary("&", OPS_MODULE, "BitAnd"),
+    Operator::binary("|", OPS_MODULE, "BitOr"),
+    Operator::binary("^", OPS_MODULE, "BitXor"),
+    Operator::binary("<<", OPS_MODULE, "ShiftLeft"),
+    Operator::binary(">>", OPS_MODULE, "ShiftRight"),
+    Operator::binary(">>>", OPS_MODULE, "UnsignedShiftRight"),
+    Operator::binary("==", CMP_MODULE, "Equal"),
+    Operator::binary("!=", CMP_MODULE, "Equal"),
+    Operator::binary("<", CMP_MODULE, "Compare"),
+    Operator::binary("<=", CMP_MODULE, "Compare"),
+    Operator::binary(">", CMP_MODULE, "Compare"),
+    Operator::binary(">=", CMP_MODULE, "Compare"),
+];
+
+/// An error produced when checking a method that overloads an operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OperatorError {
+    /// The method defines the wrong number of arguments.
+    InvalidArguments(MethodId, &'static Operator),
+
+    /// The method isn't a regular instance method (e.g. it's static or
+    /// async).
+    InvalidReceiver(MethodId, &'static Operator),
+}
+
+impl MethodId {
+    /// Returns the operator this method overloads, if any.
+    pub fn operator(self, db: &Database) -> Option<&'static Operator> {
+        Operator::from_method_name(self.name(db))
+    }
+
+    /// Checks if the method is a valid definition of its operator.
+    ///
+    /// Methods that don't overload an operator are always valid.
+    pub fn check_operator(self, db: &Database) -> Result<(), OperatorError> {
+        let Some(op) = self.operator(db) else { return Ok(()) };
+
+        match self.kind(db) {
+            MethodKind::Instance | MethodKind::Mutable | MethodKind::Moving => {
+            }
+            _ => return Err(OperatorError::InvalidReceiver(self, op)),
+        }
+
+        if self.number_of_arguments(db) != op.arguments {
+            return Err(OperatorError::InvalidArguments(self, op));
+        }
+
+        Ok(())
+    }
+}
+
+impl ClassId {
+    /// Checks the methods of the class that overload operators, returning the
+    /// errors in the order the methods are defined in.
+    pub fn check_operator_methods(self, db: &Database) -> Vec<OperatorError> {
+        let mut methods = self.methods(db);
+
+        // Methods are stored in a hash map, so we sort them to ensure the
+        // errors are produced in a consistent order.
+        methods.sort_by_key(|m| (m.location(db), m.0));
+        methods.into_iter().filter_map(|m| m.check_operator(db).err()).collect()
+    }
+}
+
+impl Database {
+    /// Returns the method to call when using the given operator with an
+    /// instance of the class as the left-hand side.
+    ///
+    /// If `operator` isn't an overloadable operator, `None` is returned.
+    pub fn operator_method(
+        &self,
+        class: ClassId,
+        operator: &str,
+    ) -> Option<MethodId> {
+        let op = Operator::from_method_name(operator)?;
+
+        class.method(self, op.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        instance, new_class, new_method, new_module, new_trait, owned,
+    };
+    use crate::{Block, Location, TypeRef};
+
+    fn define_method(
+        db: &mut Database,
+        class: ClassId,
+        kind: MethodKind,
+        name: &str,
+        arguments: usize,
+    ) -> MethodId {
+        let module = new_module(db, "foo");
+        let id = new_method(db, module, name, kind);
+
+        for idx in 0..arguments {
+            id.new_argument(
+                db,
+                format!("arg{}", idx),
+                TypeRef::int(),
+                TypeRef::int(),
+                Location::default(),
+            );
+        }
+
+        id.set_receiver(db, owned(instance(class)));
+        class.add_method(db, name.to_string(), id);
+        id
+    }
+
+    #[test]
+    fn test_operator_from_method_name() {
+        let add = Operator::from_method_name("+").unwrap();
+        let ne = Operator::from_method_name("!=").unwrap();
+
+        assert_eq!(add.arguments, 1);
+        assert_eq!(add.trait_name, "Add");
+        assert_eq!(add.module, OPS_MODULE);
+        assert_eq!(ne.trait_name, "Equal");
+        assert_eq!(ne.module, CMP_MODULE);
+        assert_eq!(Operator::from_method_name("[]"), None);
+        assert_eq!(Operator::from_method_name("to_string"), None);
+    }
+
+    #[test]
+    fn test_operator_trait_id() {
+        let mut db = Database::new();
+        let add = Operator::from_method_name("+").unwrap();
+
+        assert_eq!(add.trait_id(&db), None);
+
+        let module = new_module(&mut db, OPS_MODULE);
+        let trait_id = new_trait(&mut db, "Add");
+
+        module.new_symbol(&mut db, "Add".to_string(), Symbol::Trait(trait_id));
+
+        assert_eq!(add.trait_id(&db), Some(trait_id));
+    }
+
+    #[test]
+    fn test_method_id_check_operator() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Point");
+        let add = define_method(&mut db, class, MethodKind::Instance, "+", 1);
+        let sub = define_method(&mut db, class, MethodKind::Mutable, "-", 2);
+        let eq = define_method(&mut db, class, MethodKind::Static, "==", 1);
+        let other = define_method(&mut db, class, MethodKind::Static, "foo", 3);
+        let op = |name| Operator::from_method_name(name).unwrap();
+
+        assert_eq!(add.operator(&db), Some(op("+")));
+        assert_eq!(other.operator(&db), None);
+        assert_eq!(add.check_operator(&db), Ok(()));
+        assert_eq!(other.check_operator(&db), Ok(()));
+        assert_eq!(
+            sub.check_operator(&db),
+            Err(OperatorError::InvalidArguments(sub, op("-")))
+        );
+        assert_eq!(
+            eq.check_operator(&db),
+            Err(OperatorError::InvalidReceiver(eq, op("==")))
+        );
+        assert_eq!(class.check_operator_methods(&db).len(), 2);
+    }
+
+    #[test]
+    fn test_database_operator_method() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Point");
+        let add = define_method(&mut db, class, MethodKind::Instance, "+", 1);
+
+        define_method(&mut db, class, MethodKind::Instance, "foo", 1);
+
+        assert_eq!(db.operator_method(class, "+"), Some(add));
+        assert_eq!(db.operator_method(class, "-"), None);
+        assert_eq!(db.operator_method(class, "foo"), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! Tracking the source locations that produce types.
+//!
+//! Types only know the location of the entity that defines them (e.g. a
+//! class), not the location at which a particular `TypeRef` is written. For
+//! example, given `let a: Array[Int] = ...` the type `Array[Int]` doesn't
+//! know it's produced by the annotation of the variable `a`.
+//!
+//! To produce better diagnostics, the locations of such annotations can be
+//! recorded per expression: for every expression we record the location of
+//! the annotation that produced its type. Diagnostics for type mismatches can
+//! then point to the annotations that produced both the given and expected
+//! types.
+//!
+//! Recording origins requires memory for every expression, while the data is
+//! only needed when producing diagnostics. As such, origins are only recorded
+//! when enabled using `Database::track_origins()`.
+use crate::{Database, ModuleId};
+use location::Location;
+use std::collections::HashMap;
+
+/// An expression to record the origin of its type for.
+///
+/// Expressions are identified using their module and source location.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExpressionKey {
+    pub module: ModuleId,
+    pub location: Location,
+}
+
+impl ExpressionKey {
+    pub fn new(module: ModuleId, location: Location) -> ExpressionKey {
+        ExpressionKey { module, location }
+    }
+}
+
+/// The source location that produced a type, such as a type annotation.
+///
+/// The module may differ from the module of the expression, such as when the
+/// type of a call is produced by the return type of a method defined in a
+/// different module.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+    pub module: ModuleId,
+    pub location: Location,
+}
+
+impl Origin {
+    pub fn new(module: ModuleId, location: Location) -> Origin {
+        Origin { module, location }
+    }
+}
+
+/// A table mapping expressions to the origins of their types.
+#[derive(Default)]
+pub struct Origins {
+    mapping: HashMap<ExpressionKey, Origin>,
+}
+
+impl Origins {
+    pub fn len(&self) -> usize {
+        self.mapping.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mapping.is_empty()
+    }
+
+    /// Removes the origins of the expressions in the given module.
+    pub(crate) fn remove_module(&mut self, module: ModuleId) {
+        self.mapping.retain(|key, _| key.module != module);
+    }
+}
+
+impl Database {
+    /// Enables the recording of type origins.
+    ///
+    /// Calling this method multiple times retains any origins already
+    /// recorded.
+    pub fn track_origins(&mut self) {
+        if self.origins.is_none() {
+            self.origins = Some(Origins::default());
+        }
+    }
+
+    pub fn is_tracking_origins(&self) -> bool {
+        self.origins.is_some()
+    }
+
+    /// Records the origin of the type of an expression.
+    ///
+    /// If the tracking of origins isn't enabled, this method does nothing.
+    pub fn record_origin(&mut self, expression: ExpressionKey, origin: Origin) {
+        if let Some(origins) = self.origins.as_mut() {
+            origins.mapping.insert(expression, origin);
+        }
+    }
+
+    /// Returns the origin of the type of an expression, if any is recorded.
+    pub fn origin(&self, expression: ExpressionKey) -> Option<Origin> {
+        self.origins.as_ref()?.mapping.get(&expression).cloned()
+    }
+
+    pub fn origins(&self) -> Option<&Origins> {
+        self.origins.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(line: u32) -> Location {
+        Location::new(&(line..=line), &(1..=1))
+    }
+
+    #[test]
+    fn test_database_record_origin() {
+        let mut db = Database::new();
+        let expr = ExpressionKey::new(ModuleId(0), loc(1));
+        let origin = Origin::new(ModuleId(1), loc(2));
+
+        db.record_origin(expr, origin);
+        assert!(!db.is_tracking_origins());
+        assert_eq!(db.origin(expr), None);
+
+        db.track_origins();
+        db.record_origin(expr, origin);
+        db.track_origins();
+
+        assert!(db.is_tracking_origins());
+        assert_eq!(db.origin(expr), Some(origin));
+        assert_eq!(db.origin(ExpressionKey::new(ModuleId(1), loc(1))), None);
+        assert_eq!(db.origins().map(|o| o.len()), Some(1));
+    }
+}
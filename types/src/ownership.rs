@@ -0,0 +1,342 @@
+//! Checked conversions between the different forms of ownership.
+//!
+//! Methods such as `TypeRef::as_mut()` return the type unchanged when the
+//! conversion doesn't make sense, such as when turning a `ref T` into a
+//! `mut T`. This is useful when the conversion is known to be valid, but when
+//! checking user code we need to know why a conversion isn't valid, such that
+//! we can produce a meaningful diagnostic. The methods in this module perform
+//! the same conversions, but return an `OwnershipError` describing why the
+//! conversion isn't valid.
+//!
+//! The types for which these methods succeed are the same as those for which
+//! `TypeRef::allow_as_ref()` and `TypeRef::allow_mutating()` return `true`,
+//! except that `TypeRef::Error` is always accepted, such that we don't produce
+//! errors for types that are already invalid.
+use crate::format::format_type;
+use crate::{
+    ClassId, ClassInstance, ClassKind, Database, TypeId, TypeParameterId,
+    TypeRef,
+};
+
+/// An error produced when converting a type to a different ownership.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OwnershipError {
+    /// The type is an immutable borrow, such as `ref T`.
+    ImmutableBorrow,
+
+    /// The type is a borrow of a unique value, such as `uni ref T`.
+    UniqueBorrow,
+
+    /// The type is a type parameter that isn't required to be mutable.
+    ImmutableTypeParameter(TypeParameterId),
+
+    /// The type is an instance of an atomic class, such as `String`.
+    AtomicClass(ClassId),
+
+    /// The type is an instance of a class allocated on the stack.
+    StackClass(ClassId),
+
+    /// The type is a raw pointer.
+    Pointer,
+
+    /// The type doesn't have an ownership, such as `Never` or a union.
+    NoOwnership,
+
+    /// The type is a placeholder that isn't assigned a type yet.
+    Unknown,
+}
+
+impl OwnershipError {
+    /// Returns a description of why the conversion isn't valid.
+    ///
+    /// The description is meant to be used at the end of a sentence, such as
+    /// "a 'mut T' can't be created from a value of type 'A' because ...".
+    pub fn reason(self, db: &Database) -> String {
+        match self {
+            OwnershipError::ImmutableBorrow => {
+                "it's an immutable borrow".to_string()
+            }
+            OwnershipError::UniqueBorrow => {
+                "it's a borrow of a 'uni' value".to_string()
+            }
+            OwnershipError::ImmutableTypeParameter(id) => {
+                format!("the type parameter '{}' isn't mutable", id.name(db))
+            }
+            OwnershipError::AtomicClass(id) => {
+                format!("instances of '{}' are immutable", format_type(db, id))
+            }
+            OwnershipError::StackClass(id) => format!(
+                "instances of '{}' are allocated on the stack",
+                format_type(db, id)
+            ),
+            OwnershipError::Pointer => "it's a raw pointer".to_string(),
+            OwnershipError::NoOwnership => {
+                "the type doesn't have an ownership".to_string()
+            }
+            OwnershipError::Unknown => "its type isn't known yet".to_string(),
+        }
+    }
+}
+
+/// Returns the error to produce when mutating an instance of a class that
+/// doesn't allow mutating.
+fn immutable_class_error(db: &Database, ins: ClassInstance) -> OwnershipError {
+    let id = ins.instance_of();
+
+    if let ClassKind::Atomic = id.kind(db) {
+        OwnershipError::AtomicClass(id)
+    } else {
+        OwnershipError::StackClass(id)
+    }
+}
+
+impl TypeRef {
+    /// Converts `self` into an immutable borrow, or returns an error if this
+    /// isn't possible.
+    pub fn try_as_ref(self, db: &Database) -> Result<TypeRef, OwnershipError> {
+        match self {
+            TypeRef::Owned(_)
+            | TypeRef::Mut(_)
+            | TypeRef::Ref(_)
+            | TypeRef::Uni(_)
+            | TypeRef::Any(_) => Ok(self.as_ref(db)),
+            TypeRef::Error => Ok(self),
+            TypeRef::UniRef(_) | TypeRef::UniMut(_) => {
+                Err(OwnershipError::UniqueBorrow)
+            }
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => {
+                Err(OwnershipError::Pointer)
+            }
+            TypeRef::Placeholder(id) => match id.value(db) {
+                Some(v) => v.try_as_ref(db),
+                None => Err(OwnershipError::Unknown),
+            },
+            TypeRef::Never | TypeRef::Unknown | TypeRef::Union(_) => {
+                Err(OwnershipError::NoOwnership)
+            }
+        }
+    }
+
+    /// Converts `self` into a mutable borrow, or returns an error if this
+    /// isn't possible.
+    pub fn try_as_mut(self, db: &Database) -> Result<TypeRef, OwnershipError> {
+        match self {
+            TypeRef::Owned(TypeId::ClassInstance(ins))
+            | TypeRef::Mut(TypeId::ClassInstance(ins)) => {
+                if ins.instance_of().allow_mutating(db) {
+                    Ok(self.as_mut(db))
+                } else {
+                    Err(immutable_class_error(db, ins))
+                }
+            }
+            TypeRef::Owned(_)
+            | TypeRef::Uni(_)
+            | TypeRef::Mut(_)
+            | TypeRef::UniMut(_)
+            | TypeRef::Pointer(_)
+            | TypeRef::NonNullPointer(_)
+            | TypeRef::Error => Ok(self.as_mut(db)),
+            TypeRef::Any(
+                TypeId::TypeParameter(id) | TypeId::RigidTypeParameter(id),
+            ) => {
+                if id.is_mutable(db) {
+                    Ok(self.as_mut(db))
+                } else {
+                    Err(OwnershipError::ImmutableTypeParameter(id))
+                }
+            }
+            TypeRef::Ref(TypeId::ClassInstance(ins))
+                if ins.instance_of().is_value_type(db)
+                    && !ins.instance_of().kind(db).is_async() =>
+            {
+                Ok(self.as_mut(db))
+            }
+            TypeRef::Ref(_) => Err(OwnershipError::ImmutableBorrow),
+            TypeRef::UniRef(_) => Err(OwnershipError::UniqueBorrow),
+            TypeRef::Placeholder(id) => match id.value(db) {
+                Some(v) => v.try_as_mut(db),
+                None => Err(OwnershipError::Unknown),
+            },
+            TypeRef::Any(_)
+            | TypeRef::Never
+            | TypeRef::Unknown
+            | TypeRef::Union(_) => Err(OwnershipError::NoOwnership),
+        }
+    }
+
+    /// Converts `self` into a unique value, or returns an error if this isn't
+    /// possible.
+    ///
+    /// Value types (including pointers) are returned as-is, as these are
+    /// copied when moved.
+    pub fn try_as_uni(self, db: &Database) -> Result<TypeRef, OwnershipError> {
+        if self.is_value_type(db) {
+            return Ok(self);
+        }
+
+        match self {
+            TypeRef::Owned(_)
+            | TypeRef::Any(_)
+            | TypeRef::Uni(_)
+            | TypeRef::Mut(_)
+            | TypeRef::Ref(_)
+            | TypeRef::Error => Ok(self.as_uni(db)),
+            TypeRef::UniRef(_) | TypeRef::UniMut(_) => {
+                Err(OwnershipError::UniqueBorrow)
+            }
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => {
+                Err(OwnershipError::Pointer)
+            }
+            TypeRef::Placeholder(id) => match id.value(db) {
+                Some(v) => v.try_as_uni(db),
+                None => Err(OwnershipError::Unknown),
+            },
+            TypeRef::Never | TypeRef::Unknown | TypeRef::Union(_) => {
+                Err(OwnershipError::NoOwnership)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        any, immutable, instance, mutable, new_class, new_parameter, owned,
+        parameter, placeholder, pointer, uni,
+    };
+    use crate::TypePlaceholder;
+
+    #[test]
+    fn test_type_ref_try_as_ref() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let var = TypePlaceholder::alloc(&mut db, None);
+
+        assert_eq!(
+            owned(instance(foo)).try_as_ref(&db),
+            Ok(immutable(instance(foo)))
+        );
+        assert_eq!(
+            mutable(instance(foo)).try_as_ref(&db),
+            Ok(immutable(instance(foo)))
+        );
+        assert_eq!(TypeRef::Error.try_as_ref(&db), Ok(TypeRef::Error));
+        assert_eq!(
+            TypeRef::UniRef(instance(foo)).try_as_ref(&db),
+            Err(OwnershipError::UniqueBorrow)
+        );
+        assert_eq!(
+            pointer(instance(foo)).try_as_ref(&db),
+            Err(OwnershipError::Pointer)
+        );
+        assert_eq!(
+            TypeRef::Never.try_as_ref(&db),
+            Err(OwnershipError::NoOwnership)
+        );
+        assert_eq!(
+            placeholder(var).try_as_ref(&db),
+            Err(OwnershipError::Unknown)
+        );
+
+        var.assign(&mut db, owned(instance(foo)));
+
+        assert_eq!(
+            placeholder(var).try_as_ref(&db),
+            Ok(immutable(instance(foo)))
+        );
+    }
+
+    #[test]
+    fn test_type_ref_try_as_mut() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+        let string = ClassId::string();
+        let int = ClassId::int();
+        let param = new_parameter(&mut db, "T");
+        let mut_param = new_parameter(&mut db, "M");
+
+        mut_param.set_mutable(&mut db);
+
+        assert_eq!(
+            owned(instance(foo)).try_as_mut(&db),
+            Ok(mutable(instance(foo)))
+        );
+        assert_eq!(
+            uni(instance(foo)).try_as_mut(&db),
+            Ok(TypeRef::UniMut(instance(foo)))
+        );
+        assert_eq!(
+            any(parameter(mut_param)).try_as_mut(&db),
+            Ok(mutable(parameter(mut_param)))
+        );
+        assert_eq!(TypeRef::Error.try_as_mut(&db), Ok(TypeRef::Error));
+        assert_eq!(
+            immutable(instance(foo)).try_as_mut(&db),
+            Err(OwnershipError::ImmutableBorrow)
+        );
+        assert_eq!(
+            TypeRef::UniRef(instance(foo)).try_as_mut(&db),
+            Err(OwnershipError::UniqueBorrow)
+        );
+        assert_eq!(
+            any(parameter(param)).try_as_mut(&db),
+            Err(OwnershipError::ImmutableTypeParameter(param))
+        );
+        assert_eq!(
+            owned(instance(string)).try_as_mut(&db),
+            Err(OwnershipError::AtomicClass(string))
+        );
+        assert_eq!(
+            owned(instance(int)).try_as_mut(&db),
+            Err(OwnershipError::StackClass(int))
+        );
+        assert_eq!(
+            TypeRef::Unknown.try_as_mut(&db),
+            Err(OwnershipError::NoOwnership)
+        );
+    }
+
+    #[test]
+    fn test_type_ref_try_as_uni() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+
+        assert_eq!(
+            owned(instance(foo)).try_as_uni(&db),
+            Ok(uni(instance(foo)))
+        );
+        assert_eq!(
+            immutable(instance(foo)).try_as_uni(&db),
+            Ok(uni(instance(foo)))
+        );
+        assert_eq!(
+            TypeRef::UniMut(instance(foo)).try_as_uni(&db),
+            Err(OwnershipError::UniqueBorrow)
+        );
+        assert_eq!(
+            TypeRef::Never.try_as_uni(&db),
+            Err(OwnershipError::NoOwnership)
+        );
+    }
+
+    #[test]
+    fn test_ownership_error_reason() {
+        let mut db = Database::new();
+        let param = new_parameter(&mut db, "T");
+        let foo = new_class(&mut db, "Foo");
+
+        assert_eq!(
+            OwnershipError::ImmutableTypeParameter(param).reason(&db),
+            "the type parameter 'T' isn't mutable"
+        );
+        assert_eq!(
+            OwnershipError::StackClass(foo).reason(&db),
+            "instances of 'Foo' are allocated on the stack"
+        );
+        assert_eq!(
+            OwnershipError::ImmutableBorrow.reason(&db),
+            "it's an immutable borrow"
+        );
+    }
+}
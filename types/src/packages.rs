@@ -0,0 +1,160 @@
+//! Packages and the modules they contain.
+//!
+//! A package is a group of modules distributed together, such as the standard
+//! library, the project being compiled, or one of its dependencies. Packages
+//! form a privacy boundary: symbols private to a namespace are visible to all
+//! modules of the same package, but not to modules of other packages.
+//!
+//! Modules that aren't assigned a package (e.g. when type-checking a single
+//! file) instead use the first component of their names to determine if they
+//! belong together. This isn't used when packages are known, as unrelated
+//! packages may define modules that share the same first component.
+use crate::{Database, ModuleId};
+
+/// A group of modules distributed together.
+#[derive(Clone, Debug)]
+pub struct Package {
+    pub(crate) name: String,
+
+    /// The version of the package, if it's a dependency.
+    pub(crate) version: Option<String>,
+    pub(crate) modules: Vec<ModuleId>,
+}
+
+impl Package {
+    pub fn alloc(
+        db: &mut Database,
+        name: String,
+        version: Option<String>,
+    ) -> PackageId {
+        assert!(db.packages.len() < u32::MAX as usize);
+
+        let id = PackageId(db.packages.len() as u32);
+
+        db.packages.push(Package { name, version, modules: Vec::new() });
+        id
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PackageId(pub u32);
+
+impl PackageId {
+    pub fn name(self, db: &Database) -> &String {
+        &self.get(db).name
+    }
+
+    pub fn version(self, db: &Database) -> Option<&str> {
+        self.get(db).version.as_deref()
+    }
+
+    /// Returns the modules of the package, in the order they're added in.
+    pub fn modules(self, db: &Database) -> &[ModuleId] {
+        &self.get(db).modules
+    }
+
+    /// Adds the module to the package.
+    ///
+    /// If the module already belongs to a different package, it's removed
+    /// from that package.
+    pub fn add_module(self, db: &mut Database, module: ModuleId) {
+        if let Some(old) = module.package(db) {
+            old.get_mut(db).modules.retain(|&m| m != module);
+        }
+
+        module.get_mut(db).package = Some(self);
+        self.get_mut(db).modules.push(module);
+    }
+
+    fn get(self, db: &Database) -> &Package {
+        &db.packages[self.0 as usize]
+    }
+
+    fn get_mut(self, db: &mut Database) -> &mut Package {
+        &mut db.packages[self.0 as usize]
+    }
+}
+
+impl ModuleId {
+    /// Returns the package the module belongs to, if any.
+    pub fn package(self, db: &Database) -> Option<PackageId> {
+        self.get(db).package
+    }
+
+    /// Returns `Some(true)` if both modules belong to the same package, and
+    /// `Some(false)` if they belong to different packages.
+    ///
+    /// If either module doesn't belong to a package, `None` is returned.
+    pub fn is_same_package(
+        self,
+        db: &Database,
+        other: ModuleId,
+    ) -> Option<bool> {
+        Some(self.package(db)? == other.package(db)?)
+    }
+}
+
+impl Database {
+    pub fn packages(&self) -> impl Iterator<Item = PackageId> {
+        (0..self.packages.len()).map(|i| PackageId(i as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_module;
+
+    #[test]
+    fn test_package_add_module() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let std = Package::alloc(&mut db, "std".to_string(), None);
+        let json = Package::alloc(
+            &mut db,
+            "json".to_string(),
+            Some("1.0".to_string()),
+        );
+
+        assert_eq!(foo.package(&db), None);
+
+        std.add_module(&mut db, foo);
+        json.add_module(&mut db, bar);
+
+        assert_eq!(foo.package(&db), Some(std));
+        assert_eq!(std.modules(&db), &[foo]);
+        assert_eq!(json.name(&db), "json");
+        assert_eq!(json.version(&db), Some("1.0"));
+        assert_eq!(std.version(&db), None);
+
+        json.add_module(&mut db, foo);
+
+        assert_eq!(foo.package(&db), Some(json));
+        assert_eq!(std.modules(&db), &[]);
+        assert_eq!(json.modules(&db), &[bar, foo]);
+        assert_eq!(db.packages().collect::<Vec<_>>(), vec![std, json]);
+    }
+
+    #[test]
+    fn test_module_id_is_same_package() {
+        let mut db = Database::new();
+        let foo = new_module(&mut db, "foo");
+        let bar = new_module(&mut db, "bar");
+        let baz = new_module(&mut db, "baz");
+        let pkg = Package::alloc(&mut db, "pkg".to_string(), None);
+
+        assert_eq!(foo.is_same_package(&db, bar), None);
+
+        pkg.add_module(&mut db, foo);
+        pkg.add_module(&mut db, bar);
+
+        assert_eq!(foo.is_same_package(&db, bar), Some(true));
+        assert_eq!(foo.is_same_package(&db, baz), None);
+
+        Package::alloc(&mut db, "other".to_string(), None)
+            .add_module(&mut db, baz);
+
+        assert_eq!(foo.is_same_package(&db, baz), Some(false));
+    }
+}
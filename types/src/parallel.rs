@@ -0,0 +1,155 @@
+//! Allocating types when type checking modules in parallel.
+//!
+//! Allocating new types requires a `&mut Database`, which can't be shared
+//! between threads. For most types this isn't a problem, as they're defined
+//! before method bodies are checked. Type placeholders on the other hand are
+//! allocated while checking method bodies, which is where most of the time is
+//! spent when type checking.
+//!
+//! To allow checking of modules without mutual dependencies in parallel,
+//! placeholders are allocated in two phases:
+//!
+//! 1. While holding a `&mut Database`, a range of placeholders is reserved
+//!    for every thread using `Database::reserve_placeholders()`.
+//! 2. Each thread then takes placeholders from its own reservation, which only
+//!    requires a `&Database`.
+//!
+//! A `PlaceholderReservation` can't be cloned and requires a mutable borrow
+//! for allocating placeholders, so a placeholder is handed out to only a
+//! single thread. Type checking code in this crate assigns placeholders
+//! through a `&Database`, which is safe as long as the types containing the
+//! placeholders allocated by one thread aren't shared with other threads
+//! until all threads finish checking their modules.
+use crate::{Database, TypeParameterId, TypePlaceholder, TypeRef};
+use crate::{Ownership, TypePlaceholderId};
+
+/// A range of type placeholders reserved for a single thread.
+#[derive(Debug)]
+pub struct PlaceholderReservation {
+    /// The ID of the next placeholder to hand out.
+    next: u32,
+
+    /// The ID of the first placeholder that isn't part of this reservation.
+    end: u32,
+}
+
+impl PlaceholderReservation {
+    /// Returns a new placeholder from this reservation, or `None` if all
+    /// placeholders are handed out.
+    pub fn alloc(
+        &mut self,
+        db: &Database,
+        required: Option<TypeParameterId>,
+    ) -> Option<TypeRef> {
+        if self.next == self.end {
+            return None;
+        }
+
+        let id = self.next;
+
+        self.next += 1;
+        db.type_placeholders[id as usize].required.set(required);
+        Some(TypeRef::Placeholder(TypePlaceholderId {
+            id,
+            ownership: Ownership::Any,
+        }))
+    }
+
+    /// Returns the number of placeholders that are yet to be handed out.
+    pub fn remaining(&self) -> usize {
+        (self.end - self.next) as usize
+    }
+}
+
+impl Database {
+    /// Reserves the given number of type placeholders, such that they can be
+    /// allocated without a mutable borrow.
+    pub fn reserve_placeholders(
+        &mut self,
+        amount: usize,
+    ) -> PlaceholderReservation {
+        let start = self.type_placeholders.len();
+
+        assert!(start + amount <= u32::MAX as usize);
+        self.type_placeholders.reserve(amount);
+
+        for _ in 0..amount {
+            TypePlaceholder::alloc(self, None);
+        }
+
+        PlaceholderReservation {
+            next: start as u32,
+            end: (start + amount) as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_parameter;
+    use std::thread;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_placeholder_reservation_is_send() {
+        assert_send::<PlaceholderReservation>();
+    }
+
+    #[test]
+    fn test_reserve_placeholders() {
+        let mut db = Database::new();
+        let param = new_parameter(&mut db, "T");
+        let mut res = db.reserve_placeholders(2);
+
+        assert_eq!(res.remaining(), 2);
+
+        let var1 = res.alloc(&db, Some(param)).unwrap();
+        let var2 = res.alloc(&db, None).unwrap();
+
+        assert_eq!(res.remaining(), 0);
+        assert!(res.alloc(&db, None).is_none());
+        assert_ne!(var1, var2);
+
+        match var1 {
+            TypeRef::Placeholder(id) => {
+                assert_eq!(id.required(&db), Some(param));
+                assert_eq!(id.value(&db), None);
+            }
+            _ => panic!("expected a placeholder"),
+        }
+    }
+
+    #[test]
+    fn test_reserve_placeholders_in_parallel() {
+        let mut db = Database::new();
+        let reservations: Vec<_> =
+            (0..4).map(|_| db.reserve_placeholders(8)).collect();
+        let db = &db;
+        let vars: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = reservations
+                .into_iter()
+                .map(|mut res| {
+                    s.spawn(move || {
+                        let mut vars = Vec::new();
+
+                        while let Some(TypeRef::Placeholder(id)) =
+                            res.alloc(db, None)
+                        {
+                            assert!(id.assign_internal(db, TypeRef::int()));
+                            vars.push(id);
+                        }
+
+                        vars
+                    })
+                })
+                .collect();
+
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(vars.len(), 32);
+        assert!(vars.iter().all(|v| v.value(db) == Some(TypeRef::int())));
+    }
+}
@@ -0,0 +1,673 @@
+//! Exhaustiveness checking of pattern matching.
+//!
+//! Given the patterns of a `match` expression, this module computes which
+//! values aren't covered by any pattern, and which patterns are unreachable
+//! because earlier patterns already cover the values they match.
+//!
+//! The implementation is based on the usefulness algorithm described in the
+//! paper "Warnings for pattern matching" by Luc Maranget. Patterns are
+//! represented using the `Pattern` type instead of the compiler's HIR, such
+//! that the algorithm can be used without lowering code first.
+use crate::{ClassId, ConstructorId, Database};
+use std::collections::HashMap;
+
+/// A pattern to check for exhaustiveness.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// A pattern that matches any value, such as `_` or a variable binding.
+    Wildcard,
+
+    /// An enum constructor pattern, such as `Some(value)`.
+    Constructor(ConstructorId, Vec<Pattern>),
+
+    /// A pattern matching the values of a tuple or the fields of a class.
+    Tuple(Vec<Pattern>),
+
+    /// A boolean literal.
+    Bool(bool),
+
+    /// An integer literal.
+    Int(i64),
+
+    /// A string literal.
+    String(String),
+
+    /// A pattern matching any of the given patterns, such as `1 or 2`.
+    Or(Vec<Pattern>),
+}
+
+/// An error produced when creating a constructor pattern using named
+/// arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NamedArgumentError {
+    /// The constructor doesn't define an argument with the given name.
+    Undefined(String),
+
+    /// The argument with the given name is specified more than once.
+    Duplicate(String),
+}
+
+impl Pattern {
+    /// Returns a constructor pattern using named arguments, such as
+    /// `Circle(radius: _)`.
+    ///
+    /// The arguments may be specified in any order, and arguments that aren't
+    /// specified are matched using a wildcard.
+    pub fn named_constructor(
+        db: &Database,
+        constructor: ConstructorId,
+        arguments: Vec<(String, Pattern)>,
+    ) -> Result<Pattern, NamedArgumentError> {
+        let mut args: Vec<Option<Pattern>> =
+            vec![None; constructor.number_of_arguments(db)];
+
+        for (name, pat) in arguments {
+            let Some(idx) = constructor.argument_index(db, &name) else {
+                return Err(NamedArgumentError::Undefined(name));
+            };
+
+            if args[idx].is_some() {
+                return Err(NamedArgumentError::Duplicate(name));
+            }
+
+            args[idx] = Some(pat);
+        }
+
+        Ok(Pattern::Constructor(
+            constructor,
+            args.into_iter().map(|p| p.unwrap_or(Pattern::Wildcard)).collect(),
+        ))
+    }
+
+    fn wildcards(amount: usize) -> Vec<Pattern> {
+        vec![Pattern::Wildcard; amount]
+    }
+
+    fn head(&self) -> Option<Head> {
+        match self {
+            Pattern::Constructor(id, _) => Some(Head::Constructor(*id)),
+            Pattern::Tuple(vals) => Some(Head::Tuple(vals.len())),
+            Pattern::Bool(val) => Some(Head::Bool(*val)),
+            Pattern::Int(val) => Some(Head::Int(*val)),
+            Pattern::String(val) => Some(Head::String(val.clone())),
+            Pattern::Wildcard | Pattern::Or(_) => None,
+        }
+    }
+
+    fn arguments(self) -> Vec<Pattern> {
+        match self {
+            Pattern::Constructor(_, args) | Pattern::Tuple(args) => args,
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The outermost part of a pattern that isn't a wildcard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Head {
+    Constructor(ConstructorId),
+    Tuple(usize),
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl Head {
+    fn pattern(&self, arguments: Vec<Pattern>) -> Pattern {
+        match self {
+            Head::Constructor(id) => Pattern::Constructor(*id, arguments),
+            Head::Tuple(_) => Pattern::Tuple(arguments),
+            Head::Bool(val) => Pattern::Bool(*val),
+            Head::Int(val) => Pattern::Int(*val),
+            Head::String(val) => Pattern::String(val.clone()),
+        }
+    }
+}
+
+/// The result of checking a list of patterns.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Report {
+    /// Patterns describing the values not covered by any of the patterns.
+    ///
+    /// If this list is empty, the patterns are exhaustive.
+    pub missing: Vec<Pattern>,
+
+    /// The indexes of the patterns that can never match.
+    pub unreachable: Vec<usize>,
+}
+
+impl Report {
+    pub fn is_exhaustive(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// A matrix of patterns, with one row per pattern and one column per value to
+/// match against.
+type Rows = Vec<Vec<Pattern>>;
+
+/// Expands rows of which the first column is an OR pattern into a row per
+/// alternative.
+///
+/// Alternatives may themselves be OR patterns, so rows are expanded until the
+/// first column no longer contains an OR pattern, while retaining the order of
+/// the rows.
+fn expand(rows: Rows) -> Rows {
+    let mut result = Vec::with_capacity(rows.len());
+    let mut pending: Rows = rows.into_iter().rev().collect();
+
+    while let Some(mut row) = pending.pop() {
+        if let Pattern::Or(alts) = &row[0] {
+            let alts = alts.clone();
+
+            for alt in alts.into_iter().rev() {
+                row[0] = alt;
+                pending.push(row.clone());
+            }
+        } else {
+            result.push(row);
+        }
+    }
+
+    result
+}
+
+struct Checker<'a> {
+    db: &'a Database,
+
+    /// The enum classes that define the constructors we encounter.
+    classes: HashMap<ConstructorId, ClassId>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(db: &'a Database) -> Checker<'a> {
+        let mut classes = HashMap::new();
+
+        for idx in 0..db.number_of_classes() {
+            let class = ClassId(idx as u32);
+
+            if class.kind(db).is_enum() {
+                for id in class.constructors(db) {
+                    classes.insert(id, class);
+                }
+            }
+        }
+
+        Checker { db, classes }
+    }
+
+    fn arity(&self, head: &Head) -> usize {
+        match head {
+            Head::Constructor(id) => id.number_of_arguments(self.db),
+            Head::Tuple(len) => *len,
+            _ => 0,
+        }
+    }
+
+    /// Returns the complete list of heads of the type the given heads belong
+    /// to, or `None` if the type has an infinite number of values (e.g. Int).
+    fn signature(&self, heads: &[Head]) -> Option<Vec<Head>> {
+        match heads.first()? {
+            Head::Constructor(id) => {
+                let class = self.classes.get(id)?;
+
+                Some(
+                    class
+                        .constructors(self.db)
+                        .into_iter()
+                        .map(Head::Constructor)
+                        .collect(),
+                )
+            }
+            Head::Tuple(len) => Some(vec![Head::Tuple(*len)]),
+            Head::Bool(_) => Some(vec![Head::Bool(true), Head::Bool(false)]),
+            Head::Int(_) | Head::String(_) => None,
+        }
+    }
+
+    /// Returns the heads of the first column, in the order they're defined in.
+    fn heads(&self, rows: &Rows) -> Vec<Head> {
+        let mut heads = Vec::new();
+
+        for head in rows.iter().filter_map(|r| r[0].head()) {
+            if !heads.contains(&head) {
+                heads.push(head);
+            }
+        }
+
+        heads
+    }
+
+    /// Returns the heads that are missing from the first column, or `None` if
+    /// the type has an infinite number of values.
+    fn missing_heads(&self, heads: &[Head]) -> Option<Vec<Head>> {
+        self.signature(heads)
+            .map(|all| all.into_iter().filter(|h| !heads.contains(h)).collect())
+    }
+
+    /// Returns the rows that match the given head, with the first column
+    /// replaced by the arguments of the head.
+    fn specialize(&self, rows: &Rows, head: &Head) -> Rows {
+        let arity = self.arity(head);
+
+        rows.iter()
+            .filter_map(|row| {
+                let mut new = match row[0].head() {
+                    Some(h) if &h == head => row[0].clone().arguments(),
+                    Some(_) => return None,
+                    None => Pattern::wildcards(arity),
+                };
+
+                new.extend(row[1..].iter().cloned());
+                Some(new)
+            })
+            .collect()
+    }
+
+    /// Returns the rows of which the first column is a wildcard, without that
+    /// column.
+    fn default(&self, rows: &Rows) -> Rows {
+        rows.iter()
+            .filter(|row| row[0] == Pattern::Wildcard)
+            .map(|row| row[1..].to_vec())
+            .collect()
+    }
+
+    /// Returns `true` if the pattern vector matches any values not matched by
+    /// the rows.
+    fn is_useful(&self, rows: Rows, vector: Vec<Pattern>) -> bool {
+        if vector.is_empty() {
+            return rows.is_empty();
+        }
+
+        let rows = expand(rows);
+
+        match vector[0].clone() {
+            Pattern::Or(alts) => alts.into_iter().any(|alt| {
+                let mut vec = vector.clone();
+
+                vec[0] = alt;
+                self.is_useful(rows.clone(), vec)
+            }),
+            Pattern::Wildcard => {
+                let heads = self.heads(&rows);
+
+                match self.missing_heads(&heads) {
+                    Some(missing) if missing.is_empty() => {
+                        heads.into_iter().any(|head| {
+                            let mut vec = Pattern::wildcards(self.arity(&head));
+
+                            vec.extend(vector[1..].iter().cloned());
+                            self.is_useful(self.specialize(&rows, &head), vec)
+                        })
+                    }
+                    _ => self
+                        .is_useful(self.default(&rows), vector[1..].to_vec()),
+                }
+            }
+            pat => {
+                let head = pat.head().unwrap();
+                let mut vec = pat.arguments();
+
+                vec.extend(vector[1..].iter().cloned());
+                self.is_useful(self.specialize(&rows, &head), vec)
+            }
+        }
+    }
+
+    /// Returns the pattern vectors of the given width that don't match any of
+    /// the rows.
+    fn witnesses(&self, rows: Rows, width: usize) -> Rows {
+        if width == 0 {
+            return if rows.is_empty() { vec![Vec::new()] } else { Vec::new() };
+        }
+
+        let rows = expand(rows);
+        let heads = self.heads(&rows);
+        let mut result = Vec::new();
+
+        match self.missing_heads(&heads) {
+            Some(missing) if missing.is_empty() => {
+                for head in heads {
+                    let arity = self.arity(&head);
+                    let spec = self.specialize(&rows, &head);
+
+                    for mut wit in self.witnesses(spec, arity + width - 1) {
+                        let rest = wit.split_off(arity);
+                        let mut new = vec![head.pattern(wit)];
+
+                        new.extend(rest);
+                        result.push(new);
+                    }
+                }
+            }
+            missing => {
+                for wit in self.witnesses(self.default(&rows), width - 1) {
+                    // If none of the heads are used we report a wildcard, as
+                    // listing every possible value isn't useful.
+                    let pats = match &missing {
+                        Some(missing) if !heads.is_empty() => missing
+                            .iter()
+                            .map(|h| {
+                                h.pattern(Pattern::wildcards(self.arity(h)))
+                            })
+                            .collect(),
+                        _ => vec![Pattern::Wildcard],
+                    };
+
+                    for pat in pats {
+                        let mut new = vec![pat];
+
+                        new.extend(wit.iter().cloned());
+                        result.push(new);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Checks the given patterns, in the order in which they are defined.
+pub fn check(db: &Database, patterns: &[Pattern]) -> Report {
+    let checker = Checker::new(db);
+    let mut rows: Rows = Vec::with_capacity(patterns.len());
+    let mut unreachable = Vec::new();
+
+    for (index, pat) in patterns.iter().enumerate() {
+        if !checker.is_useful(rows.clone(), vec![pat.clone()]) {
+            unreachable.push(index);
+        }
+
+        rows.push(vec![pat.clone()]);
+    }
+
+    let missing = checker
+        .witnesses(rows, 1)
+        .into_iter()
+        .map(|mut row| row.pop().unwrap())
+        .collect();
+
+    Report { missing, unreachable }
+}
+
+/// Checks if matching against the given constructors of an enum class covers
+/// all the constructors of that class.
+///
+/// The arguments of each constructor are treated as wildcards.
+pub fn check_constructors(
+    db: &Database,
+    constructors: &[ConstructorId],
+) -> Report {
+    let patterns: Vec<_> = constructors
+        .iter()
+        .map(|&id| {
+            Pattern::Constructor(
+                id,
+                Pattern::wildcards(id.number_of_arguments(db)),
+            )
+        })
+        .collect();
+
+    check(db, &patterns)
+}
+
+/// Returns a human-readable representation of a pattern.
+pub fn format_pattern(db: &Database, pattern: &Pattern) -> String {
+    let list = |pats: &[Pattern], sep: &str| {
+        pats.iter().map(|p| format_pattern(db, p)).collect::<Vec<_>>().join(sep)
+    };
+
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Constructor(id, args) if args.is_empty() => {
+            id.name(db).clone()
+        }
+        Pattern::Constructor(id, args) if id.has_named_arguments(db) => {
+            let args: Vec<_> = id
+                .argument_names(db)
+                .iter()
+                .zip(args)
+                .map(|(name, pat)| {
+                    format!("{}: {}", name, format_pattern(db, pat))
+                })
+                .collect();
+
+            format!("{}({})", id.name(db), args.join(", "))
+        }
+        Pattern::Constructor(id, args) => {
+            format!("{}({})", id.name(db), list(args, ", "))
+        }
+        Pattern::Tuple(vals) => format!("({})", list(vals, ", ")),
+        Pattern::Bool(val) => val.to_string(),
+        Pattern::Int(val) => val.to_string(),
+        Pattern::String(val) => format!("{:?}", val),
+        Pattern::Or(alts) => list(alts, " or "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_enum_class, owned, parameter};
+    use crate::{Location, TypeRef};
+
+    fn option(db: &mut Database) -> (ConstructorId, ConstructorId) {
+        let class = new_enum_class(db, "Option");
+        let param = class.new_type_parameter(db, "T".to_string());
+        let some = class.new_constructor(
+            db,
+            "Some".to_string(),
+            vec![owned(parameter(param))],
+            Location::default(),
+        );
+        let none = class.new_constructor(
+            db,
+            "None".to_string(),
+            Vec::new(),
+            Location::default(),
+        );
+
+        (some, none)
+    }
+
+    fn missing(db: &Database, patterns: &[Pattern]) -> Vec<String> {
+        check(db, patterns)
+            .missing
+            .iter()
+            .map(|p| format_pattern(db, p))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_constructors() {
+        let mut db = Database::new();
+        let (some, none) = option(&mut db);
+
+        assert!(check_constructors(&db, &[some, none]).is_exhaustive());
+        assert_eq!(
+            check_constructors(&db, &[some]).missing,
+            vec![Pattern::Constructor(none, Vec::new())]
+        );
+        assert_eq!(
+            check_constructors(&db, &[some, none, some]).unreachable,
+            [2]
+        );
+    }
+
+    #[test]
+    fn test_check_nested_constructors() {
+        let mut db = Database::new();
+        let (some, none) = option(&mut db);
+        let some_true = Pattern::Constructor(some, vec![Pattern::Bool(true)]);
+        let some_false = Pattern::Constructor(some, vec![Pattern::Bool(false)]);
+        let none_pat = Pattern::Constructor(none, Vec::new());
+
+        assert_eq!(
+            missing(&db, &[some_true.clone(), none_pat.clone()]),
+            vec!["Some(false)"]
+        );
+        assert!(check(&db, &[some_true.clone(), some_false, none_pat.clone()])
+            .is_exhaustive());
+
+        let report = check(
+            &db,
+            &[
+                Pattern::Constructor(some, vec![Pattern::Wildcard]),
+                some_true,
+                none_pat,
+            ],
+        );
+
+        assert!(report.is_exhaustive());
+        assert_eq!(report.unreachable, vec![1]);
+    }
+
+    #[test]
+    fn test_check_tuples() {
+        let db = Database::new();
+        let tuple =
+            |a: bool, b: Pattern| Pattern::Tuple(vec![Pattern::Bool(a), b]);
+
+        assert_eq!(
+            missing(
+                &db,
+                &[
+                    tuple(true, Pattern::Wildcard),
+                    tuple(false, Pattern::Bool(true))
+                ]
+            ),
+            vec!["(false, false)"]
+        );
+        assert!(check(
+            &db,
+            &[tuple(true, Pattern::Wildcard), tuple(false, Pattern::Wildcard)]
+        )
+        .is_exhaustive());
+    }
+
+    #[test]
+    fn test_check_literals() {
+        let db = Database::new();
+
+        assert_eq!(
+            missing(&db, &[Pattern::Int(1), Pattern::Int(2)]),
+            vec!["_"]
+        );
+        assert_eq!(
+            check(&db, &[Pattern::Int(1), Pattern::Wildcard, Pattern::Int(2)])
+                .unreachable,
+            vec![2]
+        );
+        assert!(check(
+            &db,
+            &[Pattern::String("a".to_string()), Pattern::Wildcard]
+        )
+        .is_exhaustive());
+    }
+
+    #[test]
+    fn test_check_or_patterns() {
+        let mut db = Database::new();
+        let (some, none) = option(&mut db);
+        let pat = Pattern::Or(vec![
+            Pattern::Constructor(some, vec![Pattern::Wildcard]),
+            Pattern::Constructor(none, Vec::new()),
+        ]);
+
+        assert_eq!(
+            check(&db, &[pat.clone(), Pattern::Wildcard]).unreachable,
+            [1]
+        );
+        assert!(check(&db, &[pat]).is_exhaustive());
+        assert_eq!(
+            check(&db, &[Pattern::Or(vec![Pattern::Bool(true)])]).missing,
+            vec![Pattern::Bool(false)]
+        );
+    }
+
+    #[test]
+    fn test_format_pattern() {
+        let mut db = Database::new();
+        let (some, _) = option(&mut db);
+        let pat = Pattern::Or(vec![
+            Pattern::Constructor(some, vec![Pattern::Int(1)]),
+            Pattern::Tuple(vec![
+                Pattern::String("a".to_string()),
+                Pattern::Wildcard,
+            ]),
+        ]);
+
+        assert_eq!(format_pattern(&db, &pat), "Some(1) or (\"a\", _)");
+        assert_eq!(
+            format_pattern(&db, &Pattern::Tuple(vec![Pattern::Bool(true)])),
+            "(true)"
+        );
+    }
+
+    #[test]
+    fn test_named_constructor() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Shape");
+        let circle = class.new_constructor(
+            &mut db,
+            "Circle".to_string(),
+            vec![TypeRef::float()],
+            Location::default(),
+        );
+        let rect = class.new_constructor(
+            &mut db,
+            "Rect".to_string(),
+            vec![TypeRef::boolean(), TypeRef::boolean()],
+            Location::default(),
+        );
+
+        circle.set_argument_names(&mut db, vec!["radius".to_string()]);
+        rect.set_argument_names(
+            &mut db,
+            vec!["wide".to_string(), "tall".to_string()],
+        );
+
+        let named = |name: &str, pat: Pattern| (name.to_string(), pat);
+        let pat = Pattern::named_constructor(
+            &db,
+            rect,
+            vec![named("tall", Pattern::Bool(true))],
+        );
+
+        assert_eq!(
+            pat,
+            Ok(Pattern::Constructor(
+                rect,
+                vec![Pattern::Wildcard, Pattern::Bool(true)]
+            ))
+        );
+        assert_eq!(
+            Pattern::named_constructor(
+                &db,
+                circle,
+                vec![named("size", Pattern::Wildcard)]
+            ),
+            Err(NamedArgumentError::Undefined("size".to_string()))
+        );
+        assert_eq!(
+            Pattern::named_constructor(
+                &db,
+                circle,
+                vec![
+                    named("radius", Pattern::Wildcard),
+                    named("radius", Pattern::Wildcard)
+                ]
+            ),
+            Err(NamedArgumentError::Duplicate("radius".to_string()))
+        );
+
+        let circle_pat = Pattern::Constructor(circle, vec![Pattern::Wildcard]);
+
+        assert_eq!(
+            missing(&db, &[pat.unwrap(), circle_pat]),
+            vec!["Rect(wide: _, tall: false)"]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Read-only queries over a type database.
+//!
+//! The methods of the various ID types (e.g. `ClassId`) are geared towards the
+//! compiler and expose the data one field at a time. Tools such as language
+//! servers instead need to answer questions such as "what does this name refer
+//! to?" or "which classes implement this trait?". This module provides such
+//! queries, built on top of the ID types.
+//!
+//! None of the functions in this module modify the database, and the order of
+//! the values they return is stable across runs.
+use crate::module_name::SEPARATOR;
+use crate::{
+    ClassId, Database, MethodId, ModuleId, Symbol, TraitId, TraitImplementation,
+};
+use location::Location;
+use std::collections::HashSet;
+
+/// The definition of a symbol.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Definition {
+    /// The symbol that's defined.
+    pub symbol: Symbol,
+
+    /// The module the symbol is defined in.
+    pub module: ModuleId,
+
+    /// The location of the symbol in its module.
+    pub location: Location,
+}
+
+/// Returns the symbol referred to by a fully qualified path.
+///
+/// The path is either the name of a module (e.g. `std.string`), or the name of
+/// a module followed by the name of a symbol defined in or imported into that
+/// module (e.g. `std.string.String`).
+pub fn symbol_at(db: &Database, path: &str) -> Option<Symbol> {
+    if let Some(id) = db.optional_module(path) {
+        return Some(Symbol::Module(id));
+    }
+
+    let (module, name) = path.rsplit_once(SEPARATOR)?;
+
+    db.optional_module(module)?.symbol(db, name)
+}
+
+/// Returns the definition of a symbol.
+///
+/// Type parameters aren't defined in a module and don't have a location, so
+/// `None` is returned for such symbols.
+pub fn definition(db: &Database, symbol: Symbol) -> Option<Definition> {
+    let (module, location) = match symbol {
+        Symbol::Class(id) => (id.module(db), id.location(db)),
+        Symbol::Trait(id) => (id.module(db), id.location(db)),
+        Symbol::Constant(id) => (id.module(db), id.location(db)),
+        Symbol::Method(id) => (id.module(db), id.location(db)),
+        Symbol::TypeAlias(id) => (id.module(db), id.location(db)),
+        Symbol::Module(id) => (id, Location::default()),
+        Symbol::TypeParameter(_) => return None,
+    };
+
+    Some(Definition { symbol, module, location })
+}
+
+/// Resolves a name as seen from the given module, returning its definition.
+///
+/// Unlike `ModuleId::use_symbol()`, resolving a name doesn't mark the symbol as
+/// used.
+pub fn resolve(
+    db: &Database,
+    module: ModuleId,
+    name: &str,
+) -> Option<Definition> {
+    module.symbol(db, name).and_then(|sym| definition(db, sym))
+}
+
+/// Returns all methods defined for a class, sorted by their names.
+///
+/// This includes the methods implemented through traits, as well as default
+/// methods inherited from these traits.
+pub fn methods(db: &Database, class: ClassId) -> Vec<MethodId> {
+    let mut methods = class.methods(db);
+
+    methods.sort_by(|a, b| a.name(db).cmp(b.name(db)));
+    methods
+}
+
+/// Returns the traits implemented by a class, sorted by their names.
+pub fn implemented_traits(db: &Database, class: ClassId) -> Vec<TraitId> {
+    let mut traits: Vec<_> = class
+        .implemented_traits(db)
+        .map(|imp| imp.instance.instance_of())
+        .collect();
+
+    traits.sort_by(|a, b| a.name(db).cmp(b.name(db)).then(a.0.cmp(&b.0)));
+    traits
+}
+
+/// Returns the implementations of a trait, along with the classes they belong
+/// to.
+///
+/// The implementations are returned in the order in which they are defined.
+/// A class may be recorded more than once (e.g. when an implementation is
+/// redefined), but its implementation is only included once.
+pub fn implementations(
+    db: &Database,
+    trait_id: TraitId,
+) -> Vec<(ClassId, &TraitImplementation)> {
+    let mut seen = HashSet::new();
+
+    trait_id
+        .implemented_by(db)
+        .iter()
+        .filter(|&&class| seen.insert(class))
+        .filter_map(|&class| {
+            class.trait_implementation(db, trait_id).map(|imp| (class, imp))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        implement, new_class, new_module, new_trait, trait_instance,
+    };
+    use crate::{Method, MethodKind, Visibility};
+
+    #[test]
+    fn test_symbol_at() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "std.foo");
+        let class = new_class(&mut db, "Foo");
+
+        module.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(class));
+
+        assert_eq!(symbol_at(&db, "std.foo"), Some(Symbol::Module(module)));
+        assert_eq!(symbol_at(&db, "std.foo.Foo"), Some(Symbol::Class(class)));
+        assert_eq!(symbol_at(&db, "std.foo.Bar"), None);
+        assert_eq!(symbol_at(&db, "std.bar.Foo"), None);
+        assert_eq!(symbol_at(&db, "Foo"), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Foo");
+        let loc = Location::new(&(2..=3), &(4..=5));
+
+        class.set_location(&mut db, loc);
+        class.set_module(&mut db, module);
+        module.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(class));
+
+        assert_eq!(
+            resolve(&db, module, "Foo"),
+            Some(Definition {
+                symbol: Symbol::Class(class),
+                module,
+                location: loc
+            })
+        );
+        assert_eq!(resolve(&db, module, "Bar"), None);
+        assert!(!module.symbol_is_used(&db, "Foo"));
+    }
+
+    #[test]
+    fn test_methods() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Foo");
+
+        for name in ["b", "a", "c"] {
+            let method = Method::alloc(
+                &mut db,
+                module,
+                Location::default(),
+                name.to_string(),
+                Visibility::Public,
+                MethodKind::Instance,
+            );
+
+            class.add_method(&mut db, name.to_string(), method);
+        }
+
+        let names: Vec<_> = methods(&db, class)
+            .into_iter()
+            .map(|m| m.name(&db).clone())
+            .collect();
+
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_trait_implementations() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let equal = new_trait(&mut db, "Equal");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+
+        implement(&mut db, trait_instance(to_string), foo);
+        implement(&mut db, trait_instance(equal), foo);
+        implement(&mut db, trait_instance(to_string), bar);
+
+        assert_eq!(implemented_traits(&db, foo), vec![equal, to_string]);
+        assert_eq!(implemented_traits(&db, bar), vec![to_string]);
+
+        let classes: Vec<_> = implementations(&db, to_string)
+            .into_iter()
+            .map(|(class, _)| class)
+            .collect();
+
+        assert_eq!(classes, vec![foo, bar]);
+        assert!(implementations(&db, equal)
+            .iter()
+            .all(|(_, imp)| imp.instance.instance_of() == equal));
+    }
+
+    #[test]
+    fn test_implementations_with_duplicates() {
+        let mut db = Database::new();
+        let to_string = new_trait(&mut db, "ToString");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+
+        implement(&mut db, trait_instance(to_string), foo);
+        implement(&mut db, trait_instance(to_string), bar);
+        implement(&mut db, trait_instance(to_string), foo);
+
+        let classes: Vec<_> = implementations(&db, to_string)
+            .into_iter()
+            .map(|(class, _)| class)
+            .collect();
+
+        assert_eq!(classes, vec![foo, bar]);
+    }
+}
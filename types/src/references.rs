@@ -0,0 +1,123 @@
+//! Tracking the locations at which symbols are referred to.
+//!
+//! Tools such as language servers need to know where a symbol is used, such as
+//! when finding all references to a method or when renaming a class. The
+//! compiler knows this while type-checking, but this information is discarded
+//! afterwards.
+//!
+//! Similar to type origins, recording references requires memory for every
+//! reference while only tools need this data. As such, references are only
+//! recorded when enabled using `Database::track_references()`.
+use crate::{Database, ModuleId, Symbol};
+use location::Location;
+use std::collections::HashMap;
+
+/// A reference to a symbol in a module.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub module: ModuleId,
+    pub location: Location,
+}
+
+impl Reference {
+    pub fn new(module: ModuleId, location: Location) -> Reference {
+        Reference { module, location }
+    }
+}
+
+/// A table mapping symbols to the locations they are referred to at.
+#[derive(Default)]
+pub struct ReferenceIndex {
+    mapping: HashMap<Symbol, Vec<Reference>>,
+}
+
+impl ReferenceIndex {
+    pub fn len(&self) -> usize {
+        self.mapping.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mapping.is_empty()
+    }
+
+    /// Removes the references recorded in the given module.
+    pub(crate) fn remove_module(&mut self, module: ModuleId) {
+        self.mapping.retain(|_, refs| {
+            refs.retain(|r| r.module != module);
+            !refs.is_empty()
+        });
+    }
+}
+
+impl Database {
+    /// Enables the recording of references to symbols.
+    ///
+    /// Calling this method multiple times retains any references already
+    /// recorded.
+    pub fn track_references(&mut self) {
+        if self.references.is_none() {
+            self.references = Some(ReferenceIndex::default());
+        }
+    }
+
+    pub fn is_tracking_references(&self) -> bool {
+        self.references.is_some()
+    }
+
+    /// Records a reference to a symbol.
+    ///
+    /// If the tracking of references isn't enabled, this method does nothing.
+    /// Recording the same reference multiple times only records it once.
+    pub fn record_reference(&mut self, symbol: Symbol, reference: Reference) {
+        if let Some(index) = self.references.as_mut() {
+            let refs = index.mapping.entry(symbol).or_default();
+
+            if !refs.contains(&reference) {
+                refs.push(reference);
+            }
+        }
+    }
+
+    /// Returns the references to a symbol, in the order they are recorded in.
+    pub fn references(&self, symbol: Symbol) -> &[Reference] {
+        self.references
+            .as_ref()
+            .and_then(|i| i.mapping.get(&symbol))
+            .map_or(&[], |v| v.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_module};
+
+    fn loc(line: u32) -> Location {
+        Location::new(&(line..=line), &(1..=1))
+    }
+
+    #[test]
+    fn test_database_record_reference() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = Symbol::Class(new_class(&mut db, "Foo"));
+        let reference = Reference::new(module, loc(1));
+
+        db.record_reference(class, reference);
+        assert!(!db.is_tracking_references());
+        assert!(db.references(class).is_empty());
+
+        db.track_references();
+        db.record_reference(class, reference);
+        db.record_reference(class, reference);
+        db.record_reference(class, Reference::new(module, loc(2)));
+        db.track_references();
+
+        assert!(db.is_tracking_references());
+        assert_eq!(
+            db.references(class),
+            &[reference, Reference::new(module, loc(2))]
+        );
+        assert!(db.references(Symbol::Module(module)).is_empty());
+    }
+}
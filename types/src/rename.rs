@@ -0,0 +1,373 @@
+//! Renaming symbols.
+//!
+//! Renaming a symbol requires changing its definition and every reference to
+//! it, and is only safe if the new name doesn't conflict with any other name
+//! in a scope the symbol is visible in. For example, renaming class `A` to `B`
+//! isn't safe if a module importing `A` also defines or imports `B`.
+//!
+//! The references are obtained from the reference index, so references are
+//! only included if they are tracked using `Database::track_references()`.
+use crate::query::definition;
+use crate::{Database, MethodId, ModuleId, Symbol, TypeId};
+use location::Location;
+use std::fmt;
+
+/// A change to apply to a source file in order to rename a symbol.
+///
+/// The location is that of the definition or reference containing the name,
+/// and the name to replace is the first occurrence of `old_name` in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit {
+    pub module: ModuleId,
+    pub location: Location,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// An error produced when a symbol can't be renamed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenameError {
+    /// The symbol can't be renamed, such as when it's a module.
+    Unsupported,
+
+    /// The new name isn't a valid name for the symbol.
+    InvalidName(String),
+
+    /// The new name is already used by a symbol in a module the symbol is
+    /// visible in.
+    SymbolConflict(ModuleId),
+
+    /// The new name is already used by a method of the type that defines the
+    /// method to rename, or of a type implementing it.
+    MethodConflict(MethodId),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::Unsupported => {
+                f.write_str("this symbol can't be renamed")
+            }
+            RenameError::InvalidName(name) => {
+                write!(f, "'{}' isn't a valid name for this symbol", name)
+            }
+            RenameError::SymbolConflict(_) => {
+                f.write_str("the new name conflicts with an existing symbol")
+            }
+            RenameError::MethodConflict(_) => {
+                f.write_str("the new name conflicts with an existing method")
+            }
+        }
+    }
+}
+
+fn symbol_name(db: &Database, symbol: Symbol) -> Option<&String> {
+    match symbol {
+        Symbol::Class(id) => Some(id.name(db)),
+        Symbol::Trait(id) => Some(id.name(db)),
+        Symbol::Constant(id) => Some(id.name(db)),
+        Symbol::Method(id) => Some(id.name(db)),
+        Symbol::TypeAlias(id) => Some(id.name(db)),
+        Symbol::Module(_) | Symbol::TypeParameter(_) => None,
+    }
+}
+
+fn valid_name(symbol: Symbol, name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else { return false };
+
+    if !(first.is_alphabetic() || first == '_')
+        || !chars.all(|c| c.is_alphanumeric() || c == '_')
+    {
+        return false;
+    }
+
+    // Types and constants must start with an uppercase letter, while methods
+    // must not.
+    match symbol {
+        Symbol::Method(_) => !first.is_uppercase(),
+        _ => first.is_uppercase(),
+    }
+}
+
+impl Database {
+    /// Returns the edits needed to rename a symbol to the given name.
+    ///
+    /// Renaming a method defined in a trait also renames the methods of the
+    /// classes implementing the trait, as otherwise these classes would no
+    /// longer implement the trait correctly.
+    pub fn rename_symbol(
+        &self,
+        symbol: Symbol,
+        new_name: &str,
+    ) -> Result<Vec<Edit>, RenameError> {
+        let old_name =
+            symbol_name(self, symbol).ok_or(RenameError::Unsupported)?.clone();
+
+        // Compiler generated symbols (e.g. closure methods) can't be renamed,
+        // as they aren't defined in the source code.
+        if old_name.starts_with('$') {
+            return Err(RenameError::Unsupported);
+        }
+
+        if !valid_name(symbol, new_name) {
+            return Err(RenameError::InvalidName(new_name.to_string()));
+        }
+
+        if old_name == new_name {
+            return Ok(Vec::new());
+        }
+
+        let symbols = self.symbols_to_rename(symbol, &old_name, new_name)?;
+
+        for &sym in &symbols {
+            for idx in 0..self.modules.len() {
+                let module = ModuleId(idx as u32);
+
+                if module.symbol(self, &old_name) == Some(sym)
+                    && module.symbol(self, new_name).is_some()
+                {
+                    return Err(RenameError::SymbolConflict(module));
+                }
+            }
+        }
+
+        let mut edits = Vec::new();
+
+        for sym in symbols {
+            let def = definition(self, sym)
+                .into_iter()
+                .map(|d| (d.module, d.location));
+            let refs =
+                self.references(sym).iter().map(|r| (r.module, r.location));
+
+            for (module, location) in def.chain(refs) {
+                let edit = Edit {
+                    module,
+                    location,
+                    old_name: old_name.clone(),
+                    new_name: new_name.to_string(),
+                };
+
+                if !edits.contains(&edit) {
+                    edits.push(edit);
+                }
+            }
+        }
+
+        Ok(edits)
+    }
+
+    /// Returns the symbols to rename, checking for conflicts between methods
+    /// along the way.
+    fn symbols_to_rename(
+        &self,
+        symbol: Symbol,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<Vec<Symbol>, RenameError> {
+        let Symbol::Method(method) = symbol else { return Ok(vec![symbol]) };
+        let mut symbols = vec![symbol];
+        let owner = method.receiver(self).type_id(self).ok();
+        let existing = match owner {
+            Some(TypeId::Class(id)) => id.method(self, new_name),
+            Some(TypeId::ClassInstance(ins)) => ins.method(self, new_name),
+            Some(TypeId::Module(id)) => id.method(self, new_name),
+            Some(TypeId::Trait(id)) => id.method(self, new_name),
+            Some(TypeId::TraitInstance(ins)) => ins.method(self, new_name),
+            _ => None,
+        };
+
+        if let Some(id) = existing {
+            return Err(RenameError::MethodConflict(id));
+        }
+
+        let trait_id = match owner {
+            Some(TypeId::Trait(id)) => id,
+            Some(TypeId::TraitInstance(ins)) => ins.instance_of(),
+            _ => return Ok(symbols),
+        };
+
+        for &class in trait_id.implemented_by(self) {
+            let Some(imp) = class.method(self, old_name) else { continue };
+
+            if let Some(id) = class.method(self, new_name) {
+                return Err(RenameError::MethodConflict(id));
+            }
+
+            let sym = Symbol::Method(imp);
+
+            if !symbols.contains(&sym) {
+                symbols.push(sym);
+            }
+        }
+
+        Ok(symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::references::Reference;
+    use crate::test::{
+        implement, instance, new_class, new_method, new_module, new_trait,
+        owned, trait_instance,
+    };
+    use crate::MethodKind;
+
+    fn loc(line: u32) -> Location {
+        Location::new(&(line..=line), &(1..=1))
+    }
+
+    fn method_at(
+        db: &mut Database,
+        module: ModuleId,
+        name: &str,
+        receiver: TypeId,
+        line: u32,
+    ) -> MethodId {
+        let id = new_method(db, module, name, MethodKind::Instance);
+
+        id.get_mut(db).location = loc(line);
+        id.set_receiver(db, owned(receiver));
+        id
+    }
+
+    fn edit(module: ModuleId, line: u32, old: &str, new: &str) -> Edit {
+        Edit {
+            module,
+            location: loc(line),
+            old_name: old.to_string(),
+            new_name: new.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rename_symbol_invalid() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = Symbol::Class(new_class(&mut db, "Foo"));
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Module(module), "bar"),
+            Err(RenameError::Unsupported)
+        );
+        assert_eq!(
+            db.rename_symbol(class, "bar"),
+            Err(RenameError::InvalidName("bar".to_string()))
+        );
+        assert_eq!(
+            db.rename_symbol(class, "B-ar"),
+            Err(RenameError::InvalidName("B-ar".to_string()))
+        );
+        assert_eq!(db.rename_symbol(class, "Foo"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_rename_symbol_with_symbol_conflict() {
+        let mut db = Database::new();
+        let foo_mod = new_module(&mut db, "foo");
+        let bar_mod = new_module(&mut db, "bar");
+        let foo = new_class(&mut db, "Foo");
+        let bar = new_class(&mut db, "Bar");
+
+        foo_mod.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(foo));
+        bar_mod.new_symbol(&mut db, "Foo".to_string(), Symbol::Class(foo));
+        bar_mod.new_symbol(&mut db, "Bar".to_string(), Symbol::Class(bar));
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Class(foo), "Bar"),
+            Err(RenameError::SymbolConflict(bar_mod))
+        );
+        assert!(db.rename_symbol(Symbol::Class(bar), "Foo").is_err());
+        assert!(db.rename_symbol(Symbol::Class(foo), "Baz").is_ok());
+    }
+
+    #[test]
+    fn test_rename_symbol_with_references() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let method =
+            method_at(&mut db, module, "name", TypeId::Class(class), 1);
+
+        class.add_method(&mut db, "name".to_string(), method);
+        db.track_references();
+        db.record_reference(
+            Symbol::Method(method),
+            Reference::new(module, loc(5)),
+        );
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Method(method), "full_name"),
+            Ok(vec![
+                edit(module, 1, "name", "full_name"),
+                edit(module, 5, "name", "full_name"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rename_symbol_with_method_conflict() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+        let name = method_at(&mut db, module, "name", TypeId::Class(class), 1);
+        let age = method_at(&mut db, module, "age", TypeId::Class(class), 2);
+
+        class.add_method(&mut db, "name".to_string(), name);
+        class.add_method(&mut db, "age".to_string(), age);
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Method(name), "age"),
+            Err(RenameError::MethodConflict(age))
+        );
+        assert_eq!(
+            db.rename_symbol(Symbol::Method(name), "Age"),
+            Err(RenameError::InvalidName("Age".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rename_symbol_trait_method() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let to_string = new_trait(&mut db, "ToString");
+        let string = new_class(&mut db, "String");
+        let int = new_class(&mut db, "Int");
+        let req = method_at(
+            &mut db,
+            module,
+            "to_string",
+            TypeId::Trait(to_string),
+            1,
+        );
+        let string_imp =
+            method_at(&mut db, module, "to_string", instance(string), 2);
+        let int_imp = method_at(&mut db, module, "to_string", instance(int), 3);
+        let int_other = method_at(&mut db, module, "to_str", instance(int), 4);
+
+        to_string.add_required_method(&mut db, "to_string".to_string(), req);
+        implement(&mut db, trait_instance(to_string), string);
+        implement(&mut db, trait_instance(to_string), int);
+        string.add_method(&mut db, "to_string".to_string(), string_imp);
+        int.add_method(&mut db, "to_string".to_string(), int_imp);
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Method(req), "to_str"),
+            Ok(vec![
+                edit(module, 1, "to_string", "to_str"),
+                edit(module, 2, "to_string", "to_str"),
+                edit(module, 3, "to_string", "to_str"),
+            ])
+        );
+
+        int.add_method(&mut db, "to_str".to_string(), int_other);
+
+        assert_eq!(
+            db.rename_symbol(Symbol::Method(req), "to_str"),
+            Err(RenameError::MethodConflict(int_other))
+        );
+    }
+}
@@ -1,8 +1,8 @@
 //! Resolving abstract types into concrete types.
 use crate::either::Either;
 use crate::{
-    ClassInstance, Closure, Database, TraitId, TraitInstance, TypeArguments,
-    TypeBounds, TypeId, TypeParameterId, TypeRef,
+    ClassInstance, Closure, Database, TraitId, TraitInstance, Tuple,
+    TypeArguments, TypeBounds, TypeId, TypeParameterId, TypeRef,
 };
 use std::collections::HashMap;
 
@@ -46,6 +46,12 @@ pub struct TypeResolver<'a> {
     /// If present it's used to remap inherited type parameters to their correct
     /// types.
     surrounding_trait: Option<TraitId>,
+
+    /// The type parameters of the closures we're resolving.
+    ///
+    /// These parameters are assigned a value when calling the closure, so
+    /// they're left as-is when resolving the closure's type.
+    quantified: Vec<TypeParameterId>,
 }
 
 impl<'a> TypeResolver<'a> {
@@ -62,6 +68,7 @@ impl<'a> TypeResolver<'a> {
             rigid: false,
             owned: false,
             surrounding_trait: None,
+            quantified: Vec::new(),
             cached: HashMap::new(),
         }
     }
@@ -134,6 +141,20 @@ impl<'a> TypeResolver<'a> {
                 ) => TypeRef::Pointer(id),
                 Either::Right(typ) => typ,
             },
+            TypeRef::NonNullPointer(id) => match self.resolve_type_id(id) {
+                Either::Left(res) => TypeRef::NonNullPointer(res),
+                Either::Right(TypeRef::Placeholder(id)) => {
+                    TypeRef::Placeholder(id.as_pointer())
+                }
+                Either::Right(
+                    TypeRef::Owned(id)
+                    | TypeRef::Any(id)
+                    | TypeRef::Pointer(id)
+                    | TypeRef::NonNullPointer(id)
+                    | TypeRef::Uni(id),
+                ) => TypeRef::NonNullPointer(id),
+                Either::Right(typ) => typ,
+            },
             TypeRef::Ref(id) => match self.resolve_type_id(id) {
                 Either::Left(res) => TypeRef::Ref(res),
                 Either::Right(TypeRef::Placeholder(id)) => {
@@ -189,6 +210,19 @@ impl<'a> TypeResolver<'a> {
                 .value(self.db)
                 .map(|v| self.resolve_type_ref(v))
                 .unwrap_or(value),
+            TypeRef::Union(id) => {
+                let members = id.members(self.db).clone();
+                let resolved: Vec<_> = members
+                    .iter()
+                    .map(|&typ| self.resolve_type_ref(typ))
+                    .collect();
+
+                if resolved == members {
+                    value
+                } else {
+                    TypeRef::union(self.db, resolved)
+                }
+            }
             _ => value,
         };
 
@@ -232,6 +266,22 @@ impl<'a> TypeResolver<'a> {
                     self.db, base, args,
                 )))
             }
+            TypeId::Tuple(tup) => {
+                let elements = tup.elements(self.db).clone();
+                let resolved: Vec<_> = elements
+                    .iter()
+                    .map(|&typ| self.resolve_type_ref(typ))
+                    .collect();
+
+                if resolved == elements {
+                    Either::Left(id)
+                } else {
+                    Either::Left(TypeId::Tuple(Tuple::alloc(self.db, resolved)))
+                }
+            }
+            TypeId::TypeParameter(pid) if self.quantified.contains(&pid) => {
+                Either::Left(id)
+            }
             TypeId::TypeParameter(pid) => {
                 let pid = self.remap_type_parameter(pid);
 
@@ -251,6 +301,9 @@ impl<'a> TypeResolver<'a> {
             TypeId::Closure(id) => {
                 let mut new = id.get(self.db).clone();
                 let immutable = self.immutable;
+                let quantified = self.quantified.len();
+
+                self.quantified.extend(new.type_parameters.iter().cloned());
 
                 // The ownership of the closure's arguments and return type
                 // shouldn't be changed, instead the ability to use the closure
@@ -264,14 +317,36 @@ impl<'a> TypeResolver<'a> {
 
                 new.return_type = self.resolve_type_ref(new.return_type);
                 self.immutable = immutable;
+                self.quantified.truncate(quantified);
                 Either::Left(TypeId::Closure(Closure::add(self.db, new)))
             }
+            TypeId::TypeAlias(ins) => {
+                let base = ins.instance_of();
+                let typ = base.aliased_type(self.db);
+
+                // The arguments may refer to type parameters from the
+                // surrounding scope, so these are resolved first. The result
+                // is then used to replace the alias' own type parameters.
+                let Some(mut args) = ins.type_arguments(self.db).cloned()
+                else {
+                    return Either::Right(self.resolve_type_ref(typ));
+                };
+
+                self.resolve_arguments(&mut args);
+
+                let bounds = TypeBounds::new();
+                let expanded = TypeResolver::new(self.db, &args, &bounds)
+                    .with_rigid(self.rigid)
+                    .resolve_type_ref(typ);
+
+                Either::Right(expanded)
+            }
             _ => Either::Left(id),
         }
     }
 
     fn resolve_arguments(&mut self, arguments: &mut TypeArguments) {
-        for value in arguments.mapping.values_mut() {
+        for value in arguments.values_mut() {
             *value = self.resolve_type_ref(*value);
         }
     }
@@ -311,10 +386,11 @@ impl<'a> TypeResolver<'a> {
 mod tests {
     use super::*;
     use crate::test::{
-        any, closure, generic_instance_id, generic_trait_instance,
-        generic_trait_instance_id, immutable, immutable_uni, instance, mutable,
-        mutable_uni, new_parameter, new_trait, owned, parameter, placeholder,
-        pointer, rigid, type_arguments, type_bounds, uni,
+        alias_instance_id, any, closure, generic_instance_id,
+        generic_trait_instance, generic_trait_instance_id, immutable,
+        immutable_uni, instance, mutable, mutable_uni, new_parameter,
+        new_trait, new_type_alias, owned, parameter, placeholder, pointer,
+        rigid, type_arguments, type_bounds, uni,
     };
     use crate::{
         Block, ClassId, Closure, Ownership, TypePlaceholder, TypePlaceholderId,
@@ -838,6 +914,42 @@ mod tests {
         assert_eq!(output.arguments(&db)[0].value_type, TypeRef::int());
     }
 
+    #[test]
+    fn test_generic_closure() {
+        let mut db = Database::new();
+        let fun = Closure::alloc(&mut db, false);
+        let outer = new_parameter(&mut db, "A");
+        let inner = new_parameter(&mut db, "B");
+        let loc = Location::default();
+
+        fun.add_type_parameter(&mut db, inner);
+        fun.set_return_type(&mut db, owned(parameter(outer)));
+        fun.new_argument(
+            &mut db,
+            "a".to_string(),
+            immutable(parameter(inner)),
+            immutable(parameter(inner)),
+            loc,
+        );
+
+        let args = type_arguments(vec![(outer, TypeRef::int())]);
+        let bounds = TypeBounds::new();
+        let output = match resolve(&mut db, &args, &bounds, owned(closure(fun)))
+        {
+            TypeRef::Owned(TypeId::Closure(id)) => id,
+            _ => panic!("Expected the resolved value to be a closure"),
+        };
+
+        // The closure's own type parameters are assigned when it's called, so
+        // they must not be turned into placeholders.
+        assert_eq!(output.return_type(&db), TypeRef::int());
+        assert_eq!(
+            output.arguments(&db)[0].value_type,
+            immutable(parameter(inner))
+        );
+        assert_eq!(output.type_parameters(&db), vec![inner]);
+    }
+
     #[test]
     fn test_recursive() {
         let mut db = Database::new();
@@ -902,4 +1014,38 @@ mod tests {
             Some(bound)
         );
     }
+
+    #[test]
+    fn test_type_alias() {
+        let mut db = Database::new();
+        let array_param =
+            ClassId::array().new_type_parameter(&mut db, "T".to_string());
+        let list = new_type_alias(&mut db, "List", TypeRef::Unknown);
+        let list_param = list.new_type_parameter(&mut db, "V".to_string());
+        let outer = new_parameter(&mut db, "X");
+        let aliased = owned(generic_instance_id(
+            &mut db,
+            ClassId::array(),
+            vec![any(parameter(list_param))],
+        ));
+
+        list.set_aliased_type(&mut db, aliased);
+
+        let list_of_x =
+            alias_instance_id(&mut db, list, vec![any(parameter(outer))]);
+        let args = type_arguments(vec![(outer, TypeRef::int())]);
+        let bounds = TypeBounds::new();
+        let ins = resolve(&mut db, &args, &bounds, immutable(list_of_x))
+            .as_class_instance(&db)
+            .unwrap();
+
+        assert_eq!(ins.instance_of(), ClassId::array());
+        assert_eq!(
+            ins.type_arguments(&db).unwrap().get(array_param),
+            Some(TypeRef::int())
+        );
+        assert!(
+            resolve(&mut db, &args, &bounds, immutable(list_of_x)).is_ref(&db)
+        );
+    }
 }
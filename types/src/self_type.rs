@@ -0,0 +1,146 @@
+//! The `Self` type of traits.
+//!
+//! Within a trait, `Self` refers to the type that implements the trait. This
+//! allows traits to define methods such as `fn clone -> Self`, which return a
+//! value of the implementing type instead of a value of the trait.
+//!
+//! `Self` is represented as a type parameter of the trait that requires the
+//! trait to be implemented. This way methods of the trait can be called on
+//! values of type `Self`, and the existing machinery for type parameters
+//! handles the rest. When checking if the method of an implementation is
+//! compatible with the method of the trait, the parameter is assigned the
+//! implementing class, such that for `Clone` implemented by `Person` the
+//! method `fn clone -> Self` is expected to be `fn clone -> Person`.
+//!
+//! Unlike other type parameters of a trait, `Self` isn't assigned a value in
+//! a trait instance. When calling a method through a trait using dynamic
+//! dispatch, the type of `Self` isn't known, so methods that take arguments of
+//! type `Self` or return `Self` by value prevent the trait from being used as a
+//! trait object.
+use crate::{
+    ClassInstance, Database, TraitId, TraitInstance, TypeArguments, TypeId,
+    TypeParameter, TypeParameterId, TypeRef, SELF_TYPE,
+};
+
+impl TraitId {
+    /// Defines the type parameter that `Self` refers to, returning the
+    /// existing parameter if it's already defined.
+    ///
+    /// This must be called after defining the type parameters of the trait.
+    pub fn new_self_type(self, db: &mut Database) -> TypeParameterId {
+        if let Some(id) = self.get(db).self_type {
+            return id;
+        }
+
+        let param = TypeParameter::alloc(db, SELF_TYPE.to_string());
+        let params = self.type_parameters(db);
+        let requirement = if params.is_empty() {
+            TraitInstance::new(self)
+        } else {
+            let mut args = TypeArguments::new();
+
+            for p in params {
+                args.assign(p, TypeRef::Any(TypeId::TypeParameter(p)));
+            }
+
+            TraitInstance::generic(db, self, args)
+        };
+
+        param.add_requirements(db, vec![requirement]);
+        self.get_mut(db).self_type = Some(param);
+        param
+    }
+
+    /// Returns the type parameter that `Self` refers to, if it's defined.
+    pub fn self_type(self, db: &Database) -> Option<TypeParameterId> {
+        self.get(db).self_type
+    }
+}
+
+impl TypeArguments {
+    /// Returns the type arguments to use when checking the methods of an
+    /// implementation of a trait.
+    ///
+    /// This includes the type arguments of the trait instance, and assigns
+    /// `Self` the class that implements the trait.
+    pub fn for_implementation(
+        db: &Database,
+        instance: TraitInstance,
+        class: ClassInstance,
+    ) -> TypeArguments {
+        let mut args = TypeArguments::for_trait(db, instance);
+
+        if let Some(param) = instance.instance_of().self_type(db) {
+            args.assign(param, TypeRef::Owned(TypeId::ClassInstance(class)));
+        }
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{any, instance, new_class, new_trait, parameter};
+    use crate::Symbol;
+
+    #[test]
+    fn test_trait_id_new_self_type() {
+        let mut db = Database::new();
+        let clone = new_trait(&mut db, "Clone");
+
+        assert_eq!(clone.self_type(&db), None);
+        assert_eq!(clone.named_type(&db, SELF_TYPE), None);
+
+        let param = clone.new_self_type(&mut db);
+
+        assert_eq!(clone.new_self_type(&mut db), param);
+        assert_eq!(clone.self_type(&db), Some(param));
+        assert_eq!(param.name(&db), SELF_TYPE);
+        assert_eq!(param.requirements(&db), vec![TraitInstance::new(clone)]);
+        assert_eq!(
+            clone.named_type(&db, SELF_TYPE),
+            Some(Symbol::TypeParameter(param))
+        );
+        assert!(!clone.is_generic(&db));
+    }
+
+    #[test]
+    fn test_trait_id_new_self_type_with_generic_trait() {
+        let mut db = Database::new();
+        let into = new_trait(&mut db, "Into");
+        let param = into.new_type_parameter(&mut db, "T".to_string());
+        let self_type = into.new_self_type(&mut db);
+        let req = self_type.requirements(&db)[0];
+
+        assert_eq!(req.instance_of(), into);
+        assert_eq!(
+            req.type_arguments(&db).unwrap().get(param),
+            Some(any(parameter(param)))
+        );
+    }
+
+    #[test]
+    fn test_type_arguments_for_implementation() {
+        let mut db = Database::new();
+        let clone = new_trait(&mut db, "Clone");
+        let person = new_class(&mut db, "Person");
+        let ins = ClassInstance::new(person);
+
+        assert!(TypeArguments::for_implementation(
+            &db,
+            TraitInstance::new(clone),
+            ins
+        )
+        .is_empty());
+
+        let param = clone.new_self_type(&mut db);
+        let args = TypeArguments::for_implementation(
+            &db,
+            TraitInstance::new(clone),
+            ins,
+        );
+
+        assert_eq!(args.get(param), Some(TypeRef::Owned(instance(person))));
+    }
+}
@@ -0,0 +1,207 @@
+//! Determining which value types can be sent between processes.
+//!
+//! Value types are copied when sent to another process, which is fine for
+//! types such as `Int`, but not for types that wrap raw pointers: copying
+//! such a type results in two processes using the same memory without any
+//! synchronisation. For this reason, stack allocated types that (directly or
+//! through their fields) store raw pointers aren't sendable.
+//!
+//! Types for which sharing the pointed to memory is safe (e.g. because the
+//! memory is immutable) can opt in to being sendable by implementing the
+//! `std.process.Sendable` marker trait.
+//!
+//! Raw pointers themselves remain sendable, as using them is already unsafe.
+use crate::{
+    ClassId, Database, FieldId, Symbol, TraitId, TypeId, TypeRef,
+    SENDABLE_MODULE, SENDABLE_TRAIT,
+};
+
+impl Database {
+    /// Returns the `Sendable` trait, if its module is loaded.
+    pub fn sendable_trait(&self) -> Option<TraitId> {
+        match self
+            .optional_module(SENDABLE_MODULE)?
+            .symbol(self, SENDABLE_TRAIT)
+        {
+            Some(Symbol::Trait(id)) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+impl ClassId {
+    /// Returns `true` if the class implements the `Sendable` trait.
+    pub fn is_explicitly_sendable(self, db: &Database) -> bool {
+        db.sendable_trait()
+            .map_or(false, |id| self.trait_implementation(db, id).is_some())
+    }
+
+    /// Returns the first field that prevents values of this class from being
+    /// sent between processes.
+    ///
+    /// This only applies to stack allocated classes, as the sendability of
+    /// heap allocated classes depends on their ownership instead.
+    pub fn unsendable_field(self, db: &Database) -> Option<FieldId> {
+        unsendable_field(db, self, &mut Vec::new())
+    }
+}
+
+fn unsendable_field(
+    db: &Database,
+    class: ClassId,
+    visited: &mut Vec<ClassId>,
+) -> Option<FieldId> {
+    // Recursive stack allocated classes are invalid, but this function may be
+    // used before such classes are rejected, so we must guard against
+    // recursing indefinitely.
+    if !class.is_stack_allocated(db) || visited.contains(&class) {
+        return None;
+    }
+
+    visited.push(class);
+
+    // Looking up the trait is more expensive than checking the fields, and
+    // most classes don't store pointers, so we only look up the trait if
+    // necessary.
+    let found = class
+        .fields(db)
+        .into_iter()
+        .find(|f| {
+            let typ = f.value_type(db);
+
+            typ.is_pointer(db)
+                || typ
+                    .class_id(db)
+                    .and_then(|id| unsendable_field(db, id, visited))
+                    .is_some()
+        })
+        .filter(|_| !class.is_explicitly_sendable(db));
+
+    visited.pop();
+    found
+}
+
+impl TypeRef {
+    /// Returns the field that prevents `self` from being sent between
+    /// processes, if `self` is a stack allocated class instance.
+    pub fn unsendable_field(self, db: &Database) -> Option<FieldId> {
+        match self {
+            TypeRef::Owned(TypeId::ClassInstance(ins))
+            | TypeRef::Uni(TypeId::ClassInstance(ins))
+            | TypeRef::Ref(TypeId::ClassInstance(ins))
+            | TypeRef::Mut(TypeId::ClassInstance(ins))
+            | TypeRef::UniRef(TypeId::ClassInstance(ins))
+            | TypeRef::UniMut(TypeId::ClassInstance(ins)) => {
+                ins.instance_of().unsendable_field(db)
+            }
+            TypeRef::Placeholder(id) => {
+                id.value(db).and_then(|v| v.unsendable_field(db))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{implement, instance, new_module, new_trait, owned};
+    use crate::{
+        Class, ClassKind, ForeignType, Location, ModuleId, Sign, TraitInstance,
+        Visibility,
+    };
+
+    fn new_class(
+        db: &mut Database,
+        module: ModuleId,
+        name: &str,
+        kind: ClassKind,
+    ) -> ClassId {
+        Class::alloc(
+            db,
+            name.to_string(),
+            kind,
+            Visibility::Public,
+            module,
+            Location::default(),
+        )
+    }
+
+    fn new_field(db: &mut Database, class: ClassId, name: &str, typ: TypeRef) {
+        let module = class.module(db);
+        let index = class.number_of_fields(db);
+
+        class.new_field(
+            db,
+            name.to_string(),
+            index,
+            typ,
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+    }
+
+    #[test]
+    fn test_unsendable_field() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let handle = new_class(&mut db, module, "Handle", ClassKind::Extern);
+        let wrapper = new_class(&mut db, module, "Wrapper", ClassKind::Extern);
+        let heap = new_class(&mut db, module, "Heap", ClassKind::Regular);
+        let pair = new_class(&mut db, module, "Pair", ClassKind::Extern);
+        let ptr = TypeRef::pointer(TypeId::Foreign(ForeignType::Int(
+            8,
+            Sign::Unsigned,
+        )));
+
+        new_field(&mut db, handle, "raw", ptr);
+        new_field(&mut db, wrapper, "size", TypeRef::int());
+        new_field(&mut db, wrapper, "handle", owned(instance(handle)));
+        new_field(&mut db, heap, "raw", ptr);
+        new_field(&mut db, pair, "a", TypeRef::int());
+
+        let handle_field = handle.field_by_index(&db, 0).unwrap();
+        let wrapper_field = wrapper.field_by_index(&db, 1).unwrap();
+
+        assert_eq!(handle.unsendable_field(&db), Some(handle_field));
+        assert_eq!(wrapper.unsendable_field(&db), Some(wrapper_field));
+        assert_eq!(heap.unsendable_field(&db), None);
+        assert_eq!(pair.unsendable_field(&db), None);
+        assert!(!owned(instance(handle)).is_sendable(&db));
+        assert!(!owned(instance(wrapper)).is_sendable_output(&db));
+        assert!(owned(instance(pair)).is_sendable(&db));
+        assert!(ptr.is_sendable(&db));
+    }
+
+    #[test]
+    fn test_unsendable_field_with_sendable_trait() {
+        let mut db = Database::new();
+        let process = new_module(&mut db, SENDABLE_MODULE);
+        let module = new_module(&mut db, "foo");
+        let sendable = new_trait(&mut db, SENDABLE_TRAIT);
+        let handle = new_class(&mut db, module, "Handle", ClassKind::Regular);
+        let ptr = TypeRef::pointer(TypeId::Foreign(ForeignType::Int(
+            8,
+            Sign::Unsigned,
+        )));
+
+        handle.set_stack_allocated(&mut db);
+        new_field(&mut db, handle, "raw", ptr);
+
+        assert_eq!(db.sendable_trait(), None);
+        assert!(!owned(instance(handle)).is_sendable(&db));
+
+        process.new_symbol(
+            &mut db,
+            SENDABLE_TRAIT.to_string(),
+            Symbol::Trait(sendable),
+        );
+        implement(&mut db, TraitInstance::new(sendable), handle);
+
+        assert_eq!(db.sendable_trait(), Some(sendable));
+        assert!(handle.is_explicitly_sendable(&db));
+        assert_eq!(handle.unsendable_field(&db), None);
+        assert!(owned(instance(handle)).is_sendable(&db));
+    }
+}
@@ -0,0 +1,171 @@
+//! Undoing changes made to a type database.
+//!
+//! Speculative type checking (e.g. trying different interpretations of a
+//! method call) may allocate new types and assign type placeholders. Instead
+//! of cloning the entire database before doing so, a snapshot can be taken
+//! using `Database::snapshot()`, and the changes can be undone using
+//! `Database::rollback()`.
+//!
+//! A snapshot records the number of entities of each kind, and the state of
+//! all type placeholders. Rolling back removes all entities allocated after
+//! the snapshot was taken, and restores the state of the placeholders. Other
+//! changes made to entities that existed before the snapshot was taken (e.g.
+//! adding a method to an existing class) aren't undone, so code rolling back
+//! a database must not make such changes.
+use crate::module_name::ModuleName;
+use crate::{ClassId, Database, MethodId, TypeParameterId, TypeRef};
+use std::sync::atomic::Ordering;
+
+/// The state of a single type placeholder.
+#[derive(Copy, Clone)]
+struct Placeholder {
+    value: TypeRef,
+    parent: u32,
+    rank: u8,
+    required: Option<TypeParameterId>,
+}
+
+/// The state of a `Database` at a point in time.
+pub struct Snapshot {
+    modules: usize,
+    packages: usize,
+    traits: usize,
+    classes: usize,
+    type_aliases: usize,
+    unions: usize,
+    foreign_functions: usize,
+    type_parameters: usize,
+    type_arguments: u32,
+    methods: usize,
+    fields: usize,
+    closures: usize,
+    variables: usize,
+    constants: usize,
+    constructors: usize,
+    placeholders: Vec<Placeholder>,
+    main_module: Option<ModuleName>,
+    main_method: Option<MethodId>,
+    main_class: Option<ClassId>,
+}
+
+impl Database {
+    /// Returns a snapshot of the current state of the database.
+    pub fn snapshot(&self) -> Snapshot {
+        let placeholders = self
+            .type_placeholders
+            .iter()
+            .map(|p| Placeholder {
+                value: p.value.get(),
+                parent: p.parent.get(),
+                rank: p.rank.get(),
+                required: p.required.get(),
+            })
+            .collect();
+
+        Snapshot {
+            modules: self.modules.len(),
+            packages: self.packages.len(),
+            traits: self.traits.len(),
+            classes: self.classes.len(),
+            type_aliases: self.type_aliases.len(),
+            unions: self.unions.len(),
+            foreign_functions: self.foreign_functions.len(),
+            type_parameters: self.type_parameters.len(),
+            type_arguments: self.type_arguments.mark(),
+            methods: self.methods.len(),
+            fields: self.fields.len(),
+            closures: self.closures.len(),
+            variables: self.variables.len(),
+            constants: self.constants.len(),
+            constructors: self.constructors.len(),
+            placeholders,
+            main_module: self.main_module.clone(),
+            main_method: self.main_method,
+            main_class: self.main_class,
+        }
+    }
+
+    /// Restores the database to the state of the given snapshot.
+    ///
+    /// The snapshot must be taken from this database.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        self.modules.truncate(snapshot.modules);
+        self.packages.truncate(snapshot.packages);
+
+        for pkg in &mut self.packages {
+            pkg.modules.retain(|id| (id.0 as usize) < snapshot.modules);
+        }
+
+        self.traits.truncate(snapshot.traits);
+        self.classes.truncate(snapshot.classes);
+        self.type_aliases.truncate(snapshot.type_aliases);
+        self.unions.truncate(snapshot.unions);
+        self.foreign_functions.truncate(snapshot.foreign_functions);
+        self.type_parameters.truncate(snapshot.type_parameters);
+        self.type_arguments.remove_since(snapshot.type_arguments);
+        self.methods.truncate(snapshot.methods);
+        self.fields.truncate(snapshot.fields);
+        self.closures.truncate(snapshot.closures);
+        self.variables.truncate(snapshot.variables);
+        self.constants.truncate(snapshot.constants);
+        self.constructors.truncate(snapshot.constructors);
+        self.type_placeholders.truncate(snapshot.placeholders.len());
+
+        self.module_mapping.retain(|_, id| (id.0 as usize) < snapshot.modules);
+        self.module_versions.retain(|_, ids| {
+            ids.retain(|id| (id.0 as usize) < snapshot.modules);
+            !ids.is_empty()
+        });
+
+        for (placeholder, state) in
+            self.type_placeholders.iter().zip(snapshot.placeholders)
+        {
+            placeholder.value.set(state.value);
+            placeholder.parent.set(state.parent);
+            placeholder.rank.set(state.rank);
+            placeholder.required.set(state.required);
+        }
+
+        self.main_module = snapshot.main_module;
+        self.main_method = snapshot.main_method;
+        self.main_class = snapshot.main_class;
+
+        // Cached type checking results may depend on the placeholder values
+        // we just reverted, so we have to invalidate them.
+        self.placeholder_assignments.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_module, placeholder};
+    use crate::TypePlaceholder;
+
+    #[test]
+    fn test_rollback() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let var = TypePlaceholder::alloc(&mut db, None);
+        let classes = db.number_of_classes();
+        let snapshot = db.snapshot();
+        let new_var = TypePlaceholder::alloc(&mut db, None);
+
+        new_module(&mut db, "bar");
+        new_class(&mut db, "Foo");
+        var.assign(&mut db, placeholder(new_var));
+        new_var.assign(&mut db, TypeRef::int());
+
+        assert_eq!(var.value(&db), Some(TypeRef::int()));
+
+        db.rollback(snapshot);
+
+        assert_eq!(db.number_of_classes(), classes);
+        assert_eq!(db.number_of_modules(), 1);
+        assert_eq!(db.module("foo"), module);
+        assert!(db.optional_module("bar").is_none());
+        assert_eq!(db.type_placeholders.len(), 1);
+        assert_eq!(var.value(&db), None);
+        assert_eq!(var.root(&db), var.id);
+    }
+}
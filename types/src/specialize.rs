@@ -1,9 +1,83 @@
+use crate::diagnostics::Diagnostic;
+use crate::weak::weak_shapes;
 use crate::{
-    ClassId, ClassInstance, Database, InternedTypeArguments, Shape, TypeId,
-    TypeParameterId, TypeRef,
+    ClassId, ClassInstance, ConstructorId, Database, InternedTypeArguments,
+    Shape, Tuple, TypeId, TypeParameterId, TypeRef,
 };
 use std::collections::HashMap;
 
+/// The layout of an enum that stores its tag in the unused bit-patterns of its
+/// payload.
+///
+/// An enum with a single constructor storing a non-null pointer and a single
+/// constructor without arguments (e.g. `Option[ref T]`) doesn't need a tag:
+/// the constructor without arguments is represented using a NULL pointer, such
+/// that values of the enum are the size of a single pointer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Niche {
+    /// The constructor that stores the pointer.
+    pub payload: ConstructorId,
+
+    /// The constructor represented using a NULL pointer.
+    pub empty: ConstructorId,
+}
+
+/// Returns the niche layout to use for an enum, if any.
+///
+/// The `shapes` argument is used to determine the shapes of the payload's type
+/// parameters.
+pub fn niche_layout(
+    db: &Database,
+    interned: &mut InternedTypeArguments,
+    shapes: &HashMap<TypeParameterId, Shape>,
+    class: ClassId,
+) -> Option<Niche> {
+    let (payload, empty) = match *class.constructors(db).as_slice() {
+        [a, b] if a.number_of_arguments(db) == 0 => (b, a),
+        [a, b] if b.number_of_arguments(db) == 0 => (a, b),
+        _ => return None,
+    };
+
+    let typ = match *payload.arguments(db) {
+        [typ] => typ,
+        _ => return None,
+    };
+
+    // Raw pointers may be NULL and thus don't have a niche, while stack
+    // allocated values don't use pointers in the first place.
+    match typ.shape(db, interned, shapes) {
+        Shape::Owned
+        | Shape::Mut
+        | Shape::Ref
+        | Shape::String
+        | Shape::Atomic => Some(Niche { payload, empty }),
+        _ => None,
+    }
+}
+
+/// Limits applied when specializing generic types and methods.
+///
+/// These limits exist to prevent the compiler from exhausting all memory when
+/// a program results in a large or infinite number of specializations, such as
+/// when a generic type contains itself using ever growing type arguments.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpecializationLimits {
+    /// The maximum number of specializations of a single generic type or
+    /// method.
+    pub specializations: usize,
+
+    /// The maximum number of specializations of types that can be in progress
+    /// at the same time, i.e. the maximum depth of the chain of types that
+    /// require the specialization of another type.
+    pub depth: usize,
+}
+
+impl Default for SpecializationLimits {
+    fn default() -> SpecializationLimits {
+        SpecializationLimits { specializations: 4096, depth: 64 }
+    }
+}
+
 /// Returns a list of shapes from a shape mapping, sorted by the type parameter
 /// IDs.
 pub fn ordered_shapes_from_map(
@@ -17,6 +91,80 @@ pub fn ordered_shapes_from_map(
     pairs.into_iter().map(|(_, s)| *s).collect()
 }
 
+/// Returns `true` if the type refers to the given type parameter.
+///
+/// This check is conservative: types for which we can't cheaply determine this
+/// (e.g. closures and unions) are treated as referring to the type parameter.
+pub fn uses_type_parameter(
+    db: &Database,
+    typ: TypeRef,
+    param: TypeParameterId,
+) -> bool {
+    match typ {
+        TypeRef::Owned(id)
+        | TypeRef::Uni(id)
+        | TypeRef::Ref(id)
+        | TypeRef::UniRef(id)
+        | TypeRef::Mut(id)
+        | TypeRef::UniMut(id)
+        | TypeRef::Any(id)
+        | TypeRef::Pointer(id)
+        | TypeRef::NonNullPointer(id) => {
+            type_id_uses_type_parameter(db, id, param)
+        }
+        TypeRef::Placeholder(id) => {
+            id.value(db).map_or(false, |v| uses_type_parameter(db, v, param))
+        }
+        TypeRef::Union(_) => true,
+        TypeRef::Never | TypeRef::Error | TypeRef::Unknown => false,
+    }
+}
+
+fn type_id_uses_type_parameter(
+    db: &Database,
+    id: TypeId,
+    param: TypeParameterId,
+) -> bool {
+    match id {
+        TypeId::TypeParameter(id)
+        | TypeId::RigidTypeParameter(id)
+        | TypeId::AtomicTypeParameter(id) => id == param,
+        TypeId::ClassInstance(ins) => {
+            ins.type_arguments(db).map_or(false, |a| {
+                a.values().any(|t| uses_type_parameter(db, t, param))
+            })
+        }
+        TypeId::TraitInstance(ins) => {
+            ins.type_arguments(db).map_or(false, |a| {
+                a.values().any(|t| uses_type_parameter(db, t, param))
+            })
+        }
+        TypeId::Tuple(tup) => {
+            tup.elements(db).iter().any(|&t| uses_type_parameter(db, t, param))
+        }
+        TypeId::Closure(_) | TypeId::TypeAlias(_) => true,
+        TypeId::Class(_)
+        | TypeId::Trait(_)
+        | TypeId::Module(_)
+        | TypeId::Foreign(_)
+        | TypeId::Constant(_) => false,
+    }
+}
+
+/// Returns the shape to use for a type parameter that's only used through
+/// borrows, or `None` if the shape must be used as-is.
+///
+/// Owned values and borrows are all passed around as pointers, and dropping a
+/// borrow only decrements its reference count. This means that a method that
+/// only borrows values of a type parameter produces the same code for each of
+/// these shapes, allowing them to share a single specialization.
+pub fn borrowed_shape(shape: Shape) -> Option<Shape> {
+    match shape {
+        Shape::Owned | Shape::Mut | Shape::Ref => Some(Shape::Ref),
+        _ => None,
+    }
+}
+
 /// A type which takes a (potentially) generic type, and specializes it and its
 /// fields (if it has any).
 ///
@@ -72,6 +220,8 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
                 Some(Shape::Boolean) => TypeRef::boolean(),
                 Some(Shape::String) => TypeRef::string(),
                 Some(Shape::Nil) => TypeRef::nil(),
+                Some(Shape::Char) => TypeRef::char(),
+                Some(&Shape::Constant(val)) => TypeRef::constant(val),
                 Some(Shape::Ref) => value.as_ref(self.db),
                 Some(Shape::Mut) => value.force_as_mut(self.db),
                 Some(Shape::Atomic) => {
@@ -95,6 +245,8 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
                 Some(Shape::Boolean) => TypeRef::boolean(),
                 Some(Shape::String) => TypeRef::string(),
                 Some(Shape::Nil) => TypeRef::nil(),
+                Some(Shape::Char) => TypeRef::char(),
+                Some(&Shape::Constant(val)) => TypeRef::constant(val),
                 Some(Shape::Atomic) => {
                     TypeRef::Ref(TypeId::AtomicTypeParameter(id))
                 }
@@ -116,6 +268,8 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
                 Some(Shape::Boolean) => TypeRef::boolean(),
                 Some(Shape::String) => TypeRef::string(),
                 Some(Shape::Nil) => TypeRef::nil(),
+                Some(Shape::Char) => TypeRef::char(),
+                Some(&Shape::Constant(val)) => TypeRef::constant(val),
                 Some(Shape::Ref) => value.as_ref(self.db),
                 Some(Shape::Atomic) => {
                     TypeRef::Mut(TypeId::AtomicTypeParameter(id))
@@ -156,15 +310,39 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
             TypeRef::Pointer(id) => {
                 TypeRef::Pointer(self.specialize_type_id(id))
             }
+            TypeRef::NonNullPointer(id) => {
+                TypeRef::NonNullPointer(self.specialize_type_id(id))
+            }
+            TypeRef::Union(id) => {
+                let members = id
+                    .members(self.db)
+                    .clone()
+                    .into_iter()
+                    .map(|typ| self.specialize(typ))
+                    .collect();
+
+                TypeRef::union(self.db, members)
+            }
             _ => value,
         }
     }
 
     fn specialize_type_id(&mut self, id: TypeId) -> TypeId {
-        if let TypeId::ClassInstance(ins) = id {
-            TypeId::ClassInstance(self.specialize_class_instance(ins))
-        } else {
-            id
+        match id {
+            TypeId::ClassInstance(ins) => {
+                TypeId::ClassInstance(self.specialize_class_instance(ins))
+            }
+            TypeId::Tuple(tup) => {
+                let elements = tup
+                    .elements(self.db)
+                    .clone()
+                    .into_iter()
+                    .map(|typ| self.specialize(typ))
+                    .collect();
+
+                TypeId::Tuple(Tuple::alloc(self.db, elements))
+            }
+            _ => id,
         }
     }
 
@@ -225,6 +403,12 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
 
                 var.set_arguments(self.db, args);
             }
+
+            if let Some(niche) =
+                niche_layout(self.db, self.interned, self.shapes, class)
+            {
+                class.set_niche(self.db, niche);
+            }
         }
 
         for field in class.fields(self.db) {
@@ -254,7 +438,7 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
         }
 
         let mut args = ins.type_arguments(self.db).unwrap().clone();
-        let key = class
+        let mut key: Vec<Shape> = class
             .type_parameters(self.db)
             .into_iter()
             .map(|p| {
@@ -266,12 +450,29 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
             })
             .collect();
 
-        let new = class
-            .get(self.db)
-            .specializations
-            .get(&key)
-            .cloned()
-            .unwrap_or_else(|| self.specialize_class(class, key));
+        if class.is_weak(self.db) {
+            key = weak_shapes(key);
+        }
+
+        let new =
+            if let Some(&id) = class.get(self.db).specializations.get(&key) {
+                id
+            } else if !self.db.specialization_errors.is_empty() {
+                // Once a limit is reached we stop specializing any further types,
+                // as the compiler won't continue anyway and doing so may exhaust
+                // the available memory.
+                return ins;
+            } else if let Some(diag) = self.check_limits(ins) {
+                self.db.specialization_errors.push(diag);
+                return ins;
+            } else {
+                self.db.specialization_chain.push(ins);
+
+                let id = self.specialize_class(class, key);
+
+                self.db.specialization_chain.pop();
+                id
+            };
 
         // We keep the type arguments so we can perform type checking where
         // necessary during specialization (e.g. when checking if a stack type
@@ -279,6 +480,28 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
         ClassInstance::generic(self.db, new, args)
     }
 
+    fn check_limits(&self, ins: ClassInstance) -> Option<Diagnostic> {
+        let db = &*self.db;
+        let class = ins.instance_of;
+        let limits = db.specialization_limits;
+
+        if class.get(db).specializations.len() >= limits.specializations {
+            Some(Diagnostic::too_many_specializations(
+                db,
+                class,
+                limits.specializations,
+            ))
+        } else if db.specialization_chain.len() >= limits.depth {
+            Some(Diagnostic::recursive_specialization(
+                db,
+                ins,
+                &db.specialization_chain,
+            ))
+        } else {
+            None
+        }
+    }
+
     fn specialize_closure_instance(
         &mut self,
         ins: ClassInstance,
@@ -360,8 +583,21 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
                         .specialize(v)
                     })
                     .collect();
+                let names = old_var.argument_names(self.db).to_vec();
+                let tag = old_var.tag_value(self.db);
+                let new_var = new.new_constructor(self.db, name, args, loc);
+
+                new_var.set_tag_value(self.db, tag);
+
+                if !names.is_empty() {
+                    new_var.set_argument_names(self.db, names);
+                }
+            }
 
-                new.new_constructor(self.db, name, args, loc);
+            if let Some(niche) =
+                niche_layout(self.db, self.interned, mapping, new)
+            {
+                new.set_niche(self.db, niche);
             }
         }
 
@@ -396,12 +632,50 @@ impl<'a, 'b, 'c> TypeSpecializer<'a, 'b, 'c> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arena::Index;
+    use crate::diagnostics::Code;
     use crate::format::format_type;
     use crate::test::{
         any, generic_instance_id, immutable, instance, mutable, new_class,
         new_enum_class, new_parameter, owned, parameter, rigid, uni,
     };
-    use crate::{ClassId, Location, ModuleId, Visibility};
+    use crate::{
+        Class, ClassId, ClassInstance, ClassKind, Location, ModuleId,
+        Visibility,
+    };
+
+    #[test]
+    fn test_uses_type_parameter() {
+        let mut db = Database::new();
+        let param1 = new_parameter(&mut db, "A");
+        let param2 = new_parameter(&mut db, "B");
+        let class = ClassId::array();
+
+        class.new_type_parameter(&mut db, "T".to_string());
+
+        let array = owned(generic_instance_id(
+            &mut db,
+            class,
+            vec![immutable(parameter(param1))],
+        ));
+
+        assert!(uses_type_parameter(&db, owned(parameter(param1)), param1));
+        assert!(uses_type_parameter(&db, mutable(rigid(param1)), param1));
+        assert!(!uses_type_parameter(&db, owned(parameter(param2)), param1));
+        assert!(uses_type_parameter(&db, array, param1));
+        assert!(!uses_type_parameter(&db, array, param2));
+        assert!(!uses_type_parameter(&db, TypeRef::int(), param1));
+    }
+
+    #[test]
+    fn test_borrowed_shape() {
+        assert_eq!(borrowed_shape(Shape::Owned), Some(Shape::Ref));
+        assert_eq!(borrowed_shape(Shape::Mut), Some(Shape::Ref));
+        assert_eq!(borrowed_shape(Shape::Ref), Some(Shape::Ref));
+        assert_eq!(borrowed_shape(Shape::int()), None);
+        assert_eq!(borrowed_shape(Shape::Atomic), None);
+        assert_eq!(borrowed_shape(Shape::String), None);
+    }
 
     #[test]
     fn test_specialize_type() {
@@ -448,6 +722,93 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_specialization_limit() {
+        let mut db = Database::new();
+        let mut interned = InternedTypeArguments::new();
+        let class = ClassId::array();
+        let shapes = HashMap::new();
+        let mut classes = Vec::new();
+
+        class.new_type_parameter(&mut db, "T".to_string());
+        db.set_specialization_limits(SpecializationLimits {
+            specializations: 1,
+            depth: 64,
+        });
+
+        let ints =
+            owned(generic_instance_id(&mut db, class, vec![TypeRef::int()]));
+        let floats =
+            owned(generic_instance_id(&mut db, class, vec![TypeRef::float()]));
+
+        TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+            .specialize(ints);
+
+        let spec =
+            TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+                .specialize(floats);
+        let errors = db.take_specialization_errors();
+
+        assert_eq!(spec, floats);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, Code::SpecializationLimit);
+        assert_eq!(
+            errors[0].message,
+            "the type 'Array' can't be specialized more than 1 times"
+        );
+        assert!(db.take_specialization_errors().is_empty());
+    }
+
+    #[test]
+    fn test_recursive_specialization() {
+        let mut db = Database::new();
+        let mut interned = InternedTypeArguments::new();
+        let class = new_class(&mut db, "Foo");
+        let param = class.new_type_parameter(&mut db, "T".to_string());
+        let shapes = HashMap::new();
+        let mut classes = Vec::new();
+
+        class.set_stack_allocated(&mut db);
+        db.set_specialization_limits(SpecializationLimits {
+            specializations: 64,
+            depth: 4,
+        });
+
+        // This results in the type `Foo[T]` containing a `Foo[Foo[T]]`, which
+        // in turn contains a `Foo[Foo[Foo[T]]]`, and so on.
+        let inner = owned(generic_instance_id(
+            &mut db,
+            class,
+            vec![any(parameter(param))],
+        ));
+        let outer = owned(generic_instance_id(&mut db, class, vec![inner]));
+
+        class.new_field(
+            &mut db,
+            "value".to_string(),
+            0,
+            outer,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        let raw =
+            owned(generic_instance_id(&mut db, class, vec![TypeRef::int()]));
+
+        TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+            .specialize(raw);
+
+        let errors = db.take_specialization_errors();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, Code::SpecializationLimit);
+        assert!(errors[0].message.contains("is specialized recursively"));
+        assert_eq!(errors[0].notes.len(), 4);
+        assert_eq!(errors[0].notes[3].message, "required by 'Foo[Int]'");
+        assert!(class.get(&db).specializations.len() <= 5);
+    }
+
     #[test]
     fn test_specialize_pointer_type() {
         let mut db = Database::new();
@@ -493,7 +854,7 @@ mod tests {
             spec,
             TypeRef::Owned(TypeId::ClassInstance(ClassInstance {
                 instance_of: ClassId(db.number_of_classes() as u32 - 1),
-                type_arguments: 1
+                type_arguments: Index { slot: 1, generation: 2 }
             }))
         );
         assert_eq!(classes.len(), 2);
@@ -597,14 +958,15 @@ mod tests {
         let mut db = Database::new();
         let opt = new_enum_class(&mut db, "Option");
         let opt_param = opt.new_type_parameter(&mut db, "T".to_string());
-
-        opt.new_constructor(
+        let some = opt.new_constructor(
             &mut db,
             "Some".to_string(),
             vec![any(parameter(opt_param))],
             Location::default(),
         );
 
+        some.set_argument_names(&mut db, vec!["value".to_string()]);
+        some.set_tag_value(&mut db, 4);
         opt.new_constructor(
             &mut db,
             "None".to_string(),
@@ -637,6 +999,71 @@ mod tests {
             ins.instance_of().constructor(&db, "Some").unwrap().arguments(&db),
             vec![TypeRef::int()]
         );
+        assert_eq!(
+            ins.instance_of()
+                .constructor(&db, "Some")
+                .unwrap()
+                .argument_names(&db),
+            &["value".to_string()]
+        );
+        assert_eq!(
+            ins.instance_of().constructor(&db, "Some").unwrap().tag_value(&db),
+            4
+        );
+        assert_eq!(
+            ins.instance_of().constructor(&db, "None").unwrap().tag_value(&db),
+            5
+        );
+    }
+
+    #[test]
+    fn test_specialize_enum_class_with_niche() {
+        let mut db = Database::new();
+        let opt = new_enum_class(&mut db, "Option");
+        let thing = new_class(&mut db, "Thing");
+        let opt_param = opt.new_type_parameter(&mut db, "T".to_string());
+        let some = opt.new_constructor(
+            &mut db,
+            "Some".to_string(),
+            vec![any(parameter(opt_param))],
+            Location::default(),
+        );
+        let none = opt.new_constructor(
+            &mut db,
+            "None".to_string(),
+            Vec::new(),
+            Location::default(),
+        );
+
+        let mut interned = InternedTypeArguments::new();
+        let mut classes = Vec::new();
+        let shapes = HashMap::new();
+        let with_ref = owned(generic_instance_id(
+            &mut db,
+            opt,
+            vec![immutable(instance(thing))],
+        ));
+        let with_int =
+            owned(generic_instance_id(&mut db, opt, vec![TypeRef::int()]));
+        let ref_class =
+            TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+                .specialize(with_ref)
+                .class_id(&db)
+                .unwrap();
+        let int_class =
+            TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+                .specialize(with_int)
+                .class_id(&db)
+                .unwrap();
+
+        let niche = ref_class.niche(&db).unwrap();
+
+        assert_eq!(niche.payload, ref_class.constructor(&db, "Some").unwrap());
+        assert_eq!(niche.empty, ref_class.constructor(&db, "None").unwrap());
+        assert_ne!(niche.payload, some);
+        assert_ne!(niche.empty, none);
+        assert_eq!(int_class.niche(&db), None);
+        assert_eq!(opt.niche(&db), None);
     }
 
     #[test]
@@ -721,4 +1148,36 @@ mod tests {
         assert_eq!(immutable, TypeRef::Ref(TypeId::TypeParameter(param)));
         assert_eq!(mutable, TypeRef::Mut(TypeId::TypeParameter(param)));
     }
+
+    #[test]
+    fn test_specialize_union_type_parameter() {
+        let mut db = Database::new();
+        let mut shapes = HashMap::new();
+        let mut classes = Vec::new();
+        let mut interned = InternedTypeArguments::new();
+        let param = new_parameter(&mut db, "A");
+        let union = Class::alloc(
+            &mut db,
+            "Union".to_string(),
+            ClassKind::Union,
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+        let shape =
+            owned(instance(union)).shape(&db, &mut interned, &HashMap::new());
+
+        assert_eq!(shape, Shape::Stack(ClassInstance::new(union)));
+        shapes.insert(param, shape);
+
+        let immutable =
+            TypeSpecializer::new(&mut db, &mut interned, &shapes, &mut classes)
+                .specialize(immutable(parameter(param)));
+
+        // Unions are value types, so references are specialized as owned
+        // values.
+        assert_eq!(immutable, owned(instance(union)));
+        assert!(union.specialization_source(&db).is_some());
+        assert_eq!(classes, vec![union]);
+    }
 }
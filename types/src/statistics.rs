@@ -0,0 +1,143 @@
+//! Statistics about the data stored in a type database.
+//!
+//! These statistics are meant to help diagnose excessive memory usage when
+//! compiling code, such as when generic types are specialized many times. The
+//! amount of memory is an approximation, based on the capacity of the various
+//! collections and the size of their values. Memory allocated by the values
+//! themselves (e.g. the names of methods) isn't included.
+use crate::arena::Slot;
+use crate::{Database, Shape, TypeArguments};
+use std::collections::HashMap;
+use std::mem::size_of;
+
+/// Statistics about a single table in the database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    /// A human readable name of the table.
+    pub name: &'static str,
+
+    /// The number of values stored in the table.
+    pub count: usize,
+
+    /// The approximate number of bytes allocated on the heap for the table.
+    pub bytes: usize,
+}
+
+impl Table {
+    fn for_vec<T>(name: &'static str, values: &Vec<T>) -> Table {
+        Table {
+            name,
+            count: values.len(),
+            bytes: values.capacity() * size_of::<T>(),
+        }
+    }
+}
+
+/// Statistics about all the tables in the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statistics {
+    pub tables: Vec<Table>,
+}
+
+impl Statistics {
+    /// Returns the approximate number of bytes allocated for all tables.
+    pub fn total_bytes(&self) -> usize {
+        self.tables.iter().map(|t| t.bytes).sum()
+    }
+
+    /// Returns the statistics of the table with the given name.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+}
+
+fn map_bytes<V>(map: &HashMap<Vec<Shape>, V>) -> usize {
+    let keys: usize =
+        map.keys().map(|k| k.capacity() * size_of::<Shape>()).sum();
+
+    map.capacity() * size_of::<(Vec<Shape>, V)>() + keys
+}
+
+impl Database {
+    /// Returns statistics about the data stored in this database.
+    pub fn statistics(&self) -> Statistics {
+        let type_arguments = {
+            let args = &self.type_arguments;
+            let heap: usize = args
+                .slots
+                .iter()
+                .filter_map(|s| s.value.as_ref())
+                .map(TypeArguments::heap_bytes)
+                .sum();
+
+            Table {
+                name: "type arguments",
+                count: args.len(),
+                bytes: args.slots.capacity() * size_of::<Slot<TypeArguments>>()
+                    + heap,
+            }
+        };
+
+        let mut specializations =
+            Table { name: "specializations", count: 0, bytes: 0 };
+
+        for class in &self.classes {
+            specializations.count += class.specializations.len();
+            specializations.bytes += map_bytes(&class.specializations);
+        }
+
+        for method in &self.methods {
+            specializations.count += method.specializations.len();
+            specializations.bytes += map_bytes(&method.specializations);
+        }
+
+        Statistics {
+            tables: vec![
+                Table::for_vec("modules", &self.modules),
+                Table::for_vec("traits", &self.traits),
+                Table::for_vec("classes", &self.classes),
+                Table::for_vec("type aliases", &self.type_aliases),
+                Table::for_vec("unions", &self.unions),
+                Table::for_vec("methods", &self.methods),
+                Table::for_vec("fields", &self.fields),
+                Table::for_vec("closures", &self.closures),
+                Table::for_vec("variables", &self.variables),
+                Table::for_vec("constants", &self.constants),
+                Table::for_vec("constructors", &self.constructors),
+                Table::for_vec("type parameters", &self.type_parameters),
+                type_arguments,
+                Table::for_vec("type placeholders", &self.type_placeholders),
+                specializations,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_parameter};
+    use crate::{ClassId, ClassInstance, TypeRef};
+
+    #[test]
+    fn test_statistics() {
+        let mut db = Database::new();
+        let classes = db.number_of_classes();
+        let param = new_parameter(&mut db, "T");
+        let mut args = TypeArguments::new();
+
+        args.assign(param, TypeRef::int());
+        new_class(&mut db, "Foo");
+        ClassInstance::generic(&mut db, ClassId::array(), args);
+
+        let stats = db.statistics();
+        let class_table = stats.table("classes").unwrap();
+
+        assert_eq!(class_table.count, classes + 1);
+        assert!(class_table.bytes > 0);
+        assert_eq!(stats.table("type arguments").unwrap().count, 1);
+        assert_eq!(stats.table("specializations").unwrap().count, 0);
+        assert!(stats.total_bytes() >= class_table.bytes);
+        assert!(stats.table("foo").is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! Suggesting names similar to undefined names.
+//!
+//! When a symbol, method or field is undefined, it's often due to a typo. To
+//! make such errors easier to fix, we suggest the defined names that are most
+//! similar to the undefined name, based on their edit distance.
+use crate::{ClassId, ConstructorId, Database, ModuleId};
+use std::collections::HashSet;
+
+/// The names to consider when producing suggestions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SuggestionScope {
+    /// The symbols defined in and imported into a module, and its methods.
+    Module(ModuleId),
+
+    /// The methods and fields of a class.
+    Class(ClassId),
+
+    /// The names of the arguments of an enum constructor.
+    Constructor(ConstructorId),
+}
+
+/// Returns the Levenshtein distance between two strings.
+///
+/// The distance is computed using characters instead of bytes, such that
+/// names containing multi-byte characters don't produce larger distances.
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut prev: Vec<usize> = (0..=right.len()).collect();
+    let mut cur = vec![0; right.len() + 1];
+
+    for (i, lchar) in left.chars().enumerate() {
+        cur[0] = i + 1;
+
+        for (j, &rchar) in right.iter().enumerate() {
+            let cost = if lchar == rchar { 0 } else { 1 };
+
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[right.len()]
+}
+
+/// Returns the maximum edit distance for a name to be considered similar.
+///
+/// Short names allow fewer edits, as otherwise almost every short name would
+/// be considered similar (e.g. `a` and `b`).
+fn max_distance(name: &str) -> usize {
+    (name.chars().count() / 3).max(1)
+}
+
+impl Database {
+    /// Returns up to `limit` names in the given scope that are similar to the
+    /// given name, ordered from most to least similar.
+    ///
+    /// The name itself is never included, and neither are names generated by
+    /// the compiler (i.e. those starting with a `$`).
+    pub fn similar_symbols(
+        &self,
+        scope: SuggestionScope,
+        name: &str,
+        limit: usize,
+    ) -> Vec<String> {
+        let names: HashSet<String> = match scope {
+            SuggestionScope::Module(id) => id
+                .symbols(self)
+                .into_iter()
+                .map(|(name, _)| name)
+                .chain(
+                    id.class(self)
+                        .methods(self)
+                        .into_iter()
+                        .map(|m| m.name(self).clone()),
+                )
+                .chain(id.extern_methods(self).keys().cloned())
+                .collect(),
+            SuggestionScope::Class(id) => id
+                .methods(self)
+                .into_iter()
+                .map(|m| m.name(self).clone())
+                .chain(id.field_names(self))
+                .collect(),
+            SuggestionScope::Constructor(id) => {
+                id.argument_names(self).iter().cloned().collect()
+            }
+        };
+
+        let max = max_distance(name);
+        let lower = name.to_lowercase();
+        let mut found: Vec<_> = names
+            .into_iter()
+            .filter(|n| n != name && !n.starts_with('$'))
+            .filter_map(|n| {
+                // Differences in casing (e.g. `string` instead of `String`)
+                // are common mistakes, so we compare names case-insensitively.
+                let dist = edit_distance(&lower, &n.to_lowercase());
+
+                (dist <= max).then_some((dist, n))
+            })
+            .collect();
+
+        found.sort();
+        found.into_iter().take(limit).map(|(_, n)| n).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{new_class, new_method, new_module};
+    use crate::{Location, MethodKind, Symbol, TypeRef, Visibility};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("foo", "foo"), 0);
+        assert_eq!(edit_distance("foo", ""), 3);
+        assert_eq!(edit_distance("", "foo"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("lenght", "length"), 2);
+        assert_eq!(edit_distance("ä", "a"), 1);
+    }
+
+    #[test]
+    fn test_similar_symbols_in_class() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Person");
+
+        for name in ["size", "resize", "sizes", "$size", "name"] {
+            let method =
+                new_method(&mut db, module, name, MethodKind::Instance);
+
+            class.add_method(&mut db, name.to_string(), method);
+        }
+
+        class.new_field(
+            &mut db,
+            "sise".to_string(),
+            0,
+            TypeRef::int(),
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+
+        let scope = SuggestionScope::Class(class);
+
+        assert_eq!(
+            db.similar_symbols(scope, "siz", 5),
+            vec!["size".to_string()]
+        );
+        assert_eq!(
+            db.similar_symbols(scope, "size", 5),
+            vec!["sise".to_string(), "sizes".to_string()]
+        );
+        assert_eq!(
+            db.similar_symbols(scope, "size", 1),
+            vec!["sise".to_string()]
+        );
+        assert!(db.similar_symbols(scope, "xyz", 5).is_empty());
+    }
+
+    #[test]
+    fn test_similar_symbols_in_module() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "String");
+        let method =
+            new_method(&mut db, module, "to_strings", MethodKind::Instance);
+        let ext =
+            new_method(&mut db, module, "to_string", MethodKind::Instance);
+
+        module.new_symbol(&mut db, "String".to_string(), Symbol::Class(class));
+        module.add_method(&mut db, "to_strings".to_string(), method);
+        module.add_extern_method(&mut db, ext);
+
+        let scope = SuggestionScope::Module(module);
+
+        assert_eq!(
+            db.similar_symbols(scope, "string", 5),
+            vec!["String".to_string()]
+        );
+        assert_eq!(
+            db.similar_symbols(scope, "to_strin", 5),
+            vec!["to_string".to_string(), "to_strings".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_similar_symbols_in_constructor() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Shape");
+        let cons = class.new_constructor(
+            &mut db,
+            "Rect".to_string(),
+            vec![TypeRef::float(), TypeRef::float()],
+            Location::default(),
+        );
+
+        cons.set_argument_names(
+            &mut db,
+            vec!["width".to_string(), "height".to_string()],
+        );
+
+        let scope = SuggestionScope::Constructor(cons);
+
+        assert_eq!(
+            db.similar_symbols(scope, "widt", 5),
+            vec!["width".to_string()]
+        );
+        assert!(db.similar_symbols(scope, "radius", 5).is_empty());
+    }
+}
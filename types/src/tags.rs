@@ -0,0 +1,228 @@
+//! Tag values of enum constructors.
+//!
+//! Each constructor of an enum is identified at runtime using a tag stored in
+//! the enum's tag field. By default the tags start at zero and increment by
+//! one for every constructor, but constructors may specify their own tag value
+//! (e.g. to match the values of a C enum). Similar to C, constructors without
+//! an explicit tag use the value of the previous constructor plus one.
+use crate::{
+    ClassId, ConstructorId, Database, ForeignType, Sign, TypeId, TypeRef,
+    INT_ID,
+};
+
+/// The integer types considered when deriving the type of a tag field, from
+/// smallest to largest.
+const TAG_SIZES: [u32; 3] = [8, 16, 32];
+
+/// An error produced when checking the tag values of an enum.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TagError {
+    /// The first constructor uses the same tag value as the second (earlier)
+    /// constructor.
+    Duplicate(ConstructorId, ConstructorId),
+
+    /// The tag value of the constructor doesn't fit in the given type.
+    OutOfRange(ConstructorId, TypeRef),
+}
+
+impl ConstructorId {
+    /// Returns the value stored in the tag field for this constructor.
+    pub fn tag_value(self, db: &Database) -> i64 {
+        self.get(db).tag
+    }
+
+    /// Sets the tag value of this constructor, as specified in the source
+    /// code.
+    ///
+    /// Constructors defined after this constructor use this value as the
+    /// starting point for their tag values.
+    pub fn set_tag_value(self, db: &mut Database, value: i64) {
+        self.get_mut(db).tag = value;
+    }
+}
+
+impl ClassId {
+    /// Checks if the tag values of the enum's constructors are unique, and fit
+    /// in the given integer type.
+    pub fn check_enum_tags(
+        self,
+        db: &Database,
+        typ: TypeRef,
+    ) -> Result<(), TagError> {
+        let (min, max) = integer_range(db, typ);
+        let mut seen: Vec<ConstructorId> = Vec::new();
+
+        for id in self.constructors(db) {
+            let tag = id.tag_value(db);
+
+            if let Some(&existing) =
+                seen.iter().find(|c| c.tag_value(db) == tag)
+            {
+                return Err(TagError::Duplicate(id, existing));
+            }
+
+            if (tag as i128) < min || (tag as i128) > max {
+                return Err(TagError::OutOfRange(id, typ));
+            }
+
+            seen.push(id);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the smallest integer type that can store the tag values of the
+    /// enum's constructors.
+    ///
+    /// If none of the tag values are negative an unsigned type is used,
+    /// otherwise a signed type is used. Tags are limited to 32 bits, such that
+    /// they're compatible with C enums.
+    pub fn enum_tag_type(self, db: &Database) -> Result<TypeRef, TagError> {
+        let sign = if self.constructors(db).iter().any(|c| c.tag_value(db) < 0)
+        {
+            Sign::Signed
+        } else {
+            Sign::Unsigned
+        };
+        let mut error = None;
+
+        for size in TAG_SIZES {
+            let typ = TypeRef::int_with_sign(size, sign);
+
+            match self.check_enum_tags(db, typ) {
+                Ok(_) => return Ok(typ),
+                Err(err @ TagError::OutOfRange(_, _)) => error = Some(err),
+                Err(err) => return Err(err),
+            }
+        }
+
+        // The tags don't fit in any of the types, so we report the error
+        // produced for the largest type.
+        Err(error.unwrap())
+    }
+}
+
+/// Returns the minimum and maximum values of an integer type.
+fn integer_range(db: &Database, typ: TypeRef) -> (i128, i128) {
+    match typ.type_id(db) {
+        Ok(TypeId::Foreign(ForeignType::Int(size, Sign::Signed))) => {
+            let max = (1_i128 << (size - 1)) - 1;
+
+            (-max - 1, max)
+        }
+        Ok(TypeId::Foreign(ForeignType::Int(size, Sign::Unsigned))) => {
+            (0, (1_i128 << size) - 1)
+        }
+        Ok(TypeId::ClassInstance(ins)) if ins.instance_of().0 == INT_ID => {
+            (i64::MIN as i128, i64::MAX as i128)
+        }
+        _ => (0, -1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::new_enum_class;
+    use crate::Location;
+
+    fn new_constructor(
+        db: &mut Database,
+        class: ClassId,
+        name: &str,
+        tag: Option<i64>,
+    ) -> ConstructorId {
+        let id = class.new_constructor(
+            db,
+            name.to_string(),
+            Vec::new(),
+            Location::default(),
+        );
+
+        if let Some(val) = tag {
+            id.set_tag_value(db, val);
+        }
+
+        id
+    }
+
+    #[test]
+    fn test_tag_value() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Color");
+        let red = new_constructor(&mut db, class, "Red", None);
+        let green = new_constructor(&mut db, class, "Green", Some(10));
+        let blue = new_constructor(&mut db, class, "Blue", None);
+
+        assert_eq!(red.tag_value(&db), 0);
+        assert_eq!(green.tag_value(&db), 10);
+        assert_eq!(blue.tag_value(&db), 11);
+        assert_eq!(blue.id(&db), 2);
+    }
+
+    #[test]
+    fn test_check_enum_tags() {
+        let mut db = Database::new();
+        let class = new_enum_class(&mut db, "Color");
+        let red = new_constructor(&mut db, class, "Red", Some(1));
+        let green = new_constructor(&mut db, class, "Green", Some(300));
+        let u8 = TypeRef::foreign_unsigned_int(8);
+
+        assert_eq!(class.check_enum_tags(&db, TypeRef::int()), Ok(()));
+        assert_eq!(
+            class.check_enum_tags(&db, u8),
+            Err(TagError::OutOfRange(green, u8))
+        );
+
+        let blue = new_constructor(&mut db, class, "Blue", Some(1));
+
+        assert_eq!(
+            class.check_enum_tags(&db, TypeRef::int()),
+            Err(TagError::Duplicate(blue, red))
+        );
+    }
+
+    #[test]
+    fn test_enum_tag_type() {
+        let mut db = Database::new();
+        let small = new_enum_class(&mut db, "Small");
+        let large = new_enum_class(&mut db, "Large");
+        let signed = new_enum_class(&mut db, "Signed");
+        let invalid = new_enum_class(&mut db, "Invalid");
+        let dupe = new_enum_class(&mut db, "Duplicate");
+
+        new_constructor(&mut db, small, "A", None);
+        new_constructor(&mut db, small, "B", Some(255));
+        new_constructor(&mut db, large, "A", Some(70_000));
+        new_constructor(&mut db, signed, "A", Some(-1));
+        new_constructor(&mut db, signed, "B", Some(200));
+
+        let too_large = new_constructor(&mut db, invalid, "A", Some(1 << 40));
+        let dupe_a = new_constructor(&mut db, dupe, "A", Some(1_000_000));
+        let dupe_b = new_constructor(&mut db, dupe, "B", Some(1_000_000));
+
+        assert_eq!(
+            small.enum_tag_type(&db),
+            Ok(TypeRef::foreign_unsigned_int(8))
+        );
+        assert_eq!(
+            large.enum_tag_type(&db),
+            Ok(TypeRef::foreign_unsigned_int(32))
+        );
+        assert_eq!(
+            signed.enum_tag_type(&db),
+            Ok(TypeRef::foreign_signed_int(16))
+        );
+        assert_eq!(
+            invalid.enum_tag_type(&db),
+            Err(TagError::OutOfRange(
+                too_large,
+                TypeRef::foreign_unsigned_int(32)
+            ))
+        );
+        assert_eq!(
+            dupe.enum_tag_type(&db),
+            Err(TagError::Duplicate(dupe_b, dupe_a))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! The platform the program is compiled for.
+//!
+//! The type database used to assume the program is compiled for the host it's
+//! compiled on, such as by hardcoding the size of pointers. To allow
+//! type-checking of programs compiled for a different platform, these
+//! properties are instead taken from the `Target` stored in the database.
+//!
+//! The operating system and architecture use the same names as the compiler's
+//! target triples (e.g. `linux` and `amd64`), and are defined as build tags
+//! when setting the target, such that conditional symbols can depend on them.
+use crate::{Database, ForeignType};
+
+/// The name of the x86-64 architecture.
+const AMD64: &str = "amd64";
+
+/// The order in which the bytes of an integer are stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub fn native() -> Endianness {
+        if cfg!(target_endian = "big") {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+}
+
+/// The platform the program is compiled for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    /// The name of the operating system, such as `linux`.
+    pub os: String,
+
+    /// The name of the CPU architecture, such as `amd64`.
+    pub arch: String,
+
+    /// The size of a pointer, in bits.
+    pub pointer_width: u32,
+    pub endianness: Endianness,
+}
+
+impl Target {
+    pub fn new(
+        os: String,
+        arch: String,
+        pointer_width: u32,
+        endianness: Endianness,
+    ) -> Target {
+        Target { os, arch, pointer_width, endianness }
+    }
+
+    /// Returns the target for the platform the compiler runs on.
+    pub fn native() -> Target {
+        let os = if cfg!(target_os = "freebsd") {
+            "freebsd"
+        } else if cfg!(target_os = "macos") {
+            "mac"
+        } else {
+            "linux"
+        };
+        let arch = if cfg!(target_arch = "aarch64") { "arm64" } else { AMD64 };
+
+        Target::new(
+            os.to_string(),
+            arch.to_string(),
+            usize::BITS,
+            Endianness::native(),
+        )
+    }
+
+    /// Returns the size and alignment of a pointer, in bytes.
+    pub fn pointer_size(&self) -> u32 {
+        self.pointer_width / 8
+    }
+
+    /// Returns the build tags defined for this target.
+    ///
+    /// Besides the names of the operating system and architecture, this
+    /// includes the names of the families of operating systems the target
+    /// belongs to (e.g. `unix`).
+    pub fn build_tags(&self) -> Vec<String> {
+        let mut tags = vec![self.os.clone(), self.arch.clone()];
+
+        match self.os.as_str() {
+            "freebsd" => {
+                tags.push("bsd".to_string());
+                tags.push("unix".to_string());
+            }
+            "linux" | "mac" => tags.push("unix".to_string()),
+            _ => {}
+        }
+
+        tags
+    }
+
+    /// Returns `true` if values of the given foreign type can be used on this
+    /// target.
+    pub fn supports_foreign_type(&self, typ: ForeignType) -> bool {
+        match typ {
+            // x87 extended precision floats only exist on x86 platforms.
+            ForeignType::Float(80) => self.arch == AMD64,
+            _ => true,
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Target {
+        Target::native()
+    }
+}
+
+impl Database {
+    /// Sets the target to compile for, and defines its build tags.
+    pub fn set_target(&mut self, target: Target) {
+        for tag in target.build_tags() {
+            self.define_build_tag(tag);
+        }
+
+        self.pointer_size = target.pointer_size();
+        self.target = target;
+    }
+
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Returns the size and alignment of a pointer for the target, in bytes.
+    pub fn pointer_size(&self) -> u32 {
+        self.pointer_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(os: &str, arch: &str, pointer_width: u32) -> Target {
+        Target::new(
+            os.to_string(),
+            arch.to_string(),
+            pointer_width,
+            Endianness::Little,
+        )
+    }
+
+    #[test]
+    fn test_target_pointer_size() {
+        assert_eq!(target("linux", "amd64", 64).pointer_size(), 8);
+        assert_eq!(target("linux", "arm", 32).pointer_size(), 4);
+    }
+
+    #[test]
+    fn test_target_build_tags() {
+        assert_eq!(
+            target("linux", "amd64", 64).build_tags(),
+            vec!["linux".to_string(), "amd64".to_string(), "unix".to_string()]
+        );
+        assert_eq!(
+            target("freebsd", "arm64", 64).build_tags(),
+            vec![
+                "freebsd".to_string(),
+                "arm64".to_string(),
+                "bsd".to_string(),
+                "unix".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_supports_foreign_type() {
+        let amd64 = target("linux", "amd64", 64);
+        let arm64 = target("linux", "arm64", 64);
+
+        assert!(amd64.supports_foreign_type(ForeignType::Float(80)));
+        assert!(!arm64.supports_foreign_type(ForeignType::Float(80)));
+        assert!(arm64.supports_foreign_type(ForeignType::Float(64)));
+    }
+
+    #[test]
+    fn test_database_set_target() {
+        let mut db = Database::new();
+
+        db.set_target(target("mac", "arm64", 64));
+
+        assert_eq!(db.target().os, "mac");
+        assert_eq!(db.pointer_size(), 8);
+        assert!(db.is_build_tag_defined("mac"));
+        assert!(db.is_build_tag_defined("arm64"));
+        assert!(db.is_build_tag_defined("unix"));
+        assert!(!db.is_build_tag_defined("linux"));
+
+        db.set_target(target("linux", "arm", 32));
+
+        assert_eq!(db.pointer_size(), 4);
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{
     Class, ClassId, ClassInstance, ClassKind, ClosureId, Database, Location,
-    Module, ModuleId, ModuleName, Trait, TraitId, TraitImplementation,
-    TraitInstance, TypeArguments, TypeBounds, TypeId, TypeParameter,
-    TypeParameterId, TypePlaceholderId, TypeRef, Visibility,
+    Method, MethodId, MethodKind, Module, ModuleId, ModuleName, Symbol, Trait,
+    TraitId, TraitImplementation, TraitInstance, TypeAlias, TypeAliasId,
+    TypeAliasInstance, TypeArguments, TypeBounds, TypeId, TypeParameter,
+    TypeParameterId, TypePlaceholderId, TypeRef, Visibility, FUTURE_CLASS,
+    MAP_CLASS, MAP_MODULE, OPTION_CLASS, OPTION_MODULE, PROMISE_CLASS,
+    RESULT_CLASS, RESULT_MODULE, SET_CLASS, SET_MODULE, SYNC_MODULE,
+    WEAK_CLASS, WEAK_MODULE,
 };
 use std::path::PathBuf;
 
@@ -64,6 +68,114 @@ pub(crate) fn new_trait(db: &mut Database, name: &str) -> TraitId {
     )
 }
 
+pub(crate) fn new_type_alias(
+    db: &mut Database,
+    name: &str,
+    aliased: TypeRef,
+) -> TypeAliasId {
+    let id = TypeAlias::alloc(
+        db,
+        name.to_string(),
+        Visibility::Public,
+        ModuleId(0),
+        Location::default(),
+    );
+
+    id.set_aliased_type(db, aliased);
+    id
+}
+
+pub(crate) fn alias_instance_id(
+    db: &mut Database,
+    alias: TypeAliasId,
+    arguments: Vec<TypeRef>,
+) -> TypeId {
+    if !alias.is_generic(db) {
+        return TypeId::TypeAlias(TypeAliasInstance::new(alias));
+    }
+
+    let mut args = TypeArguments::new();
+
+    for (param, arg) in alias.type_parameters(db).into_iter().zip(arguments) {
+        args.assign(param, arg);
+    }
+
+    TypeId::TypeAlias(TypeAliasInstance::generic(db, alias, args))
+}
+
+/// Defines the `Option` and `Result` types in their standard library modules.
+pub(crate) fn define_option_and_result(
+    db: &mut Database,
+) -> (ClassId, ClassId) {
+    let opt_mod = new_module(db, OPTION_MODULE);
+    let res_mod = new_module(db, RESULT_MODULE);
+    let opt = new_enum_class(db, OPTION_CLASS);
+    let res = new_enum_class(db, RESULT_CLASS);
+
+    opt.new_type_parameter(db, "T".to_string());
+    res.new_type_parameter(db, "T".to_string());
+    res.new_type_parameter(db, "E".to_string());
+    opt_mod.new_symbol(db, OPTION_CLASS.to_string(), Symbol::Class(opt));
+    res_mod.new_symbol(db, RESULT_CLASS.to_string(), Symbol::Class(res));
+    (opt, res)
+}
+
+/// Defines the `Map` and `Set` types in their standard library modules.
+pub(crate) fn define_map_and_set(db: &mut Database) -> (ClassId, ClassId) {
+    let map_mod = new_module(db, MAP_MODULE);
+    let set_mod = new_module(db, SET_MODULE);
+    let map = new_class(db, MAP_CLASS);
+    let set = new_class(db, SET_CLASS);
+
+    map.new_type_parameter(db, "K".to_string());
+    map.new_type_parameter(db, "V".to_string());
+    set.new_type_parameter(db, "V".to_string());
+    map_mod.new_symbol(db, MAP_CLASS.to_string(), Symbol::Class(map));
+    set_mod.new_symbol(db, SET_CLASS.to_string(), Symbol::Class(set));
+    (map, set)
+}
+
+/// Defines the `Future` and `Promise` types in their standard library module.
+pub(crate) fn define_future_and_promise(
+    db: &mut Database,
+) -> (ClassId, ClassId) {
+    let sync_mod = new_module(db, SYNC_MODULE);
+    let future = new_class(db, FUTURE_CLASS);
+    let promise = new_class(db, PROMISE_CLASS);
+
+    future.new_type_parameter(db, "T".to_string());
+    promise.new_type_parameter(db, "T".to_string());
+    sync_mod.new_symbol(db, FUTURE_CLASS.to_string(), Symbol::Class(future));
+    sync_mod.new_symbol(db, PROMISE_CLASS.to_string(), Symbol::Class(promise));
+    (future, promise)
+}
+
+/// Defines the `Weak` type in its standard library module.
+pub(crate) fn define_weak(db: &mut Database) -> ClassId {
+    let weak_mod = new_module(db, WEAK_MODULE);
+    let weak = new_class(db, WEAK_CLASS);
+
+    weak.new_type_parameter(db, "T".to_string());
+    weak_mod.new_symbol(db, WEAK_CLASS.to_string(), Symbol::Class(weak));
+    weak
+}
+
+pub(crate) fn new_method(
+    db: &mut Database,
+    module: ModuleId,
+    name: &str,
+    kind: MethodKind,
+) -> MethodId {
+    Method::alloc(
+        db,
+        module,
+        Location::default(),
+        name.to_string(),
+        Visibility::Public,
+        kind,
+    )
+}
+
 pub(crate) fn new_parameter(db: &mut Database, name: &str) -> TypeParameterId {
     TypeParameter::alloc(db, name.to_string())
 }
@@ -73,9 +185,16 @@ pub(crate) fn implement(
     instance: TraitInstance,
     class: ClassId,
 ) {
+    let module = class.module(db);
+
     class.add_trait_implementation(
         db,
-        TraitImplementation { instance, bounds: TypeBounds::new() },
+        TraitImplementation {
+            instance,
+            bounds: TypeBounds::new(),
+            module,
+            location: Location::default(),
+        },
     );
 }
 
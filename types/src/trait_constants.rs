@@ -0,0 +1,260 @@
+//! Constants defined by traits and the classes implementing them.
+//!
+//! Traits can declare constants in the same way they declare methods: a
+//! required constant only has a type and must be defined by every class that
+//! implements the trait, while a default constant also has a value and is used
+//! when the class doesn't define the constant itself. This allows traits such
+//! as `Bounded` to expose values such as `Int.MAX`, and allows generic code to
+//! refer to these values through the trait.
+//!
+//! Trait and class constants don't define symbols in the module they're
+//! defined in. Instead, they're resolved through the type that defines them,
+//! and produce a `Symbol::Constant` when found.
+use crate::{
+    ClassId, ConstantId, Database, Symbol, TraitId, TraitInstance,
+    TypeParameterId,
+};
+
+impl TraitId {
+    pub fn add_default_constant(
+        self,
+        db: &mut Database,
+        name: String,
+        constant: ConstantId,
+    ) {
+        self.get_mut(db).default_constants.insert(name, constant);
+    }
+
+    pub fn add_required_constant(
+        self,
+        db: &mut Database,
+        name: String,
+        constant: ConstantId,
+    ) {
+        self.get_mut(db).required_constants.insert(name, constant);
+    }
+
+    pub fn default_constants(self, db: &Database) -> Vec<ConstantId> {
+        self.get(db).default_constants.values().cloned().collect()
+    }
+
+    pub fn required_constants(self, db: &Database) -> Vec<ConstantId> {
+        self.get(db).required_constants.values().cloned().collect()
+    }
+
+    /// Returns the constant with the given name, defined by this trait or any
+    /// of its required traits.
+    pub fn constant(self, db: &Database, name: &str) -> Option<ConstantId> {
+        let typ = self.get(db);
+
+        typ.default_constants
+            .get(name)
+            .or_else(|| typ.required_constants.get(name))
+            .cloned()
+            .or_else(|| {
+                typ.required_traits
+                    .iter()
+                    .find_map(|req| req.instance_of.constant(db, name))
+            })
+    }
+
+    /// Returns the default constant with the given name, defined by this trait
+    /// or any of its required traits.
+    pub fn default_constant(
+        self,
+        db: &Database,
+        name: &str,
+    ) -> Option<ConstantId> {
+        let typ = self.get(db);
+
+        typ.default_constants.get(name).cloned().or_else(|| {
+            typ.required_traits
+                .iter()
+                .find_map(|req| req.instance_of.default_constant(db, name))
+        })
+    }
+
+    /// Returns the names of the constants an implementation of this trait
+    /// must define.
+    ///
+    /// This includes the constants required by any of the required traits,
+    /// unless this trait or one of its required traits provides a default
+    /// value for the constant.
+    pub fn required_constant_names(self, db: &Database) -> Vec<String> {
+        let mut names: Vec<String> =
+            self.get(db).required_constants.keys().cloned().collect();
+
+        for req in &self.get(db).required_traits {
+            for name in req.instance_of.required_constant_names(db) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        names.retain(|n| self.default_constant(db, n).is_none());
+        names
+    }
+}
+
+impl TraitInstance {
+    pub fn constant(self, db: &Database, name: &str) -> Option<ConstantId> {
+        self.instance_of.constant(db, name)
+    }
+}
+
+impl TypeParameterId {
+    /// Returns the constant with the given name, defined by any of the
+    /// requirements of this type parameter.
+    pub fn constant(self, db: &Database, name: &str) -> Option<ConstantId> {
+        self.get(db).requirements.iter().find_map(|r| r.constant(db, name))
+    }
+}
+
+impl ClassId {
+    pub fn add_constant(
+        self,
+        db: &mut Database,
+        name: String,
+        constant: ConstantId,
+    ) {
+        self.get_mut(db).constants.insert(name, constant);
+    }
+
+    pub fn constants(self, db: &Database) -> Vec<ConstantId> {
+        self.get(db).constants.values().cloned().collect()
+    }
+
+    /// Returns the constant with the given name.
+    ///
+    /// If the class doesn't define the constant itself, the default constants
+    /// of the traits it implements are used instead.
+    pub fn constant(self, db: &Database, name: &str) -> Option<ConstantId> {
+        let typ = self.get(db);
+
+        typ.constants.get(name).cloned().or_else(|| {
+            typ.implemented_traits.values().find_map(|imp| {
+                imp.instance.instance_of.default_constant(db, name)
+            })
+        })
+    }
+
+    /// Returns the constant with the given name as a symbol.
+    pub fn constant_symbol(self, db: &Database, name: &str) -> Option<Symbol> {
+        self.constant(db, name).map(Symbol::Constant)
+    }
+
+    /// Returns the names of the constants required by the trait that this
+    /// class doesn't define.
+    pub fn missing_trait_constants(
+        self,
+        db: &Database,
+        instance: TraitInstance,
+    ) -> Vec<String> {
+        instance
+            .instance_of
+            .required_constant_names(db)
+            .into_iter()
+            .filter(|name| !self.get(db).constants.contains_key(name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostic;
+    use crate::test::{
+        implement, new_class, new_module, new_parameter, new_trait,
+    };
+    use crate::{Constant, Location, ModuleId, TypeRef, Visibility};
+
+    fn new_constant(db: &mut Database, name: &str) -> ConstantId {
+        Constant::alloc_associated(
+            db,
+            ModuleId(0),
+            Location::default(),
+            name.to_string(),
+            Visibility::Public,
+            TypeRef::int(),
+        )
+    }
+
+    #[test]
+    fn test_trait_constants() {
+        let mut db = Database::new();
+
+        new_module(&mut db, "std");
+
+        let bounded = new_trait(&mut db, "Bounded");
+        let number = new_trait(&mut db, "Number");
+        let min = new_constant(&mut db, "MIN");
+        let max = new_constant(&mut db, "MAX");
+        let zero = new_constant(&mut db, "ZERO");
+
+        bounded.add_required_constant(&mut db, "MIN".to_string(), min);
+        bounded.add_required_constant(&mut db, "MAX".to_string(), max);
+        number.add_default_constant(&mut db, "ZERO".to_string(), zero);
+        number.add_required_trait(&mut db, TraitInstance::new(bounded));
+
+        assert_eq!(bounded.required_constants(&db), vec![min, max]);
+        assert_eq!(number.default_constants(&db), vec![zero]);
+        assert_eq!(number.constant(&db, "MAX"), Some(max));
+        assert_eq!(number.constant(&db, "ZERO"), Some(zero));
+        assert_eq!(bounded.constant(&db, "ZERO"), None);
+        assert_eq!(number.default_constant(&db, "MAX"), None);
+        assert_eq!(
+            number.required_constant_names(&db),
+            vec!["MIN".to_string(), "MAX".to_string()]
+        );
+        assert_eq!(TraitInstance::new(number).constant(&db, "MIN"), Some(min));
+    }
+
+    #[test]
+    fn test_class_constants() {
+        let mut db = Database::new();
+
+        new_module(&mut db, "std");
+
+        let bounded = new_trait(&mut db, "Bounded");
+        let class = new_class(&mut db, "Int");
+        let param = new_parameter(&mut db, "T");
+        let trait_min = new_constant(&mut db, "MIN");
+        let trait_max = new_constant(&mut db, "MAX");
+        let max = new_constant(&mut db, "MAX");
+        let ins = TraitInstance::new(bounded);
+
+        bounded.add_required_constant(&mut db, "MIN".to_string(), trait_min);
+        bounded.add_default_constant(&mut db, "MAX".to_string(), trait_max);
+        param.add_requirements(&mut db, vec![ins]);
+
+        assert_eq!(class.constant(&db, "MAX"), None);
+
+        implement(&mut db, ins, class);
+
+        assert_eq!(class.constant(&db, "MAX"), Some(trait_max));
+        assert_eq!(class.missing_trait_constants(&db, ins), vec!["MIN"]);
+        assert_eq!(
+            Diagnostic::missing_trait_constant(
+                &db,
+                ins,
+                "MIN",
+                ModuleId(0),
+                Location::default()
+            )
+            .message,
+            "the constant 'MIN' of trait 'Bounded' must be defined"
+        );
+
+        class.add_constant(&mut db, "MAX".to_string(), max);
+
+        assert_eq!(class.constants(&db), vec![max]);
+        assert_eq!(class.constant(&db, "MAX"), Some(max));
+        assert_eq!(
+            class.constant_symbol(&db, "MAX"),
+            Some(Symbol::Constant(max))
+        );
+        assert_eq!(class.constant(&db, "MIN"), None);
+        assert_eq!(param.constant(&db, "MIN"), Some(trait_min));
+    }
+}
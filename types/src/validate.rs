@@ -0,0 +1,322 @@
+//! Checking the internal consistency of a type database.
+//!
+//! Many parts of the compiler assume certain invariants hold, such as the
+//! index of a field matching its position in its class. Bugs that break these
+//! invariants tend to surface much later, such as when generating code, making
+//! them difficult to debug. `Database::validate()` checks these invariants
+//! explicitly, such that such bugs are detected right after the compiler phase
+//! that introduced them.
+//!
+//! Validating the database requires iterating over all its data, so the
+//! compiler only does this for debug builds.
+use crate::arena::Index;
+use crate::{
+    ClassId, Database, FieldId, MethodId, TypeId, TypeRef, CONSTRUCTORS_LIMIT,
+    FIELDS_LIMIT,
+};
+use std::fmt;
+
+/// A broken invariant of the type database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The index of a field doesn't match its position in its class.
+    FieldIndex(ClassId, FieldId),
+
+    /// A class defines more fields than supported.
+    TooManyFields(ClassId),
+
+    /// A class defines more constructors than supported.
+    TooManyConstructors(ClassId),
+
+    /// A class is a specialization of a class that isn't a generic class, or
+    /// that doesn't list the class as one of its specializations.
+    InvalidSpecializationSource(ClassId),
+
+    /// A type refers to type arguments that don't exist (anymore).
+    ///
+    /// The values are the name of the table storing the type (e.g. "methods"),
+    /// and the index of the value in that table.
+    DanglingTypeArguments(&'static str, usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::FieldIndex(class, field) => write!(
+                f,
+                "field {} of class {} is stored at the wrong index",
+                field.0, class.0
+            ),
+            Violation::TooManyFields(class) => {
+                write!(f, "class {} defines too many fields", class.0)
+            }
+            Violation::TooManyConstructors(class) => {
+                write!(f, "class {} defines too many constructors", class.0)
+            }
+            Violation::InvalidSpecializationSource(class) => write!(
+                f,
+                "class {} has an invalid specialization source",
+                class.0
+            ),
+            Violation::DanglingTypeArguments(table, index) => write!(
+                f,
+                "value {} in table '{}' refers to undefined type arguments",
+                index, table
+            ),
+        }
+    }
+}
+
+/// The result of validating a database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The broken invariants, in the order in which they're found.
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, violation) in self.violations.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", violation)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `true` if the type refers to type arguments that aren't stored in
+/// the database.
+///
+/// Instances of non-generic types use `Index::NONE`, so such indexes are
+/// always valid.
+fn has_dangling_type_arguments(db: &Database, typ: TypeRef) -> bool {
+    let index = match typ.type_id(db) {
+        Ok(TypeId::ClassInstance(ins)) => ins.type_arguments,
+        Ok(TypeId::TraitInstance(ins)) => ins.type_arguments,
+        Ok(TypeId::TypeAlias(ins)) => ins.type_arguments,
+        _ => return false,
+    };
+
+    index != Index::NONE && db.type_arguments.get(index).is_none()
+}
+
+impl Database {
+    /// Checks the internal invariants of the database, returning a report of
+    /// the invariants that don't hold.
+    ///
+    /// `Database::compact()` removes all type arguments, so validating the
+    /// database after compacting it reports dangling type arguments.
+    pub fn validate(&self) -> Report {
+        let mut report = Report::default();
+
+        for idx in 0..self.classes.len() {
+            self.validate_class(ClassId(idx as u32), &mut report);
+        }
+
+        self.validate_type_arguments(&mut report);
+        report
+    }
+
+    fn validate_class(&self, class: ClassId, report: &mut Report) {
+        let data = class.get(self);
+
+        for fields in [&data.fields, &data.computed_fields] {
+            for (index, &field) in fields.values().enumerate() {
+                if field.index(self) != index {
+                    report.violations.push(Violation::FieldIndex(class, field));
+                }
+            }
+        }
+
+        if data.fields.len() > FIELDS_LIMIT {
+            report.violations.push(Violation::TooManyFields(class));
+        }
+
+        if data.constructors.len() > CONSTRUCTORS_LIMIT {
+            report.violations.push(Violation::TooManyConstructors(class));
+        }
+
+        // Classes that don't need to be specialized use themselves as the
+        // source, marking them as processed.
+        match data.specialization_source {
+            Some(source) if source != class => {
+                let orig = source.get(self);
+                let valid = source.is_generic(self)
+                    && orig.specialization_source.map_or(true, |s| s == source)
+                    && orig.specializations.values().any(|&c| c == class);
+
+                if !valid {
+                    report
+                        .violations
+                        .push(Violation::InvalidSpecializationSource(class));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_type_arguments(&self, report: &mut Report) {
+        let mut check = |table, index, typ| {
+            if has_dangling_type_arguments(self, typ) {
+                report
+                    .violations
+                    .push(Violation::DanglingTypeArguments(table, index));
+            }
+        };
+
+        for (idx, slot) in self.type_arguments.slots.iter().enumerate() {
+            if let Some(targs) = &slot.value {
+                targs.values().for_each(|t| check("type arguments", idx, t));
+            }
+        }
+
+        for (idx, field) in self.fields.iter().enumerate() {
+            check("fields", idx, field.value_type);
+        }
+
+        for (idx, method) in self.methods.iter().enumerate() {
+            check("methods", idx, method.return_type);
+
+            for arg in MethodId(idx as u32).arguments(self) {
+                check("methods", idx, arg.value_type);
+            }
+        }
+
+        for (idx, constant) in self.constants.iter().enumerate() {
+            check("constants", idx, constant.value_type);
+        }
+
+        for (idx, class) in self.classes.iter().enumerate() {
+            for imp in class.implemented_traits.values() {
+                check(
+                    "classes",
+                    idx,
+                    TypeRef::Owned(TypeId::TraitInstance(imp.instance)),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{generic_instance_id, new_class, new_module, owned};
+    use crate::{ClassInstance, Location, ModuleId, Visibility};
+
+    #[test]
+    fn test_database_validate_with_valid_database() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Person");
+
+        new_module(&mut db, "foo");
+        class.new_field(
+            &mut db,
+            "name".to_string(),
+            0,
+            TypeRef::string(),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        let report = db.validate();
+
+        assert!(report.is_valid());
+        assert_eq!(report.to_string(), "");
+    }
+
+    #[test]
+    fn test_database_validate_with_invalid_field_index() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Person");
+        let field = class.new_field(
+            &mut db,
+            "name".to_string(),
+            1,
+            TypeRef::string(),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+        let report = db.validate();
+
+        assert_eq!(
+            report.violations,
+            vec![Violation::FieldIndex(class, field)]
+        );
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "field {} of class {} is stored at the wrong index",
+                field.0, class.0
+            )
+        );
+    }
+
+    #[test]
+    fn test_database_validate_with_invalid_specialization_source() {
+        let mut db = Database::new();
+        let generic = new_class(&mut db, "Array");
+        let regular = new_class(&mut db, "Regular");
+        let spec1 = new_class(&mut db, "Array[Int]");
+        let spec2 = new_class(&mut db, "Regular[Int]");
+
+        generic.new_type_parameter(&mut db, "T".to_string());
+        generic.get_mut(&mut db).specializations.insert(Vec::new(), spec1);
+        spec1.set_specialization_source(&mut db, generic);
+        spec2.set_specialization_source(&mut db, regular);
+        regular.set_specialization_source(&mut db, regular);
+
+        assert_eq!(
+            db.validate().violations,
+            vec![Violation::InvalidSpecializationSource(spec2)]
+        );
+    }
+
+    #[test]
+    fn test_database_validate_with_dangling_type_arguments() {
+        let mut db = Database::new();
+        let class = new_class(&mut db, "Array");
+
+        class.new_type_parameter(&mut db, "T".to_string());
+
+        let ins = generic_instance_id(&mut db, class, vec![TypeRef::int()]);
+        let index = match ins {
+            TypeId::ClassInstance(ins) => ins.type_arguments,
+            _ => unreachable!(),
+        };
+        let field = class.new_field(
+            &mut db,
+            "values".to_string(),
+            0,
+            owned(ins),
+            Visibility::Public,
+            ModuleId(0),
+            Location::default(),
+        );
+
+        assert!(db.validate().is_valid());
+
+        db.type_arguments.remove(index);
+
+        assert_eq!(
+            db.validate().violations,
+            vec![Violation::DanglingTypeArguments("fields", field.0 as usize)]
+        );
+        assert!(!has_dangling_type_arguments(
+            &db,
+            owned(TypeId::ClassInstance(ClassInstance::new(class)))
+        ));
+    }
+}
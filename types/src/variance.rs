@@ -0,0 +1,527 @@
+//! Inference of the variance of type parameters.
+//!
+//! The variance of a class or trait type parameter is derived from the
+//! positions the parameter is used in:
+//!
+//! - Fields can be assigned new values, so parameters used in fields are
+//!   invariant.
+//! - The arguments of enum constructors can't be changed once created, so
+//!   these are covariant positions.
+//! - The return types of instance methods are covariant positions, while the
+//!   argument types are contravariant positions.
+//! - When a type parameter is used as a type argument of another generic type,
+//!   the variance of the position is combined with the variance of the
+//!   parameter the argument is assigned to.
+//!
+//! Static methods don't operate on instances of a type, and thus don't affect
+//! the variance of its type parameters.
+//!
+//! Because the variance of one type parameter may depend on the variance of
+//! other type parameters (including itself), the inference is repeated until
+//! the variances no longer change. Type parameters that aren't used at all are
+//! covariant.
+use crate::{
+    Block, ClassId, Database, MethodId, TraitId, TypeArguments, TypeId,
+    TypeParameterId, TypeRef, Variance,
+};
+use std::collections::HashMap;
+
+/// A type parameter of which the explicitly specified variance is less strict
+/// than the variance inferred from its usage.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Conflict {
+    /// The type parameter that's annotated.
+    pub parameter: TypeParameterId,
+
+    /// The variance inferred from its usage.
+    pub inferred: Variance,
+}
+
+/// Infers the variance of the type parameters of all classes and traits.
+///
+/// Type parameters with an explicit variance retain that variance. If such a
+/// variance is less strict than the inferred variance, a `Conflict` is
+/// returned for the parameter.
+pub fn infer_all(db: &mut Database) -> Vec<Conflict> {
+    let mut inference = Inference::new(db);
+
+    inference.run();
+
+    let Inference { variances, params, .. } = inference;
+    let mut conflicts = Vec::new();
+
+    for param in params {
+        let inferred = variances[&param];
+
+        if param.has_explicit_variance(db) {
+            let explicit = param.variance(db);
+
+            match inferred {
+                Some(inferred)
+                    if explicit != Variance::Invariant
+                        && explicit != inferred =>
+                {
+                    conflicts.push(Conflict { parameter: param, inferred });
+                }
+                _ => {}
+            }
+        } else {
+            // Parameters that aren't used anywhere can be treated as either
+            // covariant or contravariant, and we pick the former.
+            param.set_inferred_variance(
+                db,
+                inferred.unwrap_or(Variance::Covariant),
+            );
+        }
+    }
+
+    conflicts
+}
+
+enum Owner {
+    Class(ClassId),
+    Trait(TraitId),
+}
+
+struct Inference<'a> {
+    db: &'a Database,
+
+    /// The types to infer the variance of type parameters for, and the type
+    /// parameters they define.
+    owners: Vec<(Owner, Vec<TypeParameterId>)>,
+
+    /// All type parameters to infer the variance for, in a stable order.
+    params: Vec<TypeParameterId>,
+
+    /// The variance of each type parameter inferred thus far.
+    ///
+    /// A `None` indicates the parameter isn't used (yet). Parameters with an
+    /// explicit variance are also tracked here, but their explicit variance is
+    /// used when determining the variance of other parameters.
+    variances: HashMap<TypeParameterId, Option<Variance>>,
+}
+
+impl<'a> Inference<'a> {
+    fn new(db: &'a Database) -> Inference<'a> {
+        let mut owners = Vec::new();
+        let mut params = Vec::new();
+        let mut variances = HashMap::new();
+
+        for idx in 0..db.number_of_classes() {
+            let id = ClassId(idx as u32);
+
+            if id.is_generic(db) {
+                owners.push((Owner::Class(id), id.type_parameters(db)));
+            }
+        }
+
+        for idx in 0..db.number_of_traits() {
+            let id = TraitId(idx as u32);
+            let ours = id.type_parameters(db);
+
+            if !ours.is_empty() {
+                owners.push((Owner::Trait(id), ours));
+            }
+        }
+
+        for (_, ours) in &owners {
+            for &param in ours {
+                params.push(param);
+                variances.insert(param, None);
+            }
+        }
+
+        Inference { db, owners, params, variances }
+    }
+
+    fn run(&mut self) {
+        loop {
+            let mut changed = false;
+
+            for idx in 0..self.owners.len() {
+                for (param, variance) in self.infer_owner(idx) {
+                    let current = self.variances[&param];
+                    let new = join(current, variance);
+
+                    if new != current {
+                        self.variances.insert(param, new);
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn infer_owner(&self, index: usize) -> Vec<(TypeParameterId, Variance)> {
+        let db = self.db;
+        let (owner, ours) = &self.owners[index];
+        let mut found = Vec::new();
+
+        match owner {
+            Owner::Class(id) => {
+                for field in id.fields(db) {
+                    self.visit_type(
+                        ours,
+                        field.value_type(db),
+                        Variance::Invariant,
+                        &mut found,
+                    );
+                }
+
+                for cons in id.constructors(db) {
+                    for &typ in cons.arguments(db) {
+                        self.visit_type(
+                            ours,
+                            typ,
+                            Variance::Covariant,
+                            &mut found,
+                        );
+                    }
+                }
+
+                for method in id.methods(db) {
+                    self.visit_method(ours, method, &mut found);
+                }
+            }
+            Owner::Trait(id) => {
+                for method in id
+                    .required_methods(db)
+                    .into_iter()
+                    .chain(id.default_methods(db))
+                {
+                    self.visit_method(ours, method, &mut found);
+                }
+            }
+        }
+
+        found
+    }
+
+    fn visit_method(
+        &self,
+        ours: &[TypeParameterId],
+        method: MethodId,
+        found: &mut Vec<(TypeParameterId, Variance)>,
+    ) {
+        if !method.is_instance(self.db) {
+            return;
+        }
+
+        for &typ in method.argument_types(self.db) {
+            self.visit_type(ours, typ, Variance::Contravariant, found);
+        }
+
+        self.visit_type(
+            ours,
+            method.return_type(self.db),
+            Variance::Covariant,
+            found,
+        );
+    }
+
+    fn visit_type(
+        &self,
+        ours: &[TypeParameterId],
+        typ: TypeRef,
+        variance: Variance,
+        found: &mut Vec<(TypeParameterId, Variance)>,
+    ) {
+        match typ {
+            TypeRef::Owned(id)
+            | TypeRef::Uni(id)
+            | TypeRef::Ref(id)
+            | TypeRef::UniRef(id)
+            | TypeRef::Mut(id)
+            | TypeRef::UniMut(id)
+            | TypeRef::Any(id) => {
+                self.visit_type_id(ours, id, variance, found);
+            }
+            // Values can be written through pointers, so pointers are
+            // invariant.
+            TypeRef::Pointer(id) | TypeRef::NonNullPointer(id) => {
+                self.visit_type_id(ours, id, Variance::Invariant, found);
+            }
+            TypeRef::Union(id) => {
+                for &typ in id.members(self.db) {
+                    self.visit_type(ours, typ, variance, found);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_type_id(
+        &self,
+        ours: &[TypeParameterId],
+        id: TypeId,
+        variance: Variance,
+        found: &mut Vec<(TypeParameterId, Variance)>,
+    ) {
+        let db = self.db;
+
+        match id {
+            TypeId::TypeParameter(mut param)
+            | TypeId::RigidTypeParameter(mut param)
+            | TypeId::AtomicTypeParameter(mut param) => {
+                // Parameters introduced through type bounds refer to the
+                // parameter they originate from.
+                while let Some(orig) = param.original(db) {
+                    param = orig;
+                }
+
+                if ours.contains(&param) {
+                    found.push((param, variance));
+                }
+            }
+            TypeId::ClassInstance(ins) if ins.instance_of().is_generic(db) => {
+                self.visit_arguments(
+                    ours,
+                    ins.instance_of().type_parameters(db),
+                    ins.type_arguments(db).unwrap(),
+                    variance,
+                    found,
+                );
+            }
+            TypeId::TraitInstance(ins) if ins.instance_of().is_generic(db) => {
+                self.visit_arguments(
+                    ours,
+                    ins.instance_of().type_parameters(db),
+                    ins.type_arguments(db).unwrap(),
+                    variance,
+                    found,
+                );
+            }
+            TypeId::TypeAlias(ins) => {
+                // Type alias arguments may be used in any position of the
+                // aliased type, so we treat them as invariant.
+                if let Some(args) = ins.type_arguments(db) {
+                    for (_, typ) in args.pairs() {
+                        self.visit_type(ours, typ, Variance::Invariant, found);
+                    }
+                }
+            }
+            // The values of tuples are invariant, like the type parameters of
+            // the builtin tuple classes.
+            TypeId::Tuple(id) => {
+                for &typ in id.elements(db) {
+                    self.visit_type(ours, typ, Variance::Invariant, found);
+                }
+            }
+            TypeId::Closure(id) => {
+                let flipped = variance.compose(Variance::Contravariant);
+
+                for arg in id.arguments(db) {
+                    self.visit_type(ours, arg.value_type, flipped, found);
+                }
+
+                self.visit_type(ours, id.return_type(db), variance, found);
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_arguments(
+        &self,
+        ours: &[TypeParameterId],
+        params: Vec<TypeParameterId>,
+        arguments: &TypeArguments,
+        variance: Variance,
+        found: &mut Vec<(TypeParameterId, Variance)>,
+    ) {
+        for param in params {
+            let Some(typ) = arguments.get(param) else { continue };
+            let param_variance = if param.has_explicit_variance(self.db) {
+                Some(param.variance(self.db))
+            } else {
+                self.variances.get(&param).copied().flatten()
+            };
+
+            // If the parameter isn't used by its type, the argument doesn't
+            // affect the variance of our parameters.
+            if let Some(param_variance) = param_variance {
+                self.visit_type(
+                    ours,
+                    typ,
+                    variance.compose(param_variance),
+                    found,
+                );
+            }
+        }
+    }
+}
+
+fn join(current: Option<Variance>, new: Variance) -> Option<Variance> {
+    Some(current.map_or(new, |v| v.join(new)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        any, generic_instance_id, new_class, new_enum_class, new_method,
+        new_module, owned, parameter,
+    };
+    use crate::{MethodKind, ModuleId, Visibility};
+    use location::Location;
+
+    fn define_method(
+        db: &mut Database,
+        module: ModuleId,
+        class: ClassId,
+        name: &str,
+        kind: MethodKind,
+        arguments: Vec<TypeRef>,
+        returns: TypeRef,
+    ) {
+        let method = new_method(db, module, name, kind);
+
+        for (idx, typ) in arguments.into_iter().enumerate() {
+            method.new_argument(
+                db,
+                format!("a{}", idx),
+                typ,
+                typ,
+                Location::default(),
+            );
+        }
+
+        method.set_return_type(db, returns);
+        class.add_method(db, name.to_string(), method);
+    }
+
+    #[test]
+    fn test_infer_all() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let reader = new_class(&mut db, "Reader");
+        let reader_t = reader.new_type_parameter(&mut db, "T".to_string());
+        let writer = new_class(&mut db, "Writer");
+        let writer_t = writer.new_type_parameter(&mut db, "T".to_string());
+        let cell = new_class(&mut db, "Cell");
+        let cell_t = cell.new_type_parameter(&mut db, "T".to_string());
+        let unused = new_class(&mut db, "Unused");
+        let unused_t = unused.new_type_parameter(&mut db, "T".to_string());
+        let wrap = new_class(&mut db, "Wrap");
+        let wrap_t = wrap.new_type_parameter(&mut db, "T".to_string());
+
+        define_method(
+            &mut db,
+            module,
+            reader,
+            "get",
+            MethodKind::Instance,
+            Vec::new(),
+            any(parameter(reader_t)),
+        );
+        define_method(
+            &mut db,
+            module,
+            reader,
+            "new",
+            MethodKind::Static,
+            vec![any(parameter(reader_t))],
+            TypeRef::nil(),
+        );
+        define_method(
+            &mut db,
+            module,
+            writer,
+            "set",
+            MethodKind::Mutable,
+            vec![any(parameter(writer_t))],
+            TypeRef::nil(),
+        );
+        cell.new_field(
+            &mut db,
+            "value".to_string(),
+            0,
+            any(parameter(cell_t)),
+            Visibility::Public,
+            module,
+            Location::default(),
+        );
+
+        let writer_ins = owned(generic_instance_id(
+            &mut db,
+            writer,
+            vec![any(parameter(wrap_t))],
+        ));
+
+        define_method(
+            &mut db,
+            module,
+            wrap,
+            "writer",
+            MethodKind::Instance,
+            Vec::new(),
+            writer_ins,
+        );
+
+        assert!(infer_all(&mut db).is_empty());
+        assert_eq!(reader_t.variance(&db), Variance::Covariant);
+        assert_eq!(writer_t.variance(&db), Variance::Contravariant);
+        assert_eq!(cell_t.variance(&db), Variance::Invariant);
+        assert_eq!(unused_t.variance(&db), Variance::Covariant);
+        assert_eq!(wrap_t.variance(&db), Variance::Contravariant);
+    }
+
+    #[test]
+    fn test_infer_all_with_enum() {
+        let mut db = Database::new();
+        let option = new_enum_class(&mut db, "Option");
+        let param = option.new_type_parameter(&mut db, "T".to_string());
+
+        option.new_constructor(
+            &mut db,
+            "Some".to_string(),
+            vec![any(parameter(param))],
+            Location::default(),
+        );
+
+        assert!(infer_all(&mut db).is_empty());
+        assert_eq!(param.variance(&db), Variance::Covariant);
+    }
+
+    #[test]
+    fn test_infer_all_with_explicit_variance() {
+        let mut db = Database::new();
+        let module = new_module(&mut db, "foo");
+        let class = new_class(&mut db, "Foo");
+        let param = class.new_type_parameter(&mut db, "T".to_string());
+
+        param.set_variance(&mut db, Variance::Covariant);
+        define_method(
+            &mut db,
+            module,
+            class,
+            "set",
+            MethodKind::Instance,
+            vec![any(parameter(param))],
+            TypeRef::nil(),
+        );
+
+        assert_eq!(
+            infer_all(&mut db),
+            vec![Conflict {
+                parameter: param,
+                inferred: Variance::Contravariant
+            }]
+        );
+        assert_eq!(param.variance(&db), Variance::Covariant);
+    }
+
+    #[test]
+    fn test_variance_compose() {
+        use Variance::*;
+
+        assert_eq!(Covariant.compose(Contravariant), Contravariant);
+        assert_eq!(Contravariant.compose(Contravariant), Covariant);
+        assert_eq!(Contravariant.compose(Invariant), Invariant);
+        assert_eq!(Covariant.join(Contravariant), Invariant);
+        assert_eq!(Covariant.join(Covariant), Covariant);
+    }
+}
@@ -0,0 +1,142 @@
+//! Resolving imports when multiple versions of a module are loaded.
+//!
+//! When two dependencies depend on different versions of the same library,
+//! both versions of the library's modules are loaded. Such modules use
+//! versioned names (e.g. `json.parser@1.2.0`), such that their names and
+//! symbols don't conflict.
+//!
+//! Imports refer to modules using their logical names (e.g. `json.parser`), so
+//! when importing a module we have to decide which version to use. This is done
+//! using the following rules, in order:
+//!
+//! 1. If the import includes a version, the module with that exact name is
+//!    used.
+//! 2. If an unversioned module with the name exists, it's used. This is the
+//!    case for the standard library and modules of the project being compiled.
+//! 3. If the importing module belongs to the same package as the imported
+//!    module, the same version as the importing module is used. This ensures
+//!    modules of a package always import modules of the same version.
+//! 4. If only a single version is loaded, that version is used.
+//!
+//! If none of these rules apply, the import is ambiguous.
+use crate::module_name::ModuleName;
+use crate::{Database, ModuleId};
+
+/// An error produced when resolving the module to import.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    /// No version of the module is loaded.
+    Undefined,
+
+    /// Multiple versions are loaded, and none of the rules select a single
+    /// version.
+    Ambiguous(Vec<ModuleId>),
+}
+
+impl Database {
+    /// Returns all loaded versions of the module with the given logical name,
+    /// in the order in which they're defined.
+    pub fn module_versions(&self, name: &str) -> &[ModuleId] {
+        self.module_versions.get(name).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Resolves the module to use when `importing` imports the module with
+    /// the given name.
+    pub fn resolve_module(
+        &self,
+        importing: ModuleId,
+        name: &ModuleName,
+    ) -> Result<ModuleId, ResolveError> {
+        if name.is_versioned() {
+            return self
+                .optional_module(name.as_str())
+                .ok_or(ResolveError::Undefined);
+        }
+
+        let versions = self.module_versions(name.as_str());
+
+        if let Some(&id) =
+            versions.iter().find(|m| !m.name(self).is_versioned())
+        {
+            return Ok(id);
+        }
+
+        let ours = importing.name(self);
+
+        if ours.is_versioned() && ours.head() == name.head() {
+            if let Some(&id) = versions
+                .iter()
+                .find(|m| m.name(self).version() == ours.version())
+            {
+                return Ok(id);
+            }
+        }
+
+        match versions {
+            [] => Err(ResolveError::Undefined),
+            [id] => Ok(*id),
+            ids => Err(ResolveError::Ambiguous(ids.to_vec())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Module;
+    use std::path::PathBuf;
+
+    fn new_module(db: &mut Database, name: ModuleName) -> ModuleId {
+        Module::alloc(db, name, PathBuf::from("foo.inko"))
+    }
+
+    fn versioned(name: &str, version: &str) -> ModuleName {
+        ModuleName::versioned(name, version)
+    }
+
+    #[test]
+    fn test_module_versions() {
+        let mut db = Database::new();
+        let v1 = new_module(&mut db, versioned("json", "1.0.0"));
+        let v2 = new_module(&mut db, versioned("json", "2.0.0"));
+
+        assert_eq!(db.module_versions("json"), &[v1, v2]);
+        assert_eq!(db.module_versions("json@1.0.0"), &[]);
+        assert_eq!(db.optional_module("json@1.0.0"), Some(v1));
+        assert_eq!(db.optional_module("json"), None);
+    }
+
+    #[test]
+    fn test_resolve_module() {
+        let mut db = Database::new();
+        let main = new_module(&mut db, ModuleName::new("main"));
+        let std = new_module(&mut db, ModuleName::new("std.string"));
+        let json1 = new_module(&mut db, versioned("json", "1.0.0"));
+        let parser1 = new_module(&mut db, versioned("json.parser", "1.0.0"));
+        let json2 = new_module(&mut db, versioned("json", "2.0.0"));
+        let parser2 = new_module(&mut db, versioned("json.parser", "2.0.0"));
+        let http = new_module(&mut db, versioned("http", "1.0.0"));
+        let name = |n: &str| ModuleName::new(n);
+
+        assert_eq!(db.resolve_module(main, &name("std.string")), Ok(std));
+        assert_eq!(
+            db.resolve_module(main, &versioned("json", "2.0.0")),
+            Ok(json2)
+        );
+        assert_eq!(db.resolve_module(json1, &name("json.parser")), Ok(parser1));
+        assert_eq!(db.resolve_module(json2, &name("json.parser")), Ok(parser2));
+        assert_eq!(db.resolve_module(main, &name("http")), Ok(http));
+        assert_eq!(
+            db.resolve_module(main, &name("json.parser")),
+            Err(ResolveError::Ambiguous(vec![parser1, parser2]))
+        );
+        assert_eq!(
+            db.resolve_module(main, &name("xml")),
+            Err(ResolveError::Undefined)
+        );
+        assert_eq!(
+            db.resolve_module(main, &versioned("json", "3.0.0")),
+            Err(ResolveError::Undefined)
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Weak references to values allocated on the heap.
+//!
+//! Values allocated on the heap are dropped when their owner is dropped, and
+//! dropping a value that's still borrowed results in a panic. This makes it
+//! impossible to drop cyclic data structures such as a doubly linked list, as
+//! every node is borrowed by its neighbours. A `Weak[T]` refers to a value
+//! without borrowing it, such that it doesn't keep the value alive. Using the
+//! value requires upgrading the weak reference, producing an `Option[T]` that's
+//! a `None` if the value is already dropped.
+//!
+//! `Weak` is a regular generic class defined by the standard library, but the
+//! type system imposes additional rules on its type argument:
+//!
+//! - Value types (e.g. `Int` and `String`) are copied instead of borrowed, so
+//!   weak references to such values aren't meaningful.
+//! - Unique values can't be aliased, so they can't be referred to using a weak
+//!   reference either.
+//!
+//! The value of a weak reference is never dropped through the reference, so
+//! the ownership of its type argument doesn't affect the generated code. As
+//! such, all specializations of `Weak` for values allocated on the heap share a
+//! single shape.
+use crate::format::format_type;
+use crate::{
+    ClassId, ClassInstance, Database, Shape, Symbol, TypeArguments, TypeId,
+    TypeRef, WEAK_CLASS, WEAK_MODULE,
+};
+
+/// An error produced when a type can't be referred to using a weak reference.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WeakError {
+    /// The type is a value type, such as `Int` or `String`.
+    ValueType,
+
+    /// The type is a unique value, such as `uni T`.
+    UniqueValue,
+
+    /// The type doesn't have an ownership, such as `Never` or a union.
+    NoOwnership,
+}
+
+impl WeakError {
+    /// Returns a description of why the type isn't valid.
+    pub fn reason(self, db: &Database, typ: TypeRef) -> String {
+        match self {
+            WeakError::ValueType => format!(
+                "values of type '{}' are copied, not borrowed",
+                format_type(db, typ)
+            ),
+            WeakError::UniqueValue => format!(
+                "values of type '{}' can't be aliased",
+                format_type(db, typ)
+            ),
+            WeakError::NoOwnership => format!(
+                "the type '{}' doesn't have an ownership",
+                format_type(db, typ)
+            ),
+        }
+    }
+}
+
+/// Returns the shapes to use for specializing `Weak`.
+///
+/// Owned values and borrows share the same shape, as a weak reference doesn't
+/// drop its value.
+pub(crate) fn weak_shapes(shapes: Vec<Shape>) -> Vec<Shape> {
+    shapes
+        .into_iter()
+        .map(|s| match s {
+            Shape::Mut | Shape::Ref => Shape::Owned,
+            s => s,
+        })
+        .collect()
+}
+
+impl Database {
+    pub fn weak_class(&self) -> ClassId {
+        self.class_in_module(WEAK_MODULE, WEAK_CLASS)
+    }
+
+    /// Returns the `Weak` class, if the `std.weak` module is loaded.
+    fn optional_weak_class(&self) -> Option<ClassId> {
+        match self.optional_module(WEAK_MODULE)?.symbol(self, WEAK_CLASS) {
+            Some(Symbol::Class(id)) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+impl ClassId {
+    pub fn is_weak(self, db: &Database) -> bool {
+        db.optional_weak_class().map_or(false, |id| {
+            self == id || self.specialization_source(db) == Some(id)
+        })
+    }
+}
+
+impl TypeRef {
+    /// Returns an owned `Weak` referring to a value of the given type.
+    pub fn weak_type(db: &mut Database, value: TypeRef) -> TypeRef {
+        let class = db.weak_class();
+        let params = class.type_parameters(db);
+        let mut args = TypeArguments::new();
+
+        args.assign(params[0], value);
+
+        TypeRef::Owned(TypeId::ClassInstance(ClassInstance::generic(
+            db, class, args,
+        )))
+    }
+
+    /// Returns the type of the value a `Weak` refers to, if `self` is a
+    /// `Weak`.
+    ///
+    /// If the `std.weak` module isn't loaded, `None` is returned.
+    pub fn weak_value(self, db: &Database) -> Option<TypeRef> {
+        let weak = db.optional_weak_class()?;
+        let ins = match self.type_id(db) {
+            Ok(TypeId::ClassInstance(ins)) if ins.instance_of == weak => ins,
+            _ => return None,
+        };
+        let param = *weak.type_parameters(db).first()?;
+
+        ins.type_arguments(db).and_then(|a| a.get(param))
+    }
+
+    pub fn is_weak(self, db: &Database) -> bool {
+        match self.type_id(db) {
+            Ok(TypeId::ClassInstance(ins)) => ins.instance_of.is_weak(db),
+            _ => false,
+        }
+    }
+
+    /// Returns the type produced by upgrading a `Weak`, if `self` is a
+    /// `Weak`.
+    ///
+    /// The type is an `Option` of a borrow of the value, such that upgrading a
+    /// weak reference doesn't allow taking over the ownership of the value.
+    /// Mutable borrows remain mutable, while other types are turned into
+    /// immutable borrows.
+    pub fn upgrade_type(self, db: &mut Database) -> Option<TypeRef> {
+        let value = match self.weak_value(db)? {
+            typ @ TypeRef::Mut(_) => typ,
+            typ => typ.as_ref(db),
+        };
+
+        Some(TypeRef::option_type(db, value))
+    }
+
+    /// Checks if `self` can be referred to using a weak reference.
+    ///
+    /// Type parameters are accepted, as the rules are enforced when assigning
+    /// a type to the parameter. `TypeRef::Error` is also accepted, such that we
+    /// don't produce errors for types that are already invalid.
+    pub fn check_weak_value(self, db: &Database) -> Result<(), WeakError> {
+        if let TypeRef::Error = self {
+            return Ok(());
+        }
+
+        if self.is_value_type(db) || self.is_stack_allocated(db) {
+            return Err(WeakError::ValueType);
+        }
+
+        match self {
+            TypeRef::Owned(_)
+            | TypeRef::Ref(_)
+            | TypeRef::Mut(_)
+            | TypeRef::Any(_)
+            | TypeRef::Error => Ok(()),
+            TypeRef::Uni(_) | TypeRef::UniRef(_) | TypeRef::UniMut(_) => {
+                Err(WeakError::UniqueValue)
+            }
+            TypeRef::Placeholder(id) => match id.value(db) {
+                Some(v) => v.check_weak_value(db),
+                None => Ok(()),
+            },
+            TypeRef::Pointer(_) | TypeRef::NonNullPointer(_) => {
+                Err(WeakError::ValueType)
+            }
+            TypeRef::Never | TypeRef::Unknown | TypeRef::Union(_) => {
+                Err(WeakError::NoOwnership)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::{
+        define_option_and_result, define_weak, immutable, instance, mutable,
+        new_class, new_module, owned, uni,
+    };
+
+    #[test]
+    fn test_type_ref_weak_type() {
+        let mut db = Database::new();
+
+        new_module(&mut db, "std");
+
+        let foo = new_class(&mut db, "Foo");
+
+        assert_eq!(owned(instance(foo)).weak_value(&db), None);
+
+        let weak = define_weak(&mut db);
+        let typ = TypeRef::weak_type(&mut db, owned(instance(foo)));
+
+        assert!(typ.is_weak(&db));
+        assert!(weak.is_weak(&db));
+        assert!(!foo.is_weak(&db));
+        assert!(!owned(instance(foo)).is_weak(&db));
+        assert_eq!(typ.weak_value(&db), Some(owned(instance(foo))));
+        assert_eq!(owned(instance(foo)).weak_value(&db), None);
+    }
+
+    #[test]
+    fn test_type_ref_upgrade_type() {
+        let mut db = Database::new();
+
+        new_module(&mut db, "std");
+
+        let foo = new_class(&mut db, "Foo");
+        let (option, _) = define_option_and_result(&mut db);
+
+        define_weak(&mut db);
+
+        let owned_weak = TypeRef::weak_type(&mut db, owned(instance(foo)));
+        let mut_weak = TypeRef::weak_type(&mut db, mutable(instance(foo)));
+        let param = option.type_parameters(&db)[0];
+        let value = |db: &Database, typ: TypeRef| match typ {
+            TypeRef::Owned(TypeId::ClassInstance(ins))
+                if ins.instance_of == option =>
+            {
+                ins.type_arguments(db).and_then(|a| a.get(param))
+            }
+            _ => None,
+        };
+
+        let upgraded = owned_weak.upgrade_type(&mut db).unwrap();
+
+        assert_eq!(value(&db, upgraded), Some(immutable(instance(foo))));
+
+        let upgraded = mut_weak.upgrade_type(&mut db).unwrap();
+
+        assert_eq!(value(&db, upgraded), Some(mutable(instance(foo))));
+        assert_eq!(owned(instance(foo)).upgrade_type(&mut db), None);
+    }
+
+    #[test]
+    fn test_type_ref_check_weak_value() {
+        let mut db = Database::new();
+        let foo = new_class(&mut db, "Foo");
+
+        assert_eq!(owned(instance(foo)).check_weak_value(&db), Ok(()));
+        assert_eq!(immutable(instance(foo)).check_weak_value(&db), Ok(()));
+        assert_eq!(TypeRef::Error.check_weak_value(&db), Ok(()));
+        assert_eq!(
+            TypeRef::int().check_weak_value(&db),
+            Err(WeakError::ValueType)
+        );
+        assert_eq!(
+            TypeRef::string().check_weak_value(&db),
+            Err(WeakError::ValueType)
+        );
+        assert_eq!(
+            uni(instance(foo)).check_weak_value(&db),
+            Err(WeakError::UniqueValue)
+        );
+        assert_eq!(
+            TypeRef::Never.check_weak_value(&db),
+            Err(WeakError::NoOwnership)
+        );
+        assert_eq!(
+            WeakError::ValueType.reason(&db, TypeRef::int()),
+            "values of type 'Int' are copied, not borrowed"
+        );
+    }
+
+    #[test]
+    fn test_weak_shapes() {
+        assert_eq!(
+            weak_shapes(vec![Shape::Mut, Shape::Ref, Shape::Owned]),
+            vec![Shape::Owned, Shape::Owned, Shape::Owned]
+        );
+        assert_eq!(weak_shapes(vec![Shape::int()]), vec![Shape::int()]);
+    }
+}